@@ -0,0 +1,37 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Currency a deposit or withdrawal moved in when not the base one; the
+        // amount stays its base value, so older flows stay null
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(CashFlow::Table)
+                    .add_column(string_null(CashFlow::Currency))
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(CashFlow::Table)
+                    .drop_column(CashFlow::Currency)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum CashFlow {
+    Table,
+    Currency,
+}
@@ -0,0 +1,36 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Day of month monthly schedules fall on; existing rows take the day of next_due
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(RecurringBuy::Table)
+                    .add_column(integer_null(RecurringBuy::AnchorDay))
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(RecurringBuy::Table)
+                    .drop_column(RecurringBuy::AnchorDay)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum RecurringBuy {
+    Table,
+    AnchorDay,
+}
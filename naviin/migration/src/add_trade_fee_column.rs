@@ -0,0 +1,37 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Commission charged on each fill, kept as decimal text like the other
+        // amounts; trades from before fees were charged paid none
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Trade::Table)
+                    .add_column(ColumnDef::new(Trade::Fee).text().not_null().default("0"))
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Trade::Table)
+                    .drop_column(Trade::Fee)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Trade {
+    Table,
+    Fee,
+}
@@ -0,0 +1,36 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Optional strategy label set with `buy ... --tag <t>`; existing trades stay untagged
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Trade::Table)
+                    .add_column(string_null(Trade::Tag))
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Trade::Table)
+                    .drop_column(Trade::Tag)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Trade {
+    Table,
+    Tag,
+}
@@ -0,0 +1,39 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Ledger of `fund` and `withdraw` amounts, kept apart from trades
+        manager
+            .create_table(
+                Table::create()
+                    .table(CashFlow::Table)
+                    .if_not_exists()
+                    .col(pk_auto(CashFlow::Id))
+                    .col(string(CashFlow::Kind))
+                    .col(decimal(CashFlow::Amount))
+                    .col(big_integer(CashFlow::Timestamp))
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(CashFlow::Table).if_exists().to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum CashFlow {
+    Table,
+    Id,
+    Kind,
+    Amount,
+    Timestamp,
+}
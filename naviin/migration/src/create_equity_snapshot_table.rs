@@ -0,0 +1,42 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Account value at most once per day, the series `performance` measures
+        manager
+            .create_table(
+                Table::create()
+                    .table(EquitySnapshot::Table)
+                    .if_not_exists()
+                    .col(pk_auto(EquitySnapshot::Id))
+                    .col(big_integer(EquitySnapshot::Timestamp))
+                    .col(decimal(EquitySnapshot::Value))
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(
+                Table::drop()
+                    .table(EquitySnapshot::Table)
+                    .if_exists()
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum EquitySnapshot {
+    Table,
+    Id,
+    Timestamp,
+    Value,
+}
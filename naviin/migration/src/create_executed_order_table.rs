@@ -0,0 +1,52 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Conditional orders that filled, with the price they were set to trigger at
+        manager
+            .create_table(
+                Table::create()
+                    .table(ExecutedOrder::Table)
+                    .if_not_exists()
+                    .col(pk_auto(ExecutedOrder::Id))
+                    .col(string(ExecutedOrder::OrderType))
+                    .col(string(ExecutedOrder::Symbol))
+                    .col(decimal(ExecutedOrder::Quantity))
+                    .col(decimal(ExecutedOrder::TargetPrice))
+                    .col(decimal(ExecutedOrder::FillPrice))
+                    .col(big_integer(ExecutedOrder::PlacedAt))
+                    .col(big_integer(ExecutedOrder::ExecutedAt))
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(
+                Table::drop()
+                    .table(ExecutedOrder::Table)
+                    .if_exists()
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum ExecutedOrder {
+    Table,
+    Id,
+    OrderType,
+    Symbol,
+    Quantity,
+    TargetPrice,
+    FillPrice,
+    PlacedAt,
+    ExecutedAt,
+}
@@ -0,0 +1,37 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Free-text annotation per symbol, set with `note <symbol> <text>`
+        manager
+            .create_table(
+                Table::create()
+                    .table(Note::Table)
+                    .if_not_exists()
+                    .col(pk_auto(Note::Id))
+                    .col(string_uniq(Note::Symbol))
+                    .col(string(Note::Text))
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Note::Table).if_exists().to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Note {
+    Table,
+    Id,
+    Symbol,
+    Text,
+}
@@ -0,0 +1,42 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Saved order structures from `template save`; legs hold the spec as typed
+        manager
+            .create_table(
+                Table::create()
+                    .table(OrderTemplate::Table)
+                    .if_not_exists()
+                    .col(pk_auto(OrderTemplate::Id))
+                    .col(string_uniq(OrderTemplate::Name))
+                    .col(string(OrderTemplate::Legs))
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(
+                Table::drop()
+                    .table(OrderTemplate::Table)
+                    .if_exists()
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum OrderTemplate {
+    Table,
+    Id,
+    Name,
+    Legs,
+}
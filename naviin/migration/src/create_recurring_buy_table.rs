@@ -0,0 +1,46 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Dollar-cost-averaging schedules from `recurring`, one per symbol
+        manager
+            .create_table(
+                Table::create()
+                    .table(RecurringBuy::Table)
+                    .if_not_exists()
+                    .col(pk_auto(RecurringBuy::Id))
+                    .col(string_uniq(RecurringBuy::Symbol))
+                    .col(decimal(RecurringBuy::Amount))
+                    .col(string(RecurringBuy::Frequency))
+                    .col(big_integer(RecurringBuy::NextDue))
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(
+                Table::drop()
+                    .table(RecurringBuy::Table)
+                    .if_exists()
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum RecurringBuy {
+    Table,
+    Id,
+    Symbol,
+    Amount,
+    Frequency,
+    NextDue,
+}
@@ -74,6 +74,22 @@ impl MigrationTrait for Migration {
             .await?;
         Ok(())
     }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Reverse of creation order, so nothing is dropped before what refers to it
+        for table in [
+            Watchlist::Table.into_iden(),
+            OpenOrder::Table.into_iden(),
+            Trade::Table.into_iden(),
+            Holding::Table.into_iden(),
+            AppState::Table.into_iden(),
+        ] {
+            manager
+                .drop_table(Table::drop().table(table).if_exists().to_owned())
+                .await?;
+        }
+        Ok(())
+    }
 }
 
 #[derive(DeriveIden)]
@@ -1,12 +1,40 @@
 pub use sea_orm_migration::prelude::*;
 
+mod add_cash_flow_currency_column;
+mod add_recurring_anchor_day_column;
+mod add_trade_currency_column;
+mod add_trade_fee_column;
+mod add_trade_tag_column;
+mod create_cash_flow_table;
+mod create_equity_snapshot_table;
+mod create_executed_order_table;
+mod create_note_table;
+mod create_order_template_table;
+mod create_recurring_buy_table;
+mod create_setting_table;
 mod create_table;
+mod store_decimals_as_text;
 
 pub struct Migrator;
 
 #[async_trait::async_trait]
 impl MigratorTrait for Migrator {
     fn migrations() -> Vec<Box<dyn MigrationTrait>> {
-        vec![Box::new(create_table::Migration)]
+        vec![
+            Box::new(create_table::Migration),
+            Box::new(create_setting_table::Migration),
+            Box::new(create_note_table::Migration),
+            Box::new(create_cash_flow_table::Migration),
+            Box::new(create_equity_snapshot_table::Migration),
+            Box::new(create_order_template_table::Migration),
+            Box::new(create_recurring_buy_table::Migration),
+            Box::new(create_executed_order_table::Migration),
+            Box::new(store_decimals_as_text::Migration),
+            Box::new(add_trade_tag_column::Migration),
+            Box::new(add_recurring_anchor_day_column::Migration),
+            Box::new(add_trade_currency_column::Migration),
+            Box::new(add_trade_fee_column::Migration),
+            Box::new(add_cash_flow_currency_column::Migration),
+        ]
     }
 }
@@ -0,0 +1,73 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+/// Every decimal column, by table
+const DECIMAL_COLUMNS: &[(&str, &str)] = &[
+    ("app_state", "cash_balance"),
+    ("holding", "quantity"),
+    ("holding", "avg_cost"),
+    ("trade", "quantity"),
+    ("trade", "price_per"),
+    ("open_order", "quantity"),
+    ("open_order", "price"),
+    ("cash_flow", "amount"),
+    ("equity_snapshot", "value"),
+    ("recurring_buy", "amount"),
+    ("executed_order", "quantity"),
+    ("executed_order", "target_price"),
+    ("executed_order", "fill_price"),
+];
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // SQLite ignores a declared decimal(20, 8) and keeps numeric columns as
+        // doubles, so quantities and prices past ~15 significant digits came back
+        // rounded. Rebuild each column as text holding the decimal's exact string;
+        // SQLite cannot change a column's type in place.
+        let db = manager.get_connection();
+        for (table, column) in DECIMAL_COLUMNS {
+            let text_column = format!("{column}_text");
+            db.execute_unprepared(&format!(
+                "ALTER TABLE \"{table}\" ADD COLUMN \"{text_column}\" TEXT NOT NULL DEFAULT '0'"
+            ))
+            .await?;
+            db.execute_unprepared(&format!(
+                "UPDATE \"{table}\" SET \"{text_column}\" = CAST(\"{column}\" AS TEXT)"
+            ))
+            .await?;
+            db.execute_unprepared(&format!("ALTER TABLE \"{table}\" DROP COLUMN \"{column}\""))
+                .await?;
+            db.execute_unprepared(&format!(
+                "ALTER TABLE \"{table}\" RENAME COLUMN \"{text_column}\" TO \"{column}\""
+            ))
+            .await?;
+        }
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Back to the numeric columns `up` replaced, by the same rebuild
+        let db = manager.get_connection();
+        for (table, column) in DECIMAL_COLUMNS.iter().rev() {
+            let numeric_column = format!("{column}_numeric");
+            db.execute_unprepared(&format!(
+                "ALTER TABLE \"{table}\" ADD COLUMN \"{numeric_column}\" DECIMAL NOT NULL DEFAULT 0"
+            ))
+            .await?;
+            db.execute_unprepared(&format!(
+                "UPDATE \"{table}\" SET \"{numeric_column}\" = CAST(\"{column}\" AS NUMERIC)"
+            ))
+            .await?;
+            db.execute_unprepared(&format!("ALTER TABLE \"{table}\" DROP COLUMN \"{column}\""))
+                .await?;
+            db.execute_unprepared(&format!(
+                "ALTER TABLE \"{table}\" RENAME COLUMN \"{numeric_column}\" TO \"{column}\""
+            ))
+            .await?;
+        }
+        Ok(())
+    }
+}
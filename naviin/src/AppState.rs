@@ -1,24 +1,149 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::path::PathBuf;
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, Mutex, MutexGuard};
 use std::time::Duration;
 
-use chrono;
+use chrono::{self, NaiveDate};
 use rust_decimal::prelude::*;
+use tokio::task::JoinHandle;
 use tokio::time;
 
-use crate::Finance::{Holding, Symbol};
-use crate::Orders::{OpenOrder, OrderType, Side, Trade};
+use crate::Finance::{
+    AssetType, FeeModel, Holding, INTEREST_ORDER_TYPE, SPLIT_ORDER_TYPE, SharePolicy, Symbol,
+    apply_slippage, create_buy_with_params, normalize_symbol, percent_of, quantity_for_notional,
+};
+use crate::FinanceProvider::{
+    OfflineProvider, PriceProvider, ProviderChain, ProviderHealth, ProviderSource, Throttle,
+    ThrottleLimits, ThrottledProvider,
+};
+use crate::Orders::{ExecutedOrder, OpenOrder, OrderType, Side, Trade};
+use crate::cashflow::{self, CashFlow, CashFlowKind};
+use crate::clock::{Clock, DisplayTimezone, SystemClock};
+use crate::components::input::DEFAULT_MAX_COMMAND_LEN;
+use crate::components::output::ScrollAfterCommand;
+use crate::costbasis::{self, ClosedLot, CostBasisMethod, OpenLot, RealizedGain};
+use crate::events::{AppEvent, EVENT_LIMIT, EventKind};
+use crate::format::{Align, MoneyFormat, NumberStyle, TextTable, format_fixed, format_qty};
+use crate::fx;
+use crate::margin::{self, MarginStatus};
+use crate::market::MarketCalendar;
+use crate::performance::EquitySnapshot;
+use crate::recurring::RecurringBuy;
+use crate::refresh;
+use crate::risk::{RiskLimits, RiskRejection};
+use crate::templates::OrderTemplate;
+use crate::undo::{UNDO_LIMIT, UndoAction};
+use crate::webhook::{self, OrderFill};
+
+const SECONDS_PER_DAY: i64 = 86_400;
+// Default seconds between TUI price refreshes
+pub const DEFAULT_REFRESH_SECS: u64 = 5;
+// Default seconds between background order checks
+pub const DEFAULT_MONITOR_SECS: u64 = 10;
+// Allowed range for either interval; faster polling risks provider rate limits
+pub const MIN_INTERVAL_SECS: u64 = 1;
+pub const MAX_INTERVAL_SECS: u64 = 3_600;
+// Allowed range for the command length cap
+pub const MIN_COMMAND_LEN: usize = 64;
+pub const MAX_COMMAND_LEN: usize = 8_192;
+// Providers prices are fetched from until the chain is configured
+pub const DEFAULT_PROVIDER_CHAIN: &[ProviderSource] = &[ProviderSource::Yahoo];
+// Upper bound on days credited in one pass after a very long absence
+const MAX_ACCRUAL_DAYS: i64 = 3_650;
+// Least seconds between two refreshes of the database backup; saves in between skip it
+pub const BACKUP_INTERVAL_SECS: i64 = 300;
+
+// Where one symbol appears in the account, as listed by `symbols`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SymbolFootprint {
+    pub held: bool,
+    pub watched: bool,
+    pub open_orders: usize,
+    pub trades: usize,
+}
+
+impl SymbolFootprint {
+    // Labels for the places the symbol appears, in a fixed order
+    pub fn places(&self) -> Vec<&'static str> {
+        [
+            (self.held, "held"),
+            (self.watched, "watched"),
+            (self.open_orders > 0, "ordered"),
+            (self.trades > 0, "traded"),
+        ]
+        .into_iter()
+        .filter_map(|(present, label)| present.then_some(label))
+        .collect()
+    }
+}
 
 // Manages user account state including cash, holdings, trades, and pending orders
 #[derive(Debug)]
 pub struct AppState {
     cash_balance: Decimal,
+    // Cash in currencies other than the base one; the base currency's is cash_balance
+    currency_cash: BTreeMap<String, Decimal>,
+    cash_flows: Vec<CashFlow>,
+    deposit_cap: Option<Decimal>,
+    risk_limits: RiskLimits,
+    equity_snapshots: Vec<EquitySnapshot>,
     holdings: HashMap<Symbol, Holding>,
     trades: Vec<Trade>,
     open_orders: Vec<OpenOrder>,
     watchlist: Vec<Symbol>,
+    validated_symbols: HashSet<Symbol>,
+    notes: BTreeMap<Symbol, String>,
+    templates: BTreeMap<String, OrderTemplate>,
+    recurring_buys: BTreeMap<Symbol, RecurringBuy>,
+    executed_orders: Vec<ExecutedOrder>,
     pending_import: bool,
+    // The pending import only reports what it would do
+    import_dry_run: bool,
+    demo: bool,
+    backup_path: Option<PathBuf>,
+    // When this session last refreshed the backup, None until the first save
+    backup_written_at: Option<i64>,
+    slippage_bps: Decimal,
+    fee_model: FeeModel,
+    cost_basis: CostBasisMethod,
+    share_policy: SharePolicy,
+    refresh_secs: u64,
+    monitor_secs: u64,
+    output_scroll: ScrollAfterCommand,
+    max_command_len: usize,
+    cash_apy: Decimal,
+    yield_accrued_at: i64,
+    leverage: Decimal,
+    margin_rate: Decimal,
+    margin_loan: Decimal,
+    margin_accrued_at: i64,
+    margin_call: bool,
+    market_holidays: Vec<NaiveDate>,
+    undo_stack: VecDeque<UndoAction>,
+    money_format: MoneyFormat,
+    display_timezone: DisplayTimezone,
+    target_allocations: BTreeMap<Symbol, Decimal>,
+    // What the user types -> the symbol it stands for, checked before built-in spellings
+    symbol_aliases: BTreeMap<Symbol, Symbol>,
+    webhook_url: Option<String>,
+    webhook_error: Option<String>,
+    events: VecDeque<AppEvent>,
+    // Changed since the last save by something that doesn't save itself, e.g. a background fill
+    dirty: bool,
+    // Global kill switch for network fetches, for the session only
+    offline: bool,
+    // Limits lookups through the Yahoo provider; shared so setting changes apply at once
+    throttle: Arc<Throttle>,
+    // Configured fallback order and the live chain built from it
+    provider_sources: Vec<ProviderSource>,
+    provider_chain: Arc<ProviderChain>,
+    provider: Arc<dyn PriceProvider>,
+    // Pricing through a provider handed to set_provider rather than the configured chain
+    injected_provider: bool,
+    clock: Arc<dyn Clock>,
+    // The session's background order monitor, so shutdown can stop it before saving
+    monitor_task: Option<JoinHandle<()>>,
 }
 
 impl Default for AppState {
@@ -29,160 +154,1737 @@ impl Default for AppState {
 
 impl AppState {
     pub fn new() -> Self {
+        let throttle = Arc::new(Throttle::default());
+        let clock: Arc<dyn Clock> = Arc::new(SystemClock);
+        let provider_chain = Arc::new(ProviderChain::from_sources(
+            DEFAULT_PROVIDER_CHAIN,
+            clock.clone(),
+        ));
         Self {
             cash_balance: Decimal::ZERO,
+            currency_cash: BTreeMap::new(),
+            cash_flows: Vec::new(),
+            deposit_cap: None,
+            risk_limits: RiskLimits::default(),
+            equity_snapshots: Vec::new(),
             holdings: HashMap::new(),
             trades: Vec::new(),
             open_orders: Vec::new(),
             watchlist: Vec::new(),
+            validated_symbols: HashSet::new(),
+            notes: BTreeMap::new(),
+            templates: BTreeMap::new(),
+            recurring_buys: BTreeMap::new(),
+            executed_orders: Vec::new(),
             pending_import: false,
+            import_dry_run: false,
+            demo: false,
+            backup_path: None,
+            backup_written_at: None,
+            slippage_bps: Decimal::ZERO,
+            fee_model: FeeModel::default(),
+            cost_basis: CostBasisMethod::default(),
+            share_policy: SharePolicy::default(),
+            refresh_secs: DEFAULT_REFRESH_SECS,
+            monitor_secs: DEFAULT_MONITOR_SECS,
+            output_scroll: ScrollAfterCommand::default(),
+            max_command_len: DEFAULT_MAX_COMMAND_LEN,
+            cash_apy: Decimal::ZERO,
+            yield_accrued_at: 0,
+            leverage: margin::NO_LEVERAGE,
+            margin_rate: margin::DEFAULT_MARGIN_RATE,
+            margin_loan: Decimal::ZERO,
+            margin_accrued_at: 0,
+            margin_call: false,
+            market_holidays: Vec::new(),
+            undo_stack: VecDeque::new(),
+            money_format: MoneyFormat::default(),
+            display_timezone: DisplayTimezone::default(),
+            target_allocations: BTreeMap::new(),
+            symbol_aliases: BTreeMap::new(),
+            webhook_url: None,
+            webhook_error: None,
+            events: VecDeque::new(),
+            dirty: false,
+            offline: false,
+            throttle: throttle.clone(),
+            provider_sources: DEFAULT_PROVIDER_CHAIN.to_vec(),
+            provider_chain: provider_chain.clone(),
+            provider: Arc::new(ThrottledProvider::new(provider_chain, throttle)),
+            injected_provider: false,
+            clock,
+            monitor_task: None,
         }
     }
 
+    // Fresh state that prices through the given provider instead of Yahoo
+    pub fn with_provider(provider: Arc<dyn PriceProvider>) -> Self {
+        let mut state = Self::new();
+        state.set_provider(provider);
+        state
+    }
+
+    // Clone the provider handle so callers can drop the lock before awaiting a price;
+    // while offline, hand out a provider that never fetches instead
+    pub fn provider(&self) -> Arc<dyn PriceProvider> {
+        if self.offline {
+            return Arc::new(OfflineProvider);
+        }
+        self.provider.clone()
+    }
+
+    pub fn is_offline(&self) -> bool {
+        self.offline
+    }
+
+    pub fn set_offline(&mut self, offline: bool) {
+        self.offline = offline;
+    }
+
+    // Prices through `provider` alone, as a one-link chain so `providers` reports on it
+    pub fn set_provider(&mut self, provider: Arc<dyn PriceProvider>) {
+        let chain = Arc::new(ProviderChain::new(vec![provider], false));
+        self.provider = chain.clone();
+        self.provider_chain = chain;
+        self.injected_provider = true;
+    }
+
+    // Fallback order of the price providers, as configured
+    pub fn get_provider_sources(&self) -> Vec<ProviderSource> {
+        self.provider_sources.clone()
+    }
+
+    // Rebuilds the chain from the named providers; recent success rates start over
+    pub fn set_provider_sources(&mut self, sources: Vec<ProviderSource>) {
+        self.set_provider_chain(Arc::new(ProviderChain::from_sources(
+            &sources,
+            self.clock.clone(),
+        )));
+        self.provider_sources = sources;
+    }
+
+    // Prices through the given chain, behind the session throttle
+    pub fn set_provider_chain(&mut self, chain: Arc<ProviderChain>) {
+        self.provider = Arc::new(ThrottledProvider::new(chain.clone(), self.throttle.clone()));
+        self.provider_chain = chain;
+        self.injected_provider = false;
+    }
+
+    // Each link of the chain with its recent success rate
+    pub fn provider_health(&self) -> Vec<ProviderHealth> {
+        self.provider_chain.health()
+    }
+
+    // Shared request limiter, for the throttled warning
+    pub fn throttle(&self) -> Arc<Throttle> {
+        self.throttle.clone()
+    }
+
+    pub fn get_throttle_limits(&self) -> ThrottleLimits {
+        self.throttle.limits()
+    }
+
+    pub fn set_throttle_limits(&mut self, limits: ThrottleLimits) -> Result<(), String> {
+        self.throttle.set_limits(limits.validate()?);
+        Ok(())
+    }
+
+    // Fresh state that stamps trades, orders and events from the given clock
+    pub fn with_clock(clock: Arc<dyn Clock>) -> Self {
+        let mut state = Self::new();
+        state.set_clock(clock);
+        state
+    }
+
+    pub fn clock(&self) -> Arc<dyn Clock> {
+        self.clock.clone()
+    }
+
+    // The configured chain is rebuilt so live providers tell time from the new clock too
+    pub fn set_clock(&mut self, clock: Arc<dyn Clock>) {
+        self.clock = clock;
+        if !self.injected_provider {
+            self.set_provider_sources(self.provider_sources.clone());
+        }
+    }
+
+    // Current time from the state's clock as Unix seconds
+    pub fn now_timestamp(&self) -> i64 {
+        self.clock.timestamp()
+    }
+
     pub fn deposit(&mut self, amount: Decimal) {
-        self.cash_balance += amount;
+        self.receive_cash(amount);
+    }
+
+    // Incoming cash pays down the margin loan before it adds to the balance
+    fn receive_cash(&mut self, amount: Decimal) {
+        let repaid = amount.clamp(Decimal::ZERO, self.margin_loan);
+        self.margin_loan -= repaid;
+        self.cash_balance += amount - repaid;
+    }
+
+    // Withdraw funds with validation
+    pub fn withdraw(&mut self, amount: Decimal) {
+        if amount < Decimal::ZERO {
+            println!("Invalid amount");
+            return;
+        }
+        if amount > self.cash_balance {
+            println!("Insufficient balance");
+            return;
+        }
+        self.cash_balance -= amount;
+    }
+
+    // Deduct purchase amount from balance without validation (used in buy functions)
+    pub fn withdraw_purchase(&mut self, amount: Decimal) {
+        if amount < Decimal::ZERO {
+            println!("Invalid amount");
+            return;
+        }
+        self.cash_balance -= amount;
+        // Whatever the cash doesn't cover is borrowed on margin
+        if self.cash_balance < Decimal::ZERO {
+            if self.margin_loan <= Decimal::ZERO {
+                self.margin_accrued_at = self.now_timestamp();
+            }
+            self.margin_loan -= self.cash_balance;
+            self.cash_balance = Decimal::ZERO;
+        }
+    }
+
+    // Add sale proceeds to balance, repaying any margin loan first
+    pub fn deposit_sell(&mut self, amount: Decimal) {
+        self.receive_cash(amount);
+    }
+
+    // Most a purchase can cost: cash times the leverage factor
+    pub fn buying_power(&self) -> Decimal {
+        margin::buying_power(self.cash_balance, self.leverage)
+    }
+
+    pub fn get_leverage(&self) -> Decimal {
+        self.leverage
+    }
+
+    pub fn set_leverage(&mut self, leverage: Decimal) {
+        self.leverage = leverage;
+    }
+
+    // Yearly interest on the margin loan, in percent
+    pub fn get_margin_rate(&self) -> Decimal {
+        self.margin_rate
+    }
+
+    pub fn set_margin_rate(&mut self, rate: Decimal) {
+        self.margin_rate = rate;
+    }
+
+    // Amount borrowed on margin, tracked apart from cash
+    pub fn get_margin_loan(&self) -> Decimal {
+        self.margin_loan
+    }
+
+    pub fn set_margin_loan(&mut self, loan: Decimal) {
+        self.margin_loan = loan;
+    }
+
+    // Equity against the maintenance requirement, given what the holdings are worth
+    pub fn margin_status(&self, holdings_value: Decimal) -> Option<MarginStatus> {
+        MarginStatus::new(self.cash_balance, holdings_value, self.margin_loan)
+    }
+
+    // Add daily compounded interest on the loan for each full day since the last accrual
+    // Returns the amount charged; it is added to the loan and recorded as an
+    // "Interest" sell on CASH, the counterpart of a cash yield credit
+    pub fn accrue_margin_interest(&mut self, now: i64) -> Decimal {
+        if self.margin_loan <= Decimal::ZERO || self.margin_accrued_at == 0 {
+            self.margin_accrued_at = now;
+            return Decimal::ZERO;
+        }
+
+        let days = (now - self.margin_accrued_at) / SECONDS_PER_DAY;
+        if days <= 0 {
+            return Decimal::ZERO;
+        }
+        // Advance by whole days only so the partial day keeps accruing
+        self.margin_accrued_at += days * SECONDS_PER_DAY;
+
+        let daily_rate = self.margin_rate / Decimal::from(100) / Decimal::from(365);
+        let mut interest = Decimal::ZERO;
+        for _ in 0..days.min(MAX_ACCRUAL_DAYS) {
+            interest += (self.margin_loan + interest) * daily_rate;
+        }
+        let interest = interest.round_dp(2);
+        if interest > Decimal::ZERO {
+            self.margin_loan += interest;
+            self.add_trade(Trade::from_database(
+                "CASH".to_string(),
+                interest,
+                Decimal::ONE,
+                Side::Sell,
+                self.margin_accrued_at,
+                INTEREST_ORDER_TYPE.to_string(),
+            ));
+        }
+        interest
+    }
+
+    // Get current cash balance
+    pub fn check_balance(&self) -> Decimal {
+        self.cash_balance
+    }
+
+    pub fn set_cash_balance(&mut self, new_balance: Decimal) {
+        self.cash_balance = new_balance;
+    }
+
+    // Cash held in one currency; the base currency's is the cash balance
+    pub fn currency_balance(&self, currency: &str) -> Decimal {
+        if currency == fx::BASE_CURRENCY {
+            self.cash_balance
+        } else {
+            self.currency_cash
+                .get(currency)
+                .copied()
+                .unwrap_or_default()
+        }
+    }
+
+    // Every cash bucket, the base currency first and the rest by code
+    pub fn currency_balances(&self) -> Vec<(String, Decimal)> {
+        let mut balances = vec![(fx::BASE_CURRENCY.to_string(), self.cash_balance)];
+        balances.extend(
+            self.currency_cash
+                .iter()
+                .map(|(currency, balance)| (currency.clone(), *balance)),
+        );
+        balances
+    }
+
+    // Buckets other than the base currency with money in them, whose base value
+    // needs an exchange rate
+    pub fn foreign_currencies(&self) -> Vec<String> {
+        self.currency_cash
+            .iter()
+            .filter(|(_, balance)| !balance.is_zero())
+            .map(|(currency, _)| currency.clone())
+            .collect()
+    }
+
+    // Currencies the account's base value needs a rate for: the foreign cash
+    // buckets and the trading currency of every holding quoted in another one
+    pub fn valued_currencies(&self) -> Vec<String> {
+        let mut currencies = self.foreign_currencies();
+        for symbol in self.holdings.keys() {
+            let currency = fx::trading_currency(symbol);
+            if currency != fx::BASE_CURRENCY && !currencies.contains(&currency) {
+                currencies.push(currency);
+            }
+        }
+        currencies
+    }
+
+    // Every cash bucket in the base currency at `rates`, less any margin loan
+    // None while a bucket with money in it has no rate
+    pub fn net_cash_value(&self, rates: &HashMap<String, Decimal>) -> Option<Decimal> {
+        let mut value = self.cash_balance - self.margin_loan;
+        for currency in self.foreign_currencies() {
+            value += self.currency_cash[&currency] * rates.get(&currency).copied()?;
+        }
+        Some(value)
+    }
+
+    // Add cash in a currency, opening its bucket on first use
+    pub fn deposit_currency(&mut self, currency: &str, amount: Decimal) {
+        if currency == fx::BASE_CURRENCY {
+            self.deposit(amount);
+        } else {
+            *self.currency_cash.entry(currency.to_string()).or_default() += amount;
+        }
+    }
+
+    // Take cash out of a currency; the bucket stays open at zero so trades keep settling there
+    pub fn withdraw_currency(&mut self, currency: &str, amount: Decimal) {
+        if currency == fx::BASE_CURRENCY {
+            self.withdraw(amount);
+        } else if let Some(balance) = self.currency_cash.get_mut(currency)
+            && amount <= *balance
+        {
+            *balance -= amount;
+        }
+    }
+
+    // Currency a symbol's trades settle in: its trading currency once the account
+    // holds cash in it, otherwise the base currency as before buckets existed
+    pub fn settlement_currency(&self, symbol: &str) -> String {
+        let currency = fx::trading_currency(symbol);
+        if self.currency_cash.contains_key(&currency) {
+            currency
+        } else {
+            fx::BASE_CURRENCY.to_string()
+        }
+    }
+
+    // Most a purchase of the symbol can cost; margin only lends against base cash
+    pub fn buying_power_for(&self, symbol: &str) -> Decimal {
+        let currency = self.settlement_currency(symbol);
+        if currency == fx::BASE_CURRENCY {
+            self.buying_power()
+        } else {
+            self.currency_balance(&currency)
+        }
+    }
+
+    // Currency a logged trade settled in: the one recorded at fill time, or for
+    // trades from before that was recorded, the symbol's settlement currency now
+    pub fn trade_currency(&self, trade: &Trade) -> String {
+        trade
+            .get_currency()
+            .map(str::to_string)
+            .unwrap_or_else(|| self.settlement_currency(trade.get_symbol()))
+    }
+
+    // Pay for a purchase of the symbol from its settlement currency
+    // Returns that currency, for the trade to record
+    pub fn pay_for(&mut self, symbol: &str, amount: Decimal) -> String {
+        let currency = self.settlement_currency(symbol);
+        self.pay_in(&currency, amount);
+        currency
+    }
+
+    // Pay out of one currency's cash; base currency shortfalls are borrowed on margin
+    pub fn pay_in(&mut self, currency: &str, amount: Decimal) {
+        if currency == fx::BASE_CURRENCY {
+            self.withdraw_purchase(amount);
+        } else {
+            *self.currency_cash.entry(currency.to_string()).or_default() -= amount;
+        }
+    }
+
+    // Credit the proceeds of selling the symbol to its settlement currency
+    // Returns that currency, for the trade to record
+    pub fn receive_proceeds(&mut self, symbol: &str, amount: Decimal) -> String {
+        let currency = self.settlement_currency(symbol);
+        self.receive_in(&currency, amount);
+        currency
+    }
+
+    // Credit one currency's cash; base currency proceeds repay margin first
+    pub fn receive_in(&mut self, currency: &str, amount: Decimal) {
+        if currency == fx::BASE_CURRENCY {
+            self.deposit_sell(amount);
+        } else {
+            *self.currency_cash.entry(currency.to_string()).or_default() += amount;
+        }
+    }
+
+    // Record a fund or withdraw in the cash flow ledger, stamped with the state clock
+    // Only logs the flow; the balance is changed by deposit/withdraw
+    pub fn record_cash_flow(&mut self, kind: CashFlowKind, amount: Decimal) {
+        self.record_cash_flow_in(kind, fx::BASE_CURRENCY, amount);
+    }
+
+    // Same as `record_cash_flow` for money moved in `currency`, valued at `amount`
+    // in the base currency
+    pub fn record_cash_flow_in(&mut self, kind: CashFlowKind, currency: &str, amount: Decimal) {
+        let now = self.now_timestamp();
+        let currency = (currency != fx::BASE_CURRENCY).then(|| currency.to_string());
+        self.add_cash_flow(CashFlow::new(kind, amount, now).with_currency(currency));
+    }
+
+    pub fn add_cash_flow(&mut self, flow: CashFlow) {
+        self.cash_flows.push(flow);
+    }
+
+    // Drop the most recent flow of this kind, currency and base amount, e.g. when a
+    // fund is undone
+    pub fn remove_last_cash_flow(
+        &mut self,
+        kind: CashFlowKind,
+        currency: &str,
+        amount: Decimal,
+    ) -> bool {
+        match self.cash_flows.iter().rposition(|flow| {
+            flow.kind == kind && flow.currency_code() == currency && flow.amount == amount
+        }) {
+            Some(idx) => {
+                self.cash_flows.remove(idx);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn get_cash_flows(&self) -> Vec<CashFlow> {
+        self.cash_flows.clone()
+    }
+
+    pub fn set_cash_flows(&mut self, flows: Vec<CashFlow>) {
+        self.cash_flows = flows;
+    }
+
+    // Most that net deposits may reach, None when deposits are uncapped
+    pub fn get_deposit_cap(&self) -> Option<Decimal> {
+        self.deposit_cap
+    }
+
+    pub fn set_deposit_cap(&mut self, cap: Option<Decimal>) {
+        self.deposit_cap = cap;
+    }
+
+    // Room left under the deposit cap, None when deposits are uncapped
+    pub fn deposit_headroom(&self) -> Option<Decimal> {
+        self.deposit_cap
+            .map(|cap| cashflow::deposit_headroom(&self.cash_flows, cap))
+    }
+
+    // Position and order size limits checked on every buy
+    pub fn get_risk_limits(&self) -> RiskLimits {
+        self.risk_limits
+    }
+
+    pub fn set_risk_limits(&mut self, limits: RiskLimits) {
+        self.risk_limits = limits;
+    }
+
+    // Checks a buy of `quantity` at `price` costing `total_cost` against the risk limits,
+    // with the other holdings marked at `prices` (see `risk_prices`)
+    // Prices and costs in other currencies are converted at the FX quotes in `prices`
+    pub fn check_risk_limits(
+        &self,
+        symbol: &str,
+        quantity: Decimal,
+        price: Decimal,
+        total_cost: Decimal,
+        prices: &HashMap<Symbol, Decimal>,
+    ) -> Result<(), RiskRejection> {
+        let limits = self.risk_limits;
+        if limits.is_off() {
+            return Ok(());
+        }
+        let rate = |currency: &str| {
+            if currency == fx::BASE_CURRENCY {
+                return Ok(Decimal::ONE);
+            }
+            prices
+                .get(&fx::fx_symbol(currency, fx::BASE_CURRENCY))
+                .copied()
+                .filter(|rate| *rate > Decimal::ZERO)
+                .ok_or_else(|| RiskRejection::NoRate {
+                    currency: currency.to_string(),
+                })
+        };
+
+        // The buy is paid in its settlement currency and priced in its trading currency
+        let settlement = self.settlement_currency(symbol);
+        let cost = total_cost * rate(&settlement)?;
+        let (mut position_value, mut portfolio_value) = (Decimal::ZERO, Decimal::ZERO);
+        if let Some(limit) = limits.max_position_pct {
+            let cash = self.check_balance();
+            portfolio_value = cash;
+            for currency in self.foreign_currencies() {
+                portfolio_value += self.currency_balance(&currency) * rate(&currency)?;
+            }
+            for (held, holding) in &self.holdings {
+                let held_price = if held == symbol {
+                    Some(price)
+                } else {
+                    prices.get(held).copied()
+                };
+                match held_price.filter(|price| *price > Decimal::ZERO) {
+                    Some(held_price) => {
+                        portfolio_value +=
+                            holding.get_qty() * held_price * rate(&fx::trading_currency(held))?
+                    }
+                    None => {
+                        return Err(RiskRejection::Unpriced {
+                            symbol: held.clone(),
+                            limit,
+                        });
+                    }
+                }
+            }
+            // Cash turns into shares; anything past the cash is borrowed and adds to the
+            // total, and only base currency purchases borrow
+            let borrowed = if settlement == fx::BASE_CURRENCY {
+                (total_cost - cash).max(Decimal::ZERO)
+            } else {
+                Decimal::ZERO
+            };
+            let price = price * rate(&fx::trading_currency(symbol))?;
+            portfolio_value += quantity * price - cost + borrowed;
+            let held = self
+                .holdings
+                .get(symbol)
+                .map(|holding| holding.get_qty())
+                .unwrap_or_default();
+            position_value = (held + quantity) * price;
+        }
+
+        limits
+            .check_buy(cost, position_value, portfolio_value)
+            .map_err(RiskRejection::Breach)
+    }
+
+    // Cash plus every holding at the given prices, less any margin loan, so a
+    // leveraged account records its equity rather than its gross exposure
+    // Other currencies' cash and quotes are converted at `rates`, units of base per
+    // unit (see `valued_currencies`)
+    // None while any holding has no price, so a failed quote isn't recorded as a loss
+    pub fn portfolio_value(
+        &self,
+        prices: &HashMap<Symbol, Decimal>,
+        rates: &HashMap<String, Decimal>,
+    ) -> Option<Decimal> {
+        Some(self.net_cash_value(rates)? + self.holdings_value(prices, rates)?)
+    }
+
+    // Every holding at the given prices in the base currency, quotes in other
+    // currencies converted at `rates`
+    // None while a holding has no price or its currency has no rate
+    pub fn holdings_value(
+        &self,
+        prices: &HashMap<Symbol, Decimal>,
+        rates: &HashMap<String, Decimal>,
+    ) -> Option<Decimal> {
+        let mut value = Decimal::ZERO;
+        for (symbol, holding) in &self.holdings {
+            let price = prices.get(symbol).copied().filter(|p| *p > Decimal::ZERO)?;
+            let rate = fx::rate_in(rates, &fx::trading_currency(symbol))?;
+            value += holding.get_qty() * price * rate;
+        }
+        Some(value)
+    }
+
+    // Record the account value now, keeping only the latest snapshot of each UTC day
+    pub fn record_equity_snapshot(&mut self, value: Decimal) {
+        let snapshot = EquitySnapshot::new(self.now_timestamp(), value);
+        match self.equity_snapshots.last_mut() {
+            Some(last) if last.day() == snapshot.day() => *last = snapshot,
+            // A new day's value is worth an autosave; updates within the day ride along
+            _ => {
+                self.equity_snapshots.push(snapshot);
+                self.dirty = true;
+            }
+        }
+    }
+
+    pub fn get_equity_snapshots(&self) -> Vec<EquitySnapshot> {
+        self.equity_snapshots.clone()
+    }
+
+    pub fn set_equity_snapshots(&mut self, mut snapshots: Vec<EquitySnapshot>) {
+        snapshots.sort_by_key(|snapshot| snapshot.timestamp);
+        self.equity_snapshots = snapshots;
+    }
+
+    // Get all holdings as copy of internal map
+    pub fn get_holdings_map(&self) -> HashMap<Symbol, Holding> {
+        self.holdings.clone()
+    }
+
+    // Update holdings and refresh display
+    pub fn set_holdings_map(&mut self, new_holdings_map: HashMap<Symbol, Holding>) {
+        self.holdings = new_holdings_map;
+    }
+
+    // Add completed trade to history
+    pub fn add_trade(&mut self, trade_to_add: Trade) {
+        let mut new_trades = self.trades.clone();
+        new_trades.push(trade_to_add);
+        self.trades = new_trades;
+    }
+
+    // Conditional orders that filled, oldest first
+    pub fn get_executed_orders(&self) -> Vec<ExecutedOrder> {
+        self.executed_orders.clone()
+    }
+
+    pub fn set_executed_orders(&mut self, executed_orders: Vec<ExecutedOrder>) {
+        self.executed_orders = executed_orders;
+    }
+
+    pub fn record_executed_order(&mut self, executed: ExecutedOrder) {
+        self.executed_orders.push(executed);
+    }
+
+    pub fn set_trades(&mut self, new_trades: Vec<Trade>) {
+        self.trades = new_trades;
+    }
+
+    pub fn get_trades(&self) -> Vec<Trade> {
+        self.trades.clone()
+    }
+
+    /// Formats trade history as a string for TUI display, newest first
+    /// `limit` keeps only the most recent trades; None shows everything
+    /// `tag` keeps only trades with that strategy tag
+    /// Returns formatted string or "No trades yet" if empty
+    pub fn display_trades(&self, limit: Option<usize>, tag: Option<&str>) -> String {
+        if self.trades.is_empty() {
+            return "No trades yet".to_string();
+        }
+
+        // Stable sort keeps later-recorded trades first among equal timestamps
+        let mut newest_first: Vec<&Trade> = self
+            .trades
+            .iter()
+            .rev()
+            .filter(|trade| tag.is_none() || trade.get_tag() == tag)
+            .collect();
+        if let Some(tag) = tag
+            && newest_first.is_empty()
+        {
+            return format!("No trades tagged '{}'", tag);
+        }
+        newest_first.sort_by_key(|trade| std::cmp::Reverse(trade.get_timestamp()));
+        let matching = newest_first.len();
+        let shown = limit.unwrap_or(matching).min(matching);
+        newest_first.truncate(shown);
+
+        // The tag column only appears once something shown is tagged
+        let tagged = newest_first.iter().any(|trade| trade.get_tag().is_some());
+        let mut aligns = vec![
+            Align::Left,
+            Align::Left,
+            Align::Left,
+            Align::Right,
+            Align::Right,
+            Align::Left,
+        ];
+        let mut header = vec!["Type", "Symbol", "Side", "Qty", "Price", "Time"];
+        if tagged {
+            aligns.push(Align::Left);
+            header.push("Tag");
+        }
+        let mut table = TextTable::new(&aligns).with_header(&header);
+        for trade in newest_first {
+            let datetime = self.display_timezone.date_time(trade.get_timestamp());
+
+            // A split records its share multiplier, not a fill
+            let (side, quantity, price) = if trade.get_order_type() == SPLIT_ORDER_TYPE {
+                (
+                    "ADJ",
+                    format!("x{}", trade.get_quantity().normalize()),
+                    "-".to_string(),
+                )
+            } else {
+                let side = match trade.get_side() {
+                    Side::Buy => "BUY",
+                    Side::Sell => "SELL",
+                };
+                let asset_type = AssetType::for_symbol(trade.get_symbol());
+                let price = self
+                    .money_format
+                    .money_price(trade.get_price_per(), asset_type);
+                (side, format_qty(trade.get_quantity(), asset_type), price)
+            };
+
+            let mut row = vec![
+                trade.get_order_type().to_string(),
+                trade.get_symbol().to_string(),
+                side.to_string(),
+                quantity,
+                price,
+                datetime,
+            ];
+            if tagged {
+                row.push(trade.get_tag().unwrap_or("-").to_string());
+            }
+            table.push_row(row);
+        }
+
+        let rule = "─".repeat(table.line_width());
+        let mut lines = table.lines().into_iter();
+        let mut result = format!("Trade History:\n{}\n", rule);
+        if let Some(header) = lines.next() {
+            result.push_str(&format!("{}\n{}\n", header, rule));
+        }
+        for line in lines {
+            result.push_str(&line);
+            result.push('\n');
+        }
+
+        let hidden = matching - shown;
+        if hidden > 0 {
+            let all = match tag {
+                Some(tag) => format!("trades --all --tag {}", tag),
+                None => "trades --all".to_string(),
+            };
+            result.push_str(&format!("\n… {} more (use {})", hidden, all));
+        }
+
+        result
+    }
+
+    /// Formats the filled conditional orders, newest first, optionally for one symbol
+    /// Gap is how far the fill landed from the order's target, as a percent of the target
+    pub fn display_executed_orders(&self, symbol: Option<&str>) -> String {
+        let mut newest_first: Vec<&ExecutedOrder> = self
+            .executed_orders
+            .iter()
+            .rev()
+            .filter(|executed| symbol.is_none_or(|symbol| executed.symbol == symbol))
+            .collect();
+        if newest_first.is_empty() {
+            return match symbol {
+                Some(symbol) => format!("No executed orders for {}", symbol),
+                None => "No executed orders yet".to_string(),
+            };
+        }
+        newest_first.sort_by_key(|executed| std::cmp::Reverse(executed.executed_at));
+
+        let mut table = TextTable::new(&[
+            Align::Left,
+            Align::Left,
+            Align::Right,
+            Align::Right,
+            Align::Right,
+            Align::Right,
+            Align::Left,
+            Align::Left,
+        ])
+        .with_header(&[
+            "Type", "Symbol", "Qty", "Target", "Fill", "Gap", "Placed", "Filled",
+        ]);
+        for executed in newest_first {
+            let asset_type = AssetType::for_symbol(&executed.symbol);
+            let gap = percent_of(executed.fill_gap(), executed.target_price)
+                .map(crate::format::format_pct)
+                .unwrap_or_else(|| "-".to_string());
+            table.push_row(vec![
+                format!("{:?}", executed.order_type),
+                executed.symbol.clone(),
+                format_qty(executed.quantity, asset_type),
+                self.money_format
+                    .money_price(executed.target_price, asset_type),
+                self.money_format
+                    .money_price(executed.fill_price, asset_type),
+                gap,
+                self.display_timezone.date_time(executed.placed_at),
+                self.display_timezone.date_time(executed.executed_at),
+            ]);
+        }
+
+        let mut lines = vec!["Executed Orders:".to_string()];
+        lines.extend(table.lines());
+        lines.join("\n")
+    }
+
+    // Every symbol the account refers to, with where it appears
+    // Interest credits and charges are cash, not a symbol
+    pub fn symbol_footprint(&self) -> BTreeMap<Symbol, SymbolFootprint> {
+        let mut footprint: BTreeMap<Symbol, SymbolFootprint> = BTreeMap::new();
+        for symbol in self.holdings.keys() {
+            footprint.entry(symbol.clone()).or_default().held = true;
+        }
+        for symbol in &self.watchlist {
+            footprint.entry(symbol.clone()).or_default().watched = true;
+        }
+        for order in &self.open_orders {
+            footprint
+                .entry(order.get_symbol().clone())
+                .or_default()
+                .open_orders += 1;
+        }
+        for trade in &self.trades {
+            if trade.get_order_type() == INTEREST_ORDER_TYPE {
+                continue;
+            }
+            footprint
+                .entry(trade.get_symbol().clone())
+                .or_default()
+                .trades += 1;
+        }
+        footprint
+    }
+
+    pub fn display_symbols(&self) -> String {
+        let footprint = self.symbol_footprint();
+        if footprint.is_empty() {
+            return "No symbols in the account yet".to_string();
+        }
+
+        let mut table = TextTable::new(&[Align::Left, Align::Left, Align::Right, Align::Right])
+            .with_header(&["Symbol", "Appears in", "Orders", "Trades"]);
+        for (symbol, usage) in &footprint {
+            table.push_row(vec![
+                symbol.clone(),
+                usage.places().join(", "),
+                usage.open_orders.to_string(),
+                usage.trades.to_string(),
+            ]);
+        }
+
+        let count = |place: &str| {
+            footprint
+                .values()
+                .filter(|usage| usage.places().contains(&place))
+                .count()
+        };
+        let mut lines = vec![format!("Symbols ({}):", footprint.len())];
+        lines.extend(table.lines());
+        lines.push(format!(
+            "{} held, {} watched, {} ordered, {} traded",
+            count("held"),
+            count("watched"),
+            count("ordered"),
+            count("traded")
+        ));
+        lines.join("\n")
+    }
+
+    pub fn add_to_watchlist(&mut self, symbol: Symbol) -> bool {
+        if !self.watchlist.contains(&symbol) {
+            self.watchlist.push(symbol);
+            return true;
+        }
+        false
+    }
+
+    pub fn remove_from_watchlist(&mut self, symbol: Symbol) -> bool {
+        if let Some(pos) = self.watchlist.iter().position(|x| *x == symbol) {
+            self.watchlist.remove(pos);
+            return true;
+        }
+        false
+    }
+
+    pub fn get_watchlist(&self) -> Vec<Symbol> {
+        self.watchlist.clone()
+    }
+
+    pub fn set_watchlist(&mut self, watchlist: Vec<Symbol>) {
+        self.watchlist = watchlist;
+    }
+
+    pub fn get_note(&self, symbol: &Symbol) -> Option<&String> {
+        self.notes.get(symbol)
+    }
+
+    // Attach a note to a symbol, returning the note it replaces
+    // Notes are kept when the symbol leaves the watchlist or holdings, so they return with it
+    pub fn set_note(&mut self, symbol: Symbol, text: String) -> Option<String> {
+        self.notes.insert(symbol, text)
+    }
+
+    // Remove a symbol's note, false if it had none
+    pub fn clear_note(&mut self, symbol: &Symbol) -> bool {
+        self.notes.remove(symbol).is_some()
+    }
+
+    pub fn get_notes(&self) -> BTreeMap<Symbol, String> {
+        self.notes.clone()
+    }
+
+    pub fn set_notes(&mut self, notes: BTreeMap<Symbol, String>) {
+        self.notes = notes;
+    }
+
+    pub fn get_template(&self, name: &str) -> Option<&OrderTemplate> {
+        self.templates.get(name)
+    }
+
+    // Save a template under `name`, returning the one it replaces
+    pub fn set_template(&mut self, name: String, template: OrderTemplate) -> Option<OrderTemplate> {
+        self.templates.insert(name, template)
+    }
+
+    // Remove a template, false if there was none by that name
+    pub fn remove_template(&mut self, name: &str) -> bool {
+        self.templates.remove(name).is_some()
+    }
+
+    pub fn get_templates(&self) -> BTreeMap<String, OrderTemplate> {
+        self.templates.clone()
+    }
+
+    pub fn set_templates(&mut self, templates: BTreeMap<String, OrderTemplate>) {
+        self.templates = templates;
+    }
+
+    // Schedule a recurring buy, replacing the symbol's existing schedule if any
+    pub fn set_recurring_buy(&mut self, schedule: RecurringBuy) -> Option<RecurringBuy> {
+        self.recurring_buys
+            .insert(schedule.symbol.clone(), schedule)
+    }
+
+    // Cancel a symbol's recurring buy, false if it had none
+    pub fn remove_recurring_buy(&mut self, symbol: &Symbol) -> bool {
+        self.recurring_buys.remove(symbol).is_some()
+    }
+
+    // Move a schedule past `now` once its buy has been handled
+    pub fn advance_recurring_buy(&mut self, symbol: &Symbol, now: i64) {
+        if let Some(schedule) = self.recurring_buys.get_mut(symbol) {
+            schedule.advance(now);
+        }
+    }
+
+    pub fn get_recurring_buys(&self) -> BTreeMap<Symbol, RecurringBuy> {
+        self.recurring_buys.clone()
+    }
+
+    pub fn set_recurring_buys(&mut self, recurring_buys: BTreeMap<Symbol, RecurringBuy>) {
+        self.recurring_buys = recurring_buys;
+    }
+
+    // Whether the provider has already quoted this symbol this session
+    pub fn is_symbol_validated(&self, symbol: &Symbol) -> bool {
+        self.validated_symbols.contains(symbol)
+    }
+
+    pub fn mark_symbol_validated(&mut self, symbol: Symbol) {
+        self.validated_symbols.insert(symbol);
+    }
+
+    pub fn set_pending_import(&mut self, pending: bool) {
+        self.pending_import = pending;
+    }
+
+    pub fn is_pending_import(&self) -> bool {
+        self.pending_import
+    }
+
+    pub fn set_import_dry_run(&mut self, dry_run: bool) {
+        self.import_dry_run = dry_run;
+    }
+
+    pub fn is_import_dry_run(&self) -> bool {
+        self.import_dry_run
+    }
+
+    // Demo sessions show the sample portfolio and never persist anything
+    pub fn is_demo(&self) -> bool {
+        self.demo
+    }
+
+    pub fn set_demo(&mut self, demo: bool) {
+        self.demo = demo;
+    }
+
+    // File refreshed with a copy of the database after a successful save
+    pub fn get_backup_path(&self) -> Option<PathBuf> {
+        self.backup_path.clone()
+    }
+
+    pub fn set_backup_path(&mut self, path: Option<PathBuf>) {
+        self.backup_path = path;
+    }
+
+    // The backup is refreshed on the first save of a session and then at most once
+    // every BACKUP_INTERVAL_SECS, so frequent autosaves don't copy the whole file
+    pub fn backup_due(&self) -> bool {
+        self.backup_written_at
+            .is_none_or(|at| self.now_timestamp() - at >= BACKUP_INTERVAL_SECS)
+    }
+
+    pub fn mark_backup_written(&mut self) {
+        self.backup_written_at = Some(self.now_timestamp());
+    }
+
+    // Swap in a restored account, keeping this session's provider chain, clock,
+    // events, offline switch, backup location and order monitor. The undo history
+    // doesn't carry over
+    pub fn replace_account(&mut self, account: AppState) {
+        let session = std::mem::replace(self, account);
+        // The session's provider waits on the session's throttle; keep it with the
+        // restored account's limits
+        session.throttle.set_limits(self.throttle.limits());
+        self.throttle = session.throttle;
+        self.provider = session.provider;
+        self.provider_sources = session.provider_sources;
+        self.provider_chain = session.provider_chain;
+        self.clock = session.clock;
+        self.events = session.events;
+        self.offline = session.offline;
+        self.backup_path = session.backup_path;
+        self.backup_written_at = session.backup_written_at;
+        self.monitor_task = session.monitor_task;
+    }
+
+    // Keep the task of the order monitor just started, stopping any earlier one so
+    // only one monitor runs
+    pub fn set_monitor_task(&mut self, task: JoinHandle<()>) {
+        if let Some(previous) = self.monitor_task.replace(task) {
+            previous.abort();
+        }
+    }
+
+    pub fn take_monitor_task(&mut self) -> Option<JoinHandle<()>> {
+        self.monitor_task.take()
+    }
+
+    // Market order slippage in basis points, zero means fills at the quoted price
+    pub fn get_slippage_bps(&self) -> Decimal {
+        self.slippage_bps
+    }
+
+    pub fn set_slippage_bps(&mut self, bps: Decimal) {
+        self.slippage_bps = bps;
+    }
+
+    // Commission model charged on every fill
+    pub fn get_fee_model(&self) -> FeeModel {
+        self.fee_model
+    }
+
+    pub fn set_fee_model(&mut self, fee_model: FeeModel) {
+        self.fee_model = fee_model;
+    }
+
+    // Estimated commission on an open order filling at its order price
+    pub fn estimated_fee(&self, order: &OpenOrder) -> Decimal {
+        self.fee_model
+            .fee_for(order.get_qty() * order.get_price_per())
+    }
+
+    // Debit the commission on a fill worth `notional` from `currency`
+    // Returns the fee, for the trade to record
+    pub fn charge_fee(&mut self, currency: &str, notional: Decimal) -> Decimal {
+        let fee = self.fee_model.fee_for(notional);
+        if fee > Decimal::ZERO {
+            self.pay_in(currency, fee);
+        }
+        fee
+    }
+
+    pub fn get_cost_basis(&self) -> CostBasisMethod {
+        self.cost_basis
+    }
+
+    pub fn set_cost_basis(&mut self, method: CostBasisMethod) {
+        self.cost_basis = method;
+    }
+
+    // Realized gain of every sell in the trade log under the selected cost basis method
+    pub fn realized_gains(&self) -> Vec<RealizedGain> {
+        costbasis::realized_gains(&self.trades, self.cost_basis)
+    }
+
+    // Lot pieces closed by sells under the selected cost basis method
+    pub fn closed_lots(&self) -> Vec<ClosedLot> {
+        costbasis::closed_lots(&self.trades, self.cost_basis)
+    }
+
+    // Lots still held under the selected cost basis method
+    pub fn open_lots(&self) -> Vec<OpenLot> {
+        costbasis::open_lots(&self.trades, self.cost_basis)
+    }
+
+    // Rounding applied to bought share quantities
+    pub fn get_share_policy(&self) -> SharePolicy {
+        self.share_policy
+    }
+
+    pub fn set_share_policy(&mut self, policy: SharePolicy) {
+        self.share_policy = policy;
+    }
+
+    // Seconds between TUI price refreshes
+    pub fn get_refresh_secs(&self) -> u64 {
+        self.refresh_secs
+    }
+
+    pub fn set_refresh_secs(&mut self, secs: u64) -> Result<(), String> {
+        self.refresh_secs = check_interval(secs)?;
+        Ok(())
+    }
+
+    // Seconds between background order checks
+    pub fn get_monitor_secs(&self) -> u64 {
+        self.monitor_secs
+    }
+
+    pub fn set_monitor_secs(&mut self, secs: u64) -> Result<(), String> {
+        self.monitor_secs = check_interval(secs)?;
+        Ok(())
+    }
+
+    // Where the TUI output pane scrolls after each command
+    pub fn get_output_scroll(&self) -> ScrollAfterCommand {
+        self.output_scroll
+    }
+
+    pub fn set_output_scroll(&mut self, scroll: ScrollAfterCommand) {
+        self.output_scroll = scroll;
+    }
+
+    // Longest command the input box accepts and commands will run
+    pub fn get_max_command_len(&self) -> usize {
+        self.max_command_len
+    }
+
+    pub fn set_max_command_len(&mut self, len: usize) -> Result<(), String> {
+        if !(MIN_COMMAND_LEN..=MAX_COMMAND_LEN).contains(&len) {
+            return Err(format!(
+                "Command length limit must be between {} and {} characters",
+                MIN_COMMAND_LEN, MAX_COMMAND_LEN
+            ));
+        }
+        self.max_command_len = len;
+        Ok(())
+    }
+
+    // Annual yield paid on idle cash, as a percentage
+    pub fn get_cash_apy(&self) -> Decimal {
+        self.cash_apy
+    }
+
+    // Changing the yield restarts accrual from `now` so past days aren't paid at the new rate
+    pub fn set_cash_apy(&mut self, apy: Decimal, now: i64) {
+        self.cash_apy = apy;
+        self.yield_accrued_at = now;
+    }
+
+    // Currency symbol and separator style used for every displayed amount
+    pub fn get_money_format(&self) -> MoneyFormat {
+        self.money_format.clone()
+    }
+
+    pub fn set_money_format(&mut self, money_format: MoneyFormat) {
+        self.money_format = money_format;
+    }
+
+    // Zone trade, order, ledger and event times are shown in
+    pub fn get_display_timezone(&self) -> DisplayTimezone {
+        self.display_timezone
+    }
+
+    pub fn set_display_timezone(&mut self, display_timezone: DisplayTimezone) {
+        self.display_timezone = display_timezone;
+    }
+
+    // Target weights as percentages of portfolio value, sorted by symbol
+    pub fn get_target_allocations(&self) -> Vec<(Symbol, Decimal)> {
+        self.target_allocations
+            .iter()
+            .map(|(symbol, pct)| (symbol.clone(), *pct))
+            .collect()
+    }
+
+    // Set a symbol's target weight; 0 clears it
+    // Rejects weights outside 0-100 or that would push the total past 100%
+    // Returns the new total of all targets
+    pub fn set_target_allocation(
+        &mut self,
+        symbol: Symbol,
+        pct: Decimal,
+    ) -> Result<Decimal, String> {
+        if pct < Decimal::ZERO || pct > Decimal::from(100) {
+            return Err("Target must be between 0 and 100 percent".to_string());
+        }
+        let others: Decimal = self
+            .target_allocations
+            .iter()
+            .filter(|(existing, _)| **existing != symbol)
+            .map(|(_, weight)| *weight)
+            .sum();
+        if others + pct > Decimal::from(100) {
+            return Err(format!(
+                "Targets would total {}%. Other targets already use {}%",
+                others + pct,
+                others
+            ));
+        }
+        if pct.is_zero() {
+            self.target_allocations.remove(&symbol);
+        } else {
+            self.target_allocations.insert(symbol, pct);
+        }
+        Ok(others + pct)
+    }
+
+    // Canonical Yahoo symbol for a typed one, through the user's aliases
+    pub fn normalize_symbol(&self, raw: &str) -> Symbol {
+        normalize_symbol(raw, &self.symbol_aliases)
+    }
+
+    // User aliases, sorted by what is typed
+    pub fn get_symbol_aliases(&self) -> BTreeMap<Symbol, Symbol> {
+        self.symbol_aliases.clone()
+    }
+
+    // Maps `alias` to `symbol`; both are stored uppercased
+    // The target is normalized without user aliases, so aliases never chain
+    pub fn set_symbol_alias(&mut self, alias: &str, symbol: &str) -> Symbol {
+        let symbol = normalize_symbol(symbol, &BTreeMap::new());
+        self.symbol_aliases
+            .insert(alias.trim().to_uppercase(), symbol.clone());
+        symbol
+    }
+
+    // Returns whether the alias existed
+    pub fn remove_symbol_alias(&mut self, alias: &str) -> bool {
+        self.symbol_aliases
+            .remove(&alias.trim().to_uppercase())
+            .is_some()
     }
 
-    // Withdraw funds with validation
-    pub fn withdraw(&mut self, amount: Decimal) {
-        if amount < Decimal::ZERO {
-            println!("Invalid amount");
-            return;
-        }
-        if amount > self.cash_balance {
-            println!("Insufficient balance");
-            return;
+    // Record how to reverse a command, dropping the oldest entry past UNDO_LIMIT
+    pub fn push_undo(&mut self, action: UndoAction) {
+        if self.undo_stack.len() == UNDO_LIMIT {
+            self.undo_stack.pop_front();
         }
-        self.cash_balance -= amount;
+        self.undo_stack.push_back(action);
     }
 
-    // Deduct purchase amount from balance without validation (used in buy functions)
-    pub fn withdraw_purchase(&mut self, amount: Decimal) {
-        if amount < Decimal::ZERO {
-            println!("Invalid amount");
-            return;
+    // Reverse the most recent recorded command
+    // A failed reversal stays on the stack so nothing is lost
+    pub fn undo_last(&mut self) -> Option<Result<String, String>> {
+        let action = self.undo_stack.pop_back()?;
+        let result = action.revert(self);
+        match result {
+            Ok(message) => Some(Ok(format!(
+                "Undid {}. {}",
+                action.describe(&self.money_format),
+                message
+            ))),
+            Err(e) => {
+                self.undo_stack.push_back(action);
+                Some(Err(e))
+            }
         }
-        self.cash_balance -= amount;
     }
 
-    // Add sale proceeds to balance
-    pub fn deposit_sell(&mut self, amount: Decimal) {
-        self.cash_balance += amount;
+    // Recorded commands, oldest first
+    pub fn get_undo_history(&self) -> Vec<UndoAction> {
+        self.undo_stack.iter().cloned().collect()
     }
 
-    // Get current cash balance
-    pub fn check_balance(&self) -> Decimal {
-        self.cash_balance
+    pub fn undo_depth(&self) -> usize {
+        self.undo_stack.len()
     }
 
-    pub fn set_cash_balance(&mut self, new_balance: Decimal) {
-        self.cash_balance = new_balance;
+    // Extra market closures configured by the user, on top of NYSE holidays
+    pub fn get_market_holidays(&self) -> Vec<NaiveDate> {
+        self.market_holidays.clone()
     }
 
-    // Get all holdings as copy of internal map
-    pub fn get_holdings_map(&self) -> HashMap<Symbol, Holding> {
-        self.holdings.clone()
+    // Returns false if the date was already a custom holiday
+    pub fn add_market_holiday(&mut self, date: NaiveDate) -> bool {
+        if self.market_holidays.contains(&date) {
+            return false;
+        }
+        self.market_holidays.push(date);
+        self.market_holidays.sort();
+        true
     }
 
-    // Update holdings and refresh display
-    pub async fn set_holdings_map(&mut self, new_holdings_map: HashMap<Symbol, Holding>) {
-        self.holdings = new_holdings_map;
+    // Returns false if the date wasn't a custom holiday
+    pub fn remove_market_holiday(&mut self, date: NaiveDate) -> bool {
+        let before = self.market_holidays.len();
+        self.market_holidays.retain(|d| *d != date);
+        self.market_holidays.len() != before
     }
 
-    // Add completed trade to history
-    pub fn add_trade(&mut self, trade_to_add: Trade) {
-        let mut new_trades = self.trades.clone();
-        new_trades.push(trade_to_add);
-        self.trades = new_trades;
+    pub fn market_calendar(&self) -> MarketCalendar {
+        MarketCalendar::new(self.market_holidays.clone())
     }
 
-    pub fn set_trades(&mut self, new_trades: Vec<Trade>) {
-        self.trades = new_trades;
+    // Timestamp up to which cash yield has already been credited
+    pub fn get_yield_accrued_at(&self) -> i64 {
+        self.yield_accrued_at
     }
 
-    pub fn get_trades(&self) -> Vec<Trade> {
-        self.trades.clone()
+    pub fn set_yield_accrued_at(&mut self, timestamp: i64) {
+        self.yield_accrued_at = timestamp;
     }
 
-    /// Formats trade history as a string for TUI display
-    /// Returns formatted string or "No trades yet" if empty
-    pub fn display_trades(&self) -> String {
-        if self.trades.is_empty() {
-            return "No trades yet".to_string();
+    // Credit daily compounded interest for each full day since the last accrual
+    // Records the credit as an "Interest" trade on CASH and returns the amount
+    pub fn accrue_cash_yield(&mut self, now: i64) -> Decimal {
+        if self.yield_accrued_at == 0 {
+            self.yield_accrued_at = now;
+            return Decimal::ZERO;
         }
 
-        let mut result = String::from("Trade History:\n");
-        result.push_str("────────────────────────────────────────────────────────────\n");
-        result.push_str(&format!(
-            "{:<10} {:<8} {:<6} {:<8} {:<12} {:<16}\n",
-            "Type", "Symbol", "Side", "Qty", "Price", "Time"
-        ));
-        result.push_str("────────────────────────────────────────────────────────────\n");
-
-        for trade in self.trades.iter().rev().take(20) {
-            // Show last 20, most recent first
-            let datetime =
-                chrono::DateTime::<chrono::Utc>::from_timestamp(trade.get_timestamp(), 0)
-                    .map(|dt| {
-                        dt.with_timezone(&chrono::Local)
-                            .format("%Y-%m-%d %H:%M")
-                            .to_string()
-                    })
-                    .unwrap_or_else(|| "Unknown".to_string());
+        let days = (now - self.yield_accrued_at) / SECONDS_PER_DAY;
+        if days <= 0 || self.cash_apy <= Decimal::ZERO {
+            return Decimal::ZERO;
+        }
 
-            let side = match trade.get_side() {
-                Side::Buy => "BUY",
-                Side::Sell => "SELL",
-            };
+        // Advance by whole days only so the partial day keeps accruing
+        self.yield_accrued_at += days * SECONDS_PER_DAY;
+        if self.cash_balance <= Decimal::ZERO {
+            return Decimal::ZERO;
+        }
 
-            result.push_str(&format!(
-                "{:<10} {:<8} {:<6} {:<8} ${:<11.2} {:<16}\n",
-                trade.get_order_type(),
-                trade.get_symbol(),
-                side,
-                trade.get_quantity(),
-                trade.get_price_per(),
-                datetime
-            ));
+        let daily_rate = self.cash_apy / Decimal::from(100) / Decimal::from(365);
+        let mut interest = Decimal::ZERO;
+        for _ in 0..days.min(MAX_ACCRUAL_DAYS) {
+            interest += (self.cash_balance + interest) * daily_rate;
         }
+        let interest = interest.round_dp(2);
 
-        if self.trades.len() > 20 {
-            result.push_str(&format!("\n... and {} more trades", self.trades.len() - 20));
+        if interest > Decimal::ZERO {
+            self.cash_balance += interest;
+            self.add_trade(Trade::from_database(
+                "CASH".to_string(),
+                interest,
+                Decimal::ONE,
+                Side::Buy,
+                self.yield_accrued_at,
+                INTEREST_ORDER_TYPE.to_string(),
+            ));
         }
+        interest
+    }
 
-        result
+    // Configurable settings as key/value pairs for the setting table
+    pub fn get_settings(&self) -> Vec<(String, String)> {
+        vec![
+            ("slippage_bps".to_string(), self.slippage_bps.to_string()),
+            ("fee_flat".to_string(), self.fee_model.flat.to_string()),
+            ("fee_bps".to_string(), self.fee_model.bps.to_string()),
+            ("cost_basis".to_string(), self.cost_basis.name().to_string()),
+            ("share_policy".to_string(), self.share_policy.name()),
+            ("refresh_secs".to_string(), self.refresh_secs.to_string()),
+            ("monitor_secs".to_string(), self.monitor_secs.to_string()),
+            (
+                "output_scroll".to_string(),
+                self.output_scroll.name().to_string(),
+            ),
+            (
+                "max_command_len".to_string(),
+                self.max_command_len.to_string(),
+            ),
+            (
+                "provider_chain".to_string(),
+                self.provider_sources
+                    .iter()
+                    .map(ProviderSource::name)
+                    .collect::<Vec<_>>()
+                    .join(","),
+            ),
+            (
+                "throttle_concurrent".to_string(),
+                self.throttle.limits().max_concurrent.to_string(),
+            ),
+            (
+                "throttle_spacing_ms".to_string(),
+                self.throttle.limits().min_spacing_ms.to_string(),
+            ),
+            ("cash_apy".to_string(), self.cash_apy.to_string()),
+            (
+                "yield_accrued_at".to_string(),
+                self.yield_accrued_at.to_string(),
+            ),
+            (
+                "deposit_cap".to_string(),
+                self.deposit_cap
+                    .map(|cap| cap.to_string())
+                    .unwrap_or_default(),
+            ),
+            (
+                "risk_max_position_pct".to_string(),
+                self.risk_limits
+                    .max_position_pct
+                    .map(|pct| pct.to_string())
+                    .unwrap_or_default(),
+            ),
+            (
+                "risk_max_order".to_string(),
+                self.risk_limits
+                    .max_order_notional
+                    .map(|limit| limit.to_string())
+                    .unwrap_or_default(),
+            ),
+            ("leverage".to_string(), self.leverage.to_string()),
+            ("margin_rate".to_string(), self.margin_rate.to_string()),
+            ("margin_loan".to_string(), self.margin_loan.to_string()),
+            (
+                "margin_accrued_at".to_string(),
+                self.margin_accrued_at.to_string(),
+            ),
+            (
+                "currency_symbol".to_string(),
+                self.money_format.symbol().to_string(),
+            ),
+            (
+                "number_style".to_string(),
+                self.money_format.style().name().to_string(),
+            ),
+            ("display_timezone".to_string(), self.display_timezone.name()),
+            (
+                "target_allocations".to_string(),
+                self.target_allocations
+                    .iter()
+                    .map(|(symbol, pct)| format!("{}:{}", symbol, pct))
+                    .collect::<Vec<_>>()
+                    .join(","),
+            ),
+            (
+                "symbol_aliases".to_string(),
+                self.symbol_aliases
+                    .iter()
+                    .map(|(alias, symbol)| format!("{}:{}", alias, symbol))
+                    .collect::<Vec<_>>()
+                    .join(","),
+            ),
+            (
+                "currency_cash".to_string(),
+                self.currency_cash
+                    .iter()
+                    .map(|(currency, balance)| format!("{}:{}", currency, balance))
+                    .collect::<Vec<_>>()
+                    .join(","),
+            ),
+            (
+                "market_holidays".to_string(),
+                self.market_holidays
+                    .iter()
+                    .map(|date| date.to_string())
+                    .collect::<Vec<_>>()
+                    .join(","),
+            ),
+            (
+                "webhook_url".to_string(),
+                self.webhook_url.clone().unwrap_or_default(),
+            ),
+        ]
     }
 
-    pub fn add_to_watchlist(&mut self, symbol: Symbol) -> bool {
-        if !self.watchlist.contains(&symbol) {
-            self.watchlist.push(symbol);
-            return true;
+    // URL notified when a background order fills, None when webhooks are off
+    pub fn get_webhook_url(&self) -> Option<String> {
+        self.webhook_url.clone()
+    }
+
+    pub fn set_webhook_url(&mut self, url: Option<String>) {
+        self.webhook_url = url;
+        self.webhook_error = None;
+    }
+
+    // Remember how the last delivery went: None on success, the failure otherwise
+    // Failures are also raised as events so they show up in the TUI
+    pub fn record_webhook_result(&mut self, error: Option<String>) {
+        if let Some(error) = &error {
+            self.push_event(
+                EventKind::Error,
+                format!("Webhook delivery failed: {}", error),
+            );
         }
-        false
+        self.webhook_error = error;
     }
 
-    pub fn remove_from_watchlist(&mut self, symbol: Symbol) -> bool {
-        if let Some(pos) = self.watchlist.iter().position(|x| *x == symbol) {
-            self.watchlist.remove(pos);
-            return true;
+    // Record a background event for the TUI, dropping the oldest past the cap
+    pub fn push_event(&mut self, kind: EventKind, message: String) {
+        self.events
+            .push_back(AppEvent::new(self.now_timestamp(), kind, message));
+        while self.events.len() > EVENT_LIMIT {
+            self.events.pop_front();
         }
-        false
     }
 
-    pub fn get_watchlist(&self) -> Vec<Symbol> {
-        self.watchlist.clone()
+    // Take every pending event, oldest first
+    pub fn drain_events(&mut self) -> Vec<AppEvent> {
+        self.events.drain(..).collect()
     }
 
-    pub fn set_watchlist(&mut self, watchlist: Vec<Symbol>) {
-        self.watchlist = watchlist;
+    pub fn pending_events(&self) -> usize {
+        self.events.len()
     }
 
-    pub fn set_pending_import(&mut self, pending: bool) {
-        self.pending_import = pending;
+    // Flag a change that isn't saved yet, so the next autosave writes it
+    pub fn mark_dirty(&mut self) {
+        self.dirty = true;
     }
 
-    pub fn is_pending_import(&self) -> bool {
-        self.pending_import
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    // Called by save_state as it takes the state to write
+    pub fn clear_dirty(&mut self) {
+        self.dirty = false;
+    }
+
+    pub fn get_webhook_error(&self) -> Option<String> {
+        self.webhook_error.clone()
+    }
+
+    // Restore a stored setting, ignoring unknown keys and unparsable values
+    pub fn apply_setting(&mut self, key: &str, value: &str) {
+        match key {
+            "slippage_bps" => {
+                if let Ok(bps) = value.parse() {
+                    self.slippage_bps = bps;
+                }
+            }
+            "fee_flat" => {
+                if let Ok(flat) = value.parse() {
+                    self.fee_model.flat = flat;
+                }
+            }
+            "fee_bps" => {
+                if let Ok(bps) = value.parse() {
+                    self.fee_model.bps = bps;
+                }
+            }
+            "cost_basis" => {
+                if let Some(method) = CostBasisMethod::parse(value) {
+                    self.cost_basis = method;
+                }
+            }
+            "share_policy" => {
+                if let Some(policy) = SharePolicy::parse(value) {
+                    self.share_policy = policy;
+                }
+            }
+            "refresh_secs" => {
+                if let Ok(secs) = value.parse() {
+                    let _ = self.set_refresh_secs(secs);
+                }
+            }
+            "monitor_secs" => {
+                if let Ok(secs) = value.parse() {
+                    let _ = self.set_monitor_secs(secs);
+                }
+            }
+            "output_scroll" => {
+                if let Some(scroll) = ScrollAfterCommand::parse(value) {
+                    self.output_scroll = scroll;
+                }
+            }
+            "max_command_len" => {
+                if let Ok(len) = value.parse() {
+                    let _ = self.set_max_command_len(len);
+                }
+            }
+            "provider_chain" => {
+                if let Ok(sources) = ProviderSource::parse_chain(value)
+                    && sources != self.provider_sources
+                {
+                    self.set_provider_sources(sources);
+                }
+            }
+            "throttle_concurrent" => {
+                if let Ok(max_concurrent) = value.parse() {
+                    let _ = self.set_throttle_limits(ThrottleLimits {
+                        max_concurrent,
+                        ..self.get_throttle_limits()
+                    });
+                }
+            }
+            "throttle_spacing_ms" => {
+                if let Ok(min_spacing_ms) = value.parse() {
+                    let _ = self.set_throttle_limits(ThrottleLimits {
+                        min_spacing_ms,
+                        ..self.get_throttle_limits()
+                    });
+                }
+            }
+            "cash_apy" => {
+                if let Ok(apy) = value.parse() {
+                    self.cash_apy = apy;
+                }
+            }
+            "yield_accrued_at" => {
+                if let Ok(timestamp) = value.parse() {
+                    self.yield_accrued_at = timestamp;
+                }
+            }
+            "deposit_cap" => {
+                self.deposit_cap = value
+                    .parse::<Decimal>()
+                    .ok()
+                    .filter(|cap| *cap > Decimal::ZERO);
+            }
+            "risk_max_position_pct" => {
+                self.risk_limits.max_position_pct = value
+                    .parse::<Decimal>()
+                    .ok()
+                    .filter(|pct| *pct > Decimal::ZERO && *pct <= Decimal::ONE_HUNDRED);
+            }
+            "risk_max_order" => {
+                self.risk_limits.max_order_notional = value
+                    .parse::<Decimal>()
+                    .ok()
+                    .filter(|limit| *limit > Decimal::ZERO);
+            }
+            "leverage" => {
+                if let Ok(leverage) = value.parse::<Decimal>()
+                    && (margin::NO_LEVERAGE..=margin::MAX_LEVERAGE).contains(&leverage)
+                {
+                    self.leverage = leverage;
+                }
+            }
+            "margin_rate" => {
+                if let Ok(rate) = value.parse::<Decimal>()
+                    && rate >= Decimal::ZERO
+                {
+                    self.margin_rate = rate;
+                }
+            }
+            "margin_loan" => {
+                if let Ok(loan) = value.parse::<Decimal>()
+                    && loan >= Decimal::ZERO
+                {
+                    self.margin_loan = loan;
+                }
+            }
+            "margin_accrued_at" => {
+                if let Ok(timestamp) = value.parse() {
+                    self.margin_accrued_at = timestamp;
+                }
+            }
+            "currency_symbol" if !value.is_empty() => {
+                self.money_format = MoneyFormat::new(value, self.money_format.style());
+            }
+            "number_style" => {
+                if let Some(style) = NumberStyle::parse(value) {
+                    self.money_format = MoneyFormat::new(self.money_format.symbol(), style);
+                }
+            }
+            "display_timezone" => {
+                if let Some(display_timezone) = DisplayTimezone::parse(value) {
+                    self.display_timezone = display_timezone;
+                }
+            }
+            "target_allocations" => {
+                self.target_allocations = value
+                    .split(',')
+                    .filter_map(|entry| {
+                        let (symbol, pct) = entry.split_once(':')?;
+                        Some((symbol.trim().to_string(), pct.trim().parse().ok()?))
+                    })
+                    .collect();
+            }
+            "symbol_aliases" => {
+                self.symbol_aliases = value
+                    .split(',')
+                    .filter_map(|entry| {
+                        let (alias, symbol) = entry.split_once(':')?;
+                        Some((alias.trim().to_string(), symbol.trim().to_string()))
+                    })
+                    .filter(|(alias, symbol)| !alias.is_empty() && !symbol.is_empty())
+                    .collect();
+            }
+            "currency_cash" => {
+                self.currency_cash = value
+                    .split(',')
+                    .filter_map(|entry| {
+                        let (currency, balance) = entry.split_once(':')?;
+                        let currency = fx::parse_currency(currency)?;
+                        (currency != fx::BASE_CURRENCY)
+                            .then_some((currency, balance.trim().parse().ok()?))
+                    })
+                    .collect();
+            }
+            "market_holidays" => {
+                self.market_holidays = value
+                    .split(',')
+                    .filter_map(|date| date.trim().parse().ok())
+                    .collect();
+                self.market_holidays.sort();
+            }
+            "webhook_url" => {
+                self.webhook_url = Some(value.to_string()).filter(|url| webhook::is_valid_url(url));
+            }
+            _ => {}
+        }
     }
 
     // Get quantity of shares held for a specific ticker
@@ -195,21 +1897,74 @@ impl AppState {
 
     // Calculate available shares after accounting for pending sell orders
     pub fn get_available_holdings_qty(&self, ticker: &String) -> Decimal {
-        let mut qty = self.get_ticker_holdings_qty(ticker);
-        for o in &self.open_orders {
-            if o.get_side() == Side::Sell && o.get_symbol() == ticker {
-                qty -= o.get_qty();
-            }
+        self.get_ticker_holdings_qty(ticker) - self.get_reserved_sell_qty(ticker)
+    }
+
+    // Shares already committed to pending sell orders (stop loss, take profit) for a ticker
+    pub fn get_reserved_sell_qty(&self, ticker: &String) -> Decimal {
+        self.open_orders
+            .iter()
+            .filter(|o| o.get_side() == Side::Sell && o.get_symbol() == ticker)
+            .map(|o| o.get_qty())
+            .sum()
+    }
+
+    // Error for a sell that needs more than the unreserved shares, None if it fits
+    pub fn check_sell_qty(&self, ticker: &String, quantity: Decimal) -> Option<String> {
+        let held = self.get_ticker_holdings_qty(ticker);
+        let reserved = self.get_reserved_sell_qty(ticker);
+        if quantity <= held - reserved {
+            return None;
+        }
+        let asset_type = AssetType::for_symbol(ticker);
+        if reserved.is_zero() {
+            Some(format!(
+                "Insufficient holdings. Have {} shares of {}",
+                format_qty(held, asset_type),
+                ticker
+            ))
+        } else {
+            Some(format!(
+                "Insufficient holdings. Have {} shares of {}, {} reserved by open sell orders",
+                format_qty(held, asset_type),
+                ticker,
+                format_qty(reserved, asset_type)
+            ))
         }
-        qty
     }
 
-    // Calculate available cash after accounting for pending buy orders
+    // Take pending sell orders for a ticker off the book, e.g. when the whole position is closed
+    // Returns the cancelled orders so they can be restored if the sale fails
+    pub fn cancel_sell_orders(&mut self, ticker: &String) -> Vec<OpenOrder> {
+        let (cancelled, kept) = std::mem::take(&mut self.open_orders)
+            .into_iter()
+            .partition(|o| o.get_side() == Side::Sell && o.get_symbol() == ticker);
+        self.open_orders = kept;
+        cancelled
+    }
+
+    // Put previously cancelled orders back on the book
+    pub fn restore_open_orders(&mut self, orders: Vec<OpenOrder>) {
+        self.open_orders.extend(orders);
+        open_order_sorting(&mut self.open_orders);
+    }
+
+    // Calculate available buying power after accounting for pending buy orders
+    // and the estimated fee each of them will pay
     pub fn get_available_cash(&self) -> Decimal {
-        let mut cash = self.cash_balance;
+        self.get_available_cash_in(fx::BASE_CURRENCY)
+    }
+
+    // Available buying power in one currency, counting only the pending buys settling there
+    pub fn get_available_cash_in(&self, currency: &str) -> Decimal {
+        let mut cash = if currency == fx::BASE_CURRENCY {
+            self.buying_power()
+        } else {
+            self.currency_balance(currency)
+        };
         for o in &self.open_orders {
-            if o.get_side() == Side::Buy {
-                cash -= o.get_price_per() * o.get_qty();
+            if o.get_side() == Side::Buy && self.settlement_currency(o.get_symbol()) == currency {
+                cash -= o.get_price_per() * o.get_qty() + self.estimated_fee(o);
             }
         }
         cash
@@ -220,6 +1975,11 @@ impl AppState {
         self.open_orders.clone()
     }
 
+    // Whether this exact order, down to its type and placement time, is still on the book
+    pub fn has_open_order(&self, order: &OpenOrder) -> bool {
+        self.open_orders.contains(order)
+    }
+
     pub fn set_open_orders(&mut self, new_open_orders: Vec<OpenOrder>) {
         self.open_orders = new_open_orders;
         open_order_sorting(&mut self.open_orders);
@@ -228,202 +1988,626 @@ impl AppState {
     // Add pending order to order book with validation
     // Returns Ok(message) on success, Err(message) on failure
     pub fn add_open_order(&mut self, new_order: OpenOrder) -> Result<String, String> {
-        if new_order.get_side() == Side::Sell {
+        self.check_open_order(&new_order)?;
+        let symbol = new_order.get_symbol().clone();
+        let order_type = format!("{:?}", new_order.get_order_type());
+        self.open_orders.push(new_order);
+        open_order_sorting(&mut self.open_orders);
+        Ok(format!("{} order added for {}", order_type, symbol))
+    }
+
+    // Whether the book can take this order on top of the orders already open
+    fn check_open_order(&self, order: &OpenOrder) -> Result<(), String> {
+        if order.get_side() == Side::Sell {
             // Check that you have enough to sell after accounting for existing sell orders
-            if self.get_available_holdings_qty(new_order.get_symbol()) - new_order.get_qty()
-                < Decimal::ZERO
-            {
-                return Err("You don't have enough of this to sell!".to_string());
+            if let Some(e) = self.check_sell_qty(order.get_symbol(), order.get_qty()) {
+                return Err(e);
             }
         } else {
-            // Check for funds after accounting for other buys
-            if self.get_available_cash() < new_order.get_qty() * new_order.get_price_per() {
+            // Check for funds, fee included, after accounting for other buys in the same currency
+            let currency = self.settlement_currency(order.get_symbol());
+            if self.get_available_cash_in(&currency)
+                < order.get_qty() * order.get_price_per() + self.estimated_fee(order)
+            {
                 return Err("You don't have enough of cash for this purchase!".to_string());
             }
         }
-        let symbol = new_order.get_symbol().clone();
-        let order_type = format!("{:?}", new_order.get_order_type());
-        self.open_orders.push(new_order);
+        Ok(())
+    }
+
+    // Change the price and/or quantity of the order at `index` in book order, in place
+    // The order keeps its placement time, and is checked against cash or holdings as if
+    // it were new but without counting its old self. Returns the order before and after
+    pub fn modify_open_order(
+        &mut self,
+        index: usize,
+        price: Option<Decimal>,
+        quantity: Option<Decimal>,
+    ) -> Result<(OpenOrder, OpenOrder), String> {
+        if index >= self.open_orders.len() {
+            return Err(format!(
+                "No open order #{}. There are {} open orders",
+                index + 1,
+                self.open_orders.len()
+            ));
+        }
+        let original = self.open_orders.remove(index);
+        let mut modified = original.clone();
+        if let Some(price) = price {
+            modified.set_price_per(price);
+        }
+        if let Some(quantity) = quantity {
+            modified.set_qty(quantity);
+        }
+        let checked = self.check_open_order(&modified);
+        let kept = if checked.is_ok() {
+            modified.clone()
+        } else {
+            original.clone()
+        };
+        self.open_orders.push(kept);
         open_order_sorting(&mut self.open_orders);
-        Ok(format!("{} order added for {}", order_type, symbol))
+        checked.map(|_| (original, modified))
     }
 
-    // Remove pending order from order book
+    // Remove pending order from order book; only an exact match, so an order changed
+    // by `modify` since it was read stays put
     pub fn remove_from_open_orders(&mut self, order_to_remove: OpenOrder) {
-        self.open_orders.retain(|order| {
-            !(order.get_symbol() == order_to_remove.get_symbol()
-                && order.get_price_per() == order_to_remove.get_price_per()
-                && order.get_qty() == order_to_remove.get_qty())
-        });
+        self.open_orders.retain(|order| *order != order_to_remove);
         open_order_sorting(&mut self.open_orders);
     }
 }
 
-// Sort orders by timestamp then by price within same symbol/side
-fn open_order_sorting(order_arr: &mut Vec<OpenOrder>) {
-    order_arr.sort_by_key(|o| o.get_timestamp());
+// Validate a polling interval in seconds
+fn check_interval(secs: u64) -> Result<u64, String> {
+    if (MIN_INTERVAL_SECS..=MAX_INTERVAL_SECS).contains(&secs) {
+        Ok(secs)
+    } else {
+        Err(format!(
+            "Interval must be between {} and {} seconds",
+            MIN_INTERVAL_SECS, MAX_INTERVAL_SECS
+        ))
+    }
+}
 
-    order_arr.sort_by(|a, b| {
-        if a.get_side() != b.get_side() || a.get_symbol() != b.get_symbol() {
-            return std::cmp::Ordering::Equal;
-        }
-        if a.get_side() == Side::Buy {
-            a.get_price_per().cmp(&b.get_price_per())
-        } else {
-            b.get_price_per().cmp(&a.get_price_per())
+// Lock shared state, recovering the guard if another thread panicked while holding it
+// The poison flag is cleared after recovery so the warning is raised once per panic
+pub fn lock_state(state: &Mutex<AppState>) -> MutexGuard<'_, AppState> {
+    match state.lock() {
+        Ok(guard) => guard,
+        Err(poisoned) => {
+            state.clear_poison();
+            let mut guard = poisoned.into_inner();
+            guard.push_event(
+                EventKind::Error,
+                "A background task panicked while updating state; recovered and continuing"
+                    .to_string(),
+            );
+            guard
         }
+    }
+}
+
+// Group orders by symbol and side, ordered by price within a group, oldest first on ties
+// Must stay a total order: sort_by panics on inconsistent comparators
+fn open_order_sorting(order_arr: &mut [OpenOrder]) {
+    let side_rank = |side: Side| match side {
+        Side::Buy => 0,
+        Side::Sell => 1,
+    };
+    order_arr.sort_by(|a, b| {
+        a.get_symbol()
+            .cmp(b.get_symbol())
+            .then_with(|| side_rank(a.get_side()).cmp(&side_rank(b.get_side())))
+            .then_with(|| {
+                if a.get_side() == Side::Buy {
+                    a.get_price_per().cmp(&b.get_price_per())
+                } else {
+                    b.get_price_per().cmp(&a.get_price_per())
+                }
+            })
+            .then_with(|| a.get_timestamp().cmp(&b.get_timestamp()))
     });
 }
 
 // Background task that monitors and executes pending orders when conditions are met
 pub fn monitor_order(state: Arc<Mutex<AppState>>, running: Arc<AtomicBool>) {
-    tokio::spawn(async move {
-        let mut interval = time::interval(Duration::from_secs(10));
+    let monitor_state = state.clone();
+    let task = tokio::spawn(async move {
+        let state = monitor_state;
+        let mut monitor_secs = lock_state(&state).get_monitor_secs();
+        let mut interval = time::interval(Duration::from_secs(monitor_secs));
 
         while running.load(Ordering::Relaxed) {
             interval.tick().await;
 
-            let open_orders = {
-                let state_guard = state.lock().unwrap();
-                state_guard.get_open_orders()
-            };
-
-            let mut priced_orders = Vec::new();
-            for order in open_orders {
-                let symbol = order.get_symbol().clone();
-                let current_price = crate::FinanceProvider::curr_price(&symbol, false).await;
-                priced_orders.push((order, current_price));
+            // Pick up interval changes made with `refresh monitor <seconds>`
+            let configured = lock_state(&state).get_monitor_secs();
+            if configured != monitor_secs {
+                monitor_secs = configured;
+                let period = Duration::from_secs(monitor_secs);
+                interval = time::interval_at(time::Instant::now() + period, period);
             }
 
-            let mut state_guard = state.lock().unwrap();
-            for (order, current_price) in priced_orders {
-                if execute_order_with_price(&mut state_guard, &order, current_price) {
-                    state_guard.remove_from_open_orders(order);
+            {
+                let mut state_guard = lock_state(&state);
+                let now = state_guard.now_timestamp();
+                let interest = state_guard.accrue_cash_yield(now);
+                if interest > Decimal::ZERO {
+                    state_guard.mark_dirty();
+                    let credited = state_guard.get_money_format().money(interest);
+                    state_guard.push_event(
+                        EventKind::Info,
+                        format!("Credited {} interest on cash", credited),
+                    );
+                }
+                let charged = state_guard.accrue_margin_interest(now);
+                if charged > Decimal::ZERO {
+                    state_guard.mark_dirty();
+                    let charged = state_guard.get_money_format().money(charged);
+                    state_guard.push_event(
+                        EventKind::Info,
+                        format!("Charged {} interest on the margin loan", charged),
+                    );
                 }
             }
+            // Offline there are no prices to check against; orders wait for `online`
+            if lock_state(&state).is_offline() {
+                continue;
+            }
+            process_open_orders(&state).await;
+            process_recurring_buys(&state).await;
+            check_maintenance(&state).await;
         }
         println!("Order shutting down");
     });
+    lock_state(&state).set_monitor_task(task);
+}
+
+// Price every open order and execute the ones whose trigger is met
+// Returns how many orders filled; orders without a usable price wait for the next pass
+// Each fill is posted to the webhook, if one is configured, after the lock is released
+pub async fn process_open_orders(state: &Arc<Mutex<AppState>>) -> usize {
+    let (open_orders, provider) = {
+        let state_guard = lock_state(state);
+        (state_guard.get_open_orders(), state_guard.provider())
+    };
+
+    let buying: Vec<Symbol> = open_orders
+        .iter()
+        .filter(|order| order.get_order_type() == OrderType::BuyLimit)
+        .map(|order| order.get_symbol().clone())
+        .collect();
+    let mut prices = if buying.is_empty() {
+        HashMap::new()
+    } else {
+        risk_prices(state, &buying).await
+    };
+    let mut priced_orders = Vec::new();
+    for order in open_orders {
+        let current_price = provider.curr_price(order.get_symbol()).await;
+        if current_price > Decimal::ZERO {
+            prices.insert(order.get_symbol().clone(), current_price);
+            priced_orders.push((order, current_price));
+        }
+    }
+
+    let mut fills = Vec::new();
+    let webhook_url = {
+        let mut state_guard = lock_state(state);
+        let now = state_guard.now_timestamp();
+        for (order, current_price) in priced_orders {
+            // Prices were fetched without the lock; an order cancelled, filled or modified
+            // meanwhile is stale, and its current version waits for the next pass
+            if !state_guard.has_open_order(&order) {
+                continue;
+            }
+            // A buy limit about to fill is checked against the risk limits at its fill
+            // price; one that would break them is cancelled, one that can't be checked waits
+            if let Some(fill_price) = buy_limit_fill_price(&order, current_price) {
+                let quantity = order.get_qty();
+                match state_guard.check_risk_limits(
+                    order.get_symbol(),
+                    quantity,
+                    fill_price,
+                    fill_price * quantity,
+                    &prices,
+                ) {
+                    Ok(()) => {}
+                    Err(RiskRejection::Unpriced { .. } | RiskRejection::NoRate { .. }) => continue,
+                    Err(rejection) => {
+                        let reason =
+                            rejection.reason(order.get_symbol(), &state_guard.get_money_format());
+                        state_guard.push_event(
+                            EventKind::Error,
+                            format!(
+                                "Cancelled BuyLimit for {} {}: {}",
+                                quantity,
+                                order.get_symbol(),
+                                reason
+                            ),
+                        );
+                        state_guard.remove_from_open_orders(order);
+                        state_guard.mark_dirty();
+                        continue;
+                    }
+                }
+            }
+            if let Some((fill_price, filled_qty)) =
+                execute_order_with_price(&mut state_guard, &order, current_price)
+            {
+                // A sell fills only what is still held, which can be less than ordered
+                let mut filled = order.clone();
+                filled.set_qty(filled_qty);
+                let message = fill_message(&state_guard.get_money_format(), &filled, fill_price);
+                state_guard.push_event(EventKind::Fill, message);
+                fills.push(OrderFill::new(&filled, fill_price, now));
+                state_guard.record_executed_order(ExecutedOrder::new(&filled, fill_price, now));
+                state_guard.remove_from_open_orders(order);
+                state_guard.mark_dirty();
+            }
+        }
+        state_guard.get_webhook_url()
+    };
+
+    if let Some(url) = webhook_url {
+        for fill in &fills {
+            webhook::notify(url.clone(), fill.clone(), state.clone());
+        }
+    }
+    fills.len()
+}
+
+// Place the market buy of every recurring schedule that has come due, then move it
+// to its next due time. Returns how many bought; a buy that can't go through (no
+// price, amount too small, not enough cash, over a risk limit) is skipped for that
+// period with an event
+pub async fn process_recurring_buys(state: &Arc<Mutex<AppState>>) -> usize {
+    let (due, provider) = {
+        let state_guard = lock_state(state);
+        let now = state_guard.now_timestamp();
+        let due: Vec<RecurringBuy> = state_guard
+            .get_recurring_buys()
+            .into_values()
+            .filter(|schedule| schedule.is_due(now))
+            .collect();
+        (due, state_guard.provider())
+    };
+    let mut bought = 0;
+    for schedule in due {
+        let symbol = schedule.symbol.clone();
+        let price = provider.curr_price(&symbol).await;
+        let sized = {
+            let state_guard = lock_state(state);
+            let money = state_guard.get_money_format();
+            let fill = apply_slippage(price, &Side::Buy, state_guard.get_slippage_bps());
+            let quantity = if fill > Decimal::ZERO {
+                state_guard
+                    .get_share_policy()
+                    .apply(quantity_for_notional(schedule.amount, fill))
+            } else {
+                Decimal::ZERO
+            };
+            if fill <= Decimal::ZERO {
+                Err(format!("could not get price for {}", symbol))
+            } else if quantity <= Decimal::ZERO {
+                Err(format!(
+                    "{} is too small to buy any at {}",
+                    money.money(schedule.amount),
+                    money.money_price(fill, AssetType::for_symbol(&symbol))
+                ))
+            } else {
+                Ok(quantity)
+            }
+        };
+        let result = match sized {
+            Ok(quantity) => create_buy_with_params(state, symbol.clone(), quantity, price)
+                .await
+                .map(|fill_price| (quantity, fill_price)),
+            Err(e) => Err(e),
+        };
+
+        let mut state_guard = lock_state(state);
+        match result {
+            Ok((quantity, fill_price)) => {
+                let money = state_guard.get_money_format();
+                let asset_type = AssetType::for_symbol(&symbol);
+                let message = format!(
+                    "Recurring buy: bought {} {} at {} ({} {})",
+                    format_qty(quantity, asset_type),
+                    symbol,
+                    money.money_price(fill_price, asset_type),
+                    money.money(schedule.amount),
+                    schedule.frequency.name()
+                );
+                state_guard.push_event(EventKind::Fill, message);
+                bought += 1;
+            }
+            Err(e) => state_guard.push_event(
+                EventKind::Error,
+                format!("Skipped recurring buy of {}: {}", symbol, e),
+            ),
+        }
+        let now = state_guard.now_timestamp();
+        state_guard.advance_recurring_buy(&symbol, now);
+        state_guard.mark_dirty();
+    }
+    bought
+}
+
+// Equity against the maintenance requirement with the holdings priced at market
+// Holdings quoted in another currency count at their base value. None while nothing
+// is borrowed, or when a holding has no price or exchange rate
+pub async fn margin_status_at_market(state: &Arc<Mutex<AppState>>) -> Option<MarginStatus> {
+    let (holdings, currencies, provider) = {
+        let state_guard = lock_state(state);
+        if state_guard.get_margin_loan() <= Decimal::ZERO {
+            return None;
+        }
+        (
+            state_guard.get_holdings_map(),
+            state_guard.valued_currencies(),
+            state_guard.provider(),
+        )
+    };
+    let rates = fx::rates_to_base(provider.as_ref(), &currencies).await;
+    let mut holdings_value = Decimal::ZERO;
+    for (symbol, holding) in &holdings {
+        let price = provider.curr_price(symbol).await;
+        // An unpriced or unconverted holding would understate equity and raise a false call
+        let rate = fx::rate_in(&rates, &fx::trading_currency(symbol))?;
+        if price <= Decimal::ZERO {
+            return None;
+        }
+        holdings_value += price * holding.get_qty() * rate;
+    }
+    lock_state(state).margin_status(holdings_value)
+}
+
+// Price the holdings while money is borrowed and flag the account once when equity
+// drops below the maintenance requirement; nothing is sold. Returns the status found
+pub async fn check_maintenance(state: &Arc<Mutex<AppState>>) -> Option<MarginStatus> {
+    let status = margin_status_at_market(state).await?;
+    let mut state_guard = lock_state(state);
+    let flagged = status.is_margin_call();
+    if flagged && !state_guard.margin_call {
+        state_guard.push_event(
+            EventKind::Error,
+            format!(
+                "Margin call: equity is {}% of holdings, below the {}% maintenance. \
+                 Deposit cash or sell to reduce the loan",
+                format_fixed(status.equity_pct, 1),
+                margin::MAINTENANCE_MARGIN_PCT
+            ),
+        );
+    }
+    state_guard.margin_call = flagged;
+    Some(status)
+}
+
+// Event text for an executed order, e.g. "StopLoss sold 5 AAPL at $179.50"
+fn fill_message(money: &MoneyFormat, order: &OpenOrder, fill_price: Decimal) -> String {
+    let action = match order.get_side() {
+        Side::Buy => "bought",
+        Side::Sell => "sold",
+    };
+    let asset_type = AssetType::for_symbol(order.get_symbol());
+    format!(
+        "{:?} {} {} {} at {}",
+        order.get_order_type(),
+        action,
+        format_qty(order.get_qty(), asset_type),
+        order.get_symbol(),
+        money.money_price(fill_price, asset_type)
+    )
+}
+
+// Current prices for `check_risk_limits` on buys of `buying`, fetched concurrently:
+// the holdings when a position limit is set, plus the FX quote, under its Yahoo
+// symbol, of every other currency the check converts. Empty when the limits are off
+pub async fn risk_prices(
+    state: &Arc<Mutex<AppState>>,
+    buying: &[Symbol],
+) -> HashMap<Symbol, Decimal> {
+    let (symbols, currencies, provider) = {
+        let state_guard = lock_state(state);
+        let limits = state_guard.get_risk_limits();
+        if limits.is_off() {
+            return HashMap::new();
+        }
+        let mut currencies: Vec<String> = buying
+            .iter()
+            .flat_map(|symbol| {
+                [
+                    state_guard.settlement_currency(symbol),
+                    fx::trading_currency(symbol),
+                ]
+            })
+            .collect();
+        let mut symbols = Vec::new();
+        if limits.max_position_pct.is_some() {
+            symbols = state_guard.get_holdings_map().into_keys().collect();
+            currencies.extend(state_guard.valued_currencies());
+        }
+        currencies.retain(|currency| currency != fx::BASE_CURRENCY);
+        currencies.sort();
+        currencies.dedup();
+        (symbols, currencies, state_guard.provider())
+    };
+    let mut prices: HashMap<Symbol, Decimal> = refresh::fetch_quotes(provider.clone(), symbols)
+        .await
+        .into_iter()
+        .map(|(symbol, price, _)| (symbol, price))
+        .collect();
+    for (currency, rate) in fx::rates_to_base(provider.as_ref(), &currencies).await {
+        prices.insert(fx::fx_symbol(&currency, fx::BASE_CURRENCY), rate);
+    }
+    prices
 }
 
+// Returns the fill price and the quantity filled when the order executed
 fn execute_order_with_price(
     state: &mut AppState,
     order: &OpenOrder,
     current_price: Decimal,
-) -> bool {
+) -> Option<(Decimal, Decimal)> {
     match order.get_order_type() {
         OrderType::BuyLimit => execute_buy_limit_with_price(state, order, current_price),
         OrderType::StopLoss => execute_stop_loss_with_price(state, order, current_price),
         OrderType::TakeProfit => execute_take_profit_with_price(state, order, current_price),
+        OrderType::SellLimit => execute_sell_limit_with_price(state, order, current_price),
+    }
+}
+
+// Price a buy limit fills at when the market is at `current_price`, None while it's above
+// the limit. A buy limit never fills worse than its limit, so the fill is the lower of the two
+fn buy_limit_fill_price(order: &OpenOrder, current_price: Decimal) -> Option<Decimal> {
+    if order.get_order_type() != OrderType::BuyLimit || current_price > order.get_price_per() {
+        return None;
     }
+    Some(current_price.min(order.get_price_per()))
 }
 
 fn execute_buy_limit_with_price(
     state: &mut AppState,
     order: &OpenOrder,
     current_price: Decimal,
-) -> bool {
+) -> Option<(Decimal, Decimal)> {
     let symbol = order.get_symbol().clone();
-    let limit_price = order.get_price_per();
     let purchase_qty = order.get_qty();
-    let total_purchase_value = current_price * purchase_qty;
+    let fill_price = buy_limit_fill_price(order, current_price)?;
+    let total_purchase_value = fill_price * purchase_qty;
 
-    if current_price > limit_price || total_purchase_value > state.check_balance() {
-        return false;
+    let fee = state.get_fee_model().fee_for(total_purchase_value);
+    if total_purchase_value + fee > state.buying_power_for(&symbol) {
+        return None;
     }
 
-    state.withdraw_purchase(total_purchase_value);
-    add_to_holdings(state, &symbol, purchase_qty, current_price);
-    state.add_trade(Trade::buy_with_type(
-        symbol,
-        purchase_qty,
-        current_price,
-        "BuyLimit".to_string(),
-    ));
-    true
+    let currency = state.pay_for(&symbol, total_purchase_value);
+    let fee = state.charge_fee(&currency, total_purchase_value);
+    add_to_holdings(state, &symbol, purchase_qty, fill_price);
+    state.add_trade(
+        Trade::buy_with_type(
+            symbol,
+            purchase_qty,
+            fill_price,
+            "BuyLimit".to_string(),
+            state.now_timestamp(),
+        )
+        .with_currency(Some(currency))
+        .with_fee(fee),
+    );
+    Some((fill_price, purchase_qty))
 }
 
 fn execute_stop_loss_with_price(
     state: &mut AppState,
     order: &OpenOrder,
     current_price: Decimal,
-) -> bool {
+) -> Option<(Decimal, Decimal)> {
     let symbol = order.get_symbol().clone();
     let stop_price = order.get_price_per();
     let sale_qty = order.get_qty();
 
     if current_price > stop_price {
-        return false;
-    }
-
-    state.deposit_sell(current_price * sale_qty);
-    remove_from_holdings(state, &symbol, sale_qty);
-    state.add_trade(Trade::sell_with_type(
-        symbol,
-        sale_qty,
-        current_price,
-        "StopLoss".to_string(),
-    ));
-    true
+        return None;
+    }
+
+    // Only what is still held is sold; with nothing left the order can't fill
+    let sold = remove_from_holdings(state, &symbol, sale_qty);
+    if sold.is_zero() {
+        return None;
+    }
+    let currency = state.receive_proceeds(&symbol, current_price * sold);
+    let fee = state.charge_fee(&currency, current_price * sold);
+    state.add_trade(
+        Trade::sell_with_type(
+            symbol,
+            sold,
+            current_price,
+            "StopLoss".to_string(),
+            state.now_timestamp(),
+        )
+        .with_currency(Some(currency))
+        .with_fee(fee),
+    );
+    Some((current_price, sold))
 }
 
 fn execute_take_profit_with_price(
     state: &mut AppState,
     order: &OpenOrder,
     current_price: Decimal,
-) -> bool {
+) -> Option<(Decimal, Decimal)> {
     let symbol = order.get_symbol().clone();
     let take_profit_price = order.get_price_per();
     let sale_qty = order.get_qty();
 
     if current_price < take_profit_price {
-        return false;
-    }
-
-    state.deposit_sell(take_profit_price * sale_qty);
-    remove_from_holdings(state, &symbol, sale_qty);
-    state.add_trade(Trade::sell_with_type(
-        symbol,
-        sale_qty,
-        take_profit_price,
-        "TakeProfit".to_string(),
-    ));
-    true
-}
+        return None;
+    }
 
-fn add_to_holdings(state: &mut AppState, ticker: &String, quantity: Decimal, price_per: Decimal) {
-    if let Some(existing_holding) = state.holdings.get(ticker) {
-        let prev_avg_cost = existing_holding.get_avg_price();
-        let prev_qty = existing_holding.get_qty();
-        let new_avg_cost =
-            (prev_qty * prev_avg_cost + quantity * price_per) / (prev_qty + quantity);
-        let new_qty = prev_qty + quantity;
-
-        state.holdings.insert(
-            ticker.clone(),
-            Holding::new(ticker.clone(), new_qty, new_avg_cost),
-        );
-    } else {
-        state.holdings.insert(
-            ticker.clone(),
-            Holding::new(ticker.clone(), quantity, price_per),
-        );
+    // Only what is still held is sold; with nothing left the order can't fill
+    let sold = remove_from_holdings(state, &symbol, sale_qty);
+    if sold.is_zero() {
+        return None;
     }
+    let currency = state.receive_proceeds(&symbol, take_profit_price * sold);
+    let fee = state.charge_fee(&currency, take_profit_price * sold);
+    state.add_trade(
+        Trade::sell_with_type(
+            symbol,
+            sold,
+            take_profit_price,
+            "TakeProfit".to_string(),
+            state.now_timestamp(),
+        )
+        .with_currency(Some(currency))
+        .with_fee(fee),
+    );
+    Some((take_profit_price, sold))
 }
 
-fn remove_from_holdings(state: &mut AppState, ticker: &String, quantity: Decimal) {
-    if let Some(existing_holding) = state.holdings.get(ticker) {
-        let prev_avg_cost = existing_holding.get_avg_price();
-        let prev_qty = existing_holding.get_qty();
-        let new_qty = prev_qty - quantity;
+fn execute_sell_limit_with_price(
+    state: &mut AppState,
+    order: &OpenOrder,
+    current_price: Decimal,
+) -> Option<(Decimal, Decimal)> {
+    let symbol = order.get_symbol().clone();
+    let limit_price = order.get_price_per();
+    let sale_qty = order.get_qty();
+    // A sell limit never fills worse than its limit, so the fill is the higher of the two
+    let fill_price = current_price.max(limit_price);
 
-        if new_qty == Decimal::ZERO {
-            state.holdings.remove(ticker);
-        } else {
-            state.holdings.insert(
-                ticker.clone(),
-                Holding::new(ticker.clone(), new_qty, prev_avg_cost),
-            );
-        }
+    if current_price < limit_price {
+        return None;
+    }
+
+    // Only what is still held is sold; with nothing left the order can't fill
+    let sold = remove_from_holdings(state, &symbol, sale_qty);
+    if sold.is_zero() {
+        return None;
     }
+    let currency = state.receive_proceeds(&symbol, fill_price * sold);
+    let fee = state.charge_fee(&currency, fill_price * sold);
+    state.add_trade(
+        Trade::sell_with_type(
+            symbol,
+            sold,
+            fill_price,
+            "SellLimit".to_string(),
+            state.now_timestamp(),
+        )
+        .with_currency(Some(currency))
+        .with_fee(fee),
+    );
+    Some((fill_price, sold))
+}
+
+fn add_to_holdings(state: &mut AppState, ticker: &String, quantity: Decimal, price_per: Decimal) {
+    crate::Finance::store_fill(&mut state.holdings, ticker, quantity, price_per);
+}
+
+fn remove_from_holdings(state: &mut AppState, ticker: &String, quantity: Decimal) -> Decimal {
+    crate::Finance::store_sale(&mut state.holdings, ticker, quantity)
 }
@@ -1,8 +1,12 @@
+use chrono::NaiveDate;
 use rust_decimal::prelude::*;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::sync::{Arc, Mutex};
 
-use crate::{AppState::AppState, FinanceProvider, UserInput};
+use crate::AppState::{AppState, lock_state, risk_prices};
+use crate::FinanceProvider::PriceProvider;
+use crate::Orders::Side;
+use crate::cashflow::CashFlowKind;
 
 // Add funds to user account
 pub async fn fund(state: &Arc<Mutex<AppState>>, amount: Decimal) {
@@ -12,13 +16,14 @@ pub async fn fund(state: &Arc<Mutex<AppState>>, amount: Decimal) {
     }
     // validate payment first
     // separate thread not needed since it in run on user input
-    let mut state_guard = state.lock().unwrap();
+    let mut state_guard = lock_state(state);
     state_guard.deposit(amount);
+    state_guard.record_cash_flow(CashFlowKind::Deposit, amount);
 }
 
 // Withdraw funds from user account if sufficient balance available
 pub async fn withdraw(state: &Arc<Mutex<AppState>>, amount: Decimal) {
-    let mut state_guard = state.lock().unwrap();
+    let mut state_guard = lock_state(state);
     if amount < Decimal::ZERO {
         println!("Invalid amount");
         return;
@@ -28,27 +33,132 @@ pub async fn withdraw(state: &Arc<Mutex<AppState>>, amount: Decimal) {
         return;
     }
     state_guard.withdraw(amount);
+    state_guard.record_cash_flow(CashFlowKind::Withdrawal, amount);
 }
 
 pub type Symbol = String;
 
+/// Leftover quantity small enough to count as a closed position (1e-8 shares)
+pub const QTY_DUST: Decimal = Decimal::from_parts(1, 0, 0, false, 8);
+
+/// Quote currencies Yahoo uses for crypto pairs such as BTC-USD
+const CRYPTO_QUOTE_CURRENCIES: [&str; 7] = ["USD", "USDT", "USDC", "EUR", "GBP", "JPY", "CAD"];
+
+/// Kind of asset, which decides the quote path and display precision
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AssetType {
+    Stock,
+    Crypto,
+}
+
+impl AssetType {
+    /// Infers the asset type from a Yahoo symbol: crypto pairs look like `BTC-USD`
+    /// Share classes such as `BRK-B` stay stocks because the suffix isn't a currency
+    pub fn for_symbol(symbol: &str) -> Self {
+        match symbol.rsplit_once('-') {
+            Some((base, quote))
+                if !base.is_empty()
+                    && CRYPTO_QUOTE_CURRENCIES.contains(&quote.to_uppercase().as_str()) =>
+            {
+                AssetType::Crypto
+            }
+            _ => AssetType::Stock,
+        }
+    }
+
+    /// Crypto trades around the clock; stocks follow exchange hours
+    pub fn trades_24_7(&self) -> bool {
+        matches!(self, AssetType::Crypto)
+    }
+}
+
+/// Yahoo symbol for a crypto asset, e.g. `BTC` -> `BTC-USD`
+/// Symbols that already name a pair are returned unchanged
+pub fn crypto_pair(asset: &str, currency: Option<&str>) -> Symbol {
+    let asset = asset.to_uppercase();
+    if AssetType::for_symbol(&asset) == AssetType::Crypto {
+        return asset;
+    }
+    let currency = currency
+        .map(|c| c.trim().to_uppercase())
+        .filter(|c| !c.is_empty())
+        .unwrap_or_else(|| "USD".to_string());
+    format!("{}-{}", asset, currency)
+}
+
+/// Index nicknames mapped to Yahoo's caret symbols
+const INDEX_ALIASES: [(&str, &str); 7] = [
+    ("SPX", "^GSPC"),
+    ("SP500", "^GSPC"),
+    ("DJIA", "^DJI"),
+    ("NDX", "^NDX"),
+    ("NASDAQ", "^IXIC"),
+    ("VIX", "^VIX"),
+    ("RUT", "^RUT"),
+];
+
+/// Coins typed without a quote currency that are priced against USD, e.g. `BTC` -> `BTC-USD`
+const BARE_CRYPTO: [&str; 8] = ["BTC", "ETH", "XRP", "DOGE", "ADA", "LTC", "BNB", "AVAX"];
+
+/// Share classes written with a dot or slash by brokers but a dash by Yahoo
+/// Other one-letter suffixes are exchanges (VOD.L, 7203.T) and are left alone
+const SHARE_CLASSES: [&str; 3] = ["A", "B", "C"];
+
+/// Canonical Yahoo symbol for what a user typed. User aliases win, then index
+/// nicknames (`spx` -> `^GSPC`), pairs written with a slash (`eth/usdt` ->
+/// `ETH-USDT`), share classes (`brk.b` -> `BRK-B`) and bare coins (`btc` ->
+/// `BTC-USD`). Anything else, including canonical symbols, is only uppercased
+pub fn normalize_symbol(raw: &str, aliases: &BTreeMap<Symbol, Symbol>) -> Symbol {
+    let symbol = raw.trim().to_uppercase();
+    if let Some(alias) = aliases.get(&symbol) {
+        return alias.clone();
+    }
+    if let Some((_, index)) = INDEX_ALIASES.iter().find(|(name, _)| *name == symbol) {
+        return index.to_string();
+    }
+    if let Some((base, quote)) = symbol.split_once('/')
+        && !base.is_empty()
+        && (CRYPTO_QUOTE_CURRENCIES.contains(&quote) || SHARE_CLASSES.contains(&quote))
+    {
+        return format!("{}-{}", base, quote);
+    }
+    if let Some((base, class)) = symbol.rsplit_once('.')
+        && !base.is_empty()
+        && base.chars().all(|c| c.is_ascii_alphabetic())
+        && SHARE_CLASSES.contains(&class)
+    {
+        return format!("{}-{}", base, class);
+    }
+    if BARE_CRYPTO.contains(&symbol.as_str()) {
+        return format!("{}-USD", symbol);
+    }
+    symbol
+}
+
 // Represents owned stock position with quantity and average purchase cost
 #[derive(Clone, Debug)]
 pub struct Holding {
     name: String,
     quantity: Decimal,
     avg_cost: Decimal,
+    asset_type: AssetType,
 }
 
 impl Holding {
     pub fn new(name: String, quantity: Decimal, avg_cost: Decimal) -> Self {
+        let asset_type = AssetType::for_symbol(&name);
         Self {
             name,
             quantity,
             avg_cost,
+            asset_type,
         }
     }
 
+    pub fn get_asset_type(&self) -> AssetType {
+        self.asset_type
+    }
+
     pub fn get_qty(&self) -> Decimal {
         self.quantity
     }
@@ -57,141 +167,612 @@ impl Holding {
         self.avg_cost
     }
 
-    pub async fn get_pnl(&self) -> Decimal {
-        let curr_price = FinanceProvider::curr_price(&self.name, false).await;
-        let delta = curr_price - self.get_avg_price();
-        delta * self.get_qty()
+    /// Return on cost at `price` as a percentage, (price - avg) / avg * 100
+    /// None when the average cost is zero or the price is unavailable
+    pub fn pnl_pct(&self, price: Decimal) -> Option<Decimal> {
+        if price <= Decimal::ZERO {
+            return None;
+        }
+        percent_of(price - self.avg_cost, self.avg_cost)
+    }
+
+    /// Unrealized profit or loss at `price` against the average cost
+    /// Every surface that shows P&L (summary, holdings table, detail pane) goes through here
+    pub fn pnl(&self, price: Decimal) -> Decimal {
+        (price - self.avg_cost) * self.quantity
+    }
+
+    /// Unrealized P&L at the provider's current price, never the previous close
+    pub async fn get_pnl(&self, provider: &dyn PriceProvider) -> Decimal {
+        self.pnl(provider.curr_price(&self.name).await)
     }
 }
 
-// Asks user for input and calls Trade::buy
-pub async fn create_buy(state: &Arc<Mutex<AppState>>) {
-    let symbol = match UserInput::ask_ticker() {
-        Some(t) => t,
-        None => return,
-    };
-    let purchase_qty = match UserInput::ask_quantity() {
-        Some(q) => q,
-        None => return,
-    };
-    let curr_price = FinanceProvider::curr_price(&symbol, false).await;
-    let total_price = curr_price * purchase_qty;
+// SECTION: Trading Functions
 
-    let mut state_guard = state.lock().unwrap();
-    println!("The total price is: {total_price}");
-    if state_guard.check_balance() < total_price {
-        println!("Insufficient balance");
-    } else {
-        state_guard.withdraw_purchase(total_price);
-        add_to_holdings(&symbol, purchase_qty, curr_price, &mut state_guard).await;
-        state_guard.add_trade(crate::Orders::Trade::buy(symbol, purchase_qty, curr_price));
+/// Decimal places kept when a dollar amount is converted to shares
+const NOTIONAL_QTY_DP: u32 = 8;
+
+/// Adjust a quoted price for slippage: buys fill above the quote, sells below
+pub fn apply_slippage(price: Decimal, side: &Side, slippage_bps: Decimal) -> Decimal {
+    let factor = slippage_bps / Decimal::from(10_000);
+    match side {
+        Side::Buy => price * (Decimal::ONE + factor),
+        Side::Sell => price * (Decimal::ONE - factor),
     }
 }
 
-// Asks user for input and calls Trade::sell
-pub async fn create_sell(state: &Arc<Mutex<AppState>>) {
-    let ticker = match UserInput::ask_ticker() {
-        Some(t) => t,
-        None => return,
-    };
-    let quantity = match UserInput::ask_quantity() {
-        Some(q) => q,
-        None => return,
-    };
-    let curr_price = FinanceProvider::curr_price(&ticker, false).await;
-    let total_price = curr_price * quantity;
-    println!("The total price of sale is: {total_price}");
-
-    let mut state_guard = state.lock().unwrap();
-    // check holdings
-    if state_guard.get_ticker_holdings_qty(&ticker) < quantity {
-        println!("You dont have enough of that ticker");
-    } else {
-        // add funds
-        state_guard.deposit_sell(total_price);
-        remove_from_holdings(&ticker, quantity, &mut state_guard).await;
-        state_guard.add_trade(crate::Orders::Trade::sell(ticker, quantity, curr_price));
+/// Commission charged per order: a flat amount plus basis points of the notional
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FeeModel {
+    pub flat: Decimal,
+    pub bps: Decimal,
+}
+
+impl FeeModel {
+    pub fn is_free(&self) -> bool {
+        self.flat.is_zero() && self.bps.is_zero()
+    }
+
+    /// Fee for an order of this notional, rounded to the cent
+    pub fn fee_for(&self, notional: Decimal) -> Decimal {
+        if self.is_free() {
+            return Decimal::ZERO;
+        }
+        crate::format::round_money(self.flat + notional.abs() * self.bps / Decimal::from(10_000))
     }
 }
 
-// SECTION: Non-interactive Trading Functions
+/// Shares bought or sold for a dollar amount at the given fill price
+/// Truncated rather than rounded so the order never costs more than the amount
+pub fn quantity_for_notional(amount: Decimal, fill_price: Decimal) -> Decimal {
+    if fill_price <= Decimal::ZERO {
+        return Decimal::ZERO;
+    }
+    (amount / fill_price).round_dp_with_strategy(NOTIONAL_QTY_DP, RoundingStrategy::ToZero)
+}
+
+/// Most decimals `shares precision-N` accepts, the precision dollar buys already keep
+pub const MAX_SHARE_DP: u32 = NOTIONAL_QTY_DP;
+
+/// How finely bought share quantities are kept
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SharePolicy {
+    /// Whole shares only; the remainder of a dollar buy stays as cash
+    Whole,
+    /// Full precision
+    #[default]
+    Fractional,
+    /// Rounded down to this many decimal places
+    Precision(u32),
+}
+
+impl SharePolicy {
+    /// Parses `whole`, `fractional`, `precision-N` or a bare `N`, case-insensitively
+    pub fn parse(value: &str) -> Option<Self> {
+        let value = value.trim().to_lowercase();
+        match value.as_str() {
+            "whole" => Some(Self::Whole),
+            "fractional" => Some(Self::Fractional),
+            _ => {
+                let digits = value.strip_prefix("precision-").unwrap_or(&value);
+                match digits.parse::<u32>() {
+                    Ok(0) => Some(Self::Whole),
+                    Ok(dp) if dp <= MAX_SHARE_DP => Some(Self::Precision(dp)),
+                    _ => None,
+                }
+            }
+        }
+    }
+
+    /// Name as persisted and accepted by `shares`
+    pub fn name(&self) -> String {
+        match self {
+            Self::Whole => "whole".to_string(),
+            Self::Fractional => "fractional".to_string(),
+            Self::Precision(dp) => format!("precision-{}", dp),
+        }
+    }
+
+    /// Rounds a share quantity down so an order never costs more than planned
+    pub fn apply(&self, quantity: Decimal) -> Decimal {
+        match self {
+            Self::Whole => quantity.round_dp_with_strategy(0, RoundingStrategy::ToZero),
+            Self::Fractional => quantity,
+            Self::Precision(dp) => quantity.round_dp_with_strategy(*dp, RoundingStrategy::ToZero),
+        }
+    }
+}
 
 /// Execute buy with specified parameters (no prompts)
+/// Returns the fill price after the configured slippage
+/// Balance is checked and updated under one lock so concurrent orders can't overspend
 pub async fn create_buy_with_params(
     state: &Arc<Mutex<AppState>>,
     symbol: String,
     quantity: Decimal,
     price: Decimal,
-) {
-    let total_price = price * quantity;
+) -> Result<Decimal, String> {
+    create_tagged_buy(state, symbol, quantity, price, None).await
+}
+
+/// Same as `create_buy_with_params`, recording `tag` on the trade
+/// A buy that would break a `risklimit` at its fill price is rejected
+pub async fn create_tagged_buy(
+    state: &Arc<Mutex<AppState>>,
+    symbol: String,
+    quantity: Decimal,
+    price: Decimal,
+    tag: Option<String>,
+) -> Result<Decimal, String> {
+    let prices = risk_prices(state, std::slice::from_ref(&symbol)).await;
+    let mut state_guard = lock_state(state);
+    let fill_price = apply_slippage(price, &Side::Buy, state_guard.get_slippage_bps());
+    let total_price = fill_price * quantity;
+    let fee = state_guard.get_fee_model().fee_for(total_price);
+    let balance = state_guard.buying_power_for(&symbol);
+    if total_price + fee > balance {
+        let money = state_guard.get_money_format();
+        let currency = state_guard.settlement_currency(&symbol);
+        return Err(format!(
+            "Insufficient funds. Need {}, have {}",
+            crate::fx::money_in(&money, &currency, total_price + fee),
+            crate::fx::money_in(&money, &currency, balance)
+        ));
+    }
+    if let Err(rejection) =
+        state_guard.check_risk_limits(&symbol, quantity, fill_price, total_price, &prices)
+    {
+        return Err(rejection.describe(&symbol, &state_guard.get_money_format()));
+    }
 
-    let mut state_guard = state.lock().unwrap();
-    state_guard.withdraw_purchase(total_price);
-    add_to_holdings(&symbol, quantity, price, &mut state_guard).await;
-    state_guard.add_trade(crate::Orders::Trade::buy(symbol, quantity, price));
+    let currency = state_guard.pay_for(&symbol, total_price);
+    let fee = state_guard.charge_fee(&currency, total_price);
+    add_to_holdings(&symbol, quantity, fill_price, &mut state_guard);
+    let now = state_guard.now_timestamp();
+    state_guard.add_trade(
+        crate::Orders::Trade::buy_at(symbol, quantity, fill_price, now)
+            .with_tag(tag)
+            .with_currency(Some(currency))
+            .with_fee(fee),
+    );
+    Ok(fill_price)
 }
 
 /// Execute sell with specified parameters (no prompts)
+/// Returns the fill price after the configured slippage
+/// Holdings are checked and updated under one lock so concurrent orders can't oversell
 pub async fn create_sell_with_params(
     state: &Arc<Mutex<AppState>>,
     symbol: String,
     quantity: Decimal,
     price: Decimal,
+) -> Result<Decimal, String> {
+    create_tagged_sell(state, symbol, quantity, price, None).await
+}
+
+/// Same as `create_sell_with_params`, recording `tag` on the trade
+pub async fn create_tagged_sell(
+    state: &Arc<Mutex<AppState>>,
+    symbol: String,
+    quantity: Decimal,
+    price: Decimal,
+    tag: Option<String>,
+) -> Result<Decimal, String> {
+    let mut state_guard = lock_state(state);
+    let available_qty = state_guard.get_ticker_holdings_qty(&symbol);
+    if quantity > available_qty {
+        return Err(format!(
+            "Insufficient holdings. Have {} shares of {}",
+            crate::format::format_qty(available_qty, AssetType::for_symbol(&symbol)),
+            symbol
+        ));
+    }
+    let fill_price = apply_slippage(price, &Side::Sell, state_guard.get_slippage_bps());
+    let sold = remove_from_holdings(&symbol, quantity, &mut state_guard);
+
+    let currency = state_guard.receive_proceeds(&symbol, fill_price * sold);
+    let fee = state_guard.charge_fee(&currency, fill_price * sold);
+    let now = state_guard.now_timestamp();
+    state_guard.add_trade(
+        crate::Orders::Trade::sell_at(symbol, sold, fill_price, now)
+            .with_tag(tag)
+            .with_currency(Some(currency))
+            .with_fee(fee),
+    );
+    Ok(fill_price)
+}
+
+/// Position left after a fill of `quantity` shares at `price`; a positive quantity
+/// buys and a negative one sells. A negative position is a short whose average cost
+/// is the average price the shares were sold at, so `Holding::pnl` holds either way.
+/// Adding to a long or a short averages the cost in, trimming one keeps its cost, and
+/// a fill that crosses zero closes the old side and opens the rest at `price`.
+/// None when the position ends flat, within QTY_DUST
+pub fn apply_fill(
+    position: Option<&Holding>,
+    symbol: &Symbol,
+    quantity: Decimal,
+    price: Decimal,
+) -> Option<Holding> {
+    let (prev_qty, prev_avg_cost) = position
+        .map(|holding| (holding.get_qty(), holding.get_avg_price()))
+        .unwrap_or_default();
+    let new_qty = prev_qty + quantity;
+    if new_qty.abs() <= QTY_DUST {
+        return None;
+    }
+
+    let is_long = |qty: Decimal| qty > Decimal::ZERO;
+    let avg_cost = if prev_qty.is_zero() || is_long(prev_qty) == is_long(quantity) {
+        // Opening or adding to the same side: cost weighted by share count
+        (prev_qty.abs() * prev_avg_cost + quantity.abs() * price) / new_qty.abs()
+    } else if is_long(prev_qty) == is_long(new_qty) {
+        // Trimming: the shares left were bought (or sold short) at the old average
+        prev_avg_cost
+    } else {
+        // Crossed zero: what's left was opened by this fill
+        price
+    };
+    Some(Holding::new(symbol.clone(), new_qty, avg_cost))
+}
+
+// Puts the result of `apply_fill` into the holdings map, dropping a flat position
+pub(crate) fn store_fill(
+    holdings: &mut HashMap<Symbol, Holding>,
+    symbol: &Symbol,
+    quantity: Decimal,
+    price: Decimal,
 ) {
-    let total_price = price * quantity;
+    match apply_fill(holdings.get(symbol), symbol, quantity, price) {
+        Some(holding) => holdings.insert(symbol.clone(), holding),
+        None => holdings.remove(symbol),
+    };
+}
 
-    let mut state_guard = state.lock().unwrap();
-    state_guard.deposit_sell(total_price);
-    remove_from_holdings(&symbol, quantity, &mut state_guard).await;
-    state_guard.add_trade(crate::Orders::Trade::sell(symbol, quantity, price));
+// Sells up to the long quantity held, keeping its average cost; never goes short
+// Returns the quantity actually sold, which is zero when nothing is held
+pub(crate) fn store_sale(
+    holdings: &mut HashMap<Symbol, Holding>,
+    symbol: &Symbol,
+    quantity: Decimal,
+) -> Decimal {
+    let Some(holding) = holdings.get(symbol) else {
+        return Decimal::ZERO;
+    };
+    let sold = quantity.min(holding.get_qty().max(Decimal::ZERO));
+    let avg_cost = holding.get_avg_price();
+    store_fill(holdings, symbol, -sold, avg_cost);
+    sold
 }
 
 // Update or create holding with new purchase, calculating average cost
-pub(crate) async fn add_to_holdings(
+pub(crate) fn add_to_holdings(
     ticker: &String,
     quantity: Decimal,
     price_per: Decimal,
     state: &mut AppState,
 ) {
-    let mut prev_holdings_map: HashMap<Symbol, Holding> = state.get_holdings_map();
-
-    // Use HashMap's get method to check if holding exists
-    if let Some(existing_holding) = prev_holdings_map.get(ticker) {
-        let prev_avg_cost = existing_holding.get_avg_price();
-        let prev_qty = existing_holding.quantity;
-        let new_avg_cost =
-            (prev_qty * prev_avg_cost + quantity * price_per) / (prev_qty + quantity);
-        let new_qty = prev_qty + quantity;
-
-        prev_holdings_map.insert(
-            ticker.clone(),
-            Holding::new(ticker.clone(), new_qty, new_avg_cost),
-        );
-    } else {
-        // Insert new holding
-        prev_holdings_map.insert(
-            ticker.clone(),
-            Holding::new(ticker.clone(), quantity, price_per),
-        );
-    }
-    state.set_holdings_map(prev_holdings_map).await;
+    let mut holdings: HashMap<Symbol, Holding> = state.get_holdings_map();
+    store_fill(&mut holdings, ticker, quantity, price_per);
+    state.set_holdings_map(holdings);
 }
 
 // Reduce or remove holding after sale, keeping average cost unchanged
-pub(crate) async fn remove_from_holdings(ticker: &String, quantity: Decimal, state: &mut AppState) {
-    let mut prev_holdings_map: HashMap<Symbol, Holding> = state.get_holdings_map();
-    if let Some(existing_holding) = prev_holdings_map.get(ticker) {
-        let prev_avg_cost = existing_holding.get_avg_price();
-        let prev_qty = existing_holding.quantity;
-        let new_qty = prev_qty - quantity;
-        if new_qty == Decimal::ZERO {
-            prev_holdings_map.remove(ticker);
-        } else {
-            prev_holdings_map.insert(
-                ticker.clone(),
-                Holding::new(ticker.clone(), new_qty, prev_avg_cost),
-            );
-            state.set_holdings_map(prev_holdings_map).await;
+// A remainder at or below QTY_DUST is treated as fully sold
+// Returns the quantity actually sold, capped at what was held
+pub(crate) fn remove_from_holdings(
+    ticker: &String,
+    quantity: Decimal,
+    state: &mut AppState,
+) -> Decimal {
+    let mut holdings: HashMap<Symbol, Holding> = state.get_holdings_map();
+    let sold = store_sale(&mut holdings, ticker, quantity);
+    if !sold.is_zero() {
+        state.set_holdings_map(holdings);
+    }
+    sold
+}
+
+// SECTION: Holdings Export
+
+/// Header line of the `export-holdings` CSV
+pub const HOLDINGS_CSV_HEADER: &str =
+    "symbol,quantity,avg_cost,price,market_value,unrealized_pnl,flag";
+
+/// Flag on a holdings CSV row whose current price couldn't be fetched
+pub const NO_PRICE_FLAG: &str = "no_price";
+
+/// Point-in-time holdings CSV, one row per symbol in order
+/// A holding missing from `prices` gets blank price, value and P&L and the no-price flag
+pub fn holdings_csv(
+    holdings: &HashMap<Symbol, Holding>,
+    prices: &HashMap<Symbol, Decimal>,
+) -> String {
+    use crate::format::{csv_decimal, csv_field, csv_money};
+
+    let mut symbols: Vec<&Symbol> = holdings.keys().collect();
+    symbols.sort();
+    let mut csv = format!("{}\n", HOLDINGS_CSV_HEADER);
+    for symbol in symbols {
+        let holding = &holdings[symbol];
+        let (price, value, pnl, flag) = match prices.get(symbol) {
+            Some(price) => (
+                csv_decimal(*price),
+                csv_money(holding.get_qty() * price),
+                csv_money(holding.pnl(*price)),
+                "",
+            ),
+            None => (String::new(), String::new(), String::new(), NO_PRICE_FLAG),
+        };
+        csv.push_str(&format!(
+            "{},{},{},{},{},{},{}\n",
+            csv_field(symbol),
+            csv_decimal(holding.get_qty()),
+            csv_decimal(holding.get_avg_price()),
+            price,
+            value,
+            pnl,
+            flag
+        ));
+    }
+    csv
+}
+
+// SECTION: Corporate Actions
+
+/// Order type of the cash interest credits, which are not security trades
+pub const INTEREST_ORDER_TYPE: &str = "Interest";
+
+/// Order type of trades brought in by `import`, which add the shares without
+/// moving cash
+pub const IMPORT_ORDER_TYPE: &str = "Import";
+
+/// Order type of the trade history entry recorded for a split
+/// Its quantity is the share multiplier and its price is zero
+pub const SPLIT_ORDER_TYPE: &str = "Split";
+
+/// Stock split given as new shares for old shares, e.g. 4:1 forward or 1:10 reverse
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SplitRatio {
+    new: Decimal,
+    old: Decimal,
+}
+
+impl SplitRatio {
+    /// Parses `new:old` with both sides positive and different
+    pub fn parse(value: &str) -> Option<Self> {
+        let (new, old) = value.trim().split_once(':')?;
+        let new = Decimal::from_str(new.trim()).ok()?;
+        let old = Decimal::from_str(old.trim()).ok()?;
+        if new <= Decimal::ZERO || old <= Decimal::ZERO || new == old {
+            return None;
         }
+        Some(Self { new, old })
+    }
+
+    /// Shares held after the split for each share held before
+    pub fn multiplier(&self) -> Decimal {
+        self.new / self.old
     }
 }
+
+impl std::fmt::Display for SplitRatio {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.new.normalize(), self.old.normalize())
+    }
+}
+
+/// Applies a split to a held symbol and returns the adjusted holding
+/// Quantity is multiplied and average cost divided so the total cost is unchanged.
+/// Open orders for the symbol are adjusted the same way, and the split is recorded
+/// in the trade history so cost basis lots can be adjusted on replay
+pub fn apply_split(
+    symbol: &Symbol,
+    ratio: SplitRatio,
+    state: &mut AppState,
+) -> Result<Holding, String> {
+    let mut holdings = state.get_holdings_map();
+    let Some(holding) = holdings.get(symbol) else {
+        return Err(format!("You don't hold {}", symbol));
+    };
+    let multiplier = ratio.multiplier();
+    let quantity = holding.get_qty() * multiplier;
+    let adjusted = Holding::new(
+        symbol.clone(),
+        quantity,
+        holding.get_qty() * holding.get_avg_price() / quantity,
+    );
+    holdings.insert(symbol.clone(), adjusted.clone());
+    state.set_holdings_map(holdings);
+
+    let orders = state
+        .get_open_orders()
+        .into_iter()
+        .map(|order| {
+            if order.get_symbol() != symbol {
+                return order;
+            }
+            crate::Orders::OpenOrder::new_at(
+                symbol.clone(),
+                order.get_qty() * multiplier,
+                order.get_price_per() / multiplier,
+                order.get_order_type(),
+                order.get_side(),
+                order.get_timestamp(),
+            )
+        })
+        .collect();
+    state.set_open_orders(orders);
+
+    let now = state.now_timestamp();
+    state.add_trade(crate::Orders::Trade::from_database(
+        symbol.clone(),
+        multiplier,
+        Decimal::ZERO,
+        Side::Buy,
+        now,
+        SPLIT_ORDER_TYPE.to_string(),
+    ));
+    Ok(adjusted)
+}
+
+// SECTION: Performance Comparison
+
+/// Characters used for the compare overlay, lowest to highest
+const SPARK_LEVELS: &[u8] = b"_.-~^";
+
+/// Align several close series on the dates they all share and rebase each to 100
+/// Series without any usable closes are left out so they don't empty the window
+pub fn normalize_on_common_dates(
+    series: &[(Symbol, Vec<(NaiveDate, Decimal)>)],
+) -> Vec<(Symbol, Vec<Decimal>)> {
+    let usable: Vec<&(Symbol, Vec<(NaiveDate, Decimal)>)> = series
+        .iter()
+        .filter(|(_, closes)| closes.iter().any(|(_, close)| *close > Decimal::ZERO))
+        .collect();
+
+    let mut common: Option<BTreeSet<NaiveDate>> = None;
+    for (_, closes) in &usable {
+        let dates: BTreeSet<NaiveDate> = closes
+            .iter()
+            .filter(|(_, close)| *close > Decimal::ZERO)
+            .map(|(date, _)| *date)
+            .collect();
+        common = Some(match common {
+            Some(prev) => prev.intersection(&dates).copied().collect(),
+            None => dates,
+        });
+    }
+    let common = common.unwrap_or_default();
+    if common.is_empty() {
+        return Vec::new();
+    }
+
+    usable
+        .into_iter()
+        .map(|(symbol, closes)| {
+            let by_date: HashMap<NaiveDate, Decimal> = closes.iter().copied().collect();
+            let aligned: Vec<Decimal> = common.iter().map(|date| by_date[date]).collect();
+            let base = aligned[0];
+            let rebased = aligned
+                .iter()
+                .map(|close| close / base * Decimal::from(100))
+                .collect();
+            (symbol.clone(), rebased)
+        })
+        .collect()
+}
+
+/// Percentage return of each rebased series, best performer first
+pub fn rank_by_return(normalized: &[(Symbol, Vec<Decimal>)]) -> Vec<(Symbol, Decimal)> {
+    let mut ranked: Vec<(Symbol, Decimal)> = normalized
+        .iter()
+        .map(|(symbol, values)| {
+            let last = values.last().copied().unwrap_or(Decimal::from(100));
+            (symbol.clone(), last - Decimal::from(100))
+        })
+        .collect();
+    ranked.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    ranked
+}
+
+/// `part` as a percent of `whole`, the one place percentages are divided out
+/// None when `whole` is zero or negative, e.g. an empty portfolio, so callers
+/// report that case instead of dividing by zero
+pub fn percent_of(part: Decimal, whole: Decimal) -> Option<Decimal> {
+    if whole <= Decimal::ZERO {
+        return None;
+    }
+    Some(part / whole * Decimal::ONE_HUNDRED)
+}
+
+/// Percent change from the previous close, None when either price is missing
+pub fn day_change_pct(price: Decimal, previous_close: Decimal) -> Option<Decimal> {
+    if price <= Decimal::ZERO {
+        return None;
+    }
+    percent_of(price - previous_close, previous_close)
+}
+
+/// Symbols paired with their percent change
+pub type Movers = Vec<(Symbol, Decimal)>;
+
+/// Up to `limit` gainers (biggest rise first) and `limit` losers (biggest drop first)
+/// Unchanged symbols count as gainers so a flat day still lists them
+pub fn top_movers(changes: &[(Symbol, Decimal)], limit: usize) -> (Movers, Movers) {
+    let mut ranked = changes.to_vec();
+    ranked.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    let (mut gainers, mut losers): (Vec<_>, Vec<_>) = ranked
+        .into_iter()
+        .partition(|(_, pct)| *pct >= Decimal::ZERO);
+    gainers.truncate(limit);
+    losers.reverse();
+    losers.truncate(limit);
+    (gainers, losers)
+}
+
+/// One holding's change in value since the previous close
+#[derive(Clone, Debug, PartialEq)]
+pub struct DayMove {
+    pub symbol: Symbol,
+    pub pnl: Decimal,
+    pub pct: Decimal,
+}
+
+/// Day P&L of each holding that has both prices, biggest gain first
+/// Also returns the value of those holdings at the previous close
+pub fn holding_day_moves(
+    holdings: &HashMap<Symbol, Holding>,
+    quotes: &HashMap<Symbol, (Decimal, Decimal)>,
+) -> (Vec<DayMove>, Decimal) {
+    let mut moves = Vec::new();
+    let mut previous_value = Decimal::ZERO;
+    for (symbol, holding) in holdings {
+        let Some((price, previous_close)) = quotes.get(symbol).copied() else {
+            continue;
+        };
+        let Some(pct) = day_change_pct(price, previous_close) else {
+            continue;
+        };
+        previous_value += holding.get_qty() * previous_close;
+        moves.push(DayMove {
+            symbol: symbol.clone(),
+            pnl: holding.get_qty() * (price - previous_close),
+            pct,
+        });
+    }
+    moves.sort_by(|a, b| b.pnl.cmp(&a.pnl).then_with(|| a.symbol.cmp(&b.symbol)));
+    (moves, previous_value)
+}
+
+/// Small ASCII line of a series, sampled down to at most `width` columns
+/// `low` and `high` are shared across symbols so the lines overlay on one scale
+pub fn ascii_sparkline(values: &[Decimal], low: Decimal, high: Decimal, width: usize) -> String {
+    if values.is_empty() || width == 0 {
+        return String::new();
+    }
+
+    let columns = values.len().min(width);
+    let top = SPARK_LEVELS.len() - 1;
+    let span = high - low;
+    (0..columns)
+        .map(|col| {
+            let idx = if columns == 1 {
+                0
+            } else {
+                col * (values.len() - 1) / (columns - 1)
+            };
+            let level = if span <= Decimal::ZERO {
+                top / 2
+            } else {
+                ((values[idx] - low) / span * Decimal::from(top))
+                    .round()
+                    .to_usize()
+                    .unwrap_or(0)
+                    .min(top)
+            };
+            SPARK_LEVELS[level] as char
+        })
+        .collect()
+}
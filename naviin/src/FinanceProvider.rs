@@ -1,20 +1,939 @@
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use chrono::{Duration, NaiveDate};
 use rust_decimal::prelude::*;
-use yfinance_rs::{Ticker, YfClient};
+use tokio::sync::{Notify, OwnedSemaphorePermit, Semaphore};
+use tokio::time::Instant;
+use yfinance_rs::{Interval, Ticker, YfClient};
+
+use crate::Finance::AssetType;
+use crate::clock::{Clock, SystemClock};
+
+// SECTION: Provider Abstraction
+
+/// Boxed future returned by provider lookups, so the trait stays object safe
+pub type PriceFuture<'a> = Pin<Box<dyn Future<Output = Decimal> + Send + 'a>>;
+
+/// Boxed future returned by symbol searches; the error is a user-facing message
+pub type SearchFuture<'a> =
+    Pin<Box<dyn Future<Output = Result<Vec<SymbolMatch>, String>> + Send + 'a>>;
+
+/// Boxed future returned by daily bar lookups; None when the day has no data
+pub type BarFuture<'a> = Pin<Box<dyn Future<Output = Option<DailyBar>> + Send + 'a>>;
+
+/// Boxed future returned by history lookups; empty when there's no history
+pub type ClosesFuture<'a> = Pin<Box<dyn Future<Output = Vec<(NaiveDate, Decimal)>> + Send + 'a>>;
+
+/// One trading day's open, high, low and close
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DailyBar {
+    pub open: Decimal,
+    pub high: Decimal,
+    pub low: Decimal,
+    pub close: Decimal,
+}
+
+/// One symbol found by a search
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SymbolMatch {
+    pub symbol: String,
+    pub name: Option<String>,
+    pub exchange: Option<String>,
+    /// Instrument type, e.g. Equity, Fund, Crypto
+    pub kind: String,
+}
+
+impl SymbolMatch {
+    pub fn new(symbol: &str, name: &str, exchange: &str, kind: &str) -> Self {
+        Self {
+            symbol: symbol.to_string(),
+            name: Some(name.to_string()).filter(|name| !name.is_empty()),
+            exchange: Some(exchange.to_string()).filter(|exchange| !exchange.is_empty()),
+            kind: kind.to_string(),
+        }
+    }
+}
+
+/// Source of market prices. Lookups return `Decimal::ZERO` when a price is unavailable.
+pub trait PriceProvider: Send + Sync {
+    /// Short name shown in diagnostics
+    fn name(&self) -> &str;
+    /// Latest traded price; holdings value and P&L are measured against this
+    fn curr_price<'a>(&'a self, symbol: &'a str) -> PriceFuture<'a>;
+    /// Previous session's closing price; only the day change is measured against this
+    fn previous_close<'a>(&'a self, symbol: &'a str) -> PriceFuture<'a>;
+    /// Symbols whose ticker or name matches a free-text query, best match first
+    fn search<'a>(&'a self, query: &'a str) -> SearchFuture<'a>;
+    /// Open, high, low and close on `date`, None for weekends, holidays and missing history
+    fn daily_bar<'a>(&'a self, symbol: &'a str, date: NaiveDate) -> BarFuture<'a>;
+    /// Closes of the trading days from `from` through `to`, oldest first
+    fn daily_closes<'a>(
+        &'a self,
+        symbol: &'a str,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> ClosesFuture<'a>;
+}
+
+impl fmt::Debug for dyn PriceProvider {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "PriceProvider({})", self.name())
+    }
+}
+
+/// Live prices from Yahoo Finance; `clock` decides which daily candle is complete
+#[derive(Debug)]
+pub struct YahooProvider {
+    clock: Arc<dyn Clock>,
+}
+
+impl YahooProvider {
+    pub fn new(clock: Arc<dyn Clock>) -> Self {
+        Self { clock }
+    }
+}
+
+impl Default for YahooProvider {
+    fn default() -> Self {
+        Self::new(Arc::new(SystemClock))
+    }
+}
+
+impl PriceProvider for YahooProvider {
+    fn name(&self) -> &str {
+        "yahoo"
+    }
+
+    fn curr_price<'a>(&'a self, symbol: &'a str) -> PriceFuture<'a> {
+        Box::pin(async move { yahoo_last_price(&symbol.to_string(), false).await })
+    }
+
+    fn previous_close<'a>(&'a self, symbol: &'a str) -> PriceFuture<'a> {
+        Box::pin(async move {
+            let symbol = symbol.to_string();
+            match AssetType::for_symbol(&symbol) {
+                AssetType::Crypto => {
+                    crypto_previous_close(&symbol, self.clock.now().date_naive()).await
+                }
+                AssetType::Stock => yahoo_previous_close(&symbol, false).await,
+            }
+        })
+    }
+
+    fn search<'a>(&'a self, query: &'a str) -> SearchFuture<'a> {
+        Box::pin(search_symbols(query))
+    }
+
+    fn daily_bar<'a>(&'a self, symbol: &'a str, date: NaiveDate) -> BarFuture<'a> {
+        Box::pin(yahoo_daily_bar(symbol, date))
+    }
+
+    fn daily_closes<'a>(
+        &'a self,
+        symbol: &'a str,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> ClosesFuture<'a> {
+        Box::pin(yahoo_daily_closes(symbol, from, to))
+    }
+}
+
+/// Stand-in while `offline` is on: every price is unavailable and nothing is fetched
+#[derive(Debug, Default)]
+pub struct OfflineProvider;
+
+/// Error searches return while offline
+pub const OFFLINE_MESSAGE: &str = "Offline: network lookups are paused. Type 'online' to resume";
+
+impl PriceProvider for OfflineProvider {
+    fn name(&self) -> &str {
+        "offline"
+    }
+
+    fn curr_price<'a>(&'a self, _symbol: &'a str) -> PriceFuture<'a> {
+        Box::pin(async { Decimal::ZERO })
+    }
+
+    fn previous_close<'a>(&'a self, _symbol: &'a str) -> PriceFuture<'a> {
+        Box::pin(async { Decimal::ZERO })
+    }
+
+    fn search<'a>(&'a self, _query: &'a str) -> SearchFuture<'a> {
+        Box::pin(async { Err(OFFLINE_MESSAGE.to_string()) })
+    }
+
+    fn daily_bar<'a>(&'a self, _symbol: &'a str, _date: NaiveDate) -> BarFuture<'a> {
+        Box::pin(async { None })
+    }
+
+    fn daily_closes<'a>(
+        &'a self,
+        _symbol: &'a str,
+        _from: NaiveDate,
+        _to: NaiveDate,
+    ) -> ClosesFuture<'a> {
+        Box::pin(async { Vec::new() })
+    }
+}
+
+/// In-memory prices for tests and simulations; never touches the network
+#[derive(Debug, Default)]
+pub struct MockProvider {
+    // symbol -> (current price, previous close)
+    quotes: Mutex<HashMap<String, (Decimal, Decimal)>>,
+    // Symbols searches are matched against
+    listings: Mutex<Vec<SymbolMatch>>,
+    // Error every search returns instead, when set
+    search_error: Mutex<Option<String>>,
+    // (symbol, date) -> that day's bar
+    bars: Mutex<HashMap<(String, NaiveDate), DailyBar>>,
+    calls: AtomicUsize,
+    // Current-price lookups wait while set, like a slow network
+    held: AtomicBool,
+    released: Notify,
+}
+
+impl MockProvider {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the current price, keeping any previous close already set
+    pub fn set_price(&self, symbol: &str, price: Decimal) {
+        let mut quotes = self.quotes.lock().unwrap_or_else(|e| e.into_inner());
+        quotes.entry(symbol.to_string()).or_default().0 = price;
+    }
+
+    pub fn set_previous_close(&self, symbol: &str, price: Decimal) {
+        let mut quotes = self.quotes.lock().unwrap_or_else(|e| e.into_inner());
+        quotes.entry(symbol.to_string()).or_default().1 = price;
+    }
+
+    /// Adds a symbol that searches can find by ticker or name
+    pub fn add_listing(&self, listing: SymbolMatch) {
+        let mut listings = self.listings.lock().unwrap_or_else(|e| e.into_inner());
+        listings.push(listing);
+    }
+
+    /// Makes every search fail with `message`, like a network error
+    pub fn fail_search(&self, message: &str) {
+        *self.search_error.lock().unwrap_or_else(|e| e.into_inner()) = Some(message.to_string());
+    }
+
+    /// Sets the open, high, low and close `daily_bar` returns for one day
+    pub fn set_daily_bar(&self, symbol: &str, date: NaiveDate, bar: DailyBar) {
+        let mut bars = self.bars.lock().unwrap_or_else(|e| e.into_inner());
+        bars.insert((symbol.to_string(), date), bar);
+    }
+
+    /// Makes `curr_price` lookups wait until `release_prices`, so a test can change
+    /// state between a price being requested and it being used
+    pub fn hold_prices(&self) {
+        self.held.store(true, Ordering::SeqCst);
+    }
+
+    /// Lets held and later `curr_price` lookups complete
+    pub fn release_prices(&self) {
+        self.held.store(false, Ordering::SeqCst);
+        self.released.notify_waiters();
+    }
+
+    /// Number of lookups served so far
+    pub fn calls(&self) -> usize {
+        self.calls.load(Ordering::Relaxed)
+    }
+
+    fn lookup(&self, symbol: &str) -> (Decimal, Decimal) {
+        self.calls.fetch_add(1, Ordering::Relaxed);
+        let quotes = self.quotes.lock().unwrap_or_else(|e| e.into_inner());
+        quotes.get(symbol).copied().unwrap_or_default()
+    }
+}
+
+impl PriceProvider for MockProvider {
+    fn name(&self) -> &str {
+        "mock"
+    }
+
+    fn curr_price<'a>(&'a self, symbol: &'a str) -> PriceFuture<'a> {
+        let (price, _) = self.lookup(symbol);
+        Box::pin(async move {
+            // Listen before checking so a release in between isn't missed
+            loop {
+                let released = self.released.notified();
+                if !self.held.load(Ordering::SeqCst) {
+                    break;
+                }
+                released.await;
+            }
+            price
+        })
+    }
+
+    fn previous_close<'a>(&'a self, symbol: &'a str) -> PriceFuture<'a> {
+        let (_, previous_close) = self.lookup(symbol);
+        Box::pin(async move { previous_close })
+    }
+
+    // Case-insensitive substring match on ticker or name, in the order added
+    fn search<'a>(&'a self, query: &'a str) -> SearchFuture<'a> {
+        let error = self
+            .search_error
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone();
+        let query = query.to_lowercase();
+        let found = self
+            .listings
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .filter(|listing| {
+                listing.symbol.to_lowercase().contains(&query)
+                    || listing
+                        .name
+                        .as_ref()
+                        .is_some_and(|name| name.to_lowercase().contains(&query))
+            })
+            .cloned()
+            .collect();
+        Box::pin(async move {
+            match error {
+                Some(message) => Err(message),
+                None => Ok(found),
+            }
+        })
+    }
+
+    fn daily_bar<'a>(&'a self, symbol: &'a str, date: NaiveDate) -> BarFuture<'a> {
+        self.calls.fetch_add(1, Ordering::Relaxed);
+        let bar = self
+            .bars
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(&(symbol.to_string(), date))
+            .copied();
+        Box::pin(async move { bar })
+    }
+
+    // Closes of the bars set with `set_daily_bar` in the range
+    fn daily_closes<'a>(
+        &'a self,
+        symbol: &'a str,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> ClosesFuture<'a> {
+        self.calls.fetch_add(1, Ordering::Relaxed);
+        let mut closes: Vec<(NaiveDate, Decimal)> = self
+            .bars
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .filter(|((bar_symbol, date), _)| bar_symbol == symbol && (from..=to).contains(date))
+            .map(|((_, date), bar)| (*date, bar.close))
+            .collect();
+        closes.sort();
+        Box::pin(async move { closes })
+    }
+}
+
+/// Current prices for the symbols, one concurrent lookup each
+/// Symbols the provider can't price (a zero quote) are left out
+pub async fn current_prices(
+    provider: Arc<dyn PriceProvider>,
+    symbols: impl IntoIterator<Item = String>,
+) -> HashMap<String, Decimal> {
+    let fetches: Vec<_> = symbols
+        .into_iter()
+        .map(|symbol| {
+            let provider = provider.clone();
+            tokio::spawn(async move {
+                let price = provider.curr_price(&symbol).await;
+                (symbol, price)
+            })
+        })
+        .collect();
+    let mut prices = HashMap::new();
+    for fetch in fetches {
+        if let Ok((symbol, price)) = fetch.await
+            && price > Decimal::ZERO
+        {
+            prices.insert(symbol, price);
+        }
+    }
+    prices
+}
+
+/// Current price and previous close for the symbols, one concurrent lookup each
+/// Symbols missing either price (a zero quote) are left out
+pub async fn day_quotes(
+    provider: Arc<dyn PriceProvider>,
+    symbols: impl IntoIterator<Item = String>,
+) -> HashMap<String, (Decimal, Decimal)> {
+    let fetches: Vec<_> = symbols
+        .into_iter()
+        .map(|symbol| {
+            let provider = provider.clone();
+            tokio::spawn(async move {
+                let (price, previous_close) = tokio::join!(
+                    provider.curr_price(&symbol),
+                    provider.previous_close(&symbol)
+                );
+                (symbol, price, previous_close)
+            })
+        })
+        .collect();
+    let mut quotes = HashMap::new();
+    for fetch in fetches {
+        if let Ok((symbol, price, previous_close)) = fetch.await
+            && price > Decimal::ZERO
+            && previous_close > Decimal::ZERO
+        {
+            quotes.insert(symbol, (price, previous_close));
+        }
+    }
+    quotes
+}
+
+// SECTION: Request Throttling
+
+/// Default cap on lookups in flight at once
+pub const DEFAULT_MAX_CONCURRENT: usize = 4;
+/// Default minimum gap between the starts of two lookups
+pub const DEFAULT_MIN_SPACING_MS: u64 = 100;
+/// Allowed ranges for `throttle`
+pub const MAX_CONCURRENT_LIMIT: usize = 32;
+pub const MAX_SPACING_MS: u64 = 10_000;
+/// A lookup held back at least this long counts as throttled in the status bar
+pub const THROTTLE_WARN_MS: u64 = 500;
+/// How long the warning stays up after the last long wait
+const THROTTLE_WARN_WINDOW_SECS: u64 = 10;
+
+/// How hard the throttle holds lookups back
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ThrottleLimits {
+    pub max_concurrent: usize,
+    pub min_spacing_ms: u64,
+}
+
+impl Default for ThrottleLimits {
+    fn default() -> Self {
+        Self {
+            max_concurrent: DEFAULT_MAX_CONCURRENT,
+            min_spacing_ms: DEFAULT_MIN_SPACING_MS,
+        }
+    }
+}
+
+impl ThrottleLimits {
+    /// Checks both limits are in range, returning the message `throttle` shows
+    pub fn validate(self) -> Result<Self, String> {
+        if !(1..=MAX_CONCURRENT_LIMIT).contains(&self.max_concurrent) {
+            return Err(format!(
+                "Concurrent lookups must be between 1 and {}",
+                MAX_CONCURRENT_LIMIT
+            ));
+        }
+        if self.min_spacing_ms > MAX_SPACING_MS {
+            return Err(format!("Spacing must be at most {}ms", MAX_SPACING_MS));
+        }
+        Ok(self)
+    }
+}
+
+/// Shared limit on network lookups: at most `max_concurrent` in flight and starts at
+/// least `min_spacing_ms` apart, so refreshes, order checks and commands firing
+/// together are smoothed out instead of tripping Yahoo's rate limits
+#[derive(Debug)]
+pub struct Throttle {
+    limits: Mutex<ThrottleLimits>,
+    // Replaced when the limit changes; permits held on the old one drain naturally
+    semaphore: Mutex<Arc<Semaphore>>,
+    // Earliest time the next lookup may start
+    next_start: Mutex<Option<Instant>>,
+    // When a lookup last waited THROTTLE_WARN_MS or more
+    last_long_wait: Mutex<Option<Instant>>,
+}
+
+impl Default for Throttle {
+    fn default() -> Self {
+        Self::new(ThrottleLimits::default())
+    }
+}
+
+impl Throttle {
+    pub fn new(limits: ThrottleLimits) -> Self {
+        Self {
+            limits: Mutex::new(limits),
+            semaphore: Mutex::new(Arc::new(Semaphore::new(limits.max_concurrent))),
+            next_start: Mutex::new(None),
+            last_long_wait: Mutex::new(None),
+        }
+    }
+
+    pub fn limits(&self) -> ThrottleLimits {
+        *self.limits.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn set_limits(&self, limits: ThrottleLimits) {
+        let mut current = self.limits.lock().unwrap_or_else(|e| e.into_inner());
+        if current.max_concurrent != limits.max_concurrent {
+            *self.semaphore.lock().unwrap_or_else(|e| e.into_inner()) =
+                Arc::new(Semaphore::new(limits.max_concurrent));
+        }
+        *current = limits;
+    }
+
+    /// Waits for a free slot and the spacing gap; the lookup runs while the permit is held
+    pub async fn acquire(&self) -> OwnedSemaphorePermit {
+        let started = Instant::now();
+        let semaphore = self
+            .semaphore
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone();
+        let permit = semaphore
+            .acquire_owned()
+            .await
+            .expect("throttle semaphore is never closed");
+
+        // Claim the next start time, then wait for it outside the lock
+        let spacing = std::time::Duration::from_millis(self.limits().min_spacing_ms);
+        let start = {
+            let mut next_start = self.next_start.lock().unwrap_or_else(|e| e.into_inner());
+            let now = Instant::now();
+            let start = next_start.map_or(now, |next| next.max(now));
+            *next_start = Some(start + spacing);
+            start
+        };
+        tokio::time::sleep_until(start).await;
+
+        if started.elapsed() >= std::time::Duration::from_millis(THROTTLE_WARN_MS) {
+            *self
+                .last_long_wait
+                .lock()
+                .unwrap_or_else(|e| e.into_inner()) = Some(Instant::now());
+        }
+        permit
+    }
+
+    /// Whether a lookup was held back long enough recently to warn about
+    pub fn delayed_recently(&self) -> bool {
+        self.last_long_wait
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .is_some_and(|at| {
+                at.elapsed() < std::time::Duration::from_secs(THROTTLE_WARN_WINDOW_SECS)
+            })
+    }
+}
+
+/// Provider whose every lookup first waits its turn on a shared `Throttle`
+#[derive(Debug)]
+pub struct ThrottledProvider {
+    inner: Arc<dyn PriceProvider>,
+    throttle: Arc<Throttle>,
+}
+
+impl ThrottledProvider {
+    pub fn new(inner: Arc<dyn PriceProvider>, throttle: Arc<Throttle>) -> Self {
+        Self { inner, throttle }
+    }
+}
 
-pub async fn previous_price_close(symbol: &String, print: bool) -> Decimal {
+impl PriceProvider for ThrottledProvider {
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn curr_price<'a>(&'a self, symbol: &'a str) -> PriceFuture<'a> {
+        Box::pin(async move {
+            let _permit = self.throttle.acquire().await;
+            self.inner.curr_price(symbol).await
+        })
+    }
+
+    fn previous_close<'a>(&'a self, symbol: &'a str) -> PriceFuture<'a> {
+        Box::pin(async move {
+            let _permit = self.throttle.acquire().await;
+            self.inner.previous_close(symbol).await
+        })
+    }
+
+    fn search<'a>(&'a self, query: &'a str) -> SearchFuture<'a> {
+        Box::pin(async move {
+            let _permit = self.throttle.acquire().await;
+            self.inner.search(query).await
+        })
+    }
+
+    fn daily_bar<'a>(&'a self, symbol: &'a str, date: NaiveDate) -> BarFuture<'a> {
+        Box::pin(async move {
+            let _permit = self.throttle.acquire().await;
+            self.inner.daily_bar(symbol, date).await
+        })
+    }
+
+    fn daily_closes<'a>(
+        &'a self,
+        symbol: &'a str,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> ClosesFuture<'a> {
+        Box::pin(async move {
+            let _permit = self.throttle.acquire().await;
+            self.inner.daily_closes(symbol, from, to).await
+        })
+    }
+}
+
+// SECTION: Provider Fallback Chain
+
+/// Lookups each chain link remembers when reporting its success rate
+pub const HEALTH_WINDOW: usize = 50;
+
+/// A price source that can be named in the `provider_chain` setting
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProviderSource {
+    Yahoo,
+    Stooq,
+    /// The last price the chain served for the symbol this session
+    Cache,
+}
+
+impl ProviderSource {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "yahoo" => Some(Self::Yahoo),
+            "stooq" => Some(Self::Stooq),
+            "cache" => Some(Self::Cache),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Yahoo => "yahoo",
+            Self::Stooq => "stooq",
+            Self::Cache => "cache",
+        }
+    }
+
+    /// Parses a comma-separated chain such as `yahoo,stooq,cache`
+    /// Rejects unknown names, repeats and an empty chain
+    pub fn parse_chain(value: &str) -> Result<Vec<Self>, String> {
+        let mut chain = Vec::new();
+        for name in value
+            .split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+        {
+            let Some(source) = Self::parse(name) else {
+                return Err(format!(
+                    "Unknown provider: '{}' (expected yahoo, stooq or cache)",
+                    name
+                ));
+            };
+            if chain.contains(&source) {
+                return Err(format!("{} is listed twice", source.name()));
+            }
+            chain.push(source);
+        }
+        if chain.is_empty() {
+            return Err("The chain needs at least one provider".to_string());
+        }
+        Ok(chain)
+    }
+}
+
+/// A chain link's outcomes over its last `HEALTH_WINDOW` lookups
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProviderHealth {
+    pub name: String,
+    pub attempts: usize,
+    pub successes: usize,
+}
+
+impl ProviderHealth {
+    /// Share of recent lookups that returned data, in percent
+    pub fn success_rate(&self) -> Option<Decimal> {
+        if self.attempts == 0 {
+            return None;
+        }
+        Some(Decimal::from(self.successes * 100) / Decimal::from(self.attempts))
+    }
+}
+
+enum ChainSource {
+    Provider(Arc<dyn PriceProvider>),
+    Cache,
+}
+
+struct ChainLink {
+    source: ChainSource,
+    // Newest outcome last
+    recent: Mutex<VecDeque<bool>>,
+}
+
+impl ChainLink {
+    fn new(source: ChainSource) -> Self {
+        Self {
+            source,
+            recent: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    fn name(&self) -> &str {
+        match &self.source {
+            ChainSource::Provider(provider) => provider.name(),
+            ChainSource::Cache => ProviderSource::Cache.name(),
+        }
+    }
+
+    fn record(&self, success: bool) {
+        let mut recent = self.recent.lock().unwrap_or_else(|e| e.into_inner());
+        if recent.len() == HEALTH_WINDOW {
+            recent.pop_front();
+        }
+        recent.push_back(success);
+    }
+}
+
+/// Providers tried in order until one has the data; a failed lookup (a zero
+/// price, a search error, a missing bar) falls through to the next link. A
+/// `cache` link answers price lookups with the last value the chain served
+pub struct ProviderChain {
+    links: Vec<ChainLink>,
+    // symbol -> (current price, previous close) last served
+    cache: Mutex<HashMap<String, (Decimal, Decimal)>>,
+}
+
+impl fmt::Debug for ProviderChain {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let names: Vec<&str> = self.links.iter().map(ChainLink::name).collect();
+        write!(f, "ProviderChain({})", names.join(" -> "))
+    }
+}
+
+impl ProviderChain {
+    /// Chain over the given providers, optionally ending in the cache
+    pub fn new(providers: Vec<Arc<dyn PriceProvider>>, cache_last: bool) -> Self {
+        let mut links: Vec<ChainLink> = providers
+            .into_iter()
+            .map(|provider| ChainLink::new(ChainSource::Provider(provider)))
+            .collect();
+        if cache_last {
+            links.push(ChainLink::new(ChainSource::Cache));
+        }
+        Self {
+            links,
+            cache: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Chain of the live providers named in settings, in order, telling time from `clock`
+    pub fn from_sources(sources: &[ProviderSource], clock: Arc<dyn Clock>) -> Self {
+        let links = sources
+            .iter()
+            .map(|source| {
+                ChainLink::new(match source {
+                    ProviderSource::Yahoo => {
+                        ChainSource::Provider(Arc::new(YahooProvider::new(clock.clone())))
+                    }
+                    ProviderSource::Stooq => ChainSource::Provider(Arc::new(StooqProvider)),
+                    ProviderSource::Cache => ChainSource::Cache,
+                })
+            })
+            .collect();
+        Self {
+            links,
+            cache: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Each link in order with its recent success rate
+    pub fn health(&self) -> Vec<ProviderHealth> {
+        self.links
+            .iter()
+            .map(|link| {
+                let recent = link.recent.lock().unwrap_or_else(|e| e.into_inner());
+                ProviderHealth {
+                    name: link.name().to_string(),
+                    attempts: recent.len(),
+                    successes: recent.iter().filter(|success| **success).count(),
+                }
+            })
+            .collect()
+    }
+
+    fn cached(&self, symbol: &str) -> (Decimal, Decimal) {
+        let cache = self.cache.lock().unwrap_or_else(|e| e.into_inner());
+        cache.get(symbol).copied().unwrap_or_default()
+    }
+
+    // First non-zero price down the chain; `previous` picks the previous close
+    async fn price(&self, symbol: &str, previous: bool) -> Decimal {
+        for link in &self.links {
+            let price = match &link.source {
+                ChainSource::Provider(provider) if previous => {
+                    provider.previous_close(symbol).await
+                }
+                ChainSource::Provider(provider) => provider.curr_price(symbol).await,
+                ChainSource::Cache => {
+                    let (curr, previous_close) = self.cached(symbol);
+                    if previous { previous_close } else { curr }
+                }
+            };
+            link.record(price > Decimal::ZERO);
+            if price > Decimal::ZERO {
+                let mut cache = self.cache.lock().unwrap_or_else(|e| e.into_inner());
+                let entry = cache.entry(symbol.to_string()).or_default();
+                if previous {
+                    entry.1 = price;
+                } else {
+                    entry.0 = price;
+                }
+                return price;
+            }
+        }
+        Decimal::ZERO
+    }
+}
+
+impl PriceProvider for ProviderChain {
+    fn name(&self) -> &str {
+        self.links.first().map_or("chain", ChainLink::name)
+    }
+
+    fn curr_price<'a>(&'a self, symbol: &'a str) -> PriceFuture<'a> {
+        Box::pin(self.price(symbol, false))
+    }
+
+    fn previous_close<'a>(&'a self, symbol: &'a str) -> PriceFuture<'a> {
+        Box::pin(self.price(symbol, true))
+    }
+
+    fn search<'a>(&'a self, query: &'a str) -> SearchFuture<'a> {
+        Box::pin(async move {
+            let mut last_error = "No provider can search".to_string();
+            for link in &self.links {
+                let ChainSource::Provider(provider) = &link.source else {
+                    continue;
+                };
+                match provider.search(query).await {
+                    Ok(found) => {
+                        link.record(true);
+                        return Ok(found);
+                    }
+                    Err(err) => {
+                        link.record(false);
+                        last_error = err;
+                    }
+                }
+            }
+            Err(last_error)
+        })
+    }
+
+    fn daily_bar<'a>(&'a self, symbol: &'a str, date: NaiveDate) -> BarFuture<'a> {
+        Box::pin(async move {
+            for link in &self.links {
+                let ChainSource::Provider(provider) = &link.source else {
+                    continue;
+                };
+                let bar = provider.daily_bar(symbol, date).await;
+                link.record(bar.is_some());
+                if bar.is_some() {
+                    return bar;
+                }
+            }
+            None
+        })
+    }
+
+    fn daily_closes<'a>(
+        &'a self,
+        symbol: &'a str,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> ClosesFuture<'a> {
+        Box::pin(async move {
+            for link in &self.links {
+                let ChainSource::Provider(provider) = &link.source else {
+                    continue;
+                };
+                let closes = provider.daily_closes(symbol, from, to).await;
+                link.record(!closes.is_empty());
+                if !closes.is_empty() {
+                    return closes;
+                }
+            }
+            Vec::new()
+        })
+    }
+}
+
+/// Delayed last prices from Stooq, a backup for when Yahoo is down. Covers
+/// US-listed stocks only, with no previous close, search or history
+#[derive(Debug, Default)]
+pub struct StooqProvider;
+
+impl PriceProvider for StooqProvider {
+    fn name(&self) -> &str {
+        "stooq"
+    }
+
+    fn curr_price<'a>(&'a self, symbol: &'a str) -> PriceFuture<'a> {
+        Box::pin(stooq_last_price(symbol))
+    }
+
+    fn previous_close<'a>(&'a self, _symbol: &'a str) -> PriceFuture<'a> {
+        Box::pin(async { Decimal::ZERO })
+    }
+
+    fn search<'a>(&'a self, _query: &'a str) -> SearchFuture<'a> {
+        Box::pin(async { Err("Search isn't available from stooq".to_string()) })
+    }
+
+    fn daily_bar<'a>(&'a self, _symbol: &'a str, _date: NaiveDate) -> BarFuture<'a> {
+        Box::pin(async { None })
+    }
+
+    fn daily_closes<'a>(
+        &'a self,
+        _symbol: &'a str,
+        _from: NaiveDate,
+        _to: NaiveDate,
+    ) -> ClosesFuture<'a> {
+        Box::pin(async { Vec::new() })
+    }
+}
+
+// SECTION: Yahoo Finance Lookups
+
+// Yesterday's close from the quote endpoint; use PriceProvider::previous_close instead
+pub async fn yahoo_previous_close(symbol: &String, print: bool) -> Decimal {
     let client = YfClient::default();
     let ticker = Ticker::new(&client, symbol);
 
     match ticker.quote().await {
-        Ok(quote) => match quote.previous_close {
+        Ok(quote) => match previous_close_or_last(
+            quote.previous_close.map(|price| price.amount()),
+            quote.price.map(|price| price.amount()),
+        ) {
             Some(price) => {
                 if print {
                     println!("Previous close: {price}");
                 }
-                price.amount()
+                price
             }
             None => {
-                eprintln!("{symbol} -> previous close unavailable");
+                eprintln!("{symbol} -> quote has no previous close or last price");
                 Decimal::ZERO
             }
         },
@@ -25,7 +944,20 @@ pub async fn previous_price_close(symbol: &String, print: bool) -> Decimal {
     }
 }
 
-pub async fn curr_price(symbol: &String, print: bool) -> Decimal {
+// A quote's previous close, or its last regular-market price when Yahoo leaves the
+// close out or reports it as zero. None only when the quote has neither
+pub fn previous_close_or_last(
+    previous_close: Option<Decimal>,
+    last_price: Option<Decimal>,
+) -> Option<Decimal> {
+    [previous_close, last_price]
+        .into_iter()
+        .flatten()
+        .find(|price| *price > Decimal::ZERO)
+}
+
+// Last trade from fast info; use PriceProvider::curr_price instead
+pub async fn yahoo_last_price(symbol: &String, print: bool) -> Decimal {
     let client = YfClient::default();
     let ticker = Ticker::new(&client, symbol);
 
@@ -49,3 +981,139 @@ pub async fn curr_price(symbol: &String, print: bool) -> Decimal {
         }
     }
 }
+
+// Yahoo's symbol search; an error means the lookup itself failed, not that nothing matched
+pub async fn search_symbols(query: &str) -> Result<Vec<SymbolMatch>, String> {
+    let client = YfClient::default();
+    match yfinance_rs::search(&client, query).await {
+        Ok(response) => Ok(response
+            .results
+            .into_iter()
+            .map(|result| SymbolMatch {
+                symbol: result.symbol.to_string(),
+                name: result.name,
+                exchange: result.exchange.map(|exchange| exchange.to_string()),
+                kind: result.kind.full_name().to_string(),
+            })
+            .collect()),
+        Err(err) => Err(format!("Search failed: {err}")),
+    }
+}
+
+// Crypto never closes, so use the last completed daily candle (00:00 UTC) as the reference
+// Falls back to the quote's previous close when history is unavailable
+pub async fn crypto_previous_close(symbol: &String, today: NaiveDate) -> Decimal {
+    let last_completed = yahoo_daily_closes(symbol, today - Duration::days(3), today)
+        .await
+        .into_iter()
+        .rev()
+        .find(|(date, close)| *date < today && *close > Decimal::ZERO);
+    match last_completed {
+        Some((_, close)) => close,
+        None => yahoo_previous_close(symbol, false).await,
+    }
+}
+
+// Daily closes from `from` through `to`, oldest first; use PriceProvider::daily_closes instead
+pub async fn yahoo_daily_closes(
+    symbol: &str,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> Vec<(NaiveDate, Decimal)> {
+    let client = YfClient::default();
+    let ticker = Ticker::new(&client, symbol);
+    let (Some(start), Some(end)) = (
+        from.and_hms_opt(0, 0, 0),
+        (to + Duration::days(1)).and_hms_opt(0, 0, 0),
+    ) else {
+        return Vec::new();
+    };
+    let (start, end) = (start.and_utc(), end.and_utc());
+
+    match ticker
+        .history_builder()
+        .between(start, end)
+        .interval(Interval::D1)
+        .fetch()
+        .await
+    {
+        Ok(candles) => candles
+            .into_iter()
+            .map(|candle| (candle.ts.date_naive(), candle.close.amount()))
+            .collect(),
+        Err(err) => {
+            eprintln!("Failed to fetch {symbol} history: {err}");
+            Vec::new()
+        }
+    }
+}
+
+// The daily candle for `date`; the window is padded a day each side because Yahoo
+// stamps candles at the exchange open, which can fall on the neighbouring UTC date
+pub async fn yahoo_daily_bar(symbol: &str, date: NaiveDate) -> Option<DailyBar> {
+    let client = YfClient::default();
+    let ticker = Ticker::new(&client, symbol);
+    let start = (date - Duration::days(1)).and_hms_opt(0, 0, 0)?.and_utc();
+    let end = (date + Duration::days(2)).and_hms_opt(0, 0, 0)?.and_utc();
+
+    match ticker
+        .history_builder()
+        .between(start, end)
+        .interval(Interval::D1)
+        .fetch()
+        .await
+    {
+        Ok(candles) => candles
+            .into_iter()
+            .find(|candle| candle.ts.date_naive() == date)
+            .map(|candle| DailyBar {
+                open: candle.open.amount(),
+                high: candle.high.amount(),
+                low: candle.low.amount(),
+                close: candle.close.amount(),
+            }),
+        Err(err) => {
+            eprintln!("Failed to fetch {symbol} history: {err}");
+            None
+        }
+    }
+}
+
+// SECTION: Stooq Lookups
+
+const STOOQ_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+// Last price from Stooq's quote CSV; zero for crypto and anything Stooq can't price
+pub async fn stooq_last_price(symbol: &str) -> Decimal {
+    if AssetType::for_symbol(symbol) == AssetType::Crypto {
+        return Decimal::ZERO;
+    }
+    let url = format!(
+        "https://stooq.com/q/l/?s={}.us&f=sd2t2ohlc&h&e=csv",
+        symbol.to_lowercase()
+    );
+    let client = match reqwest::Client::builder().timeout(STOOQ_TIMEOUT).build() {
+        Ok(client) => client,
+        Err(err) => {
+            eprintln!("Failed to build stooq client: {err}");
+            return Decimal::ZERO;
+        }
+    };
+    let body = match client.get(&url).send().await {
+        Ok(response) => response.text().await.unwrap_or_default(),
+        Err(err) => {
+            eprintln!("Failed to fetch {symbol} from stooq: {err}");
+            return Decimal::ZERO;
+        }
+    };
+    parse_stooq_close(&body).unwrap_or(Decimal::ZERO)
+}
+
+// Close column of a Stooq quote CSV (header, then one row); None for "N/D" or garbage
+pub fn parse_stooq_close(csv: &str) -> Option<Decimal> {
+    let row = csv.lines().nth(1)?;
+    let close = row.split(',').nth(6)?.trim();
+    Decimal::from_str(close)
+        .ok()
+        .filter(|price| *price > Decimal::ZERO)
+}
@@ -1,7 +1,8 @@
-use chrono::Utc;
 use rust_decimal::prelude::*;
 
-use crate::{AppState::AppState, FinanceProvider, UserInput};
+use crate::FinanceProvider::DailyBar;
+use crate::UserInput;
+use crate::clock::{Clock, SystemClock};
 
 #[derive(Clone, Debug, PartialEq)]
 pub enum Side {
@@ -16,33 +17,59 @@ pub struct Trade {
     price_per: Decimal,
     side: Side,
     timestamp: i64,
-    order_type: String, // "Market", "BuyLimit", "StopLoss", "TakeProfit"
+    order_type: String, // "Market", "BuyLimit", "StopLoss", "TakeProfit", "SellLimit"
+    tag: Option<String>, // Strategy label set with `--tag`
+    currency: Option<String>, // Cash bucket the fill settled in; None on older trades
+    fee: Decimal,       // Commission charged on the fill, in its currency
+}
+
+/// Longest strategy label a trade can carry
+pub const MAX_TAG_LEN: usize = 24;
+
+/// Parses a strategy label: letters, digits, `-` and `_`, stored lowercase
+pub fn parse_tag(raw: &str) -> Result<String, String> {
+    let tag = raw.trim().to_lowercase();
+    if tag.is_empty() {
+        return Err("Tag can't be empty".to_string());
+    }
+    if tag.chars().count() > MAX_TAG_LEN {
+        return Err(format!(
+            "Tag '{}' is too long (limit {} characters)",
+            tag, MAX_TAG_LEN
+        ));
+    }
+    if !tag
+        .chars()
+        .all(|c| c.is_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err(format!(
+            "Invalid tag: '{}' (use letters, digits, '-' and '_')",
+            raw.trim()
+        ));
+    }
+    Ok(tag)
 }
 
 // A completed transaction record for both market orders and executed conditional orders
 impl Trade {
-    // Create buy transaction record from immediate market order
+    // Create buy transaction record from immediate market order, stamped now
     pub fn buy(symbol: String, quantity: Decimal, price_per: Decimal) -> Self {
-        Self {
-            symbol,
-            quantity,
-            price_per,
-            side: Side::Buy,
-            timestamp: Utc::now().timestamp(),
-            order_type: "Market".to_string(),
-        }
+        Self::buy_at(symbol, quantity, price_per, SystemClock.timestamp())
     }
 
-    // Create sell transaction record from immediate market order
+    // Create sell transaction record from immediate market order, stamped now
     pub fn sell(symbol: String, quantity: Decimal, price_per: Decimal) -> Self {
-        Self {
-            symbol,
-            quantity,
-            price_per,
-            side: Side::Sell,
-            timestamp: Utc::now().timestamp(),
-            order_type: "Market".to_string(),
-        }
+        Self::sell_at(symbol, quantity, price_per, SystemClock.timestamp())
+    }
+
+    // Create buy transaction record from market order executed at `timestamp`
+    pub fn buy_at(symbol: String, quantity: Decimal, price_per: Decimal, timestamp: i64) -> Self {
+        Self::buy_with_type(symbol, quantity, price_per, "Market".to_string(), timestamp)
+    }
+
+    // Create sell transaction record from market order executed at `timestamp`
+    pub fn sell_at(symbol: String, quantity: Decimal, price_per: Decimal, timestamp: i64) -> Self {
+        Self::sell_with_type(symbol, quantity, price_per, "Market".to_string(), timestamp)
     }
 
     // Create buy transaction with specific order type
@@ -51,14 +78,18 @@ impl Trade {
         quantity: Decimal,
         price_per: Decimal,
         order_type: String,
+        timestamp: i64,
     ) -> Self {
         Self {
             symbol,
             quantity,
             price_per,
             side: Side::Buy,
-            timestamp: Utc::now().timestamp(),
+            timestamp,
             order_type,
+            tag: None,
+            currency: None,
+            fee: Decimal::ZERO,
         }
     }
 
@@ -68,14 +99,18 @@ impl Trade {
         quantity: Decimal,
         price_per: Decimal,
         order_type: String,
+        timestamp: i64,
     ) -> Self {
         Self {
             symbol,
             quantity,
             price_per,
             side: Side::Sell,
-            timestamp: Utc::now().timestamp(),
+            timestamp,
             order_type,
+            tag: None,
+            currency: None,
+            fee: Decimal::ZERO,
         }
     }
 
@@ -107,6 +142,45 @@ impl Trade {
         &self.order_type
     }
 
+    pub fn get_tag(&self) -> Option<&str> {
+        self.tag.as_deref()
+    }
+
+    // Same trade labelled with a strategy tag
+    pub fn with_tag(mut self, tag: Option<String>) -> Self {
+        self.tag = tag;
+        self
+    }
+
+    // Currency the fill paid from or was credited to, fixed when it executed
+    pub fn get_currency(&self) -> Option<&str> {
+        self.currency.as_deref()
+    }
+
+    pub fn with_currency(mut self, currency: Option<String>) -> Self {
+        self.currency = currency;
+        self
+    }
+
+    // Commission paid on top of a buy's cost or out of a sell's proceeds
+    pub fn get_fee(&self) -> Decimal {
+        self.fee
+    }
+
+    pub fn with_fee(mut self, fee: Decimal) -> Self {
+        self.fee = fee;
+        self
+    }
+
+    // Cash the fill moved, fee included: paid for a buy, received for a sell
+    pub fn cash_value(&self) -> Decimal {
+        let value = self.quantity * self.price_per;
+        match self.side {
+            Side::Buy => value + self.fee,
+            Side::Sell => value - self.fee,
+        }
+    }
+
     pub fn from_database(
         symbol: String,
         quantity: Decimal,
@@ -122,20 +196,25 @@ impl Trade {
             side,
             timestamp,
             order_type,
+            tag: None,
+            currency: None,
+            fee: Decimal::ZERO,
         }
     }
 }
 
 // Category of conditional order to create
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum OrderType {
     BuyLimit,
     StopLoss,
     TakeProfit,
+    // Resting ask: fills once the price reaches the limit, never below it
+    SellLimit,
 }
 
 // A pending order waiting for execution conditions to be met
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct OpenOrder {
     symbol: String,
     quantity: Decimal,
@@ -153,7 +232,25 @@ impl OpenOrder {
         order_type: OrderType,
         side: Side,
     ) -> Self {
-        let timestamp = Utc::now().timestamp();
+        Self::new_at(
+            symbol,
+            quantity,
+            price,
+            order_type,
+            side,
+            SystemClock.timestamp(),
+        )
+    }
+
+    // Create an order placed at `timestamp`, e.g. from the state's clock or a saved row
+    pub fn new_at(
+        symbol: String,
+        quantity: Decimal,
+        price: Decimal,
+        order_type: OrderType,
+        side: Side,
+        timestamp: i64,
+    ) -> Self {
         Self {
             symbol,
             quantity,
@@ -187,6 +284,72 @@ impl OpenOrder {
     pub fn get_order_type(&self) -> OrderType {
         self.order_type.clone()
     }
+
+    // Change the trigger or limit price; the placement time is kept
+    pub fn set_price_per(&mut self, price: Decimal) {
+        self.price = price;
+    }
+
+    // Change the quantity; the placement time is kept
+    pub fn set_qty(&mut self, quantity: Decimal) {
+        self.quantity = quantity;
+    }
+}
+
+// Audit record of a conditional order that filled: what it was set to trigger at
+// and what it actually filled at, kept alongside the plain trade it produced
+#[derive(Clone, Debug)]
+pub struct ExecutedOrder {
+    pub order_type: OrderType,
+    pub symbol: String,
+    pub quantity: Decimal,
+    // Limit or trigger price the order was placed with
+    pub target_price: Decimal,
+    pub fill_price: Decimal,
+    pub placed_at: i64,
+    pub executed_at: i64,
+}
+
+impl ExecutedOrder {
+    pub fn new(order: &OpenOrder, fill_price: Decimal, executed_at: i64) -> Self {
+        Self {
+            order_type: order.get_order_type(),
+            symbol: order.get_symbol().clone(),
+            quantity: order.get_qty(),
+            target_price: order.get_price_per(),
+            fill_price,
+            placed_at: order.get_timestamp(),
+            executed_at,
+        }
+    }
+
+    // Buy limits buy; stop losses, take profits and sell limits sell
+    pub fn side(&self) -> Side {
+        match self.order_type {
+            OrderType::BuyLimit => Side::Buy,
+            OrderType::StopLoss | OrderType::TakeProfit | OrderType::SellLimit => Side::Sell,
+        }
+    }
+
+    // How far the fill landed from the target, positive when it filled above it
+    pub fn fill_gap(&self) -> Decimal {
+        self.fill_price - self.target_price
+    }
+}
+
+// Price a conditional order would have filled at during `bar`, None if the day never
+// reached it. A day that opened through the trigger fills at the open, as a live
+// order resting overnight would have; otherwise it fills at the trigger itself
+pub fn simulated_fill(order_type: &OrderType, price: Decimal, bar: &DailyBar) -> Option<Decimal> {
+    match order_type {
+        // Buy limit and stop loss both trigger on the way down
+        OrderType::BuyLimit | OrderType::StopLoss => {
+            (bar.low <= price).then(|| bar.open.min(price))
+        }
+        OrderType::TakeProfit | OrderType::SellLimit => {
+            (bar.high >= price).then(|| bar.open.max(price))
+        }
+    }
 }
 
 // Factory function to create pending orders based on user input and order type
@@ -200,7 +363,7 @@ pub fn create_order(order_type: OrderType) -> Option<OpenOrder> {
             symbol,
             quantity,
             price,
-            timestamp: Utc::now().timestamp(),
+            timestamp: SystemClock.timestamp(),
             order_type: OrderType::BuyLimit,
             side: Side::Buy,
         },
@@ -208,7 +371,7 @@ pub fn create_order(order_type: OrderType) -> Option<OpenOrder> {
             symbol,
             quantity,
             price,
-            timestamp: Utc::now().timestamp(),
+            timestamp: SystemClock.timestamp(),
             order_type: OrderType::StopLoss,
             side: Side::Sell,
         },
@@ -216,77 +379,18 @@ pub fn create_order(order_type: OrderType) -> Option<OpenOrder> {
             symbol,
             quantity,
             price,
-            timestamp: Utc::now().timestamp(),
+            timestamp: SystemClock.timestamp(),
             order_type: OrderType::TakeProfit,
             side: Side::Sell,
         },
+        OrderType::SellLimit => OpenOrder {
+            symbol,
+            quantity,
+            price,
+            timestamp: SystemClock.timestamp(),
+            order_type: OrderType::SellLimit,
+            side: Side::Sell,
+        },
     };
     Some(order)
 }
-
-// Execute buy limit order when current price is at or below limit price
-pub async fn buy_limit(state: &mut AppState, order: &OpenOrder) -> bool {
-    let symbol = order.get_symbol().clone();
-    let limit_price = order.get_price_per();
-    let purchase_qty = order.get_qty();
-    let curr_cash = state.check_balance();
-    let curr_price = FinanceProvider::curr_price(&symbol, false).await;
-    let total_purchase_value = curr_price * purchase_qty;
-    if curr_price <= limit_price {
-        if total_purchase_value > curr_cash {
-            return false;
-        }
-        state.withdraw_purchase(total_purchase_value);
-        crate::Finance::add_to_holdings(&symbol, purchase_qty, curr_price, state).await;
-        state.add_trade(Trade::buy_with_type(
-            symbol,
-            purchase_qty,
-            curr_price,
-            "BuyLimit".to_string(),
-        ));
-        return true;
-    }
-    false
-}
-
-// Execute stop loss order when current price is at or below stop price to limit losses
-pub async fn sell_stop_loss(state: &mut AppState, order: &OpenOrder) -> bool {
-    let symbol = order.get_symbol().clone();
-    let limit_price = order.get_price_per();
-    let sale_qty = order.get_qty();
-    let curr_price = FinanceProvider::curr_price(&symbol, false).await;
-    let total_sale_value = curr_price * sale_qty;
-    if curr_price <= limit_price {
-        state.deposit_sell(total_sale_value);
-        crate::Finance::remove_from_holdings(&symbol, sale_qty, state).await;
-        state.add_trade(Trade::sell_with_type(
-            symbol,
-            sale_qty,
-            curr_price,
-            "StopLoss".to_string(),
-        ));
-        return true;
-    }
-    false
-}
-
-// Execute take profit order when current price is at or above target price to lock in gains
-pub async fn sell_take_profit(state: &mut AppState, order: &OpenOrder) -> bool {
-    let symbol = order.get_symbol().clone();
-    let take_profit_price = order.get_price_per();
-    let sale_qty = order.get_qty();
-    let curr_price = FinanceProvider::curr_price(&symbol, false).await;
-    let total_sale_value = take_profit_price * sale_qty;
-    if curr_price >= take_profit_price {
-        state.deposit_sell(total_sale_value);
-        crate::Finance::remove_from_holdings(&symbol, sale_qty, state).await;
-        state.add_trade(Trade::sell_with_type(
-            symbol,
-            sale_qty,
-            take_profit_price,
-            "TakeProfit".to_string(),
-        ));
-        return true;
-    }
-    false
-}
@@ -0,0 +1,114 @@
+/// Cash Flow Module
+///
+/// Ledger of money moved into and out of the account with `fund` and
+/// `withdraw`. Trades and interest change the cash balance too, but they stay
+/// inside the account, so only external deposits and withdrawals are recorded.
+/// Flows in another currency are recorded at their base value on the day.
+/// The ledger answers "how much have I put in net?" and gives return
+/// calculations the external flows they need.
+use rust_decimal::Decimal;
+
+use crate::format::csv_decimal;
+
+/// Header line of the ledger CSV written by `cashflow export`
+pub const CSV_HEADER: &str = "date,type,amount,currency";
+
+/// Direction of a cash flow
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CashFlowKind {
+    Deposit,
+    Withdrawal,
+}
+
+impl CashFlowKind {
+    /// Parses `deposit` or `withdrawal` (also `fund` and `withdraw`), case-insensitively
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "deposit" | "fund" => Some(Self::Deposit),
+            "withdrawal" | "withdraw" => Some(Self::Withdrawal),
+            _ => None,
+        }
+    }
+
+    /// Name as persisted and exported
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Deposit => "Deposit",
+            Self::Withdrawal => "Withdrawal",
+        }
+    }
+}
+
+/// One deposit or withdrawal
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CashFlow {
+    pub kind: CashFlowKind,
+    /// Always positive and in the base currency; `kind` gives the direction
+    pub amount: Decimal,
+    pub timestamp: i64,
+    /// Currency the money moved in when not the base one, with `amount` its
+    /// base value at the rate of the day
+    pub currency: Option<String>,
+}
+
+impl CashFlow {
+    pub fn new(kind: CashFlowKind, amount: Decimal, timestamp: i64) -> Self {
+        Self {
+            kind,
+            amount,
+            timestamp,
+            currency: None,
+        }
+    }
+
+    /// Same flow, moved in `currency` (None for the base currency)
+    pub fn with_currency(mut self, currency: Option<String>) -> Self {
+        self.currency = currency;
+        self
+    }
+
+    /// Currency the money moved in
+    pub fn currency_code(&self) -> &str {
+        self.currency.as_deref().unwrap_or(crate::fx::BASE_CURRENCY)
+    }
+
+    /// Amount with deposits positive and withdrawals negative
+    pub fn signed_amount(&self) -> Decimal {
+        match self.kind {
+            CashFlowKind::Deposit => self.amount,
+            CashFlowKind::Withdrawal => -self.amount,
+        }
+    }
+}
+
+/// Deposits minus withdrawals
+pub fn net_deposited(flows: &[CashFlow]) -> Decimal {
+    flows.iter().map(CashFlow::signed_amount).sum()
+}
+
+/// How much more can be deposited before net deposits reach `cap`
+pub fn deposit_headroom(flows: &[CashFlow], cap: Decimal) -> Decimal {
+    (cap - net_deposited(flows)).max(Decimal::ZERO)
+}
+
+/// Ledger as CSV with RFC 3339 dates, oldest first, so `cashflow import` reads it back exactly
+/// Amounts are base values; `currency` is the one the money moved in
+pub fn to_csv(flows: &[CashFlow]) -> String {
+    let mut ordered: Vec<&CashFlow> = flows.iter().collect();
+    ordered.sort_by_key(|flow| flow.timestamp);
+
+    let mut csv = format!("{}\n", CSV_HEADER);
+    for flow in ordered {
+        let date = chrono::DateTime::from_timestamp(flow.timestamp, 0)
+            .map(|dt| dt.to_rfc3339())
+            .unwrap_or_default();
+        csv.push_str(&format!(
+            "{},{},{},{}\n",
+            date,
+            flow.kind.name(),
+            csv_decimal(flow.amount),
+            flow.currency_code()
+        ));
+    }
+    csv
+}
@@ -0,0 +1,129 @@
+/// Clock Module
+///
+/// Source of the current time for everything that stamps trades, orders and
+/// events. The app runs on `SystemClock`; tests swap in a `MockClock` on the
+/// state so timestamps and anything derived from them (order and trade stamps,
+/// market status, interest accrual) are deterministic. Times are stored as Unix
+/// seconds and only turned into wall-clock text through the configured
+/// `DisplayTimezone`.
+use std::fmt;
+use std::sync::Mutex;
+
+use chrono::{DateTime, Duration, Local, Utc};
+use chrono_tz::Tz;
+
+/// Anything that can tell the time
+pub trait Clock: Send + Sync {
+    /// Current instant in UTC
+    fn now(&self) -> DateTime<Utc>;
+
+    /// Current instant as Unix seconds, the form stored on trades and orders
+    fn timestamp(&self) -> i64 {
+        self.now().timestamp()
+    }
+}
+
+impl fmt::Debug for dyn Clock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Clock({})", self.now())
+    }
+}
+
+/// The real wall clock
+#[derive(Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+/// Fixed time that only moves when told to; for tests
+#[derive(Debug)]
+pub struct MockClock {
+    now: Mutex<DateTime<Utc>>,
+}
+
+impl MockClock {
+    pub fn new(start: DateTime<Utc>) -> Self {
+        Self {
+            now: Mutex::new(start),
+        }
+    }
+
+    /// Starts at the given Unix timestamp
+    /// Out-of-range timestamps start at the epoch
+    pub fn at_timestamp(timestamp: i64) -> Self {
+        Self::new(DateTime::from_timestamp(timestamp, 0).unwrap_or_default())
+    }
+
+    pub fn set(&self, now: DateTime<Utc>) {
+        *self.now.lock().unwrap_or_else(|e| e.into_inner()) = now;
+    }
+
+    /// Moves the clock forward by `by`
+    pub fn advance(&self, by: Duration) {
+        let mut now = self.now.lock().unwrap_or_else(|e| e.into_inner());
+        *now += by;
+    }
+}
+
+impl Clock for MockClock {
+    fn now(&self) -> DateTime<Utc> {
+        *self.now.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+// SECTION: Display Timezone
+
+/// Zone every rendered timestamp is shown in
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DisplayTimezone {
+    /// The machine's local zone
+    #[default]
+    Local,
+    Utc,
+    /// An IANA zone such as `Europe/Berlin`
+    Named(Tz),
+}
+
+impl DisplayTimezone {
+    /// Parses `local`, `utc` (case-insensitive) or an IANA zone name
+    pub fn parse(value: &str) -> Option<Self> {
+        let value = value.trim();
+        if value.eq_ignore_ascii_case("local") {
+            return Some(Self::Local);
+        }
+        if value.eq_ignore_ascii_case("utc") {
+            return Some(Self::Utc);
+        }
+        value.parse::<Tz>().ok().map(Self::Named)
+    }
+
+    /// Name as persisted and accepted by `timezone`
+    pub fn name(&self) -> String {
+        match self {
+            Self::Local => "local".to_string(),
+            Self::Utc => "utc".to_string(),
+            Self::Named(tz) => tz.name().to_string(),
+        }
+    }
+
+    /// Unix timestamp formatted with a chrono pattern in this zone
+    /// None for timestamps chrono can't represent
+    pub fn format(&self, timestamp: i64, pattern: &str) -> Option<String> {
+        let utc = DateTime::<Utc>::from_timestamp(timestamp, 0)?;
+        Some(match self {
+            Self::Local => utc.with_timezone(&Local).format(pattern).to_string(),
+            Self::Utc => utc.format(pattern).to_string(),
+            Self::Named(tz) => utc.with_timezone(tz).format(pattern).to_string(),
+        })
+    }
+
+    /// "YYYY-MM-DD HH:MM", as trade history and the ledger show times
+    pub fn date_time(&self, timestamp: i64) -> String {
+        self.format(timestamp, "%Y-%m-%d %H:%M")
+            .unwrap_or_else(|| "Unknown".to_string())
+    }
+}
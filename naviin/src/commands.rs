@@ -2,21 +2,48 @@
 ///
 /// Processes user commands and executes the appropriate actions.
 /// All command logic is centralized here for easy maintenance.
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
 
 use rust_decimal::Decimal;
+use serde::Serialize;
 
-use crate::AppState::{AppState, monitor_order};
+use crate::AppState::{AppState, lock_state, margin_status_at_market, monitor_order, risk_prices};
 use crate::Finance;
 use crate::FinanceProvider;
 use crate::Orders;
 use crate::Storage;
+use crate::cashflow::{self, CashFlowKind};
+use crate::clock::DisplayTimezone;
+use crate::components::output::ScrollAfterCommand;
+use crate::costbasis::{self, CostBasisMethod};
+use crate::error::NaviinError;
+use crate::format::{self, Align, DOWN_MARKER, MoneyFormat, NumberStyle, TextTable, UP_MARKER};
+use crate::fx;
 use crate::import;
+use crate::leaderboard::{self, Standing};
+use crate::margin;
+use crate::market::{self, MarketStatus};
+use crate::performance;
+use crate::rebalance;
+use crate::recurring::{Frequency, RecurringBuy};
+use crate::refresh;
+use crate::replay;
+use crate::risk::RiskLimits;
+use crate::scenario::{self, Scenario, Shock};
+use crate::simulate;
+use crate::templates::{self, LegKind, OrderTemplate, TemplateLeg};
+use crate::undo::UndoAction;
+use crate::webhook;
 
 use sea_orm::DatabaseConnection;
 
 /// SECTION: Command Processing
 
+/// Most arguments a command may take; anything longer is rejected before parsing
+pub const MAX_COMMAND_ARGS: usize = 64;
+
 /// Main command processor - parses and executes commands
 ///
 /// # Arguments
@@ -37,13 +64,27 @@ pub async fn process_command(
     if trimmed.is_empty() {
         return "Empty command".to_string();
     }
+    let max_len = lock_state(state).get_max_command_len();
+    if trimmed.chars().count() > max_len {
+        return format!(
+            "Command too long: {} characters (limit {}). Change it with: inputlimit <chars>",
+            trimmed.chars().count(),
+            max_len
+        );
+    }
 
-    let pending_import = { state.lock().unwrap().is_pending_import() };
+    let pending_import = { lock_state(state).is_pending_import() };
     if pending_import {
         return handle_import_path(trimmed, state, db).await;
     }
 
-    let parts: Vec<&str> = trimmed.split_whitespace().collect();
+    let parts: Vec<&str> = trimmed
+        .split_whitespace()
+        .take(MAX_COMMAND_ARGS + 2)
+        .collect();
+    if parts.len() > MAX_COMMAND_ARGS + 1 {
+        return format!("Too many arguments (limit {})", MAX_COMMAND_ARGS);
+    }
     let cmd = parts[0].to_lowercase();
     let args = &parts[1..];
 
@@ -51,32 +92,85 @@ pub async fn process_command(
         // Account commands
         "fund" => handle_fund(state, db, args).await,
         "withdraw" => handle_withdraw(state, db, args).await,
-        "summary" => handle_summary(state).await,
+        "cashflow" => handle_cash_flow(state, db, args).await,
+        "cap" => handle_cap(state, db, args).await,
+        "performance" | "perf" => handle_performance(state, args).await,
+        "summary" => handle_summary(state, args).await,
+        "cashyield" => handle_cash_yield(state, db, args).await,
+        "leverage" => handle_leverage(state, db, args).await,
+        "holidays" => handle_holidays(state, db, args).await,
+        "currency" => handle_currency(state, db, args).await,
+        "webhook" => handle_webhook(state, db, args).await,
 
         // Price and watchlist commands
-        "price" => handle_price(args).await,
+        "price" => handle_price(state, args).await,
+        "prices" => handle_prices(state, args).await,
+        "search" => handle_search(state, args).await,
+        "watch" => handle_watch(state, args),
         "addwatch" => handle_add_watch(state, db, args).await,
         "unwatch" => handle_remove_watch(state, db, args).await,
+        "loadwatch" => handle_load_watch(state, db, args).await,
+        "note" => handle_note(state, db, args).await,
+        "alias" => handle_alias(state, db, args).await,
+        "compare" => handle_compare(state, args).await,
+        "movers" => handle_movers(state, args).await,
+        "today" => handle_today(state, args).await,
+        "whatif" => handle_whatif(state, args).await,
 
         // Trading commands
         "buy" => handle_buy(state, db, args).await,
         "sell" => handle_sell(state, db, args).await,
+        "close" => handle_close(state, db, args).await,
+        "closeall" => handle_close_all(state, db).await,
+        "target" => handle_target(state, db, args).await,
+        "rebalance" => handle_rebalance(state, db, args).await,
         "buylimit" => handle_buy_limit(state, db, args).await,
         "stoploss" => handle_stop_loss(state, db, args).await,
         "takeprofit" => handle_take_profit(state, db, args).await,
+        "selllimit" => handle_sell_limit(state, db, args).await,
+        "modify" => handle_modify(state, db, args).await,
+        "simfill" => handle_simfill(state, args).await,
+        "slippage" => handle_slippage(state, db, args).await,
+        "fee" => handle_fee(state, db, args).await,
+        "risklimit" => handle_risk_limit(state, db, args).await,
+        "costbasis" => handle_cost_basis(state, db, args).await,
+        "shares" => handle_shares(state, db, args).await,
+        "split" => handle_split(state, db, args).await,
+        "template" => handle_template(state, db, args).await,
+        "recurring" => handle_recurring(state, db, args).await,
 
         // Background order commands
         "stopbg" => handle_stop_bg(running).await,
         "startbg" => handle_start_bg(state.clone(), running).await,
+        "offline" => handle_offline(state, true),
+        "online" => handle_offline(state, false),
+        "refresh" => handle_refresh(state, db, args).await,
+        "outputscroll" => handle_output_scroll(state, db, args).await,
+        "timezone" => handle_timezone(state, db, args).await,
+        "inputlimit" => handle_input_limit(state, db, args).await,
+        "throttle" => handle_throttle(state, db, args).await,
+        "providers" => handle_providers(state, db, args).await,
 
         // Trade history command
-        "trades" => handle_trades(state).await,
+        "trades" => handle_trades(state, args).await,
+        "executed" => handle_executed(state, args),
+        "symbols" => handle_symbols(state, args),
+        "realized" => handle_realized(state, args).await,
+        "pnl" => handle_pnl(state, args).await,
+        "taxreport" => handle_tax_report(state, args).await,
+        "export-holdings" => handle_export_holdings(state, args).await,
 
         // System commands
-        "import" => handle_import(state).await,
+        "import" => handle_import(state, args).await,
         "reset" => handle_reset(state, db).await,
+        "backup" => handle_backup(state, db, args).await,
+        "restore" => handle_restore(state, db, args).await,
+        "undo" => handle_undo(state, db).await,
+        "simulate" => handle_simulate(state, args).await,
+        "replay" => handle_replay(state, db, args).await,
+        "leaderboard" => handle_leaderboard(state, args).await,
         "clear" => "__CLEAR__".to_string(),
-        "help" => handle_help(),
+        "help" => handle_help(args),
         "exit" | "quit" => "Exiting...".to_string(),
 
         // Unknown command
@@ -87,465 +181,3938 @@ pub async fn process_command(
     }
 }
 
+/// Parses a numeric command argument without panicking on malformed input
+///
+/// # Arguments
+/// * `raw` - The argument as typed by the user
+/// * `field` - Name of the argument, used in the error message
+pub fn parse_decimal_arg(raw: &str, field: &str) -> Result<Decimal, NaviinError> {
+    raw.trim()
+        .parse::<Decimal>()
+        .map_err(|_| NaviinError::InvalidNumber {
+            field: field.to_string(),
+            value: raw.to_string(),
+        })
+}
+
+/// Canonical symbol for one typed by the user, through the session's aliases
+fn normalize(state: &Arc<Mutex<AppState>>, raw: &str) -> Finance::Symbol {
+    lock_state(state).normalize_symbol(raw)
+}
+
+/// Size of a market order: a share count, or a `$`-prefixed dollar amount
+enum OrderSize {
+    Shares(Decimal),
+    Notional(Decimal),
+}
+
+/// Parses `10` as shares and `$250` as a dollar amount
+fn parse_order_size(raw: &str) -> Result<OrderSize, String> {
+    match raw.strip_prefix('$') {
+        Some(amount) => {
+            let amount = parse_decimal_arg(amount, "amount").map_err(|e| e.to_string())?;
+            if amount <= Decimal::ZERO {
+                return Err("Amount must be positive".to_string());
+            }
+            Ok(OrderSize::Notional(amount))
+        }
+        None => {
+            let quantity = parse_decimal_arg(raw, "quantity").map_err(|e| e.to_string())?;
+            if quantity <= Decimal::ZERO {
+                return Err("Quantity must be positive".to_string());
+            }
+            Ok(OrderSize::Shares(quantity))
+        }
+    }
+}
+
+/// Pulls `--tag <t>` out of the arguments, returning the rest and the parsed tag
+fn take_tag<'a>(args: &[&'a str]) -> Result<(Vec<&'a str>, Option<String>), String> {
+    let mut rest = Vec::new();
+    let mut tag = None;
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        if arg.eq_ignore_ascii_case("--tag") {
+            let Some(raw) = iter.next() else {
+                return Err("--tag needs a value, e.g. --tag swing".to_string());
+            };
+            if tag.is_some() {
+                return Err("--tag is given twice".to_string());
+            }
+            tag = Some(Orders::parse_tag(raw)?);
+        } else {
+            rest.push(*arg);
+        }
+    }
+    Ok((rest, tag))
+}
+
 /// SECTION: Account Commands
 
-/// Adds funds to the account
-/// Usage: fund <amount>
+/// Adds funds to the account, in the base currency unless another is named
+/// Usage: fund <amount> [currency]
 async fn handle_fund(
     state: &Arc<Mutex<AppState>>,
     db: &DatabaseConnection,
     args: &[&str],
 ) -> String {
-    if args.is_empty() {
-        return "Usage: fund <amount>".to_string();
+    if args.is_empty() || args.len() > 2 {
+        return "Usage: fund <amount> [currency]".to_string();
     }
 
-    let amount: Decimal = match args[0].parse() {
+    let amount = match parse_decimal_arg(args[0], "amount") {
         Ok(v) => v,
-        Err(_) => return "Invalid amount".to_string(),
+        Err(e) => return e.to_string(),
     };
 
     if amount <= Decimal::ZERO {
         return "Amount must be positive".to_string();
     }
 
-    Finance::fund(state, amount).await;
+    let currency = match parse_currency_arg(args.get(1)) {
+        Ok(currency) => currency,
+        Err(e) => return e,
+    };
+
+    // Money in another currency goes in the ledger, and against the cap, at its base value
+    let value = match base_value(state, &currency, amount).await {
+        Ok(value) => value,
+        Err(e) => return e,
+    };
+
+    {
+        let state_guard = lock_state(state);
+        if let (Some(cap), Some(headroom)) = (
+            state_guard.get_deposit_cap(),
+            state_guard.deposit_headroom(),
+        ) && value > headroom
+        {
+            let money = state_guard.get_money_format();
+            return format!(
+                "Deposit would exceed the {} deposit cap. You can add up to {} more",
+                money.money(cap),
+                money.money(headroom)
+            );
+        }
+    }
+
+    if currency == fx::BASE_CURRENCY {
+        Finance::fund(state, amount).await;
+    } else {
+        let mut state_guard = lock_state(state);
+        state_guard.deposit_currency(&currency, amount);
+        state_guard.record_cash_flow_in(CashFlowKind::Deposit, &currency, value);
+    }
+    let money = {
+        let mut state_guard = lock_state(state);
+        state_guard.push_undo(UndoAction::Fund {
+            amount,
+            currency: currency.clone(),
+            value,
+        });
+        state_guard.get_money_format()
+    };
     Storage::save_state(state, db).await;
 
-    format!("Added ${} to account", amount)
+    format!(
+        "Added {} to account{}",
+        fx::money_in(&money, &currency, amount),
+        base_value_note(&money, &currency, value)
+    )
 }
 
-/// Withdraws funds from the account
-/// Usage: withdraw <amount>
+/// Withdraws funds from the account, from the base currency unless another is named
+/// Usage: withdraw <amount> [currency]
 async fn handle_withdraw(
     state: &Arc<Mutex<AppState>>,
     db: &DatabaseConnection,
     args: &[&str],
 ) -> String {
-    if args.is_empty() {
-        return "Usage: withdraw <amount>".to_string();
+    if args.is_empty() || args.len() > 2 {
+        return "Usage: withdraw <amount> [currency]".to_string();
     }
 
-    let amount: Decimal = match args[0].parse() {
+    let amount = match parse_decimal_arg(args[0], "amount") {
         Ok(v) => v,
-        Err(_) => return "Invalid amount".to_string(),
+        Err(e) => return e.to_string(),
+    };
+
+    if amount <= Decimal::ZERO {
+        return "Amount must be positive".to_string();
+    }
+
+    let currency = match parse_currency_arg(args.get(1)) {
+        Ok(currency) => currency,
+        Err(e) => return e,
     };
 
-    let balance = {
-        let state_guard = state.lock().unwrap();
-        state_guard.check_balance()
+    let (balance, money) = {
+        let state_guard = lock_state(state);
+        (
+            state_guard.currency_balance(&currency),
+            state_guard.get_money_format(),
+        )
     };
 
     if amount > balance {
-        return format!("Insufficient balance. Current: ${}", balance);
+        return format!(
+            "Insufficient balance. Current: {}",
+            fx::money_in(&money, &currency, balance)
+        );
     }
 
-    Finance::withdraw(state, amount).await;
-    Storage::save_state(state, db).await;
-
-    format!("Withdrew ${} from account", amount)
-}
+    let value = match base_value(state, &currency, amount).await {
+        Ok(value) => value,
+        Err(e) => return e,
+    };
 
-/// Displays account summary
-/// Usage: display or d
-async fn handle_summary(state: &Arc<Mutex<AppState>>) -> String {
-    let state_guard = state.lock().unwrap();
-    let balance = state_guard.check_balance();
-    let watchlist = state_guard.get_watchlist();
-    let holdings_count = state_guard.get_holdings_map().len();
+    if currency == fx::BASE_CURRENCY {
+        Finance::withdraw(state, amount).await;
+    } else {
+        let mut state_guard = lock_state(state);
+        state_guard.withdraw_currency(&currency, amount);
+        state_guard.record_cash_flow_in(CashFlowKind::Withdrawal, &currency, value);
+    }
+    lock_state(state).push_undo(UndoAction::Withdraw {
+        amount,
+        currency: currency.clone(),
+        value,
+    });
+    Storage::save_state(state, db).await;
 
     format!(
-        "Cash balance: ${}\nWatchlist: {} symbols\nHoldings: {} positions",
-        balance,
-        watchlist.len(),
-        holdings_count
+        "Withdrew {} from account{}",
+        fx::money_in(&money, &currency, amount),
+        base_value_note(&money, &currency, value)
     )
 }
 
-/// SECTION: Price and Watchlist Commands
-///
-/// Gets current price for a symbol
-/// Usage: price <symbol>
-async fn handle_price(args: &[&str]) -> String {
-    if args.is_empty() {
-        return "Usage: price <symbol>".to_string();
+// Base currency value of `amount` in `currency` at the current rate, as the ledger
+// records it; Err for a currency without a quote, which can't be valued
+async fn base_value(
+    state: &Arc<Mutex<AppState>>,
+    currency: &str,
+    amount: Decimal,
+) -> Result<Decimal, String> {
+    let provider = lock_state(state).provider();
+    match fx::rate_to_base(provider.as_ref(), currency).await {
+        Some(rate) => Ok(format::round_money(amount * rate)),
+        None => Err(format!(
+            "No exchange rate for {} to {}",
+            currency,
+            fx::BASE_CURRENCY
+        )),
     }
+}
 
-    let symbol = args[0].to_uppercase();
-    let price = FinanceProvider::curr_price(&symbol, false).await;
-
-    if price == Decimal::ZERO {
-        format!("Could not fetch price for {}", symbol)
+// e.g. " (worth $108.00)" after a foreign amount, nothing for the base currency
+fn base_value_note(money: &MoneyFormat, currency: &str, value: Decimal) -> String {
+    if currency == fx::BASE_CURRENCY {
+        String::new()
     } else {
-        format!("{}: ${:.2}", symbol, price)
+        format!(" (worth {})", money.money(value))
     }
 }
 
-/// Adds a symbol to the watchlist
-/// Usage: addwatch <symbol>
-async fn handle_add_watch(
+// Optional currency code argument, the base currency when left out
+fn parse_currency_arg(arg: Option<&&str>) -> Result<String, String> {
+    match arg {
+        None => Ok(fx::BASE_CURRENCY.to_string()),
+        Some(raw) => fx::parse_currency(raw)
+            .ok_or_else(|| format!("Invalid currency: '{}'. Use a code like EUR", raw)),
+    }
+}
+
+/// Caps the net amount that can be deposited, so returns come from trading rather than top-ups
+/// Usage: cap [<amount> | off]
+async fn handle_cap(
     state: &Arc<Mutex<AppState>>,
     db: &DatabaseConnection,
     args: &[&str],
 ) -> String {
-    if args.is_empty() {
-        return "Usage: addwatch <symbol>".to_string();
-    }
-
-    let symbol = args[0].to_uppercase();
-    let mut action_result = false;
-    {
-        let mut state_guard = state.lock().unwrap();
-        action_result = state_guard.add_to_watchlist(symbol.clone());
-    }
-    if action_result {
-        Storage::save_state(state, db).await;
-        return format!("Added {} to watchlist", symbol);
+    match args {
+        [] => {
+            let state_guard = lock_state(state);
+            let money = state_guard.get_money_format();
+            match (
+                state_guard.get_deposit_cap(),
+                state_guard.deposit_headroom(),
+            ) {
+                (Some(cap), Some(headroom)) => format!(
+                    "Deposit cap: {}, {} left to deposit",
+                    money.money(cap),
+                    money.money(headroom)
+                ),
+                _ => "No deposit cap. Usage: cap <amount> | cap off".to_string(),
+            }
+        }
+        [value] if value.eq_ignore_ascii_case("off") => {
+            lock_state(state).set_deposit_cap(None);
+            Storage::save_state(state, db).await;
+            "Deposit cap off".to_string()
+        }
+        [raw_cap] => {
+            let cap = match parse_decimal_arg(raw_cap, "cap") {
+                Ok(v) => v,
+                Err(e) => return e.to_string(),
+            };
+            if cap <= Decimal::ZERO {
+                return "Cap must be positive".to_string();
+            }
+            let (headroom, money) = {
+                let mut state_guard = lock_state(state);
+                state_guard.set_deposit_cap(Some(cap));
+                (
+                    state_guard.deposit_headroom().unwrap_or_default(),
+                    state_guard.get_money_format(),
+                )
+            };
+            Storage::save_state(state, db).await;
+            format!(
+                "Deposit cap set to {}: {} left to deposit",
+                money.money(cap),
+                money.money(headroom)
+            )
+        }
+        _ => "Usage: cap [<amount> | off]".to_string(),
     }
-    format!("Error adding {} to watchlist", symbol)
 }
 
-/// Removes a symbol from the watchlist
-/// Usage: unwatch <symbol>
-async fn handle_remove_watch(
+/// Lists deposits and withdrawals with a running net total, or moves the ledger to/from CSV
+/// Usage: cashflow [export <path> | import <path>]
+async fn handle_cash_flow(
     state: &Arc<Mutex<AppState>>,
     db: &DatabaseConnection,
     args: &[&str],
 ) -> String {
-    if args.is_empty() {
-        return "Usage: unwatch <symbol>".to_string();
+    const USAGE: &str = "Usage: cashflow [export <path> | import <path>]";
+    match args.split_first() {
+        None => {}
+        Some((action, path)) if !path.is_empty() => {
+            let path = path.join(" ");
+            let path = path.trim().trim_matches('"');
+            if action.eq_ignore_ascii_case("export") {
+                let flows = lock_state(state).get_cash_flows();
+                return match std::fs::write(path, cashflow::to_csv(&flows)) {
+                    Ok(()) => format!("Exported {} cash flows to {}", flows.len(), path),
+                    Err(e) => format!("Failed to write {}: {}", path, e),
+                };
+            }
+            if action.eq_ignore_ascii_case("import") {
+                return match import::import_cash_flows_from_csv(state, path) {
+                    Ok(report) => {
+                        Storage::save_state(state, db).await;
+                        report
+                    }
+                    Err(err) => err,
+                };
+            }
+            return USAGE.to_string();
+        }
+        Some(_) => return USAGE.to_string(),
     }
 
-    let symbol = args[0].to_uppercase();
-    let mut action_result = false;
-    {
-        let mut state_guard = state.lock().unwrap();
-        action_result = state_guard.remove_from_watchlist(symbol.clone());
+    let (mut flows, money, timezone) = {
+        let state_guard = lock_state(state);
+        (
+            state_guard.get_cash_flows(),
+            state_guard.get_money_format(),
+            state_guard.get_display_timezone(),
+        )
+    };
+    if flows.is_empty() {
+        return "No deposits or withdrawals yet".to_string();
     }
-    if action_result {
-        Storage::save_state(state, db).await;
-        return format!("Removed {} from watchlist", symbol);
+    flows.sort_by_key(|flow| flow.timestamp);
+
+    let mut lines = vec![
+        "Cash Flows:".to_string(),
+        format!(
+            "  {:<16}  {:<10} {:>14} {:>14}",
+            "Time", "Type", "Amount", "Net Deposited"
+        ),
+    ];
+    let mut running = Decimal::ZERO;
+    for flow in &flows {
+        running += flow.signed_amount();
+        lines.push(format!(
+            "  {:<16}  {:<10} {:>14} {:>14}",
+            timezone.date_time(flow.timestamp),
+            flow.kind.name(),
+            money.money(flow.signed_amount()),
+            money.money(running)
+        ));
     }
-    format!("Error removing {} from watchlist", symbol)
+    let deposits = flows
+        .iter()
+        .filter(|flow| flow.kind == CashFlowKind::Deposit)
+        .count();
+    lines.push(format!(
+        "Net deposited: {} ({} deposits, {} withdrawals)",
+        money.money(cashflow::net_deposited(&flows)),
+        deposits,
+        flows.len() - deposits
+    ));
+    lines.join("\n")
 }
 
-/// SECTION: Trading Commands
+/// Reports simple, time-weighted, and money-weighted returns from the daily equity snapshots
+/// Usage: performance [--days N]
+async fn handle_performance(state: &Arc<Mutex<AppState>>, args: &[&str]) -> String {
+    const USAGE: &str = "Usage: performance [--days N]";
+    let days = match args {
+        [] => None,
+        ["--days", raw] => match raw.parse::<i64>() {
+            Ok(v) if (1..=36_500).contains(&v) => Some(v),
+            _ => return format!("Invalid days: '{}' (expected 1-36500)", raw),
+        },
+        _ => return USAGE.to_string(),
+    };
 
-/// Executes a market buy order
-/// Usage: buy <symbol> <quantity>
-async fn handle_buy(
-    state: &Arc<Mutex<AppState>>,
-    db: &DatabaseConnection,
-    args: &[&str],
+    // Value the account now so the window always ends at the current price
+    let (symbols, currencies, provider) = {
+        let state_guard = lock_state(state);
+        let symbols: Vec<_> = state_guard.get_holdings_map().into_keys().collect();
+        (
+            symbols,
+            state_guard.valued_currencies(),
+            state_guard.provider(),
+        )
+    };
+    let mut prices = HashMap::new();
+    for symbol in symbols {
+        let price = provider.curr_price(&symbol).await;
+        prices.insert(symbol, price);
+    }
+    let rates = fx::rates_to_base(provider.as_ref(), &currencies).await;
+    let (report, money, timezone) = {
+        let mut state_guard = lock_state(state);
+        if let Some(value) = state_guard.portfolio_value(&prices, &rates) {
+            state_guard.record_equity_snapshot(value);
+        }
+        let since = days.map(|days| state_guard.now_timestamp() - days * 86_400);
+        (
+            performance::performance(
+                &state_guard.get_equity_snapshots(),
+                &state_guard.get_cash_flows(),
+                since,
+            ),
+            state_guard.get_money_format(),
+            state_guard.get_display_timezone(),
+        )
+    };
+    let Some(report) = report else {
+        return "Not enough history yet: performance needs account values from at least two \
+                different days. Values are recorded once a day while Naviin runs"
+            .to_string();
+    };
+
+    let pct = |rate: Option<Decimal>| {
+        rate.map(|rate| format::format_pct(rate * Decimal::from(100)))
+            .unwrap_or_else(|| "N/A".to_string())
+    };
+    let date = |timestamp: i64| {
+        timezone
+            .format(timestamp, "%Y-%m-%d")
+            .unwrap_or_else(|| "Unknown".to_string())
+    };
+    let row = |label: &str, period: Option<Decimal>, annual: Option<Decimal>| {
+        format!("  {:<16} {:>10}  {:>10}", label, pct(period), pct(annual))
+    };
+    [
+        format!(
+            "Performance {} to {} ({} days):",
+            date(report.start.timestamp),
+            date(report.end.timestamp),
+            format::format_fixed(report.days, 1)
+        ),
+        format!(
+            "  {:<16} {:>10}",
+            "Start value",
+            money.money(report.start.value)
+        ),
+        format!(
+            "  {:<16} {:>10}",
+            "End value",
+            money.money(report.end.value)
+        ),
+        format!(
+            "  {:<16} {:>10}",
+            "Net deposits",
+            money.money(report.net_flows)
+        ),
+        format!("  {:<16} {:>10}  {:>10}", "Return", "Period", "Annualized"),
+        row(
+            "Simple",
+            report.simple_return,
+            report.simple_return.and_then(|rate| report.annualize(rate)),
+        ),
+        row(
+            "Time-weighted",
+            report.time_weighted,
+            report.time_weighted.and_then(|rate| report.annualize(rate)),
+        ),
+        row(
+            "Money-weighted",
+            report.money_weighted_period(),
+            report.money_weighted_annual(),
+        ),
+        "Assumptions: account values are daily snapshots (cash plus holdings at market); \
+         deposits and withdrawals count from the snapshot before them; simple return is gain over \
+         starting value plus deposits; windows under a year are annualized by compounding, \
+         so short windows exaggerate"
+            .to_string(),
+    ]
+    .join("\n")
+}
+
+// Each cash bucket with its base currency value, then the total converted at current rates
+async fn currency_cash_lines(
+    provider: &dyn FinanceProvider::PriceProvider,
+    money: &MoneyFormat,
+    balances: &[(String, Decimal)],
 ) -> String {
-    if args.len() < 2 {
-        return "Usage: buy <symbol> <quantity>".to_string();
+    let mut table = TextTable::new(&[Align::Left, Align::Right, Align::Right]);
+    let mut total = Some(Decimal::ZERO);
+    for (currency, balance) in balances {
+        let rate = fx::rate_to_base(provider, currency).await;
+        total = total.zip(rate).map(|(sum, rate)| sum + balance * rate);
+        let converted = match rate {
+            Some(_) if currency == fx::BASE_CURRENCY => String::new(),
+            Some(rate) => format!("= {}", money.money(balance * rate)),
+            None => "no rate".to_string(),
+        };
+        table.push_row(vec![
+            currency.clone(),
+            fx::money_in(money, currency, *balance),
+            converted,
+        ]);
+    }
+    let mut lines = "\nCash by currency:".to_string();
+    for line in table.lines() {
+        lines.push_str(&format!("\n  {}", line));
+    }
+    match total {
+        Some(total) => lines.push_str(&format!(
+            "\nTotal cash: {} ({})",
+            money.money(total),
+            fx::BASE_CURRENCY
+        )),
+        None => lines.push_str("\nTotal cash: N/A, a currency has no exchange rate"),
     }
+    lines
+}
 
-    let symbol = args[0].to_uppercase();
-    let quantity: Decimal = match args[1].parse() {
-        Ok(v) => v,
-        Err(_) => return "Invalid quantity".to_string(),
+/// Displays account summary
+/// Usage: display or d
+async fn handle_summary(state: &Arc<Mutex<AppState>>, args: &[&str]) -> String {
+    match args {
+        [] => {}
+        ["--json"] => return to_json(&summary_response(state).await),
+        _ => return "Usage: summary [--json]".to_string(),
+    }
+    let (
+        balance,
+        buying_power,
+        leverage,
+        loan,
+        watchlist,
+        holdings,
+        market_status,
+        money,
+        provider,
+        currency_balances,
+    ) = {
+        let state_guard = lock_state(state);
+        (
+            state_guard.check_balance(),
+            state_guard.buying_power(),
+            state_guard.get_leverage(),
+            state_guard.get_margin_loan(),
+            state_guard.get_watchlist(),
+            state_guard.get_holdings_map(),
+            state_guard
+                .market_calendar()
+                .status_at(state_guard.clock().now()),
+            state_guard.get_money_format(),
+            state_guard.provider(),
+            state_guard.currency_balances(),
+        )
     };
 
-    if quantity <= Decimal::ZERO {
-        return "Quantity must be positive".to_string();
+    let mut summary = format!(
+        "Cash balance: {}\nBuying power: {}",
+        money.money(balance),
+        money.money(buying_power)
+    );
+    if leverage > margin::NO_LEVERAGE || loan > Decimal::ZERO {
+        summary.push_str(&format!(
+            " ({}x leverage)\nMargin loan: {}",
+            leverage.normalize(),
+            money.money(loan)
+        ));
+    }
+    if currency_balances.len() > 1 {
+        summary.push_str(&currency_cash_lines(provider.as_ref(), &money, &currency_balances).await);
     }
+    let positions_count = match holdings.len() {
+        0 => "no positions".to_string(),
+        count => format!("{} positions", count),
+    };
+    summary.push_str(&format!(
+        "\nWatchlist: {} symbols\nHoldings: {}\n{}",
+        watchlist.len(),
+        positions_count,
+        market_status_line(&market_status)
+    ));
 
-    // Get current price
-    let price = FinanceProvider::curr_price(&symbol, false).await;
-    if price == Decimal::ZERO {
-        return format!("Could not get price for {}", symbol);
+    // P&L per position, matching the holdings table; the output pane colors rows by arrow
+    let mut symbols: Vec<_> = holdings.keys().cloned().collect();
+    symbols.sort();
+    let mut positions = TextTable::new(&[
+        Align::Left,
+        Align::Left,
+        Align::Left,
+        Align::Right,
+        Align::Right,
+    ]);
+    for symbol in symbols {
+        let holding = &holdings[&symbol];
+        let price = provider.curr_price(&symbol).await;
+        let row = match holding.pnl_pct(price) {
+            Some(pct) => {
+                let pnl = holding.pnl(price);
+                let arrow = if pnl < Decimal::ZERO {
+                    DOWN_MARKER
+                } else {
+                    UP_MARKER
+                };
+                vec![
+                    arrow.to_string(),
+                    symbol,
+                    "P&L".to_string(),
+                    money.money(pnl),
+                    format!("({})", format::format_pct(pct)),
+                ]
+            }
+            None => vec![String::new(), symbol, "P&L".to_string(), "N/A".to_string()],
+        };
+        positions.push_row(row);
     }
+    let position_lines = positions.lines();
+    if !position_lines.is_empty() {
+        summary.push_str("\nPositions:");
+    }
+    for line in position_lines {
+        summary.push_str(&format!("\n  {}", line));
+    }
+    if holdings
+        .values()
+        .any(|holding| holding.get_asset_type().trades_24_7())
+    {
+        summary.push_str("\nCrypto: trades 24/7");
+    }
+    // Left out when a holding can't be priced or converted, rather than show a false call
+    if let Some(status) = margin_status_at_market(state).await {
+        let label = if status.is_margin_call() {
+            "MARGIN CALL"
+        } else {
+            "Margin"
+        };
+        summary.push_str(&format!(
+            "\n{}: equity {} is {}% of holdings (maintenance {}%)",
+            label,
+            money.money(status.equity),
+            format::format_fixed(status.equity_pct, 1),
+            margin::MAINTENANCE_MARGIN_PCT
+        ));
+    }
+    summary
+}
+
+/// `summary --json` payload
+#[derive(Serialize)]
+struct SummaryResponse {
+    cash: Decimal,
+    buying_power: Decimal,
+    leverage: Decimal,
+    margin_loan: Decimal,
+    /// Every cash bucket by currency, the base currency included
+    cash_by_currency: BTreeMap<String, Decimal>,
+    watchlist_count: usize,
+    market_open: bool,
+    market_status: String,
+    positions: Vec<PositionResponse>,
+    /// Base currency value of the positions that could be priced
+    holdings_value: Decimal,
+    /// Cash net of any margin loan, other currencies converted at current rates,
+    /// plus the holdings value; null when a currency has no exchange rate
+    total_value: Option<Decimal>,
+}
 
-    let total_cost = price * quantity;
+/// One holding in `summary --json`; price fields are null when it can't be priced
+#[derive(Serialize)]
+struct PositionResponse {
+    symbol: String,
+    quantity: Decimal,
+    avg_cost: Decimal,
+    price: Option<Decimal>,
+    market_value: Option<Decimal>,
+    pnl: Option<Decimal>,
+    pnl_pct: Option<Decimal>,
+}
 
-    // Check balance
-    let balance = {
-        let state_guard = state.lock().unwrap();
-        state_guard.check_balance()
+async fn summary_response(state: &Arc<Mutex<AppState>>) -> SummaryResponse {
+    let (mut response, holdings, currencies, provider) = {
+        let state_guard = lock_state(state);
+        let status = state_guard
+            .market_calendar()
+            .status_at(state_guard.clock().now());
+        let response = SummaryResponse {
+            cash: state_guard.check_balance(),
+            buying_power: state_guard.buying_power(),
+            leverage: state_guard.get_leverage(),
+            margin_loan: state_guard.get_margin_loan(),
+            cash_by_currency: state_guard.currency_balances().into_iter().collect(),
+            watchlist_count: state_guard.get_watchlist().len(),
+            market_open: status.is_open(),
+            market_status: status.to_string(),
+            positions: Vec::new(),
+            holdings_value: Decimal::ZERO,
+            total_value: None,
+        };
+        (
+            response,
+            state_guard.get_holdings_map(),
+            state_guard.valued_currencies(),
+            state_guard.provider(),
+        )
     };
+    let rates = fx::rates_to_base(provider.as_ref(), &currencies).await;
 
-    if total_cost > balance {
-        return format!(
-            "Insufficient funds. Need ${:.2}, have ${:.2}",
-            total_cost, balance
-        );
+    let prices = FinanceProvider::current_prices(provider, holdings.keys().cloned()).await;
+    let mut symbols: Vec<_> = holdings.keys().cloned().collect();
+    symbols.sort();
+    let mut unconverted = false;
+    for symbol in symbols {
+        let holding = &holdings[&symbol];
+        let price = prices.get(&symbol).copied();
+        let market_value = price.map(|price| price * holding.get_qty());
+        if let Some(value) = market_value {
+            match fx::rate_in(&rates, &fx::trading_currency(&symbol)) {
+                Some(rate) => response.holdings_value += value * rate,
+                None => unconverted = true,
+            }
+        }
+        response.positions.push(PositionResponse {
+            quantity: holding.get_qty(),
+            avg_cost: holding.get_avg_price(),
+            price,
+            market_value,
+            pnl: price.map(|price| holding.pnl(price)),
+            pnl_pct: price.and_then(|price| holding.pnl_pct(price)),
+            symbol,
+        });
     }
+    response.total_value = lock_state(state)
+        .net_cash_value(&rates)
+        .filter(|_| !unconverted)
+        .map(|cash| cash + response.holdings_value);
+    response
+}
 
-    // Execute buy
-    Finance::create_buy_with_params(state, symbol.clone(), quantity, price).await;
-    Storage::save_state(state, db).await;
-
-    format!(
-        "Bought {} shares of {} at ${:.2} (total: ${:.2})",
-        quantity, symbol, price, total_cost
-    )
+// One-line US market status for command output
+fn market_status_line(status: &MarketStatus) -> String {
+    match status {
+        MarketStatus::Open => format!("US market: OPEN until {}", market::session_close_label()),
+        _ => format!("US market: {}", status),
+    }
 }
 
-/// Executes a market sell order
-/// Usage: sell <symbol> <quantity>
-async fn handle_sell(
+/// Most characters accepted for a currency symbol, e.g. "CHF " or "R$"
+const MAX_CURRENCY_SYMBOL_LEN: usize = 4;
+
+/// Sets the currency symbol and separator style used to display money
+/// Usage: currency <symbol> [us|eu|fr|ch]
+async fn handle_currency(
     state: &Arc<Mutex<AppState>>,
     db: &DatabaseConnection,
     args: &[&str],
 ) -> String {
-    if args.len() < 2 {
-        return "Usage: sell <symbol> <quantity>".to_string();
+    let current = lock_state(state).get_money_format();
+    let styles = NumberStyle::ALL.map(|style| style.name()).join("|");
+    if args.is_empty() {
+        return format!(
+            "Currency: {} ({}, e.g. {}). Usage: currency <symbol> [{}]",
+            current.symbol(),
+            current.style(),
+            current.money(Decimal::new(123456, 2)),
+            styles
+        );
     }
 
-    let symbol = args[0].to_uppercase();
-    let quantity: Decimal = match args[1].parse() {
-        Ok(v) => v,
-        Err(_) => return "Invalid quantity".to_string(),
+    let symbol = args[0];
+    if symbol.chars().count() > MAX_CURRENCY_SYMBOL_LEN
+        || symbol.chars().any(|c| c.is_ascii_digit() || c == '-')
+    {
+        return format!(
+            "Invalid currency symbol: '{}'. Use up to {} characters, no digits",
+            symbol, MAX_CURRENCY_SYMBOL_LEN
+        );
+    }
+    let style = match args.get(1) {
+        Some(raw) => match NumberStyle::parse(raw) {
+            Some(style) => style,
+            None => return format!("Unknown number style '{}'. Use {}", raw, styles),
+        },
+        None => current.style(),
     };
 
-    if quantity <= Decimal::ZERO {
-        return "Quantity must be positive".to_string();
-    }
+    let money = MoneyFormat::new(symbol, style);
+    let example = money.money(Decimal::new(123456, 2));
+    lock_state(state).set_money_format(money);
+    Storage::save_state(state, db).await;
+
+    format!("Amounts will be shown like {}", example)
+}
 
-    // Check holdings
-    let available_qty = {
-        let state_guard = state.lock().unwrap();
-        state_guard.get_ticker_holdings_qty(&symbol)
+/// Shows, sets, or turns off the URL notified when a background order fills
+/// Usage: webhook [<url>|off]
+async fn handle_webhook(
+    state: &Arc<Mutex<AppState>>,
+    db: &DatabaseConnection,
+    args: &[&str],
+) -> String {
+    let Some(arg) = args.first() else {
+        let state_guard = lock_state(state);
+        return match (
+            state_guard.get_webhook_url(),
+            state_guard.get_webhook_error(),
+        ) {
+            (None, _) => "No webhook configured. Usage: webhook <url>|off".to_string(),
+            (Some(url), None) => format!("Order fills are posted to {}", url),
+            (Some(url), Some(error)) => format!(
+                "Order fills are posted to {}\nLast delivery failed: {}",
+                url, error
+            ),
+        };
     };
 
-    if quantity > available_qty {
+    if arg.eq_ignore_ascii_case("off") {
+        lock_state(state).set_webhook_url(None);
+        Storage::save_state(state, db).await;
+        return "Webhook disabled".to_string();
+    }
+    if !webhook::is_valid_url(arg) {
         return format!(
-            "Insufficient holdings. Have {:.2} shares of {}",
-            available_qty, symbol
+            "Invalid webhook URL: '{}'. Use an http:// or https:// address",
+            arg
         );
     }
 
-    // Get current price
-    let price = FinanceProvider::curr_price(&symbol, false).await;
-    if price == Decimal::ZERO {
-        return format!("Could not get price for {}", symbol);
+    lock_state(state).set_webhook_url(Some(arg.to_string()));
+    Storage::save_state(state, db).await;
+    format!("Order fills will be posted to {}", arg)
+}
+
+/// Lists upcoming market holidays, or adds/removes a custom closure
+/// Usage: holidays [add|remove <YYYY-MM-DD>]
+async fn handle_holidays(
+    state: &Arc<Mutex<AppState>>,
+    db: &DatabaseConnection,
+    args: &[&str],
+) -> String {
+    if args.is_empty() {
+        let (calendar, now) = {
+            let state_guard = lock_state(state);
+            (state_guard.market_calendar(), state_guard.clock().now())
+        };
+        let today = market::new_york_time(now).date();
+        let lines: Vec<String> = calendar
+            .upcoming_holidays(today, 8)
+            .into_iter()
+            .map(|(date, name)| format!("{}  {}", date.format("%a %Y-%m-%d"), name))
+            .collect();
+        return format!("Upcoming market holidays:\n{}", lines.join("\n"));
     }
 
-    let total_value = price * quantity;
+    if args.len() < 2 {
+        return "Usage: holidays [add|remove <YYYY-MM-DD>]".to_string();
+    }
+    let date = match chrono::NaiveDate::parse_from_str(args[1], "%Y-%m-%d") {
+        Ok(date) => date,
+        Err(_) => return format!("Invalid date: '{}'. Use YYYY-MM-DD", args[1]),
+    };
 
-    // Execute sell
-    Finance::create_sell_with_params(state, symbol.clone(), quantity, price).await;
+    let message = {
+        let mut state_guard = lock_state(state);
+        match args[0].to_lowercase().as_str() {
+            "add" if state_guard.add_market_holiday(date) => {
+                format!("Market marked closed on {}", date)
+            }
+            "add" => return format!("{} is already a custom holiday", date),
+            "remove" if state_guard.remove_market_holiday(date) => {
+                format!("Removed custom holiday {}", date)
+            }
+            "remove" => return format!("{} is not a custom holiday", date),
+            _ => return "Usage: holidays [add|remove <YYYY-MM-DD>]".to_string(),
+        }
+    };
     Storage::save_state(state, db).await;
-
-    format!(
-        "Sold {} shares of {} at ${:.2} (total: ${:.2})",
-        quantity, symbol, price, total_value
-    )
+    message
 }
 
-/// Creates a buy limit order
-/// Usage: buylimit <symbol> <quantity> <price>
-async fn handle_buy_limit(
+/// Sets the annual yield paid on idle cash
+/// Usage: cashyield <apy>
+async fn handle_cash_yield(
     state: &Arc<Mutex<AppState>>,
     db: &DatabaseConnection,
     args: &[&str],
 ) -> String {
-    if args.len() < 3 {
-        return "Usage: buylimit <symbol> <quantity> <price>".to_string();
+    if args.is_empty() {
+        let current = lock_state(state).get_cash_apy();
+        return format!("Cash yield: {}% APY. Usage: cashyield <apy>", current);
     }
 
-    let symbol = args[0].to_uppercase();
-    let quantity: Decimal = match args[1].parse() {
-        Ok(v) => v,
-        Err(_) => return "Invalid quantity".to_string(),
-    };
-    let price: Decimal = match args[2].parse() {
+    let apy = match parse_decimal_arg(args[0], "apy") {
         Ok(v) => v,
-        Err(_) => return "Invalid price".to_string(),
+        Err(e) => return e.to_string(),
     };
 
-    if quantity <= Decimal::ZERO || price <= Decimal::ZERO {
-        return "Quantity and price must be positive".to_string();
+    if apy < Decimal::ZERO || apy > Decimal::from(100) {
+        return "APY must be between 0 and 100".to_string();
     }
 
-    // Create order
-    let order = Orders::OpenOrder::new(
-        symbol.clone(),
-        quantity,
-        price,
-        Orders::OrderType::BuyLimit,
-        Orders::Side::Buy,
-    );
-
     {
-        let mut state_guard = state.lock().unwrap();
-        match state_guard.add_open_order(order) {
-            Ok(msg) => msg,
-            Err(e) => return e,
-        };
+        let mut state_guard = lock_state(state);
+        // Pay out what was earned at the old rate before switching
+        let now = state_guard.now_timestamp();
+        state_guard.accrue_cash_yield(now);
+        state_guard.set_cash_apy(apy, now);
     }
     Storage::save_state(state, db).await;
 
-    format!(
-        "Buy limit order created: {} shares of {} at ${:.2}",
-        quantity, symbol, price
-    )
+    if apy == Decimal::ZERO {
+        "Cash yield disabled".to_string()
+    } else {
+        format!("Idle cash will earn {}% APY, credited daily", apy)
+    }
 }
 
-/// Creates a stop loss order
-/// Usage: stoploss <symbol> <quantity> <price>
-async fn handle_stop_loss(
+/// Sets the margin leverage factor or the interest rate on borrowed cash
+/// Usage: leverage [<factor>|rate <apr>]
+async fn handle_leverage(
     state: &Arc<Mutex<AppState>>,
     db: &DatabaseConnection,
     args: &[&str],
 ) -> String {
-    if args.len() < 3 {
-        return "Usage: stoploss <symbol> <quantity> <price>".to_string();
+    match args {
+        [] => {
+            let state_guard = lock_state(state);
+            let money = state_guard.get_money_format();
+            format!(
+                "Leverage: {}x, buying power {}, margin loan {} at {}% APR. Usage: leverage <factor> | leverage rate <apr>",
+                state_guard.get_leverage().normalize(),
+                money.money(state_guard.buying_power()),
+                money.money(state_guard.get_margin_loan()),
+                state_guard.get_margin_rate().normalize()
+            )
+        }
+        [keyword, raw_rate] if keyword.eq_ignore_ascii_case("rate") => {
+            let rate = match parse_decimal_arg(raw_rate, "rate") {
+                Ok(v) => v,
+                Err(e) => return e.to_string(),
+            };
+            if rate < Decimal::ZERO || rate > Decimal::from(100) {
+                return "Rate must be between 0 and 100".to_string();
+            }
+            {
+                let mut state_guard = lock_state(state);
+                // Charge what was owed at the old rate before switching
+                let now = state_guard.now_timestamp();
+                state_guard.accrue_margin_interest(now);
+                state_guard.set_margin_rate(rate);
+            }
+            Storage::save_state(state, db).await;
+            format!("Margin loans will accrue {}% APR, charged daily", rate)
+        }
+        [raw_factor] => {
+            let factor = match parse_decimal_arg(raw_factor.trim_end_matches('x'), "leverage") {
+                Ok(v) => v,
+                Err(e) => return e.to_string(),
+            };
+            if factor < margin::NO_LEVERAGE || factor > margin::MAX_LEVERAGE {
+                return format!(
+                    "Leverage must be between {} and {}",
+                    margin::NO_LEVERAGE,
+                    margin::MAX_LEVERAGE
+                );
+            }
+            let (buying_power, money) = {
+                let mut state_guard = lock_state(state);
+                state_guard.set_leverage(factor);
+                (state_guard.buying_power(), state_guard.get_money_format())
+            };
+            Storage::save_state(state, db).await;
+            if factor == margin::NO_LEVERAGE {
+                format!(
+                    "Leverage off: buying power is your cash, {}",
+                    money.money(buying_power)
+                )
+            } else {
+                format!(
+                    "Leverage set to {}x: buying power {}",
+                    factor.normalize(),
+                    money.money(buying_power)
+                )
+            }
+        }
+        _ => "Usage: leverage <factor> | leverage rate <apr>".to_string(),
     }
+}
 
-    let symbol = args[0].to_uppercase();
-    let quantity: Decimal = match args[1].parse() {
-        Ok(v) => v,
-        Err(_) => return "Invalid quantity".to_string(),
-    };
-    let price: Decimal = match args[2].parse() {
-        Ok(v) => v,
-        Err(_) => return "Invalid price".to_string(),
-    };
+/// SECTION: Price and Watchlist Commands
+///
+/// Symbol to follow for `watch <symbol>`, None for any other command
+/// The TUI checks this before dispatching so it can switch the output pane to the live quote
+pub fn watch_symbol(command: &str) -> Option<Finance::Symbol> {
+    let mut parts = command.split_whitespace();
+    if !parts.next()?.eq_ignore_ascii_case("watch") {
+        return None;
+    }
+    parts.next().map(|symbol| symbol.to_uppercase())
+}
 
-    if quantity <= Decimal::ZERO || price <= Decimal::ZERO {
-        return "Quantity and price must be positive".to_string();
+/// Confirms live-follow mode for a symbol; the TUI does the switching
+/// Usage: watch <symbol>
+fn handle_watch(state: &Arc<Mutex<AppState>>, args: &[&str]) -> String {
+    match args.first() {
+        Some(symbol) => format!("Watching {}. Press Esc to return", normalize(state, symbol)),
+        None => "Usage: watch <symbol>".to_string(),
     }
+}
 
-    // Check holdings
-    let available_qty = {
-        let state_guard = state.lock().unwrap();
-        state_guard.get_ticker_holdings_qty(&symbol)
+/// `price --json` payload; decimals are strings so no precision is lost
+#[derive(Serialize)]
+struct PriceResponse {
+    symbol: String,
+    price: Decimal,
+    asset_type: &'static str,
+    market_open: bool,
+    market_status: String,
+}
+
+/// JSON text for a `--json` response
+fn to_json<T: Serialize>(response: &T) -> String {
+    serde_json::to_string(response).unwrap_or_else(|e| json_error(&e.to_string()))
+}
+
+/// `{"error": ...}` returned by `--json` commands that can't answer
+fn json_error(message: &str) -> String {
+    serde_json::json!({ "error": message }).to_string()
+}
+
+/// Gets current price for a symbol
+/// Usage: price <symbol> [--json]
+async fn handle_price(state: &Arc<Mutex<AppState>>, args: &[&str]) -> String {
+    let (symbol, json) = match args {
+        [symbol] => (symbol, false),
+        [symbol, "--json"] | ["--json", symbol] => (symbol, true),
+        _ => return "Usage: price <symbol> [--json]".to_string(),
+    };
+
+    let symbol = normalize(state, symbol);
+    let (provider, calendar, money, now) = {
+        let state_guard = lock_state(state);
+        (
+            state_guard.provider(),
+            state_guard.market_calendar(),
+            state_guard.get_money_format(),
+            state_guard.clock().now(),
+        )
+    };
+    let price = provider.curr_price(&symbol).await;
+
+    if price == Decimal::ZERO {
+        let message = format!("Could not fetch price for {}", symbol);
+        return if json { json_error(&message) } else { message };
+    }
+
+    let asset_type = Finance::AssetType::for_symbol(&symbol);
+    let status = calendar.status_for(asset_type, now);
+    if json {
+        return to_json(&PriceResponse {
+            symbol,
+            price,
+            asset_type: match asset_type {
+                Finance::AssetType::Stock => "stock",
+                Finance::AssetType::Crypto => "crypto",
+            },
+            market_open: status.is_open(),
+            market_status: status.to_string(),
+        });
+    }
+    let quote = format!("{}: {}", symbol, money.money_price(price, asset_type));
+    match status {
+        MarketStatus::Open => quote,
+        // Outside the session Yahoo reports the last close
+        status @ MarketStatus::Closed(_) => format!("{} [{}] last close", quote, status),
+        status => format!("{} [{}]", quote, status),
+    }
+}
+
+/// Most symbols one `prices` call looks up
+pub const MAX_PRICES_SYMBOLS: usize = 50;
+
+/// Quotes several symbols at once with their previous close and day change
+/// Lookups run concurrently; symbols that can't be priced are listed after the table
+/// Usage: prices <sym1> <sym2> ...
+async fn handle_prices(state: &Arc<Mutex<AppState>>, args: &[&str]) -> String {
+    if args.is_empty() {
+        return "Usage: prices <sym1> <sym2> ...".to_string();
+    }
+    let mut symbols: Vec<Finance::Symbol> = Vec::new();
+    for arg in args {
+        let symbol = normalize(state, arg);
+        if !symbols.contains(&symbol) {
+            symbols.push(symbol);
+        }
+    }
+    if symbols.len() > MAX_PRICES_SYMBOLS {
+        return format!(
+            "Too many symbols: {} (limit {} per call)",
+            symbols.len(),
+            MAX_PRICES_SYMBOLS
+        );
+    }
+
+    let (provider, money) = {
+        let state_guard = lock_state(state);
+        (state_guard.provider(), state_guard.get_money_format())
+    };
+    let requested = symbols.len();
+    let quotes = refresh::fetch_quotes(provider, symbols).await;
+
+    let mut table = TextTable::new(&[
+        Align::Left,
+        Align::Left,
+        Align::Right,
+        Align::Right,
+        Align::Right,
+        Align::Right,
+    ])
+    .with_header(&["", "Symbol", "Last", "Prev close", "Change", "Change %"]);
+    let mut failed = Vec::new();
+    for (symbol, price, prev_close) in quotes {
+        if price <= Decimal::ZERO {
+            failed.push(symbol);
+            continue;
+        }
+        let asset_type = Finance::AssetType::for_symbol(&symbol);
+        let (arrow, prev, change, pct) = match Finance::day_change_pct(price, prev_close) {
+            Some(pct) => {
+                let change = price - prev_close;
+                let signed = if change > Decimal::ZERO {
+                    format!("+{}", money.money_price(change, asset_type))
+                } else {
+                    money.money_price(change, asset_type)
+                };
+                let arrow = if change < Decimal::ZERO {
+                    DOWN_MARKER
+                } else {
+                    UP_MARKER
+                };
+                (
+                    arrow,
+                    money.money_price(prev_close, asset_type),
+                    signed,
+                    format::format_pct(pct),
+                )
+            }
+            None => ("", "-".to_string(), "-".to_string(), "-".to_string()),
+        };
+        table.push_row(vec![
+            arrow.to_string(),
+            symbol,
+            money.money_price(price, asset_type),
+            prev,
+            change,
+            pct,
+        ]);
+    }
+
+    let mut lines = Vec::new();
+    if failed.len() == requested {
+        lines.push("Could not fetch a price for any symbol".to_string());
+    } else {
+        lines.push("Prices:".to_string());
+        lines.extend(table.lines().into_iter().map(|line| format!("  {}", line)));
+    }
+    if !failed.is_empty() {
+        lines.push(format!("No price for {}", failed.join(", ")));
+    }
+    lines.join("\n")
+}
+
+/// How long `addwatch` waits for the validating quote, and `search` for results
+const SYMBOL_CHECK_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// Most matches `search` lists
+pub const MAX_SEARCH_RESULTS: usize = 8;
+
+/// Looks up symbols by company name or partial ticker
+/// Usage: search <query>
+async fn handle_search(state: &Arc<Mutex<AppState>>, args: &[&str]) -> String {
+    let query = args.join(" ");
+    let query = query.trim();
+    if query.is_empty() {
+        return "Usage: search <company or partial symbol>".to_string();
+    }
+
+    let provider = lock_state(state).provider();
+    let matches = match tokio::time::timeout(SYMBOL_CHECK_TIMEOUT, provider.search(query)).await {
+        Ok(Ok(matches)) => matches,
+        Ok(Err(e)) => return e,
+        Err(_) => return format!("Search for \"{}\" timed out, try again", query),
+    };
+    if matches.is_empty() {
+        return format!("No symbols found for \"{}\"", query);
+    }
+
+    let mut lines = vec![format!("Symbols matching \"{}\":", query)];
+    for found in matches.iter().take(MAX_SEARCH_RESULTS) {
+        lines.push(format!(
+            "  {:<10} {:<32} {:<10} {}",
+            found.symbol,
+            found.name.as_deref().unwrap_or("-"),
+            found.exchange.as_deref().unwrap_or("-"),
+            found.kind
+        ));
+    }
+    if matches.len() > MAX_SEARCH_RESULTS {
+        lines.push(format!(
+            "… {} more, refine the query to narrow it down",
+            matches.len() - MAX_SEARCH_RESULTS
+        ));
+    }
+    lines.join("\n")
+}
+
+/// Adds a symbol to the watchlist after checking the provider can quote it
+/// Usage: addwatch <symbol> [--force]
+async fn handle_add_watch(
+    state: &Arc<Mutex<AppState>>,
+    db: &DatabaseConnection,
+    args: &[&str],
+) -> String {
+    const USAGE: &str = "Usage: addwatch <symbol> [--force]";
+    let (symbol, force) = match args {
+        [symbol] => (symbol, false),
+        [symbol, "--force"] | ["--force", symbol] => (symbol, true),
+        _ => return USAGE.to_string(),
+    };
+    let symbol = normalize(state, symbol);
+
+    let (already_watched, validated, provider) = {
+        let state_guard = lock_state(state);
+        (
+            state_guard.get_watchlist().contains(&symbol),
+            state_guard.is_symbol_validated(&symbol),
+            state_guard.provider(),
+        )
+    };
+    if already_watched {
+        return format!("Error adding {} to watchlist", symbol);
+    }
+
+    if !force && !validated {
+        match tokio::time::timeout(SYMBOL_CHECK_TIMEOUT, provider.curr_price(&symbol)).await {
+            Ok(price) if price > Decimal::ZERO => {
+                lock_state(state).mark_symbol_validated(symbol.clone());
+            }
+            Ok(_) => {
+                return format!(
+                    "Unknown symbol: {}. Use 'addwatch {} --force' to add it anyway",
+                    symbol, symbol
+                );
+            }
+            Err(_) => {
+                return format!(
+                    "Could not validate {}: quote lookup timed out. Use 'addwatch {} --force' \
+                     to add it anyway",
+                    symbol, symbol
+                );
+            }
+        }
+    }
+
+    let added = lock_state(state).add_to_watchlist(symbol.clone());
+    if added {
+        Storage::save_state(state, db).await;
+        return format!("Added {} to watchlist", symbol);
+    }
+    format!("Error adding {} to watchlist", symbol)
+}
+
+/// Removes a symbol from the watchlist
+/// Usage: unwatch <symbol>
+async fn handle_remove_watch(
+    state: &Arc<Mutex<AppState>>,
+    db: &DatabaseConnection,
+    args: &[&str],
+) -> String {
+    if args.is_empty() {
+        return "Usage: unwatch <symbol>".to_string();
+    }
+
+    let symbol = normalize(state, args[0]);
+    let mut action_result = false;
+    {
+        let mut state_guard = lock_state(state);
+        action_result = state_guard.remove_from_watchlist(symbol.clone());
+    }
+    if action_result {
+        Storage::save_state(state, db).await;
+        return format!("Removed {} from watchlist", symbol);
+    }
+    format!("Error removing {} from watchlist", symbol)
+}
+
+/// Lists, sets, or removes the user's symbol aliases
+/// Usage: alias [<name> <symbol> | remove <name>]
+async fn handle_alias(
+    state: &Arc<Mutex<AppState>>,
+    db: &DatabaseConnection,
+    args: &[&str],
+) -> String {
+    const USAGE: &str = "Usage: alias [<name> <symbol> | remove <name>]";
+    let message = match args {
+        [] => {
+            let aliases = lock_state(state).get_symbol_aliases();
+            if aliases.is_empty() {
+                return format!("No symbol aliases. {}", USAGE);
+            }
+            let mut lines = vec!["Symbol Aliases:".to_string()];
+            lines.extend(
+                aliases
+                    .iter()
+                    .map(|(alias, symbol)| format!("  {:<10} -> {}", alias, symbol)),
+            );
+            return lines.join("\n");
+        }
+        [action, name] if action.eq_ignore_ascii_case("remove") => {
+            if !lock_state(state).remove_symbol_alias(name) {
+                return format!("No alias named {}", name.to_uppercase());
+            }
+            format!("Removed alias {}", name.to_uppercase())
+        }
+        [name, symbol] => {
+            if !import::is_valid_symbol(&name.to_uppercase()) {
+                return format!("Invalid alias name: '{}'", name);
+            }
+            if !import::is_valid_symbol(&Finance::normalize_symbol(symbol, &BTreeMap::new())) {
+                return format!("Invalid symbol: '{}'", symbol);
+            }
+            let symbol = lock_state(state).set_symbol_alias(name, symbol);
+            format!("{} now means {}", name.to_uppercase(), symbol)
+        }
+        _ => return USAGE.to_string(),
+    };
+    Storage::save_state(state, db).await;
+    message
+}
+
+/// Most characters kept in a single note
+const MAX_NOTE_LEN: usize = 200;
+
+/// Shows, sets, or clears the free-text note attached to a symbol
+/// Usage: note [<symbol> [<text> | --clear]]
+async fn handle_note(
+    state: &Arc<Mutex<AppState>>,
+    db: &DatabaseConnection,
+    args: &[&str],
+) -> String {
+    const USAGE: &str = "Usage: note <symbol> <text> | note <symbol> --clear";
+    let Some((raw_symbol, text)) = args.split_first() else {
+        let notes = lock_state(state).get_notes();
+        if notes.is_empty() {
+            return format!("No notes yet. {}", USAGE);
+        }
+        let mut lines = vec!["Notes:".to_string()];
+        lines.extend(
+            notes
+                .iter()
+                .map(|(symbol, text)| format!("  {:<8} {}", symbol, text)),
+        );
+        return lines.join("\n");
+    };
+    let symbol = normalize(state, raw_symbol);
+
+    let message = {
+        let mut state_guard = lock_state(state);
+        match text {
+            [] => {
+                return match state_guard.get_note(&symbol) {
+                    Some(note) => format!("{}: {}", symbol, note),
+                    None => format!("No note for {}. {}", symbol, USAGE),
+                };
+            }
+            ["--clear"] => {
+                if !state_guard.clear_note(&symbol) {
+                    return format!("No note for {}", symbol);
+                }
+                format!("Cleared note for {}", symbol)
+            }
+            words => {
+                let note = words.join(" ");
+                if note.chars().count() > MAX_NOTE_LEN {
+                    return format!("Note is too long (max {} characters)", MAX_NOTE_LEN);
+                }
+                match state_guard.set_note(symbol.clone(), note) {
+                    Some(_) => format!("Updated note for {}", symbol),
+                    None => format!("Saved note for {}", symbol),
+                }
+            }
+        }
+    };
+    Storage::save_state(state, db).await;
+    message
+}
+
+/// Adds every symbol listed in a text file to the watchlist
+/// Usage: loadwatch <path>
+async fn handle_load_watch(
+    state: &Arc<Mutex<AppState>>,
+    db: &DatabaseConnection,
+    args: &[&str],
+) -> String {
+    if args.is_empty() {
+        return "Usage: loadwatch <path>".to_string();
+    }
+
+    let path = args.join(" ");
+    let path = path.trim().trim_matches('"');
+    match import::import_watchlist_from_file(state, path) {
+        Ok(report) => {
+            Storage::save_state(state, db).await;
+            report
+        }
+        Err(err) => err,
+    }
+}
+
+/// Compares relative performance of several symbols over a window
+/// Usage: compare <sym1> <sym2> [...] [--days N]
+async fn handle_compare(state: &Arc<Mutex<AppState>>, args: &[&str]) -> String {
+    const USAGE: &str = "Usage: compare <sym1> <sym2> [...] [--days N]";
+
+    let mut days: i64 = 30;
+    let mut symbols: Vec<String> = Vec::new();
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        if *arg == "--days" {
+            let Some(raw) = iter.next() else {
+                return USAGE.to_string();
+            };
+            days = match raw.parse::<i64>() {
+                Ok(v) if (2..=3650).contains(&v) => v,
+                _ => return format!("Invalid days: '{}' (expected 2-3650)", raw),
+            };
+        } else {
+            let symbol = normalize(state, arg);
+            if !symbols.contains(&symbol) {
+                symbols.push(symbol);
+            }
+        }
+    }
+    if symbols.len() < 2 {
+        return USAGE.to_string();
+    }
+
+    let (provider, today) = {
+        let state_guard = lock_state(state);
+        (
+            state_guard.provider(),
+            state_guard.clock().now().date_naive(),
+        )
+    };
+    let from = today - chrono::Duration::days(days);
+    let fetches: Vec<_> = symbols
+        .iter()
+        .cloned()
+        .map(|symbol| {
+            let provider = provider.clone();
+            tokio::spawn(async move {
+                let closes = provider.daily_closes(&symbol, from, today).await;
+                (symbol, closes)
+            })
+        })
+        .collect();
+    let mut series = Vec::new();
+    for fetch in fetches {
+        if let Ok(result) = fetch.await {
+            series.push(result);
+        }
+    }
+
+    let normalized = Finance::normalize_on_common_dates(&series);
+    if normalized.len() < 2 {
+        return format!(
+            "Not enough overlapping history to compare over {} days",
+            days
+        );
+    }
+
+    let (low, high) = normalized
+        .iter()
+        .flat_map(|(_, values)| values.iter().copied())
+        .fold((Decimal::MAX, Decimal::MIN), |(lo, hi), v| {
+            (lo.min(v), hi.max(v))
+        });
+    let sessions = normalized[0].1.len();
+
+    let mut lines = vec![format!(
+        "Relative performance, last {} days ({} common sessions, start = 100):",
+        days, sessions
+    )];
+    for (rank, (symbol, pct)) in Finance::rank_by_return(&normalized).iter().enumerate() {
+        let values = normalized
+            .iter()
+            .find(|(s, _)| s == symbol)
+            .map(|(_, v)| v.as_slice())
+            .unwrap_or_default();
+        lines.push(format!(
+            "{:>2}. {:<8} {:>8}  {}",
+            rank + 1,
+            symbol,
+            format::format_pct(*pct),
+            Finance::ascii_sparkline(values, low, high, 30)
+        ));
+    }
+
+    let compared: Vec<&String> = normalized.iter().map(|(s, _)| s).collect();
+    let missing: Vec<&str> = symbols
+        .iter()
+        .filter(|s| !compared.contains(s))
+        .map(|s| s.as_str())
+        .collect();
+    if !missing.is_empty() {
+        lines.push(format!("No history for: {}", missing.join(", ")));
+    }
+
+    lines.join("\n")
+}
+
+/// Gainers and losers listed by `movers` unless `--top` says otherwise
+const DEFAULT_MOVERS_LIMIT: usize = 5;
+
+/// Lists the watchlist's biggest gainers and losers since the previous close
+/// Usage: movers [--top N]
+async fn handle_movers(state: &Arc<Mutex<AppState>>, args: &[&str]) -> String {
+    const USAGE: &str = "Usage: movers [--top N]";
+    let limit = match args {
+        [] => DEFAULT_MOVERS_LIMIT,
+        ["--top", raw] => match raw.parse::<usize>() {
+            Ok(v) if (1..=50).contains(&v) => v,
+            _ => return format!("Invalid count: '{}' (expected 1-50)", raw),
+        },
+        _ => return USAGE.to_string(),
+    };
+
+    let (watchlist, provider) = {
+        let state_guard = lock_state(state);
+        (state_guard.get_watchlist(), state_guard.provider())
+    };
+    if watchlist.is_empty() {
+        return "Watchlist is empty. Add symbols with 'addwatch <symbol>'".to_string();
+    }
+
+    let fetches: Vec<_> = watchlist
+        .into_iter()
+        .map(|symbol| {
+            let provider = provider.clone();
+            tokio::spawn(async move {
+                let (price, previous_close) = tokio::join!(
+                    provider.curr_price(&symbol),
+                    provider.previous_close(&symbol)
+                );
+                (symbol, Finance::day_change_pct(price, previous_close))
+            })
+        })
+        .collect();
+    let mut changes = Vec::new();
+    let mut excluded = Vec::new();
+    for fetch in fetches {
+        match fetch.await {
+            Ok((symbol, Some(pct))) => changes.push((symbol, pct)),
+            Ok((symbol, None)) => excluded.push(symbol),
+            Err(_) => {}
+        }
+    }
+    excluded.sort();
+
+    let mut lines = Vec::new();
+    if changes.is_empty() {
+        lines.push("No day change available for any watchlist symbol".to_string());
+    } else {
+        let (gainers, losers) = Finance::top_movers(&changes, limit);
+        lines.push(format!(
+            "Watchlist movers since previous close (top {}):",
+            limit
+        ));
+        // The output pane colors lines by their leading arrow
+        for (title, movers) in [("Gainers:", gainers), ("Losers:", losers)] {
+            lines.push(title.to_string());
+            if movers.is_empty() {
+                lines.push("  (none)".to_string());
+            }
+            for (symbol, pct) in movers {
+                let arrow = if pct >= Decimal::ZERO {
+                    UP_MARKER
+                } else {
+                    DOWN_MARKER
+                };
+                lines.push(format!(
+                    "  {} {:<8} {:>8}",
+                    arrow,
+                    symbol,
+                    format::format_pct(pct)
+                ));
+            }
+        }
+    }
+    if !excluded.is_empty() {
+        lines.push(format!(
+            "Excluded (no previous close): {}",
+            excluded.join(", ")
+        ));
+    }
+    lines.join("\n")
+}
+
+/// Lists the holdings that gained and lost the most since the previous close
+/// Usage: today [--top N]
+async fn handle_today(state: &Arc<Mutex<AppState>>, args: &[&str]) -> String {
+    const USAGE: &str = "Usage: today [--top N]";
+    let limit = match args {
+        [] => DEFAULT_MOVERS_LIMIT,
+        ["--top", raw] => match raw.parse::<usize>() {
+            Ok(v) if (1..=50).contains(&v) => v,
+            _ => return format!("Invalid count: '{}' (expected 1-50)", raw),
+        },
+        _ => return USAGE.to_string(),
+    };
+
+    let (holdings, provider, money) = {
+        let state_guard = lock_state(state);
+        (
+            state_guard.get_holdings_map(),
+            state_guard.provider(),
+            state_guard.get_money_format(),
+        )
+    };
+    if holdings.is_empty() {
+        return "No holdings".to_string();
+    }
+
+    let quotes = FinanceProvider::day_quotes(provider, holdings.keys().cloned()).await;
+    let (moves, previous_value) = Finance::holding_day_moves(&holdings, &quotes);
+    let mut unavailable: Vec<&Finance::Symbol> = holdings
+        .keys()
+        .filter(|symbol| !quotes.contains_key(*symbol))
+        .collect();
+    unavailable.sort();
+
+    let signed = |amount: Decimal| {
+        if amount > Decimal::ZERO {
+            format!("+{}", money.money(amount))
+        } else {
+            money.money(amount)
+        }
+    };
+    let mut lines = Vec::new();
+    if moves.is_empty() {
+        lines.push("No day change available for any holding".to_string());
+    } else {
+        let total: Decimal = moves.iter().map(|day_move| day_move.pnl).sum();
+        let pct = Finance::percent_of(total, previous_value)
+            .map(format::format_pct)
+            .unwrap_or_else(|| "N/A".to_string());
+        lines.push(format!(
+            "Today's P&L: {} ({}) across {} holdings",
+            signed(total),
+            pct,
+            moves.len()
+        ));
+        let (winners, losers): (Vec<_>, Vec<_>) = moves
+            .iter()
+            .partition(|day_move| day_move.pnl >= Decimal::ZERO);
+        let losers = losers.into_iter().rev();
+        // The output pane colors lines by their leading arrow
+        for (title, listed) in [
+            (
+                "Winners:",
+                winners.into_iter().take(limit).collect::<Vec<_>>(),
+            ),
+            ("Losers:", losers.take(limit).collect()),
+        ] {
+            lines.push(title.to_string());
+            if listed.is_empty() {
+                lines.push("  (none)".to_string());
+            }
+            for day_move in listed {
+                let arrow = if day_move.pnl >= Decimal::ZERO {
+                    UP_MARKER
+                } else {
+                    DOWN_MARKER
+                };
+                lines.push(format!(
+                    "  {} {:<8} {:>12} {:>8}",
+                    arrow,
+                    day_move.symbol,
+                    signed(day_move.pnl),
+                    format::format_pct(day_move.pct)
+                ));
+            }
+        }
+    }
+    if !unavailable.is_empty() {
+        lines.push(format!(
+            "Unavailable (no current price or previous close): {}",
+            unavailable
+                .into_iter()
+                .cloned()
+                .collect::<Vec<_>>()
+                .join(", ")
+        ));
+    }
+    lines.join("\n")
+}
+
+/// Revalues the portfolio with hypothetical price moves, without trading
+/// Usage: whatif <symbol>=<±%> [...]
+async fn handle_whatif(state: &Arc<Mutex<AppState>>, args: &[&str]) -> String {
+    if args.is_empty() {
+        return "Usage: whatif <symbol>=<±%> [...], e.g. whatif AAPL=-10 TSLA=+5".to_string();
+    }
+    let mut shocks = match args
+        .iter()
+        .map(|arg| Shock::parse(arg))
+        .collect::<Result<Vec<_>, _>>()
+    {
+        Ok(shocks) => shocks,
+        Err(e) => return e,
+    };
+    for shock in &mut shocks {
+        shock.symbol = normalize(state, &shock.symbol);
+    }
+
+    let (holdings, cash, provider, money) = {
+        let state_guard = lock_state(state);
+        (
+            state_guard.get_holdings_map(),
+            state_guard.check_balance() - state_guard.get_margin_loan(),
+            state_guard.provider(),
+            state_guard.get_money_format(),
+        )
+    };
+    if holdings.is_empty() {
+        return "No holdings".to_string();
+    }
+
+    let marks = FinanceProvider::current_prices(provider, holdings.keys().cloned()).await;
+    match Scenario::new(&holdings, &marks, &shocks, cash) {
+        Ok(scenario) => scenario::format_scenario(&scenario, &money),
+        Err(e) => e,
+    }
+}
+
+/// SECTION: Trading Commands
+
+/// Executes a market buy order
+/// Usage: buy <symbol> <quantity|$amount> [--tag <t>]
+async fn handle_buy(
+    state: &Arc<Mutex<AppState>>,
+    db: &DatabaseConnection,
+    args: &[&str],
+) -> String {
+    let (args, tag) = match take_tag(args) {
+        Ok(parsed) => parsed,
+        Err(e) => return e,
+    };
+    if args.len() < 2 {
+        return "Usage: buy <symbol> <quantity|$amount> [--tag <t>]".to_string();
+    }
+
+    let symbol = normalize(state, args[0]);
+    let size = match parse_order_size(args[1]) {
+        Ok(v) => v,
+        Err(e) => return e,
+    };
+
+    // Get current price
+    let provider = lock_state(state).provider();
+    let price = provider.curr_price(&symbol).await;
+    if price == Decimal::ZERO {
+        return format!("Could not get price for {}", symbol);
+    }
+
+    // Check buying power in the settlement currency against the fill price including slippage
+    let (currency, balance, slippage_bps, share_policy, fee_model, money) = {
+        let state_guard = lock_state(state);
+        (
+            state_guard.settlement_currency(&symbol),
+            state_guard.buying_power_for(&symbol),
+            state_guard.get_slippage_bps(),
+            state_guard.get_share_policy(),
+            state_guard.get_fee_model(),
+            state_guard.get_money_format(),
+        )
+    };
+    let asset_type = Finance::AssetType::for_symbol(&symbol);
+    let expected_fill = Finance::apply_slippage(price, &Orders::Side::Buy, slippage_bps);
+    let quantity = share_policy.apply(match size {
+        OrderSize::Shares(quantity) => quantity,
+        OrderSize::Notional(amount) => Finance::quantity_for_notional(amount, expected_fill),
+    });
+    if quantity <= Decimal::ZERO {
+        return format!(
+            "Amount is too small to buy any {} at {}",
+            symbol,
+            money.money_price(expected_fill, asset_type)
+        );
+    }
+    let total_cost = expected_fill * quantity;
+
+    let fee = fee_model.fee_for(total_cost);
+    if total_cost + fee > balance {
+        return format!(
+            "Insufficient funds. Need {}, have {}",
+            fx::money_in(&money, &currency, total_cost + fee),
+            fx::money_in(&money, &currency, balance)
+        );
+    }
+
+    // Execute buy
+    let fill_price =
+        match Finance::create_tagged_buy(state, symbol.clone(), quantity, price, tag.clone()).await
+        {
+            Ok(fill_price) => fill_price,
+            Err(e) => return e,
+        };
+    lock_state(state).push_undo(UndoAction::Buy {
+        symbol: symbol.clone(),
+        quantity,
+        price: fill_price,
+    });
+    Storage::save_state(state, db).await;
+
+    let total = fill_price * quantity;
+    let fee = fee_model.fee_for(total);
+    let mut message = format!(
+        "Bought {} shares of {} at {} (total: {}{}){}",
+        format::format_qty(quantity, asset_type),
+        symbol,
+        money.money_price(fill_price, asset_type),
+        money.money(total),
+        fee_note(fee, &money),
+        tag_note(tag.as_deref())
+    );
+    // The unspent part of a dollar buy was never withdrawn, so it is still cash
+    if let OrderSize::Notional(amount) = size {
+        let leftover = format::round_money(amount - total - fee);
+        if leftover > Decimal::ZERO {
+            message.push_str(&format!(". {} left as cash", money.money(leftover)));
+        }
+    }
+    message
+}
+
+/// Executes a market sell order
+/// Usage: sell <symbol> <quantity|$amount> [--tag <t>]
+async fn handle_sell(
+    state: &Arc<Mutex<AppState>>,
+    db: &DatabaseConnection,
+    args: &[&str],
+) -> String {
+    let (args, tag) = match take_tag(args) {
+        Ok(parsed) => parsed,
+        Err(e) => return e,
+    };
+    if args.len() < 2 {
+        return "Usage: sell <symbol> <quantity|$amount> [--tag <t>]".to_string();
+    }
+
+    let symbol = normalize(state, args[0]);
+    let size = match parse_order_size(args[1]) {
+        Ok(v) => v,
+        Err(e) => return e,
+    };
+
+    let (quantity, fill_price) = match market_sell(state, &symbol, size, tag.clone()).await {
+        Ok(fill) => fill,
+        Err(e) => return e,
+    };
+    Storage::save_state(state, db).await;
+
+    let (money, fee_model) = {
+        let state_guard = lock_state(state);
+        (state_guard.get_money_format(), state_guard.get_fee_model())
+    };
+    let asset_type = Finance::AssetType::for_symbol(&symbol);
+    format!(
+        "Sold {} shares of {} at {} (total: {}{}){}",
+        format::format_qty(quantity, asset_type),
+        symbol,
+        money.money_price(fill_price, asset_type),
+        money.money(fill_price * quantity),
+        fee_note(fee_model.fee_for(fill_price * quantity), &money),
+        tag_note(tag.as_deref())
+    )
+}
+
+fn tag_note(tag: Option<&str>) -> String {
+    tag.map(|tag| format!(" [tag: {}]", tag))
+        .unwrap_or_default()
+}
+
+// `, fee: $1.50` after a fill's total, nothing when the fill was free
+fn fee_note(fee: Decimal, money: &MoneyFormat) -> String {
+    if fee.is_zero() {
+        return String::new();
+    }
+    format!(", fee: {}", money.money(fee))
+}
+
+/// Sells a holding at market after checking the quantity held
+/// Shares reserved by open sell orders can't be sold
+/// Returns the quantity sold and the fill price; the caller saves state
+async fn market_sell(
+    state: &Arc<Mutex<AppState>>,
+    symbol: &String,
+    size: OrderSize,
+    tag: Option<String>,
+) -> Result<(Decimal, Decimal), String> {
+    // Check holdings not already reserved by pending sells
+    let (available_qty, avg_cost, slippage_bps, provider) = {
+        let state_guard = lock_state(state);
+        (
+            state_guard.get_available_holdings_qty(symbol),
+            state_guard
+                .get_holdings_map()
+                .get(symbol)
+                .map(|holding| holding.get_avg_price())
+                .unwrap_or_default(),
+            state_guard.get_slippage_bps(),
+            state_guard.provider(),
+        )
+    };
+    let insufficient = |quantity: Decimal| {
+        lock_state(state)
+            .check_sell_qty(symbol, quantity)
+            .unwrap_or_default()
+    };
+
+    if let OrderSize::Shares(quantity) = size
+        && quantity > available_qty
+    {
+        return Err(insufficient(quantity));
+    }
+
+    // Get current price
+    let price = provider.curr_price(symbol).await;
+    if price == Decimal::ZERO {
+        return Err(format!("Could not get price for {}", symbol));
+    }
+
+    let quantity = match size {
+        OrderSize::Shares(quantity) => quantity,
+        OrderSize::Notional(amount) => {
+            let expected_fill = Finance::apply_slippage(price, &Orders::Side::Sell, slippage_bps);
+            Finance::quantity_for_notional(amount, expected_fill)
+        }
+    };
+    if quantity <= Decimal::ZERO {
+        let money = lock_state(state).get_money_format();
+        return Err(format!(
+            "Amount is too small to sell any {} at {}",
+            symbol,
+            money.money_price(price, Finance::AssetType::for_symbol(symbol))
+        ));
+    }
+    if quantity > available_qty {
+        return Err(insufficient(quantity));
+    }
+
+    // Execute sell
+    let fill_price =
+        Finance::create_tagged_sell(state, symbol.clone(), quantity, price, tag).await?;
+    lock_state(state).push_undo(UndoAction::Sell {
+        symbol: symbol.clone(),
+        quantity,
+        price: fill_price,
+        avg_cost,
+    });
+    Ok((quantity, fill_price))
+}
+
+/// Sells the entire position in a symbol at market
+/// Usage: close <symbol>
+async fn handle_close(
+    state: &Arc<Mutex<AppState>>,
+    db: &DatabaseConnection,
+    args: &[&str],
+) -> String {
+    if args.is_empty() {
+        return "Usage: close <symbol>".to_string();
+    }
+
+    let symbol = normalize(state, args[0]);
+    let quantity = lock_state(state).get_ticker_holdings_qty(&symbol);
+    if quantity <= Decimal::ZERO {
+        return format!("You don't hold any {}", symbol);
+    }
+
+    let (quantity, fill_price, cancelled) = match close_position(state, &symbol, quantity).await {
+        Ok(fill) => fill,
+        Err(e) => return e,
+    };
+    Storage::save_state(state, db).await;
+
+    let (cash, money, fee_model) = {
+        let state_guard = lock_state(state);
+        (
+            state_guard.check_balance(),
+            state_guard.get_money_format(),
+            state_guard.get_fee_model(),
+        )
+    };
+    // Proceeds are what reached cash, after the fee
+    let value = format::round_money(fill_price * quantity);
+    let fee = fee_model.fee_for(value);
+    let asset_type = Finance::AssetType::for_symbol(&symbol);
+    format!(
+        "Closed {}: sold {} shares at {}. Proceeds: {}{}. Cash: {}{}",
+        symbol,
+        format::format_qty(quantity, asset_type),
+        money.money_price(fill_price, asset_type),
+        money.money(value - fee),
+        fee_note(fee, &money),
+        money.money(cash),
+        cancelled_orders_note(cancelled)
+    )
+}
+
+/// Sells a whole position, cancelling its pending sell orders first
+/// The orders are put back if the sale fails
+/// Returns the quantity sold, the fill price and how many orders were cancelled
+async fn close_position(
+    state: &Arc<Mutex<AppState>>,
+    symbol: &String,
+    quantity: Decimal,
+) -> Result<(Decimal, Decimal, usize), String> {
+    let cancelled = lock_state(state).cancel_sell_orders(symbol);
+    let count = cancelled.len();
+    match market_sell(state, symbol, OrderSize::Shares(quantity), None).await {
+        Ok((quantity, fill_price)) => Ok((quantity, fill_price, count)),
+        Err(e) => {
+            lock_state(state).restore_open_orders(cancelled);
+            Err(e)
+        }
+    }
+}
+
+fn cancelled_orders_note(cancelled: usize) -> String {
+    match cancelled {
+        0 => String::new(),
+        1 => ". Cancelled 1 open sell order".to_string(),
+        n => format!(". Cancelled {} open sell orders", n),
+    }
+}
+
+/// Sells every position at market
+/// Usage: closeall
+async fn handle_close_all(state: &Arc<Mutex<AppState>>, db: &DatabaseConnection) -> String {
+    let mut positions: Vec<(String, Decimal)> = lock_state(state)
+        .get_holdings_map()
+        .into_iter()
+        .map(|(symbol, holding)| (symbol, holding.get_qty()))
+        .collect();
+    if positions.is_empty() {
+        return "No positions to close".to_string();
+    }
+    positions.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let (money, fee_model) = {
+        let state_guard = lock_state(state);
+        (state_guard.get_money_format(), state_guard.get_fee_model())
+    };
+    let mut lines = Vec::new();
+    let mut proceeds = Decimal::ZERO;
+    let mut closed = 0;
+    for (symbol, quantity) in positions {
+        match close_position(state, &symbol, quantity).await {
+            Ok((quantity, fill_price, cancelled)) => {
+                closed += 1;
+                let value = format::round_money(fill_price * quantity);
+                proceeds += value - fee_model.fee_for(value);
+                let asset_type = Finance::AssetType::for_symbol(&symbol);
+                lines.push(format!(
+                    "Sold {} shares of {} at {}{}",
+                    format::format_qty(quantity, asset_type),
+                    symbol,
+                    money.money_price(fill_price, asset_type),
+                    cancelled_orders_note(cancelled)
+                ));
+            }
+            Err(e) => lines.push(format!("{}: {}", symbol, e)),
+        }
+    }
+    if closed > 0 {
+        Storage::save_state(state, db).await;
+    }
+
+    let cash = lock_state(state).check_balance();
+    lines.push(format!(
+        "Closed {} positions. Proceeds: {}. Cash: {}",
+        closed,
+        money.money(proceeds),
+        money.money(cash)
+    ));
+    lines.join("\n")
+}
+
+/// Sets or lists target allocation weights
+/// Usage: target [<symbol> <percent>]
+async fn handle_target(
+    state: &Arc<Mutex<AppState>>,
+    db: &DatabaseConnection,
+    args: &[&str],
+) -> String {
+    if args.is_empty() {
+        let targets = lock_state(state).get_target_allocations();
+        if targets.is_empty() {
+            return "No targets set. Usage: target <symbol> <percent>".to_string();
+        }
+        let total: Decimal = targets.iter().map(|(_, pct)| *pct).sum();
+        let mut lines: Vec<String> = targets
+            .iter()
+            .map(|(symbol, pct)| format!("{:<8} {:>6}%", symbol, pct))
+            .collect();
+        lines.push(format!(
+            "Total {}%, cash target {}%",
+            total,
+            Decimal::from(100) - total
+        ));
+        return lines.join("\n");
+    }
+    if args.len() < 2 {
+        return "Usage: target <symbol> <percent>".to_string();
+    }
+
+    let symbol = normalize(state, args[0]);
+    let pct = match parse_decimal_arg(args[1].trim_end_matches('%'), "percent") {
+        Ok(v) => v,
+        Err(e) => return e.to_string(),
+    };
+
+    let total = match lock_state(state).set_target_allocation(symbol.clone(), pct) {
+        Ok(total) => total,
+        Err(e) => return e,
+    };
+    Storage::save_state(state, db).await;
+
+    if pct.is_zero() {
+        format!("Cleared target for {}. Targets total {}%", symbol, total)
+    } else {
+        format!(
+            "Target for {} set to {}%. Targets total {}%",
+            symbol, pct, total
+        )
+    }
+}
+
+/// Suggests, and with --execute places, trades that move holdings to their targets
+/// Usage: rebalance [--execute]
+async fn handle_rebalance(
+    state: &Arc<Mutex<AppState>>,
+    db: &DatabaseConnection,
+    args: &[&str],
+) -> String {
+    let execute = match args.first() {
+        None => false,
+        Some(&"--execute") => true,
+        Some(_) => return "Usage: rebalance [--execute]".to_string(),
+    };
+
+    let (targets, holdings, cash, provider, money) = {
+        let state_guard = lock_state(state);
+        (
+            state_guard.get_target_allocations(),
+            state_guard.get_holdings_map(),
+            state_guard.check_balance(),
+            state_guard.provider(),
+            state_guard.get_money_format(),
+        )
+    };
+    if targets.is_empty() {
+        return "No targets set. Use target <symbol> <percent> first".to_string();
+    }
+
+    // Price every holding for the portfolio total, plus targets not yet held
+    let quantities: HashMap<String, Decimal> = holdings
+        .iter()
+        .map(|(symbol, holding)| (symbol.clone(), holding.get_qty()))
+        .collect();
+    let mut symbols: Vec<String> = quantities.keys().cloned().collect();
+    for (symbol, _) in &targets {
+        if !symbols.contains(symbol) {
+            symbols.push(symbol.clone());
+        }
+    }
+    let mut prices = HashMap::new();
+    let mut unpriced = Vec::new();
+    for symbol in symbols {
+        let price = provider.curr_price(&symbol).await;
+        if price > Decimal::ZERO {
+            prices.insert(symbol, price);
+        } else {
+            unpriced.push(symbol);
+        }
+    }
+
+    let mut lines = Vec::new();
+    if !unpriced.is_empty() {
+        unpriced.sort();
+        lines.push(format!("No price for {}; skipped", unpriced.join(", ")));
+    }
+    // Weights are shares of the portfolio, which means nothing when it is worth nothing
+    let holdings_value: Decimal = quantities
+        .iter()
+        .filter_map(|(symbol, qty)| prices.get(symbol).map(|price| *qty * *price))
+        .sum();
+    if cash + holdings_value <= Decimal::ZERO {
+        lines.push("No positions or cash to rebalance".to_string());
+        return lines.join("\n");
+    }
+    let plan = rebalance::plan_rebalance(&quantities, &prices, cash, &targets);
+    if plan.is_empty() {
+        lines.push("Portfolio is already on target".to_string());
+        return lines.join("\n");
+    }
+
+    for order in &plan {
+        let side = match order.side {
+            Orders::Side::Buy => "BUY ",
+            Orders::Side::Sell => "SELL",
+        };
+        let description = format!(
+            "{} {} {} at ~{} ({}% -> {}%)",
+            side,
+            order.quantity,
+            order.symbol,
+            money.money_price(order.price, Finance::AssetType::for_symbol(&order.symbol)),
+            format::format_fixed(order.current_pct, 1),
+            order.target_pct
+        );
+        if !execute {
+            lines.push(description);
+            continue;
+        }
+
+        // Sells run first, so their proceeds are in cash before the buys
+        let result = match order.side {
+            Orders::Side::Sell => market_sell(
+                state,
+                &order.symbol,
+                OrderSize::Shares(order.quantity),
+                None,
+            )
+            .await
+            .map(|_| ()),
+            Orders::Side::Buy => Finance::create_buy_with_params(
+                state,
+                order.symbol.clone(),
+                order.quantity,
+                order.price,
+            )
+            .await
+            .map(|fill_price| {
+                lock_state(state).push_undo(UndoAction::Buy {
+                    symbol: order.symbol.clone(),
+                    quantity: order.quantity,
+                    price: fill_price,
+                });
+            }),
+        };
+        match result {
+            Ok(()) => lines.push(format!("Done: {}", description)),
+            Err(e) => lines.push(format!("Failed: {} ({})", description, e)),
+        }
+    }
+
+    if execute {
+        Storage::save_state(state, db).await;
+        let cash = lock_state(state).check_balance();
+        lines.push(format!("Cash: {}", money.money(cash)));
+    } else {
+        lines.push("Suggestions only. Run rebalance --execute to place these trades".to_string());
+    }
+    lines.join("\n")
+}
+
+/// Creates a buy limit order
+/// Usage: buylimit <symbol> <quantity> <price>
+async fn handle_buy_limit(
+    state: &Arc<Mutex<AppState>>,
+    db: &DatabaseConnection,
+    args: &[&str],
+) -> String {
+    if args.len() < 3 {
+        return "Usage: buylimit <symbol> <quantity> <price>".to_string();
+    }
+
+    let symbol = normalize(state, args[0]);
+    let quantity = match parse_decimal_arg(args[1], "quantity") {
+        Ok(v) => v,
+        Err(e) => return e.to_string(),
+    };
+    let price = match parse_decimal_arg(args[2], "price") {
+        Ok(v) => v,
+        Err(e) => return e.to_string(),
+    };
+
+    if quantity <= Decimal::ZERO || price <= Decimal::ZERO {
+        return "Quantity and price must be positive".to_string();
+    }
+
+    // Checked at the limit price now and again at the fill price when it fills
+    let money = lock_state(state).get_money_format();
+    if let Err(e) =
+        check_risk_limits(state, &symbol, quantity, price, price * quantity, &money).await
+    {
+        return e;
+    }
+
+    // Create order
+    {
+        let mut state_guard = lock_state(state);
+        let order = Orders::OpenOrder::new_at(
+            symbol.clone(),
+            quantity,
+            price,
+            Orders::OrderType::BuyLimit,
+            Orders::Side::Buy,
+            state_guard.now_timestamp(),
+        );
+        match state_guard.add_open_order(order.clone()) {
+            Ok(msg) => msg,
+            Err(e) => return e,
+        };
+        state_guard.push_undo(UndoAction::PlaceOrder { order });
+    }
+    Storage::save_state(state, db).await;
+
+    format!(
+        "Buy limit order created: {} shares of {} at {}",
+        quantity,
+        symbol,
+        money.money_price(price, Finance::AssetType::for_symbol(&symbol))
+    )
+}
+
+/// Creates a stop loss order
+/// Usage: stoploss <symbol> <quantity> <price>
+async fn handle_stop_loss(
+    state: &Arc<Mutex<AppState>>,
+    db: &DatabaseConnection,
+    args: &[&str],
+) -> String {
+    if args.len() < 3 {
+        return "Usage: stoploss <symbol> <quantity> <price>".to_string();
+    }
+
+    let symbol = normalize(state, args[0]);
+    let quantity = match parse_decimal_arg(args[1], "quantity") {
+        Ok(v) => v,
+        Err(e) => return e.to_string(),
+    };
+    let price = match parse_decimal_arg(args[2], "price") {
+        Ok(v) => v,
+        Err(e) => return e.to_string(),
+    };
+
+    if quantity <= Decimal::ZERO || price <= Decimal::ZERO {
+        return "Quantity and price must be positive".to_string();
+    }
+
+    // Create order; holdings net of other open sell orders are checked when it's added
+    {
+        let mut state_guard = lock_state(state);
+        let order = Orders::OpenOrder::new_at(
+            symbol.clone(),
+            quantity,
+            price,
+            Orders::OrderType::StopLoss,
+            Orders::Side::Sell,
+            state_guard.now_timestamp(),
+        );
+        match state_guard.add_open_order(order.clone()) {
+            Ok(msg) => msg,
+            Err(e) => return e,
+        };
+        state_guard.push_undo(UndoAction::PlaceOrder { order });
+    }
+    Storage::save_state(state, db).await;
+
+    let money = lock_state(state).get_money_format();
+    format!(
+        "Stop loss order created: {} shares of {} at {}",
+        quantity,
+        symbol,
+        money.money_price(price, Finance::AssetType::for_symbol(&symbol))
+    )
+}
+
+/// Creates a take profit order
+/// Usage: takeprofit <symbol> <quantity> <price>
+async fn handle_take_profit(
+    state: &Arc<Mutex<AppState>>,
+    db: &DatabaseConnection,
+    args: &[&str],
+) -> String {
+    if args.len() < 3 {
+        return "Usage: takeprofit <symbol> <quantity> <price>".to_string();
+    }
+
+    let symbol = normalize(state, args[0]);
+    let quantity = match parse_decimal_arg(args[1], "quantity") {
+        Ok(v) => v,
+        Err(e) => return e.to_string(),
+    };
+    let price = match parse_decimal_arg(args[2], "price") {
+        Ok(v) => v,
+        Err(e) => return e.to_string(),
+    };
+
+    if quantity <= Decimal::ZERO || price <= Decimal::ZERO {
+        return "Quantity and price must be positive".to_string();
+    }
+
+    // Create order; holdings net of other open sell orders are checked when it's added
+    {
+        let mut state_guard = lock_state(state);
+        let order = Orders::OpenOrder::new_at(
+            symbol.clone(),
+            quantity,
+            price,
+            Orders::OrderType::TakeProfit,
+            Orders::Side::Sell,
+            state_guard.now_timestamp(),
+        );
+        match state_guard.add_open_order(order.clone()) {
+            Ok(msg) => msg,
+            Err(e) => return e,
+        };
+        state_guard.push_undo(UndoAction::PlaceOrder { order });
+    }
+    Storage::save_state(state, db).await;
+
+    let money = lock_state(state).get_money_format();
+    format!(
+        "Take profit order created: {} shares of {} at {}",
+        quantity,
+        symbol,
+        money.money_price(price, Finance::AssetType::for_symbol(&symbol))
+    )
+}
+
+/// Creates a sell limit order
+/// Usage: selllimit <symbol> <quantity> <price>
+async fn handle_sell_limit(
+    state: &Arc<Mutex<AppState>>,
+    db: &DatabaseConnection,
+    args: &[&str],
+) -> String {
+    if args.len() < 3 {
+        return "Usage: selllimit <symbol> <quantity> <price>".to_string();
+    }
+
+    let symbol = normalize(state, args[0]);
+    let quantity = match parse_decimal_arg(args[1], "quantity") {
+        Ok(v) => v,
+        Err(e) => return e.to_string(),
+    };
+    let price = match parse_decimal_arg(args[2], "price") {
+        Ok(v) => v,
+        Err(e) => return e.to_string(),
+    };
+
+    if quantity <= Decimal::ZERO || price <= Decimal::ZERO {
+        return "Quantity and price must be positive".to_string();
+    }
+
+    // Create order; holdings net of other open sell orders are checked when it's added
+    {
+        let mut state_guard = lock_state(state);
+        let order = Orders::OpenOrder::new_at(
+            symbol.clone(),
+            quantity,
+            price,
+            Orders::OrderType::SellLimit,
+            Orders::Side::Sell,
+            state_guard.now_timestamp(),
+        );
+        match state_guard.add_open_order(order.clone()) {
+            Ok(msg) => msg,
+            Err(e) => return e,
+        };
+        state_guard.push_undo(UndoAction::PlaceOrder { order });
+    }
+    Storage::save_state(state, db).await;
+
+    let money = lock_state(state).get_money_format();
+    format!(
+        "Sell limit order created: {} shares of {} at {}",
+        quantity,
+        symbol,
+        money.money_price(price, Finance::AssetType::for_symbol(&symbol))
+    )
+}
+
+/// Changes an open order's price and/or quantity without losing its place
+/// Usage: modify <n> [price <p>] [qty <q>]
+async fn handle_modify(
+    state: &Arc<Mutex<AppState>>,
+    db: &DatabaseConnection,
+    args: &[&str],
+) -> String {
+    const USAGE: &str = "Usage: modify <n> [price <p>] [qty <q>]";
+    if args.is_empty() {
+        let state_guard = lock_state(state);
+        let orders = state_guard.get_open_orders();
+        if orders.is_empty() {
+            return "No open orders".to_string();
+        }
+        let money = state_guard.get_money_format();
+        let mut lines = vec!["Open orders:".to_string()];
+        for (idx, order) in orders.iter().enumerate() {
+            lines.push(format!("  {}. {}", idx + 1, describe_order(order, &money)));
+        }
+        lines.push(USAGE.to_string());
+        return lines.join("\n");
+    }
+
+    let index = match args[0].parse::<usize>() {
+        Ok(n) if n > 0 => n - 1,
+        _ => return format!("Invalid order number: '{}'. {}", args[0], USAGE),
+    };
+    let mut price = None;
+    let mut quantity = None;
+    for pair in args[1..].chunks(2) {
+        let [field, raw] = pair else {
+            return USAGE.to_string();
+        };
+        let value = match field.to_lowercase().as_str() {
+            "price" => match parse_decimal_arg(raw, "price") {
+                Ok(v) => price.insert(v),
+                Err(e) => return e.to_string(),
+            },
+            "qty" | "quantity" => match parse_decimal_arg(raw, "quantity") {
+                Ok(v) => quantity.insert(v),
+                Err(e) => return e.to_string(),
+            },
+            _ => return USAGE.to_string(),
+        };
+        if *value <= Decimal::ZERO {
+            return "Quantity and price must be positive".to_string();
+        }
+    }
+    if price.is_none() && quantity.is_none() {
+        return USAGE.to_string();
+    }
+
+    let (result, money) = {
+        let mut state_guard = lock_state(state);
+        let result = state_guard.modify_open_order(index, price, quantity);
+        if let Ok((before, after)) = &result {
+            state_guard.push_undo(UndoAction::ModifyOrder {
+                before: before.clone(),
+                after: after.clone(),
+            });
+        }
+        (result, state_guard.get_money_format())
+    };
+    match result {
+        Ok((before, after)) => {
+            Storage::save_state(state, db).await;
+            format!(
+                "Modified order {}: {} (was {})",
+                index + 1,
+                describe_order(&after, &money),
+                describe_order(&before, &money)
+            )
+        }
+        Err(e) => e,
+    }
+}
+
+/// Replays an open order, or one described inline, against a past day's range to see
+/// whether and where it would have filled. Analysis only; nothing is traded
+/// Usage: simfill <n> <date> | simfill <buylimit|stoploss|takeprofit|selllimit> <symbol> <quantity> <price> <date>
+async fn handle_simfill(state: &Arc<Mutex<AppState>>, args: &[&str]) -> String {
+    const USAGE: &str = "Usage: simfill <n> <date> | simfill <buylimit|stoploss|takeprofit|selllimit> <symbol> <quantity> <price> <date>";
+    let (order, raw_date) = match args {
+        [raw_index, raw_date] => {
+            let index = match raw_index.parse::<usize>() {
+                Ok(n) if n > 0 => n - 1,
+                _ => return format!("Invalid order number: '{}'. {}", raw_index, USAGE),
+            };
+            match lock_state(state).get_open_orders().get(index) {
+                Some(order) => (order.clone(), *raw_date),
+                None => {
+                    return format!("No open order {}. 'modify' lists them by number", index + 1);
+                }
+            }
+        }
+        [kind, symbol, raw_qty, raw_price, raw_date] => {
+            let (order_type, side) = match kind.to_lowercase().as_str() {
+                "buylimit" => (Orders::OrderType::BuyLimit, Orders::Side::Buy),
+                "stoploss" => (Orders::OrderType::StopLoss, Orders::Side::Sell),
+                "takeprofit" => (Orders::OrderType::TakeProfit, Orders::Side::Sell),
+                "selllimit" => (Orders::OrderType::SellLimit, Orders::Side::Sell),
+                _ => return format!("Unknown order type: '{}'. {}", kind, USAGE),
+            };
+            let quantity = match parse_decimal_arg(raw_qty, "quantity") {
+                Ok(v) => v,
+                Err(e) => return e.to_string(),
+            };
+            let price = match parse_decimal_arg(raw_price, "price") {
+                Ok(v) => v,
+                Err(e) => return e.to_string(),
+            };
+            if quantity <= Decimal::ZERO || price <= Decimal::ZERO {
+                return "Quantity and price must be positive".to_string();
+            }
+            let order = Orders::OpenOrder::new_at(
+                normalize(state, symbol),
+                quantity,
+                price,
+                order_type,
+                side,
+                lock_state(state).now_timestamp(),
+            );
+            (order, *raw_date)
+        }
+        _ => return USAGE.to_string(),
+    };
+    let Ok(date) = chrono::NaiveDate::parse_from_str(raw_date, "%Y-%m-%d") else {
+        return format!("Invalid date: '{}' (expected YYYY-MM-DD)", raw_date);
+    };
+
+    let (provider, money, today) = {
+        let state_guard = lock_state(state);
+        let today = chrono::DateTime::from_timestamp(state_guard.now_timestamp(), 0)
+            .map(|dt| dt.date_naive())
+            .unwrap_or_default();
+        (
+            state_guard.provider(),
+            state_guard.get_money_format(),
+            today,
+        )
+    };
+    if date > today {
+        return format!("{} is in the future; pick a day that has traded", date);
+    }
+    let symbol = order.get_symbol().clone();
+    let Some(bar) = provider.daily_bar(&symbol, date).await else {
+        return format!(
+            "No price data for {} on {}: the market was closed or history is unavailable",
+            symbol, date
+        );
+    };
+
+    let asset_type = Finance::AssetType::for_symbol(&symbol);
+    let price = |value: Decimal| money.money_price(value, asset_type);
+    let mut lines = vec![
+        format!("Simulated {} on {}", describe_order(&order, &money), date),
+        format!(
+            "Day: open {}, high {}, low {}, close {}",
+            price(bar.open),
+            price(bar.high),
+            price(bar.low),
+            price(bar.close)
+        ),
+    ];
+    let order_type = order.get_order_type();
+    match Orders::simulated_fill(&order_type, order.get_price_per(), &bar) {
+        Some(fill) => {
+            let gapped = if fill == order.get_price_per() {
+                ""
+            } else {
+                " (opened through the trigger)"
+            };
+            lines.push(format!(
+                "Would have filled at {}{}, {} total",
+                price(fill),
+                gapped,
+                money.money(fill * order.get_qty())
+            ));
+        }
+        None => {
+            let (side, extreme, direction) = match order_type {
+                Orders::OrderType::TakeProfit | Orders::OrderType::SellLimit => {
+                    ("high", bar.high, "below")
+                }
+                _ => ("low", bar.low, "above"),
+            };
+            lines.push(format!(
+                "Would not have filled: the day's {} of {} stayed {} {}",
+                side,
+                price(extreme),
+                direction,
+                price(order.get_price_per())
+            ));
+        }
+    }
+    lines.join("\n")
+}
+
+// One-line description of an open order, e.g. "StopLoss AAPL 5 @ $95.00"
+fn describe_order(order: &Orders::OpenOrder, money: &MoneyFormat) -> String {
+    let asset_type = Finance::AssetType::for_symbol(order.get_symbol());
+    format!(
+        "{:?} {} {} @ {}",
+        order.get_order_type(),
+        order.get_symbol(),
+        format::format_qty(order.get_qty(), asset_type),
+        money.money_price(order.get_price_per(), asset_type)
+    )
+}
+
+/// Rejects a buy that would break a `risklimit`. Holdings are marked at current prices
+/// and the bought shares at `price`
+async fn check_risk_limits(
+    state: &Arc<Mutex<AppState>>,
+    symbol: &str,
+    quantity: Decimal,
+    price: Decimal,
+    total_cost: Decimal,
+    money: &MoneyFormat,
+) -> Result<(), String> {
+    let prices = risk_prices(state, &[symbol.to_string()]).await;
+    lock_state(state)
+        .check_risk_limits(symbol, quantity, price, total_cost, &prices)
+        .map_err(|rejection| rejection.describe(symbol, money))
+}
+
+/// Shows or sets the risk limits checked on every buy
+/// Usage: risklimit [position <pct>|off] [order <amount>|off]
+async fn handle_risk_limit(
+    state: &Arc<Mutex<AppState>>,
+    db: &DatabaseConnection,
+    args: &[&str],
+) -> String {
+    const USAGE: &str = "Usage: risklimit [position <pct>|off] [order <amount>|off]";
+    let (mut limits, money) = {
+        let state_guard = lock_state(state);
+        (
+            state_guard.get_risk_limits(),
+            state_guard.get_money_format(),
+        )
+    };
+    if args.is_empty() {
+        if limits.is_off() {
+            return format!("No risk limits. {}", USAGE);
+        }
+        return describe_risk_limits(&limits, &money);
+    }
+    if !args.len().is_multiple_of(2) {
+        return USAGE.to_string();
+    }
+
+    for pair in args.chunks(2) {
+        let value = pair[1];
+        let off = value.eq_ignore_ascii_case("off");
+        match pair[0].to_lowercase().as_str() {
+            "position" if off => limits.max_position_pct = None,
+            "position" => {
+                let pct = match parse_decimal_arg(value.trim_end_matches('%'), "percent") {
+                    Ok(v) => v,
+                    Err(e) => return e.to_string(),
+                };
+                if pct <= Decimal::ZERO || pct > Decimal::ONE_HUNDRED {
+                    return "Position limit must be above 0 and at most 100 percent".to_string();
+                }
+                limits.max_position_pct = Some(pct);
+            }
+            "order" if off => limits.max_order_notional = None,
+            "order" => {
+                let limit = match parse_decimal_arg(value.trim_start_matches('$'), "amount") {
+                    Ok(v) => v,
+                    Err(e) => return e.to_string(),
+                };
+                if limit <= Decimal::ZERO {
+                    return "Order limit must be positive".to_string();
+                }
+                limits.max_order_notional = Some(limit);
+            }
+            _ => return USAGE.to_string(),
+        }
+    }
+
+    lock_state(state).set_risk_limits(limits);
+    Storage::save_state(state, db).await;
+    if limits.is_off() {
+        return "Risk limits off".to_string();
+    }
+    describe_risk_limits(&limits, &money)
+}
+
+fn describe_risk_limits(limits: &RiskLimits, money: &MoneyFormat) -> String {
+    let position = limits.max_position_pct.map_or("off".to_string(), |pct| {
+        format!("{}% of the portfolio", pct.normalize())
+    });
+    let order = limits
+        .max_order_notional
+        .map_or("off".to_string(), |limit| money.money(limit));
+    format!("Risk limits: position {}, order {}", position, order)
+}
+
+/// Sets the simulated slippage applied to market orders
+/// Usage: slippage <bps>
+async fn handle_slippage(
+    state: &Arc<Mutex<AppState>>,
+    db: &DatabaseConnection,
+    args: &[&str],
+) -> String {
+    if args.is_empty() {
+        let current = lock_state(state).get_slippage_bps();
+        return format!("Slippage: {} bps. Usage: slippage <bps>", current);
+    }
+
+    let bps = match parse_decimal_arg(args[0], "bps") {
+        Ok(v) => v,
+        Err(e) => return e.to_string(),
+    };
+
+    if bps < Decimal::ZERO || bps > Decimal::from(1000) {
+        return "Slippage must be between 0 and 1000 bps".to_string();
+    }
+
+    lock_state(state).set_slippage_bps(bps);
+    Storage::save_state(state, db).await;
+
+    if bps == Decimal::ZERO {
+        "Slippage disabled, market orders fill at the quoted price".to_string()
+    } else {
+        format!("Market orders will fill with {} bps slippage", bps)
+    }
+}
+
+/// Describes a fee model as `$1.50 + 5 bps`, or `none` when trading is free
+fn describe_fee_model(fee_model: Finance::FeeModel, money: &MoneyFormat) -> String {
+    match (fee_model.flat.is_zero(), fee_model.bps.is_zero()) {
+        (true, true) => "none".to_string(),
+        (false, true) => money.money(fee_model.flat),
+        (true, false) => format!("{} bps", fee_model.bps),
+        (false, false) => format!("{} + {} bps", money.money(fee_model.flat), fee_model.bps),
+    }
+}
+
+/// Sets the commission model charged on every fill
+/// Usage: fee <flat> [bps]
+async fn handle_fee(
+    state: &Arc<Mutex<AppState>>,
+    db: &DatabaseConnection,
+    args: &[&str],
+) -> String {
+    let money = lock_state(state).get_money_format();
+    if args.is_empty() {
+        let current = lock_state(state).get_fee_model();
+        return format!(
+            "Fee per order: {}. Usage: fee <flat> [bps]",
+            describe_fee_model(current, &money)
+        );
+    }
+
+    let flat = match parse_decimal_arg(args[0], "flat fee") {
+        Ok(v) => v,
+        Err(e) => return e.to_string(),
+    };
+    let bps = match args.get(1) {
+        Some(arg) => match parse_decimal_arg(arg, "bps") {
+            Ok(v) => v,
+            Err(e) => return e.to_string(),
+        },
+        None => Decimal::ZERO,
+    };
+
+    if flat < Decimal::ZERO {
+        return "Flat fee can't be negative".to_string();
+    }
+    if bps < Decimal::ZERO || bps > Decimal::from(1000) {
+        return "Fee must be between 0 and 1000 bps".to_string();
+    }
+
+    let fee_model = Finance::FeeModel { flat, bps };
+    lock_state(state).set_fee_model(fee_model);
+    Storage::save_state(state, db).await;
+
+    if fee_model.is_free() {
+        "Fees disabled".to_string()
+    } else {
+        format!(
+            "Fee per order: {}, charged on every fill",
+            describe_fee_model(fee_model, &money)
+        )
+    }
+}
+
+/// Selects how sells are matched to lots for realized P&L
+/// Usage: costbasis [fifo|lifo|average]
+async fn handle_cost_basis(
+    state: &Arc<Mutex<AppState>>,
+    db: &DatabaseConnection,
+    args: &[&str],
+) -> String {
+    const USAGE: &str = "Usage: costbasis [fifo|lifo|average]";
+    let Some(raw) = args.first() else {
+        let current = lock_state(state).get_cost_basis();
+        return format!("Cost basis: {}. {}", current.label(), USAGE);
+    };
+
+    let Some(method) = CostBasisMethod::parse(raw) else {
+        return format!("Unknown cost basis method: '{}'. {}", raw, USAGE);
+    };
+
+    lock_state(state).set_cost_basis(method);
+    Storage::save_state(state, db).await;
+    format!("Realized P&L will use {} cost basis", method.label())
+}
+
+/// Sets how finely bought share quantities are kept
+/// Usage: shares [whole|fractional|N]
+async fn handle_shares(
+    state: &Arc<Mutex<AppState>>,
+    db: &DatabaseConnection,
+    args: &[&str],
+) -> String {
+    const USAGE: &str = "Usage: shares [whole|fractional|N]";
+    let Some(raw) = args.first() else {
+        let current = lock_state(state).get_share_policy();
+        return format!("Share policy: {}. {}", current.name(), USAGE);
+    };
+
+    let Some(policy) = Finance::SharePolicy::parse(raw) else {
+        return format!(
+            "Unknown share policy: '{}' (expected whole, fractional, or 0-{} decimals). {}",
+            raw,
+            Finance::MAX_SHARE_DP,
+            USAGE
+        );
+    };
+
+    lock_state(state).set_share_policy(policy);
+    Storage::save_state(state, db).await;
+    match policy {
+        Finance::SharePolicy::Whole => {
+            "Buys will round down to whole shares; the rest stays as cash".to_string()
+        }
+        Finance::SharePolicy::Fractional => "Buys will keep fractional shares".to_string(),
+        Finance::SharePolicy::Precision(dp) => {
+            format!("Buys will round down to {} decimal places", dp)
+        }
+    }
+}
+
+/// Adjusts a held position for a stock split
+/// Usage: split <symbol> <new:old>
+async fn handle_split(
+    state: &Arc<Mutex<AppState>>,
+    db: &DatabaseConnection,
+    args: &[&str],
+) -> String {
+    const USAGE: &str = "Usage: split <symbol> <new:old>";
+    let [symbol, raw_ratio] = args else {
+        return USAGE.to_string();
+    };
+    let Some(ratio) = Finance::SplitRatio::parse(raw_ratio) else {
+        return format!(
+            "Invalid split ratio: '{}' (expected new:old, e.g. 4:1 or 1:10). {}",
+            raw_ratio, USAGE
+        );
+    };
+    let symbol = normalize(state, symbol);
+
+    let (result, money) = {
+        let mut state_guard = lock_state(state);
+        (
+            Finance::apply_split(&symbol, ratio, &mut state_guard),
+            state_guard.get_money_format(),
+        )
+    };
+    match result {
+        Ok(holding) => {
+            Storage::save_state(state, db).await;
+            format!(
+                "Applied {} split to {}: now {} shares at {} avg",
+                ratio,
+                symbol,
+                holding.get_qty().normalize(),
+                money.money_price(holding.get_avg_price(), holding.get_asset_type())
+            )
+        }
+        Err(e) => e,
+    }
+}
+
+/// Saves, lists, runs and deletes multi-leg order templates
+/// Usage: template [list] | template save <name> [legs] | template run <name> <symbol> [quantity] | template delete <name>
+async fn handle_template(
+    state: &Arc<Mutex<AppState>>,
+    db: &DatabaseConnection,
+    args: &[&str],
+) -> String {
+    let Some(action) = args.first() else {
+        return list_templates(state);
+    };
+    match action.to_lowercase().as_str() {
+        "list" => list_templates(state),
+        "save" => handle_template_save(state, db, &args[1..]).await,
+        "run" => handle_template_run(state, db, &args[1..]).await,
+        "delete" => handle_template_delete(state, db, &args[1..]).await,
+        _ => "Usage: template [list] | template save <name> [legs] | template run <name> <symbol> [quantity] | template delete <name>".to_string(),
+    }
+}
+
+// Every saved template with its legs
+fn list_templates(state: &Arc<Mutex<AppState>>) -> String {
+    let saved = lock_state(state).get_templates();
+    if saved.is_empty() {
+        return "No templates saved. Create one with: template save <name> buy 10; stoploss 10 -5%"
+            .to_string();
+    }
+    let mut lines = vec!["Templates:".to_string()];
+    for (name, template) in saved {
+        lines.push(format!("  {:<12} {}", name, template));
+    }
+    lines.join("\n")
+}
+
+/// Saves the described legs, or the orders just placed on one symbol, under a name
+/// Usage: template save <name> [<leg>; <leg>...]
+async fn handle_template_save(
+    state: &Arc<Mutex<AppState>>,
+    db: &DatabaseConnection,
+    args: &[&str],
+) -> String {
+    let Some(name) = args.first() else {
+        return "Usage: template save <name> [<leg>; <leg>...]".to_string();
+    };
+    let name = name.to_lowercase();
+    if !templates::is_valid_name(&name) {
+        return format!(
+            "Invalid template name: '{}' (use letters, digits, - and _)",
+            name
+        );
+    }
+
+    let spec = args[1..].join(" ");
+    let template = if spec.trim().is_empty() {
+        capture_recent_orders(state).await
+    } else {
+        OrderTemplate::parse(&spec)
+    };
+    let template = match template {
+        Ok(template) => template,
+        Err(e) => return e,
+    };
+
+    let replaced = lock_state(state)
+        .set_template(name.clone(), template.clone())
+        .is_some();
+    Storage::save_state(state, db).await;
+    format!(
+        "{} template {}: {}",
+        if replaced { "Updated" } else { "Saved" },
+        name,
+        template
+    )
+}
+
+// Template from the orders just placed on one symbol. Prices are kept relative to the
+// buy's fill, or to the current quote when there was no buy
+async fn capture_recent_orders(state: &Arc<Mutex<AppState>>) -> Result<OrderTemplate, String> {
+    let history = lock_state(state).get_undo_history();
+    let Some((symbol, actions)) = templates::recent_orders(&history) else {
+        return Err(
+            "No recent orders to capture. Place them first, or describe the legs: \
+                    template save <name> buy 10; stoploss 10 -5%"
+                .to_string(),
+        );
+    };
+    let fill = actions.iter().find_map(|action| match action {
+        UndoAction::Buy { price, .. } => Some(*price),
+        _ => None,
+    });
+    // Without a quote the captured prices are kept as they were
+    let reference = match fill {
+        Some(price) => price,
+        None => {
+            let provider = lock_state(state).provider();
+            provider.curr_price(&symbol).await
+        }
+    };
+    let legs = actions
+        .iter()
+        .filter_map(|action| TemplateLeg::from_action(action, reference))
+        .collect();
+    OrderTemplate::new(legs)
+}
+
+/// Places every leg of a template on a symbol, priced from its current quote
+/// Usage: template run <name> <symbol> [quantity]
+async fn handle_template_run(
+    state: &Arc<Mutex<AppState>>,
+    db: &DatabaseConnection,
+    args: &[&str],
+) -> String {
+    const USAGE: &str = "Usage: template run <name> <symbol> [quantity]";
+    let (name, symbol, raw_quantity) = match args {
+        [name, symbol] => (name, symbol, None),
+        [name, symbol, quantity] => (name, symbol, Some(quantity)),
+        _ => return USAGE.to_string(),
+    };
+    let quantity = match raw_quantity
+        .map(|q| parse_decimal_arg(q, "quantity"))
+        .transpose()
+    {
+        Ok(quantity) => quantity,
+        Err(e) => return e.to_string(),
+    };
+    if quantity.is_some_and(|q| q <= Decimal::ZERO) {
+        return "Quantity must be positive".to_string();
+    }
+    let name = name.to_lowercase();
+    let symbol = normalize(state, symbol);
+
+    let (template, provider) = {
+        let state_guard = lock_state(state);
+        (
+            state_guard.get_template(&name).cloned(),
+            state_guard.provider(),
+        )
+    };
+    let Some(template) = template else {
+        return format!(
+            "No template named '{}'. Type 'template list' to see saved templates",
+            name
+        );
+    };
+    let reference = provider.curr_price(&symbol).await;
+    if reference == Decimal::ZERO {
+        return format!("Could not get price for {}", symbol);
+    }
+
+    let legs = template.plan(reference, quantity);
+    let mut lines = vec![format!("Running template {} on {}:", name, symbol)];
+    for (i, leg) in legs.iter().enumerate() {
+        let quantity = leg.quantity.to_string();
+        let price = leg.price.map(|price| price.to_string());
+        let mut leg_args = vec![symbol.as_str(), quantity.as_str()];
+        if let Some(price) = &price {
+            leg_args.push(price);
+        }
+
+        let placed_before = placed_count(state);
+        let result = match leg.kind {
+            LegKind::Buy => handle_buy(state, db, &leg_args).await,
+            LegKind::BuyLimit => handle_buy_limit(state, db, &leg_args).await,
+            LegKind::StopLoss => handle_stop_loss(state, db, &leg_args).await,
+            LegKind::TakeProfit => handle_take_profit(state, db, &leg_args).await,
+            LegKind::SellLimit => handle_sell_limit(state, db, &leg_args).await,
+        };
+        lines.push(format!("  {}", result));
+        if placed_count(state) == placed_before {
+            lines.push(format!(
+                "Stopped at leg {} of {}; earlier legs stay placed (undo reverses them)",
+                i + 1,
+                legs.len()
+            ));
+            break;
+        }
+    }
+    lines.join("\n")
+}
+
+// Trades plus open orders, to tell whether a leg went through
+fn placed_count(state: &Arc<Mutex<AppState>>) -> usize {
+    let state_guard = lock_state(state);
+    state_guard.get_trades().len() + state_guard.get_open_orders().len()
+}
+
+/// Deletes a saved template
+/// Usage: template delete <name>
+async fn handle_template_delete(
+    state: &Arc<Mutex<AppState>>,
+    db: &DatabaseConnection,
+    args: &[&str],
+) -> String {
+    let [name] = args else {
+        return "Usage: template delete <name>".to_string();
+    };
+    let name = name.to_lowercase();
+    if !lock_state(state).remove_template(&name) {
+        return format!("No template named '{}'", name);
+    }
+    Storage::save_state(state, db).await;
+    format!("Deleted template {}", name)
+}
+
+/// Schedules, lists and cancels recurring dollar-cost-averaging buys
+/// Usage: recurring <symbol> $<amount> <daily|weekly|monthly> | recurring [list] | recurring cancel <symbol>
+async fn handle_recurring(
+    state: &Arc<Mutex<AppState>>,
+    db: &DatabaseConnection,
+    args: &[&str],
+) -> String {
+    const USAGE: &str = "Usage: recurring <symbol> $<amount> <daily|weekly|monthly> | recurring list | recurring cancel <symbol>";
+    match args {
+        [] => list_recurring_buys(state),
+        [action] if action.eq_ignore_ascii_case("list") => list_recurring_buys(state),
+        [action, symbol] if action.eq_ignore_ascii_case("cancel") => {
+            let symbol = normalize(state, symbol);
+            if !lock_state(state).remove_recurring_buy(&symbol) {
+                return format!("No recurring buy for {}", symbol);
+            }
+            Storage::save_state(state, db).await;
+            format!("Cancelled recurring buy of {}", symbol)
+        }
+        [symbol, raw_amount, raw_frequency] => {
+            let amount = match parse_decimal_arg(
+                raw_amount.strip_prefix('$').unwrap_or(raw_amount),
+                "amount",
+            ) {
+                Ok(amount) => amount,
+                Err(e) => return e.to_string(),
+            };
+            if amount <= Decimal::ZERO {
+                return "Amount must be positive".to_string();
+            }
+            let Some(frequency) = Frequency::parse(raw_frequency) else {
+                return format!(
+                    "Invalid frequency: '{}' (expected daily, weekly or monthly)",
+                    raw_frequency
+                );
+            };
+            let symbol = normalize(state, symbol);
+
+            let (schedule, replaced, money, timezone) = {
+                let mut state_guard = lock_state(state);
+                let now = state_guard.now_timestamp();
+                let schedule = RecurringBuy::starting_at(symbol.clone(), amount, frequency, now);
+                let replaced = state_guard.set_recurring_buy(schedule.clone()).is_some();
+                (
+                    schedule,
+                    replaced,
+                    state_guard.get_money_format(),
+                    state_guard.get_display_timezone(),
+                )
+            };
+            Storage::save_state(state, db).await;
+            format!(
+                "Recurring buy {}: {} of {} {}, first on {}",
+                if replaced { "updated" } else { "set" },
+                money.money(amount),
+                symbol,
+                frequency.name(),
+                timezone.date_time(schedule.next_due)
+            )
+        }
+        _ => USAGE.to_string(),
+    }
+}
+
+// Active schedules with their next buy
+fn list_recurring_buys(state: &Arc<Mutex<AppState>>) -> String {
+    let (schedules, money, timezone) = {
+        let state_guard = lock_state(state);
+        (
+            state_guard.get_recurring_buys(),
+            state_guard.get_money_format(),
+            state_guard.get_display_timezone(),
+        )
+    };
+    if schedules.is_empty() {
+        return "No recurring buys. Set one with: recurring <symbol> $<amount> <daily|weekly|monthly>"
+            .to_string();
+    }
+    let mut lines = vec![
+        "Recurring Buys:".to_string(),
+        format!(
+            "  {:<10} {:>12}  {:<8}  {}",
+            "Symbol", "Amount", "Every", "Next"
+        ),
+    ];
+    for schedule in schedules.values() {
+        lines.push(format!(
+            "  {:<10} {:>12}  {:<8}  {}",
+            schedule.symbol,
+            money.money(schedule.amount),
+            schedule.frequency.name(),
+            timezone.date_time(schedule.next_due)
+        ));
+    }
+    lines.join("\n")
+}
+
+/// SECTION: Background Order Commands
+
+/// Stops background order monitoring
+/// Usage: stopbg
+async fn handle_stop_bg(running: &Arc<std::sync::atomic::AtomicBool>) -> String {
+    running.store(false, std::sync::atomic::Ordering::Relaxed);
+    "Background order monitoring stopped".to_string()
+}
+
+/// Starts background order monitoring
+/// Usage: startbg
+async fn handle_start_bg(
+    state: Arc<Mutex<AppState>>,
+    running: &Arc<std::sync::atomic::AtomicBool>,
+) -> String {
+    running.store(true, std::sync::atomic::Ordering::Relaxed);
+    monitor_order(state, running.clone());
+    "Background order monitoring started".to_string()
+}
+
+/// Pauses or resumes every network fetch: price refreshes, order checks and lookups
+/// Usage: offline | online
+fn handle_offline(state: &Arc<Mutex<AppState>>, offline: bool) -> String {
+    let mut state_guard = lock_state(state);
+    let was_offline = state_guard.is_offline();
+    state_guard.set_offline(offline);
+    match (offline, was_offline) {
+        (true, true) => "Already offline. Type 'online' to resume fetching".to_string(),
+        (true, false) => "Offline: price refreshes, order checks and lookups are paused; \
+                          panels show the last prices as stale. Type 'online' to resume"
+            .to_string(),
+        (false, false) => "Already online".to_string(),
+        (false, true) => "Online: fetching prices again".to_string(),
+    }
+}
+
+/// Shows or sets where the output pane scrolls after each command
+/// Usage: outputscroll [top|bottom|preserve]
+async fn handle_output_scroll(
+    state: &Arc<Mutex<AppState>>,
+    db: &DatabaseConnection,
+    args: &[&str],
+) -> String {
+    const USAGE: &str = "Usage: outputscroll [top|bottom|preserve]";
+    let scroll = match args {
+        [] => {
+            let current = lock_state(state).get_output_scroll();
+            return format!(
+                "Output scroll after commands: {}. {}",
+                current.name(),
+                USAGE
+            );
+        }
+        [raw] => match ScrollAfterCommand::parse(raw) {
+            Some(scroll) => scroll,
+            None => return format!("Unknown scroll mode: '{}'. {}", raw, USAGE),
+        },
+        _ => return USAGE.to_string(),
+    };
+
+    lock_state(state).set_output_scroll(scroll);
+    Storage::save_state(state, db).await;
+
+    match scroll {
+        ScrollAfterCommand::Top => "New output will start at the top of the pane",
+        ScrollAfterCommand::Bottom => "New output will scroll to its last line",
+        ScrollAfterCommand::Preserve => "The output pane will stay where it is after commands",
+    }
+    .to_string()
+}
+
+/// Shows or sets the zone timestamps are displayed in
+/// Usage: timezone [local|utc|<Area/City>]
+async fn handle_timezone(
+    state: &Arc<Mutex<AppState>>,
+    db: &DatabaseConnection,
+    args: &[&str],
+) -> String {
+    const USAGE: &str = "Usage: timezone [local|utc|<Area/City>]";
+    let timezone = match args {
+        [] => {
+            let current = lock_state(state).get_display_timezone();
+            return format!("Times are shown in {}. {}", current.name(), USAGE);
+        }
+        [raw] => match DisplayTimezone::parse(raw) {
+            Some(timezone) => timezone,
+            None => {
+                return format!(
+                    "Unknown timezone: '{}' (expected local, utc or a name like America/New_York)",
+                    raw
+                );
+            }
+        },
+        _ => return USAGE.to_string(),
     };
 
-    if quantity > available_qty {
-        return format!(
-            "Insufficient holdings. Have {:.2} shares of {}",
-            available_qty, symbol
-        );
+    lock_state(state).set_display_timezone(timezone);
+    Storage::save_state(state, db).await;
+    format!("Times will be shown in {}", timezone.name())
+}
+
+/// Shows or sets the longest command the input box accepts
+/// Usage: inputlimit [chars]
+async fn handle_input_limit(
+    state: &Arc<Mutex<AppState>>,
+    db: &DatabaseConnection,
+    args: &[&str],
+) -> String {
+    const USAGE: &str = "Usage: inputlimit [chars]";
+    let len = match args {
+        [] => {
+            let current = lock_state(state).get_max_command_len();
+            return format!("Commands are limited to {} characters. {}", current, USAGE);
+        }
+        [raw] => match raw.parse::<usize>() {
+            Ok(len) => len,
+            Err(_) => return format!("Invalid length: '{}'. {}", raw, USAGE),
+        },
+        _ => return USAGE.to_string(),
+    };
+
+    if let Err(e) = lock_state(state).set_max_command_len(len) {
+        return e;
     }
+    Storage::save_state(state, db).await;
+    format!("Commands are now limited to {} characters", len)
+}
 
-    // Create order
-    let order = Orders::OpenOrder::new(
-        symbol.clone(),
-        quantity,
-        price,
-        Orders::OrderType::StopLoss,
-        Orders::Side::Sell,
-    );
+/// Shows or sets how often prices refresh and background orders are checked
+/// Usage: refresh [<seconds> | monitor <seconds>]
+async fn handle_refresh(
+    state: &Arc<Mutex<AppState>>,
+    db: &DatabaseConnection,
+    args: &[&str],
+) -> String {
+    const USAGE: &str = "Usage: refresh [<seconds> | monitor <seconds>]";
+    let (monitor, raw) = match args {
+        [] => {
+            let state_guard = lock_state(state);
+            return format!(
+                "Prices refresh every {}s, orders are checked every {}s. {}",
+                state_guard.get_refresh_secs(),
+                state_guard.get_monitor_secs(),
+                USAGE
+            );
+        }
+        [secs] => (false, *secs),
+        [target, secs] if target.eq_ignore_ascii_case("monitor") => (true, *secs),
+        _ => return USAGE.to_string(),
+    };
 
-    {
-        let mut state_guard = state.lock().unwrap();
-        match state_guard.add_open_order(order) {
-            Ok(msg) => msg,
-            Err(e) => return e,
-        };
+    let Ok(secs) = raw.parse::<u64>() else {
+        return format!("Invalid seconds: '{}'. {}", raw, USAGE);
+    };
+    let result = {
+        let mut state_guard = lock_state(state);
+        if monitor {
+            state_guard.set_monitor_secs(secs)
+        } else {
+            state_guard.set_refresh_secs(secs)
+        }
+    };
+    if let Err(e) = result {
+        return e;
+    }
+    Storage::save_state(state, db).await;
+
+    if monitor {
+        format!("Open orders will be checked every {}s", secs)
+    } else {
+        format!("Prices will refresh every {}s", secs)
+    }
+}
+
+/// Shows or sets how hard network lookups are throttled
+/// Usage: throttle [<max_concurrent> <spacing_ms>]
+async fn handle_throttle(
+    state: &Arc<Mutex<AppState>>,
+    db: &DatabaseConnection,
+    args: &[&str],
+) -> String {
+    const USAGE: &str = "Usage: throttle [<max_concurrent> <spacing_ms>]";
+    let (raw_concurrent, raw_spacing) = match args {
+        [] => {
+            let limits = lock_state(state).get_throttle_limits();
+            return format!(
+                "Up to {} lookups run at once, started at least {}ms apart. {}",
+                limits.max_concurrent, limits.min_spacing_ms, USAGE
+            );
+        }
+        [concurrent, spacing] => (*concurrent, *spacing),
+        _ => return USAGE.to_string(),
+    };
+
+    let Ok(max_concurrent) = raw_concurrent.parse::<usize>() else {
+        return format!("Invalid concurrency: '{}'. {}", raw_concurrent, USAGE);
+    };
+    let Ok(min_spacing_ms) = raw_spacing.parse::<u64>() else {
+        return format!("Invalid spacing: '{}'. {}", raw_spacing, USAGE);
+    };
+    let limits = FinanceProvider::ThrottleLimits {
+        max_concurrent,
+        min_spacing_ms,
+    };
+    if let Err(e) = lock_state(state).set_throttle_limits(limits) {
+        return e;
     }
     Storage::save_state(state, db).await;
 
     format!(
-        "Stop loss order created: {} shares of {} at ${:.2}",
-        quantity, symbol, price
+        "Lookups limited to {} at once, started at least {}ms apart",
+        max_concurrent, min_spacing_ms
     )
 }
 
-/// Creates a take profit order
-/// Usage: takeprofit <symbol> <quantity> <price>
-async fn handle_take_profit(
+/// Lists the price provider chain with recent success rates, or sets its order
+/// Usage: providers [<provider>[,<provider>...]]
+async fn handle_providers(
     state: &Arc<Mutex<AppState>>,
     db: &DatabaseConnection,
     args: &[&str],
 ) -> String {
-    if args.len() < 3 {
-        return "Usage: takeprofit <symbol> <quantity> <price>".to_string();
+    if !args.is_empty() {
+        let sources = match FinanceProvider::ProviderSource::parse_chain(&args.join(",")) {
+            Ok(sources) => sources,
+            Err(e) => return format!("{}. Usage: providers [yahoo,stooq,cache]", e),
+        };
+        lock_state(state).set_provider_sources(sources.clone());
+        Storage::save_state(state, db).await;
+        let names: Vec<&str> = sources.iter().map(|source| source.name()).collect();
+        return format!("Provider chain set: {}", names.join(" -> "));
     }
 
-    let symbol = args[0].to_uppercase();
-    let quantity: Decimal = match args[1].parse() {
-        Ok(v) => v,
-        Err(_) => return "Invalid quantity".to_string(),
+    let (health, offline) = {
+        let state_guard = lock_state(state);
+        (state_guard.provider_health(), state_guard.is_offline())
     };
-    let price: Decimal = match args[2].parse() {
-        Ok(v) => v,
-        Err(_) => return "Invalid price".to_string(),
+    let mut lines = vec!["Provider chain (tried in order):".to_string()];
+    for (place, link) in health.iter().enumerate() {
+        let rate = match link.success_rate() {
+            Some(rate) => format!(
+                "{}% of the last {} lookups ({} ok)",
+                rate.round(),
+                link.attempts,
+                link.successes
+            ),
+            None => "no lookups yet".to_string(),
+        };
+        lines.push(format!("  {}. {:<8} {}", place + 1, link.name, rate));
+    }
+    if offline {
+        lines.push("Offline: the chain is bypassed until 'online'".to_string());
+    }
+    lines.join("\n")
+}
+
+/// SECTION: Trade History
+
+/// Trades shown by a bare `trades`, so a big import doesn't flood the output pane
+const DEFAULT_TRADES_SHOWN: usize = 50;
+
+/// Displays trade history, newest first
+/// Usage: trades [--last N | --all] [--tag <t>]
+async fn handle_trades(state: &Arc<Mutex<AppState>>, args: &[&str]) -> String {
+    const USAGE: &str = "Usage: trades [--last N | --all] [--tag <t>]";
+    let (args, tag) = match take_tag(args) {
+        Ok(parsed) => parsed,
+        Err(e) => return e,
+    };
+    let limit = match args.as_slice() {
+        [] => Some(DEFAULT_TRADES_SHOWN),
+        ["--all"] => None,
+        ["--last", raw] => match raw.parse::<usize>() {
+            Ok(n) if n > 0 => Some(n),
+            _ => return format!("Invalid count: '{}'. {}", raw, USAGE),
+        },
+        _ => return USAGE.to_string(),
     };
 
-    if quantity <= Decimal::ZERO || price <= Decimal::ZERO {
-        return "Quantity and price must be positive".to_string();
+    let state_guard = lock_state(state);
+    state_guard.display_trades(limit, tag.as_deref())
+}
+
+/// Lists filled conditional orders with their target and fill prices, newest first
+/// Usage: executed [symbol]
+fn handle_executed(state: &Arc<Mutex<AppState>>, args: &[&str]) -> String {
+    let symbol = match args {
+        [] => None,
+        [symbol] => Some(normalize(state, symbol)),
+        _ => return "Usage: executed [symbol]".to_string(),
+    };
+    lock_state(state).display_executed_orders(symbol.as_deref())
+}
+
+/// Lists every symbol in holdings, open orders, the watchlist and trade history
+/// Usage: symbols
+fn handle_symbols(state: &Arc<Mutex<AppState>>, args: &[&str]) -> String {
+    if !args.is_empty() {
+        return "Usage: symbols".to_string();
     }
+    lock_state(state).display_symbols()
+}
 
-    // Check holdings
-    let available_qty = {
-        let state_guard = state.lock().unwrap();
-        state_guard.get_ticker_holdings_qty(&symbol)
+/// Displays realized P&L per symbol under the selected cost basis method
+/// Usage: realized [--by-tag | --tag <t>]
+async fn handle_realized(state: &Arc<Mutex<AppState>>, args: &[&str]) -> String {
+    const USAGE: &str = "Usage: realized [--by-tag | --tag <t>]";
+    let (args, tag) = match take_tag(args) {
+        Ok(parsed) => parsed,
+        Err(e) => return e,
+    };
+    let by_tag = match args.as_slice() {
+        [] => false,
+        [flag] if flag.eq_ignore_ascii_case("--by-tag") && tag.is_none() => true,
+        _ => return USAGE.to_string(),
     };
 
-    if quantity > available_qty {
-        return format!(
-            "Insufficient holdings. Have {:.2} shares of {}",
-            available_qty, symbol
-        );
+    let (gains, lots, method, money) = {
+        let state_guard = lock_state(state);
+        (
+            state_guard.realized_gains(),
+            state_guard.closed_lots(),
+            state_guard.get_cost_basis(),
+            state_guard.get_money_format(),
+        )
+    };
+    if gains.is_empty() {
+        return "No realized P&L yet".to_string();
     }
 
-    // Create order
-    let order = Orders::OpenOrder::new(
-        symbol.clone(),
-        quantity,
-        price,
-        Orders::OrderType::TakeProfit,
-        Orders::Side::Sell,
-    );
+    let (title, rows): (String, Vec<(String, Decimal)>) = if by_tag {
+        // Untagged trades go last so the strategies read first
+        let totals = costbasis::realized_by_tag(&lots);
+        let mut rows: Vec<_> = totals
+            .iter()
+            .filter_map(|(tag, gain)| tag.clone().map(|tag| (tag, *gain)))
+            .collect();
+        if let Some(untagged) = totals.get(&None) {
+            rows.push(("(untagged)".to_string(), *untagged));
+        }
+        (format!("Realized P&L by tag ({}):", method.label()), rows)
+    } else if let Some(tag) = &tag {
+        let tagged: Vec<_> = lots
+            .into_iter()
+            .filter(|lot| lot.tag.as_ref() == Some(tag))
+            .collect();
+        if tagged.is_empty() {
+            return format!("No realized P&L tagged '{}'", tag);
+        }
+        (
+            format!("Realized P&L tagged '{}' ({}):", tag, method.label()),
+            realized_lots_by_symbol(&tagged).into_iter().collect(),
+        )
+    } else {
+        (
+            format!("Realized P&L ({}):", method.label()),
+            costbasis::realized_by_symbol(&gains).into_iter().collect(),
+        )
+    };
 
-    {
-        let mut state_guard = state.lock().unwrap();
-        match state_guard.add_open_order(order) {
-            Ok(msg) => msg,
-            Err(e) => return e,
-        };
+    let mut table = TextTable::new(&[Align::Left, Align::Right]);
+    for (name, gain) in &rows {
+        table.push_row(vec![name.clone(), money.money(*gain)]);
     }
-    Storage::save_state(state, db).await;
+    // Total of the rounded rows so the column adds up
+    let total = format::sum_rounded(rows.iter().map(|(_, gain)| *gain));
+    table.push_row(vec!["Total".to_string(), money.money(total)]);
 
-    format!(
-        "Take profit order created: {} shares of {} at ${:.2}",
-        quantity, symbol, price
-    )
+    let mut lines = vec![title];
+    lines.extend(table.lines().into_iter().map(|line| format!("  {}", line)));
+    lines.join("\n")
 }
 
-/// SECTION: Background Order Commands
+/// Realized gain of closed lot pieces, per symbol
+fn realized_lots_by_symbol(lots: &[costbasis::ClosedLot]) -> BTreeMap<Finance::Symbol, Decimal> {
+    let mut totals = BTreeMap::new();
+    for lot in lots {
+        *totals.entry(lot.symbol.clone()).or_insert(Decimal::ZERO) += lot.gain();
+    }
+    totals
+}
 
-/// Stops background order monitoring
-/// Usage: stopbg
-async fn handle_stop_bg(running: &Arc<std::sync::atomic::AtomicBool>) -> String {
-    running.store(false, std::sync::atomic::Ordering::Relaxed);
-    "Background order monitoring stopped".to_string()
+/// Displays unrealized, realized and total P&L per symbol and for the account
+/// Prices are fetched concurrently; a symbol without a price shows N/A unrealized
+/// With a tag, only lots opened or closed by trades with that tag count
+/// Usage: pnl [--tag <t>]
+async fn handle_pnl(state: &Arc<Mutex<AppState>>, args: &[&str]) -> String {
+    let (args, tag) = match take_tag(args) {
+        Ok(parsed) => parsed,
+        Err(e) => return e,
+    };
+    if !args.is_empty() {
+        return "Usage: pnl [--tag <t>]".to_string();
+    }
+
+    let (holdings, realized, method, money, provider) = {
+        let state_guard = lock_state(state);
+        let (holdings, realized) = match &tag {
+            None => (
+                state_guard.get_holdings_map(),
+                costbasis::realized_by_symbol(&state_guard.realized_gains()),
+            ),
+            Some(tag) => {
+                let closed: Vec<_> = state_guard
+                    .closed_lots()
+                    .into_iter()
+                    .filter(|lot| lot.tag.as_ref() == Some(tag))
+                    .collect();
+                (
+                    tagged_holdings(&state_guard.open_lots(), tag),
+                    realized_lots_by_symbol(&closed),
+                )
+            }
+        };
+        (
+            holdings,
+            realized,
+            state_guard.get_cost_basis(),
+            state_guard.get_money_format(),
+            state_guard.provider(),
+        )
+    };
+    if holdings.is_empty() && realized.is_empty() {
+        return match &tag {
+            Some(tag) => format!("No P&L tagged '{}'", tag),
+            None => "No P&L yet".to_string(),
+        };
+    }
+
+    let fetches: Vec<_> = holdings
+        .keys()
+        .cloned()
+        .map(|symbol| {
+            let provider = provider.clone();
+            tokio::spawn(async move {
+                let price = provider.curr_price(&symbol).await;
+                (symbol, price)
+            })
+        })
+        .collect();
+    let mut prices = HashMap::new();
+    for fetch in fetches {
+        if let Ok((symbol, price)) = fetch.await {
+            prices.insert(symbol, price);
+        }
+    }
+
+    let mut symbols: Vec<_> = holdings.keys().chain(realized.keys()).cloned().collect();
+    symbols.sort();
+    symbols.dedup();
+
+    let mut table = TextTable::new(&[
+        Align::Left,
+        Align::Left,
+        Align::Right,
+        Align::Right,
+        Align::Right,
+    ])
+    .with_header(&["", "Symbol", "Unrealized", "Realized", "Total"]);
+    let mut unrealized_rows = Vec::new();
+    let mut realized_rows = Vec::new();
+    let mut unpriced = Vec::new();
+    for symbol in symbols {
+        let unrealized = match (holdings.get(&symbol), prices.get(&symbol)) {
+            (Some(holding), Some(price)) if *price > Decimal::ZERO => {
+                Some(holding.pnl(*price).round_dp(2))
+            }
+            (Some(_), _) => {
+                unpriced.push(symbol.clone());
+                None
+            }
+            (None, _) => Some(Decimal::ZERO),
+        };
+        let realized = realized
+            .get(&symbol)
+            .copied()
+            .unwrap_or_default()
+            .round_dp(2);
+        let total = unrealized.unwrap_or_default() + realized;
+        unrealized_rows.extend(unrealized);
+        realized_rows.push(realized);
+
+        let arrow = if total < Decimal::ZERO {
+            DOWN_MARKER
+        } else {
+            UP_MARKER
+        };
+        table.push_row(vec![
+            arrow.to_string(),
+            symbol,
+            unrealized.map_or("N/A".to_string(), |pnl| money.money(pnl)),
+            money.money(realized),
+            money.money(total),
+        ]);
+    }
+    // Totals of the rounded rows so the columns add up
+    let unrealized_total = format::sum_rounded(unrealized_rows);
+    let realized_total = format::sum_rounded(realized_rows);
+    table.push_row(vec![
+        String::new(),
+        "Total".to_string(),
+        money.money(unrealized_total),
+        money.money(realized_total),
+        money.money(unrealized_total + realized_total),
+    ]);
+
+    let mut lines = vec![match &tag {
+        Some(tag) => format!("P&L tagged '{}' (realized at {}):", tag, method.label()),
+        None => format!("P&L (realized at {}):", method.label()),
+    }];
+    lines.extend(table.lines().into_iter().map(|line| format!("  {}", line)));
+    if !unpriced.is_empty() {
+        lines.push(format!(
+            "No price for {}; left out of the unrealized total",
+            unpriced.join(", ")
+        ));
+    }
+    lines.join("\n")
 }
 
-/// Starts background order monitoring
-/// Usage: startbg
-async fn handle_start_bg(
-    state: Arc<Mutex<AppState>>,
-    running: &Arc<std::sync::atomic::AtomicBool>,
-) -> String {
-    running.store(true, std::sync::atomic::Ordering::Relaxed);
-    monitor_order(state, running.clone());
-    "Background order monitoring started".to_string()
+/// Open lots bought with `tag`, merged per symbol at their average lot cost
+fn tagged_holdings(
+    lots: &[costbasis::OpenLot],
+    tag: &str,
+) -> HashMap<Finance::Symbol, Finance::Holding> {
+    let mut totals: HashMap<Finance::Symbol, (Decimal, Decimal)> = HashMap::new();
+    for lot in lots.iter().filter(|lot| lot.tag.as_deref() == Some(tag)) {
+        let (quantity, cost) = totals.entry(lot.symbol.clone()).or_default();
+        *quantity += lot.quantity;
+        *cost += lot.quantity * lot.price;
+    }
+    totals
+        .into_iter()
+        .map(|(symbol, (quantity, cost))| {
+            let holding = Finance::Holding::new(symbol.clone(), quantity, cost / quantity);
+            (symbol, holding)
+        })
+        .collect()
 }
 
-/// SECTION: Trade History
+/// Writes the holdings with current prices and P&L to a CSV file
+/// Usage: export-holdings <path>
+async fn handle_export_holdings(state: &Arc<Mutex<AppState>>, args: &[&str]) -> String {
+    if args.is_empty() {
+        return "Usage: export-holdings <path>".to_string();
+    }
+    let path = args.join(" ");
+    let path = path.trim().trim_matches('"');
+
+    let (holdings, provider) = {
+        let state_guard = lock_state(state);
+        (state_guard.get_holdings_map(), state_guard.provider())
+    };
+    if holdings.is_empty() {
+        return "No holdings to export".to_string();
+    }
+    let prices = FinanceProvider::current_prices(provider, holdings.keys().cloned()).await;
+
+    if let Err(e) = std::fs::write(path, Finance::holdings_csv(&holdings, &prices)) {
+        return format!("Failed to write {}: {}", path, e);
+    }
+    let unpriced = holdings.len() - prices.len();
+    let mut result = format!("Exported {} holdings to {}", holdings.len(), path);
+    if unpriced > 0 {
+        result.push_str(&format!(
+            "\n{} without a current price are flagged {}",
+            unpriced,
+            Finance::NO_PRICE_FLAG
+        ));
+    }
+    result
+}
+
+/// Writes the lots closed in one calendar year (UTC) to a CSV file
+/// Usage: taxreport <year> <path>
+async fn handle_tax_report(state: &Arc<Mutex<AppState>>, args: &[&str]) -> String {
+    use chrono::Datelike;
 
-/// Displays trade history
-/// Usage: trades
-async fn handle_trades(state: &Arc<Mutex<AppState>>) -> String {
-    let state_guard = state.lock().unwrap();
-    state_guard.display_trades()
+    const USAGE: &str = "Usage: taxreport <year> <path>";
+    let [raw_year, path @ ..] = args else {
+        return USAGE.to_string();
+    };
+    if path.is_empty() {
+        return USAGE.to_string();
+    }
+    let Ok(year) = raw_year.parse::<i32>() else {
+        return format!("Invalid year: '{}'. {}", raw_year, USAGE);
+    };
+    let path = path.join(" ");
+    let path = path.trim().trim_matches('"');
+
+    let (lots, method, money) = {
+        let state_guard = lock_state(state);
+        (
+            state_guard.closed_lots(),
+            state_guard.get_cost_basis(),
+            state_guard.get_money_format(),
+        )
+    };
+    let lots: Vec<_> = lots
+        .into_iter()
+        .filter(|lot| {
+            chrono::DateTime::from_timestamp(lot.closed, 0).is_some_and(|dt| dt.year() == year)
+        })
+        .collect();
+    if lots.is_empty() {
+        return format!("No lots closed in {}", year);
+    }
+
+    if let Err(e) = std::fs::write(path, costbasis::tax_report_csv(&lots)) {
+        return format!("Failed to write {}: {}", path, e);
+    }
+    let (long, short): (Vec<_>, Vec<_>) = lots.iter().partition(|lot| lot.is_long_term());
+    let total =
+        |lots: &[&costbasis::ClosedLot]| format::sum_rounded(lots.iter().map(|lot| lot.gain()));
+    format!(
+        "Exported {} closed lots for {} ({}) to {}\nShort term: {}\nLong term:  {}",
+        lots.len(),
+        year,
+        method.label(),
+        path,
+        money.money(total(&short)),
+        money.money(total(&long))
+    )
 }
 
 /// SECTION: System Commands
 /// Import past trades using user-provided csv file
-async fn handle_import(state: &Arc<Mutex<AppState>>) -> String {
+/// Usage: import [--dry-run]
+async fn handle_import(state: &Arc<Mutex<AppState>>, args: &[&str]) -> String {
+    let dry_run = match args {
+        [] => false,
+        [flag] if flag.eq_ignore_ascii_case("--dry-run") => true,
+        _ => return "Usage: import [--dry-run]".to_string(),
+    };
     {
-        let mut guard = state.lock().unwrap();
+        let mut guard = lock_state(state);
         guard.set_pending_import(true);
+        guard.set_import_dry_run(dry_run);
     }
 
-    let mut message = String::from("Enter the path of your csv file (or 'cancel' to go back):\n");
+    let mut message = String::new();
+    if dry_run {
+        message.push_str("Dry run: the file is checked but nothing is imported.\n");
+    }
+    message.push_str("Enter the path of your csv file (or 'cancel' to go back):\n");
     message.push_str("The csv format should be:\n");
     message.push_str("date,asset,asset_type,side,quantity,price,currency");
     message
@@ -557,61 +4124,1399 @@ async fn handle_import_path(
     db: &DatabaseConnection,
 ) -> String {
     let path = input.trim().trim_matches('"');
-    if path.eq_ignore_ascii_case("cancel") || path.is_empty() {
-        let mut guard = state.lock().unwrap();
+    let dry_run = {
+        let mut guard = lock_state(state);
         guard.set_pending_import(false);
+        let dry_run = guard.is_import_dry_run();
+        guard.set_import_dry_run(false);
+        dry_run
+    };
+    if path.eq_ignore_ascii_case("cancel") || path.is_empty() {
         return "Import cancelled".to_string();
     }
 
-    let result = match import::import_trades_from_csv(state, path).await {
+    if dry_run {
+        return import::dry_run_trades_from_csv(state, path)
+            .await
+            .unwrap_or_else(|err| err);
+    }
+    match import::import_trades_from_csv(state, path).await {
         Ok(report) => {
             Storage::save_state(state, db).await;
             report
         }
         Err(err) => err,
-    };
-
-    let mut guard = state.lock().unwrap();
-    guard.set_pending_import(false);
-    result
+    }
 }
 
 /// Resets all data to default state
 /// Usage: reset
 async fn handle_reset(state: &Arc<Mutex<AppState>>, db: &DatabaseConnection) -> String {
-    Storage::default_state(state, db).await;
-    "Account reset to default state".to_string()
+    match Storage::default_state(state, db).await {
+        Ok(()) => "Account reset to default state".to_string(),
+        Err(e) => format!("Could not reset the account: {}", e),
+    }
+}
+
+/// Writes the whole account to one file for moving between machines
+/// Usage: backup <path>
+async fn handle_backup(
+    state: &Arc<Mutex<AppState>>,
+    db: &DatabaseConnection,
+    args: &[&str],
+) -> String {
+    let [path] = args else {
+        return "Usage: backup <path>".to_string();
+    };
+    let path = Path::new(path.trim_matches('"'));
+    match Storage::backup_account(state, db, path).await {
+        Ok(()) => format!("Account backed up to {}", path.display()),
+        Err(e) => e,
+    }
+}
+
+/// Replaces the account with a backup; without `confirm` only reports what it holds
+/// Usage: restore <path> [confirm]
+async fn handle_restore(
+    state: &Arc<Mutex<AppState>>,
+    db: &DatabaseConnection,
+    args: &[&str],
+) -> String {
+    let (path, confirmed) = match args {
+        [path] => (path, false),
+        [path, confirm] if confirm.eq_ignore_ascii_case("confirm") => (path, true),
+        _ => return "Usage: restore <path> [confirm]".to_string(),
+    };
+    let path = Path::new(path.trim_matches('"'));
+    let account = match Storage::read_account_backup(path).await {
+        Ok(account) => account,
+        Err(e) => return e,
+    };
+
+    if !confirmed {
+        let balance = lock_state(state)
+            .get_money_format()
+            .money(account.check_balance());
+        return format!(
+            "{} holds {} cash, {} holdings, {} trades, {} open orders, {} watchlist symbols, \
+             {} notes and {} templates.\nRestoring replaces the current account. \
+             Run 'restore {} confirm' to continue",
+            path.display(),
+            balance,
+            account.get_holdings_map().len(),
+            account.get_trades().len(),
+            account.get_open_orders().len(),
+            account.get_watchlist().len(),
+            account.get_notes().len(),
+            account.get_templates().len(),
+            path.display()
+        );
+    }
+    match Storage::restore_account(state, db, account).await {
+        Ok(()) => format!("Account restored from {}", path.display()),
+        Err(e) => e,
+    }
+}
+
+/// Reverses the most recent fund, withdraw, trade, or order placement
+/// Usage: undo
+async fn handle_undo(state: &Arc<Mutex<AppState>>, db: &DatabaseConnection) -> String {
+    let result = lock_state(state).undo_last();
+    match result {
+        None => "Nothing to undo".to_string(),
+        Some(Err(e)) => e,
+        Some(Ok(message)) => {
+            Storage::save_state(state, db).await;
+            message
+        }
+    }
+}
+
+/// Detailed help for a single command, shown by `help <command>`
+struct CommandHelp {
+    /// Names the topic is looked up by; the first is shown as the title
+    names: &'static [&'static str],
+    summary: &'static str,
+    usage: &'static str,
+    arguments: &'static [(&'static str, &'static str)],
+    example: &'static str,
+    notes: &'static str,
+}
+
+/// Per-command help topics. Add an entry here when adding a command.
+const COMMAND_HELP: &[CommandHelp] = &[
+    CommandHelp {
+        names: &["fund"],
+        summary: "Add cash to the account",
+        usage: "fund <amount> [currency]",
+        arguments: &[
+            ("<amount>", "Amount to deposit"),
+            (
+                "[currency]",
+                "Three-letter code such as EUR; USD when left out",
+            ),
+        ],
+        example: "fund 5000 | fund 1000 EUR",
+        notes: "Cash in another currency is kept in its own bucket. Once a bucket exists, \
+                trades in assets quoted in that currency (SAP.DE, BTC-EUR) pay from and \
+                settle to it instead of USD. A foreign deposit is recorded in the ledger, \
+                and counts toward the deposit cap, at its USD value at the current rate; a \
+                currency without a Yahoo rate is rejected.",
+    },
+    CommandHelp {
+        names: &["withdraw"],
+        summary: "Withdraw cash from the account",
+        usage: "withdraw <amount> [currency]",
+        arguments: &[
+            (
+                "<amount>",
+                "Positive amount, at most the balance in that currency",
+            ),
+            (
+                "[currency]",
+                "Three-letter code such as EUR; USD when left out",
+            ),
+        ],
+        example: "withdraw 250 | withdraw 100 EUR",
+        notes: "A foreign withdrawal is recorded in the ledger at its USD value at the \
+                current rate.",
+    },
+    CommandHelp {
+        names: &["cap"],
+        summary: "Limit how much cash can be deposited in total",
+        usage: "cap [<amount> | off]",
+        arguments: &[
+            (
+                "<amount>",
+                "Most that net deposits (fund minus withdraw) may reach",
+            ),
+            ("off", "Remove the cap"),
+        ],
+        example: "cap 10000",
+        notes: "With a cap set, fund rejects deposits that would push net deposits past \
+                it and says how much room is left. Withdrawing frees room again. No \
+                arguments shows the cap and the room left.",
+    },
+    CommandHelp {
+        names: &["cashflow"],
+        summary: "List deposits and withdrawals with the net amount deposited",
+        usage: "cashflow [export <path> | import <path>]",
+        arguments: &[
+            (
+                "export <path>",
+                "Write the ledger to a CSV file (date,type,amount,currency)",
+            ),
+            (
+                "import <path>",
+                "Replay a ledger CSV as deposits and withdrawals",
+            ),
+        ],
+        example: "cashflow export cashflow.csv",
+        notes: "Only fund and withdraw are recorded; trades and interest stay inside the \
+                account. Imported rows change the cash balance like fund and withdraw.",
+    },
+    CommandHelp {
+        names: &["performance", "perf"],
+        summary: "Show simple, time-weighted, and money-weighted returns",
+        usage: "performance [--days N]",
+        arguments: &[(
+            "--days N",
+            "Look back N days, 1 to 36500 (default: all recorded history)",
+        )],
+        example: "performance --days 90",
+        notes: "Uses the account value recorded once a day while Naviin runs and the \
+                cashflow ledger. Time-weighted return ignores when money was added or \
+                removed; money-weighted return (IRR) rewards adding before gains.",
+    },
+    CommandHelp {
+        names: &["summary"],
+        summary: "Show cash, holdings, and whether the market is open",
+        usage: "summary [--json]",
+        arguments: &[(
+            "--json",
+            "Print one JSON object instead of the text summary, for scripts",
+        )],
+        example: "summary --json",
+        notes: "With cash in more than one currency, each bucket is listed with its USD value \
+                and a USD total at current exchange rates (e.g. EURUSD=X). JSON decimals are \
+                strings, and a position that can't be priced has null price fields.",
+    },
+    CommandHelp {
+        names: &["leverage"],
+        summary: "Trade on margin with a leverage factor",
+        usage: "leverage [<factor> | rate <apr>]",
+        arguments: &[
+            (
+                "<factor>",
+                "Buying power as a multiple of cash, from 1 (off) to 4",
+            ),
+            ("<apr>", "Yearly interest on the margin loan, 0 to 100"),
+        ],
+        example: "leverage 2",
+        notes: "Purchases past your cash borrow the rest as a margin loan, shown with \
+                buying power in summary. Sale proceeds and deposits repay the loan first. \
+                The loan accrues daily interest (8% APR by default). When equity drops \
+                below 25% of the holdings' value you get a margin call event; nothing is \
+                sold for you.",
+    },
+    CommandHelp {
+        names: &["cashyield"],
+        summary: "Earn daily-compounded interest on idle cash",
+        usage: "cashyield <apy>",
+        arguments: &[(
+            "<apy>",
+            "Annual percentage yield from 0 to 100; 0 turns it off",
+        )],
+        example: "cashyield 4.5",
+        notes: "Interest is credited once per whole day and recorded as an Interest \
+                trade. Interest earned at the old rate is credited before the rate changes.",
+    },
+    CommandHelp {
+        names: &["currency"],
+        summary: "Change how money is displayed",
+        usage: "currency <symbol> [us|eu|fr|ch]",
+        arguments: &[
+            (
+                "<symbol>",
+                "Currency symbol shown before amounts, e.g. €, £, CHF",
+            ),
+            (
+                "[style]",
+                "Separators: us 1,234.56 / eu 1.234,56 / fr 1 234,56 / ch 1'234.56",
+            ),
+        ],
+        example: "currency € eu",
+        notes: "Presentation only: amounts are not converted between currencies. With no \
+                arguments, shows the current setting. Omitting the style keeps the current one.",
+    },
+    CommandHelp {
+        names: &["webhook"],
+        summary: "Post a JSON notice when a background order fills",
+        usage: "webhook [<url>|off]",
+        arguments: &[(
+            "<url>",
+            "http:// or https:// endpoint; off disables, omit to show the current one",
+        )],
+        example: "webhook https://example.com/naviin",
+        notes: "Payload: event, order_type, side, symbol, quantity, fill_price, timestamp. \
+                Delivery runs in the background with a 5 second timeout and is not retried. \
+                The last failure is shown by webhook with no arguments.",
+    },
+    CommandHelp {
+        names: &["holidays"],
+        summary: "List upcoming market holidays or add custom closures",
+        usage: "holidays [add|remove <YYYY-MM-DD>]",
+        arguments: &[
+            ("add <date>", "Treat the market as closed on this date"),
+            ("remove <date>", "Drop a custom closure"),
+        ],
+        example: "holidays add 2026-12-24",
+        notes: "NYSE holidays are built in. Regular hours are 9:30-16:00 New York time; \
+                outside them prices are the last close and the TUI shows MARKET CLOSED. \
+                Crypto trades 24/7.",
+    },
+    CommandHelp {
+        names: &["price"],
+        summary: "Fetch the current market price",
+        usage: "price <symbol> [--json]",
+        arguments: &[
+            ("<symbol>", "Ticker symbol, e.g. AAPL or BTC-USD"),
+            ("--json", "Print the quote as a JSON object, for scripts"),
+        ],
+        example: "price MSFT --json",
+        notes: "A symbol that can't be priced gives {\"error\": ...} with --json.",
+    },
+    CommandHelp {
+        names: &["prices"],
+        summary: "Quote several symbols at once with their day change",
+        usage: "prices <sym1> <sym2> ...",
+        arguments: &[("<sym>", "Ticker symbols to quote, up to 50")],
+        example: "prices AAPL MSFT BTC-USD",
+        notes: "Lookups run together and come back as one table of last price, previous \
+                close and change since it. Symbols that can't be priced are listed under \
+                the table; a symbol without a previous close shows - for the change.",
+    },
+    CommandHelp {
+        names: &["search"],
+        summary: "Find symbols by company name or partial ticker",
+        usage: "search <query>",
+        arguments: &[("<query>", "Company name or part of a ticker, e.g. apple")],
+        example: "search apple",
+        notes: "Lists up to 8 matches with their name, exchange and type. Add one with \
+                addwatch or look it up with price.",
+    },
+    CommandHelp {
+        names: &["watch"],
+        summary: "Follow one symbol's quote live in the output pane",
+        usage: "watch <symbol>",
+        arguments: &[("<symbol>", "Ticker symbol, e.g. AAPL or BTC-USD")],
+        example: "watch TSLA",
+        notes: "Shows price, day change and update time, refreshed on the price timer. \
+                The symbol isn't added to the watchlist. Press Esc, or run another \
+                command, to return to the normal output.",
+    },
+    CommandHelp {
+        names: &["addwatch"],
+        summary: "Add a symbol to the watchlist",
+        usage: "addwatch <symbol> [--force]",
+        arguments: &[
+            ("<symbol>", "Ticker symbol to track"),
+            (
+                "--force",
+                "Add without checking the symbol, for tickers the provider doesn't cover",
+            ),
+        ],
+        example: "addwatch NVDA",
+        notes: "The symbol is rejected unless a quote can be fetched within 5 seconds. \
+                Symbols that passed once aren't checked again this session.",
+    },
+    CommandHelp {
+        names: &["unwatch"],
+        summary: "Remove a symbol from the watchlist",
+        usage: "unwatch <symbol>",
+        arguments: &[("<symbol>", "Ticker symbol currently on the watchlist")],
+        example: "unwatch NVDA",
+        notes: "",
+    },
+    CommandHelp {
+        names: &["loadwatch"],
+        summary: "Add every symbol listed in a text file to the watchlist",
+        usage: "loadwatch <path>",
+        arguments: &[(
+            "<path>",
+            "File with symbols separated by newlines or commas",
+        )],
+        example: "loadwatch ~/watchlist.txt",
+        notes: "Symbols already on the watchlist and invalid entries are skipped and counted.",
+    },
+    CommandHelp {
+        names: &["note"],
+        summary: "Attach a note to a symbol, e.g. why you bought it",
+        usage: "note [<symbol> [<text> | --clear]]",
+        arguments: &[
+            ("<symbol>", "Ticker symbol; alone, shows its note"),
+            (
+                "<text>",
+                "Note text, up to 200 characters; replaces any existing note",
+            ),
+            ("--clear", "Remove the symbol's note"),
+        ],
+        example: "note AAPL Bought ahead of earnings",
+        notes: "Without arguments every note is listed. Notes are saved and stay with the \
+                symbol after it is sold or unwatched. Press Tab to move to the holdings or \
+                watchlist panel; the selected row's note is shown there.",
+    },
+    CommandHelp {
+        names: &["alias"],
+        summary: "Name your own spellings for symbols, e.g. bitcoin for BTC-USD",
+        usage: "alias [<name> <symbol> | remove <name>]",
+        arguments: &[
+            ("<name>", "What you'll type; letters, digits and . - ^ ="),
+            ("<symbol>", "The symbol it stands for"),
+            ("remove <name>", "Forget an alias"),
+        ],
+        example: "alias bitcoin BTC-USD",
+        notes: "Without arguments every alias is listed. Every command and the importers \
+                already map common spellings: btc -> BTC-USD, eth/usdt -> ETH-USDT, \
+                brk.b -> BRK-B, spx -> ^GSPC. Your aliases are checked first, so \
+                'alias BTC BTC' keeps BTC as a stock ticker.",
+    },
+    CommandHelp {
+        names: &["compare"],
+        summary: "Rank symbols by return over a window",
+        usage: "compare <sym1> <sym2> [...] [--days N]",
+        arguments: &[
+            ("<sym1> <sym2> ...", "Two or more ticker symbols"),
+            (
+                "--days N",
+                "Calendar days to look back, 2 to 3650 (default 30)",
+            ),
+        ],
+        example: "compare AAPL MSFT GOOG --days 90",
+        notes: "Closes are aligned on dates every symbol traded and rebased to 100 at the \
+                start of the window.",
+    },
+    CommandHelp {
+        names: &["movers"],
+        summary: "Show the watchlist's biggest gainers and losers today",
+        usage: "movers [--top N]",
+        arguments: &[(
+            "--top N",
+            "Gainers and losers to list, 1 to 50 (default 5 each)",
+        )],
+        example: "movers --top 3",
+        notes: "Compares each symbol's current price with its previous close. Symbols without \
+                a previous close are left out and listed at the end.",
+    },
+    CommandHelp {
+        names: &["today"],
+        summary: "Show today's P&L and your biggest winning and losing holdings",
+        usage: "today [--top N]",
+        arguments: &[(
+            "--top N",
+            "Winners and losers to list, 1 to 50 (default 5 each)",
+        )],
+        example: "today --top 3",
+        notes: "Day P&L is each holding's quantity times the move from its previous close, \
+                ranked in dollars. The total at the top covers every holding with both \
+                prices; the rest are listed as unavailable.",
+    },
+    CommandHelp {
+        names: &["whatif"],
+        summary: "Value the portfolio as if some prices moved, without trading",
+        usage: "whatif <symbol>=<±%> [...]",
+        arguments: &[(
+            "<symbol>=<±%>",
+            "A held symbol and its hypothetical move from the current price, e.g. AAPL=-10",
+        )],
+        example: "whatif AAPL=-10 TSLA=+5",
+        notes: "Holdings without a shock stay at their current price. Totals include cash \
+                less any margin loan. Nothing is bought, sold or saved.",
+    },
+    CommandHelp {
+        names: &["buy"],
+        summary: "Buy at the current market price",
+        usage: "buy <symbol> <quantity|$amount> [--tag <t>]",
+        arguments: &[
+            ("<symbol>", "Ticker symbol to buy"),
+            ("<quantity>", "Number of shares; fractions allowed"),
+            (
+                "$<amount>",
+                "Dollar amount to spend instead of a share count",
+            ),
+            (
+                "--tag <t>",
+                "Strategy label for the trade: letters, digits, '-' and '_'",
+            ),
+        ],
+        example: "buy AAPL 10 --tag swing  or  buy AAPL $500",
+        notes: "Fills at the quoted price plus any configured slippage. A tag is stored \
+                lowercase; filter by it with trades, realized and pnl --tag.",
+    },
+    CommandHelp {
+        names: &["sell"],
+        summary: "Sell at the current market price",
+        usage: "sell <symbol> <quantity|$amount> [--tag <t>]",
+        arguments: &[
+            ("<symbol>", "Ticker symbol you hold"),
+            ("<quantity>", "Number of shares, at most the quantity held"),
+            (
+                "$<amount>",
+                "Dollar amount to raise instead of a share count",
+            ),
+            ("--tag <t>", "Strategy label for the trade"),
+        ],
+        example: "sell AAPL 5  or  sell AAPL $200 --tag swing",
+        notes: "Fills at the quoted price minus any configured slippage. An untagged \
+                sell counts toward the tags of the buys it closes.",
+    },
+    CommandHelp {
+        names: &["close"],
+        summary: "Sell an entire position at the current market price",
+        usage: "close <symbol>",
+        arguments: &[("<symbol>", "Ticker symbol you hold")],
+        example: "close AAPL",
+        notes: "Sells the exact quantity held, including fractional shares, so no \
+                residual position is left behind. Open stop loss, take profit and \
+                sell limit orders for the symbol are cancelled.",
+    },
+    CommandHelp {
+        names: &["closeall"],
+        summary: "Liquidate every position at the current market price",
+        usage: "closeall",
+        arguments: &[],
+        example: "closeall",
+        notes: "Positions whose price cannot be fetched are left open and reported.",
+    },
+    CommandHelp {
+        names: &["target"],
+        summary: "Set or list target allocation weights",
+        usage: "target [<symbol> <percent>]",
+        arguments: &[
+            ("<symbol>", "Ticker symbol, held or not"),
+            (
+                "<percent>",
+                "Share of portfolio value from 0 to 100; 0 clears the target",
+            ),
+        ],
+        example: "target VTI 60",
+        notes: "Targets may total at most 100%; the rest is the cash target. With no \
+                arguments, lists current targets.",
+    },
+    CommandHelp {
+        names: &["rebalance"],
+        summary: "Suggest trades that move holdings toward their target weights",
+        usage: "rebalance [--execute]",
+        arguments: &[("--execute", "Place the suggested market orders")],
+        example: "rebalance --execute",
+        notes: "Portfolio value is cash plus all priced holdings. Holdings without a \
+                target are left alone, and trades worth under 1.00 are skipped. Without \
+                --execute nothing is traded. Sells run before buys.",
+    },
+    CommandHelp {
+        names: &["buylimit"],
+        summary: "Create a buy limit order",
+        usage: "buylimit <symbol> <quantity> <price>",
+        arguments: &[
+            ("<symbol>", "Ticker symbol to buy"),
+            ("<quantity>", "Number of shares to buy"),
+            ("<price>", "Highest price per share you are willing to pay"),
+        ],
+        example: "buylimit AAPL 10 150",
+        notes: "Good till cancelled: the order stays open until the price is at or below \
+                the limit while background monitoring runs and enough cash is available.",
+    },
+    CommandHelp {
+        names: &["stoploss"],
+        summary: "Create a stop loss order",
+        usage: "stoploss <symbol> <quantity> <price>",
+        arguments: &[
+            ("<symbol>", "Ticker symbol you hold"),
+            ("<quantity>", "Number of shares to sell"),
+            (
+                "<price>",
+                "Sell once the price falls to or below this level",
+            ),
+        ],
+        example: "stoploss TSLA 5 180",
+        notes: "Good till cancelled. Fills at the market price when triggered, which can \
+                be below the stop price. Shares already reserved by other open sell \
+                orders can't be used.",
+    },
+    CommandHelp {
+        names: &["takeprofit"],
+        summary: "Create a take profit order",
+        usage: "takeprofit <symbol> <quantity> <price>",
+        arguments: &[
+            ("<symbol>", "Ticker symbol you hold"),
+            ("<quantity>", "Number of shares to sell"),
+            (
+                "<price>",
+                "Sell once the price rises to or above this level",
+            ),
+        ],
+        example: "takeprofit TSLA 5 300",
+        notes: "Good till cancelled. Fills at the take profit price when triggered. \
+                Shares already reserved by other open sell orders can't be used.",
+    },
+    CommandHelp {
+        names: &["selllimit"],
+        summary: "Create a sell limit order",
+        usage: "selllimit <symbol> <quantity> <price>",
+        arguments: &[
+            ("<symbol>", "Ticker symbol you hold"),
+            ("<quantity>", "Number of shares to sell"),
+            ("<price>", "Lowest price to sell at"),
+        ],
+        example: "selllimit TSLA 5 300",
+        notes: "Good till cancelled. Triggers like a take profit, once the price is at or \
+                above the limit, but fills at the current price when that is higher \
+                rather than at the limit. Shares already reserved by other open sell \
+                orders can't be used.",
+    },
+    CommandHelp {
+        names: &["modify"],
+        summary: "Change an open order's price or quantity in place",
+        usage: "modify <n> [price <p>] [qty <q>]",
+        arguments: &[
+            (
+                "<n>",
+                "Order number, as listed by modify or the Open Orders panel",
+            ),
+            ("price <p>", "New limit or trigger price"),
+            ("qty <q>", "New quantity"),
+        ],
+        example: "modify 2 price 95 qty 5",
+        notes: "The order keeps its original placement time. The new size is checked \
+                against cash for buys and unreserved shares for sells, not counting the \
+                order's old size. With no arguments, lists the open orders by number.",
+    },
+    CommandHelp {
+        names: &["simfill"],
+        summary: "Check whether a limit, stop or target order would have filled on a past day",
+        usage: "simfill <n> <date> | simfill <buylimit|stoploss|takeprofit|selllimit> <symbol> <quantity> <price> <date>",
+        arguments: &[
+            ("<n>", "Open order number, as listed by 'modify'"),
+            (
+                "<buylimit|stoploss|takeprofit|selllimit> ...",
+                "Or describe the order inline, as you would place it",
+            ),
+            ("<date>", "Trading day to replay, YYYY-MM-DD"),
+        ],
+        example: "simfill buylimit AAPL 10 145 2025-03-04",
+        notes: "Uses the day's open, high, low and close. A buy limit or stop loss fills \
+                when the low reaches its price, a take profit or sell limit when the high \
+                does; a day that opened through the price fills at the open. Analysis \
+                only: nothing is traded and the order is left as it is.",
+    },
+    CommandHelp {
+        names: &["risklimit"],
+        summary: "Limit position concentration and order size on buys",
+        usage: "risklimit [position <pct>|off] [order <amount>|off]",
+        arguments: &[
+            (
+                "position <pct>",
+                "Most any one position may be of the portfolio's value, in percent",
+            ),
+            ("order <amount>", "Most a single buy may cost"),
+            ("off", "Remove that limit"),
+        ],
+        example: "risklimit position 25 order 5000",
+        notes: "A buy is rejected when it costs more than the order limit or would leave \
+                the position above the position limit, measured with every holding at \
+                its current price plus the buy at its fill price. Market and rebalance \
+                buys are checked as they fill. A buy limit is checked when placed and \
+                again when it fills, where a breach cancels it; a recurring buy that \
+                would break a limit is skipped. Sells are never limited. No arguments \
+                shows the limits.",
+    },
+    CommandHelp {
+        names: &["slippage"],
+        summary: "Set simulated slippage for market orders",
+        usage: "slippage [bps]",
+        arguments: &[(
+            "[bps]",
+            "Basis points from 0 to 1000; omit to show the current value",
+        )],
+        example: "slippage 10",
+        notes: "Buys fill above the quote and sells below it. Limit and stop orders are \
+                not affected.",
+    },
+    CommandHelp {
+        names: &["fee"],
+        summary: "Set the commission charged on every fill",
+        usage: "fee [flat] [bps]",
+        arguments: &[
+            (
+                "[flat]",
+                "Flat amount per order; omit both to show the current model",
+            ),
+            (
+                "[bps]",
+                "Basis points of the order's value from 0 to 1000, default 0",
+            ),
+        ],
+        example: "fee 1.50 5",
+        notes: "Every fill pays the fee, market or conditional: a buy pays it on top of \
+                its cost and a sell out of its proceeds, and the trade records it. Open \
+                Orders shows each order's estimated fee at its order price, and a buy \
+                is only accepted when cash covers its shares and its fee. `fee 0` turns \
+                fees off.",
+    },
+    CommandHelp {
+        names: &["costbasis"],
+        summary: "Choose how sells are matched to buys for realized P&L",
+        usage: "costbasis [fifo|lifo|average]",
+        arguments: &[(
+            "[method]",
+            "fifo (oldest shares first), lifo (newest first), or average; omit to show \
+             the current method",
+        )],
+        example: "costbasis fifo",
+        notes: "Defaults to average. Applies to the whole trade history, so switching \
+                recomputes past realized P&L. Holdings always show average cost.",
+    },
+    CommandHelp {
+        names: &["shares"],
+        summary: "Choose whole, fractional, or rounded share quantities for buys",
+        usage: "shares [whole|fractional|N]",
+        arguments: &[(
+            "[policy]",
+            "whole, fractional, or N decimal places (0 to 8, also precision-N); omit to \
+             show the current policy",
+        )],
+        example: "shares whole",
+        notes: "Defaults to fractional. Quantities are rounded down, so a dollar buy never \
+                spends more than asked and the remainder stays as cash. Sells are unaffected.",
+    },
+    CommandHelp {
+        names: &["split"],
+        summary: "Adjust a held position for a stock split",
+        usage: "split <symbol> <new:old>",
+        arguments: &[
+            ("symbol", "Held symbol that split"),
+            (
+                "new:old",
+                "New shares for old shares: 4:1 forward, 1:10 reverse",
+            ),
+        ],
+        example: "split AAPL 4:1",
+        notes: "Multiplies the quantity and divides the average cost, so total cost is \
+                unchanged. Open orders for the symbol are adjusted too, and the split is \
+                recorded in trade history so realized P&L stays correct.",
+    },
+    CommandHelp {
+        names: &["template"],
+        summary: "Save and reuse multi-leg order structures",
+        usage: "template [list] | template save <name> [legs] | template run <name> <symbol> [quantity] | template delete <name>",
+        arguments: &[
+            ("name", "Template name: letters, digits, - and _"),
+            (
+                "legs",
+                "Orders separated by ';', each <command> <quantity> [price], e.g. \
+                 buy 10; stoploss 10 -5%; takeprofit 10 +10%",
+            ),
+            ("symbol", "Symbol to place the legs on when running"),
+            (
+                "quantity",
+                "Optional size of the first leg; the others scale with it",
+            ),
+        ],
+        example: "template run bracket MSFT 5",
+        notes: "Leg prices are a percent offset from the quote at run time (-5%) or a fixed \
+                price (150). Without legs, save captures the orders just placed on one \
+                symbol, priced relative to the buy's fill or the current quote. A run stops \
+                at the first leg that is rejected; undo reverses the legs already placed.",
+    },
+    CommandHelp {
+        names: &["recurring"],
+        summary: "Schedule recurring dollar-cost-averaging buys",
+        usage: "recurring <symbol> $<amount> <daily|weekly|monthly> | recurring [list] | recurring cancel <symbol>",
+        arguments: &[
+            ("symbol", "Symbol to buy; one schedule per symbol"),
+            ("amount", "Cash spent on each buy"),
+            ("frequency", "daily, weekly or monthly"),
+        ],
+        example: "recurring VOO $100 weekly",
+        notes: "The first buy is one period from now. Buys are market orders placed by the \
+                background order monitor when due, sized like a dollar buy. A buy that \
+                can't go through is skipped with an event; a schedule that missed several \
+                periods buys once and moves to its next date.",
+    },
+    CommandHelp {
+        names: &["trades"],
+        summary: "Show trade history, newest first",
+        usage: "trades [--last N | --all] [--tag <t>]",
+        arguments: &[
+            ("--last N", "Show only the N most recent trades"),
+            ("--all", "Show the full history"),
+            ("--tag <t>", "Only show trades with this strategy tag"),
+        ],
+        example: "trades --last 10 --tag swing",
+        notes: "Without options the 50 most recent trades are shown, with a count of \
+                the rest. A Tag column appears once a shown trade is tagged.",
+    },
+    CommandHelp {
+        names: &["executed"],
+        summary: "List conditional orders that filled, with target and fill prices",
+        usage: "executed [symbol]",
+        arguments: &[("[symbol]", "Only show orders for this symbol")],
+        example: "executed AAPL",
+        notes: "Shows each filled buylimit, stoploss, takeprofit and selllimit with the price it was \
+                set at, the price it filled at, the gap between them, and when it was \
+                placed and filled. Newest first.",
+    },
+    CommandHelp {
+        names: &["symbols"],
+        summary: "List every symbol the account refers to",
+        usage: "symbols",
+        arguments: &[],
+        example: "symbols",
+        notes: "Each symbol in holdings, open orders, the watchlist or trade history is \
+                listed once, alphabetically, with where it appears and how many open \
+                orders and trades it has. Interest credits are not counted.",
+    },
+    CommandHelp {
+        names: &["realized"],
+        summary: "Show realized P&L per symbol or per strategy tag",
+        usage: "realized [--by-tag | --tag <t>]",
+        arguments: &[
+            ("--by-tag", "One row per tag, untagged trades last"),
+            ("--tag <t>", "Only P&L tagged <t>, per symbol"),
+        ],
+        example: "realized --by-tag",
+        notes: "Sells are matched to earlier buys using the costbasis method. Shares sold \
+                without a recorded buy are left out. Each closed lot counts toward the \
+                sell's tag, or the tag of the buy that opened it when the sell has none.",
+    },
+    CommandHelp {
+        names: &["pnl"],
+        summary: "Show unrealized, realized and total P&L per symbol",
+        usage: "pnl [--tag <t>]",
+        arguments: &[(
+            "--tag <t>",
+            "Only lots bought or sold with this strategy tag",
+        )],
+        example: "pnl --tag swing",
+        notes: "Unrealized P&L marks open positions at the current price against average \
+                cost; realized P&L matches sells to buys using the costbasis method. A \
+                position without a price shows N/A and is left out of the totals. With a \
+                tag, unrealized P&L covers the open lots bought with it, at their lot cost.",
+    },
+    CommandHelp {
+        names: &["taxreport"],
+        summary: "Export the lots closed in a year as CSV",
+        usage: "taxreport <year> <path>",
+        arguments: &[
+            ("year", "Calendar year of the sells (UTC)"),
+            ("path", "CSV file to write"),
+        ],
+        example: "taxreport 2026 taxes-2026.csv",
+        notes: "One row per lot piece sold, matched by the costbasis method: symbol, open and \
+                close dates, quantity, proceeds, cost basis, gain/loss, and short or long \
+                holding period (long means held more than 365 days). Open positions are left \
+                out. Wash sales are not adjusted.",
+    },
+    CommandHelp {
+        names: &["export-holdings"],
+        summary: "Export a snapshot of the holdings as CSV",
+        usage: "export-holdings <path>",
+        arguments: &[("path", "CSV file to write")],
+        example: "export-holdings holdings.csv",
+        notes: "Columns: symbol, quantity, avg_cost, price, market_value, unrealized_pnl, \
+                flag. Prices are fetched at export time. A holding whose price can't be \
+                fetched has blank price, value and P&L and is flagged no_price.",
+    },
+    CommandHelp {
+        names: &["import"],
+        summary: "Load previous trades from a CSV file",
+        usage: "import [--dry-run]",
+        arguments: &[(
+            "--dry-run",
+            "Check the file and report what would be imported, changing nothing",
+        )],
+        example: "import --dry-run",
+        notes: "Prompts for the file path next. Required columns: date, asset, asset_type, \
+                side, quantity, price. Rows apply in order, so a sell needs the shares \
+                held or bought on an earlier row.",
+    },
+    CommandHelp {
+        names: &["stopbg"],
+        summary: "Stop background order monitoring",
+        usage: "stopbg",
+        arguments: &[],
+        example: "stopbg",
+        notes: "Open orders are kept but will not fill until monitoring restarts.",
+    },
+    CommandHelp {
+        names: &["startbg"],
+        summary: "Start background order monitoring",
+        usage: "startbg",
+        arguments: &[],
+        example: "startbg",
+        notes: "Open orders are checked against market prices every 10 seconds by \
+                default; change it with refresh monitor <seconds>.",
+    },
+    CommandHelp {
+        names: &["offline", "online"],
+        summary: "Pause or resume all network fetches",
+        usage: "offline | online",
+        arguments: &[],
+        example: "offline",
+        notes: "Offline, the price refresh and background order checks skip fetching, \
+                and the panels keep the last prices with a STALE badge. Commands that \
+                need a quote (price, buy, sell, search) report it as unavailable. \
+                Open orders wait and fill on the next check after online. Lasts for \
+                the session.",
+    },
+    CommandHelp {
+        names: &["refresh"],
+        summary: "Set the price refresh and order check intervals",
+        usage: "refresh [<seconds> | monitor <seconds>]",
+        arguments: &[
+            (
+                "<seconds>",
+                "Seconds between TUI price refreshes, 1 to 3600",
+            ),
+            (
+                "monitor <seconds>",
+                "Seconds between background order checks, 1 to 3600",
+            ),
+        ],
+        example: "refresh 15",
+        notes: "Defaults are 5s for prices and 10s for orders. Slower intervals reduce \
+                API load. The active price interval is shown in the watchlist footer. \
+                Ctrl+R still refreshes immediately.",
+    },
+    CommandHelp {
+        names: &["outputscroll"],
+        summary: "Choose where the output pane scrolls after each command",
+        usage: "outputscroll [top|bottom|preserve]",
+        arguments: &[(
+            "[top|bottom|preserve]",
+            "Start of the new output, its last line, or wherever the pane already was; \
+             omit to show the current mode",
+        )],
+        example: "outputscroll bottom",
+        notes: "The default is top. PgUp/PgDn, Ctrl+Home and Ctrl+End scroll as usual \
+                whichever mode is set.",
+    },
+    CommandHelp {
+        names: &["timezone"],
+        summary: "Choose the timezone trade, order, ledger and event times are shown in",
+        usage: "timezone [local|utc|<Area/City>]",
+        arguments: &[(
+            "[local|utc|<Area/City>]",
+            "This machine's zone, UTC, or an IANA name such as Europe/London; omit to \
+             show the current zone",
+        )],
+        example: "timezone America/New_York",
+        notes: "The default is local. Times are stored as UTC timestamps, so changing the \
+                zone only changes how they are displayed. CSV exports stay in UTC.",
+    },
+    CommandHelp {
+        names: &["inputlimit"],
+        summary: "Show or set the longest command the input box accepts",
+        usage: "inputlimit [chars]",
+        arguments: &[(
+            "[chars]",
+            "Character limit, 64 to 8192; omit to show the current limit",
+        )],
+        example: "inputlimit 1024",
+        notes: "The default is 512. Typing past the limit is ignored and the input box \
+                title says so. Commands longer than the limit, or with more than 64 \
+                arguments, are rejected without running.",
+    },
+    CommandHelp {
+        names: &["throttle"],
+        summary: "Limit how fast prices are fetched, to stay under rate limits",
+        usage: "throttle [<max_concurrent> <spacing_ms>]",
+        arguments: &[
+            (
+                "<max_concurrent>",
+                "Most lookups in flight at once, 1 to 32",
+            ),
+            (
+                "<spacing_ms>",
+                "Least milliseconds between starting two lookups, 0 to 10000",
+            ),
+        ],
+        example: "throttle 2 250",
+        notes: "Defaults are 4 at once, 100ms apart. The limit is shared by refreshes, \
+                order checks and commands. While lookups are being held back noticeably \
+                the status bar shows 'throttled'; raise the limits or slow 'refresh'.",
+    },
+    CommandHelp {
+        names: &["providers"],
+        summary: "Show or set the chain of price providers tried in order",
+        usage: "providers [<provider>[,<provider>...]]",
+        arguments: &[(
+            "[<provider>,...]",
+            "New order, from yahoo, stooq (delayed US stock prices) and cache (the last \
+             price seen this session); omit to list the chain with recent success rates",
+        )],
+        example: "providers yahoo,stooq,cache",
+        notes: "The default is yahoo alone. A lookup that comes back empty falls through \
+                to the next provider. Success rates cover each provider's last 50 lookups \
+                and start over when the chain changes.",
+    },
+    CommandHelp {
+        names: &["undo"],
+        summary: "Reverse the most recent fund, withdraw, trade, or order",
+        usage: "undo",
+        arguments: &[],
+        example: "undo",
+        notes: "A buy is undone by refunding its cost and removing the shares; a sell by \
+                debiting the proceeds and restoring the shares at their old cost. Placed \
+                orders are cancelled if they haven't filled. Up to 20 steps are kept for \
+                the session. Background order fills can't be undone.",
+    },
+    CommandHelp {
+        names: &["reset"],
+        summary: "Reset cash, holdings, orders, and trades",
+        usage: "reset",
+        arguments: &[],
+        example: "reset",
+        notes: "This cannot be undone.",
+    },
+    CommandHelp {
+        names: &["backup"],
+        summary: "Save the whole account to one file",
+        usage: "backup <path>",
+        arguments: &[("<path>", "File to write; an existing file is replaced")],
+        example: "backup ~/naviin-account.sqlite",
+        notes: "The file holds cash, holdings, trades, orders, the watchlist, notes, \
+                templates, recurring buys and every setting, along with the schema and \
+                state versions they were saved at. Load it with `restore`.",
+    },
+    CommandHelp {
+        names: &["restore"],
+        summary: "Replace the account with a backup",
+        usage: "restore <path> [confirm]",
+        arguments: &[
+            ("<path>", "File written by `backup`"),
+            (
+                "confirm",
+                "Replace the current account; without it the backup is only checked",
+            ),
+        ],
+        example: "restore ~/naviin-account.sqlite confirm",
+        notes: "Backups from older versions are upgraded as they load; ones from a newer \
+                version are refused. The current account is replaced entirely and the \
+                undo history is cleared.",
+    },
+    CommandHelp {
+        names: &["simulate"],
+        summary: "Benchmark random trading on an isolated mock account",
+        usage: "simulate <n> [seed]",
+        arguments: &[
+            ("<n>", "Number of random operations, 1 to 100000"),
+            ("[seed]", "Random seed for a reproducible run (default 42)"),
+        ],
+        example: "simulate 5000 7",
+        notes: "Uses in-memory prices and a fresh account, so your portfolio and the \
+                network are never touched. Final cash is checked against the trade log.",
+    },
+    CommandHelp {
+        names: &["leaderboard"],
+        summary: "Rank saved portfolios by total return",
+        usage: "leaderboard [dir]",
+        arguments: &[(
+            "[dir]",
+            "Folder of portfolio .sqlite files; defaults to the current portfolio's folder",
+        )],
+        example: "leaderboard ~/classroom",
+        notes: "Each portfolio is read without switching to it. Return is value (cash plus \
+                holdings at current prices, less any margin loan) against net deposits from \
+                its cash flow ledger. Each symbol is priced once for all portfolios. A \
+                portfolio with an unpriced holding is marked * and ranked on the rest.",
+    },
+    CommandHelp {
+        names: &["replay"],
+        summary: "Rebuild cash and holdings from the trade log and report differences",
+        usage: "replay [--apply]",
+        arguments: &[(
+            "[--apply]",
+            "Replace the stored cash and holdings with the rebuilt ones",
+        )],
+        example: "replay",
+        notes: "Starts from nothing and applies deposits, withdrawals, trades, interest \
+                credits and charges, and splits in time order. Reports cash drift, quantity \
+                mismatches and average cost mismatches; nothing changes without --apply. \
+                Trades settled in another currency's cash, and trades brought in by \
+                `import`, aren't counted in cash.",
+    },
+    CommandHelp {
+        names: &["clear"],
+        summary: "Clear the output panel",
+        usage: "clear",
+        arguments: &[],
+        example: "clear",
+        notes: "",
+    },
+    CommandHelp {
+        names: &["help"],
+        summary: "Show the command overview or help for one command",
+        usage: "help [command]",
+        arguments: &[("[command]", "Command to explain")],
+        example: "help buylimit",
+        notes: "",
+    },
+    CommandHelp {
+        names: &["exit", "quit"],
+        summary: "Save and exit the application",
+        usage: "exit",
+        arguments: &[],
+        example: "quit",
+        notes: "",
+    },
+];
+
+/// Looks up the detailed help for a command name
+fn command_help(name: &str) -> Option<&'static CommandHelp> {
+    COMMAND_HELP
+        .iter()
+        .find(|topic| topic.names.contains(&name))
+}
+
+/// Formats a help topic with syntax, arguments, an example, and notes
+fn format_command_help(topic: &CommandHelp) -> String {
+    let mut lines = vec![
+        format!("{} - {}", topic.names[0], topic.summary),
+        String::new(),
+        format!("Usage: {}", topic.usage),
+    ];
+    if topic.names.len() > 1 {
+        lines.push(format!("Aliases: {}", topic.names[1..].join(", ")));
+    }
+    if !topic.arguments.is_empty() {
+        lines.push(String::new());
+        lines.push("Arguments:".to_string());
+        for (arg, meaning) in topic.arguments {
+            lines.push(format!("  {:<18} {}", arg, meaning));
+        }
+    }
+    lines.push(String::new());
+    lines.push(format!("Example: {}", topic.example));
+    if !topic.notes.is_empty() {
+        lines.push(String::new());
+        lines.push(format!("Notes: {}", topic.notes));
+    }
+    lines.join("\n")
+}
+
+/// Ranks the saved portfolios in a directory by total return
+/// Usage: leaderboard [dir]
+async fn handle_leaderboard(state: &Arc<Mutex<AppState>>, args: &[&str]) -> String {
+    // The session's database sits beside its backup
+    let current_file = lock_state(state)
+        .get_backup_path()
+        .map(|backup| backup.with_extension(""));
+    let dir = match (args, &current_file) {
+        ([dir], _) => PathBuf::from(dir.trim_matches('"')),
+        ([], Some(file)) => file.parent().map(Path::to_path_buf).unwrap_or_default(),
+        ([], None) => {
+            return "This session isn't saved to a file. Usage: leaderboard <dir>".to_string();
+        }
+        _ => return "Usage: leaderboard [dir]".to_string(),
+    };
+    let files = match Storage::portfolio_files(&dir) {
+        Ok(files) => files,
+        Err(e) => return format!("Could not read {}: {}", dir.display(), e),
+    };
+
+    // Other portfolios are read from disk; the session's own is used as it is now
+    let mut accounts = Vec::new();
+    let mut skipped = Vec::new();
+    let mut includes_current = false;
+    for file in files {
+        if current_file.as_ref() == Some(&file) {
+            includes_current = true;
+            continue;
+        }
+        let name = Storage::portfolio_name(&format!("sqlite://{}", file.display()));
+        match Storage::read_account_backup(&file).await {
+            Ok(account) => accounts.push((name, account)),
+            Err(e) => skipped.push(format!("Skipped {}: {}", name, e)),
+        }
+    }
+    if accounts.is_empty() && !includes_current {
+        return format!("No saved portfolios in {}", dir.display());
+    }
+
+    let (provider, money, mut symbols) = {
+        let state_guard = lock_state(state);
+        let symbols = if includes_current {
+            state_guard.get_holdings_map().into_keys().collect()
+        } else {
+            BTreeSet::new()
+        };
+        (
+            state_guard.provider(),
+            state_guard.get_money_format(),
+            symbols,
+        )
+    };
+    for (_, account) in &accounts {
+        symbols.extend(account.get_holdings_map().into_keys());
+    }
+    let marks = FinanceProvider::current_prices(provider, symbols).await;
+
+    let mut standings: Vec<Standing> = accounts
+        .iter()
+        .map(|(name, account)| Standing::new(name.clone(), account, &marks))
+        .collect();
+    if let Some(file) = current_file.filter(|_| includes_current) {
+        let name = Storage::portfolio_name(&format!("sqlite://{}", file.display()));
+        let mut standing = Standing::new(name, &lock_state(state), &marks);
+        standing.current = true;
+        standings.push(standing);
+    }
+    leaderboard::rank(&mut standings);
+
+    let mut lines = vec![leaderboard::format_board(&standings, &money)];
+    lines.extend(skipped);
+    lines.join("\n")
+}
+
+/// Rebuilds cash and holdings from the ledger and trade log and reports where the stored
+/// account differs
+/// Usage: replay [--apply]
+async fn handle_replay(
+    state: &Arc<Mutex<AppState>>,
+    db: &DatabaseConnection,
+    args: &[&str],
+) -> String {
+    let apply = match args {
+        [] => false,
+        ["--apply"] => true,
+        _ => return "Usage: replay [--apply]".to_string(),
+    };
+
+    let (mut lines, discrepancies) = {
+        let mut state_guard = lock_state(state);
+        let replayed = replay::replay_state(&state_guard);
+        let discrepancies = replay::compare(&state_guard, &replayed);
+        let money = state_guard.get_money_format();
+        let mut lines = vec![format!(
+            "Replayed {} cash flows and {} trades",
+            state_guard.get_cash_flows().len(),
+            state_guard.get_trades().len()
+        )];
+        for symbol in &replayed.oversold {
+            lines.push(format!(
+                "Warning: the log sells more {} than it held at the time",
+                symbol
+            ));
+        }
+        if discrepancies.is_empty() {
+            lines.push("Stored account matches the trade log".to_string());
+        } else {
+            lines.push(format!("{} discrepancies:", discrepancies.len()));
+            lines.extend(
+                discrepancies
+                    .iter()
+                    .map(|discrepancy| format!("  {}", discrepancy.describe(&money))),
+            );
+        }
+        if apply && !discrepancies.is_empty() {
+            replay::apply(&mut state_guard, &replayed);
+        }
+        (lines, discrepancies)
+    };
+
+    if discrepancies.is_empty() {
+        return lines.join("\n");
+    }
+    if apply {
+        Storage::save_state(state, db).await;
+        lines.push("Applied: cash and holdings now match the trade log".to_string());
+    } else {
+        lines.push("Run replay --apply to rebuild cash and holdings from the log".to_string());
+    }
+    lines.join("\n")
+}
+
+/// Runs random trades against the mock provider and reports timing and final state
+/// Usage: simulate <n> [seed]
+async fn handle_simulate(state: &Arc<Mutex<AppState>>, args: &[&str]) -> String {
+    const USAGE: &str = "Usage: simulate <n> [seed]";
+    let Some(raw) = args.first() else {
+        return USAGE.to_string();
+    };
+    let operations = match raw.parse::<usize>() {
+        Ok(n) if (1..=100_000).contains(&n) => n,
+        _ => return format!("Invalid operation count: '{}' (expected 1-100000)", raw),
+    };
+    let seed = match args.get(1) {
+        Some(raw) => match raw.parse::<u64>() {
+            Ok(seed) => seed,
+            Err(_) => return format!("Invalid seed: '{}'", raw),
+        },
+        None => 42,
+    };
+
+    let report = simulate::run_simulation(operations, seed).await;
+    let money = lock_state(state).get_money_format();
+    format!(
+        "Simulated {} operations in {} ms (seed {})\n\
+        Buys: {}, sells: {}, orders placed: {}, filled: {}, rejected: {}\n\
+        Trades: {}\n\
+        Final cash: {} (expected {}, {})",
+        report.operations,
+        report.elapsed.as_millis(),
+        seed,
+        report.buys,
+        report.sells,
+        report.orders_placed,
+        report.orders_filled,
+        report.rejected,
+        report.trades,
+        money.money(report.final_cash),
+        money.money(report.expected_cash),
+        if report.is_consistent() {
+            "consistent"
+        } else {
+            "MISMATCH"
+        }
+    )
 }
 
 /// Displays help information
-/// Usage: help
-fn handle_help() -> String {
+/// Usage: help [command]
+fn handle_help(args: &[&str]) -> String {
+    if let Some(topic) = args.first() {
+        let topic = topic.to_lowercase();
+        return match command_help(&topic) {
+            Some(help) => format_command_help(help),
+            None => format!("No help for '{}'", topic),
+        };
+    }
+
     String::from(
         "Available Commands:\n\n\
         ACCOUNT:\n\
         fund <amount>              - Add funds to account\n\
         withdraw <amount>          - Withdraw funds from account\n\
-        summary                    - Show summary of finances\n\
+        cashflow [export|import <path>] - List deposits/withdrawals and net deposited\n\
+        cap [<amount>|off]         - Limit total net deposits\n\
+        performance [--days N]     - Simple, time-weighted, and money-weighted returns\n\
+        summary [--json]           - Show summary of finances\n\
+        leverage <factor>          - Borrow on margin up to cash x factor (1 = off)\n\
+        cashyield <apy>            - Earn daily interest on idle cash (0 = off)\n\
+        holidays [add|remove <d>]  - List market holidays or add custom closures\n\
+        currency <symbol> [style]  - Display money with another symbol (no FX conversion)\n\
+        webhook [<url>|off]        - Post order fills to a URL\n\
         PRICES & WATCHLIST:\n\
-        price <symbol>             - Get current price for symbol\n\
-        addwatch <symbol>          - Add symbol to watchlist\n\
-        unwatch <symbol>           - Remove symbol from watchlist\n\n\
+        price <symbol> [--json]    - Get current price for symbol\n\
+        prices <sym1> <sym2> ...   - Quote several symbols with their day change\n\
+        search <query>             - Find symbols by company name or partial ticker\n\
+        watch <symbol>             - Follow one symbol's quote live (Esc to return)\n\
+        addwatch <symbol> [--force] - Add symbol to watchlist (checked against the provider)\n\
+        unwatch <symbol>           - Remove symbol from watchlist\n\
+        loadwatch <path>           - Add symbols listed in a text file to watchlist\n\
+        note <sym> [text|--clear]  - Show, set, or clear a symbol's note\n\
+        alias [name symbol]        - List or set your own spellings for symbols\n\
+        compare <s1> <s2> [--days N] - Compare relative performance (default 30 days)\n\
+        movers [--top N]           - Watchlist gainers and losers since previous close\n\
+        today [--top N]            - Day P&L with your biggest winning and losing holdings\n\
+        whatif <sym>=<±%> [...]    - Value the portfolio under hypothetical price moves\n\n\
         TRADING:\n\
         buy <symbol> <qty>         - Buy shares at market price\n\
         sell <symbol> <qty>        - Sell shares at market price\n\
+                                     (use $<amount> instead of <qty> to trade by dollar value)\n\
+                                     (add --tag <t> to label the trade with a strategy)\n\
+        close <symbol>             - Sell the entire position at market price\n\
+        closeall                   - Sell every position at market price\n\
+        target <sym> <pct>         - Set a target allocation weight (0 clears)\n\
+        rebalance [--execute]      - Suggest (or place) trades to reach target weights\n\
         buylimit <sym> <qty> <pr>  - Create buy limit order\n\
         stoploss <sym> <qty> <pr>  - Create stop loss order\n\
         takeprofit <sym> <qty> <pr> - Create take profit order\n\
-        trades                     - Show trade history\n\n\
+        selllimit <sym> <qty> <pr> - Create sell limit order (fills at or above the limit)\n\
+        modify <n> [price|qty <v>] - Change an open order in place\n\
+        simfill <n|order> <date>   - Would this order have filled that day?\n\
+        slippage <bps>             - Set market order slippage (0 = off)\n\
+        fee <flat> [bps]           - Set the fee charged per fill (0 = off)\n\
+        risklimit [position|order <v>] - Cap position concentration and buy size\n\
+        costbasis [fifo|lifo|average] - Set how realized P&L matches sells to buys\n\
+        shares [whole|fractional|N] - Round bought quantities (leftover stays as cash)\n\
+        split <sym> <new:old>      - Adjust a held position for a stock split\n\
+        template save|run|list|delete - Reuse multi-leg order structures\n\
+        recurring <sym> $<amt> <every> - Buy a fixed amount daily, weekly or monthly\n\
+        trades [--last N|--all]    - Show trade history (default: latest 50; --tag <t> filters)\n\
+        executed [symbol]          - List filled conditional orders vs their targets\n\
+        symbols                    - List every symbol held, watched, ordered or traded\n\
+        realized [--by-tag]        - Show realized P&L per symbol or per tag\n\
+        pnl [--tag <t>]            - Show unrealized, realized and total P&L\n\
+        taxreport <year> <path>    - Export the year's closed lots as CSV\n\
+        export-holdings <path>     - Export holdings with current prices as CSV\n\n\
         SYSTEM:\n\
-        import                     - Start the import process to load previous trades\n\
+        import [--dry-run]         - Start the import process to load previous trades\n\
         stopbg                     - Stop background orders\n\
         startbg                    - Start background orders\n\
+        offline / online           - Pause or resume all network fetches\n\
+        refresh [secs|monitor secs] - Set price refresh / order check intervals\n\
+        outputscroll [top|bottom|preserve] - Where the output pane scrolls after commands\n\
+        timezone [zone]            - Show times in local, UTC or a named zone\n\
+        inputlimit [chars]         - Longest command the input box accepts\n\
+        throttle [n ms]            - Limit concurrent lookups and their spacing\n\
+        providers [p1,p2,...]      - Show or set the price provider fallback chain\n\
+        undo                       - Reverse the last fund, withdraw, trade, or order\n\
         reset                      - Reset all data\n\
+        backup <path>              - Save the whole account to one file\n\
+        restore <path> [confirm]   - Replace the account with a backup\n\
+        simulate <n> [seed]        - Benchmark n random trades on a mock account\n\
+        replay [--apply]           - Check cash and holdings against the trade log\n\
+        leaderboard [dir]          - Rank saved portfolios by total return\n\
         clear                      - Clear screen\n\
-        help                       - Show this help\n\
+        help [command]             - Show this help, or details for one command\n\
         exit, quit                 - Exit application\n\n\
         NAVIGATION:\n\
+        Tab                        - Focus holdings / watchlist / input\n\
+        Up/Down                    - Select a row in the focused panel\n\
+        Ctrl+R                     - Refresh prices now\n\
+        Ctrl+O                     - Guided order entry (Esc cancels)\n\
         PgUp/PgDn                  - Scroll output\n\
         Ctrl+Home/Ctrl+End         - Output top/bottom",
     )
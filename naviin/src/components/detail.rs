@@ -0,0 +1,212 @@
+/// Detail Component - Drill-down view of one symbol
+///
+/// Opened with `i` on a selected holdings or watchlist row and drawn over the top
+/// row. Brings together the symbol's quote and day change, a sparkline of recent
+/// daily closes, the current position, and its note. Esc closes it.
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Stylize},
+    symbols::border,
+    text::{Line, Text},
+    widgets::{Block, Clear, Paragraph, Widget, Wrap},
+};
+use rust_decimal::Decimal;
+
+use crate::Finance::{self, AssetType, Holding, Symbol};
+use crate::format::{MoneyFormat, format_pct};
+
+/// Calendar days of closes fetched for the sparkline
+pub const DETAIL_HISTORY_DAYS: i64 = 30;
+
+/// Widest sparkline drawn, in columns
+const SPARKLINE_WIDTH: usize = 40;
+
+/// Component showing everything known about one symbol
+pub struct DetailComponent {
+    /// Symbol being shown
+    symbol: Symbol,
+    /// Latest price, None until the quote arrives
+    price: Option<Decimal>,
+    /// Previous close used for the day change
+    previous_close: Option<Decimal>,
+    /// Recent daily closes, oldest first; None while loading
+    closes: Option<Vec<Decimal>>,
+    /// Shares held, if any
+    position: Option<Holding>,
+    /// Note attached with `note`
+    note: Option<String>,
+    /// Configured currency display
+    money_format: MoneyFormat,
+}
+
+impl DetailComponent {
+    // SECTION: Constructor
+
+    /// Creates a detail view with the state-side data; the quote and history load later
+    pub fn new(
+        symbol: Symbol,
+        position: Option<Holding>,
+        note: Option<String>,
+        money_format: MoneyFormat,
+    ) -> Self {
+        Self {
+            symbol,
+            price: None,
+            previous_close: None,
+            closes: None,
+            position,
+            note,
+            money_format,
+        }
+    }
+
+    pub fn get_symbol(&self) -> &Symbol {
+        &self.symbol
+    }
+
+    // SECTION: Data Management
+
+    /// Stores a quote; a zero price means the lookup failed and is ignored
+    pub fn update_quote(&mut self, price: Decimal, previous_close: Decimal) {
+        if price <= Decimal::ZERO {
+            return;
+        }
+        self.price = Some(price);
+        if previous_close > Decimal::ZERO {
+            self.previous_close = Some(previous_close);
+        }
+    }
+
+    /// Stores the daily closes for the sparkline, oldest first
+    pub fn set_history(&mut self, closes: Vec<Decimal>) {
+        self.closes = Some(closes.into_iter().filter(|c| *c > Decimal::ZERO).collect());
+    }
+
+    /// Change from the previous close as (amount, percent)
+    pub fn day_change(&self) -> Option<(Decimal, Decimal)> {
+        let (price, previous_close) = (self.price?, self.previous_close?);
+        let pct = Finance::day_change_pct(price, previous_close)?;
+        Some((price - previous_close, pct))
+    }
+
+    // SECTION: Rendering
+
+    /// Every line of the pane, top to bottom
+    pub fn lines(&self) -> Vec<Line<'static>> {
+        let asset_type = AssetType::for_symbol(&self.symbol);
+        let money = &self.money_format;
+        let mut lines = Vec::new();
+
+        // Quote
+        match self.price {
+            Some(price) => lines.push(Line::from(money.money_price(price, asset_type)).bold()),
+            None => lines.push(Line::from("Loading quote...").dim()),
+        }
+        match (self.day_change(), self.previous_close) {
+            (Some((change, pct)), Some(previous_close)) => {
+                let (color, sign) = if change < Decimal::ZERO {
+                    (Color::Red, "-")
+                } else {
+                    (Color::Green, "+")
+                };
+                lines.push(
+                    Line::from(format!(
+                        "{}{} ({}) today, previous close {}",
+                        sign,
+                        money.money_price(change.abs(), asset_type),
+                        format_pct(pct),
+                        money.money_price(previous_close, asset_type)
+                    ))
+                    .fg(color),
+                );
+            }
+            _ if self.price.is_some() => lines.push(Line::from("Day change unavailable").dim()),
+            _ => {}
+        }
+        lines.push(Line::from(""));
+
+        // History
+        match &self.closes {
+            None => lines.push(Line::from("Loading history...").dim()),
+            Some(closes) if closes.len() < 2 => lines.push(Line::from("No recent history").dim()),
+            Some(closes) => {
+                let low = closes.iter().copied().min().unwrap_or_default();
+                let high = closes.iter().copied().max().unwrap_or_default();
+                lines.push(Line::from(format!(
+                    "{} days  {}",
+                    DETAIL_HISTORY_DAYS,
+                    Finance::ascii_sparkline(closes, low, high, SPARKLINE_WIDTH)
+                )));
+                lines.push(
+                    Line::from(format!(
+                        "Range {} - {}",
+                        money.money_price(low, asset_type),
+                        money.money_price(high, asset_type)
+                    ))
+                    .dim(),
+                );
+            }
+        }
+        lines.push(Line::from(""));
+
+        // Position
+        match &self.position {
+            Some(holding) => {
+                lines.push(Line::from(format!(
+                    "Position: {} @ {} avg",
+                    holding.get_qty(),
+                    money.money_price(holding.get_avg_price(), asset_type)
+                )));
+                if let Some(price) = self.price {
+                    let pnl = holding.pnl(price);
+                    let pct = holding
+                        .pnl_pct(price)
+                        .map(format_pct)
+                        .unwrap_or_else(|| "N/A".to_string());
+                    let color = if pnl < Decimal::ZERO {
+                        Color::Red
+                    } else {
+                        Color::Green
+                    };
+                    lines.push(
+                        Line::from(format!(
+                            "Value {}, P&L {} ({})",
+                            money.money(price * holding.get_qty()),
+                            money.money(pnl),
+                            pct
+                        ))
+                        .fg(color),
+                    );
+                }
+            }
+            None => lines.push(Line::from("No position").dim()),
+        }
+
+        // Note
+        match &self.note {
+            Some(note) => lines.push(Line::from(format!("Note: {}", note))),
+            None => lines.push(Line::from("No note").dim()),
+        }
+        lines
+    }
+}
+
+impl Widget for &DetailComponent {
+    /// Clears the area underneath so the pane reads as an overlay
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let block = Block::bordered()
+            .title(Line::from(format!(" {} ", self.symbol)).bold().cyan())
+            .title_bottom(
+                Line::from(vec![" Close ".into(), "<Esc>".blue().bold(), " ".into()]).centered(),
+            )
+            .border_set(border::ROUNDED)
+            .border_style(Color::Cyan);
+
+        Clear.render(area, buf);
+        Paragraph::new(Text::from(self.lines()))
+            .wrap(Wrap { trim: false })
+            .block(block)
+            .render(area, buf);
+    }
+}
@@ -1,19 +1,26 @@
 /// Holdings Component - Displays owned stock positions
 ///
-/// Shows current holdings with quantity, average cost, current price, and P&L.
+/// Shows current holdings with quantity, average cost, current price, day change, P&L,
+/// and P&L as a percentage of cost.
+/// The title carries a MARKET CLOSED badge outside US regular hours.
+/// The footer total is summed from the rounded P&L cells so it matches the rows.
+/// While the panel is active (Tab) the selected row is highlighted and its note, if any,
+/// is shown in the footer.
 use ratatui::{
     buffer::Buffer,
     layout::{Constraint, Rect},
     style::{Color, Style, Stylize},
     symbols::border,
-    text::{Line, Text},
-    widgets::{Block, Borders, Cell, Paragraph, Row, Table, TableState, Widget},
+    text::{Line, Span, Text},
+    widgets::{Block, Borders, Cell, Paragraph, Row, StatefulWidget, Table, TableState, Widget},
 };
 use rust_decimal::Decimal;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 use crate::Finance::{Holding, Symbol};
 use crate::FinanceProvider;
+use crate::format::{MoneyFormat, format_pct, format_qty, sum_rounded};
+use crate::market::MarketStatus;
 
 /// Component that displays holdings with real-time prices and P&L
 pub struct HoldingsComponent {
@@ -21,12 +28,24 @@ pub struct HoldingsComponent {
     holdings: HashMap<Symbol, Holding>,
     /// Cached prices for each holding
     prices: HashMap<Symbol, Decimal>,
+    /// Cached previous close for each holding, used for day change
+    previous_closes: HashMap<Symbol, Decimal>,
     /// Current selected row
     table_state: TableState,
     /// List of symbols for indexing (since HashMap is unordered)
     symbol_list: Vec<Symbol>,
     /// Cash balance
     cash: Decimal,
+    /// US equity market status, shown as a badge in the title
+    market_status: MarketStatus,
+    /// Configured currency display
+    money_format: MoneyFormat,
+    /// Per-symbol notes, the selected one is shown while active
+    notes: BTreeMap<Symbol, String>,
+    /// Whether keyboard navigation is on this panel
+    active: bool,
+    /// Offline: prices are the last ones fetched and marked stale
+    offline: bool,
 }
 
 impl HoldingsComponent {
@@ -37,9 +56,15 @@ impl HoldingsComponent {
         Self {
             holdings: HashMap::new(),
             prices: HashMap::new(),
+            previous_closes: HashMap::new(),
             table_state: TableState::default(),
             symbol_list: Vec::new(),
             cash: Decimal::ZERO,
+            market_status: MarketStatus::Open,
+            money_format: MoneyFormat::default(),
+            notes: BTreeMap::new(),
+            active: false,
+            offline: false,
         }
     }
 
@@ -51,11 +76,46 @@ impl HoldingsComponent {
 
     /// Updates holdings data and cash from state
     pub fn update_holdings(&mut self, holdings: HashMap<Symbol, Holding>, cash: Decimal) {
+        // HashMap order changes between rebuilds, so the selection follows the symbol
+        let previous = self.selected_symbol().cloned();
         self.holdings = holdings;
         self.cash = cash;
         self.symbol_list = self.holdings.keys().cloned().collect();
-        if !self.symbol_list.is_empty() && self.table_state.selected().is_none() {
-            self.table_state.select(Some(0));
+        super::follow_selection(
+            &mut self.table_state,
+            previous,
+            &self.symbol_list,
+            Clone::clone,
+        );
+    }
+
+    pub fn set_notes(&mut self, notes: BTreeMap<Symbol, String>) {
+        self.notes = notes;
+    }
+
+    pub fn set_active(&mut self, active: bool) {
+        self.active = active;
+    }
+
+    /// Symbol on the selected row, if any
+    pub fn selected_symbol(&self) -> Option<&Symbol> {
+        self.symbol_list.get(self.table_state.selected()?)
+    }
+
+    /// Moves the selection down one row, wrapping to the top
+    pub fn select_next(&mut self) {
+        if !self.symbol_list.is_empty() {
+            let next = self.table_state.selected().map_or(0, |i| i + 1);
+            self.table_state.select(Some(next % self.symbol_list.len()));
+        }
+    }
+
+    /// Moves the selection up one row, wrapping to the bottom
+    pub fn select_previous(&mut self) {
+        if !self.symbol_list.is_empty() {
+            let len = self.symbol_list.len();
+            let previous = self.table_state.selected().map_or(0, |i| i + len - 1);
+            self.table_state.select(Some(previous % len));
         }
     }
 
@@ -63,15 +123,126 @@ impl HoldingsComponent {
         self.prices = prices;
     }
 
+    /// Replaces only the prices given, keeping the rest of the cache
+    pub fn merge_prices(&mut self, prices: HashMap<Symbol, Decimal>) {
+        self.prices.extend(prices);
+    }
+
+    pub fn set_money_format(&mut self, money_format: MoneyFormat) {
+        self.money_format = money_format;
+    }
+
+    pub fn set_offline(&mut self, offline: bool) {
+        self.offline = offline;
+    }
+
+    pub fn set_market_status(&mut self, status: MarketStatus) {
+        self.market_status = status;
+    }
+
+    pub fn get_market_status(&self) -> &MarketStatus {
+        &self.market_status
+    }
+
+    pub fn update_previous_closes(&mut self, previous_closes: HashMap<Symbol, Decimal>) {
+        self.previous_closes = previous_closes;
+    }
+
+    pub fn merge_previous_closes(&mut self, previous_closes: HashMap<Symbol, Decimal>) {
+        self.previous_closes.extend(previous_closes);
+    }
+
+    /// Percentage change from previous close to current price
+    /// Returns None when either price is missing or the previous close is zero
+    fn day_change_pct(&self, symbol: &Symbol) -> Option<Decimal> {
+        let price = self.prices.get(symbol).copied()?;
+        let prev_close = self.previous_closes.get(symbol).copied()?;
+        crate::Finance::day_change_pct(price, prev_close)
+    }
+
+    /// Profit or loss for one holding at the cached price, None while the price is loading
+    fn pnl(&self, symbol: &Symbol) -> Option<Decimal> {
+        let holding = self.holdings.get(symbol)?;
+        let price = self.prices.get(symbol).copied()?;
+        Some(holding.pnl(price))
+    }
+
+    /// Return on cost for one holding, None while loading or when the cost is zero
+    pub fn pnl_pct(&self, symbol: &Symbol) -> Option<Decimal> {
+        let holding = self.holdings.get(symbol)?;
+        holding.pnl_pct(self.prices.get(symbol).copied()?)
+    }
+
+    /// Total P&L across priced holdings, the sum of the values shown in the P&L column
+    pub fn total_pnl(&self) -> Decimal {
+        sum_rounded(
+            self.symbol_list
+                .iter()
+                .filter_map(|symbol| self.pnl(symbol)),
+        )
+    }
+
+    pub fn cash(&self) -> Decimal {
+        self.cash
+    }
+
+    /// Cached prices by symbol, for valuing the account
+    pub fn prices(&self) -> &HashMap<Symbol, Decimal> {
+        &self.prices
+    }
+
+    /// Change in value since the previous close across holdings with both prices,
+    /// None when no holding has them yet
+    pub fn day_pnl(&self) -> Option<Decimal> {
+        let mut total = None;
+        for (symbol, holding) in &self.holdings {
+            let price = self.prices.get(symbol).copied();
+            let prev_close = self.previous_closes.get(symbol).copied();
+            if let (Some(price), Some(prev_close)) = (price, prev_close)
+                && prev_close > Decimal::ZERO
+            {
+                *total.get_or_insert(Decimal::ZERO) += holding.get_qty() * (price - prev_close);
+            }
+        }
+        total
+    }
+
     /// SECTION: Rendering
 
+    /// Block title with a red MARKET CLOSED badge when the US market isn't trading,
+    /// and a yellow STALE badge while offline
+    fn title_line(&self, text: String) -> Line<'static> {
+        let mut spans = vec![Span::from(text).bold()];
+        if self.offline {
+            spans.push(
+                Span::from(" STALE ")
+                    .bold()
+                    .fg(Color::Black)
+                    .bg(Color::Yellow),
+            );
+            spans.push(Span::from(" "));
+        }
+        if !self.market_status.is_open() {
+            spans.push(
+                Span::from(" MARKET CLOSED ")
+                    .bold()
+                    .fg(Color::White)
+                    .bg(Color::Red),
+            );
+            spans.push(Span::from(" "));
+        }
+        Line::from(spans)
+    }
+
     fn render_table(&self, area: Rect, buf: &mut Buffer) {
         let header = Row::new(vec![
             Cell::from("Symbol").style(Style::default().fg(Color::Yellow).bold()),
             Cell::from("Qty").style(Style::default().fg(Color::Yellow).bold()),
             Cell::from("Avg").style(Style::default().fg(Color::Yellow).bold()),
             Cell::from("Price").style(Style::default().fg(Color::Yellow).bold()),
+            Cell::from("Day").style(Style::default().fg(Color::Yellow).bold()),
             Cell::from("P&L").style(Style::default().fg(Color::Yellow).bold()),
+            Cell::from("P&L%").style(Style::default().fg(Color::Yellow).bold()),
         ])
         .height(1);
 
@@ -82,28 +253,59 @@ impl HoldingsComponent {
                 let holding = self.holdings.get(symbol).unwrap();
                 let qty = holding.get_qty();
                 let avg = holding.get_avg_price();
+                let asset_type = holding.get_asset_type();
                 let curr_price = self.prices.get(symbol).copied();
 
                 // Calculate P&L
-                let pnl = curr_price.map(|price| (price - avg) * qty);
+                let pnl = self.pnl(symbol);
                 let pnl_str = pnl
-                    .map(|value| format!("{:.2}", value))
+                    .map(|pnl| self.money_format.amount(pnl))
                     .unwrap_or_else(|| "Loading".to_string());
                 let pnl_color = if pnl.unwrap_or(Decimal::ZERO) >= Decimal::ZERO {
                     Color::Green
                 } else {
                     Color::Red
                 };
+                let pnl_pct = self.pnl_pct(symbol);
+                let pnl_pct_str = pnl_pct.map(format_pct).unwrap_or_else(|| "N/A".to_string());
+                let pnl_pct_color = match pnl_pct {
+                    Some(pct) if pct < Decimal::ZERO => Color::Red,
+                    Some(_) => Color::Green,
+                    None => Color::DarkGray,
+                };
                 let price_str = curr_price
-                    .map(|price| format!("{:.2}", price))
+                    .map(|price| self.money_format.price(price, asset_type))
                     .unwrap_or_else(|| "Loading".to_string());
 
+                // Day change against previous close
+                let day_change = self.day_change_pct(symbol);
+                let day_str = day_change
+                    .map(format_pct)
+                    .unwrap_or_else(|| "—".to_string());
+                let day_color = match day_change {
+                    Some(pct) if pct < Decimal::ZERO => Color::Red,
+                    Some(_) => Color::Green,
+                    None => Color::DarkGray,
+                };
+
+                // Crypto keeps trading while the stock market is closed
+                let symbol_cell = if asset_type.trades_24_7() {
+                    Cell::from(Line::from(vec![
+                        Span::from(symbol.clone()),
+                        Span::from(" 24/7").dim(),
+                    ]))
+                } else {
+                    Cell::from(symbol.clone())
+                };
+
                 let cells = vec![
-                    Cell::from(symbol.clone()),
-                    Cell::from(format!("{:.2}", qty)),
-                    Cell::from(format!("{:.2}", avg)),
+                    symbol_cell,
+                    Cell::from(format_qty(qty, asset_type)),
+                    Cell::from(self.money_format.price(avg, asset_type)),
                     Cell::from(price_str).style(Style::default().fg(Color::Green)),
+                    Cell::from(day_str).style(Style::default().fg(day_color)),
                     Cell::from(pnl_str).style(Style::default().fg(pnl_color)),
+                    Cell::from(pnl_pct_str).style(Style::default().fg(pnl_pct_color)),
                 ];
 
                 Row::new(cells).height(1)
@@ -111,29 +313,50 @@ impl HoldingsComponent {
             .collect();
 
         // Format title with cash balance
-        let title = format!(" Holdings | Cash: ${:.2} ", self.cash);
+        let title = format!(" Holdings | Cash: {} ", self.money_format.money(self.cash));
+
+        let mut block = Block::default()
+            .borders(Borders::ALL)
+            .border_set(border::ROUNDED)
+            .title(self.title_line(title))
+            .title_bottom(
+                Line::from(format!(
+                    " Total P&L: {} ",
+                    self.money_format.money(self.total_pnl())
+                ))
+                .right_aligned(),
+            );
+        if self.active {
+            block = block.border_style(Style::default().fg(Color::Cyan));
+            if let Some(note) = self.selected_symbol().and_then(|s| self.notes.get(s)) {
+                block = block.title_bottom(Line::from(format!(" Note: {} ", note)).left_aligned());
+            }
+        }
 
         let table = Table::new(
             rows,
             &[
-                Constraint::Percentage(20),
-                Constraint::Percentage(20),
-                Constraint::Percentage(20),
-                Constraint::Percentage(20),
-                Constraint::Percentage(20),
+                Constraint::Percentage(14),
+                Constraint::Percentage(12),
+                Constraint::Percentage(15),
+                Constraint::Percentage(15),
+                Constraint::Percentage(13),
+                Constraint::Percentage(17),
+                Constraint::Percentage(14),
             ],
         )
         .header(header)
-        .block(
-            Block::default()
-                .borders(Borders::ALL)
-                .border_set(border::ROUNDED)
-                .title(title.bold()),
-        )
+        .block(block)
         .row_highlight_style(Style::default().bg(Color::DarkGray).fg(Color::White))
         .highlight_symbol("> ");
 
-        table.render(area, buf);
+        // Only the active panel shows its selection
+        let mut table_state = if self.active {
+            self.table_state
+        } else {
+            TableState::default()
+        };
+        StatefulWidget::render(table, area, buf, &mut table_state);
     }
 }
 
@@ -153,7 +376,7 @@ impl Widget for &HoldingsComponent {
                     Block::default()
                         .borders(Borders::ALL)
                         .border_set(border::ROUNDED)
-                        .title(" Holdings ".bold()),
+                        .title(self.title_line(" Holdings ".to_string())),
                 )
                 .render(area, buf);
         } else {
@@ -2,10 +2,11 @@
 ///
 /// Handles user text input with cursor navigation.
 /// Provides a text field where users can type commands.
+/// The command is capped in length so a stuck key or a huge paste can't grow it without bound.
 use ratatui::{
     buffer::Buffer,
-    layout::Rect,
-    style::Stylize,
+    layout::{Position, Rect},
+    style::{Color, Stylize},
     symbols::border,
     text::{Line, Text},
     widgets::{Block, Paragraph, Widget},
@@ -17,8 +18,15 @@ pub struct InputComponent {
     command: String,
     /// Current cursor position (character index)
     cursor_position: usize,
+    /// Most characters the command may hold
+    max_len: usize,
+    /// Whether the last keystroke was dropped for hitting `max_len`
+    at_limit: bool,
 }
 
+/// Command length cap until one is configured
+pub const DEFAULT_MAX_COMMAND_LEN: usize = 512;
+
 impl Default for InputComponent {
     fn default() -> Self {
         Self::new()
@@ -33,24 +41,39 @@ impl InputComponent {
         Self {
             command: String::new(),
             cursor_position: 0,
+            max_len: DEFAULT_MAX_COMMAND_LEN,
+            at_limit: false,
         }
     }
 
+    /// Sets the command length cap; text already typed past it is kept
+    pub fn set_max_len(&mut self, max_len: usize) {
+        self.max_len = max_len;
+    }
+
     /// SECTION: Input Handling
 
     /// Adds a character at the current cursor position
     /// Moves cursor right after insertion
+    /// Ignored once the command is at its length cap; returns whether it was added
     ///
     /// # Arguments
     /// * `ch` - Character to insert
-    pub fn enter_char(&mut self, ch: char) {
+    pub fn enter_char(&mut self, ch: char) -> bool {
+        if self.command.chars().count() >= self.max_len {
+            self.at_limit = true;
+            return false;
+        }
+        self.at_limit = false;
         let index = self.byte_index();
         self.command.insert(index, ch);
         self.move_cursor_right();
+        true
     }
 
     /// Removes the character before the cursor (backspace)
     pub fn backspace(&mut self) {
+        self.at_limit = false;
         if self.cursor_position > 0 {
             self.move_cursor_left();
             let index = self.byte_index();
@@ -58,6 +81,14 @@ impl InputComponent {
         }
     }
 
+    /// Replaces the command with `text`, leaving the cursor at the end
+    /// Used to stage a command for the user to finish
+    pub fn set_command(&mut self, text: &str) {
+        self.command = text.chars().take(self.max_len).collect();
+        self.at_limit = false;
+        self.move_cursor_end();
+    }
+
     /// SECTION: Cursor Navigation
 
     /// Moves cursor one position to the left
@@ -81,7 +112,7 @@ impl InputComponent {
 
     /// Moves cursor to the end of the command
     pub fn move_cursor_end(&mut self) {
-        self.cursor_position = self.command.len();
+        self.cursor_position = self.command.chars().count();
     }
 
     /// SECTION: Query Methods
@@ -91,33 +122,79 @@ impl InputComponent {
         &self.command
     }
 
+    /// Whether input is being dropped because the command is at its cap
+    pub fn is_at_limit(&self) -> bool {
+        self.at_limit
+    }
+
     /// Clears the current command and resets cursor
     pub fn clear(&mut self) {
         self.command.clear();
         self.cursor_position = 0;
+        self.at_limit = false;
+    }
+
+    /// Returns the terminal cell where the cursor belongs when rendered in `area`
+    /// Accounts for the border and any horizontal scrolling of long commands
+    ///
+    /// # Arguments
+    /// * `area` - The same area the component is rendered into
+    pub fn cursor_screen_position(&self, area: Rect) -> Position {
+        let inner_width = area.width.saturating_sub(2) as usize;
+        let scroll = self.horizontal_scroll(inner_width);
+        let column = (self.cursor_position - scroll) as u16;
+        let x = (area.x + 1 + column).min(area.right().saturating_sub(2));
+        Position::new(x, area.y + 1)
     }
 
     /// SECTION: Helper Methods
 
     /// Converts character index to byte index for string operations
+    /// Falls back to the end of the string when the cursor is past the last character
     fn byte_index(&self) -> usize {
-        self.cursor_position
+        self.command
+            .char_indices()
+            .map(|(i, _)| i)
+            .nth(self.cursor_position)
+            .unwrap_or(self.command.len())
+    }
+
+    /// Number of leading characters hidden so the cursor stays inside the box
+    fn horizontal_scroll(&self, inner_width: usize) -> usize {
+        if inner_width == 0 {
+            return 0;
+        }
+        self.cursor_position.saturating_sub(inner_width - 1)
     }
 
     /// Ensures cursor position stays within valid bounds
     fn clamp_cursor(&self, new_cursor_pos: usize) -> usize {
-        new_cursor_pos.clamp(0, self.command.len())
+        new_cursor_pos.clamp(0, self.command.chars().count())
     }
 }
 
 impl Widget for &InputComponent {
-    /// Renders the input area with the command text and cursor
+    /// Renders the input area with the command text
+    /// Long commands are scrolled horizontally to keep the cursor visible
     fn render(self, area: Rect, buf: &mut Buffer) {
-        let text = Text::from(Line::from(self.command.clone()));
-
-        let block = Block::bordered()
-            .title(" Command ".bold())
-            .border_set(border::ROUNDED);
+        let title = if self.at_limit {
+            format!(" Command (limit: {} characters) ", self.max_len)
+                .bold()
+                .fg(Color::Yellow)
+        } else {
+            " Command ".bold()
+        };
+        let block = Block::bordered().title(title).border_set(border::ROUNDED);
+
+        let inner_width = block.inner(area).width as usize;
+        let scroll = self.horizontal_scroll(inner_width);
+        let visible: String = self
+            .command
+            .chars()
+            .skip(scroll)
+            .take(inner_width)
+            .collect();
+        let text = Text::from(Line::from(visible));
 
         Paragraph::new(text).block(block).render(area, buf);
     }
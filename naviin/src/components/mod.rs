@@ -2,8 +2,38 @@
 ///
 /// Contains reusable UI components for the TUI application.
 /// Each component handles its own display logic and state management.
+use ratatui::widgets::TableState;
+
+pub mod detail;
 pub mod holdings;
 pub mod input;
 pub mod open_orders;
+pub mod order_wizard;
 pub mod output;
+pub mod quote;
+pub mod status_bar;
 pub mod watchlist;
+
+/// Keeps the highlight on the same row after a list is rebuilt
+/// Finds the previously selected key in the new rows; if it is gone, clamps the old
+/// index to the new length. `previous` is the key of the row selected before the update
+pub(crate) fn follow_selection<T, K: PartialEq>(
+    table_state: &mut TableState,
+    previous: Option<K>,
+    rows: &[T],
+    key: impl Fn(&T) -> K,
+) {
+    if rows.is_empty() {
+        table_state.select(None);
+        return;
+    }
+    let index = match (table_state.selected(), previous) {
+        (Some(index), Some(previous)) => rows
+            .iter()
+            .position(|row| key(row) == previous)
+            .unwrap_or(index.min(rows.len() - 1)),
+        (Some(index), None) => index.min(rows.len() - 1),
+        (None, _) => 0,
+    };
+    table_state.select(Some(index));
+}
@@ -1,6 +1,7 @@
 /// Open Orders Component - Displays pending orders
 ///
-/// Shows all open/pending orders (BuyLimit, StopLoss, TakeProfit) with details.
+/// Shows all open/pending orders (BuyLimit, StopLoss, TakeProfit, SellLimit) with details,
+/// including the fee each is estimated to pay when it fills.
 use ratatui::{
     buffer::Buffer,
     layout::{Constraint, Rect},
@@ -10,7 +11,18 @@ use ratatui::{
     widgets::{Block, Borders, Cell, Paragraph, Row, Table, TableState, Widget},
 };
 
+use crate::Finance::{AssetType, FeeModel};
 use crate::Orders::{OpenOrder, OrderType};
+use crate::format::{MoneyFormat, format_qty};
+
+// Identifies an order across refreshes; modify changes price and quantity but not these
+fn order_key(order: &OpenOrder) -> (String, OrderType, i64) {
+    (
+        order.get_symbol().clone(),
+        order.get_order_type(),
+        order.get_timestamp(),
+    )
+}
 
 /// Component that displays open orders
 pub struct OpenOrdersComponent {
@@ -18,6 +30,10 @@ pub struct OpenOrdersComponent {
     orders: Vec<OpenOrder>,
     /// Current selected row
     table_state: TableState,
+    /// Configured currency display
+    money_format: MoneyFormat,
+    /// Commission model the estimated fees come from
+    fee_model: FeeModel,
 }
 
 impl OpenOrdersComponent {
@@ -28,51 +44,84 @@ impl OpenOrdersComponent {
         Self {
             orders: Vec::new(),
             table_state: TableState::default(),
+            money_format: MoneyFormat::default(),
+            fee_model: FeeModel::default(),
         }
     }
 
     /// SECTION: Data Management
 
-    /// Updates the orders list
+    /// Updates the orders list, keeping the selected order selected
     pub fn update_orders(&mut self, orders: Vec<OpenOrder>) {
+        let previous = self.selected_order().map(order_key);
         self.orders = orders;
-        if !self.orders.is_empty() && self.table_state.selected().is_none() {
-            self.table_state.select(Some(0));
+        super::follow_selection(&mut self.table_state, previous, &self.orders, order_key);
+    }
+
+    /// Order on the selected row, if any
+    pub fn selected_order(&self) -> Option<&OpenOrder> {
+        self.orders.get(self.table_state.selected()?)
+    }
+
+    pub fn set_money_format(&mut self, money_format: MoneyFormat) {
+        self.money_format = money_format;
+    }
+
+    pub fn set_fee_model(&mut self, fee_model: FeeModel) {
+        self.fee_model = fee_model;
+    }
+
+    /// Estimated fee for the order filling at its order price, "-" when trading is free
+    pub fn estimated_fee_cell(&self, order: &OpenOrder) -> String {
+        if self.fee_model.is_free() {
+            return "-".to_string();
         }
+        let fee = self
+            .fee_model
+            .fee_for(order.get_qty() * order.get_price_per());
+        self.money_format.money(fee)
     }
 
     /// SECTION: Rendering
 
     fn render_table(&self, area: Rect, buf: &mut Buffer) {
         let header = Row::new(vec![
+            Cell::from("#").style(Style::default().fg(Color::Yellow).bold()),
             Cell::from("Type").style(Style::default().fg(Color::Yellow).bold()),
             Cell::from("Symbol").style(Style::default().fg(Color::Yellow).bold()),
             Cell::from("Qty").style(Style::default().fg(Color::Yellow).bold()),
             Cell::from("Price").style(Style::default().fg(Color::Yellow).bold()),
+            Cell::from("Est. Fee").style(Style::default().fg(Color::Yellow).bold()),
         ])
         .height(1);
 
         let rows: Vec<Row> = self
             .orders
             .iter()
-            .map(|order| {
+            .enumerate()
+            .map(|(idx, order)| {
                 let order_type = order.get_order_type();
                 let symbol = order.get_symbol();
                 let qty = order.get_qty();
                 let price = order.get_price_per();
+                let asset_type = AssetType::for_symbol(symbol);
 
                 // Color based on order type
                 let type_color = match order_type {
                     OrderType::BuyLimit => Color::Green,
                     OrderType::StopLoss => Color::Red,
                     OrderType::TakeProfit => Color::Blue,
+                    OrderType::SellLimit => Color::Magenta,
                 };
 
+                // Numbered from 1, the position `modify <n>` refers to
                 let cells = vec![
+                    Cell::from((idx + 1).to_string()).dim(),
                     Cell::from(format!("{:?}", order_type)).style(Style::default().fg(type_color)),
                     Cell::from(symbol.clone()),
-                    Cell::from(format!("{:.2}", qty)),
-                    Cell::from(format!("{:.2}", price)),
+                    Cell::from(format_qty(qty, asset_type)),
+                    Cell::from(self.money_format.price(price, asset_type)),
+                    Cell::from(self.estimated_fee_cell(order)).dim(),
                 ];
 
                 Row::new(cells).height(1)
@@ -82,10 +131,12 @@ impl OpenOrdersComponent {
         let table = Table::new(
             rows,
             &[
-                Constraint::Percentage(25),
-                Constraint::Percentage(25),
-                Constraint::Percentage(25),
-                Constraint::Percentage(25),
+                Constraint::Length(3),
+                Constraint::Percentage(20),
+                Constraint::Percentage(20),
+                Constraint::Percentage(20),
+                Constraint::Percentage(20),
+                Constraint::Percentage(20),
             ],
         )
         .header(header)
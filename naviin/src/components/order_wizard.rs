@@ -0,0 +1,456 @@
+/// Order Wizard Component - Guided order entry drawn over the top row
+///
+/// Opened with Ctrl+O. Steps through order type, symbol (Tab completes from holdings
+/// and the watchlist), quantity and, for resting orders, the trigger price. Each field
+/// is checked before moving on and the estimated cost or proceeds is shown against cash
+/// or shares held as it is typed. Finishing builds the same command line a user would
+/// type, e.g. `buylimit AAPL 10 145`, so orders go through the normal command path.
+/// Backspace on an empty field steps back; Esc cancels at any step.
+use crossterm::event::KeyCode;
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Stylize},
+    symbols::border,
+    text::{Line, Text},
+    widgets::{Block, Clear, Paragraph, Widget, Wrap},
+};
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+
+use crate::Finance::{AssetType, Symbol};
+use crate::format::{MoneyFormat, format_qty};
+use crate::import::is_valid_symbol;
+
+/// Most completion candidates listed under the symbol field
+const MAX_MATCHES: usize = 5;
+
+/// Order the wizard can place, each matching a typed command
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WizardOrder {
+    Buy,
+    Sell,
+    BuyLimit,
+    StopLoss,
+    TakeProfit,
+    SellLimit,
+}
+
+impl WizardOrder {
+    /// Every order type, in the order they are listed
+    pub const ALL: [WizardOrder; 6] = [
+        WizardOrder::Buy,
+        WizardOrder::Sell,
+        WizardOrder::BuyLimit,
+        WizardOrder::StopLoss,
+        WizardOrder::TakeProfit,
+        WizardOrder::SellLimit,
+    ];
+
+    /// Command the order is submitted as
+    pub fn command(&self) -> &'static str {
+        match self {
+            WizardOrder::Buy => "buy",
+            WizardOrder::Sell => "sell",
+            WizardOrder::BuyLimit => "buylimit",
+            WizardOrder::StopLoss => "stoploss",
+            WizardOrder::TakeProfit => "takeprofit",
+            WizardOrder::SellLimit => "selllimit",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            WizardOrder::Buy => "Market buy",
+            WizardOrder::Sell => "Market sell",
+            WizardOrder::BuyLimit => "Buy limit",
+            WizardOrder::StopLoss => "Stop loss",
+            WizardOrder::TakeProfit => "Take profit",
+            WizardOrder::SellLimit => "Sell limit",
+        }
+    }
+
+    /// Resting orders take a trigger price; market orders fill at the quote
+    pub fn needs_price(&self) -> bool {
+        !matches!(self, WizardOrder::Buy | WizardOrder::Sell)
+    }
+
+    /// Whether the order spends cash rather than selling shares
+    pub fn is_buy(&self) -> bool {
+        matches!(self, WizardOrder::Buy | WizardOrder::BuyLimit)
+    }
+}
+
+/// Field the wizard is asking for
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WizardStep {
+    OrderType,
+    Symbol,
+    Quantity,
+    Price,
+}
+
+/// What the TUI should do after a key
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum WizardAction {
+    /// Keep the wizard open
+    Continue,
+    /// Close without placing anything
+    Cancel,
+    /// Fetch a quote for the estimate, then keep the wizard open
+    Quote(Symbol),
+    /// Close and run this command line
+    Submit(String),
+}
+
+/// Component holding the order being built
+pub struct OrderWizardComponent {
+    /// Field being filled in
+    step: WizardStep,
+    /// Index into `WizardOrder::ALL`
+    order_index: usize,
+    symbol: String,
+    quantity: String,
+    price: String,
+    /// Why the last Enter didn't move on
+    error: Option<String>,
+    /// Symbols offered for completion: holdings then watchlist
+    known_symbols: Vec<Symbol>,
+    /// Shares held per symbol, for sell estimates
+    held: HashMap<Symbol, Decimal>,
+    /// Cash available for buys
+    cash: Decimal,
+    /// Latest quote for the chosen symbol, used when no price is typed
+    quote: Option<(Symbol, Decimal)>,
+    /// Configured currency display
+    money_format: MoneyFormat,
+}
+
+impl OrderWizardComponent {
+    // SECTION: Constructor
+
+    /// Starts at the order type; a `symbol` (e.g. the selected row) is filled in ahead
+    pub fn new(
+        known_symbols: Vec<Symbol>,
+        held: HashMap<Symbol, Decimal>,
+        cash: Decimal,
+        money_format: MoneyFormat,
+        symbol: Option<Symbol>,
+    ) -> Self {
+        Self {
+            step: WizardStep::OrderType,
+            order_index: 0,
+            symbol: symbol.unwrap_or_default(),
+            quantity: String::new(),
+            price: String::new(),
+            error: None,
+            known_symbols,
+            held,
+            cash,
+            quote: None,
+            money_format,
+        }
+    }
+
+    pub fn step(&self) -> WizardStep {
+        self.step
+    }
+
+    pub fn order(&self) -> WizardOrder {
+        WizardOrder::ALL[self.order_index]
+    }
+
+    pub fn symbol(&self) -> &str {
+        &self.symbol
+    }
+
+    pub fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+
+    // SECTION: Data Management
+
+    /// Stores a quote for the estimate; a zero price means the lookup failed
+    pub fn set_quote(&mut self, symbol: Symbol, price: Decimal) {
+        if price > Decimal::ZERO {
+            self.quote = Some((symbol, price));
+        }
+    }
+
+    /// Known symbols starting with what has been typed, at most `MAX_MATCHES`
+    pub fn completions(&self) -> Vec<&Symbol> {
+        let typed = self.symbol.to_uppercase();
+        self.known_symbols
+            .iter()
+            .filter(|symbol| symbol.starts_with(&typed))
+            .take(MAX_MATCHES)
+            .collect()
+    }
+
+    // SECTION: Key Handling
+
+    /// Applies one key press to the current step
+    pub fn handle_key(&mut self, code: KeyCode) -> WizardAction {
+        if code == KeyCode::Esc {
+            return WizardAction::Cancel;
+        }
+        match self.step {
+            WizardStep::OrderType => match code {
+                KeyCode::Up | KeyCode::Left => {
+                    self.order_index =
+                        (self.order_index + WizardOrder::ALL.len() - 1) % WizardOrder::ALL.len();
+                }
+                KeyCode::Down | KeyCode::Right | KeyCode::Tab => {
+                    self.order_index = (self.order_index + 1) % WizardOrder::ALL.len();
+                }
+                KeyCode::Enter => self.step = WizardStep::Symbol,
+                _ => {}
+            },
+            WizardStep::Symbol => match code {
+                KeyCode::Tab => {
+                    if let Some(first) = self.completions().first() {
+                        self.symbol = first.to_string();
+                    }
+                }
+                KeyCode::Enter => return self.confirm_symbol(),
+                KeyCode::Backspace if self.symbol.is_empty() => self.back(),
+                KeyCode::Backspace => {
+                    self.symbol.pop();
+                }
+                KeyCode::Char(c) if !c.is_whitespace() => self.symbol.push(c.to_ascii_uppercase()),
+                _ => {}
+            },
+            WizardStep::Quantity => match code {
+                KeyCode::Enter => return self.confirm_quantity(),
+                KeyCode::Backspace if self.quantity.is_empty() => self.back(),
+                KeyCode::Backspace => {
+                    self.quantity.pop();
+                }
+                KeyCode::Char(c) if c.is_ascii_digit() || c == '.' => self.quantity.push(c),
+                _ => {}
+            },
+            WizardStep::Price => match code {
+                KeyCode::Enter => return self.confirm_price(),
+                KeyCode::Backspace if self.price.is_empty() => self.back(),
+                KeyCode::Backspace => {
+                    self.price.pop();
+                }
+                KeyCode::Char(c) if c.is_ascii_digit() || c == '.' => self.price.push(c),
+                _ => {}
+            },
+        }
+        WizardAction::Continue
+    }
+
+    fn back(&mut self) {
+        self.error = None;
+        self.step = match self.step {
+            WizardStep::OrderType | WizardStep::Symbol => WizardStep::OrderType,
+            WizardStep::Quantity => WizardStep::Symbol,
+            WizardStep::Price => WizardStep::Quantity,
+        };
+    }
+
+    fn confirm_symbol(&mut self) -> WizardAction {
+        if !is_valid_symbol(&self.symbol) {
+            self.error = Some("Enter a symbol such as AAPL or BTC-USD".to_string());
+            return WizardAction::Continue;
+        }
+        self.error = None;
+        self.step = WizardStep::Quantity;
+        match &self.quote {
+            Some((symbol, _)) if *symbol == self.symbol => WizardAction::Continue,
+            _ => {
+                self.quote = None;
+                WizardAction::Quote(self.symbol.clone())
+            }
+        }
+    }
+
+    fn confirm_quantity(&mut self) -> WizardAction {
+        let Some(quantity) = positive(&self.quantity) else {
+            self.error = Some("Quantity must be a number greater than zero".to_string());
+            return WizardAction::Continue;
+        };
+        let held = self.held_qty();
+        if !self.order().is_buy() && quantity > held {
+            self.error = Some(format!(
+                "Only {} {} held",
+                format_qty(held, AssetType::for_symbol(&self.symbol)),
+                self.symbol
+            ));
+            return WizardAction::Continue;
+        }
+        self.error = None;
+        if self.order().needs_price() {
+            self.step = WizardStep::Price;
+            return WizardAction::Continue;
+        }
+        WizardAction::Submit(self.command_line())
+    }
+
+    fn confirm_price(&mut self) -> WizardAction {
+        if positive(&self.price).is_none() {
+            self.error = Some("Price must be a number greater than zero".to_string());
+            return WizardAction::Continue;
+        }
+        self.error = None;
+        WizardAction::Submit(self.command_line())
+    }
+
+    /// The command the finished order runs as
+    pub fn command_line(&self) -> String {
+        let mut line = format!(
+            "{} {} {}",
+            self.order().command(),
+            self.symbol,
+            self.quantity
+        );
+        if self.order().needs_price() {
+            line.push(' ');
+            line.push_str(&self.price);
+        }
+        line
+    }
+
+    fn held_qty(&self) -> Decimal {
+        self.held.get(&self.symbol).copied().unwrap_or_default()
+    }
+
+    // SECTION: Rendering
+
+    /// Estimated cost or proceeds against cash or shares held, None until a
+    /// quantity has been typed
+    pub fn estimate(&self) -> Option<String> {
+        let quantity = positive(&self.quantity)?;
+        let asset_type = AssetType::for_symbol(&self.symbol);
+        let money = &self.money_format;
+        let unit_price = positive(&self.price)
+            .filter(|_| self.order().needs_price())
+            .or(self
+                .quote
+                .as_ref()
+                .filter(|(symbol, _)| *symbol == self.symbol)
+                .map(|(_, price)| *price));
+        let Some(unit_price) = unit_price else {
+            return Some("Estimate: waiting for a price".to_string());
+        };
+        let total = quantity * unit_price;
+        if self.order().is_buy() {
+            let fit = if total <= self.cash {
+                "affordable".to_string()
+            } else {
+                format!("short by {}", money.money(total - self.cash))
+            };
+            Some(format!(
+                "Estimated cost: {} of {} cash ({})",
+                money.money(total),
+                money.money(self.cash),
+                fit
+            ))
+        } else {
+            Some(format!(
+                "Estimated proceeds: {} ({} held)",
+                money.money(total),
+                format_qty(self.held_qty(), asset_type)
+            ))
+        }
+    }
+
+    /// Every line of the overlay, top to bottom
+    pub fn lines(&self) -> Vec<Line<'static>> {
+        let mut lines = Vec::new();
+        let field = |label: &str, value: &str, active: bool| {
+            let text = if active {
+                format!("> {label}: {value}_")
+            } else {
+                format!("  {label}: {value}")
+            };
+            if active {
+                Line::from(text).bold()
+            } else {
+                Line::from(text)
+            }
+        };
+
+        if self.step == WizardStep::OrderType {
+            lines.push(Line::from("Order type (Up/Down, Enter):").bold());
+            for (index, order) in WizardOrder::ALL.iter().enumerate() {
+                if index == self.order_index {
+                    lines.push(Line::from(format!("> {}", order.label())).bold().cyan());
+                } else {
+                    lines.push(Line::from(format!("  {}", order.label())));
+                }
+            }
+        } else {
+            lines.push(Line::from(format!(
+                "  Order type: {}",
+                self.order().label()
+            )));
+            lines.push(field(
+                "Symbol",
+                &self.symbol,
+                self.step == WizardStep::Symbol,
+            ));
+            if self.step == WizardStep::Symbol {
+                let matches = self.completions();
+                if !matches.is_empty() {
+                    let names: Vec<&str> = matches.iter().map(|s| s.as_str()).collect();
+                    lines.push(Line::from(format!("    Tab: {}", names.join(", "))).dim());
+                }
+            }
+            if self.step != WizardStep::Symbol {
+                lines.push(field(
+                    "Quantity",
+                    &self.quantity,
+                    self.step == WizardStep::Quantity,
+                ));
+            }
+            if self.step == WizardStep::Price {
+                lines.push(field("Price", &self.price, true));
+            }
+            if let Some(estimate) = self.estimate() {
+                lines.push(Line::from(""));
+                lines.push(Line::from(estimate));
+            }
+        }
+        if let Some(error) = &self.error {
+            lines.push(Line::from(""));
+            lines.push(Line::from(error.clone()).red());
+        }
+        lines
+    }
+}
+
+// A typed number above zero, None while blank or malformed
+fn positive(value: &str) -> Option<Decimal> {
+    value
+        .parse::<Decimal>()
+        .ok()
+        .filter(|amount| *amount > Decimal::ZERO)
+}
+
+impl Widget for &OrderWizardComponent {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let block = Block::bordered()
+            .title(Line::from(" New Order ").bold().cyan())
+            .title_bottom(
+                Line::from(vec![
+                    " Back ".into(),
+                    "<Backspace>".blue().bold(),
+                    " Cancel ".into(),
+                    "<Esc>".blue().bold(),
+                    " ".into(),
+                ])
+                .centered(),
+            )
+            .border_set(border::ROUNDED)
+            .border_style(Color::Cyan);
+
+        Clear.render(area, buf);
+        Paragraph::new(Text::from(self.lines()))
+            .wrap(Wrap { trim: false })
+            .block(block)
+            .render(area, buf);
+    }
+}
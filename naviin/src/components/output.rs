@@ -1,7 +1,10 @@
 /// Output Component - Command results display area
 ///
 /// Displays the output and results from executed commands.
-/// Shows command history and responses in a scrollable format.
+/// Shows command history followed by the latest response in one scrollable view.
+use std::cell::Cell;
+use std::collections::VecDeque;
+
 use ratatui::{
     buffer::Buffer,
     layout::Rect,
@@ -11,14 +14,58 @@ use ratatui::{
     widgets::{Block, Paragraph, Widget},
 };
 
+use crate::format::{DOWN_MARKER, UP_MARKER};
+
+/// Number of past outputs kept unless configured otherwise
+pub const DEFAULT_HISTORY_LIMIT: usize = 500;
+
+/// Where the pane scrolls when a command's output replaces the current one
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ScrollAfterCommand {
+    /// The new output starts at the top of the pane
+    #[default]
+    Top,
+    /// The last line of the new output is at the bottom of the pane
+    Bottom,
+    /// The view stays on the lines it was showing
+    Preserve,
+}
+
+impl ScrollAfterCommand {
+    /// Parses `top`, `bottom` or `preserve`, case-insensitively
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "top" => Some(Self::Top),
+            "bottom" => Some(Self::Bottom),
+            "preserve" => Some(Self::Preserve),
+            _ => None,
+        }
+    }
+
+    /// Name as persisted and accepted by `outputscroll`
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Top => "top",
+            Self::Bottom => "bottom",
+            Self::Preserve => "preserve",
+        }
+    }
+}
+
 /// Component for displaying command output and results
 pub struct OutputComponent {
     /// The current output text to display
     output_text: String,
-    /// History of previous outputs
-    history: Vec<String>,
-    /// Current scroll offset (how many lines scrolled down)
+    /// History of previous outputs, oldest first
+    history: VecDeque<String>,
+    /// Maximum number of entries kept in history
+    history_limit: usize,
+    /// Current scroll offset (how many lines scrolled down the combined view)
     scroll_offset: usize,
+    /// Rows visible inside the border at the last render, used to clamp scrolling
+    visible_lines: Cell<usize>,
+    /// Where new output scrolls the pane
+    scroll_after: ScrollAfterCommand,
 }
 
 impl Default for OutputComponent {
@@ -34,21 +81,37 @@ impl OutputComponent {
     pub fn new() -> Self {
         Self {
             output_text: String::new(),
-            history: Vec::new(),
+            history: VecDeque::new(),
+            history_limit: DEFAULT_HISTORY_LIMIT,
             scroll_offset: 0,
+            visible_lines: Cell::new(0),
+            scroll_after: ScrollAfterCommand::default(),
+        }
+    }
+
+    /// Creates an output component that keeps at most `limit` past outputs
+    pub fn with_history_limit(limit: usize) -> Self {
+        Self {
+            history_limit: limit,
+            ..Self::new()
         }
     }
 
     /// SECTION: Output Management
 
     /// Sets the current output text to display
-    /// Resets scroll position to show the beginning of new content
+    /// Scrolls as the configured `ScrollAfterCommand` says, by default so the new
+    /// content starts at the top of the pane
     ///
     /// # Arguments
     /// * `text` - The output text to show
     pub fn set_output(&mut self, text: String) {
         self.output_text = text;
-        self.reset_scroll();
+        match self.scroll_after {
+            ScrollAfterCommand::Top => self.reset_scroll(),
+            ScrollAfterCommand::Bottom => self.scroll_to_bottom(),
+            ScrollAfterCommand::Preserve => {}
+        }
     }
 
     /// Appends text to the current output
@@ -63,9 +126,44 @@ impl OutputComponent {
     }
 
     /// Adds current output to history and clears display
+    /// Drops the oldest entries once the history limit is exceeded
     pub fn commit_to_history(&mut self) {
         if !self.output_text.is_empty() {
-            self.history.push(self.output_text.clone());
+            self.history
+                .push_back(std::mem::take(&mut self.output_text));
+        }
+        self.trim_history();
+    }
+
+    /// Removes every past output from history
+    pub fn clear_history(&mut self) {
+        self.history.clear();
+        self.scroll_offset = 0;
+    }
+
+    /// Changes where the pane scrolls when the next output is set
+    pub fn set_scroll_after(&mut self, scroll_after: ScrollAfterCommand) {
+        self.scroll_after = scroll_after;
+    }
+
+    pub fn get_scroll_after(&self) -> ScrollAfterCommand {
+        self.scroll_after
+    }
+
+    /// Changes the history limit, trimming existing entries if needed
+    pub fn set_history_limit(&mut self, limit: usize) {
+        self.history_limit = limit;
+        self.trim_history();
+    }
+
+    /// Drops the oldest history entries beyond the limit
+    /// Shifts the scroll offset so the same lines stay in view
+    fn trim_history(&mut self) {
+        while self.history.len() > self.history_limit {
+            if let Some(dropped) = self.history.pop_front() {
+                let removed = dropped.lines().count() + 1;
+                self.scroll_offset = self.scroll_offset.saturating_sub(removed);
+            }
         }
     }
 
@@ -81,11 +179,43 @@ impl OutputComponent {
         &self.output_text
     }
 
-    /// Returns the full output history
-    pub fn get_history(&self) -> &[String] {
+    /// Returns the retained output history, oldest first
+    pub fn get_history(&self) -> &VecDeque<String> {
         &self.history
     }
 
+    /// Returns the maximum number of history entries kept
+    pub fn get_history_limit(&self) -> usize {
+        self.history_limit
+    }
+
+    /// Returns every line shown in the pane: history entries, then the current output
+    /// Entries are separated by a blank line
+    pub fn combined_lines(&self) -> Vec<&str> {
+        let mut lines = Vec::new();
+        for entry in &self.history {
+            lines.extend(entry.lines());
+            lines.push("");
+        }
+        lines.extend(self.output_text.lines());
+        lines
+    }
+
+    /// Line index in the combined view where the current output begins
+    pub fn current_start_line(&self) -> usize {
+        self.history
+            .iter()
+            .map(|entry| entry.lines().count() + 1)
+            .sum()
+    }
+
+    /// Scroll offset actually applied for a pane showing `visible_lines` rows
+    pub fn effective_scroll(&self, visible_lines: usize) -> usize {
+        let total_lines = self.combined_lines().len();
+        self.scroll_offset
+            .min(total_lines.saturating_sub(visible_lines))
+    }
+
     /// SECTION: Scrolling
 
     /// Scrolls the output up by the specified number of lines
@@ -93,7 +223,9 @@ impl OutputComponent {
     /// # Arguments
     /// * `lines` - Number of lines to scroll up
     pub fn scroll_up(&mut self, lines: usize) {
-        self.scroll_offset = self.scroll_offset.saturating_sub(lines);
+        self.scroll_offset = self
+            .effective_scroll(self.visible_lines.get())
+            .saturating_sub(lines);
     }
 
     /// Scrolls the output down by the specified number of lines
@@ -101,7 +233,9 @@ impl OutputComponent {
     /// # Arguments
     /// * `lines` - Number of lines to scroll down
     pub fn scroll_down(&mut self, lines: usize) {
-        self.scroll_offset += lines;
+        self.scroll_offset = self
+            .effective_scroll(self.visible_lines.get())
+            .saturating_add(lines);
     }
 
     /// Scrolls to the top of the output
@@ -109,33 +243,28 @@ impl OutputComponent {
         self.scroll_offset = 0;
     }
 
-    /// Scrolls to the bottom of the output
+    /// Scrolls to the bottom of the output so the newest line is visible
     pub fn scroll_to_bottom(&mut self) {
         // Set to a large number - widget will clamp to actual max
         self.scroll_offset = usize::MAX;
     }
 
-    /// Resets scroll offset when new content is displayed
+    /// Returns the raw scroll offset before clamping
+    pub fn get_scroll_offset(&self) -> usize {
+        self.scroll_offset
+    }
+
+    /// Moves the scroll offset to the start of newly displayed content
     fn reset_scroll(&mut self) {
-        self.scroll_offset = 0;
+        self.scroll_offset = self.current_start_line();
     }
 }
 
 impl Widget for &OutputComponent {
-    /// Renders the output area with the current output text
-    /// Applies scrolling based on the current scroll offset
+    /// Renders the output area with history followed by the current output
+    /// Only the rows in view are built, so the offset isn't bound by the
+    /// paragraph's u16 scroll and very long outputs still reach their last line
     fn render(self, area: Rect, buf: &mut Buffer) {
-        let text = if self.output_text.is_empty() {
-            Text::from(vec![
-                Line::from(""),
-                Line::from("Command output will appear here")
-                    .centered()
-                    .dim(),
-            ])
-        } else {
-            Text::from(self.output_text.clone())
-        };
-
         let block = Block::bordered()
             .title(" Output ".bold())
             .title_bottom(
@@ -154,18 +283,47 @@ impl Widget for &OutputComponent {
         // Calculate visible area (accounting for borders)
         let inner_area = block.inner(area);
         let visible_lines = inner_area.height as usize;
+        self.visible_lines.set(visible_lines);
 
-        // Only apply scroll if content exceeds visible area
-        let total_lines = self.output_text.lines().count();
-        let scroll = if total_lines > visible_lines {
-            (self.scroll_offset as u16).min((total_lines - visible_lines) as u16)
+        let text = if self.output_text.is_empty() && self.history.is_empty() {
+            Text::from(vec![
+                Line::from(""),
+                Line::from("Command output will appear here")
+                    .centered()
+                    .dim(),
+            ])
         } else {
-            0
+            // Past outputs are dimmed so the latest result stands out
+            let history_end = self.current_start_line();
+            // Clamp scroll over the combined line count
+            let scroll = self.effective_scroll(visible_lines);
+            Text::from(
+                self.combined_lines()
+                    .into_iter()
+                    .enumerate()
+                    .skip(scroll)
+                    .take(visible_lines)
+                    .map(|(idx, line)| {
+                        let line = marker_colored(Line::from(line.to_string()));
+                        if idx < history_end { line.dim() } else { line }
+                    })
+                    .collect::<Vec<Line>>(),
+            )
         };
 
-        Paragraph::new(text)
-            .block(block)
-            .scroll((scroll, 0))
-            .render(area, buf);
+        Paragraph::new(text).block(block).render(area, buf);
+    }
+}
+
+/// Colors a line green or red when it starts with an up or down marker
+fn marker_colored(line: Line<'static>) -> Line<'static> {
+    let text = line.to_string();
+    let text = text.trim_start();
+    if text.starts_with(UP_MARKER) {
+        line.green()
+    } else if text.starts_with(DOWN_MARKER) {
+        line.red()
+    } else {
+        line
     }
 }
@@ -0,0 +1,172 @@
+/// Quote Component - Live view of a single symbol
+///
+/// Takes over the output pane while `watch <symbol>` is active. Shows the latest
+/// price, day change against the previous close, and when the quote was received,
+/// refreshed on the regular price timer. Esc returns to the normal output view.
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Stylize},
+    symbols::border,
+    text::{Line, Span, Text},
+    widgets::{Block, Paragraph, Widget},
+};
+use rust_decimal::Decimal;
+
+use crate::Finance::{AssetType, Symbol, percent_of};
+use crate::clock::DisplayTimezone;
+use crate::format::{MoneyFormat, format_pct};
+use crate::market::MarketStatus;
+
+/// Component that follows one symbol's quote
+pub struct QuoteComponent {
+    /// Symbol being watched
+    symbol: Symbol,
+    /// Latest price, None until the first refresh arrives
+    price: Option<Decimal>,
+    /// Previous close used for the day change
+    previous_close: Option<Decimal>,
+    /// When the latest quote was received, as a Unix timestamp
+    updated_at: Option<i64>,
+    /// Market status for the symbol's asset class
+    market_status: MarketStatus,
+    /// Configured currency display
+    money_format: MoneyFormat,
+    /// Zone the update time is shown in
+    timezone: DisplayTimezone,
+}
+
+impl QuoteComponent {
+    // SECTION: Constructor
+
+    /// Creates a quote view for `symbol` with no price yet
+    pub fn new(symbol: Symbol) -> Self {
+        Self {
+            symbol,
+            price: None,
+            previous_close: None,
+            updated_at: None,
+            market_status: MarketStatus::Open,
+            money_format: MoneyFormat::default(),
+            timezone: DisplayTimezone::default(),
+        }
+    }
+
+    pub fn get_symbol(&self) -> &Symbol {
+        &self.symbol
+    }
+
+    // SECTION: Data Management
+
+    /// Stores a refreshed quote
+    /// A zero price means the lookup failed, so the previous quote is kept
+    pub fn update_quote(&mut self, price: Decimal, previous_close: Decimal, at: i64) {
+        if price <= Decimal::ZERO {
+            return;
+        }
+        self.price = Some(price);
+        if previous_close > Decimal::ZERO {
+            self.previous_close = Some(previous_close);
+        }
+        self.updated_at = Some(at);
+    }
+
+    pub fn set_market_status(&mut self, status: MarketStatus) {
+        self.market_status = status;
+    }
+
+    pub fn set_money_format(&mut self, money_format: MoneyFormat) {
+        self.money_format = money_format;
+    }
+
+    pub fn set_display_timezone(&mut self, timezone: DisplayTimezone) {
+        self.timezone = timezone;
+    }
+
+    /// Change from the previous close as (amount, percent)
+    /// Returns None until both prices are known
+    pub fn day_change(&self) -> Option<(Decimal, Decimal)> {
+        let price = self.price?;
+        let previous_close = self.previous_close?;
+        let change = price - previous_close;
+        Some((change, percent_of(change, previous_close)?))
+    }
+
+    // SECTION: Rendering
+
+    fn quote_lines(&self) -> Vec<Line<'static>> {
+        let asset_type = AssetType::for_symbol(&self.symbol);
+        let Some(price) = self.price else {
+            return vec![Line::from("Loading quote...").dim()];
+        };
+
+        let mut lines = vec![
+            Line::from(self.money_format.money_price(price, asset_type))
+                .bold()
+                .fg(Color::White),
+            Line::from(""),
+        ];
+
+        lines.push(match self.day_change() {
+            Some((change, pct)) => {
+                let color = if change < Decimal::ZERO {
+                    Color::Red
+                } else {
+                    Color::Green
+                };
+                let sign = if change < Decimal::ZERO { "-" } else { "+" };
+                Line::from(format!(
+                    "{}{} ({}) today",
+                    sign,
+                    self.money_format.money_price(change.abs(), asset_type),
+                    format_pct(pct)
+                ))
+                .bold()
+                .fg(color)
+            }
+            None => Line::from("Day change unavailable").dim(),
+        });
+
+        if let Some(at) = self
+            .updated_at
+            .and_then(|at| self.timezone.format(at, "%H:%M:%S"))
+        {
+            lines.push(Line::from(format!("Updated {}", at)).dim());
+        }
+        lines
+    }
+}
+
+impl Widget for &QuoteComponent {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let mut title = vec![Span::from(format!(" Watching {} ", self.symbol)).bold()];
+        if !self.market_status.is_open() {
+            title.push(
+                Span::from(" MARKET CLOSED ")
+                    .bold()
+                    .fg(Color::White)
+                    .bg(Color::Red),
+            );
+            title.push(Span::from(" "));
+        }
+
+        // Pad from the top so the quote sits in the middle of the pane
+        let lines = self.quote_lines();
+        let inner_height = area.height.saturating_sub(2) as usize;
+        let padding = inner_height.saturating_sub(lines.len()) / 2;
+        let mut text = vec![Line::from(""); padding];
+        text.extend(lines);
+
+        let block = Block::bordered()
+            .title(Line::from(title))
+            .title_bottom(
+                Line::from(vec![" Back ".into(), "<Esc>".blue().bold(), " ".into()]).centered(),
+            )
+            .border_set(border::ROUNDED);
+
+        Paragraph::new(Text::from(text))
+            .centered()
+            .block(block)
+            .render(area, buf);
+    }
+}
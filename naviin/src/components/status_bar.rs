@@ -0,0 +1,205 @@
+/// Status Bar Component - One-line account summary between the panels and the input
+///
+/// Shows the active portfolio, total value, cash, day P&L, online/offline state (noting
+/// when the request throttle is holding lookups back), how many symbols the last refresh
+/// priced and how long ago prices arrived. On a narrow terminal the least important fields
+/// are dropped first (a full price count, refresh age, portfolio name, cash, day P&L) and
+/// what remains is cut with `…`. A price count with failures goes just before day P&L
+/// and turns the line yellow.
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Style},
+    widgets::{Paragraph, Widget},
+};
+use rust_decimal::Decimal;
+use std::time::Instant;
+
+use crate::format::MoneyFormat;
+use crate::refresh::RefreshHealth;
+
+/// Placed between fields
+const SEPARATOR: &str = " │ ";
+
+/// Component that renders the status line
+pub struct StatusBarComponent {
+    /// Name of the open account, e.g. the database file name
+    portfolio_name: String,
+    /// Cash plus holdings at market, None until every holding has a price
+    portfolio_value: Option<Decimal>,
+    /// Cash balance
+    cash: Decimal,
+    /// Change in holdings' value since the previous close, None until any is known
+    day_pnl: Option<Decimal>,
+    /// Offline: refreshes are paused
+    offline: bool,
+    /// Lookups were recently held back by the request throttle
+    throttled: bool,
+    /// When prices were last received
+    last_refresh: Option<Instant>,
+    /// Symbols priced by the latest refresh, None before the first one
+    refresh_health: Option<RefreshHealth>,
+    /// Configured currency display
+    money_format: MoneyFormat,
+}
+
+impl StatusBarComponent {
+    // SECTION: Constructor
+
+    pub fn new() -> Self {
+        Self {
+            portfolio_name: String::new(),
+            portfolio_value: None,
+            cash: Decimal::ZERO,
+            day_pnl: None,
+            offline: false,
+            throttled: false,
+            last_refresh: None,
+            refresh_health: None,
+            money_format: MoneyFormat::default(),
+        }
+    }
+
+    // SECTION: Data Management
+
+    pub fn set_portfolio_name(&mut self, name: String) {
+        self.portfolio_name = name;
+    }
+
+    /// Updates the money fields from the latest holdings and prices
+    pub fn update_account(
+        &mut self,
+        portfolio_value: Option<Decimal>,
+        cash: Decimal,
+        day_pnl: Option<Decimal>,
+    ) {
+        self.portfolio_value = portfolio_value;
+        self.cash = cash;
+        self.day_pnl = day_pnl;
+    }
+
+    pub fn set_offline(&mut self, offline: bool) {
+        self.offline = offline;
+    }
+
+    pub fn set_throttled(&mut self, throttled: bool) {
+        self.throttled = throttled;
+    }
+
+    pub fn set_last_refresh(&mut self, at: Instant) {
+        self.last_refresh = Some(at);
+    }
+
+    pub fn set_refresh_health(&mut self, health: RefreshHealth) {
+        self.refresh_health = Some(health);
+    }
+
+    /// Some symbols came back without a price
+    pub fn is_degraded(&self) -> bool {
+        self.refresh_health
+            .as_ref()
+            .is_some_and(RefreshHealth::is_degraded)
+    }
+
+    pub fn set_money_format(&mut self, money_format: MoneyFormat) {
+        self.money_format = money_format;
+    }
+
+    // SECTION: Rendering
+
+    /// The status text fitted to `width` characters
+    pub fn status_line(&self, width: usize) -> String {
+        let value = match self.portfolio_value {
+            Some(value) => self.money_format.money(value),
+            None => "--".to_string(),
+        };
+        let day = match self.day_pnl {
+            Some(pnl) if pnl > Decimal::ZERO => format!("+{}", self.money_format.money(pnl)),
+            Some(pnl) => self.money_format.money(pnl),
+            None => "--".to_string(),
+        };
+        let refreshed = match self.last_refresh {
+            Some(at) => format!("refreshed {}s ago", at.elapsed().as_secs()),
+            None => "not refreshed yet".to_string(),
+        };
+        let connection = match (self.offline, self.throttled) {
+            (true, _) => "OFFLINE",
+            (false, true) => "online, throttled",
+            (false, false) => "online",
+        };
+
+        // (text, drop priority): higher numbers go first when space runs out
+        let mut fields: Vec<(String, u8)> = Vec::new();
+        if !self.portfolio_name.is_empty() {
+            fields.push((format!("[{}]", self.portfolio_name), 3));
+        }
+        fields.push((format!("Value {}", value), 0));
+        fields.push((format!("Cash {}", self.money_format.money(self.cash)), 2));
+        fields.push((format!("Day {}", day), 1));
+        fields.push((connection.to_string(), 0));
+        // Nothing to refresh yet (no holdings or watchlist) leaves the count out
+        if let Some(health) = self.refresh_health.as_ref().filter(|h| h.total > 0) {
+            let priority = if health.is_degraded() { 1 } else { 5 };
+            fields.push((health.summary(), priority));
+        }
+        fields.push((refreshed, 4));
+
+        let joined = |fields: &[(String, u8)]| {
+            fields
+                .iter()
+                .map(|(text, _)| text.as_str())
+                .collect::<Vec<_>>()
+                .join(SEPARATOR)
+        };
+        let mut line = joined(&fields);
+        while line.chars().count() > width {
+            let Some(drop) = fields
+                .iter()
+                .enumerate()
+                .filter(|(_, (_, priority))| *priority > 0)
+                .max_by_key(|(_, (_, priority))| *priority)
+                .map(|(index, _)| index)
+            else {
+                break;
+            };
+            fields.remove(drop);
+            line = joined(&fields);
+        }
+        truncate(&line, width)
+    }
+}
+
+// Cuts `text` to `width` characters, ending in `…` when anything was removed
+fn truncate(text: &str, width: usize) -> String {
+    if text.chars().count() <= width {
+        return text.to_string();
+    }
+    if width == 0 {
+        return String::new();
+    }
+    let mut cut: String = text.chars().take(width - 1).collect();
+    cut.push('…');
+    cut
+}
+
+impl Default for StatusBarComponent {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Widget for &StatusBarComponent {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let style = if self.offline || self.is_degraded() {
+            Style::default().fg(Color::Yellow)
+        } else {
+            Style::default().fg(Color::Gray)
+        };
+        Paragraph::new(format!(
+            " {}",
+            self.status_line(area.width.saturating_sub(1) as usize)
+        ))
+        .style(style)
+        .render(area, buf);
+    }
+}
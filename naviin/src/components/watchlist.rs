@@ -1,28 +1,47 @@
 /// Watchlist Component - Displays stock symbols with real-time prices
 ///
 /// This component renders a table showing watched stock symbols and their
-/// current market prices. It supports navigation and price refresh.
+/// current market prices. It supports navigation and price refresh; while the panel
+/// is active (Tab) the selected row is highlighted and its note shown in the title bar.
+/// The Tick column shows the move since the previous refresh (↑ green, ↓ red), so
+/// the panel visibly ticks between refreshes without any price history.
 use ratatui::{
     buffer::Buffer,
     layout::{Constraint, Rect},
     style::{Color, Style, Stylize},
     symbols::border,
     text::{Line, Text},
-    widgets::{Block, Borders, Cell, Paragraph, Row, Table, TableState, Widget},
+    widgets::{Block, Borders, Cell, Paragraph, Row, StatefulWidget, Table, TableState, Widget},
 };
 use rust_decimal::Decimal;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::time::Instant;
 
-use crate::Finance::Symbol;
+use crate::Finance::{AssetType, Symbol};
+use crate::format::{MoneyFormat, format_fixed, price_decimals};
 
 /// Component that displays the watchlist with real-time prices
 pub struct WatchlistComponent {
     /// List of stock symbols being watched
     symbols: Vec<Symbol>,
-    /// Cached prices for each symbol (aligned by index)
+    /// Cached prices for each symbol
     prices: HashMap<Symbol, Decimal>,
+    /// Prices from the refresh before, for the tick-to-tick change
+    previous_prices: HashMap<Symbol, Decimal>,
     /// Current selected row in the table
     table_state: TableState,
+    /// When prices were last received, shown in the footer
+    last_refresh: Option<Instant>,
+    /// Seconds between automatic refreshes, shown in the footer
+    refresh_secs: u64,
+    /// Configured currency display
+    money_format: MoneyFormat,
+    /// Per-symbol notes, the selected one is shown while active
+    notes: BTreeMap<Symbol, String>,
+    /// Whether keyboard navigation is on this panel
+    active: bool,
+    /// Offline: refreshes are paused and prices are marked stale
+    offline: bool,
 }
 
 impl WatchlistComponent {
@@ -40,39 +59,172 @@ impl WatchlistComponent {
         Self {
             symbols,
             prices: HashMap::new(),
+            previous_prices: HashMap::new(),
             table_state,
+            last_refresh: None,
+            refresh_secs: crate::AppState::DEFAULT_REFRESH_SECS,
+            money_format: MoneyFormat::default(),
+            notes: BTreeMap::new(),
+            active: false,
+            offline: false,
         }
     }
 
+    pub fn set_money_format(&mut self, money_format: MoneyFormat) {
+        self.money_format = money_format;
+    }
+
     pub fn get_symbols(&self) -> Vec<Symbol> {
         self.symbols.clone()
     }
 
+    pub fn set_notes(&mut self, notes: BTreeMap<Symbol, String>) {
+        self.notes = notes;
+    }
+
+    pub fn set_active(&mut self, active: bool) {
+        self.active = active;
+    }
+
+    pub fn set_offline(&mut self, offline: bool) {
+        self.offline = offline;
+    }
+
+    /// Symbol on the selected row, if any
+    pub fn selected_symbol(&self) -> Option<&Symbol> {
+        self.symbols.get(self.table_state.selected()?)
+    }
+
+    /// Moves the selection down one row, wrapping to the top
+    pub fn select_next(&mut self) {
+        if !self.symbols.is_empty() {
+            let next = self.table_state.selected().map_or(0, |i| i + 1);
+            self.table_state.select(Some(next % self.symbols.len()));
+        }
+    }
+
+    /// Moves the selection up one row, wrapping to the bottom
+    pub fn select_previous(&mut self) {
+        if !self.symbols.is_empty() {
+            let len = self.symbols.len();
+            let previous = self.table_state.selected().map_or(0, |i| i + len - 1);
+            self.table_state.select(Some(previous % len));
+        }
+    }
+
     /// SECTION: Data Management
 
-    /// Updates the list of symbols and resets selection
+    /// Updates the list of symbols, keeping the selected symbol selected
     ///
     /// # Arguments
     /// * `symbols` - New vector of stock symbols
     pub fn update_symbols(&mut self, symbols: Vec<Symbol>) {
+        let previous = self.selected_symbol().cloned();
         self.symbols = symbols;
-        if !self.symbols.is_empty() && self.table_state.selected().is_none() {
-            self.table_state.select(Some(0));
-        }
+        super::follow_selection(&mut self.table_state, previous, &self.symbols, Clone::clone);
     }
 
+    /// Replaces the cached prices, keeping the old ones to measure the next tick against
+    /// Prices are keyed by symbol, so adding or removing symbols doesn't shift other rows
     pub fn update_prices(&mut self, prices: HashMap<Symbol, Decimal>) {
-        self.prices = prices;
+        self.previous_prices = std::mem::replace(&mut self.prices, prices);
+    }
+
+    /// Fills in the prices given without moving the tick baseline, for a retry
+    /// of the symbols the last refresh couldn't price
+    pub fn merge_prices(&mut self, prices: HashMap<Symbol, Decimal>) {
+        self.prices.extend(prices);
+    }
+
+    /// Change since the previous refresh, None on the first refresh for a symbol or
+    /// when either price failed to load
+    pub fn tick_delta(&self, symbol: &Symbol) -> Option<Decimal> {
+        let price = self.prices.get(symbol).copied()?;
+        let previous = self.previous_prices.get(symbol).copied()?;
+        if price <= Decimal::ZERO || previous <= Decimal::ZERO {
+            return None;
+        }
+        Some(price - previous)
+    }
+
+    /// Arrow and size of the tick for display, with its color; neutral when unchanged
+    fn tick_cell(&self, symbol: &Symbol) -> Cell<'static> {
+        let delta = match self.tick_delta(symbol) {
+            Some(delta) if !delta.is_zero() => delta,
+            _ => return Cell::from("·").style(Style::default().fg(Color::DarkGray)),
+        };
+        let price = self.prices.get(symbol).copied().unwrap_or_default();
+        let amount = format_fixed(
+            delta.abs(),
+            price_decimals(price, AssetType::for_symbol(symbol)),
+        );
+        if delta > Decimal::ZERO {
+            Cell::from(format!("↑{}", amount)).style(Style::default().fg(Color::Green))
+        } else {
+            Cell::from(format!("↓{}", amount)).style(Style::default().fg(Color::Red))
+        }
+    }
+
+    /// Records when prices were last refreshed
+    pub fn set_last_refresh(&mut self, at: Instant) {
+        self.last_refresh = Some(at);
+    }
+
+    /// Sets the auto-refresh interval shown in the footer
+    pub fn set_refresh_interval(&mut self, secs: u64) {
+        self.refresh_secs = secs;
     }
 
     /// SECTION: Rendering
 
+    /// Builds the bordered block with the refresh hint, interval and age in the footer
+    /// Offline, the footer says so and the title carries a STALE badge
+    fn block(&self) -> Block<'_> {
+        let schedule = if self.offline {
+            "offline".to_string()
+        } else {
+            format!("every {}s", self.refresh_secs)
+        };
+        let age = match self.last_refresh {
+            Some(at) => format!(" {}, {}s ago ", schedule, at.elapsed().as_secs()),
+            None => format!(" {}, never ", schedule),
+        };
+        let mut title = vec![" Watchlist ".bold()];
+        if self.offline {
+            title.push(" STALE ".bold().fg(Color::Black).bg(Color::Yellow));
+            title.push(" ".into());
+        }
+
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_set(border::ROUNDED)
+            .title(Line::from(title))
+            .title_bottom(
+                Line::from(vec![
+                    " Refresh ".into(),
+                    "<Ctrl+R>".blue().bold(),
+                    age.dim(),
+                ])
+                .centered(),
+            );
+        if !self.active {
+            return block;
+        }
+
+        let block = block.border_style(Style::default().fg(Color::Cyan));
+        match self.selected_symbol().and_then(|s| self.notes.get(s)) {
+            Some(note) => block.title(Line::from(format!(" Note: {} ", note)).right_aligned()),
+            None => block,
+        }
+    }
+
     /// Renders the watchlist table with headers and data rows
     fn render_table(&self, area: Rect, buf: &mut Buffer) {
         // Create header row with styled column titles
         let header = Row::new(vec![
             Cell::from("Symbol").style(Style::default().fg(Color::Yellow).bold()),
             Cell::from("Price").style(Style::default().fg(Color::Yellow).bold()),
+            Cell::from("Tick").style(Style::default().fg(Color::Yellow).bold()),
         ])
         .height(1);
 
@@ -84,12 +236,16 @@ impl WatchlistComponent {
                 let price_str = self
                     .prices
                     .get(symbol)
-                    .map(|price| format!("{:.2}", price))
+                    .map(|price| {
+                        self.money_format
+                            .price(*price, AssetType::for_symbol(symbol))
+                    })
                     .unwrap_or_else(|| "Loading".to_string());
 
                 let cells = vec![
                     Cell::from(symbol.clone()),
                     Cell::from(price_str).style(Style::default().fg(Color::Green)),
+                    self.tick_cell(symbol),
                 ];
 
                 Row::new(cells).height(1)
@@ -99,19 +255,24 @@ impl WatchlistComponent {
         // Build the table with styling and borders
         let table = Table::new(
             rows,
-            &[Constraint::Percentage(50), Constraint::Percentage(50)],
+            &[
+                Constraint::Percentage(35),
+                Constraint::Percentage(35),
+                Constraint::Percentage(30),
+            ],
         )
         .header(header)
-        .block(
-            Block::default()
-                .borders(Borders::ALL)
-                .border_set(border::ROUNDED)
-                .title(" Watchlist ".bold()),
-        )
+        .block(self.block())
         .row_highlight_style(Style::default().bg(Color::DarkGray).fg(Color::White))
         .highlight_symbol("> ");
 
-        table.render(area, buf);
+        // Only the active panel shows its selection
+        let mut table_state = if self.active {
+            self.table_state
+        } else {
+            TableState::default()
+        };
+        StatefulWidget::render(table, area, buf, &mut table_state);
     }
 }
 
@@ -132,12 +293,7 @@ impl Widget for &WatchlistComponent {
 
             Paragraph::new(empty_text)
                 .centered()
-                .block(
-                    Block::default()
-                        .borders(Borders::ALL)
-                        .border_set(border::ROUNDED)
-                        .title(" Watchlist ".bold()),
-                )
+                .block(self.block())
                 .render(area, buf);
         } else {
             // Render the data table
@@ -0,0 +1,338 @@
+/// Cost Basis Module
+///
+/// Realized gains computed by replaying the trade log. Every buy opens a lot;
+/// every sell consumes lots according to the selected `CostBasisMethod`. Fees
+/// count against the gain: a buy's fee adds to its lot's cost and a sell's fee
+/// comes out of its proceeds. The method only changes realized P&L: holdings
+/// keep their running average cost for display and unrealized P&L either way.
+/// Lots keep the time they were opened, so each closed piece can be reported as
+/// a short or long term lot.
+/// Each closed piece is labelled with the sell's tag, or the buy's when the sell
+/// has none, so realized P&L can be grouped by strategy.
+use std::collections::{BTreeMap, HashMap, VecDeque};
+
+use rust_decimal::Decimal;
+
+use crate::Finance::{INTEREST_ORDER_TYPE, QTY_DUST, SPLIT_ORDER_TYPE, Symbol};
+use crate::Orders::{Side, Trade};
+use crate::format::{csv_decimal, csv_money};
+
+/// Lots held longer than this many days are long term
+pub const LONG_TERM_DAYS: i64 = 365;
+
+const SECONDS_PER_DAY: i64 = 86_400;
+
+/// Header line of the CSV written by `taxreport`
+pub const TAX_REPORT_CSV_HEADER: &str =
+    "symbol,open_date,close_date,quantity,proceeds,cost_basis,gain_loss,holding_period";
+
+/// Which shares a sell is matched against
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CostBasisMethod {
+    /// Oldest lots first
+    Fifo,
+    /// Newest lots first
+    Lifo,
+    /// Running average cost of the position
+    #[default]
+    Average,
+}
+
+impl CostBasisMethod {
+    /// Parses `fifo`, `lifo` or `average` (also `avg`), case-insensitively
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "fifo" => Some(Self::Fifo),
+            "lifo" => Some(Self::Lifo),
+            "average" | "avg" => Some(Self::Average),
+            _ => None,
+        }
+    }
+
+    /// Lowercase name, as persisted and accepted by `costbasis`
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Fifo => "fifo",
+            Self::Lifo => "lifo",
+            Self::Average => "average",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Fifo => "FIFO",
+            Self::Lifo => "LIFO",
+            Self::Average => "Average cost",
+        }
+    }
+}
+
+/// One sell matched against its cost basis
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RealizedGain {
+    pub symbol: Symbol,
+    pub quantity: Decimal,
+    pub proceeds: Decimal,
+    pub cost: Decimal,
+    /// When the sell happened
+    pub timestamp: i64,
+}
+
+impl RealizedGain {
+    pub fn gain(&self) -> Decimal {
+        self.proceeds - self.cost
+    }
+}
+
+/// Part of one lot closed by one sell
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ClosedLot {
+    pub symbol: Symbol,
+    pub quantity: Decimal,
+    /// When the lot was bought
+    pub opened: i64,
+    /// When the sell happened
+    pub closed: i64,
+    pub proceeds: Decimal,
+    pub cost: Decimal,
+    /// Strategy tag of the sell, else of the buy that opened the lot
+    pub tag: Option<String>,
+}
+
+impl ClosedLot {
+    pub fn gain(&self) -> Decimal {
+        self.proceeds - self.cost
+    }
+
+    /// Held for more than `LONG_TERM_DAYS`
+    pub fn is_long_term(&self) -> bool {
+        self.closed - self.opened > LONG_TERM_DAYS * SECONDS_PER_DAY
+    }
+
+    pub fn holding_period(&self) -> &'static str {
+        if self.is_long_term() { "long" } else { "short" }
+    }
+}
+
+/// Shares still held from one buy
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OpenLot {
+    pub symbol: Symbol,
+    pub quantity: Decimal,
+    /// Cost per share; average cost reprices every lot at each sell
+    pub price: Decimal,
+    pub opened: i64,
+    /// Strategy tag of the buy
+    pub tag: Option<String>,
+}
+
+// Shares bought together at one price
+#[derive(Clone, Debug)]
+struct Lot {
+    quantity: Decimal,
+    price: Decimal,
+    opened: i64,
+    tag: Option<String>,
+}
+
+/// Realized gain for every sell in the log, oldest first
+/// Trades are replayed in timestamp order; shares sold beyond the recorded buys
+/// have no known cost and are left out of the gain
+pub fn realized_gains(trades: &[Trade], method: CostBasisMethod) -> Vec<RealizedGain> {
+    replay(trades, method)
+        .0
+        .into_iter()
+        .map(|(sell, taken)| {
+            let quantity: Decimal = taken.iter().map(|lot| lot.quantity).sum();
+            RealizedGain {
+                symbol: sell.get_symbol().clone(),
+                quantity,
+                proceeds: quantity * net_price(sell),
+                cost: taken.iter().map(|lot| lot.quantity * lot.price).sum(),
+                timestamp: sell.get_timestamp(),
+            }
+        })
+        .collect()
+}
+
+/// Every lot piece closed by a sell, oldest sell first
+/// A sell that spans several lots gives one entry per lot; a partly sold lot
+/// stays open for the rest of its shares
+pub fn closed_lots(trades: &[Trade], method: CostBasisMethod) -> Vec<ClosedLot> {
+    replay(trades, method)
+        .0
+        .into_iter()
+        .flat_map(|(sell, taken)| {
+            taken.into_iter().map(move |lot| ClosedLot {
+                symbol: sell.get_symbol().clone(),
+                quantity: lot.quantity,
+                opened: lot.opened,
+                closed: sell.get_timestamp(),
+                proceeds: lot.quantity * net_price(sell),
+                cost: lot.quantity * lot.price,
+                tag: sell.get_tag().map(str::to_string).or(lot.tag),
+            })
+        })
+        .collect()
+}
+
+/// Lots left open after replaying every sell, by symbol then open time
+pub fn open_lots(trades: &[Trade], method: CostBasisMethod) -> Vec<OpenLot> {
+    let mut open: Vec<OpenLot> = replay(trades, method)
+        .1
+        .into_iter()
+        .flat_map(|(symbol, lots)| {
+            lots.into_iter()
+                .filter(|lot| lot.quantity > QTY_DUST)
+                .map(move |lot| OpenLot {
+                    symbol: symbol.clone(),
+                    quantity: lot.quantity,
+                    price: lot.price,
+                    opened: lot.opened,
+                    tag: lot.tag,
+                })
+        })
+        .collect();
+    open.sort_by(|a, b| a.symbol.cmp(&b.symbol).then(a.opened.cmp(&b.opened)));
+    open
+}
+
+/// Closed lots as CSV with UTC dates, one row per lot piece
+pub fn tax_report_csv(lots: &[ClosedLot]) -> String {
+    let date = |timestamp: i64| {
+        chrono::DateTime::from_timestamp(timestamp, 0)
+            .map(|dt| dt.format("%Y-%m-%d").to_string())
+            .unwrap_or_default()
+    };
+    let mut csv = format!("{}\n", TAX_REPORT_CSV_HEADER);
+    for lot in lots {
+        csv.push_str(&format!(
+            "{},{},{},{},{},{},{},{}\n",
+            lot.symbol,
+            date(lot.opened),
+            date(lot.closed),
+            csv_decimal(lot.quantity),
+            csv_money(lot.proceeds),
+            csv_money(lot.cost),
+            csv_money(lot.gain()),
+            lot.holding_period()
+        ));
+    }
+    csv
+}
+
+// Sells that matched something with the lot pieces they took, and the lots
+// still open per symbol
+type Replay<'a> = (
+    Vec<(&'a Trade, Vec<Lot>)>,
+    HashMap<&'a Symbol, VecDeque<Lot>>,
+);
+
+// Matches each sell against the open lots
+fn replay(trades: &[Trade], method: CostBasisMethod) -> Replay<'_> {
+    // Stable sort keeps recording order among equal timestamps
+    let mut ordered: Vec<&Trade> = trades
+        .iter()
+        .filter(|trade| trade.get_order_type() != INTEREST_ORDER_TYPE)
+        .collect();
+    ordered.sort_by_key(|trade| trade.get_timestamp());
+
+    let mut lots: HashMap<&Symbol, VecDeque<Lot>> = HashMap::new();
+    let mut sells = Vec::new();
+    for trade in ordered {
+        let symbol_lots = lots.entry(trade.get_symbol()).or_default();
+        if trade.get_order_type() == SPLIT_ORDER_TYPE {
+            // Quantity is the share multiplier; each lot keeps its total cost
+            let multiplier = trade.get_quantity();
+            for lot in symbol_lots.iter_mut() {
+                lot.quantity *= multiplier;
+                lot.price /= multiplier;
+            }
+            continue;
+        }
+        match trade.get_side() {
+            Side::Buy => symbol_lots.push_back(Lot {
+                quantity: trade.get_quantity(),
+                price: net_price(trade),
+                opened: trade.get_timestamp(),
+                tag: trade.get_tag().map(str::to_string),
+            }),
+            Side::Sell => {
+                let taken = consume_lots(symbol_lots, trade.get_quantity(), method);
+                if !taken.is_empty() {
+                    sells.push((trade, taken));
+                }
+            }
+        }
+    }
+    (sells, lots)
+}
+
+/// Total realized gain per symbol
+pub fn realized_by_symbol(gains: &[RealizedGain]) -> BTreeMap<Symbol, Decimal> {
+    let mut totals = BTreeMap::new();
+    for gain in gains {
+        *totals.entry(gain.symbol.clone()).or_insert(Decimal::ZERO) += gain.gain();
+    }
+    totals
+}
+
+/// Total realized gain per strategy tag; untagged pieces are keyed by None
+pub fn realized_by_tag(lots: &[ClosedLot]) -> BTreeMap<Option<String>, Decimal> {
+    let mut totals = BTreeMap::new();
+    for lot in lots {
+        *totals.entry(lot.tag.clone()).or_insert(Decimal::ZERO) += lot.gain();
+    }
+    totals
+}
+
+// Per-share cash the fill moved, fee included: a buy's cost, a sell's proceeds
+fn net_price(trade: &Trade) -> Decimal {
+    if trade.get_quantity().is_zero() {
+        return trade.get_price_per();
+    }
+    trade.cash_value() / trade.get_quantity()
+}
+
+// Removes `quantity` shares from the lots and returns the pieces taken
+fn consume_lots(lots: &mut VecDeque<Lot>, quantity: Decimal, method: CostBasisMethod) -> Vec<Lot> {
+    if method == CostBasisMethod::Average {
+        // Every lot is repriced at the average and sold oldest first, so the cost
+        // matches the average while each piece keeps its open date
+        let held: Decimal = lots.iter().map(|lot| lot.quantity).sum();
+        if held <= Decimal::ZERO {
+            return Vec::new();
+        }
+        let cost: Decimal = lots.iter().map(|lot| lot.quantity * lot.price).sum();
+        let average = cost / held;
+        for lot in lots.iter_mut() {
+            lot.price = average;
+        }
+    }
+
+    let mut remaining = quantity;
+    let mut taken = Vec::new();
+    while remaining > Decimal::ZERO {
+        let lot = match method {
+            CostBasisMethod::Lifo => lots.back_mut(),
+            _ => lots.front_mut(),
+        };
+        let Some(lot) = lot else {
+            break;
+        };
+        let quantity = remaining.min(lot.quantity);
+        taken.push(Lot {
+            quantity,
+            ..lot.clone()
+        });
+        lot.quantity -= quantity;
+        remaining -= quantity;
+        if lot.quantity <= QTY_DUST {
+            match method {
+                CostBasisMethod::Lifo => lots.pop_back(),
+                _ => lots.pop_front(),
+            };
+        }
+    }
+    taken
+}
@@ -0,0 +1,110 @@
+/// Demo Module
+///
+/// Bundled sample portfolio for `naviin --demo`: a funded account with a few
+/// positions, their trade history, a watchlist and notes. Prices come from a
+/// `MockProvider` with fixed quotes, so the app looks the same every run and
+/// works offline. The state is flagged as demo, which turns `save_state` into a
+/// no-op so nothing is written over the user's real data.
+use std::sync::Arc;
+
+use rust_decimal::Decimal;
+
+use crate::AppState::AppState;
+use crate::Finance::{self, Symbol};
+use crate::FinanceProvider::MockProvider;
+use crate::Orders::{OpenOrder, OrderType, Side, Trade};
+use crate::cashflow::{CashFlow, CashFlowKind};
+
+/// Cash deposited when the sample account was opened
+const DEMO_DEPOSIT: i64 = 50_000;
+/// 2026-01-02 14:30 UTC
+const DEMO_OPENED_AT: i64 = 1_767_364_200;
+
+/// (symbol, side, quantity, price, timestamp) of the sample trades, oldest first
+const DEMO_TRADES: &[(&str, Side, &str, &str, i64)] = &[
+    ("AAPL", Side::Buy, "40", "172.50", 1_767_625_200),
+    ("MSFT", Side::Buy, "15", "405.00", 1_768_230_000),
+    ("NVDA", Side::Buy, "60", "88.00", 1_770_134_400),
+    ("BTC-USD", Side::Buy, "0.25", "61000.00", 1_773_158_400),
+    ("NVDA", Side::Sell, "20", "121.00", 1_781_796_600),
+];
+
+/// (symbol, price, previous close) served by the demo provider
+const DEMO_QUOTES: &[(&str, &str, &str)] = &[
+    ("AAPL", "189.30", "187.10"),
+    ("MSFT", "412.80", "415.25"),
+    ("NVDA", "118.40", "114.90"),
+    ("BTC-USD", "64250.00", "63100.00"),
+    ("TSLA", "248.60", "252.00"),
+    ("AMZN", "186.20", "184.75"),
+];
+
+const DEMO_WATCHLIST: &[&str] = &["AAPL", "MSFT", "NVDA", "TSLA", "AMZN", "BTC-USD"];
+
+const DEMO_NOTES: &[(&str, &str)] = &[
+    ("NVDA", "Trimmed a third after the run-up"),
+    ("TSLA", "Waiting for a pullback before buying"),
+];
+
+// Parses one of the literals above
+fn dec(value: &str) -> Decimal {
+    value.parse().expect("demo decimal literal")
+}
+
+/// Provider quoting the sample symbols at fixed prices
+pub fn demo_provider() -> Arc<MockProvider> {
+    let provider = Arc::new(MockProvider::new());
+    for (symbol, price, previous_close) in DEMO_QUOTES {
+        provider.set_price(symbol, dec(price));
+        provider.set_previous_close(symbol, dec(previous_close));
+    }
+    provider
+}
+
+/// Sample portfolio flagged as demo, priced by `demo_provider`
+/// Holdings and cash are built by replaying the sample trades, so every view agrees
+pub fn demo_state() -> AppState {
+    let mut state = AppState::with_provider(demo_provider());
+    state.set_demo(true);
+
+    let deposit = Decimal::from(DEMO_DEPOSIT);
+    state.deposit(deposit);
+    state.add_cash_flow(CashFlow::new(
+        CashFlowKind::Deposit,
+        deposit,
+        DEMO_OPENED_AT,
+    ));
+
+    for (symbol, side, quantity, price, timestamp) in DEMO_TRADES {
+        let symbol: Symbol = symbol.to_string();
+        let (quantity, price) = (dec(quantity), dec(price));
+        match side {
+            Side::Buy => {
+                state.withdraw_purchase(quantity * price);
+                Finance::add_to_holdings(&symbol, quantity, price, &mut state);
+                state.add_trade(Trade::buy_at(symbol, quantity, price, *timestamp));
+            }
+            Side::Sell => {
+                state.deposit_sell(quantity * price);
+                Finance::remove_from_holdings(&symbol, quantity, &mut state);
+                state.add_trade(Trade::sell_at(symbol, quantity, price, *timestamp));
+            }
+        }
+    }
+
+    // A protective stop well below the market so it never fills during a demo
+    let _ = state.add_open_order(OpenOrder::new_at(
+        "AAPL".to_string(),
+        Decimal::from(10),
+        dec("160.00"),
+        OrderType::StopLoss,
+        Side::Sell,
+        DEMO_TRADES[0].4,
+    ));
+
+    state.set_watchlist(DEMO_WATCHLIST.iter().map(|s| s.to_string()).collect());
+    for (symbol, note) in DEMO_NOTES {
+        state.set_note(symbol.to_string(), note.to_string());
+    }
+    state
+}
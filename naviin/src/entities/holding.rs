@@ -1,5 +1,6 @@
 //! `SeaORM` Entity, @generated by sea-orm-codegen 2.0
 
+use super::stored_decimal::StoredDecimal;
 use sea_orm::entity::prelude::*;
 
 #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq)]
@@ -8,8 +9,8 @@ pub struct Model {
     #[sea_orm(primary_key)]
     pub id: i64,
     pub symbol: String,
-    pub quantity: Decimal,
-    pub avg_cost: Decimal,
+    pub quantity: StoredDecimal,
+    pub avg_cost: StoredDecimal,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
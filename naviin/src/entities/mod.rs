@@ -3,7 +3,15 @@
 pub mod prelude;
 
 pub mod app_state;
+pub mod cash_flow;
+pub mod equity_snapshot;
+pub mod executed_order;
 pub mod holding;
+pub mod note;
 pub mod open_order;
+pub mod order_template;
+pub mod recurring_buy;
+pub mod setting;
+pub mod stored_decimal;
 pub mod trade;
 pub mod watchlist;
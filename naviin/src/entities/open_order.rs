@@ -1,5 +1,6 @@
 //! `SeaORM` Entity, @generated by sea-orm-codegen 2.0
 
+use super::stored_decimal::StoredDecimal;
 use sea_orm::entity::prelude::*;
 
 #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq)]
@@ -9,8 +10,8 @@ pub struct Model {
     pub id: i64,
     pub order_type: String,
     pub symbol: String,
-    pub quantity: Decimal,
-    pub price: Decimal,
+    pub quantity: StoredDecimal,
+    pub price: StoredDecimal,
     pub timestamp: i64,
 }
 
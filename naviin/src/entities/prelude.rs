@@ -1,9 +1,17 @@
 //! `SeaORM` Entity, @generated by sea-orm-codegen 2.0
 
 pub use super::app_state::Entity as AppState;
+pub use super::cash_flow::Entity as CashFlow;
+pub use super::equity_snapshot::Entity as EquitySnapshot;
+pub use super::executed_order::Entity as ExecutedOrder;
 pub use super::holding::Entity as Holding;
+pub use super::note::Entity as Note;
 pub use super::open_order::Entity as OpenOrder;
+pub use super::order_template::Entity as OrderTemplate;
+pub use super::recurring_buy::Entity as RecurringBuy;
+pub use super::setting::Entity as Setting;
 pub use super::trade::Entity as Trade;
 pub use super::watchlist::Entity as Watchlist;
 
+pub use super::stored_decimal::StoredDecimal;
 pub use rust_decimal::Decimal;
@@ -0,0 +1,45 @@
+//! Decimal column value stored as its exact text
+//!
+//! SQLite ignores a declared precision, gives `decimal` columns numeric affinity
+//! and hands them back as `f64`, so anything past ~15 significant digits was lost
+//! on a save and reload. Storing the decimal's own string keeps every digit.
+
+use rust_decimal::Decimal;
+use sea_orm::entity::prelude::*;
+use std::fmt;
+use std::str::FromStr;
+
+/// Declared as `decimal(20, 8)` for schema generation; stored as text
+#[derive(Clone, Copy, Debug, PartialEq, Eq, DeriveValueType)]
+#[sea_orm(value_type = "String", column_type = "Decimal(Some((20, 8)))")]
+pub struct StoredDecimal(pub Decimal);
+
+impl fmt::Display for StoredDecimal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl FromStr for StoredDecimal {
+    type Err = rust_decimal::Error;
+
+    // Rows written before the text columns were converted by SQLite itself, which
+    // renders small reals in scientific notation (e.g. 1.0e-07)
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Decimal::from_str(s)
+            .or_else(|_| Decimal::from_scientific(s))
+            .map(StoredDecimal)
+    }
+}
+
+impl From<Decimal> for StoredDecimal {
+    fn from(value: Decimal) -> Self {
+        StoredDecimal(value)
+    }
+}
+
+impl From<StoredDecimal> for Decimal {
+    fn from(value: StoredDecimal) -> Self {
+        value.0
+    }
+}
@@ -1,5 +1,6 @@
 //! `SeaORM` Entity, @generated by sea-orm-codegen 2.0
 
+use super::stored_decimal::StoredDecimal;
 use sea_orm::entity::prelude::*;
 
 #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq)]
@@ -8,11 +9,14 @@ pub struct Model {
     #[sea_orm(primary_key)]
     pub id: i64,
     pub symbol: String,
-    pub quantity: Decimal,
-    pub price_per: Decimal,
+    pub quantity: StoredDecimal,
+    pub price_per: StoredDecimal,
     pub side: String,
     pub order_type: String,
     pub timestamp: i64,
+    pub tag: Option<String>,
+    pub currency: Option<String>,
+    pub fee: StoredDecimal,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
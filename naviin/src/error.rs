@@ -0,0 +1,53 @@
+/// Error Module
+///
+/// Crate-level error type shared by startup, command parsing, and storage.
+/// Handlers convert these into user-facing messages instead of panicking.
+use std::fmt;
+use std::io;
+
+use sea_orm::DbErr;
+
+/// All recoverable failures the application can report
+#[derive(Debug)]
+pub enum NaviinError {
+    /// A required environment variable or setting is missing
+    MissingConfig(String),
+    /// An environment variable or setting has an unusable value
+    InvalidConfig { key: String, value: String },
+    /// A numeric argument could not be parsed
+    InvalidNumber { field: String, value: String },
+    /// Database error from SeaORM
+    Database(DbErr),
+    /// Terminal or filesystem error
+    Io(io::Error),
+}
+
+impl fmt::Display for NaviinError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NaviinError::MissingConfig(key) => write!(f, "{} must be set in .env", key),
+            NaviinError::InvalidConfig { key, value } => {
+                write!(f, "Invalid {} in .env: '{}'", key, value)
+            }
+            NaviinError::InvalidNumber { field, value } => {
+                write!(f, "Invalid {}: '{}'", field, value)
+            }
+            NaviinError::Database(err) => write!(f, "Database error: {}", err),
+            NaviinError::Io(err) => write!(f, "IO error: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for NaviinError {}
+
+impl From<DbErr> for NaviinError {
+    fn from(err: DbErr) -> Self {
+        NaviinError::Database(err)
+    }
+}
+
+impl From<io::Error> for NaviinError {
+    fn from(err: io::Error) -> Self {
+        NaviinError::Io(err)
+    }
+}
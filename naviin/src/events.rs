@@ -0,0 +1,73 @@
+/// Events Module
+///
+/// Notices from background work the user would otherwise never see: order fills
+/// from the monitor, interest credits, and delivery errors. The monitor pushes
+/// them onto a capped buffer in `AppState`; the TUI drains the buffer on its
+/// refresh tick and shows them in the output pane.
+use std::fmt;
+
+use crate::clock::DisplayTimezone;
+
+/// Most undrained events kept; the oldest are dropped first
+pub const EVENT_LIMIT: usize = 50;
+
+/// What kind of thing happened
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EventKind {
+    /// An open order executed
+    Fill,
+    /// Informational, e.g. interest credited
+    Info,
+    /// Something failed in the background
+    Error,
+}
+
+impl EventKind {
+    fn label(&self) -> &'static str {
+        match self {
+            EventKind::Fill => "FILL",
+            EventKind::Info => "INFO",
+            EventKind::Error => "ERROR",
+        }
+    }
+}
+
+/// One recorded event
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AppEvent {
+    pub timestamp: i64,
+    pub kind: EventKind,
+    pub message: String,
+}
+
+impl AppEvent {
+    pub fn new(timestamp: i64, kind: EventKind, message: String) -> Self {
+        Self {
+            timestamp,
+            kind,
+            message,
+        }
+    }
+
+    /// The Display line with its time shown in `timezone`
+    pub fn format_in(&self, timezone: DisplayTimezone) -> String {
+        let time = timezone
+            .format(self.timestamp, "%H:%M:%S")
+            .unwrap_or_else(|| "--:--:--".to_string());
+        format!("[{}] {:<5} {}", time, self.kind.label(), self.message)
+    }
+}
+
+impl fmt::Display for AppEvent {
+    /// e.g. `[14:05:09] FILL  StopLoss sold 5 AAPL at $179.50`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.format_in(DisplayTimezone::Local))
+    }
+}
+
+/// Output pane text for a batch of drained events
+pub fn format_events(events: &[AppEvent], timezone: DisplayTimezone) -> String {
+    let mut lines = vec![format!("Background activity ({}):", events.len())];
+    lines.extend(events.iter().map(|event| event.format_in(timezone)));
+    lines.join("\n")
+}
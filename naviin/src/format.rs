@@ -0,0 +1,385 @@
+/// Format Module
+///
+/// Number formatting shared by the TUI and command output.
+/// Money is shown to two decimal places with thousands separators.
+/// Rounding is explicit and half away from zero; totals shown next to rows
+/// should be summed from the rounded rows with `sum_rounded` so they add up.
+/// `MoneyFormat` carries the configured currency symbol and separator style;
+/// every displayed amount should go through it.
+use std::fmt;
+
+use rust_decimal::prelude::*;
+
+use crate::Finance::AssetType;
+
+/// Currency symbol used when none is configured
+pub const DEFAULT_CURRENCY_SYMBOL: &str = "$";
+
+/// Decimal places shown for money values
+pub const CURRENCY_DP: u32 = 2;
+
+/// Rounding used for every displayed value: 0.005 -> 0.01, -0.005 -> -0.01
+pub const DISPLAY_ROUNDING: RoundingStrategy = RoundingStrategy::MidpointAwayFromZero;
+
+/// Leading marker for a rising line in command output; the output pane shows it green
+pub const UP_MARKER: &str = "▲";
+/// Leading marker for a falling line in command output; the output pane shows it red
+pub const DOWN_MARKER: &str = "▼";
+
+/// Significant digits kept for prices under $1
+const SUB_DOLLAR_SIG_DIGITS: u32 = 4;
+/// Most decimal places shown for a sub-dollar stock price
+const MAX_STOCK_PRICE_DP: u32 = 4;
+/// Most decimal places shown for a sub-dollar crypto price
+const MAX_CRYPTO_PRICE_DP: u32 = 8;
+/// Significant digits kept for quantities under one unit
+const SUB_UNIT_QTY_SIG_DIGITS: u32 = 4;
+/// Decimal places shown for a fractional stock quantity of one share or more
+const STOCK_QTY_DP: u32 = 2;
+/// Decimal places shown for a fractional crypto quantity of one coin or more
+const CRYPTO_QTY_DP: u32 = 4;
+/// Most decimal places shown for a sub-share stock quantity
+const MAX_STOCK_QTY_DP: u32 = 4;
+/// Most decimal places shown for a sub-coin crypto quantity (one satoshi)
+const MAX_CRYPTO_QTY_DP: u32 = 8;
+
+/// Digit grouping and decimal mark conventions
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum NumberStyle {
+    /// 1,234.56
+    #[default]
+    Us,
+    /// 1.234,56
+    Eu,
+    /// 1 234,56
+    Fr,
+    /// 1'234.56
+    Ch,
+}
+
+impl NumberStyle {
+    pub const ALL: [NumberStyle; 4] = [
+        NumberStyle::Us,
+        NumberStyle::Eu,
+        NumberStyle::Fr,
+        NumberStyle::Ch,
+    ];
+
+    /// Name used by the `currency` command and the stored setting
+    pub fn name(&self) -> &'static str {
+        match self {
+            NumberStyle::Us => "us",
+            NumberStyle::Eu => "eu",
+            NumberStyle::Fr => "fr",
+            NumberStyle::Ch => "ch",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        NumberStyle::ALL
+            .into_iter()
+            .find(|style| style.name().eq_ignore_ascii_case(name.trim()))
+    }
+
+    // (thousands separator, decimal mark)
+    fn separators(&self) -> (char, char) {
+        match self {
+            NumberStyle::Us => (',', '.'),
+            NumberStyle::Eu => ('.', ','),
+            NumberStyle::Fr => (' ', ','),
+            NumberStyle::Ch => ('\'', '.'),
+        }
+    }
+}
+
+impl fmt::Display for NumberStyle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// How money is displayed: currency symbol plus separator style
+/// This is presentation only; amounts are never converted
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MoneyFormat {
+    symbol: String,
+    style: NumberStyle,
+}
+
+impl Default for MoneyFormat {
+    fn default() -> Self {
+        Self::new(DEFAULT_CURRENCY_SYMBOL, NumberStyle::Us)
+    }
+}
+
+impl MoneyFormat {
+    pub fn new(symbol: &str, style: NumberStyle) -> Self {
+        Self {
+            symbol: symbol.to_string(),
+            style,
+        }
+    }
+
+    pub fn symbol(&self) -> &str {
+        &self.symbol
+    }
+
+    pub fn style(&self) -> NumberStyle {
+        self.style
+    }
+
+    /// Amount with separators and no symbol, e.g. `1,234.50`
+    pub fn amount(&self, amount: Decimal) -> String {
+        format_grouped(amount, CURRENCY_DP, self.style)
+    }
+
+    /// Amount with the currency symbol; the sign goes first, e.g. `-€1.250,00`
+    pub fn money(&self, amount: Decimal) -> String {
+        self.with_symbol(self.amount(amount))
+    }
+
+    /// Unit price with size-aware precision and no symbol
+    pub fn price(&self, price: Decimal, asset_type: AssetType) -> String {
+        format_grouped(price, price_decimals(price, asset_type), self.style)
+    }
+
+    /// Unit price with size-aware precision and the currency symbol
+    pub fn money_price(&self, price: Decimal, asset_type: AssetType) -> String {
+        self.with_symbol(self.price(price, asset_type))
+    }
+
+    fn with_symbol(&self, formatted: String) -> String {
+        match formatted.strip_prefix('-') {
+            Some(unsigned) => format!("-{}{}", self.symbol, unsigned),
+            None => format!("{}{}", self.symbol, formatted),
+        }
+    }
+}
+
+/// Rounds a money amount to the places it is displayed with
+pub fn round_money(amount: Decimal) -> Decimal {
+    round_display(amount, CURRENCY_DP)
+}
+
+/// Rounds any displayed value to `dp` places with the display rounding rule
+/// Decimal's own `{:.2}` truncates, so format the rounded value instead
+pub fn round_display(value: Decimal, dp: u32) -> Decimal {
+    value.round_dp_with_strategy(dp, DISPLAY_ROUNDING)
+}
+
+/// Fixed-point text for a non-money value such as a share count, e.g. `2.675` -> `2.68`
+pub fn format_fixed(value: Decimal, dp: u32) -> String {
+    format!("{:.*}", dp as usize, round_display(value, dp))
+}
+
+/// Signed percentage with two places, e.g. `+5.00%`
+pub fn format_pct(pct: Decimal) -> String {
+    format!("{:+.2}%", round_display(pct, CURRENCY_DP))
+}
+
+/// Sums amounts after rounding each one, so a displayed total matches its displayed rows
+/// e.g. three rows of 0.005 show as 0.01 each and total 0.03, not 0.02
+pub fn sum_rounded<I: IntoIterator<Item = Decimal>>(amounts: I) -> Decimal {
+    amounts.into_iter().map(round_money).sum()
+}
+
+/// Formats a money amount with thousands separators and no symbol
+/// e.g. `1234567.891` -> `1,234,567.89`, `-0.5` -> `-0.50`
+pub fn format_amount(amount: Decimal) -> String {
+    MoneyFormat::default().amount(amount)
+}
+
+/// Exact CSV text for a quantity or unit price, read back unchanged by the importers
+/// Trailing zeros are dropped and there is never an exponent, e.g. `1.500000` -> `1.5`
+pub fn csv_decimal(value: Decimal) -> String {
+    value.normalize().to_string()
+}
+
+/// CSV text for a money amount, rounded to cents like on screen, e.g. `1500.00` -> `1500`
+pub fn csv_money(amount: Decimal) -> String {
+    csv_decimal(round_money(amount))
+}
+
+/// One CSV field, quoted when it holds a comma, quote or line break
+/// Inner quotes are doubled, e.g. `A "B"` -> `"A ""B"""`
+pub fn csv_field(text: &str) -> String {
+    if text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+/// Decimal places to show for a unit price
+/// Prices of $1 and up use cents; cheaper assets keep enough places for
+/// four significant digits, up to 4 for stocks and 8 for crypto
+pub fn price_decimals(price: Decimal, asset_type: AssetType) -> u32 {
+    let abs = price.abs();
+    if abs.is_zero() || abs >= Decimal::ONE {
+        return CURRENCY_DP;
+    }
+
+    let max_dp = match asset_type {
+        AssetType::Stock => MAX_STOCK_PRICE_DP,
+        AssetType::Crypto => MAX_CRYPTO_PRICE_DP,
+    };
+    (leading_fraction_zeros(abs, MAX_CRYPTO_PRICE_DP) + SUB_DOLLAR_SIG_DIGITS)
+        .clamp(CURRENCY_DP, max_dp)
+}
+
+/// Zeros between the decimal point and the first significant digit of a value under 1
+/// e.g. `0.00031` -> 3, counting no further than `limit`
+fn leading_fraction_zeros(abs: Decimal, limit: u32) -> u32 {
+    let mut leading_zeros = 0;
+    let mut scaled = abs;
+    while scaled < Decimal::new(1, 1) && leading_zeros < limit {
+        scaled *= Decimal::TEN;
+        leading_zeros += 1;
+    }
+    leading_zeros
+}
+
+/// Most decimal places to show for a quantity, before trailing zeros are dropped
+/// Whole counts need none; fractions of a unit keep four significant digits, up to
+/// 4 places for stocks and 8 for crypto
+pub fn quantity_decimals(quantity: Decimal, asset_type: AssetType) -> u32 {
+    let abs = quantity.abs();
+    let (unit_dp, max_dp) = match asset_type {
+        AssetType::Stock => (STOCK_QTY_DP, MAX_STOCK_QTY_DP),
+        AssetType::Crypto => (CRYPTO_QTY_DP, MAX_CRYPTO_QTY_DP),
+    };
+    if abs.is_zero() || abs.fract().is_zero() {
+        return 0;
+    }
+    if abs >= Decimal::ONE {
+        return unit_dp;
+    }
+    (leading_fraction_zeros(abs, max_dp) + SUB_UNIT_QTY_SIG_DIGITS).min(max_dp)
+}
+
+/// Formats a share or coin quantity with precision suited to its size and asset type
+/// e.g. `0.00031` BTC -> `0.00031`, `1200` shares -> `1200`, `2.5` shares -> `2.5`
+pub fn format_qty(quantity: Decimal, asset_type: AssetType) -> String {
+    let dp = quantity_decimals(quantity, asset_type);
+    round_display(quantity, dp).normalize().to_string()
+}
+
+/// Formats a unit price with precision suited to its size and asset type
+/// e.g. `0.00001234` crypto -> `0.00001234`, `64250.5` -> `64,250.50`
+pub fn format_price(price: Decimal, asset_type: AssetType) -> String {
+    MoneyFormat::default().price(price, asset_type)
+}
+
+/// Rounds to `dp` places and inserts the style's separators
+fn format_grouped(amount: Decimal, dp: u32, style: NumberStyle) -> String {
+    let (group_sep, decimal_mark) = style.separators();
+    let rounded = round_display(amount, dp);
+    let sign = if rounded.is_sign_negative() && !rounded.is_zero() {
+        "-"
+    } else {
+        ""
+    };
+
+    let plain = format!("{:.*}", dp as usize, rounded.abs());
+    let (int_part, frac_part) = plain.split_once('.').unwrap_or((plain.as_str(), ""));
+    let mut grouped = String::with_capacity(int_part.len() + int_part.len() / 3);
+    for (idx, digit) in int_part.chars().enumerate() {
+        if idx > 0 && (int_part.len() - idx) % 3 == 0 {
+            grouped.push(group_sep);
+        }
+        grouped.push(digit);
+    }
+
+    if frac_part.is_empty() {
+        format!("{}{}", sign, grouped)
+    } else {
+        format!("{}{}{}{}", sign, grouped, decimal_mark, frac_part)
+    }
+}
+
+/// Formats a money amount with the given currency symbol
+/// The sign goes before the symbol, e.g. `-$1,250.00`
+pub fn format_currency(amount: Decimal, symbol: &str) -> String {
+    MoneyFormat::new(symbol, NumberStyle::Us).money(amount)
+}
+
+/// Which side of its column a cell is padded against
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Align {
+    Left,
+    Right,
+}
+
+/// Plain-text table for command output
+/// Column widths come from the widest cell, so large values never push later
+/// columns out of line. Widths count characters, not bytes, so `…` and `▲` pad
+/// like any other character
+#[derive(Clone, Debug, Default)]
+pub struct TextTable {
+    aligns: Vec<Align>,
+    header: Option<Vec<String>>,
+    rows: Vec<Vec<String>>,
+}
+
+impl TextTable {
+    /// Table whose columns are aligned as given; rows with fewer cells are padded
+    pub fn new(aligns: &[Align]) -> Self {
+        Self {
+            aligns: aligns.to_vec(),
+            header: None,
+            rows: Vec::new(),
+        }
+    }
+
+    pub fn with_header(mut self, header: &[&str]) -> Self {
+        self.header = Some(header.iter().map(|cell| cell.to_string()).collect());
+        self
+    }
+
+    pub fn push_row(&mut self, cells: Vec<String>) {
+        self.rows.push(cells);
+    }
+
+    /// Width of every column: its widest cell, header included
+    pub fn widths(&self) -> Vec<usize> {
+        let mut widths = vec![0; self.aligns.len()];
+        for row in self.header.iter().chain(&self.rows) {
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = (*width).max(cell.chars().count());
+            }
+        }
+        widths
+    }
+
+    /// Width of a full line, for rules drawn under the header
+    pub fn line_width(&self) -> usize {
+        let widths = self.widths();
+        widths.iter().sum::<usize>() + widths.len().saturating_sub(1)
+    }
+
+    /// The header (if any) then each row, columns separated by a space
+    /// Trailing padding is trimmed
+    pub fn lines(&self) -> Vec<String> {
+        let widths = self.widths();
+        self.header
+            .iter()
+            .chain(&self.rows)
+            .map(|row| {
+                let cells: Vec<String> = widths
+                    .iter()
+                    .zip(&self.aligns)
+                    .enumerate()
+                    .map(|(i, (width, align))| {
+                        let cell = row.get(i).map(String::as_str).unwrap_or("");
+                        let padding = " ".repeat(width - cell.chars().count());
+                        match align {
+                            Align::Left => format!("{}{}", cell, padding),
+                            Align::Right => format!("{}{}", padding, cell),
+                        }
+                    })
+                    .collect();
+                cells.join(" ").trim_end().to_string()
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,119 @@
+/// FX Module
+///
+/// Cash can be held in currencies other than the base one. Each currency is a
+/// separate bucket; nothing converts between them automatically. A trade
+/// settles in the asset's trading currency when the account holds a bucket in
+/// it, so funding EUR makes `SAP.DE` buys draw on euros. Totals in the base
+/// currency, foreign cash and holdings quoted in another currency alike, use
+/// Yahoo FX quotes such as `EURUSD=X`.
+use std::collections::HashMap;
+
+use rust_decimal::Decimal;
+
+use crate::Finance::AssetType;
+use crate::FinanceProvider::PriceProvider;
+use crate::format::MoneyFormat;
+
+/// Currency of the main cash balance, and of any asset without a known other one
+pub const BASE_CURRENCY: &str = "USD";
+
+// Exchange suffixes whose listings trade in a currency other than the base
+// London (.L) is left out: its quotes are in pence
+const EXCHANGE_CURRENCIES: [(&str, &str); 17] = [
+    ("DE", "EUR"),
+    ("F", "EUR"),
+    ("PA", "EUR"),
+    ("AS", "EUR"),
+    ("BR", "EUR"),
+    ("MI", "EUR"),
+    ("MC", "EUR"),
+    ("LS", "EUR"),
+    ("HE", "EUR"),
+    ("TO", "CAD"),
+    ("V", "CAD"),
+    ("SW", "CHF"),
+    ("T", "JPY"),
+    ("HK", "HKD"),
+    ("AX", "AUD"),
+    ("ST", "SEK"),
+    ("OL", "NOK"),
+];
+
+/// Three-letter currency code in upper case, None for anything else
+pub fn parse_currency(text: &str) -> Option<String> {
+    let code = text.trim().to_uppercase();
+    (code.len() == 3 && code.chars().all(|c| c.is_ascii_alphabetic())).then_some(code)
+}
+
+/// Currency a symbol is quoted in: the quote side of a crypto pair, the
+/// exchange's currency for a suffixed listing, otherwise the base currency
+pub fn trading_currency(symbol: &str) -> String {
+    let symbol = symbol.to_uppercase();
+    if let Some((_, quote)) = symbol.rsplit_once('-')
+        && AssetType::for_symbol(&symbol) == AssetType::Crypto
+    {
+        // Dollar stablecoins settle like dollars
+        return match quote {
+            "USDT" | "USDC" => "USD".to_string(),
+            other => other.to_string(),
+        };
+    }
+    symbol
+        .rsplit_once('.')
+        .and_then(|(_, suffix)| {
+            EXCHANGE_CURRENCIES
+                .iter()
+                .find(|(exchange, _)| *exchange == suffix)
+        })
+        .map(|(_, currency)| currency.to_string())
+        .unwrap_or_else(|| BASE_CURRENCY.to_string())
+}
+
+/// Yahoo symbol for the rate converting `from` into `to`, e.g. `EURUSD=X`
+pub fn fx_symbol(from: &str, to: &str) -> String {
+    format!("{}{}=X", from, to)
+}
+
+/// Units of the base currency one unit of `currency` buys, None without a quote
+pub async fn rate_to_base(provider: &dyn PriceProvider, currency: &str) -> Option<Decimal> {
+    if currency == BASE_CURRENCY {
+        return Some(Decimal::ONE);
+    }
+    let rate = provider
+        .curr_price(&fx_symbol(currency, BASE_CURRENCY))
+        .await;
+    (rate > Decimal::ZERO).then_some(rate)
+}
+
+/// Rate of `currency` from a map built by `rates_to_base`; the base currency is 1
+pub fn rate_in(rates: &HashMap<String, Decimal>, currency: &str) -> Option<Decimal> {
+    if currency == BASE_CURRENCY {
+        Some(Decimal::ONE)
+    } else {
+        rates.get(currency).copied()
+    }
+}
+
+/// Rate to the base currency of each of `currencies` that has a quote
+pub async fn rates_to_base(
+    provider: &dyn PriceProvider,
+    currencies: &[String],
+) -> HashMap<String, Decimal> {
+    let mut rates = HashMap::new();
+    for currency in currencies {
+        if let Some(rate) = rate_to_base(provider, currency).await {
+            rates.insert(currency.clone(), rate);
+        }
+    }
+    rates
+}
+
+/// Amount in the base currency with its symbol, other currencies with their code
+/// e.g. `$1,250.00` or `1,250.00 EUR`
+pub fn money_in(money: &MoneyFormat, currency: &str, amount: Decimal) -> String {
+    if currency == BASE_CURRENCY {
+        money.money(amount)
+    } else {
+        format!("{} {}", money.amount(amount), currency)
+    }
+}
@@ -0,0 +1,265 @@
+/// HTTP Module
+///
+/// Optional read-only status server, compiled with the `http` feature.
+/// Serves `GET /portfolio` and `GET /orders` as JSON from the shared app state so
+/// the portfolio can be scraped while the TUI runs. It only starts when
+/// NAVIIN_HTTP_ADDR is set, and every request needs the bearer token from
+/// NAVIIN_HTTP_TOKEN. Requests are handled on the main tokio runtime.
+use std::env;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+
+use rust_decimal::Decimal;
+use serde_json::{Value, json};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::task::JoinHandle;
+
+use crate::AppState::{AppState, lock_state};
+use crate::error::NaviinError;
+use crate::fx;
+
+/// Environment variable holding the bind address, e.g. 127.0.0.1:8787
+pub const ADDR_VAR: &str = "NAVIIN_HTTP_ADDR";
+/// Environment variable holding the bearer token clients must send
+pub const TOKEN_VAR: &str = "NAVIIN_HTTP_TOKEN";
+
+/// Largest request head accepted; these endpoints take no body
+const MAX_REQUEST_BYTES: usize = 8 * 1024;
+
+/// Where to listen and the token to require
+#[derive(Clone, Debug)]
+pub struct HttpConfig {
+    pub addr: SocketAddr,
+    pub token: String,
+}
+
+impl HttpConfig {
+    /// Reads the server settings from the environment
+    /// Returns Ok(None) when no address is set, which leaves the server disabled
+    pub fn from_env() -> Result<Option<Self>, NaviinError> {
+        let addr = match env::var(ADDR_VAR) {
+            Ok(addr) if !addr.trim().is_empty() => addr,
+            _ => return Ok(None),
+        };
+        let addr = addr
+            .trim()
+            .parse()
+            .map_err(|_| NaviinError::InvalidConfig {
+                key: ADDR_VAR.to_string(),
+                value: addr.clone(),
+            })?;
+        let token = match env::var(TOKEN_VAR) {
+            Ok(token) if !token.trim().is_empty() => token.trim().to_string(),
+            _ => return Err(NaviinError::MissingConfig(TOKEN_VAR.to_string())),
+        };
+        Ok(Some(Self { addr, token }))
+    }
+}
+
+// SECTION: Server
+
+/// Binds the configured address and serves requests in the background
+/// Binding happens before returning so address errors can be reported at startup
+pub async fn start(
+    config: HttpConfig,
+    state: Arc<Mutex<AppState>>,
+) -> Result<JoinHandle<()>, NaviinError> {
+    let listener = TcpListener::bind(config.addr).await?;
+    Ok(tokio::spawn(serve(listener, state, config.token)))
+}
+
+/// Accepts connections until the task is dropped, one task per connection
+pub async fn serve(listener: TcpListener, state: Arc<Mutex<AppState>>, token: String) {
+    let token = Arc::new(token);
+    loop {
+        let Ok((stream, _)) = listener.accept().await else {
+            continue;
+        };
+        let state = state.clone();
+        let token = token.clone();
+        tokio::spawn(async move {
+            // A client that disconnects early is not an error worth reporting
+            let _ = handle_connection(stream, &state, &token).await;
+        });
+    }
+}
+
+async fn handle_connection(
+    mut stream: TcpStream,
+    state: &Arc<Mutex<AppState>>,
+    token: &str,
+) -> std::io::Result<()> {
+    let head = match read_request_head(&mut stream).await? {
+        Some(head) => head,
+        None => return write_response(&mut stream, 400, &error_body("Bad request")).await,
+    };
+    let (status, body) = route(&head, state, token).await;
+    write_response(&mut stream, status, &body).await
+}
+
+// Reads up to the blank line ending the headers, None if it never arrives
+async fn read_request_head(stream: &mut TcpStream) -> std::io::Result<Option<String>> {
+    let mut buf = Vec::new();
+    let mut chunk = [0u8; 1024];
+    while !buf.windows(4).any(|w| w == b"\r\n\r\n") {
+        if buf.len() > MAX_REQUEST_BYTES {
+            return Ok(None);
+        }
+        let n = stream.read(&mut chunk).await?;
+        if n == 0 {
+            return Ok(None);
+        }
+        buf.extend_from_slice(&chunk[..n]);
+    }
+    Ok(String::from_utf8(buf).ok())
+}
+
+async fn write_response(stream: &mut TcpStream, status: u16, body: &Value) -> std::io::Result<()> {
+    let body = body.to_string();
+    let reason = match status {
+        200 => "OK",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        404 => "Not Found",
+        _ => "Method Not Allowed",
+    };
+    let auth_header = if status == 401 {
+        "WWW-Authenticate: Bearer\r\n"
+    } else {
+        ""
+    };
+    let response = format!(
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n{}Connection: close\r\n\r\n{}",
+        status,
+        reason,
+        body.len(),
+        auth_header,
+        body
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await
+}
+
+// SECTION: Routing
+
+// Status code and JSON body for a request head
+async fn route(head: &str, state: &Arc<Mutex<AppState>>, token: &str) -> (u16, Value) {
+    let mut lines = head.lines();
+    let mut request_line = lines.next().unwrap_or_default().split_whitespace();
+    let (Some(method), Some(target)) = (request_line.next(), request_line.next()) else {
+        return (400, error_body("Bad request"));
+    };
+
+    let authorized = lines
+        .filter_map(|line| line.split_once(':'))
+        .filter(|(name, _)| name.trim().eq_ignore_ascii_case("authorization"))
+        .filter_map(|(_, value)| value.trim().strip_prefix("Bearer "))
+        .any(|sent| tokens_match(sent.trim(), token));
+    if !authorized {
+        return (401, error_body("Missing or invalid bearer token"));
+    }
+    if method != "GET" {
+        return (405, error_body("Only GET is supported"));
+    }
+
+    // Ignore any query string
+    let path = target.split('?').next().unwrap_or_default();
+    match path {
+        "/portfolio" => (200, portfolio_json(state).await),
+        "/orders" => (200, orders_json(state)),
+        _ => (404, error_body("Not found")),
+    }
+}
+
+// Compares every byte so the time taken doesn't reveal how much of the token matched
+fn tokens_match(sent: &str, expected: &str) -> bool {
+    sent.len() == expected.len()
+        && sent
+            .bytes()
+            .zip(expected.bytes())
+            .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+fn error_body(message: &str) -> Value {
+    json!({ "error": message })
+}
+
+// SECTION: Payloads
+
+/// Cash, each holding marked at the current price, and the total account value
+/// Holdings value and total are in the base currency, other currencies' cash and
+/// quotes converted at current rates; the total is net of any margin loan and
+/// null when one of the currencies has no rate
+/// Decimals are encoded as strings so no precision is lost
+pub async fn portfolio_json(state: &Arc<Mutex<AppState>>) -> Value {
+    let (cash, holdings, currencies, provider) = {
+        let state_guard = lock_state(state);
+        (
+            state_guard.check_balance(),
+            state_guard.get_holdings_map(),
+            state_guard.valued_currencies(),
+            state_guard.provider(),
+        )
+    };
+
+    let rates = fx::rates_to_base(provider.as_ref(), &currencies).await;
+    let mut symbols: Vec<_> = holdings.keys().cloned().collect();
+    symbols.sort();
+    let mut rows = Vec::new();
+    let mut holdings_value = Decimal::ZERO;
+    let mut unconverted = false;
+    for symbol in symbols {
+        let holding = &holdings[&symbol];
+        let price = provider.curr_price(&symbol).await;
+        // An unpriced holding is reported without a value rather than as zero
+        let (price, value) = if price > Decimal::ZERO {
+            let value = price * holding.get_qty();
+            match fx::rate_in(&rates, &fx::trading_currency(&symbol)) {
+                Some(rate) => holdings_value += value * rate,
+                None => unconverted = true,
+            }
+            (Some(price), Some(value))
+        } else {
+            (None, None)
+        };
+        rows.push(json!({
+            "symbol": symbol,
+            "quantity": holding.get_qty(),
+            "avg_price": holding.get_avg_price(),
+            "price": price,
+            "market_value": value,
+        }));
+    }
+
+    let total_value = lock_state(state)
+        .net_cash_value(&rates)
+        .filter(|_| !unconverted)
+        .map(|cash| cash + holdings_value);
+    json!({
+        "cash": cash,
+        "holdings": rows,
+        "holdings_value": holdings_value,
+        "total_value": total_value,
+    })
+}
+
+/// Open orders in book order
+pub fn orders_json(state: &Arc<Mutex<AppState>>) -> Value {
+    let orders = lock_state(state).get_open_orders();
+    let rows: Vec<Value> = orders
+        .iter()
+        .map(|order| {
+            json!({
+                "symbol": order.get_symbol(),
+                "order_type": format!("{:?}", order.get_order_type()),
+                "side": format!("{:?}", order.get_side()),
+                "quantity": order.get_qty(),
+                "price": order.get_price_per(),
+                "timestamp": order.get_timestamp(),
+            })
+        })
+        .collect();
+    json!({ "orders": rows })
+}
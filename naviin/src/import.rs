@@ -1,12 +1,16 @@
 use std::collections::HashMap;
 use std::fs::File;
-use std::io::{BufRead, BufReader};
+use std::io::{BufRead, BufReader, Cursor, Read};
+use std::path::Path;
 
+use flate2::read::{DeflateDecoder, MultiGzDecoder};
 use rust_decimal::Decimal;
 
-use crate::AppState::AppState;
+use crate::AppState::{AppState, lock_state};
 use crate::Finance;
 use crate::Orders::{Side, Trade};
+use crate::cashflow::{CashFlow, CashFlowKind};
+use crate::fx;
 
 #[derive(Debug)]
 struct CsvTradeRow {
@@ -19,12 +23,163 @@ struct CsvTradeRow {
     currency: Option<String>,
 }
 
+impl CsvTradeRow {
+    // Symbol as priced by the provider: crypto assets become pairs like BTC-USD,
+    // stocks go through the account's symbol normalization (BRK.B -> BRK-B)
+    fn symbol(&self, account: &AppState) -> String {
+        if self.asset_type == "CRYPTO" {
+            return Finance::crypto_pair(&self.asset, self.currency.as_deref());
+        }
+        let symbol = account.normalize_symbol(&self.asset);
+        // A row marked STOCK stays a stock even if it shares a coin's ticker
+        if Finance::AssetType::for_symbol(&symbol) == Finance::AssetType::Crypto {
+            self.asset.clone()
+        } else {
+            symbol
+        }
+    }
+}
+
+/// Imports a broker trade CSV (`date,asset,asset_type,side,quantity,price[,currency]`)
+/// Rows apply in file order, so a sell can use shares bought on an earlier line.
+/// A gzipped CSV or a zip holding a single CSV is decompressed first
 pub async fn import_trades_from_csv(
     state: &std::sync::Arc<std::sync::Mutex<AppState>>,
     path: &str,
 ) -> Result<String, String> {
+    import_trades(state, path, false).await
+}
+
+/// Parses and validates a trade CSV exactly as `import_trades_from_csv` would and
+/// reports what it would import, without changing state. Sells are checked against
+/// a copy of the current holdings that earlier buy and sell rows are applied to
+pub async fn dry_run_trades_from_csv(
+    state: &std::sync::Arc<std::sync::Mutex<AppState>>,
+    path: &str,
+) -> Result<String, String> {
+    import_trades(state, path, true).await
+}
+
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+const ZIP_LOCAL_HEADER: [u8; 4] = *b"PK\x03\x04";
+const ZIP_CENTRAL_HEADER: [u8; 4] = *b"PK\x01\x02";
+const ZIP_END_OF_DIRECTORY: [u8; 4] = *b"PK\x05\x06";
+
+// Opens a trade file for line-by-line reading, recognising gzip and zip by their
+// magic bytes or extension so a broker export can be imported without unpacking it
+fn open_trade_file(path: &str) -> Result<Box<dyn BufRead>, String> {
     let file = File::open(path).map_err(|e| format!("Failed to open file: {e}"))?;
-    let reader = BufReader::new(file);
+    let mut reader = BufReader::new(file);
+    let head = reader
+        .fill_buf()
+        .map_err(|e| format!("Failed to read file: {e}"))?;
+    let extension = Path::new(path)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_ascii_lowercase());
+
+    if head.starts_with(&GZIP_MAGIC) || extension.as_deref() == Some("gz") {
+        return Ok(Box::new(BufReader::new(MultiGzDecoder::new(reader))));
+    }
+    if head.starts_with(&ZIP_LOCAL_HEADER) || extension.as_deref() == Some("zip") {
+        let mut bytes = Vec::new();
+        reader
+            .read_to_end(&mut bytes)
+            .map_err(|e| format!("Failed to read file: {e}"))?;
+        return Ok(Box::new(Cursor::new(read_single_zip_entry(&bytes)?)));
+    }
+    Ok(Box::new(reader))
+}
+
+fn zip_u16(bytes: &[u8], at: usize) -> Option<usize> {
+    let field = bytes.get(at..at + 2)?;
+    Some(u16::from_le_bytes([field[0], field[1]]) as usize)
+}
+
+fn zip_u32(bytes: &[u8], at: usize) -> Option<usize> {
+    let field = bytes.get(at..at + 4)?;
+    Some(u32::from_le_bytes([field[0], field[1], field[2], field[3]]) as usize)
+}
+
+// Extracts the only file in a zip archive. Archives with several entries, a folder,
+// encryption or a compression method other than stored/deflate are refused
+fn read_single_zip_entry(bytes: &[u8]) -> Result<Vec<u8>, String> {
+    let corrupt = || "Zip archive is corrupt or truncated".to_string();
+
+    // The end-of-directory record sits at the end, after an optional comment
+    let end = bytes
+        .len()
+        .checked_sub(22)
+        .and_then(|last| {
+            (0..=last)
+                .rev()
+                .find(|&at| bytes[at..].starts_with(&ZIP_END_OF_DIRECTORY))
+        })
+        .ok_or_else(corrupt)?;
+    let entries = zip_u16(bytes, end + 10).ok_or_else(corrupt)?;
+    if entries != 1 {
+        return Err(format!(
+            "Zip archive must contain exactly one CSV file (found {entries} entries)"
+        ));
+    }
+
+    let central = zip_u32(bytes, end + 16).ok_or_else(corrupt)?;
+    if !bytes[central.min(bytes.len())..].starts_with(&ZIP_CENTRAL_HEADER) {
+        return Err(corrupt());
+    }
+    let flags = zip_u16(bytes, central + 8).ok_or_else(corrupt)?;
+    let method = zip_u16(bytes, central + 10).ok_or_else(corrupt)?;
+    let compressed_size = zip_u32(bytes, central + 20).ok_or_else(corrupt)?;
+    let name_len = zip_u16(bytes, central + 28).ok_or_else(corrupt)?;
+    let local = zip_u32(bytes, central + 42).ok_or_else(corrupt)?;
+    let name = bytes
+        .get(central + 46..central + 46 + name_len)
+        .map(String::from_utf8_lossy)
+        .ok_or_else(corrupt)?;
+
+    if name.ends_with('/') {
+        return Err(format!(
+            "Zip archive must contain a CSV file, not the folder {name}"
+        ));
+    }
+    if flags & 1 != 0 {
+        return Err("Encrypted zip archives are not supported".to_string());
+    }
+    if compressed_size == u32::MAX as usize {
+        return Err("Zip64 archives are not supported".to_string());
+    }
+
+    if !bytes[local.min(bytes.len())..].starts_with(&ZIP_LOCAL_HEADER) {
+        return Err(corrupt());
+    }
+    let local_name_len = zip_u16(bytes, local + 26).ok_or_else(corrupt)?;
+    let extra_len = zip_u16(bytes, local + 28).ok_or_else(corrupt)?;
+    let start = local + 30 + local_name_len + extra_len;
+    let data = bytes
+        .get(start..start + compressed_size)
+        .ok_or_else(corrupt)?;
+
+    match method {
+        0 => Ok(data.to_vec()),
+        8 => {
+            let mut contents = Vec::new();
+            DeflateDecoder::new(data)
+                .read_to_end(&mut contents)
+                .map_err(|e| format!("Failed to decompress {name}: {e}"))?;
+            Ok(contents)
+        }
+        other => Err(format!(
+            "Zip entry {name} uses unsupported compression method {other}"
+        )),
+    }
+}
+
+async fn import_trades(
+    state: &std::sync::Arc<std::sync::Mutex<AppState>>,
+    path: &str,
+    dry_run: bool,
+) -> Result<String, String> {
+    let reader = open_trade_file(path)?;
 
     let mut lines = reader.lines();
     let header_line = match lines.next() {
@@ -48,6 +203,15 @@ pub async fn import_trades_from_csv(
     let mut errors = 0usize;
     let mut last_errors: Vec<String> = Vec::new();
 
+    // A dry run applies rows to a copy of the quantities held instead of the state
+    let mut simulated: Option<HashMap<String, Decimal>> = dry_run.then(|| {
+        lock_state(state)
+            .get_holdings_map()
+            .into_iter()
+            .map(|(symbol, holding)| (symbol, holding.get_qty()))
+            .collect()
+    });
+
     for (idx, line) in lines.enumerate() {
         let line_number = idx + 2; // header is line 1
         let raw = match line {
@@ -75,49 +239,82 @@ pub async fn import_trades_from_csv(
             }
         };
 
+        let symbol = row.symbol(&lock_state(state));
+        if let Some(held) = simulated.as_mut() {
+            let available_qty = held.get(&symbol).copied().unwrap_or_default();
+            match row.side {
+                Side::Buy => {
+                    held.insert(symbol, available_qty + row.quantity);
+                }
+                Side::Sell if available_qty < row.quantity => {
+                    errors += 1;
+                    skipped += 1;
+                    push_error(
+                        &mut last_errors,
+                        insufficient_holdings(line_number, &symbol, available_qty, row.quantity),
+                    );
+                    continue;
+                }
+                Side::Sell => {
+                    held.insert(symbol, available_qty - row.quantity);
+                }
+            }
+            imported += 1;
+            continue;
+        }
         match row.side {
             Side::Buy => {
-                let mut trade = Trade::buy(row.asset.clone(), row.quantity, row.price);
-                trade.set_timestamp(parse_date_to_timestamp(&row.date));
-                {
-                    let mut guard = state.lock().unwrap();
-                    guard.add_trade(trade);
-                }
-                Finance::add_to_holdings(
-                    &row.asset,
+                let mut guard = lock_state(state);
+                let timestamp = parse_date_to_timestamp(&row.date, guard.now_timestamp());
+                let trade = Trade::buy_with_type(
+                    symbol.clone(),
                     row.quantity,
                     row.price,
-                    &mut state.lock().unwrap(),
-                )
-                .await;
+                    Finance::IMPORT_ORDER_TYPE.to_string(),
+                    timestamp,
+                );
+                guard.add_trade(trade);
+                Finance::add_to_holdings(&symbol, row.quantity, row.price, &mut guard);
             }
             Side::Sell => {
-                let available_qty = { state.lock().unwrap().get_ticker_holdings_qty(&row.asset) };
+                // Check and apply under one lock so a concurrent sell can't interleave
+                let mut guard = lock_state(state);
+                let available_qty = guard.get_ticker_holdings_qty(&symbol);
                 if available_qty < row.quantity {
                     errors += 1;
                     skipped += 1;
                     push_error(
                         &mut last_errors,
-                        format!(
-                            "Line {line_number}: Insufficient holdings for {} (have {}, need {})",
-                            row.asset, available_qty, row.quantity
-                        ),
+                        insufficient_holdings(line_number, &symbol, available_qty, row.quantity),
                     );
                     continue;
                 }
-                let mut trade = Trade::sell(row.asset.clone(), row.quantity, row.price);
-                trade.set_timestamp(parse_date_to_timestamp(&row.date));
-                {
-                    let mut guard = state.lock().unwrap();
-                    guard.add_trade(trade);
-                }
-                Finance::remove_from_holdings(&row.asset, row.quantity, &mut state.lock().unwrap())
-                    .await;
+                let timestamp = parse_date_to_timestamp(&row.date, guard.now_timestamp());
+                let sold = Finance::remove_from_holdings(&symbol, row.quantity, &mut guard);
+                guard.add_trade(Trade::sell_with_type(
+                    symbol.clone(),
+                    sold,
+                    row.price,
+                    Finance::IMPORT_ORDER_TYPE.to_string(),
+                    timestamp,
+                ));
             }
         }
         imported += 1;
     }
 
+    if dry_run {
+        let examples = if errors > 0 {
+            format!(" {errors} errors. Example: {}", last_errors.join(" | "))
+        } else {
+            String::new()
+        };
+        return Ok(format!(
+            "Dry run: would import {imported} trades ({skipped} skipped).{examples} \
+             Nothing was changed"
+        ));
+    }
+
     if imported == 0 && errors > 0 {
         return Err(format!(
             "No trades imported. Errors: {errors}. Example: {}",
@@ -135,6 +332,165 @@ pub async fn import_trades_from_csv(
     }
 }
 
+fn insufficient_holdings(line_number: usize, symbol: &str, have: Decimal, need: Decimal) -> String {
+    format!("Line {line_number}: Insufficient holdings for {symbol} (have {have}, need {need})")
+}
+
+/// Replays a cash flow CSV (`date,type,amount,currency`, as written by `cashflow export`)
+/// Each row changes the balance like `fund`/`withdraw` and is added to the ledger
+/// Withdrawals larger than the balance at that point are skipped, and so are rows in
+/// another currency, whose amount in that currency the ledger doesn't keep
+pub fn import_cash_flows_from_csv(
+    state: &std::sync::Arc<std::sync::Mutex<AppState>>,
+    path: &str,
+) -> Result<String, String> {
+    let contents =
+        std::fs::read_to_string(path).map_err(|e| format!("Failed to open file: {e}"))?;
+    let mut lines = contents.lines();
+    let Some(header_line) = lines.next() else {
+        return Err("CSV is empty".to_string());
+    };
+    let header_map = build_header_map(&parse_csv_row(header_line));
+    for required in ["date", "type", "amount"] {
+        if !header_map.contains_key(required) {
+            return Err(format!("Missing required column: {required}"));
+        }
+    }
+
+    let mut imported = 0usize;
+    let mut errors = 0usize;
+    let mut last_errors: Vec<String> = Vec::new();
+    let mut guard = lock_state(state);
+    for (idx, raw) in lines.enumerate() {
+        let line_number = idx + 2; // header is line 1
+        if raw.trim().is_empty() {
+            continue;
+        }
+        let cols = parse_csv_row(raw);
+        let flow = match parse_cash_flow_row(&cols, &header_map, guard.now_timestamp()) {
+            Ok(flow) => flow,
+            Err(msg) => {
+                errors += 1;
+                push_error(&mut last_errors, format!("Line {line_number}: {msg}"));
+                continue;
+            }
+        };
+        match flow.kind {
+            CashFlowKind::Deposit => guard.deposit(flow.amount),
+            CashFlowKind::Withdrawal => {
+                if flow.amount > guard.check_balance() {
+                    errors += 1;
+                    push_error(
+                        &mut last_errors,
+                        format!("Line {line_number}: Withdrawal exceeds balance"),
+                    );
+                    continue;
+                }
+                guard.withdraw(flow.amount);
+            }
+        }
+        guard.add_cash_flow(flow);
+        imported += 1;
+    }
+
+    if imported == 0 && errors > 0 {
+        return Err(format!(
+            "No cash flows imported. Errors: {errors}. Example: {}",
+            last_errors.join(" | ")
+        ));
+    }
+    if errors > 0 {
+        Ok(format!(
+            "Imported {imported} cash flows. {errors} errors. Example: {}",
+            last_errors.join(" | ")
+        ))
+    } else {
+        Ok(format!("Imported {imported} cash flows."))
+    }
+}
+
+fn parse_cash_flow_row(
+    cols: &[String],
+    header_map: &HashMap<String, usize>,
+    now: i64,
+) -> Result<CashFlow, String> {
+    let date = get_value(cols, header_map, "date")?;
+    let kind_raw = get_value(cols, header_map, "type")?;
+    let amount = parse_decimal(&get_value(cols, header_map, "amount")?, "amount")?;
+
+    let kind =
+        CashFlowKind::parse(&kind_raw).ok_or("type must be Deposit or Withdrawal".to_string())?;
+    if amount <= Decimal::ZERO {
+        return Err("Amount must be positive".to_string());
+    }
+    // A missing or blank currency is the base one, as in ledgers exported before the column
+    if let Some(raw) = get_optional(cols, header_map, "currency") {
+        let currency = fx::parse_currency(&raw).ok_or(format!("Invalid currency: '{raw}'"))?;
+        if currency != fx::BASE_CURRENCY {
+            return Err(format!(
+                "{currency} flows can't be imported; fund them with 'fund <amount> {currency}'"
+            ));
+        }
+    }
+    Ok(CashFlow::new(
+        kind,
+        amount,
+        parse_date_to_timestamp(&date, now),
+    ))
+}
+
+/// Reads a newline- or comma-separated list of symbols and adds each to the watchlist
+/// Symbols are uppercased; blanks are ignored, duplicates and bad shapes are skipped
+pub fn import_watchlist_from_file(
+    state: &std::sync::Arc<std::sync::Mutex<AppState>>,
+    path: &str,
+) -> Result<String, String> {
+    let contents =
+        std::fs::read_to_string(path).map_err(|e| format!("Failed to open file: {e}"))?;
+
+    let mut added = 0usize;
+    let mut already_present = 0usize;
+    let mut invalid = 0usize;
+    let mut invalid_examples: Vec<String> = Vec::new();
+
+    let mut guard = lock_state(state);
+    for raw in contents.split(['\n', ',']) {
+        let symbol = guard.normalize_symbol(raw.trim().trim_matches('"'));
+        if symbol.is_empty() {
+            continue;
+        }
+        if !is_valid_symbol(&symbol) {
+            invalid += 1;
+            push_error(&mut invalid_examples, symbol);
+            continue;
+        }
+        if guard.add_to_watchlist(symbol) {
+            added += 1;
+        } else {
+            already_present += 1;
+        }
+    }
+
+    let mut report = format!(
+        "Added {added} symbols to watchlist ({already_present} already present, {invalid} invalid)."
+    );
+    if !invalid_examples.is_empty() {
+        report.push_str(&format!(" Invalid: {}", invalid_examples.join(" | ")));
+    }
+    Ok(report)
+}
+
+/// Cheap shape check for a ticker before it is stored
+/// Allows letters, digits, and the punctuation yfinance uses (BRK-B, ^GSPC, EURUSD=X)
+pub fn is_valid_symbol(symbol: &str) -> bool {
+    let len = symbol.chars().count();
+    (1..=12).contains(&len)
+        && symbol.chars().any(|c| c.is_ascii_alphanumeric())
+        && symbol
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '^' | '='))
+}
+
 fn push_error(errors: &mut Vec<String>, msg: String) {
     if errors.len() < 3 {
         errors.push(msg);
@@ -259,10 +615,11 @@ fn parse_csv_row(line: &str) -> Vec<String> {
     out
 }
 
-fn parse_date_to_timestamp(date: &str) -> i64 {
+// Missing or unrecognised dates fall back to `now`
+fn parse_date_to_timestamp(date: &str, now: i64) -> i64 {
     let trimmed = date.trim();
     if trimmed.is_empty() {
-        return chrono::Utc::now().timestamp();
+        return now;
     }
 
     if let Ok(dt) = chrono::DateTime::parse_from_rfc3339(trimmed) {
@@ -277,5 +634,5 @@ fn parse_date_to_timestamp(date: &str) -> i64 {
         .timestamp();
     }
 
-    chrono::Utc::now().timestamp()
+    now
 }
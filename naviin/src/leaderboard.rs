@@ -0,0 +1,108 @@
+/// Leaderboard Module
+///
+/// Ranks saved portfolios by total return for the `leaderboard` command. Each
+/// portfolio is read from its SQLite file without switching the session to it.
+/// Marks are fetched once per symbol across every portfolio, and a portfolio
+/// missing a mark is ranked on what could be priced and flagged.
+use std::collections::HashMap;
+
+use rust_decimal::Decimal;
+
+use crate::AppState::AppState;
+use crate::Finance::{Symbol, percent_of};
+use crate::format::{self, Align, MoneyFormat, TextTable};
+
+/// One portfolio's place on the board
+#[derive(Clone, Debug, PartialEq)]
+pub struct Standing {
+    pub name: String,
+    /// Cash net of any margin loan plus the holdings that could be priced
+    pub value: Decimal,
+    /// Deposits minus withdrawals from the cash flow ledger
+    pub net_deposited: Decimal,
+    /// Gain on net deposits in percent, None when nothing was deposited
+    pub return_pct: Option<Decimal>,
+    /// Holdings without a mark, left out of the value
+    pub unpriced: Vec<Symbol>,
+    /// The session's own portfolio
+    pub current: bool,
+}
+
+impl Standing {
+    pub fn new(name: String, account: &AppState, marks: &HashMap<Symbol, Decimal>) -> Self {
+        let mut value = account.check_balance() - account.get_margin_loan();
+        let mut unpriced = Vec::new();
+        for (symbol, holding) in account.get_holdings_map() {
+            match marks.get(&symbol) {
+                Some(price) => value += holding.get_qty() * price,
+                None => unpriced.push(symbol),
+            }
+        }
+        unpriced.sort();
+        let net_deposited = crate::cashflow::net_deposited(&account.get_cash_flows());
+        Self {
+            name,
+            value,
+            net_deposited,
+            return_pct: percent_of(value - net_deposited, net_deposited),
+            unpriced,
+            current: false,
+        }
+    }
+}
+
+/// Best return first; portfolios with nothing deposited go last, then by name
+pub fn rank(standings: &mut [Standing]) {
+    standings.sort_by(|a, b| {
+        b.return_pct
+            .is_some()
+            .cmp(&a.return_pct.is_some())
+            .then(b.return_pct.cmp(&a.return_pct))
+            .then(a.name.cmp(&b.name))
+    });
+}
+
+/// Ranked table with a note for each portfolio priced from partial data
+pub fn format_board(standings: &[Standing], money: &MoneyFormat) -> String {
+    let mut table = TextTable::new(&[
+        Align::Right,
+        Align::Left,
+        Align::Right,
+        Align::Right,
+        Align::Right,
+    ])
+    .with_header(&["#", "Portfolio", "Value", "Deposited", "Return"]);
+    for (place, standing) in standings.iter().enumerate() {
+        let name = if standing.current {
+            format!("{} (current)", standing.name)
+        } else {
+            standing.name.clone()
+        };
+        let name = if standing.unpriced.is_empty() {
+            name
+        } else {
+            format!("{} *", name)
+        };
+        table.push_row(vec![
+            (place + 1).to_string(),
+            name,
+            money.money(standing.value),
+            money.money(standing.net_deposited),
+            standing
+                .return_pct
+                .map(format::format_pct)
+                .unwrap_or_else(|| "N/A".to_string()),
+        ]);
+    }
+
+    let mut lines = vec!["Leaderboard:".to_string()];
+    lines.extend(table.lines());
+    for standing in standings.iter().filter(|s| !s.unpriced.is_empty()) {
+        lines.push(format!(
+            "* {}: partial data, no price for {}",
+            standing.name,
+            standing.unpriced.join(", ")
+        ));
+    }
+    lines.join("\n")
+}
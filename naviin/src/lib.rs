@@ -7,7 +7,31 @@ pub mod Orders;
 pub mod Storage;
 pub mod Tui;
 pub mod UserInput;
+pub mod cashflow;
+pub mod clock;
 pub mod commands;
 pub mod components;
+pub mod costbasis;
+pub mod demo;
 pub mod entities;
+pub mod error;
+pub mod events;
+pub mod format;
+pub mod fx;
+#[cfg(feature = "http")]
+pub mod http;
 pub mod import;
+pub mod leaderboard;
+pub mod margin;
+pub mod market;
+pub mod performance;
+pub mod rebalance;
+pub mod recurring;
+pub mod refresh;
+pub mod replay;
+pub mod risk;
+pub mod scenario;
+pub mod simulate;
+pub mod templates;
+pub mod undo;
+pub mod webhook;
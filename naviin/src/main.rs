@@ -2,22 +2,30 @@
 ///
 /// Initializes the application and starts the TUI interface.
 /// All command processing is now handled through the TUI.
+/// `naviin --demo` opens the bundled sample portfolio against an in-memory
+/// database, so nothing is read from or written to the real one.
+/// `naviin --starting-balance 25000` sets the paper money a brand new account
+/// is seeded with on first run (default $10,000).
 use dotenvy::dotenv;
 use std::env;
 use std::io;
-use std::sync::{Arc, atomic::AtomicBool};
+use std::panic;
+use std::sync::{Arc, Mutex, atomic::AtomicBool};
 
 use crossterm::{
+    cursor::Show,
     event::{DisableMouseCapture, EnableMouseCapture},
     execute,
     terminal::{EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode},
 };
 use ratatui::{Terminal, backend::CrosstermBackend};
-use sea_orm::{Database, DatabaseConnection};
+use sea_orm::DatabaseConnection;
 
-use naviin::AppState::monitor_order;
+use naviin::AppState::{AppState, lock_state, monitor_order};
 use naviin::Storage;
 use naviin::Tui::Tui;
+use naviin::demo;
+use naviin::error::NaviinError;
 
 /// SECTION: Terminal Setup
 
@@ -41,40 +49,95 @@ fn setup_terminal() -> io::Result<Terminal<CrosstermBackend<io::Stdout>>> {
 }
 
 /// Restores terminal to normal state
-/// Disables raw mode and returns to main screen
+/// Disables raw mode, returns to main screen and shows the cursor
+/// Every step is attempted even if an earlier one fails
 fn restore_terminal() -> io::Result<()> {
     // Disable raw mode
-    disable_raw_mode()?;
+    let raw_result = disable_raw_mode();
+
+    // Leave alternate screen, disable mouse capture and show the cursor
+    let screen_result = execute!(
+        io::stdout(),
+        LeaveAlternateScreen,
+        DisableMouseCapture,
+        Show
+    );
 
-    // Leave alternate screen and disable mouse capture
-    execute!(io::stdout(), LeaveAlternateScreen, DisableMouseCapture)?;
+    raw_result.and(screen_result)
+}
 
-    Ok(())
+/// Restores the terminal before the default panic message is printed
+/// Without this a panic leaves the shell in raw mode on the alternate screen
+fn install_panic_hook() {
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        let _ = restore_terminal();
+        default_hook(info);
+    }));
 }
 
 /// SECTION: Application Entry Point
 
+/// Flag that starts the sample portfolio without persistence
+const DEMO_FLAG: &str = "--demo";
+
 #[tokio::main]
 async fn main() {
     // Load environment variables from .env file
     dotenv().ok();
+    let args: Vec<String> = env::args().skip(1).collect();
+    let demo_mode = args.iter().any(|arg| arg == DEMO_FLAG);
+    let starting = match Storage::StartingAccount::from_config(&args, |key| env::var(key).ok()) {
+        Ok(starting) => starting,
+        Err(e) => {
+            eprintln!("{}", e);
+            return;
+        }
+    };
 
     // SECTION: Database Setup
 
-    // Connect to database
-    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set in .env");
-    let db: DatabaseConnection = Database::connect(&database_url)
-        .await
-        .expect("Failed to connect to database");
+    // Connect to database; the demo gets a throwaway in-memory one
+    let database_url = if demo_mode {
+        "sqlite::memory:".to_string()
+    } else {
+        match Storage::database_url() {
+            Ok(url) => url,
+            Err(e) => {
+                eprintln!("Failed to locate the database: {}", e);
+                return;
+            }
+        }
+    };
+    // Connect, bring the schema up to date and load the account, falling back to
+    // the backup if the database can't be read
+    let (db, loaded): (DatabaseConnection, AppState) =
+        match Storage::open_or_recover(&database_url).await {
+            Ok(opened) => opened,
+            Err(e) => {
+                eprintln!("Failed to open database: {}", e);
+                return;
+            }
+        };
 
     // SECTION: State Initialization
 
-    // Load application state from database
-    let state = Storage::load_state().await;
+    // Use the loaded account, or the sample portfolio for a demo
+    let state = Arc::new(Mutex::new(if demo_mode {
+        demo::demo_state()
+    } else {
+        loaded
+    }));
+
+    // A brand new account starts with paper money instead of $0
+    if let Err(e) = Storage::seed_first_run(&state, &db, &starting).await {
+        eprintln!("Failed to set up the new account: {}", e);
+        return;
+    }
 
     // Get initial watchlist for TUI
     let initial_watchlist = {
-        let state_guard = state.lock().unwrap();
+        let state_guard = lock_state(&state);
         state_guard.get_watchlist()
     };
 
@@ -87,12 +150,31 @@ async fn main() {
     // Start background order monitoring task
     monitor_order(state.clone(), running_clone);
 
+    // Start the status server if one is configured
+    #[cfg(feature = "http")]
+    let _http_server = match naviin::http::HttpConfig::from_env() {
+        Ok(Some(config)) => match naviin::http::start(config, state.clone()).await {
+            Ok(handle) => Some(handle),
+            Err(e) => {
+                eprintln!("Failed to start HTTP server: {}", e);
+                return;
+            }
+        },
+        Ok(None) => None,
+        Err(e) => {
+            eprintln!("{}", e);
+            return;
+        }
+    };
+
     // SECTION: TUI Launch
 
-    // Setup terminal
+    // Setup terminal, restoring it on panic from here on
+    install_panic_hook();
     let mut terminal = match setup_terminal() {
         Ok(term) => term,
         Err(e) => {
+            let _ = restore_terminal();
             eprintln!("Failed to setup terminal: {}", e);
             return;
         }
@@ -105,6 +187,11 @@ async fn main() {
         db.clone(),
         running.clone(),
     );
+    tui.set_portfolio_name(if demo_mode {
+        "demo".to_string()
+    } else {
+        Storage::portfolio_name(&database_url)
+    });
 
     // Run the TUI event loop
     let tui_result = tui.run(&mut terminal).await;
@@ -121,14 +208,13 @@ async fn main() {
         eprintln!("Failed to restore terminal: {}", e);
     }
 
-    // Stop background monitoring
-    running.store(false, std::sync::atomic::Ordering::Relaxed);
-
-    // Save final state to database
-    Storage::save_state(&state, &db).await;
+    // Stop background monitoring and save whatever changed since the last save
+    Storage::shutdown(&state, &db, &running).await;
 
     // Close database connection
-    db.close().await.expect("Failed to close database");
+    if let Err(e) = db.close().await {
+        eprintln!("Failed to close database: {}", NaviinError::from(e));
+    }
 
     println!("Naviin closed successfully");
 }
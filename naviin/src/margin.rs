@@ -0,0 +1,54 @@
+/// Margin Module
+///
+/// Optional leverage on the account. With a leverage factor above 1, buying
+/// power is cash times the factor and a purchase past the cash on hand borrows
+/// the difference as a margin loan. Incoming cash (sale proceeds, deposits)
+/// repays the loan before it lands in the balance, and the loan accrues daily
+/// interest. When equity falls below the maintenance share of the holdings'
+/// value the account is flagged; nothing is ever sold automatically.
+use crate::Finance::percent_of;
+use rust_decimal::Decimal;
+
+/// Leverage without margin: buying power is the cash balance
+pub const NO_LEVERAGE: Decimal = Decimal::ONE;
+/// Highest leverage factor `leverage` accepts
+pub const MAX_LEVERAGE: Decimal = Decimal::from_parts(4, 0, 0, false, 0);
+/// Yearly interest charged on the margin loan, in percent
+pub const DEFAULT_MARGIN_RATE: Decimal = Decimal::from_parts(8, 0, 0, false, 0);
+/// Equity below this percent of the holdings' market value is a margin call
+pub const MAINTENANCE_MARGIN_PCT: Decimal = Decimal::from_parts(25, 0, 0, false, 0);
+
+/// Most that can be spent on a purchase: cash times leverage, nothing without cash
+pub fn buying_power(cash: Decimal, leverage: Decimal) -> Decimal {
+    if cash <= Decimal::ZERO {
+        return Decimal::ZERO;
+    }
+    cash * leverage.max(NO_LEVERAGE)
+}
+
+/// Where an account stands against the maintenance requirement
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MarginStatus {
+    /// Cash plus holdings at market, less the loan
+    pub equity: Decimal,
+    /// Equity as a percent of the holdings' market value
+    pub equity_pct: Decimal,
+}
+
+impl MarginStatus {
+    /// None when nothing is borrowed or nothing is held, so there is nothing to maintain
+    pub fn new(cash: Decimal, holdings_value: Decimal, loan: Decimal) -> Option<Self> {
+        if loan <= Decimal::ZERO {
+            return None;
+        }
+        let equity = cash + holdings_value - loan;
+        Some(Self {
+            equity,
+            equity_pct: percent_of(equity, holdings_value)?,
+        })
+    }
+
+    pub fn is_margin_call(&self) -> bool {
+        self.equity_pct < MAINTENANCE_MARGIN_PCT
+    }
+}
@@ -0,0 +1,220 @@
+/// Market Module
+///
+/// US equity market hours and holiday calendar.
+/// Regular sessions run 9:30-16:00 New York time on weekdays, except NYSE holidays
+/// and any custom closures the user adds. Crypto trades around the clock.
+use std::fmt;
+
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime, Utc, Weekday};
+use chrono_tz::America::New_York;
+
+use crate::Finance::AssetType;
+
+/// Regular session open, New York time
+const SESSION_OPEN: (u32, u32) = (9, 30);
+/// Regular session close, New York time
+const SESSION_CLOSE: (u32, u32) = (16, 0);
+
+/// Why the market isn't trading right now
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ClosedReason {
+    Weekend,
+    Holiday(String),
+    PreMarket,
+    AfterHours,
+}
+
+/// Whether a market is currently in its regular session
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MarketStatus {
+    Open,
+    Closed(ClosedReason),
+    /// Crypto never closes
+    AlwaysOpen,
+}
+
+impl MarketStatus {
+    pub fn is_open(&self) -> bool {
+        !matches!(self, MarketStatus::Closed(_))
+    }
+}
+
+impl fmt::Display for MarketStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MarketStatus::Open => write!(f, "OPEN"),
+            MarketStatus::AlwaysOpen => write!(f, "24/7"),
+            MarketStatus::Closed(reason) => {
+                write!(f, "MARKET CLOSED")?;
+                match reason {
+                    ClosedReason::Weekend => write!(f, " (weekend)"),
+                    ClosedReason::Holiday(name) => write!(f, " ({})", name),
+                    ClosedReason::PreMarket => write!(f, " (pre-market)"),
+                    ClosedReason::AfterHours => write!(f, " (after hours)"),
+                }
+            }
+        }
+    }
+}
+
+/// NYSE holiday calendar plus user-configured closures
+#[derive(Clone, Debug, Default)]
+pub struct MarketCalendar {
+    custom_holidays: Vec<NaiveDate>,
+}
+
+impl MarketCalendar {
+    pub fn new(custom_holidays: Vec<NaiveDate>) -> Self {
+        Self { custom_holidays }
+    }
+
+    /// Name of the holiday on `date`, if the market is closed that day
+    pub fn holiday_name(&self, date: NaiveDate) -> Option<String> {
+        if let Some((_, name)) = us_holidays(date.year())
+            .into_iter()
+            .find(|(holiday, _)| *holiday == date)
+        {
+            return Some(name.to_string());
+        }
+        if self.custom_holidays.contains(&date) {
+            return Some("Custom holiday".to_string());
+        }
+        None
+    }
+
+    /// US equity market status at the given instant
+    pub fn status_at(&self, now: DateTime<Utc>) -> MarketStatus {
+        let local = new_york_time(now);
+        let date = local.date();
+
+        if matches!(date.weekday(), Weekday::Sat | Weekday::Sun) {
+            return MarketStatus::Closed(ClosedReason::Weekend);
+        }
+        if let Some(name) = self.holiday_name(date) {
+            return MarketStatus::Closed(ClosedReason::Holiday(name));
+        }
+
+        let time = local.time();
+        if time < session_time(SESSION_OPEN) {
+            MarketStatus::Closed(ClosedReason::PreMarket)
+        } else if time >= session_time(SESSION_CLOSE) {
+            MarketStatus::Closed(ClosedReason::AfterHours)
+        } else {
+            MarketStatus::Open
+        }
+    }
+
+    /// Market status for a symbol: crypto is always open
+    pub fn status_for(&self, asset_type: AssetType, now: DateTime<Utc>) -> MarketStatus {
+        if asset_type.trades_24_7() {
+            MarketStatus::AlwaysOpen
+        } else {
+            self.status_at(now)
+        }
+    }
+
+    /// Next `count` market holidays on or after `from`, earliest first
+    pub fn upcoming_holidays(&self, from: NaiveDate, count: usize) -> Vec<(NaiveDate, String)> {
+        let mut holidays: Vec<(NaiveDate, String)> = (from.year()..=from.year() + 1)
+            .flat_map(us_holidays)
+            .map(|(date, name)| (date, name.to_string()))
+            .chain(
+                self.custom_holidays
+                    .iter()
+                    .map(|date| (*date, "Custom holiday".to_string())),
+            )
+            .filter(|(date, _)| *date >= from)
+            .collect();
+        holidays.sort();
+        holidays.dedup_by_key(|(date, _)| *date);
+        holidays.truncate(count);
+        holidays
+    }
+}
+
+/// Formats the regular session close for status messages, e.g. "16:00 ET"
+pub fn session_close_label() -> String {
+    format!("{:02}:{:02} ET", SESSION_CLOSE.0, SESSION_CLOSE.1)
+}
+
+fn session_time((hour, minute): (u32, u32)) -> NaiveTime {
+    NaiveTime::from_hms_opt(hour, minute, 0).unwrap()
+}
+
+// SECTION: Time Zone
+
+/// Converts a UTC instant to New York local time, daylight saving included
+pub fn new_york_time(now: DateTime<Utc>) -> NaiveDateTime {
+    now.with_timezone(&New_York).naive_local()
+}
+
+// SECTION: Holiday Rules
+
+/// NYSE full-day closures for a year, with weekend observance rules applied
+pub fn us_holidays(year: i32) -> Vec<(NaiveDate, &'static str)> {
+    let fixed = |month, day| NaiveDate::from_ymd_opt(year, month, day).unwrap();
+    let mut holidays = Vec::new();
+
+    // New Year's Day on a Saturday is not made up on the prior Friday
+    let new_year = fixed(1, 1);
+    if new_year.weekday() != Weekday::Sat {
+        holidays.push((observed(new_year), "New Year's Day"));
+    }
+    holidays.push((
+        nth_weekday(year, 1, Weekday::Mon, 3),
+        "Martin Luther King Jr. Day",
+    ));
+    holidays.push((
+        nth_weekday(year, 2, Weekday::Mon, 3),
+        "Washington's Birthday",
+    ));
+    holidays.push((easter_sunday(year) - Duration::days(2), "Good Friday"));
+    holidays.push((last_weekday(year, 5, Weekday::Mon), "Memorial Day"));
+    if year >= 2022 {
+        holidays.push((observed(fixed(6, 19)), "Juneteenth"));
+    }
+    holidays.push((observed(fixed(7, 4)), "Independence Day"));
+    holidays.push((nth_weekday(year, 9, Weekday::Mon, 1), "Labor Day"));
+    holidays.push((nth_weekday(year, 11, Weekday::Thu, 4), "Thanksgiving Day"));
+    holidays.push((observed(fixed(12, 25)), "Christmas Day"));
+
+    holidays
+}
+
+// Saturday holidays close the Friday before, Sunday holidays the Monday after
+fn observed(date: NaiveDate) -> NaiveDate {
+    match date.weekday() {
+        Weekday::Sat => date - Duration::days(1),
+        Weekday::Sun => date + Duration::days(1),
+        _ => date,
+    }
+}
+
+// The nth (1-based) given weekday of a month
+fn nth_weekday(year: i32, month: u32, weekday: Weekday, n: u8) -> NaiveDate {
+    NaiveDate::from_weekday_of_month_opt(year, month, weekday, n).unwrap()
+}
+
+fn last_weekday(year: i32, month: u32, weekday: Weekday) -> NaiveDate {
+    NaiveDate::from_weekday_of_month_opt(year, month, weekday, 5)
+        .unwrap_or_else(|| nth_weekday(year, month, weekday, 4))
+}
+
+// Anonymous Gregorian algorithm
+fn easter_sunday(year: i32) -> NaiveDate {
+    let a = year % 19;
+    let b = year / 100;
+    let c = year % 100;
+    let d = b / 4;
+    let e = b % 4;
+    let f = (b + 8) / 25;
+    let g = (b - f + 1) / 3;
+    let h = (19 * a + b - d - g + 15) % 30;
+    let i = c / 4;
+    let k = c % 4;
+    let l = (32 + 2 * e + 2 * i - h - k) % 7;
+    let m = (a + 11 * h + 22 * l) / 451;
+    let month = (h + l - 7 * m + 114) / 31;
+    let day = (h + l - 7 * m + 114) % 31 + 1;
+    NaiveDate::from_ymd_opt(year, month as u32, day as u32).unwrap()
+}
@@ -0,0 +1,207 @@
+/// Performance Module
+///
+/// Return metrics over a window of daily equity snapshots and the cash flow
+/// ledger. Simple return divides the gain by the capital put to work;
+/// time-weighted return chains the growth between snapshots with each
+/// deposit or withdrawal backed out, so flow timing doesn't count for or
+/// against it; money-weighted return is the rate (IRR) that grows the
+/// starting value and every flow into the ending value.
+use rust_decimal::prelude::*;
+
+use crate::cashflow::{CashFlow, CashFlowKind};
+
+const SECONDS_PER_DAY: i64 = 86_400;
+const DAYS_PER_YEAR: f64 = 365.0;
+
+/// Bounds of the daily growth factor searched for the money-weighted return
+/// Solving per day rather than per year keeps short windows inside f64 range
+const MIN_DAILY_GROWTH: f64 = 1e-6;
+const MAX_DAILY_GROWTH: f64 = 10.0;
+const IRR_ITERATIONS: usize = 200;
+
+/// Account value (cash plus holdings at market) at one instant
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct EquitySnapshot {
+    pub timestamp: i64,
+    pub value: Decimal,
+}
+
+impl EquitySnapshot {
+    pub fn new(timestamp: i64, value: Decimal) -> Self {
+        Self { timestamp, value }
+    }
+
+    /// UTC day number, used to keep one snapshot per day
+    pub fn day(&self) -> i64 {
+        self.timestamp.div_euclid(SECONDS_PER_DAY)
+    }
+}
+
+/// Returns over one window; every rate is a fraction (0.05 = 5%)
+/// A rate is None when it can't be computed for the window, e.g. no starting capital
+#[derive(Clone, Debug, PartialEq)]
+pub struct PerformanceReport {
+    pub start: EquitySnapshot,
+    pub end: EquitySnapshot,
+    pub days: Decimal,
+    /// Deposits minus withdrawals inside the window
+    pub net_flows: Decimal,
+    pub simple_return: Option<Decimal>,
+    pub time_weighted: Option<Decimal>,
+    /// Daily rate that balances the flows (IRR per day)
+    pub money_weighted_daily: Option<Decimal>,
+}
+
+impl PerformanceReport {
+    /// Compounds a window return to a yearly rate
+    pub fn annualize(&self, rate: Decimal) -> Option<Decimal> {
+        annualize(rate, self.days)
+    }
+
+    /// Money-weighted return compounded over the window
+    pub fn money_weighted_period(&self) -> Option<Decimal> {
+        compound_daily(self.money_weighted_daily?, self.days.to_f64()?)
+    }
+
+    /// Money-weighted return compounded over a year
+    pub fn money_weighted_annual(&self) -> Option<Decimal> {
+        compound_daily(self.money_weighted_daily?, DAYS_PER_YEAR)
+    }
+}
+
+/// Computes returns from `snapshots` between `since` (or the first snapshot) and the last one
+/// Starts at the latest snapshot taken at or before `since` so the whole window is covered
+/// None when fewer than two snapshots on different days fall in the window
+pub fn performance(
+    snapshots: &[EquitySnapshot],
+    flows: &[CashFlow],
+    since: Option<i64>,
+) -> Option<PerformanceReport> {
+    let mut ordered = snapshots.to_vec();
+    ordered.sort_by_key(|snapshot| snapshot.timestamp);
+    let first = match since {
+        Some(since) => ordered
+            .iter()
+            .rposition(|snapshot| snapshot.timestamp <= since)
+            .unwrap_or(0),
+        None => 0,
+    };
+    let window = &ordered[first..];
+    let (start, end) = (*window.first()?, *window.last()?);
+    if end.day() <= start.day() {
+        return None;
+    }
+
+    let flows: Vec<&CashFlow> = flows
+        .iter()
+        .filter(|flow| flow.timestamp > start.timestamp && flow.timestamp <= end.timestamp)
+        .collect();
+    let net_flows: Decimal = flows.iter().map(|flow| flow.signed_amount()).sum();
+    let deposits: Decimal = flows
+        .iter()
+        .filter(|flow| flow.kind == CashFlowKind::Deposit)
+        .map(|flow| flow.amount)
+        .sum();
+
+    let capital = start.value + deposits;
+    let simple_return =
+        (capital > Decimal::ZERO).then(|| (end.value - start.value - net_flows) / capital);
+
+    Some(PerformanceReport {
+        start,
+        end,
+        days: Decimal::from(end.timestamp - start.timestamp) / Decimal::from(SECONDS_PER_DAY),
+        net_flows,
+        simple_return,
+        time_weighted: time_weighted_return(window, &flows),
+        money_weighted_daily: money_weighted_return(start, end, &flows),
+    })
+}
+
+/// Chains the return of each period between consecutive snapshots
+/// A flow is assumed to land right after the snapshot before it, so it is added to
+/// that period's starting value. Periods that start with nothing invested have no
+/// return and are skipped
+fn time_weighted_return(window: &[EquitySnapshot], flows: &[&CashFlow]) -> Option<Decimal> {
+    let mut growth = Decimal::ONE;
+    let mut measured = false;
+    for pair in window.windows(2) {
+        let (before, after) = (pair[0], pair[1]);
+        let period_flows: Decimal = flows
+            .iter()
+            .filter(|flow| flow.timestamp > before.timestamp && flow.timestamp <= after.timestamp)
+            .map(|flow| flow.signed_amount())
+            .sum();
+        let invested = before.value + period_flows;
+        if invested <= Decimal::ZERO {
+            continue;
+        }
+        growth = growth.checked_mul(after.value / invested)?;
+        measured = true;
+    }
+    measured.then(|| growth - Decimal::ONE)
+}
+
+/// Daily rate r where the starting value and each flow, compounded at r to the end,
+/// add up to the ending value; found by bisection
+/// None when nothing was invested or no rate in range balances the flows
+fn money_weighted_return(
+    start: EquitySnapshot,
+    end: EquitySnapshot,
+    flows: &[&CashFlow],
+) -> Option<Decimal> {
+    let days_to_end = |timestamp: i64| (end.timestamp - timestamp) as f64 / SECONDS_PER_DAY as f64;
+    let mut contributions = vec![(start.value.to_f64()?, days_to_end(start.timestamp))];
+    for flow in flows {
+        contributions.push((flow.signed_amount().to_f64()?, days_to_end(flow.timestamp)));
+    }
+    if contributions.iter().all(|(amount, _)| *amount <= 0.0) {
+        return None;
+    }
+    let end_value = end.value.to_f64()?;
+    // Future value of the contributions at a daily growth factor, less what the account is worth
+    let gap = |growth: f64| -> f64 {
+        contributions
+            .iter()
+            .map(|(amount, days)| amount * growth.powf(*days))
+            .sum::<f64>()
+            - end_value
+    };
+
+    let (mut low, mut high) = (MIN_DAILY_GROWTH, MAX_DAILY_GROWTH);
+    let (gap_low, gap_high) = (gap(low), gap(high));
+    // Long windows overflow to infinity at the top bound, which still has a sign
+    if gap_low.is_nan() || gap_high.is_nan() || gap_low.signum() == gap_high.signum() {
+        return None;
+    }
+    for _ in 0..IRR_ITERATIONS {
+        let mid = (low + high) / 2.0;
+        if gap(mid).signum() == gap_low.signum() {
+            low = mid;
+        } else {
+            high = mid;
+        }
+    }
+    Decimal::from_f64((low + high) / 2.0 - 1.0)
+}
+
+// (1 + daily)^days - 1, None if it overflows
+fn compound_daily(daily: Decimal, days: f64) -> Option<Decimal> {
+    let compounded = (1.0 + daily.to_f64()?).powf(days) - 1.0;
+    if !compounded.is_finite() {
+        return None;
+    }
+    Decimal::from_f64(compounded)
+}
+
+/// (1 + rate)^(365 / days) - 1; None for an empty window or a rate at or below -100%
+pub fn annualize(rate: Decimal, days: Decimal) -> Option<Decimal> {
+    if days <= Decimal::ZERO || rate <= -Decimal::ONE {
+        return None;
+    }
+    let annual = (1.0 + rate.to_f64()?).powf(DAYS_PER_YEAR / days.to_f64()?) - 1.0;
+    if !annual.is_finite() {
+        return None;
+    }
+    Decimal::from_f64(annual)
+}
@@ -0,0 +1,85 @@
+/// Rebalance Module
+///
+/// Plans the trades that move each holding toward its target share of the
+/// portfolio, set with `target`. `rebalance` prints the plan and, with
+/// `--execute`, places it through the regular market buy and sell paths.
+use std::collections::HashMap;
+
+use rust_decimal::Decimal;
+
+use crate::Finance::{Symbol, percent_of, quantity_for_notional};
+use crate::Orders::Side;
+
+/// Trades smaller than this are left out of a rebalance plan
+pub const MIN_REBALANCE_VALUE: Decimal = Decimal::from_parts(1, 0, 0, false, 0);
+
+/// One suggested trade that moves a holding toward its target weight
+#[derive(Clone, Debug, PartialEq)]
+pub struct RebalanceOrder {
+    pub symbol: Symbol,
+    pub side: Side,
+    pub quantity: Decimal,
+    pub price: Decimal,
+    /// Weight before the trade, as a percentage of portfolio value
+    pub current_pct: Decimal,
+    pub target_pct: Decimal,
+}
+
+/// Trades that bring each targeted symbol to its target share of portfolio value
+/// Portfolio value is cash plus every priced holding; untargeted holdings are left alone
+/// Symbols without a price are skipped. Sells come first so their proceeds fund the buys
+pub fn plan_rebalance(
+    quantities: &HashMap<Symbol, Decimal>,
+    prices: &HashMap<Symbol, Decimal>,
+    cash: Decimal,
+    targets: &[(Symbol, Decimal)],
+) -> Vec<RebalanceOrder> {
+    let holdings_value: Decimal = quantities
+        .iter()
+        .filter_map(|(symbol, qty)| prices.get(symbol).map(|price| *qty * *price))
+        .sum();
+    let total = cash + holdings_value;
+    if total <= Decimal::ZERO {
+        return Vec::new();
+    }
+
+    let mut orders: Vec<RebalanceOrder> = targets
+        .iter()
+        .filter_map(|(symbol, target_pct)| {
+            let price = prices.get(symbol).copied().filter(|p| *p > Decimal::ZERO)?;
+            let held = quantities.get(symbol).copied().unwrap_or_default();
+            let current_value = held * price;
+            let diff = total * *target_pct / Decimal::from(100) - current_value;
+            if diff.abs() < MIN_REBALANCE_VALUE {
+                return None;
+            }
+            let side = if diff > Decimal::ZERO {
+                Side::Buy
+            } else {
+                Side::Sell
+            };
+            let mut quantity = quantity_for_notional(diff.abs(), price);
+            if side == Side::Sell {
+                // Rounding must never sell more than is held
+                quantity = quantity.min(held);
+            }
+            if quantity <= Decimal::ZERO {
+                return None;
+            }
+            Some(RebalanceOrder {
+                symbol: symbol.clone(),
+                side,
+                quantity,
+                price,
+                current_pct: percent_of(current_value, total).unwrap_or_default(),
+                target_pct: *target_pct,
+            })
+        })
+        .collect();
+
+    orders.sort_by(|a, b| {
+        let rank = |order: &RebalanceOrder| matches!(order.side, Side::Buy);
+        rank(a).cmp(&rank(b)).then_with(|| a.symbol.cmp(&b.symbol))
+    });
+    orders
+}
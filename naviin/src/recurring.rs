@@ -0,0 +1,116 @@
+/// Recurring Module
+///
+/// Scheduled dollar-cost-averaging buys set up with `recurring`. Each schedule
+/// spends a fixed amount on one symbol every day, week or month. Due times are
+/// Unix seconds read from the state's clock, so the background monitor and
+/// tests agree on when a buy is owed. A schedule that fell several periods
+/// behind (the app was closed) buys once and skips ahead to its next future
+/// due time rather than buying for every missed period.
+use chrono::{DateTime, Datelike, Duration, Months, Utc};
+use rust_decimal::Decimal;
+
+use crate::Finance::Symbol;
+
+/// How often a recurring buy repeats
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+}
+
+impl Frequency {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.to_lowercase().as_str() {
+            "daily" => Some(Self::Daily),
+            "weekly" => Some(Self::Weekly),
+            "monthly" => Some(Self::Monthly),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Daily => "daily",
+            Self::Weekly => "weekly",
+            Self::Monthly => "monthly",
+        }
+    }
+
+    /// One period after `timestamp`; months land on `anchor_day`, or the last day of
+    /// a month too short for it, so a schedule set on the 31st is back on the 31st
+    /// after February
+    pub fn next_after(&self, timestamp: i64, anchor_day: u32) -> i64 {
+        let Some(at) = DateTime::from_timestamp(timestamp, 0) else {
+            return timestamp;
+        };
+        let next = match self {
+            Self::Daily => at.checked_add_signed(Duration::days(1)),
+            Self::Weekly => at.checked_add_signed(Duration::weeks(1)),
+            Self::Monthly => at
+                .with_day(1)
+                .and_then(|first| first.checked_add_months(Months::new(1)))
+                .and_then(|month| on_anchor_day(month, anchor_day)),
+        };
+        next.map(|next| next.timestamp()).unwrap_or(timestamp)
+    }
+}
+
+// `at` moved to `anchor_day` of its month, clamped to the month's last day
+fn on_anchor_day(at: DateTime<Utc>, anchor_day: u32) -> Option<DateTime<Utc>> {
+    (1..=anchor_day.clamp(1, 31))
+        .rev()
+        .find_map(|day| at.with_day(day))
+}
+
+/// Day of month of `timestamp`, the anchor for a monthly schedule starting then
+pub fn day_of_month(timestamp: i64) -> u32 {
+    DateTime::from_timestamp(timestamp, 0)
+        .map(|at| at.day())
+        .unwrap_or(1)
+}
+
+/// A fixed amount bought on a schedule
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RecurringBuy {
+    pub symbol: Symbol,
+    /// Cash spent per buy
+    pub amount: Decimal,
+    pub frequency: Frequency,
+    /// When the next buy is owed, in Unix seconds
+    pub next_due: i64,
+    /// Day of month monthly buys are due on, kept so a short month doesn't pull
+    /// every later due date back
+    pub anchor_day: u32,
+}
+
+impl RecurringBuy {
+    /// Schedule whose first buy is one period after `now`
+    pub fn starting_at(symbol: Symbol, amount: Decimal, frequency: Frequency, now: i64) -> Self {
+        let anchor_day = day_of_month(now);
+        Self {
+            symbol,
+            amount,
+            frequency,
+            next_due: frequency.next_after(now, anchor_day),
+            anchor_day,
+        }
+    }
+
+    pub fn is_due(&self, now: i64) -> bool {
+        self.next_due <= now
+    }
+
+    /// Moves the due time to the first one after `now`
+    pub fn advance(&mut self, now: i64) {
+        while self.next_due <= now {
+            let next = self.frequency.next_after(self.next_due, self.anchor_day);
+            if next <= self.next_due {
+                // Past the end of chrono's range; stop rather than spin
+                self.next_due = i64::MAX;
+                return;
+            }
+            self.next_due = next;
+        }
+    }
+}
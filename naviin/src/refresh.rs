@@ -0,0 +1,170 @@
+/// Refresh Module
+///
+/// Background price fetches for the TUI panels. Every refresh reports which
+/// symbols came back without a price, so the status bar can say how healthy the
+/// feed is and the next tick can retry just those symbols instead of fetching
+/// everything again.
+use std::collections::{BTreeSet, HashMap};
+use std::sync::Arc;
+
+use rust_decimal::Decimal;
+
+use crate::Finance::Symbol;
+use crate::FinanceProvider::PriceProvider;
+
+/// Prices from one refresh; a zero price means the lookup failed
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PriceBatch {
+    pub holdings: HashMap<Symbol, Decimal>,
+    pub previous_closes: HashMap<Symbol, Decimal>,
+    pub watchlist: HashMap<Symbol, Decimal>,
+    /// Price and previous close for the watched symbol, if any
+    pub watched: Option<(Symbol, Decimal, Decimal)>,
+    /// Rate to the base currency of each foreign cash bucket that has a quote
+    pub fx_rates: HashMap<String, Decimal>,
+}
+
+impl PriceBatch {
+    /// Every symbol looked up, once each
+    pub fn symbols(&self) -> BTreeSet<Symbol> {
+        let mut symbols: BTreeSet<Symbol> = self
+            .holdings
+            .keys()
+            .chain(self.watchlist.keys())
+            .cloned()
+            .collect();
+        if let Some((symbol, _, _)) = &self.watched {
+            symbols.insert(symbol.clone());
+        }
+        symbols
+    }
+
+    /// Symbols that came back without a current price, alphabetically
+    pub fn failed(&self) -> Vec<Symbol> {
+        let mut failed: BTreeSet<Symbol> = self
+            .holdings
+            .iter()
+            .chain(self.watchlist.iter())
+            .filter(|(_, price)| **price <= Decimal::ZERO)
+            .map(|(symbol, _)| symbol.clone())
+            .collect();
+        if let Some((symbol, price, _)) = &self.watched
+            && *price <= Decimal::ZERO
+        {
+            failed.insert(symbol.clone());
+        }
+        failed.into_iter().collect()
+    }
+
+    pub fn health(&self) -> RefreshHealth {
+        let failed = self.failed();
+        let total = self.symbols().len();
+        RefreshHealth {
+            priced: total - failed.len(),
+            total,
+            failed,
+        }
+    }
+}
+
+/// How many of the refreshed symbols got a price
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RefreshHealth {
+    pub priced: usize,
+    pub total: usize,
+    /// Symbols still without a price, alphabetically
+    pub failed: Vec<Symbol>,
+}
+
+impl RefreshHealth {
+    pub fn is_degraded(&self) -> bool {
+        !self.failed.is_empty()
+    }
+
+    /// e.g. `12/15 symbols priced`
+    pub fn summary(&self) -> String {
+        format!("{}/{} symbols priced", self.priced, self.total)
+    }
+
+    /// Health once `retry`, a fetch of just the failed symbols, has come back
+    pub fn after_retry(&self, retry: &PriceBatch) -> Self {
+        let still_failed = retry.failed();
+        let failed: Vec<Symbol> = self
+            .failed
+            .iter()
+            .filter(|symbol| still_failed.contains(symbol))
+            .cloned()
+            .collect();
+        Self {
+            priced: self.total - failed.len(),
+            total: self.total,
+            failed,
+        }
+    }
+}
+
+/// Price and previous close for each symbol, fetched concurrently and returned
+/// in the order asked; a failed lookup gives zero
+pub async fn fetch_quotes(
+    provider: Arc<dyn PriceProvider>,
+    symbols: Vec<Symbol>,
+) -> Vec<(Symbol, Decimal, Decimal)> {
+    let fetches: Vec<_> = symbols
+        .into_iter()
+        .map(|symbol| {
+            let provider = provider.clone();
+            tokio::spawn(async move {
+                let (price, prev_close) = tokio::join!(
+                    provider.curr_price(&symbol),
+                    provider.previous_close(&symbol),
+                );
+                (symbol, price, prev_close)
+            })
+        })
+        .collect();
+    let mut quotes = Vec::new();
+    for fetch in fetches {
+        if let Ok(quote) = fetch.await {
+            quotes.push(quote);
+        }
+    }
+    quotes
+}
+
+/// Fetches prices for the holdings, the watchlist and the watched symbol
+/// Holdings get their previous close too, for the day change column
+pub async fn fetch_prices(
+    provider: Arc<dyn PriceProvider>,
+    holding_symbols: Vec<Symbol>,
+    watchlist_symbols: Vec<Symbol>,
+    watched_symbol: Option<Symbol>,
+) -> PriceBatch {
+    let mut holdings: HashMap<Symbol, Decimal> = HashMap::new();
+    let mut previous_closes: HashMap<Symbol, Decimal> = HashMap::new();
+    for (symbol, price, prev_close) in fetch_quotes(provider.clone(), holding_symbols).await {
+        holdings.insert(symbol.clone(), price);
+        previous_closes.insert(symbol, prev_close);
+    }
+    let mut watchlist: HashMap<Symbol, Decimal> = HashMap::new();
+    for symbol in watchlist_symbols {
+        let price = provider.curr_price(&symbol).await;
+        watchlist.insert(symbol, price);
+    }
+    let watched = match watched_symbol {
+        Some(symbol) => {
+            let (price, prev_close) = tokio::join!(
+                provider.curr_price(&symbol),
+                provider.previous_close(&symbol),
+            );
+            Some((symbol, price, prev_close))
+        }
+        None => None,
+    };
+    PriceBatch {
+        holdings,
+        previous_closes,
+        watchlist,
+        watched,
+        fx_rates: HashMap::new(),
+    }
+}
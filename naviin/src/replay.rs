@@ -0,0 +1,253 @@
+/// Replay Module
+///
+/// Rebuilds cash and holdings from nothing by applying the cash flow ledger and
+/// the trade log in timestamp order, then lists where the stored account
+/// disagrees. Used by `replay` to check data integrity; with `--apply` the
+/// rebuilt cash and holdings replace the stored ones.
+use std::collections::{BTreeMap, HashMap};
+
+use rust_decimal::Decimal;
+
+use crate::AppState::AppState;
+use crate::Finance::{
+    Holding, IMPORT_ORDER_TYPE, INTEREST_ORDER_TYPE, QTY_DUST, SPLIT_ORDER_TYPE, Symbol,
+};
+use crate::Orders::{Side, Trade};
+use crate::cashflow::CashFlow;
+use crate::format::{MoneyFormat, format_qty, round_money};
+
+/// Cash differences up to half a cent are rounding, not drift
+const CASH_TOLERANCE: Decimal = Decimal::from_parts(5, 0, 0, false, 3);
+/// Average costs are compared at this many decimal places
+const AVG_COST_DP: u32 = 4;
+
+/// Account as the ledger and trade log say it should be
+#[derive(Clone, Debug, Default)]
+pub struct Replay {
+    /// Base currency cash net of any margin loan; negative means borrowed
+    pub cash: Decimal,
+    pub holdings: BTreeMap<Symbol, Holding>,
+    /// Sells of more shares than the log shows held at that point
+    pub oversold: Vec<Symbol>,
+}
+
+/// One way the stored account differs from the replay
+#[derive(Clone, Debug, PartialEq)]
+pub enum Discrepancy {
+    Cash {
+        stored: Decimal,
+        replayed: Decimal,
+    },
+    Quantity {
+        symbol: Symbol,
+        stored: Decimal,
+        replayed: Decimal,
+    },
+    AvgCost {
+        symbol: Symbol,
+        stored: Decimal,
+        replayed: Decimal,
+    },
+}
+
+impl Discrepancy {
+    pub fn describe(&self, money: &MoneyFormat) -> String {
+        match self {
+            Discrepancy::Cash { stored, replayed } => format!(
+                "Cash drift: stored {}, replayed {} ({} off)",
+                money.money(*stored),
+                money.money(*replayed),
+                money.money(*stored - *replayed)
+            ),
+            Discrepancy::Quantity {
+                symbol,
+                stored,
+                replayed,
+            } => {
+                let asset_type = crate::Finance::AssetType::for_symbol(symbol);
+                format!(
+                    "{} quantity mismatch: stored {}, replayed {}",
+                    symbol,
+                    format_qty(*stored, asset_type),
+                    format_qty(*replayed, asset_type)
+                )
+            }
+            Discrepancy::AvgCost {
+                symbol,
+                stored,
+                replayed,
+            } => format!(
+                "{} average cost mismatch: stored {}, replayed {}",
+                symbol,
+                money.money(*stored),
+                money.money(*replayed)
+            ),
+        }
+    }
+}
+
+// Ledger flows and trades merged into one timeline
+enum Event<'a> {
+    Flow(&'a CashFlow),
+    Trade(&'a Trade),
+}
+
+/// Applies the flows and trades in timestamp order, flows first within a second
+/// Trades for which `settles_in_base` is false move no base currency cash
+pub fn replay(
+    flows: &[CashFlow],
+    trades: &[Trade],
+    settles_in_base: impl Fn(&Trade) -> bool,
+) -> Replay {
+    let mut events: Vec<(i64, Event)> = flows
+        .iter()
+        .map(|flow| (flow.timestamp, Event::Flow(flow)))
+        .chain(
+            trades
+                .iter()
+                .map(|trade| (trade.get_timestamp(), Event::Trade(trade))),
+        )
+        .collect();
+    // Stable, so equal timestamps keep ledger order then log order
+    events.sort_by_key(|(timestamp, _)| *timestamp);
+
+    let mut result = Replay::default();
+    for (_, event) in events {
+        let trade = match event {
+            Event::Flow(flow) => {
+                // Money moved in another currency lands in its own bucket
+                if flow.currency.is_none() {
+                    result.cash += flow.signed_amount();
+                }
+                continue;
+            }
+            Event::Trade(trade) => trade,
+        };
+        let symbol = trade.get_symbol();
+        let quantity = trade.get_quantity();
+        let amount = quantity * trade.get_price_per();
+        // Imported history moves shares only; its cash was never in this account
+        let moves_cash = trade.get_order_type() != IMPORT_ORDER_TYPE && settles_in_base(trade);
+        match trade.get_order_type().as_str() {
+            // Cash yield is credited as a buy, margin interest charged as a sell
+            INTEREST_ORDER_TYPE => match trade.get_side() {
+                Side::Buy => result.cash += amount,
+                Side::Sell => result.cash -= amount,
+            },
+            // A split's quantity is the share multiplier; total cost is unchanged
+            SPLIT_ORDER_TYPE => {
+                if let Some(holding) = result.holdings.get_mut(symbol) {
+                    let held = holding.get_qty() * quantity;
+                    let avg = holding.get_qty() * holding.get_avg_price() / held;
+                    *holding = Holding::new(symbol.clone(), held, avg);
+                }
+            }
+            _ => match trade.get_side() {
+                Side::Buy => {
+                    if moves_cash {
+                        result.cash -= trade.cash_value();
+                    }
+                    let (held, cost) = result
+                        .holdings
+                        .get(symbol)
+                        .map(|holding| {
+                            (
+                                holding.get_qty(),
+                                holding.get_qty() * holding.get_avg_price(),
+                            )
+                        })
+                        .unwrap_or_default();
+                    let held = held + quantity;
+                    result.holdings.insert(
+                        symbol.clone(),
+                        Holding::new(symbol.clone(), held, (cost + amount) / held),
+                    );
+                }
+                Side::Sell => {
+                    if moves_cash {
+                        result.cash += trade.cash_value();
+                    }
+                    let Some(holding) = result.holdings.get(symbol) else {
+                        result.oversold.push(symbol.clone());
+                        continue;
+                    };
+                    let remaining = holding.get_qty() - quantity;
+                    if remaining < -QTY_DUST {
+                        result.oversold.push(symbol.clone());
+                    }
+                    if remaining <= QTY_DUST {
+                        result.holdings.remove(symbol);
+                    } else {
+                        let avg = holding.get_avg_price();
+                        result
+                            .holdings
+                            .insert(symbol.clone(), Holding::new(symbol.clone(), remaining, avg));
+                    }
+                }
+            },
+        }
+    }
+    result
+}
+
+/// Replays the state's own ledger and trade log, each trade in the currency it
+/// settled in when it filled
+pub fn replay_state(state: &AppState) -> Replay {
+    replay(&state.get_cash_flows(), &state.get_trades(), |trade| {
+        state.trade_currency(trade) == crate::fx::BASE_CURRENCY
+    })
+}
+
+/// Where the stored cash and holdings differ from the replay, cash first then by symbol
+pub fn compare(state: &AppState, replay: &Replay) -> Vec<Discrepancy> {
+    let mut discrepancies = Vec::new();
+    let stored_cash = state.check_balance() - state.get_margin_loan();
+    if (stored_cash - replay.cash).abs() > CASH_TOLERANCE {
+        discrepancies.push(Discrepancy::Cash {
+            stored: round_money(stored_cash),
+            replayed: round_money(replay.cash),
+        });
+    }
+
+    let stored = state.get_holdings_map();
+    let mut symbols: Vec<&Symbol> = stored.keys().chain(replay.holdings.keys()).collect();
+    symbols.sort();
+    symbols.dedup();
+    for symbol in symbols {
+        let (stored_qty, stored_avg) = position(stored.get(symbol));
+        let (replayed_qty, replayed_avg) = position(replay.holdings.get(symbol));
+        if (stored_qty - replayed_qty).abs() > QTY_DUST {
+            discrepancies.push(Discrepancy::Quantity {
+                symbol: symbol.clone(),
+                stored: stored_qty,
+                replayed: replayed_qty,
+            });
+        } else if stored_avg.round_dp(AVG_COST_DP) != replayed_avg.round_dp(AVG_COST_DP) {
+            discrepancies.push(Discrepancy::AvgCost {
+                symbol: symbol.clone(),
+                stored: stored_avg,
+                replayed: replayed_avg,
+            });
+        }
+    }
+    discrepancies
+}
+
+fn position(holding: Option<&Holding>) -> (Decimal, Decimal) {
+    holding
+        .map(|holding| (holding.get_qty(), holding.get_avg_price()))
+        .unwrap_or_default()
+}
+
+/// Replaces the stored cash and holdings with the replay's
+/// A negative replayed cash becomes a margin loan
+pub fn apply(state: &mut AppState, replay: &Replay) {
+    state.set_cash_balance(replay.cash.max(Decimal::ZERO));
+    state.set_margin_loan((-replay.cash).max(Decimal::ZERO));
+    let holdings: HashMap<Symbol, Holding> = replay
+        .holdings
+        .iter()
+        .map(|(symbol, holding)| (symbol.clone(), holding.clone()))
+        .collect();
+    state.set_holdings_map(holdings);
+}
@@ -0,0 +1,117 @@
+/// Risk Module
+///
+/// Optional guardrails on buys, set with `risklimit`: the largest share of the
+/// portfolio any one position may reach and the largest cost of a single order.
+/// Concentration is measured after the trade, with every holding marked at its
+/// current price plus the proposed buy. A buy that would break a limit is
+/// rejected; sells are never limited, since they only reduce risk.
+use crate::Finance::{Symbol, percent_of};
+use crate::format::{MoneyFormat, format_fixed};
+use rust_decimal::Decimal;
+
+/// Limits a buy must stay within; both are off by default
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RiskLimits {
+    /// Most any one position may be of the portfolio's value, in percent
+    pub max_position_pct: Option<Decimal>,
+    /// Most a single order may cost
+    pub max_order_notional: Option<Decimal>,
+}
+
+/// The limit a proposed buy would break
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RiskBreach {
+    /// The order costs more than the order limit
+    OrderSize { notional: Decimal, limit: Decimal },
+    /// The position would be this percent of the portfolio, above the limit
+    Concentration { pct: Decimal, limit: Decimal },
+}
+
+/// Why a proposed buy didn't pass the risk check
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RiskRejection {
+    /// The buy would break a limit
+    Breach(RiskBreach),
+    /// A holding has no price, so the position limit can't be checked
+    Unpriced { symbol: Symbol, limit: Decimal },
+    /// A currency the check converts has no exchange rate
+    NoRate { currency: String },
+}
+
+impl RiskRejection {
+    /// e.g. `Buy rejected: AAPL would be 33.33% of the portfolio, above the 25% position limit`
+    pub fn describe(&self, symbol: &str, money: &MoneyFormat) -> String {
+        match self {
+            RiskRejection::Breach(_) => format!("Buy rejected: {}", self.reason(symbol, money)),
+            RiskRejection::Unpriced { symbol, limit } => format!(
+                "Can't check the {}% position limit without a price for {}",
+                limit.normalize(),
+                symbol
+            ),
+            RiskRejection::NoRate { currency } => format!(
+                "Can't check the risk limits without an exchange rate for {}",
+                currency
+            ),
+        }
+    }
+
+    /// The rejection without the lead-in, for messages that have their own
+    pub fn reason(&self, symbol: &str, money: &MoneyFormat) -> String {
+        match self {
+            RiskRejection::Breach(RiskBreach::OrderSize { notional, limit }) => format!(
+                "the {} order is above the {} order limit",
+                money.money(*notional),
+                money.money(*limit)
+            ),
+            RiskRejection::Breach(RiskBreach::Concentration { pct, limit }) => format!(
+                "{} would be {}% of the portfolio, above the {}% position limit",
+                symbol,
+                format_fixed(*pct, 2),
+                limit.normalize()
+            ),
+            RiskRejection::Unpriced { symbol, limit } => format!(
+                "can't check the {}% position limit without a price for {}",
+                limit.normalize(),
+                symbol
+            ),
+            RiskRejection::NoRate { currency } => format!(
+                "can't check the risk limits without an exchange rate for {}",
+                currency
+            ),
+        }
+    }
+}
+
+impl RiskLimits {
+    pub fn is_off(&self) -> bool {
+        self.max_position_pct.is_none() && self.max_order_notional.is_none()
+    }
+
+    /// Checks a buy costing `notional` that leaves the position worth `position_value`
+    /// in a portfolio worth `portfolio_value`, both marked after the trade
+    pub fn check_buy(
+        &self,
+        notional: Decimal,
+        position_value: Decimal,
+        portfolio_value: Decimal,
+    ) -> Result<(), RiskBreach> {
+        if let Some(limit) = self.max_order_notional
+            && notional > limit
+        {
+            return Err(RiskBreach::OrderSize { notional, limit });
+        }
+        if let Some(limit) = self.max_position_pct {
+            let pct = concentration_pct(position_value, portfolio_value);
+            if pct > limit {
+                return Err(RiskBreach::Concentration { pct, limit });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Percent of `portfolio_value` held in `position_value`
+/// An empty portfolio counts as fully concentrated
+pub fn concentration_pct(position_value: Decimal, portfolio_value: Decimal) -> Decimal {
+    percent_of(position_value, portfolio_value).unwrap_or(Decimal::ONE_HUNDRED)
+}
@@ -0,0 +1,214 @@
+/// Scenario Module
+///
+/// What-if pricing for the `whatif` command: percentage shocks are applied to
+/// a copy of the current marks and the portfolio is revalued against them.
+/// Nothing here touches the account; holdings are only read.
+use std::collections::HashMap;
+
+use rust_decimal::Decimal;
+
+use crate::Finance::{AssetType, Holding, Symbol, percent_of};
+use crate::format::{self, Align, MoneyFormat, TextTable, format_qty};
+
+/// A hypothetical move in one symbol's price, in percent
+#[derive(Clone, Debug, PartialEq)]
+pub struct Shock {
+    pub symbol: Symbol,
+    pub pct: Decimal,
+}
+
+impl Shock {
+    /// Parses `AAPL=-10` or `tsla=+5%`; a move below -100% is rejected
+    pub fn parse(arg: &str) -> Result<Self, String> {
+        let Some((symbol, raw_pct)) = arg.split_once('=') else {
+            return Err(format!(
+                "Invalid shock: '{}' (expected <symbol>=<±%>, e.g. AAPL=-10)",
+                arg
+            ));
+        };
+        let symbol = symbol.trim().to_uppercase();
+        if symbol.is_empty() {
+            return Err(format!("Invalid shock: '{}' (missing symbol)", arg));
+        }
+        let raw_pct = raw_pct.trim();
+        let Ok(pct) = raw_pct
+            .strip_suffix('%')
+            .unwrap_or(raw_pct)
+            .trim_start_matches('+')
+            .parse::<Decimal>()
+        else {
+            return Err(format!("Invalid percentage for {}: '{}'", symbol, raw_pct));
+        };
+        if pct < Decimal::from(-100) {
+            return Err(format!(
+                "{} can't fall more than 100% (got {})",
+                symbol,
+                format::format_pct(pct)
+            ));
+        }
+        Ok(Self { symbol, pct })
+    }
+
+    /// `price` moved by this shock
+    pub fn apply(&self, price: Decimal) -> Decimal {
+        price * (Decimal::ONE + self.pct / Decimal::from(100))
+    }
+}
+
+/// One priced holding, at market and under the scenario
+#[derive(Clone, Debug, PartialEq)]
+pub struct ScenarioPosition {
+    pub symbol: Symbol,
+    pub quantity: Decimal,
+    pub price: Decimal,
+    pub shocked_price: Decimal,
+    /// None when the symbol was left at market
+    pub shock_pct: Option<Decimal>,
+}
+
+impl ScenarioPosition {
+    pub fn value(&self) -> Decimal {
+        self.quantity * self.price
+    }
+
+    pub fn shocked_value(&self) -> Decimal {
+        self.quantity * self.shocked_price
+    }
+
+    pub fn change(&self) -> Decimal {
+        self.shocked_value() - self.value()
+    }
+}
+
+/// The portfolio revalued under a set of shocks
+#[derive(Clone, Debug, PartialEq)]
+pub struct Scenario {
+    /// Priced holdings, by symbol
+    pub positions: Vec<ScenarioPosition>,
+    /// Cash net of any margin loan; shocks don't move it
+    pub cash: Decimal,
+    /// Holdings without a mark, left out of both values
+    pub unpriced: Vec<Symbol>,
+}
+
+impl Scenario {
+    /// Revalues the holdings with each shock applied to its symbol's mark.
+    /// Every shocked symbol must be held
+    pub fn new(
+        holdings: &HashMap<Symbol, Holding>,
+        marks: &HashMap<Symbol, Decimal>,
+        shocks: &[Shock],
+        cash: Decimal,
+    ) -> Result<Self, String> {
+        for (i, shock) in shocks.iter().enumerate() {
+            if !holdings.contains_key(&shock.symbol) {
+                return Err(format!("No holding in {}", shock.symbol));
+            }
+            if shocks[..i].iter().any(|other| other.symbol == shock.symbol) {
+                return Err(format!("{} is shocked twice", shock.symbol));
+            }
+        }
+
+        let mut positions = Vec::new();
+        let mut unpriced = Vec::new();
+        for (symbol, holding) in holdings {
+            let Some(price) = marks.get(symbol).copied() else {
+                unpriced.push(symbol.clone());
+                continue;
+            };
+            let shock = shocks.iter().find(|shock| &shock.symbol == symbol);
+            positions.push(ScenarioPosition {
+                symbol: symbol.clone(),
+                quantity: holding.get_qty(),
+                price,
+                shocked_price: shock.map_or(price, |shock| shock.apply(price)),
+                shock_pct: shock.map(|shock| shock.pct),
+            });
+        }
+        positions.sort_by(|a, b| a.symbol.cmp(&b.symbol));
+        unpriced.sort();
+        Ok(Self {
+            positions,
+            cash,
+            unpriced,
+        })
+    }
+
+    pub fn current_value(&self) -> Decimal {
+        self.cash
+            + self
+                .positions
+                .iter()
+                .map(ScenarioPosition::value)
+                .sum::<Decimal>()
+    }
+
+    pub fn shocked_value(&self) -> Decimal {
+        self.cash
+            + self
+                .positions
+                .iter()
+                .map(ScenarioPosition::shocked_value)
+                .sum::<Decimal>()
+    }
+}
+
+/// Per-position table followed by the current and hypothetical totals
+pub fn format_scenario(scenario: &Scenario, money: &MoneyFormat) -> String {
+    let signed = |amount: Decimal| {
+        if amount > Decimal::ZERO {
+            format!("+{}", money.money(amount))
+        } else {
+            money.money(amount)
+        }
+    };
+    let mut table = TextTable::new(&[
+        Align::Left,
+        Align::Right,
+        Align::Right,
+        Align::Right,
+        Align::Right,
+        Align::Right,
+        Align::Right,
+    ])
+    .with_header(&[
+        "Symbol", "Qty", "Price", "Shock", "What-if", "Value", "Change",
+    ]);
+    for position in &scenario.positions {
+        let asset_type = AssetType::for_symbol(&position.symbol);
+        table.push_row(vec![
+            position.symbol.clone(),
+            format_qty(position.quantity, asset_type),
+            money.money_price(position.price, asset_type),
+            position
+                .shock_pct
+                .map(format::format_pct)
+                .unwrap_or_else(|| "-".to_string()),
+            money.money_price(position.shocked_price, asset_type),
+            money.money(position.shocked_value()),
+            signed(position.change()),
+        ]);
+    }
+
+    let current = scenario.current_value();
+    let shocked = scenario.shocked_value();
+    let pct = percent_of(shocked - current, current)
+        .map(format::format_pct)
+        .unwrap_or_else(|| "N/A".to_string());
+    let mut lines = vec!["What-if (nothing is traded):".to_string()];
+    lines.extend(table.lines());
+    lines.push(format!("Current value:  {}", money.money(current)));
+    lines.push(format!(
+        "Scenario value: {} ({}, {})",
+        money.money(shocked),
+        signed(shocked - current),
+        pct
+    ));
+    if !scenario.unpriced.is_empty() {
+        lines.push(format!(
+            "Left out (no current price): {}",
+            scenario.unpriced.join(", ")
+        ));
+    }
+    lines.join("\n")
+}
@@ -0,0 +1,163 @@
+/// Simulation Module
+///
+/// Runs a reproducible stream of random trading operations against the mock
+/// provider and checks that cash matches the trade log afterwards.
+/// Used by the `simulate` command and by tests to catch performance regressions.
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use rust_decimal::prelude::*;
+
+use crate::AppState::{AppState, lock_state, process_open_orders};
+use crate::Finance;
+use crate::FinanceProvider::MockProvider;
+use crate::Orders::{OpenOrder, OrderType, Side};
+
+/// Cash the simulated account starts with
+const STARTING_CASH: i64 = 100_000;
+/// Symbols traded during a simulation, all starting at $100
+const SYMBOLS: [&str; 4] = ["SIMA", "SIMB", "SIMC", "SIMD"];
+
+/// Outcome of a simulation run
+#[derive(Debug)]
+pub struct SimulationReport {
+    pub operations: usize,
+    pub buys: usize,
+    pub sells: usize,
+    pub orders_placed: usize,
+    pub orders_filled: usize,
+    pub rejected: usize,
+    pub trades: usize,
+    pub elapsed: Duration,
+    pub final_cash: Decimal,
+    /// Starting cash plus sell proceeds minus buy costs, computed from the trade log
+    pub expected_cash: Decimal,
+}
+
+impl SimulationReport {
+    /// True when the account balance agrees with the trade log
+    pub fn is_consistent(&self) -> bool {
+        self.final_cash == self.expected_cash
+    }
+}
+
+/// Small xorshift generator so runs are reproducible without extra dependencies
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        // Zero would make xorshift stick at zero forever
+        Self(seed.max(1))
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, n: u64) -> u64 {
+        self.next() % n
+    }
+}
+
+/// Runs `operations` random buys, sells, and limit/stop orders on an isolated account
+/// The user's own state is never touched
+pub async fn run_simulation(operations: usize, seed: u64) -> SimulationReport {
+    let provider = Arc::new(MockProvider::new());
+    let mut prices: HashMap<String, Decimal> = HashMap::new();
+    for symbol in SYMBOLS {
+        provider.set_price(symbol, Decimal::from(100));
+        prices.insert(symbol.to_string(), Decimal::from(100));
+    }
+    let state = Arc::new(Mutex::new(AppState::with_provider(provider.clone())));
+    lock_state(&state).deposit(Decimal::from(STARTING_CASH));
+
+    let mut rng = Rng::new(seed);
+    let mut report = SimulationReport {
+        operations,
+        buys: 0,
+        sells: 0,
+        orders_placed: 0,
+        orders_filled: 0,
+        rejected: 0,
+        trades: 0,
+        elapsed: Duration::ZERO,
+        final_cash: Decimal::ZERO,
+        expected_cash: Decimal::ZERO,
+    };
+
+    let started = Instant::now();
+    for _ in 0..operations {
+        let symbol = SYMBOLS[rng.below(SYMBOLS.len() as u64) as usize].to_string();
+
+        // Random walk of up to +/-2% per step, kept to cents and above $1
+        let step = Decimal::new(rng.below(401) as i64 - 200, 4);
+        let price = (prices[&symbol] * (Decimal::ONE + step))
+            .round_dp(2)
+            .max(Decimal::ONE);
+        prices.insert(symbol.clone(), price);
+        provider.set_price(&symbol, price);
+
+        let quantity = Decimal::from(rng.below(10) + 1);
+        match rng.below(4) {
+            0 => match Finance::create_buy_with_params(&state, symbol, quantity, price).await {
+                Ok(_) => report.buys += 1,
+                Err(_) => report.rejected += 1,
+            },
+            1 => {
+                let held = lock_state(&state).get_available_holdings_qty(&symbol);
+                let quantity = quantity.min(held);
+                if quantity <= Decimal::ZERO {
+                    report.rejected += 1;
+                } else {
+                    match Finance::create_sell_with_params(&state, symbol, quantity, price).await {
+                        Ok(_) => report.sells += 1,
+                        Err(_) => report.rejected += 1,
+                    }
+                }
+            }
+            2 => {
+                let limit = (price * Decimal::new(99, 2)).round_dp(2);
+                let mut state_guard = lock_state(&state);
+                let now = state_guard.now_timestamp();
+                let order =
+                    OpenOrder::new_at(symbol, quantity, limit, OrderType::BuyLimit, Side::Buy, now);
+                match state_guard.add_open_order(order) {
+                    Ok(_) => report.orders_placed += 1,
+                    Err(_) => report.rejected += 1,
+                }
+            }
+            _ => {
+                let stop = (price * Decimal::new(98, 2)).round_dp(2);
+                let mut state_guard = lock_state(&state);
+                let now = state_guard.now_timestamp();
+                let order =
+                    OpenOrder::new_at(symbol, quantity, stop, OrderType::StopLoss, Side::Sell, now);
+                match state_guard.add_open_order(order) {
+                    Ok(_) => report.orders_placed += 1,
+                    Err(_) => report.rejected += 1,
+                }
+            }
+        }
+
+        report.orders_filled += process_open_orders(&state).await;
+    }
+    report.elapsed = started.elapsed();
+
+    let state_guard = lock_state(&state);
+    let trades = state_guard.get_trades();
+    report.trades = trades.len();
+    report.final_cash = state_guard.check_balance();
+    report.expected_cash = trades
+        .iter()
+        .fold(Decimal::from(STARTING_CASH), |cash, trade| {
+            match trade.get_side() {
+                Side::Buy => cash - trade.cash_value(),
+                Side::Sell => cash + trade.cash_value(),
+            }
+        });
+    report
+}
@@ -1,26 +1,236 @@
 use super::entities::app_state::ActiveModel as AppStateActiveModel;
 use super::entities::app_state::Entity as AppStateEntity;
+use super::entities::cash_flow::ActiveModel as CashFlowActiveModel;
+use super::entities::cash_flow::Entity as CashFlowEntity;
+use super::entities::equity_snapshot::ActiveModel as EquitySnapshotActiveModel;
+use super::entities::equity_snapshot::Entity as EquitySnapshotEntity;
+use super::entities::executed_order::ActiveModel as ExecutedOrderActiveModel;
+use super::entities::executed_order::Entity as ExecutedOrderEntity;
 use super::entities::holding::ActiveModel as HoldingActiveModel;
 use super::entities::holding::Column as HoldingColumn;
 use super::entities::holding::Entity as HoldingEntity;
+use super::entities::note::ActiveModel as NoteActiveModel;
+use super::entities::note::Entity as NoteEntity;
 use super::entities::open_order::ActiveModel as OpenOrderActiveModel;
 use super::entities::open_order::Entity as OpenOrderEntity;
+use super::entities::order_template::ActiveModel as OrderTemplateActiveModel;
+use super::entities::order_template::Entity as OrderTemplateEntity;
+use super::entities::recurring_buy::ActiveModel as RecurringBuyActiveModel;
+use super::entities::recurring_buy::Entity as RecurringBuyEntity;
+use super::entities::setting::ActiveModel as SettingActiveModel;
+use super::entities::setting::Entity as SettingEntity;
 use super::entities::trade::ActiveModel as TradeActiveModel;
 use super::entities::trade::Entity as TradeEntity;
 use super::entities::watchlist::ActiveModel as WatchlistActiveModel;
 use super::entities::watchlist::Entity as WatchlistEntity;
-use crate::AppState::AppState;
-use crate::Finance::{Holding, Symbol};
-use crate::Orders::{OpenOrder, OrderType, Side, Trade};
+use crate::AppState::{AppState, lock_state};
+use crate::Finance::{self, Holding, Symbol};
+use crate::Orders::{ExecutedOrder, OpenOrder, OrderType, Side, Trade};
+use crate::cashflow::{CashFlow, CashFlowKind};
+use crate::demo;
+use crate::error::NaviinError;
+use crate::events::EventKind;
+use crate::performance::EquitySnapshot;
+use crate::recurring::{self, Frequency, RecurringBuy};
+use crate::templates::OrderTemplate;
+use migration::{Migrator, MigratorTrait};
 use sea_orm::{
-    ActiveModelTrait, ColumnTrait, Database, DatabaseConnection, DatabaseTransaction, DbErr,
-    EntityTrait, IntoActiveModel, NotSet, QueryFilter, Set, TransactionTrait,
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, Database, DatabaseConnection,
+    DatabaseTransaction, DbErr, EntityTrait, IntoActiveModel, NotSet, QueryFilter, Set,
+    TransactionError, TransactionTrait,
 };
-use std::{collections::HashMap, env, sync::Arc, sync::Mutex};
+use std::{
+    collections::{BTreeMap, HashMap},
+    env,
+    path::{Path, PathBuf},
+    sync::Arc,
+    sync::Mutex,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+/// Full database URL; takes precedence over everything else
+pub const DATABASE_URL_VAR: &str = "DATABASE_URL";
+/// Path of the SQLite file holding the account, used when no URL is given
+pub const STATE_PATH_VAR: &str = "NAVIIN_STATE";
+/// File name of the database inside the per-user data directory
+const STATE_FILE_NAME: &str = "naviin.sqlite";
+
+// SECTION: Storage Location
+
+/// Per-user data directory for Naviin, e.g. `~/.local/share/naviin` on Linux,
+/// `~/Library/Application Support/naviin` on macOS, `%APPDATA%\naviin` on Windows
+/// `lookup` reads environment variables so callers and tests can supply their own
+pub fn data_dir_from(lookup: impl Fn(&str) -> Option<String>) -> Option<PathBuf> {
+    let base = if cfg!(windows) {
+        PathBuf::from(lookup("APPDATA")?)
+    } else if cfg!(target_os = "macos") {
+        PathBuf::from(lookup("HOME")?).join("Library/Application Support")
+    } else {
+        match lookup("XDG_DATA_HOME").filter(|dir| !dir.is_empty()) {
+            Some(dir) => PathBuf::from(dir),
+            None => PathBuf::from(lookup("HOME")?).join(".local/share"),
+        }
+    };
+    Some(base.join("naviin"))
+}
+
+/// SQLite file for the account: `NAVIIN_STATE` if set, else the data directory's file
+pub fn state_path_from(lookup: impl Fn(&str) -> Option<String>) -> Option<PathBuf> {
+    match lookup(STATE_PATH_VAR).filter(|path| !path.is_empty()) {
+        Some(path) => Some(PathBuf::from(path)),
+        None => Some(data_dir_from(lookup)?.join(STATE_FILE_NAME)),
+    }
+}
+
+/// Database URL: `DATABASE_URL` if set, else a SQLite URL for `state_path_from`
+/// The SQLite file is created on first connect
+pub fn database_url_from(lookup: impl Fn(&str) -> Option<String>) -> Option<String> {
+    if let Some(url) = lookup(DATABASE_URL_VAR).filter(|url| !url.is_empty()) {
+        return Some(url);
+    }
+    let path = state_path_from(lookup)?;
+    Some(format!("sqlite://{}?mode=rwc", path.display()))
+}
+
+/// Resolves the database URL from the environment and creates the directory
+/// the SQLite file goes in, so the first run works from any working directory
+pub fn database_url() -> Result<String, NaviinError> {
+    let lookup = |key: &str| env::var(key).ok();
+    let url = database_url_from(lookup)
+        .ok_or_else(|| NaviinError::MissingConfig(STATE_PATH_VAR.to_string()))?;
+    if lookup(DATABASE_URL_VAR)
+        .filter(|url| !url.is_empty())
+        .is_none()
+        && let Some(dir) = state_path_from(lookup)
+            .as_ref()
+            .and_then(|path| path.parent())
+        && !dir.as_os_str().is_empty()
+    {
+        std::fs::create_dir_all(dir)?;
+    }
+    Ok(url)
+}
+
+// SECTION: First Run
+
+/// Cash a new account is seeded with; `0` starts empty
+pub const STARTING_BALANCE_VAR: &str = "NAVIIN_STARTING_BALANCE";
+/// Comma-separated symbols a new account's watchlist starts with
+pub const STARTING_WATCHLIST_VAR: &str = "NAVIIN_STARTING_WATCHLIST";
+/// Command-line flag overriding `NAVIIN_STARTING_BALANCE`
+pub const STARTING_BALANCE_FLAG: &str = "--starting-balance";
+/// Paper money a new account gets when nothing else is configured
+pub const DEFAULT_STARTING_BALANCE: rust_decimal::Decimal =
+    rust_decimal::Decimal::from_parts(10_000, 0, 0, false, 0);
+
+/// What a brand new account is seeded with on first run
+#[derive(Clone, Debug, PartialEq)]
+pub struct StartingAccount {
+    pub balance: rust_decimal::Decimal,
+    pub watchlist: Vec<String>,
+}
+
+impl Default for StartingAccount {
+    fn default() -> Self {
+        StartingAccount {
+            balance: DEFAULT_STARTING_BALANCE,
+            watchlist: Vec::new(),
+        }
+    }
+}
+
+impl StartingAccount {
+    /// Reads the starting account from `--starting-balance <amount>` (or `=<amount>`)
+    /// in `args`, then the environment through `lookup`, then the defaults
+    pub fn from_config(
+        args: &[String],
+        lookup: impl Fn(&str) -> Option<String>,
+    ) -> Result<Self, NaviinError> {
+        let mut starting = StartingAccount::default();
+
+        if let Some(value) = lookup(STARTING_BALANCE_VAR).filter(|value| !value.is_empty()) {
+            starting.balance =
+                parse_starting_balance(&value).ok_or(NaviinError::InvalidConfig {
+                    key: STARTING_BALANCE_VAR.to_string(),
+                    value,
+                })?;
+        }
+        let flag_value = args.iter().enumerate().find_map(|(idx, arg)| {
+            if arg == STARTING_BALANCE_FLAG {
+                Some(args.get(idx + 1).cloned().unwrap_or_default())
+            } else {
+                arg.strip_prefix(STARTING_BALANCE_FLAG)
+                    .and_then(|rest| rest.strip_prefix('='))
+                    .map(str::to_string)
+            }
+        });
+        if let Some(value) = flag_value {
+            starting.balance =
+                parse_starting_balance(&value).ok_or(NaviinError::InvalidNumber {
+                    field: "starting balance".to_string(),
+                    value,
+                })?;
+        }
+
+        if let Some(symbols) = lookup(STARTING_WATCHLIST_VAR) {
+            for symbol in symbols
+                .split(',')
+                .map(|symbol| symbol.trim().to_uppercase())
+            {
+                if symbol.is_empty() || starting.watchlist.contains(&symbol) {
+                    continue;
+                }
+                if !crate::import::is_valid_symbol(&symbol) {
+                    return Err(NaviinError::InvalidConfig {
+                        key: STARTING_WATCHLIST_VAR.to_string(),
+                        value: symbol,
+                    });
+                }
+                starting.watchlist.push(symbol);
+            }
+        }
+        Ok(starting)
+    }
+}
 
-async fn load_app_state(db: &DatabaseConnection) -> Result<Option<rust_decimal::Decimal>, DbErr> {
+fn parse_starting_balance(value: &str) -> Option<rust_decimal::Decimal> {
+    value
+        .trim()
+        .parse::<rust_decimal::Decimal>()
+        .ok()
+        .filter(|balance| *balance >= rust_decimal::Decimal::ZERO)
+}
+
+/// Seeds and saves a new account when the database has never had one saved,
+/// so later runs load it instead. Returns whether the account was seeded
+pub async fn seed_first_run(
+    state: &Arc<Mutex<AppState>>,
+    db: &DatabaseConnection,
+    starting: &StartingAccount,
+) -> Result<bool, DbErr> {
+    if lock_state(state).is_demo() || load_app_state(db).await?.is_some() {
+        return Ok(false);
+    }
+    {
+        let mut state_guard = lock_state(state);
+        if starting.balance > rust_decimal::Decimal::ZERO {
+            state_guard.deposit(starting.balance);
+            state_guard.record_cash_flow(CashFlowKind::Deposit, starting.balance);
+        }
+        for symbol in &starting.watchlist {
+            state_guard.add_to_watchlist(symbol.clone());
+        }
+    }
+    save_state(state, db).await;
+    Ok(true)
+}
+
+/// Loads the cash balance and last save time of the single app state row.
+async fn load_app_state(
+    db: &DatabaseConnection,
+) -> Result<Option<(rust_decimal::Decimal, i64)>, DbErr> {
     match AppStateEntity::find_by_id(1).one(db).await? {
-        Some(model) => Ok(Some(model.cash_balance)),
+        Some(model) => Ok(Some((model.cash_balance.0, model.updated_at))),
         None => Ok(None),
     }
 }
@@ -30,7 +240,7 @@ async fn load_holdings(db: &DatabaseConnection) -> Result<HashMap<String, Holdin
     let holdings_models = HoldingEntity::find().all(db).await?;
     let mut holdings_map: HashMap<String, Holding> = HashMap::new();
     for h in holdings_models {
-        let holding = Holding::new(h.symbol.clone(), h.quantity, h.avg_cost);
+        let holding = Holding::new(h.symbol.clone(), h.quantity.0, h.avg_cost.0);
         holdings_map.insert(h.symbol, holding);
     }
     Ok(holdings_map)
@@ -40,19 +250,28 @@ async fn load_trades(db: &DatabaseConnection) -> Result<Vec<Trade>, DbErr> {
     let trades_models = TradeEntity::find().all(db).await?;
     let trades: Vec<Trade> = trades_models
         .into_iter()
-        .map(|t| {
+        .filter_map(|t| {
             let side = match t.side.as_str() {
                 "Buy" => Side::Buy,
                 "Sell" => Side::Sell,
-                _ => panic!("Unknown trade side: {}", t.side),
+                _ => {
+                    // Skip the corrupt row rather than aborting the whole load
+                    eprintln!("Skipping trade {} with unknown side: {}", t.id, t.side);
+                    return None;
+                }
             };
-            Trade::from_database(
-                t.symbol,
-                t.quantity,
-                t.price_per,
-                side,
-                t.timestamp,
-                t.order_type,
+            Some(
+                Trade::from_database(
+                    t.symbol,
+                    t.quantity.0,
+                    t.price_per.0,
+                    side,
+                    t.timestamp,
+                    t.order_type,
+                )
+                .with_tag(t.tag)
+                .with_currency(t.currency)
+                .with_fee(t.fee.0),
             )
         })
         .collect();
@@ -63,39 +282,63 @@ async fn load_open_orders(db: &DatabaseConnection) -> Result<Vec<OpenOrder>, DbE
     let open_orders_models = OpenOrderEntity::find().all(db).await?;
     let open_orders: Vec<OpenOrder> = open_orders_models
         .into_iter()
-        .map(|o| match o.order_type.as_str() {
-            "BuyLimit" => OpenOrder::new(
+        .filter_map(|o| match o.order_type.as_str() {
+            "BuyLimit" => Some(OpenOrder::new_at(
                 o.symbol,
-                o.quantity,
-                o.price,
+                o.quantity.0,
+                o.price.0,
                 OrderType::BuyLimit,
                 Side::Buy,
-            ),
-            "StopLoss" => OpenOrder::new(
+                o.timestamp,
+            )),
+            "StopLoss" => Some(OpenOrder::new_at(
                 o.symbol,
-                o.quantity,
-                o.price,
+                o.quantity.0,
+                o.price.0,
                 OrderType::StopLoss,
                 Side::Sell,
-            ),
-            "TakeProfit" => OpenOrder::new(
+                o.timestamp,
+            )),
+            "TakeProfit" => Some(OpenOrder::new_at(
                 o.symbol,
-                o.quantity,
-                o.price,
+                o.quantity.0,
+                o.price.0,
                 OrderType::TakeProfit,
                 Side::Sell,
-            ),
-            _ => panic!("Unknown order type: {}", o.order_type),
+                o.timestamp,
+            )),
+            "SellLimit" => Some(OpenOrder::new_at(
+                o.symbol,
+                o.quantity.0,
+                o.price.0,
+                OrderType::SellLimit,
+                Side::Sell,
+                o.timestamp,
+            )),
+            _ => {
+                eprintln!(
+                    "Skipping open order {} with unknown type: {}",
+                    o.id, o.order_type
+                );
+                None
+            }
         })
         .collect();
     Ok(open_orders)
 }
 
 /// Synchronizes the holdings in the database with the provided list, updating existing or inserting new ones.
+/// Rows for symbols no longer held (e.g. a closed position) are deleted.
 async fn sync_holdings(
     txn: &DatabaseTransaction,
     holdings: &[(String, rust_decimal::Decimal, rust_decimal::Decimal)],
 ) -> Result<(), DbErr> {
+    let held: Vec<&String> = holdings.iter().map(|(symbol, _, _)| symbol).collect();
+    HoldingEntity::delete_many()
+        .filter(HoldingColumn::Symbol.is_not_in(held))
+        .exec(txn)
+        .await?;
+
     for (symbol, quantity, avg_price) in holdings {
         let existing = HoldingEntity::find()
             .filter(HoldingColumn::Symbol.eq(symbol))
@@ -104,15 +347,15 @@ async fn sync_holdings(
 
         if let Some(model) = existing {
             let mut active_model = model.into_active_model();
-            active_model.quantity = Set(*quantity);
-            active_model.avg_cost = Set(*avg_price);
+            active_model.quantity = Set((*quantity).into());
+            active_model.avg_cost = Set((*avg_price).into());
             active_model.update(txn).await?;
         } else {
             let holding = HoldingActiveModel {
                 id: NotSet,
                 symbol: Set(symbol.clone()),
-                quantity: Set(*quantity),
-                avg_cost: Set(*avg_price),
+                quantity: Set((*quantity).into()),
+                avg_cost: Set((*avg_price).into()),
             };
             holding.insert(txn).await?;
         }
@@ -121,32 +364,45 @@ async fn sync_holdings(
 }
 
 /// Synchronizes the trades in the database, inserting new ones if they don't exist.
+/// Rows with no matching trade in memory (e.g. an undone trade) are deleted.
 async fn sync_trades(txn: &DatabaseTransaction, trades: &[Trade]) -> Result<(), DbErr> {
     let existing_trades = TradeEntity::find().all(txn).await?;
 
-    for trade in trades {
-        let side_str = match trade.get_side() {
-            Side::Buy => "Buy",
-            Side::Sell => "Sell",
-        };
+    let side_str = |trade: &Trade| match trade.get_side() {
+        Side::Buy => "Buy",
+        Side::Sell => "Sell",
+    };
+    let matches = |t: &super::entities::trade::Model, trade: &Trade| {
+        t.symbol == *trade.get_symbol()
+            && t.quantity.0 == trade.get_quantity()
+            && t.price_per.0 == trade.get_price_per()
+            && t.side == side_str(trade)
+            && t.timestamp == trade.get_timestamp()
+    };
 
-        let already_exists = existing_trades.iter().any(|t| {
-            t.symbol == *trade.get_symbol()
-                && t.quantity == trade.get_quantity()
-                && t.price_per == trade.get_price_per()
-                && t.side == side_str
-                && t.timestamp == trade.get_timestamp()
-        });
+    for stale in existing_trades
+        .iter()
+        .filter(|t| !trades.iter().any(|trade| matches(t, trade)))
+    {
+        TradeEntity::delete_by_id(stale.id).exec(txn).await?;
+    }
+
+    for trade in trades {
+        let side_str = side_str(trade);
+        let already_exists = existing_trades.iter().any(|t| matches(t, trade));
 
         if !already_exists {
             let db_trade = TradeActiveModel {
                 id: NotSet,
                 symbol: Set(trade.get_symbol().clone()),
-                quantity: Set(trade.get_quantity()),
-                price_per: Set(trade.get_price_per()),
+                quantity: Set(trade.get_quantity().into()),
+                price_per: Set(trade.get_price_per().into()),
                 side: Set(side_str.to_string()),
                 order_type: Set(trade.get_order_type().clone()),
                 timestamp: Set(trade.get_timestamp()),
+                tag: Set(trade.get_tag().map(str::to_string)),
+                currency: Set(trade.get_currency().map(str::to_string)),
+                fee: Set(trade.get_fee().into()),
             };
             db_trade.insert(txn).await?;
         }
@@ -166,13 +422,14 @@ async fn sync_open_orders(
             OrderType::BuyLimit => "BuyLimit",
             OrderType::StopLoss => "StopLoss",
             OrderType::TakeProfit => "TakeProfit",
+            OrderType::SellLimit => "SellLimit",
         };
         let db_order = OpenOrderActiveModel {
             id: NotSet,
             order_type: Set(order_type_str.to_string()),
             symbol: Set(open_order.get_symbol().clone()),
-            quantity: Set(open_order.get_qty()),
-            price: Set(open_order.get_price_per()),
+            quantity: Set(open_order.get_qty().into()),
+            price: Set(open_order.get_price_per().into()),
             timestamp: Set(open_order.get_timestamp()),
         };
         db_order.insert(txn).await?;
@@ -200,18 +457,291 @@ async fn sync_watchlist(txn: &DatabaseTransaction, watchlist: &[Symbol]) -> Resu
     Ok(())
 }
 
+async fn load_settings(db: &DatabaseConnection) -> Result<Vec<(String, String)>, DbErr> {
+    let setting_models = SettingEntity::find().all(db).await?;
+    Ok(setting_models
+        .into_iter()
+        .map(|s| (s.key, s.value))
+        .collect())
+}
+
+/// Synchronizes the settings in the database by deleting all and re-inserting.
+async fn sync_settings(
+    txn: &DatabaseTransaction,
+    settings: &[(String, String)],
+) -> Result<(), DbErr> {
+    SettingEntity::delete_many().exec(txn).await?;
+
+    for (key, value) in settings {
+        let db_setting = SettingActiveModel {
+            id: NotSet,
+            key: Set(key.clone()),
+            value: Set(value.clone()),
+        };
+        db_setting.insert(txn).await?;
+    }
+    Ok(())
+}
+
+async fn load_notes(db: &DatabaseConnection) -> Result<BTreeMap<Symbol, String>, DbErr> {
+    let note_models = NoteEntity::find().all(db).await?;
+    Ok(note_models
+        .into_iter()
+        .map(|n| (n.symbol, n.text))
+        .collect())
+}
+
+/// Synchronizes the notes in the database by deleting all and re-inserting.
+async fn sync_notes(
+    txn: &DatabaseTransaction,
+    notes: &BTreeMap<Symbol, String>,
+) -> Result<(), DbErr> {
+    NoteEntity::delete_many().exec(txn).await?;
+
+    for (symbol, text) in notes {
+        let db_note = NoteActiveModel {
+            id: NotSet,
+            symbol: Set(symbol.clone()),
+            text: Set(text.clone()),
+        };
+        db_note.insert(txn).await?;
+    }
+    Ok(())
+}
+
+async fn load_templates(db: &DatabaseConnection) -> Result<BTreeMap<String, OrderTemplate>, DbErr> {
+    let template_models = OrderTemplateEntity::find().all(db).await?;
+    Ok(template_models
+        .into_iter()
+        .filter_map(|t| match OrderTemplate::parse(&t.legs) {
+            Ok(template) => Some((t.name, template)),
+            Err(e) => {
+                eprintln!("Skipping template {}: {}", t.name, e);
+                None
+            }
+        })
+        .collect())
+}
+
+/// Synchronizes the order templates in the database by deleting all and re-inserting.
+async fn sync_templates(
+    txn: &DatabaseTransaction,
+    templates: &BTreeMap<String, OrderTemplate>,
+) -> Result<(), DbErr> {
+    OrderTemplateEntity::delete_many().exec(txn).await?;
+
+    for (name, template) in templates {
+        let db_template = OrderTemplateActiveModel {
+            id: NotSet,
+            name: Set(name.clone()),
+            legs: Set(template.to_string()),
+        };
+        db_template.insert(txn).await?;
+    }
+    Ok(())
+}
+
+async fn load_recurring_buys(
+    db: &DatabaseConnection,
+) -> Result<BTreeMap<Symbol, RecurringBuy>, DbErr> {
+    let schedule_models = RecurringBuyEntity::find().all(db).await?;
+    Ok(schedule_models
+        .into_iter()
+        .filter_map(|r| match Frequency::parse(&r.frequency) {
+            Some(frequency) => Some((
+                r.symbol.clone(),
+                RecurringBuy {
+                    symbol: r.symbol,
+                    amount: r.amount.0,
+                    frequency,
+                    next_due: r.next_due,
+                    anchor_day: r
+                        .anchor_day
+                        .and_then(|day| u32::try_from(day).ok())
+                        .unwrap_or_else(|| recurring::day_of_month(r.next_due)),
+                },
+            )),
+            None => {
+                eprintln!(
+                    "Skipping recurring buy of {} with unknown frequency: {}",
+                    r.symbol, r.frequency
+                );
+                None
+            }
+        })
+        .collect())
+}
+
+async fn load_executed_orders(db: &DatabaseConnection) -> Result<Vec<ExecutedOrder>, DbErr> {
+    let executed_models = ExecutedOrderEntity::find().all(db).await?;
+    Ok(executed_models
+        .into_iter()
+        .filter_map(|e| {
+            let order_type = match e.order_type.as_str() {
+                "BuyLimit" => OrderType::BuyLimit,
+                "StopLoss" => OrderType::StopLoss,
+                "TakeProfit" => OrderType::TakeProfit,
+                "SellLimit" => OrderType::SellLimit,
+                _ => {
+                    eprintln!(
+                        "Skipping executed order {} with unknown type: {}",
+                        e.id, e.order_type
+                    );
+                    return None;
+                }
+            };
+            Some(ExecutedOrder {
+                order_type,
+                symbol: e.symbol,
+                quantity: e.quantity.0,
+                target_price: e.target_price.0,
+                fill_price: e.fill_price.0,
+                placed_at: e.placed_at,
+                executed_at: e.executed_at,
+            })
+        })
+        .collect())
+}
+
+/// Synchronizes the executed orders in the database by deleting all and re-inserting.
+async fn sync_executed_orders(
+    txn: &DatabaseTransaction,
+    executed_orders: &[ExecutedOrder],
+) -> Result<(), DbErr> {
+    ExecutedOrderEntity::delete_many().exec(txn).await?;
+
+    for executed in executed_orders {
+        let db_executed = ExecutedOrderActiveModel {
+            id: NotSet,
+            order_type: Set(format!("{:?}", executed.order_type)),
+            symbol: Set(executed.symbol.clone()),
+            quantity: Set(executed.quantity.into()),
+            target_price: Set(executed.target_price.into()),
+            fill_price: Set(executed.fill_price.into()),
+            placed_at: Set(executed.placed_at),
+            executed_at: Set(executed.executed_at),
+        };
+        db_executed.insert(txn).await?;
+    }
+    Ok(())
+}
+
+/// Synchronizes the recurring buys in the database by deleting all and re-inserting.
+async fn sync_recurring_buys(
+    txn: &DatabaseTransaction,
+    schedules: &BTreeMap<Symbol, RecurringBuy>,
+) -> Result<(), DbErr> {
+    RecurringBuyEntity::delete_many().exec(txn).await?;
+
+    for schedule in schedules.values() {
+        let db_schedule = RecurringBuyActiveModel {
+            id: NotSet,
+            symbol: Set(schedule.symbol.clone()),
+            amount: Set(schedule.amount.into()),
+            frequency: Set(schedule.frequency.name().to_string()),
+            next_due: Set(schedule.next_due),
+            anchor_day: Set(i32::try_from(schedule.anchor_day).ok()),
+        };
+        db_schedule.insert(txn).await?;
+    }
+    Ok(())
+}
+
+async fn load_cash_flows(db: &DatabaseConnection) -> Result<Vec<CashFlow>, DbErr> {
+    let flow_models = CashFlowEntity::find().all(db).await?;
+    Ok(flow_models
+        .into_iter()
+        .filter_map(|f| match CashFlowKind::parse(&f.kind) {
+            Some(kind) => {
+                Some(CashFlow::new(kind, f.amount.0, f.timestamp).with_currency(f.currency))
+            }
+            None => {
+                eprintln!("Skipping cash flow {} with unknown kind: {}", f.id, f.kind);
+                None
+            }
+        })
+        .collect())
+}
+
+/// Synchronizes the cash flow ledger in the database by deleting all and re-inserting.
+async fn sync_cash_flows(txn: &DatabaseTransaction, flows: &[CashFlow]) -> Result<(), DbErr> {
+    CashFlowEntity::delete_many().exec(txn).await?;
+
+    for flow in flows {
+        let db_flow = CashFlowActiveModel {
+            id: NotSet,
+            kind: Set(flow.kind.name().to_string()),
+            amount: Set(flow.amount.into()),
+            timestamp: Set(flow.timestamp),
+            currency: Set(flow.currency.clone()),
+        };
+        db_flow.insert(txn).await?;
+    }
+    Ok(())
+}
+
+async fn load_equity_snapshots(db: &DatabaseConnection) -> Result<Vec<EquitySnapshot>, DbErr> {
+    let snapshot_models = EquitySnapshotEntity::find().all(db).await?;
+    Ok(snapshot_models
+        .into_iter()
+        .map(|s| EquitySnapshot::new(s.timestamp, s.value.0))
+        .collect())
+}
+
+/// Synchronizes the equity snapshots in the database by deleting all and re-inserting.
+async fn sync_equity_snapshots(
+    txn: &DatabaseTransaction,
+    snapshots: &[EquitySnapshot],
+) -> Result<(), DbErr> {
+    EquitySnapshotEntity::delete_many().exec(txn).await?;
+
+    for snapshot in snapshots {
+        let db_snapshot = EquitySnapshotActiveModel {
+            id: NotSet,
+            timestamp: Set(snapshot.timestamp),
+            value: Set(snapshot.value.into()),
+        };
+        db_snapshot.insert(txn).await?;
+    }
+    Ok(())
+}
+
 pub fn username_checker(username: &String) -> bool {
     println!("Validating username: {username} against storage");
     true
 }
 
 /// Saves the current app state to the database.
+/// Does nothing in demo mode, so the sample portfolio never overwrites real data.
 pub async fn save_state(state: &Arc<Mutex<AppState>>, db: &DatabaseConnection) {
+    if lock_state(state).is_demo() {
+        return;
+    }
+
     // No cloning of arc mutex needed here, only required for threads
     // get relevant data first to not block more than required
-    let (cash, current_holdings, trades, open_orders, watchlist) = {
-        let state_guard = state.lock().unwrap();
+    let (
+        cash,
+        cash_flows,
+        equity_snapshots,
+        current_holdings,
+        trades,
+        open_orders,
+        watchlist,
+        settings,
+        notes,
+        templates,
+        recurring_buys,
+        executed_orders,
+        backup,
+        now,
+    ) = {
+        let mut state_guard = lock_state(state);
+        // Changes made from here on belong to the next save
+        state_guard.clear_dirty();
         let cash = state_guard.check_balance();
+        let cash_flows = state_guard.get_cash_flows();
+        let equity_snapshots = state_guard.get_equity_snapshots();
 
         // Collect holdings into a vector of simple data tuples
         let holdings = state_guard
@@ -224,96 +754,485 @@ pub async fn save_state(state: &Arc<Mutex<AppState>>, db: &DatabaseConnection) {
 
         let open_orders = state_guard.get_open_orders();
         let watchlist = state_guard.get_watchlist();
-        (cash, holdings, trades, open_orders, watchlist)
+        let mut settings = state_guard.get_settings();
+        settings.push((STATE_VERSION_KEY.to_string(), STATE_VERSION.to_string()));
+        let notes = state_guard.get_notes();
+        let templates = state_guard.get_templates();
+        let recurring_buys = state_guard.get_recurring_buys();
+        let executed_orders = state_guard.get_executed_orders();
+        (
+            cash,
+            cash_flows,
+            equity_snapshots,
+            holdings,
+            trades,
+            open_orders,
+            watchlist,
+            settings,
+            notes,
+            templates,
+            recurring_buys,
+            executed_orders,
+            state_guard
+                .get_backup_path()
+                .filter(|_| state_guard.backup_due()),
+            state_guard.now_timestamp(),
+        )
     };
 
-    let _txn_result = db
+    let saved = db
         .transaction::<_, _, DbErr>(|txn| {
             Box::pin(async move {
                 let app_state_opt = AppStateEntity::find_by_id(1).one(txn).await?;
                 if let Some(model) = app_state_opt {
                     let mut active_model = model.into_active_model();
-                    active_model.cash_balance = Set(cash);
-                    active_model.updated_at = Set(chrono::Utc::now().timestamp());
+                    active_model.cash_balance = Set(cash.into());
+                    active_model.updated_at = Set(now);
                     active_model.update(txn).await?;
                 } else {
                     let new_app_state = AppStateActiveModel {
                         id: Set(1),
-                        cash_balance: Set(cash),
-                        updated_at: Set(chrono::Utc::now().timestamp()),
+                        cash_balance: Set(cash.into()),
+                        updated_at: Set(now),
                     };
                     new_app_state.insert(txn).await?;
                 }
 
+                sync_cash_flows(txn, &cash_flows).await?;
+                sync_equity_snapshots(txn, &equity_snapshots).await?;
                 sync_holdings(txn, &current_holdings).await?;
                 sync_trades(txn, &trades).await?;
                 sync_open_orders(txn, &open_orders).await?;
                 sync_watchlist(txn, &watchlist).await?;
+                sync_settings(txn, &settings).await?;
+                sync_notes(txn, &notes).await?;
+                sync_templates(txn, &templates).await?;
+                sync_recurring_buys(txn, &recurring_buys).await?;
+                sync_executed_orders(txn, &executed_orders).await?;
 
                 Ok(())
             })
         })
-        .await
-        .ok();
+        .await;
+
+    // Only a state that committed is worth keeping as the backup
+    if saved.is_err() {
+        lock_state(state).mark_dirty();
+    } else if let Some(path) = backup
+        && write_backup(db, &path).await.is_ok()
+    {
+        lock_state(state).mark_backup_written();
+    }
+}
+
+/// Saves only when something changed since the last save, for the autosave timer and exit
+/// Returns whether a save ran
+pub async fn save_if_dirty(state: &Arc<Mutex<AppState>>, db: &DatabaseConnection) -> bool {
+    {
+        let state_guard = lock_state(state);
+        if state_guard.is_demo() || !state_guard.is_dirty() {
+            return false;
+        }
+    }
+    save_state(state, db).await;
+    true
+}
+
+/// Clean shutdown shared by quit, Ctrl+C and SIGINT: stops the background order
+/// monitor so nothing fills after the final save, then saves what changed
+/// Clearing `running` only ends the loop at its next pass, so the task itself is
+/// stopped and awaited; fills apply under the state lock, so none is left half done
+pub async fn shutdown(state: &Arc<Mutex<AppState>>, db: &DatabaseConnection, running: &AtomicBool) {
+    running.store(false, Ordering::Relaxed);
+    let monitor = lock_state(state).take_monitor_task();
+    if let Some(monitor) = monitor {
+        monitor.abort();
+        let _ = monitor.await;
+    }
+    save_if_dirty(state, db).await;
 }
 
 /// Loads the app state from the database, or initializes a new one if not found.
-pub async fn load_state() -> Arc<Mutex<AppState>> {
-    let database_url =
-        env::var("DATABASE_URL").unwrap_or_else(|_| "sqlite://db.sqlite".to_string());
-
-    match Database::connect(&database_url).await {
-        Ok(db) => match load_app_state(&db).await {
-            Ok(Some(cash_balance)) => {
-                let holdings_map = load_holdings(&db).await.unwrap_or_default();
-                let trades = load_trades(&db).await.unwrap_or_default();
-                let open_orders = load_open_orders(&db).await.unwrap_or_default();
-                let watchlist = load_watchlist(&db).await.unwrap_or_default();
-
-                let mut state = AppState::new();
-                state.set_cash_balance(cash_balance);
-                state.set_holdings_map(holdings_map).await;
-                state.set_trades(trades);
-                state.set_open_orders(open_orders);
-                state.set_watchlist(watchlist);
-
-                Arc::new(Mutex::new(state))
-            }
-            Ok(None) => {
-                println!("No app state found in database, initializing new state");
-                Arc::new(Mutex::new(AppState::new()))
-            }
-            Err(e) => {
-                eprintln!("Error loading state from database: {}", e);
-                Arc::new(Mutex::new(AppState::new()))
-            }
-        },
+/// Reads the same connection `save_state` writes to.
+pub async fn load_state(db: &DatabaseConnection) -> Arc<Mutex<AppState>> {
+    match try_load_state(db).await {
+        Ok(state) => Arc::new(Mutex::new(state)),
         Err(e) => {
-            eprintln!("Failed to connect to database: {}", e);
+            eprintln!("Error loading state from database: {}", e);
             Arc::new(Mutex::new(AppState::new()))
         }
     }
 }
 
+/// Loads the app state, failing on any unreadable table instead of starting empty
+pub async fn try_load_state(db: &DatabaseConnection) -> Result<AppState, DbErr> {
+    let Some((cash_balance, updated_at)) = load_app_state(db).await? else {
+        return Ok(AppState::new());
+    };
+
+    let settings = load_settings(db).await?;
+    let version = stored_version(&settings);
+
+    let mut state = AppState::new();
+    state.set_cash_balance(cash_balance);
+    state.set_cash_flows(load_cash_flows(db).await?);
+    state.set_equity_snapshots(load_equity_snapshots(db).await?);
+    state.set_holdings_map(load_holdings(db).await?);
+    state.set_trades(load_trades(db).await?);
+    state.set_open_orders(load_open_orders(db).await?);
+    state.set_watchlist(load_watchlist(db).await?);
+    state.set_notes(load_notes(db).await?);
+    state.set_templates(load_templates(db).await?);
+    state.set_recurring_buys(load_recurring_buys(db).await?);
+    state.set_executed_orders(load_executed_orders(db).await?);
+    for (key, value) in settings {
+        state.apply_setting(&key, &value);
+    }
+    upgrade_state(&mut state, version, updated_at);
+
+    // Accounts saved before yield tracking resume accrual from their last save
+    if state.get_yield_accrued_at() == 0 {
+        state.set_yield_accrued_at(updated_at);
+    }
+    let now = state.now_timestamp();
+    state.accrue_cash_yield(now);
+    state.accrue_margin_interest(now);
+    Ok(state)
+}
+
+// SECTION: State Versioning
+
+/// Version of the data `save_state` writes
+/// 0: saved before versioning, with no cash flow ledger
+/// 1: cash flow ledger holds every deposit and withdrawal
+pub const STATE_VERSION: u32 = 1;
+/// Setting row holding the version the data was saved as
+pub const STATE_VERSION_KEY: &str = "state_version";
+
+/// Version recorded in the settings; data saved before versioning has none and is version 0
+pub fn stored_version(settings: &[(String, String)]) -> u32 {
+    settings
+        .iter()
+        .find(|(key, _)| key == STATE_VERSION_KEY)
+        .and_then(|(_, value)| value.parse().ok())
+        .unwrap_or(0)
+}
+
+/// Upgrades a state loaded from `version` data to `STATE_VERSION`, one step at a time
+/// `saved_at` is when the data was last saved. Newer versions are left as they are
+pub fn upgrade_state(state: &mut AppState, version: u32, saved_at: i64) {
+    for from in version..STATE_VERSION {
+        if from == 0 {
+            backfill_opening_flow(state, saved_at);
+        }
+    }
+}
+
+// v0 -> v1: accounts from before the ledger get one flow for the net amount put in,
+// inferred from cash and the trade log, dated at the first trade
+fn backfill_opening_flow(state: &mut AppState, saved_at: i64) {
+    if !state.get_cash_flows().is_empty() {
+        return;
+    }
+    let trades = state.get_trades();
+    let mut net = state.check_balance();
+    for trade in &trades {
+        let amount = trade.get_quantity() * trade.get_price_per();
+        match trade.get_order_type().as_str() {
+            Finance::INTEREST_ORDER_TYPE => match trade.get_side() {
+                Side::Buy => net -= amount,
+                Side::Sell => net += amount,
+            },
+            Finance::SPLIT_ORDER_TYPE | Finance::IMPORT_ORDER_TYPE => {}
+            _ => match trade.get_side() {
+                Side::Buy => net += trade.cash_value(),
+                Side::Sell => net -= trade.cash_value(),
+            },
+        }
+    }
+    let net = net.round_dp(2);
+    if net == rust_decimal::Decimal::ZERO {
+        return;
+    }
+    let timestamp = trades
+        .iter()
+        .map(|trade| trade.get_timestamp())
+        .min()
+        .unwrap_or(saved_at)
+        .min(saved_at);
+    let kind = if net > rust_decimal::Decimal::ZERO {
+        CashFlowKind::Deposit
+    } else {
+        CashFlowKind::Withdrawal
+    };
+    state.add_cash_flow(CashFlow::new(kind, net.abs(), timestamp));
+}
+
+// SECTION: Backup And Recovery
+
+/// File path of a SQLite database URL, None for in-memory or non-SQLite URLs
+pub fn sqlite_path(database_url: &str) -> Option<PathBuf> {
+    let rest = database_url
+        .strip_prefix("sqlite://")
+        .or_else(|| database_url.strip_prefix("sqlite:"))?;
+    let path = rest.split('?').next().unwrap_or_default();
+    if path.is_empty() || path == ":memory:" {
+        return None;
+    }
+    Some(PathBuf::from(path))
+}
+
+/// Name the status bar shows for the account: the SQLite file's name without its
+/// extension, or "memory" when the database isn't a file
+pub fn portfolio_name(database_url: &str) -> String {
+    sqlite_path(database_url)
+        .and_then(|path| {
+            path.file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+        })
+        .unwrap_or_else(|| "memory".to_string())
+}
+
+/// Saved portfolios in a directory: its `.sqlite` files by name
+/// Backups, temp files and damaged copies have other extensions and are skipped
+pub fn portfolio_files(dir: &Path) -> std::io::Result<Vec<PathBuf>> {
+    let mut files: Vec<PathBuf> = std::fs::read_dir(dir)?
+        .filter_map(|entry| Some(entry.ok()?.path()))
+        .filter(|path| path.is_file() && path.extension().is_some_and(|ext| ext == "sqlite"))
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+/// Where the last good copy of `path` is kept: the same name with `.bak` appended
+pub fn backup_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".bak");
+    PathBuf::from(name)
+}
+
+/// Snapshots the database into `backup` through a temp file renamed into place,
+/// so an interrupted backup never replaces the previous good one
+pub async fn write_backup(db: &DatabaseConnection, backup: &Path) -> Result<(), NaviinError> {
+    let mut temp = backup.as_os_str().to_owned();
+    temp.push(".tmp");
+    let temp = PathBuf::from(temp);
+    // VACUUM INTO refuses to overwrite, so clear a temp file left by a crash
+    if temp.exists() {
+        std::fs::remove_file(&temp)?;
+    }
+    let target = temp.display().to_string().replace('\'', "''");
+    db.execute_unprepared(&format!("VACUUM INTO '{}'", target))
+        .await?;
+    std::fs::rename(&temp, backup)?;
+    Ok(())
+}
+
+/// Connects to the database, brings the schema up to date and loads the state
+pub async fn open(database_url: &str) -> Result<(DatabaseConnection, AppState), NaviinError> {
+    let db = Database::connect(database_url).await?;
+    Migrator::up(&db, None).await?;
+    let mut state = try_load_state(&db).await?;
+    state.set_backup_path(sqlite_path(database_url).map(|path| backup_path(&path)));
+    Ok((db, state))
+}
+
+// Whether `error` says the file itself is damaged: not a SQLite database at all,
+// or one with malformed pages. A locked file, an I/O hiccup or a failed migration
+// is not, and restoring the backup over it would throw away good data
+fn is_corruption(error: &NaviinError) -> bool {
+    let NaviinError::Database(e) = error else {
+        return false;
+    };
+    let message = e.to_string();
+    message.contains("file is not a database")
+        || message.contains("database disk image is malformed")
+}
+
+/// Opens the database, falling back to its backup when the file is corrupt
+/// The damaged file is kept next to it with `.corrupt` appended and the
+/// recovery is reported as an event. Any other failure is returned as is
+pub async fn open_or_recover(
+    database_url: &str,
+) -> Result<(DatabaseConnection, AppState), NaviinError> {
+    let error = match open(database_url).await {
+        Ok(opened) => return Ok(opened),
+        Err(e) if is_corruption(&e) => e,
+        Err(e) => return Err(e),
+    };
+    let Some(path) = sqlite_path(database_url) else {
+        return Err(error);
+    };
+    let backup = backup_path(&path);
+    if !backup.exists() {
+        return Err(error);
+    }
+
+    let mut corrupt = path.as_os_str().to_owned();
+    corrupt.push(".corrupt");
+    let corrupt = PathBuf::from(corrupt);
+    std::fs::rename(&path, &corrupt)?;
+    std::fs::copy(&backup, &path)?;
+    let (db, mut state) = open(database_url).await?;
+    state.push_event(
+        EventKind::Error,
+        format!(
+            "Database could not be read ({}); restored the last backup. The damaged file is at {}",
+            error,
+            corrupt.display()
+        ),
+    );
+    Ok((db, state))
+}
+
 /// Resets the app state to default and clears the database.
-pub async fn default_state(state: &Arc<Mutex<AppState>>, db: &DatabaseConnection) {
+/// In demo mode the sample portfolio is restored instead and the database is left alone.
+/// The database is cleared first, so an account it couldn't clear is left untouched
+pub async fn default_state(
+    state: &Arc<Mutex<AppState>>,
+    db: &DatabaseConnection,
+) -> Result<(), DbErr> {
+    let demo = lock_state(state).is_demo();
+    if !demo {
+        clear_account(db).await?;
+    }
+
     {
-        let mut state_guard = state.lock().unwrap();
-        *state_guard = AppState::new();
+        let mut state_guard = lock_state(state);
+        // The backup location comes from startup and the order monitor belongs to the
+        // session, not to the account
+        let backup = state_guard.get_backup_path();
+        let monitor = state_guard.take_monitor_task();
+        *state_guard = if demo {
+            demo::demo_state()
+        } else {
+            AppState::new()
+        };
+        if !demo {
+            state_guard.set_backup_path(backup);
+        }
+        if let Some(monitor) = monitor {
+            state_guard.set_monitor_task(monitor);
+        }
     }
 
-    let _ = db
-        .transaction::<_, _, DbErr>(|txn| {
-            Box::pin(async move {
-                AppStateEntity::delete_many().exec(txn).await?;
-                HoldingEntity::delete_many().exec(txn).await?;
-                TradeEntity::delete_many().exec(txn).await?;
-                OpenOrderEntity::delete_many().exec(txn).await?;
-                WatchlistEntity::delete_many().exec(txn).await?;
-                Ok(())
-            })
+    save_state(state, db).await;
+    Ok(())
+}
+
+/// Deletes every account row, leaving the schema in place
+async fn clear_account(db: &DatabaseConnection) -> Result<(), DbErr> {
+    db.transaction::<_, _, DbErr>(|txn| {
+        Box::pin(async move {
+            AppStateEntity::delete_many().exec(txn).await?;
+            CashFlowEntity::delete_many().exec(txn).await?;
+            EquitySnapshotEntity::delete_many().exec(txn).await?;
+            HoldingEntity::delete_many().exec(txn).await?;
+            TradeEntity::delete_many().exec(txn).await?;
+            OpenOrderEntity::delete_many().exec(txn).await?;
+            WatchlistEntity::delete_many().exec(txn).await?;
+            SettingEntity::delete_many().exec(txn).await?;
+            NoteEntity::delete_many().exec(txn).await?;
+            OrderTemplateEntity::delete_many().exec(txn).await?;
+            RecurringBuyEntity::delete_many().exec(txn).await?;
+            ExecutedOrderEntity::delete_many().exec(txn).await?;
+            Ok(())
         })
-        .await;
+    })
+    .await
+    .map_err(|e| match e {
+        TransactionError::Connection(e) | TransactionError::Transaction(e) => e,
+    })
+}
+
+// SECTION: Account Backup
+
+/// First bytes of every SQLite database file
+const SQLITE_HEADER: &[u8] = b"SQLite format 3\0";
 
+/// Writes the whole account to `path` as one portable file for `restore`
+/// The file is a snapshot of the database, so it carries the settings, notes,
+/// templates and watchlist along with the schema and state versions it was saved at
+pub async fn backup_account(
+    state: &Arc<Mutex<AppState>>,
+    db: &DatabaseConnection,
+    path: &Path,
+) -> Result<(), String> {
+    if lock_state(state).is_demo() {
+        return Err("Backups are not available in demo mode".to_string());
+    }
+    // Flush anything not yet saved so the snapshot matches what's on screen
+    save_state(state, db).await;
+    write_backup(db, path)
+        .await
+        .map_err(|e| format!("Could not write backup {}: {}", path.display(), e))
+}
+
+/// Reads and validates a file written by `backup_account` without touching the
+/// live account. Older backups are brought up to date on a scratch copy; ones
+/// from a newer version of Naviin are refused
+pub async fn read_account_backup(path: &Path) -> Result<AppState, String> {
+    let unreadable =
+        |e: &dyn std::fmt::Display| format!("Could not read backup {}: {}", path.display(), e);
+    let mut header = [0u8; 16];
+    let is_sqlite = std::fs::File::open(path)
+        .and_then(|mut file| std::io::Read::read_exact(&mut file, &mut header))
+        .map(|_| header == SQLITE_HEADER);
+    match is_sqlite {
+        Ok(true) => {}
+        Err(e) if e.kind() != std::io::ErrorKind::UnexpectedEof => return Err(unreadable(&e)),
+        _ => return Err(format!("{} is not a Naviin backup", path.display())),
+    }
+
+    // Migrating upgrades the file in place, so work on a copy, numbered so reads
+    // running at the same time don't share one
+    static SCRATCH_COUNT: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(0);
+    let scratch = env::temp_dir().join(format!(
+        "naviin_restore_{}_{}.sqlite",
+        std::process::id(),
+        SCRATCH_COUNT.fetch_add(1, std::sync::atomic::Ordering::Relaxed)
+    ));
+    std::fs::copy(path, &scratch).map_err(|e| unreadable(&e))?;
+    let result = load_backup_copy(&scratch).await;
+    std::fs::remove_file(&scratch).ok();
+    result.map_err(|e| unreadable(&e))?.ok_or_else(|| {
+        format!(
+            "{} was made by a newer version of Naviin or holds no account",
+            path.display()
+        )
+    })
+}
+
+// The account in a scratch copy of a backup, None if it is empty or too new
+async fn load_backup_copy(scratch: &Path) -> Result<Option<AppState>, DbErr> {
+    let db = Database::connect(format!("sqlite://{}?mode=rw", scratch.display())).await?;
+    let loaded = async {
+        Migrator::up(&db, None).await?;
+        if load_app_state(&db).await?.is_none()
+            || stored_version(&load_settings(&db).await?) > STATE_VERSION
+        {
+            return Ok(None);
+        }
+        try_load_state(&db).await.map(Some)
+    }
+    .await;
+    db.close().await.ok();
+    loaded
+}
+
+/// Replaces the live account with one read by `read_account_backup` and saves it
+pub async fn restore_account(
+    state: &Arc<Mutex<AppState>>,
+    db: &DatabaseConnection,
+    account: AppState,
+) -> Result<(), String> {
+    if lock_state(state).is_demo() {
+        return Err("Restore is not available in demo mode".to_string());
+    }
+    clear_account(db)
+        .await
+        .map_err(|e| format!("Could not restore the backup: {}", e))?;
+    lock_state(state).replace_account(account);
     save_state(state, db).await;
+    Ok(())
 }
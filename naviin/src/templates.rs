@@ -0,0 +1,297 @@
+/// Templates Module
+///
+/// Saved multi-leg order structures, e.g. a bracket of a market buy with a stop
+/// below and a take profit above. A template is a list of legs written the way
+/// they are typed (`buy 10; stoploss 10 -5%; takeprofit 10 +10%`), with prices as
+/// an offset from the symbol's quote so the same structure fits any symbol.
+/// `template save` can also capture the orders just placed from the undo history.
+use std::fmt;
+
+use rust_decimal::Decimal;
+
+use crate::Finance::Symbol;
+use crate::Orders::OrderType;
+use crate::undo::UndoAction;
+
+/// Separates legs in a template spec
+pub const LEG_SEPARATOR: char = ';';
+
+/// Order command a leg places
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LegKind {
+    Buy,
+    BuyLimit,
+    StopLoss,
+    TakeProfit,
+    SellLimit,
+}
+
+impl LegKind {
+    /// Accepts the command names used to place the order
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.to_lowercase().as_str() {
+            "buy" => Some(Self::Buy),
+            "buylimit" => Some(Self::BuyLimit),
+            "stoploss" => Some(Self::StopLoss),
+            "takeprofit" => Some(Self::TakeProfit),
+            "selllimit" => Some(Self::SellLimit),
+            _ => None,
+        }
+    }
+
+    pub fn command(&self) -> &'static str {
+        match self {
+            Self::Buy => "buy",
+            Self::BuyLimit => "buylimit",
+            Self::StopLoss => "stoploss",
+            Self::TakeProfit => "takeprofit",
+            Self::SellLimit => "selllimit",
+        }
+    }
+
+    /// Market buys fill at the quote; every other leg needs a trigger price
+    pub fn is_priced(&self) -> bool {
+        *self != Self::Buy
+    }
+}
+
+/// Trigger price of a leg
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LegPrice {
+    /// Percent above (positive) or below (negative) the quote when the template runs
+    Offset(Decimal),
+    /// The same price whatever the quote
+    Fixed(Decimal),
+}
+
+impl LegPrice {
+    /// Accepts `+5%`, `-5%` or `5%` as an offset and a bare number as a fixed price
+    pub fn parse(value: &str) -> Result<Self, String> {
+        let invalid = || format!("Invalid leg price: '{}' (e.g. -5% or 150)", value);
+        match value.strip_suffix('%') {
+            Some(pct) => {
+                let pct: Decimal = pct
+                    .strip_prefix('+')
+                    .unwrap_or(pct)
+                    .parse()
+                    .map_err(|_| invalid())?;
+                if pct <= -Decimal::ONE_HUNDRED {
+                    return Err(format!("Offset must be above -100%, got {}%", pct));
+                }
+                Ok(Self::Offset(pct))
+            }
+            None => {
+                let price: Decimal = value.parse().map_err(|_| invalid())?;
+                if price <= Decimal::ZERO {
+                    return Err("Leg price must be positive".to_string());
+                }
+                Ok(Self::Fixed(price))
+            }
+        }
+    }
+
+    /// Price this resolves to when the symbol is quoted at `reference`
+    pub fn resolve(&self, reference: Decimal) -> Decimal {
+        match self {
+            Self::Offset(pct) => {
+                let price = reference * (Decimal::ONE + pct / Decimal::ONE_HUNDRED);
+                // Keep sub-dollar quotes from rounding to zero
+                let dp = if reference < Decimal::ONE { 6 } else { 2 };
+                price.round_dp(dp)
+            }
+            Self::Fixed(price) => *price,
+        }
+    }
+}
+
+impl fmt::Display for LegPrice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Offset(pct) if *pct >= Decimal::ZERO => write!(f, "+{}%", pct.normalize()),
+            Self::Offset(pct) => write!(f, "{}%", pct.normalize()),
+            Self::Fixed(price) => write!(f, "{}", price.normalize()),
+        }
+    }
+}
+
+/// One order of a template
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TemplateLeg {
+    pub kind: LegKind,
+    pub quantity: Decimal,
+    /// None only for a market buy
+    pub price: Option<LegPrice>,
+}
+
+impl TemplateLeg {
+    /// Parses `<command> <quantity> [price]`
+    pub fn parse(value: &str) -> Result<Self, String> {
+        let parts: Vec<&str> = value.split_whitespace().collect();
+        let Some(kind) = parts.first().and_then(|name| LegKind::parse(name)) else {
+            return Err(format!(
+                "Invalid leg: '{}' (expected buy, buylimit, stoploss, takeprofit or selllimit)",
+                value.trim()
+            ));
+        };
+        let expected = if kind.is_priced() { 3 } else { 2 };
+        if parts.len() != expected {
+            let usage = if kind.is_priced() {
+                "<quantity> <price|offset%>"
+            } else {
+                "<quantity>"
+            };
+            return Err(format!(
+                "Invalid leg: '{}' (expected {} {})",
+                value.trim(),
+                kind.command(),
+                usage
+            ));
+        }
+        let quantity: Decimal = parts[1]
+            .parse()
+            .map_err(|_| format!("Invalid leg quantity: '{}'", parts[1]))?;
+        if quantity <= Decimal::ZERO {
+            return Err("Leg quantity must be positive".to_string());
+        }
+        let price = match parts.get(2) {
+            Some(price) => Some(LegPrice::parse(price)?),
+            None => None,
+        };
+        Ok(Self {
+            kind,
+            quantity,
+            price,
+        })
+    }
+
+    /// Leg reproducing a recorded buy or order, priced relative to `reference`
+    /// None for actions that aren't orders
+    pub fn from_action(action: &UndoAction, reference: Decimal) -> Option<Self> {
+        match action {
+            UndoAction::Buy { quantity, .. } => Some(Self {
+                kind: LegKind::Buy,
+                quantity: *quantity,
+                price: None,
+            }),
+            UndoAction::PlaceOrder { order } => {
+                let kind = match order.get_order_type() {
+                    OrderType::BuyLimit => LegKind::BuyLimit,
+                    OrderType::StopLoss => LegKind::StopLoss,
+                    OrderType::TakeProfit => LegKind::TakeProfit,
+                    OrderType::SellLimit => LegKind::SellLimit,
+                };
+                let price = if reference > Decimal::ZERO {
+                    let pct =
+                        (order.get_price_per() / reference - Decimal::ONE) * Decimal::ONE_HUNDRED;
+                    LegPrice::Offset(pct.round_dp(2))
+                } else {
+                    LegPrice::Fixed(order.get_price_per())
+                };
+                Some(Self {
+                    kind,
+                    quantity: order.get_qty(),
+                    price: Some(price),
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for TemplateLeg {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.kind.command(), self.quantity.normalize())?;
+        if let Some(price) = &self.price {
+            write!(f, " {}", price)?;
+        }
+        Ok(())
+    }
+}
+
+/// One leg ready to place: the command, quantity and resolved price
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PlannedLeg {
+    pub kind: LegKind,
+    pub quantity: Decimal,
+    pub price: Option<Decimal>,
+}
+
+/// Named list of legs placed together on one symbol
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OrderTemplate {
+    legs: Vec<TemplateLeg>,
+}
+
+impl OrderTemplate {
+    pub fn new(legs: Vec<TemplateLeg>) -> Result<Self, String> {
+        if legs.is_empty() {
+            return Err("A template needs at least one leg".to_string());
+        }
+        Ok(Self { legs })
+    }
+
+    /// Parses legs separated by `;`, e.g. `buy 10; stoploss 10 -5%`
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let legs = spec
+            .split(LEG_SEPARATOR)
+            .filter(|leg| !leg.trim().is_empty())
+            .map(TemplateLeg::parse)
+            .collect::<Result<Vec<_>, _>>()?;
+        Self::new(legs)
+    }
+
+    pub fn get_legs(&self) -> &[TemplateLeg] {
+        &self.legs
+    }
+
+    /// Legs priced against `reference`, scaled so the first leg has `quantity`
+    /// Without a quantity the saved sizes are used
+    pub fn plan(&self, reference: Decimal, quantity: Option<Decimal>) -> Vec<PlannedLeg> {
+        let scale = match quantity {
+            Some(quantity) => quantity / self.legs[0].quantity,
+            None => Decimal::ONE,
+        };
+        self.legs
+            .iter()
+            .map(|leg| PlannedLeg {
+                kind: leg.kind,
+                quantity: (leg.quantity * scale).normalize(),
+                price: leg.price.map(|price| price.resolve(reference)),
+            })
+            .collect()
+    }
+}
+
+impl fmt::Display for OrderTemplate {
+    /// The spec `parse` reads back
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let legs: Vec<String> = self.legs.iter().map(|leg| leg.to_string()).collect();
+        write!(f, "{}", legs.join("; "))
+    }
+}
+
+/// Whether `name` can be used for a template: letters, digits, `-` and `_`
+pub fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// The run of orders at the end of the undo history placed on one symbol, oldest first
+/// This is what was just entered, e.g. a buy followed by its stop and take profit
+pub fn recent_orders(history: &[UndoAction]) -> Option<(Symbol, Vec<&UndoAction>)> {
+    let symbol_of = |action: &UndoAction| match action {
+        UndoAction::Buy { symbol, .. } => Some(symbol.clone()),
+        UndoAction::PlaceOrder { order } => Some(order.get_symbol().clone()),
+        _ => None,
+    };
+    let symbol = symbol_of(history.last()?)?;
+    let mut run: Vec<&UndoAction> = history
+        .iter()
+        .rev()
+        .take_while(|action| symbol_of(action).as_ref() == Some(&symbol))
+        .collect();
+    run.reverse();
+    Some((symbol, run))
+}
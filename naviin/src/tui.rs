@@ -1,12 +1,25 @@
-use std::collections::HashMap;
 /// TUI Module - Main terminal user interface
 ///
 /// This module coordinates the display of UI areas:
 /// 1. Top Row: Holdings | Open Orders | Watchlist (3 components, horizontal)
-/// 2. Middle: Input component (command typing area)
-/// 3. Bottom: Output component (command results display)
+/// 2. Status bar: one line with the portfolio, value, cash, day P&L, connection and refresh age
+/// 3. Middle: Input component (command typing area)
+/// 4. Bottom: Output component (command results display)
 ///
-/// Auto-refreshes top components every 5 seconds for real-time price updates.
+/// Auto-refreshes top components on a configurable timer (5 seconds by default).
+/// In `watch` mode the bottom area shows a live quote for one symbol instead of output.
+/// Background events (order fills, interest, errors) are appended to the output on refresh.
+/// Changes those background tasks make are autosaved every 30 seconds when there are any.
+/// While `offline` is on, refreshes fetch nothing and the panels show cached prices as stale.
+/// Tab moves keyboard focus to the holdings or watchlist panel, where Up/Down select a row
+/// and the row's note is shown. `i` on a selected row opens the symbol's detail pane.
+/// Resizes redraw immediately; a terminal under `MIN_WIDTH` x `MIN_HEIGHT` shows a
+/// "Terminal too small" prompt in place of the panels.
+/// Ctrl+C, or SIGINT from outside, quits the same way `Q` does so the exit save still runs.
+/// `b`/`s` on a selected holding stage a market buy/sell of it in the input; it only runs
+/// once a quantity is typed and Enter is pressed, and Esc discards it.
+/// Ctrl+O opens a guided order wizard over the top row that builds the same command line.
+use std::collections::HashMap;
 use std::io;
 use std::sync::{Arc, Mutex};
 use std::time::Duration;
@@ -15,22 +28,38 @@ use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind};
 use ratatui::{
     Frame, Terminal,
     backend::Backend,
-    layout::{Constraint, Direction, Layout, Rect},
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    widgets::{Paragraph, Wrap},
 };
 use rust_decimal::Decimal;
 use sea_orm::DatabaseConnection;
 use tokio::sync::mpsc;
-use tokio::time::{Instant, interval};
+use tokio::time::{Instant, interval, interval_at};
 
-use crate::AppState::AppState;
-use crate::Finance::Symbol;
-use crate::FinanceProvider;
-use crate::commands::process_command;
+use crate::AppState::{AppState, lock_state};
+use crate::Finance::{AssetType, Symbol};
+use crate::Storage;
+use crate::commands::{process_command, watch_symbol};
+use crate::components::detail::{DETAIL_HISTORY_DAYS, DetailComponent};
 use crate::components::holdings::HoldingsComponent;
 use crate::components::input::InputComponent;
 use crate::components::open_orders::OpenOrdersComponent;
+use crate::components::order_wizard::{OrderWizardComponent, WizardAction};
 use crate::components::output::OutputComponent;
+use crate::components::quote::QuoteComponent;
+use crate::components::status_bar::StatusBarComponent;
 use crate::components::watchlist::WatchlistComponent;
+use crate::events::format_events;
+use crate::fx;
+use crate::refresh::{self, PriceBatch, RefreshHealth};
+
+/// Seconds between autosaves of changes no command has saved, such as background fills
+pub const AUTOSAVE_SECS: u64 = 30;
+
+/// Smallest terminal the panels are drawn in; below it only a resize prompt is shown.
+/// At this height every bordered panel still has a row of content.
+pub const MIN_WIDTH: u16 = 60;
+pub const MIN_HEIGHT: u16 = 16;
 
 /// Layout areas for all UI components
 struct LayoutAreas {
@@ -40,12 +69,33 @@ struct LayoutAreas {
     open_orders: Rect,
     /// Area for watchlist component (top right)
     watchlist: Rect,
+    /// One-line status bar between the top row and the input
+    status: Rect,
     /// Area for input component (middle)
     input: Rect,
     /// Area for output component (bottom)
     output: Rect,
 }
 
+/// Which area Up/Down navigate; typing always goes to the input
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Focus {
+    Input,
+    Holdings,
+    Watchlist,
+}
+
+impl Focus {
+    /// Next area in Tab order
+    fn next(self) -> Self {
+        match self {
+            Focus::Input => Focus::Holdings,
+            Focus::Holdings => Focus::Watchlist,
+            Focus::Watchlist => Focus::Input,
+        }
+    }
+}
+
 /// Main TUI application state and coordinator
 pub struct Tui {
     /// Flag to indicate if the application should exit
@@ -56,29 +106,59 @@ pub struct Tui {
     open_orders: OpenOrdersComponent,
     /// Top right: Watchlist display component
     watchlist: WatchlistComponent,
+    /// Account summary line under the top row
+    status_bar: StatusBarComponent,
     /// Middle section: Command input component
     input: InputComponent,
     /// Bottom section: Output display component
     output: OutputComponent,
+    /// Live quote shown in place of the output while `watch` is active
+    watching: Option<QuoteComponent>,
+    /// Symbol detail drawn over the top row after `i`
+    detail: Option<DetailComponent>,
+    /// Guided order entry drawn over the top row after Ctrl+O; takes every key while open
+    wizard: Option<OrderWizardComponent>,
+    /// Panel receiving Up/Down
+    focus: Focus,
+    /// Input holds a quick buy/sell staged from the holdings panel, so Esc discards it
+    quick_trade: bool,
     /// Application state (holdings, cash, orders)
     state: Arc<Mutex<AppState>>,
     /// Database connection for persistence
     db: DatabaseConnection,
     /// Background order monitoring flag
     running: Arc<std::sync::atomic::AtomicBool>,
-    /// Last time data was refreshed (for status/debugging)
+    /// Last time prices were received, shown as "Ns ago" in the watchlist
     last_refresh: Instant,
 
     message_tx: mpsc::UnboundedSender<TuiMessage>,
     message_rx: mpsc::UnboundedReceiver<TuiMessage>,
     price_refresh_running: bool,
+    /// How many symbols the latest prices cover
+    refresh_health: RefreshHealth,
+    /// Symbols the last full refresh couldn't price, fetched alone on the next tick
+    retry_symbols: Vec<Symbol>,
+    /// Exchange rates from the last full refresh, for valuing the account
+    fx_rates: HashMap<String, Decimal>,
 }
 
 /// Used for message passing via channel
 enum TuiMessage {
     PricesUpdated {
-        holdings: HashMap<Symbol, Decimal>,
-        watchlist: HashMap<Symbol, Decimal>,
+        prices: PriceBatch,
+        /// Only the symbols the previous refresh failed were fetched
+        retry: bool,
+    },
+    DetailLoaded {
+        symbol: Symbol,
+        price: Decimal,
+        previous_close: Decimal,
+        /// Daily closes, oldest first
+        closes: Vec<Decimal>,
+    },
+    WizardQuote {
+        symbol: Symbol,
+        price: Decimal,
     },
 }
 impl Tui {
@@ -97,8 +177,14 @@ impl Tui {
             holdings: HoldingsComponent::new(),
             open_orders: OpenOrdersComponent::new(),
             watchlist: WatchlistComponent::new(symbols),
+            status_bar: StatusBarComponent::new(),
             input: InputComponent::new(),
             output: OutputComponent::new(),
+            watching: None,
+            detail: None,
+            wizard: None,
+            focus: Focus::Input,
+            quick_trade: false,
             state,
             db,
             running,
@@ -106,9 +192,17 @@ impl Tui {
             message_tx,
             message_rx,
             price_refresh_running: false,
+            refresh_health: RefreshHealth::default(),
+            retry_symbols: Vec::new(),
+            fx_rates: HashMap::new(),
         }
     }
 
+    /// Sets the account name shown in the status bar
+    pub fn set_portfolio_name(&mut self, name: String) {
+        self.status_bar.set_portfolio_name(name);
+    }
+
     /// SECTION: Main Loop
 
     /// Runs the application's main event loop until user quits, async event handling for responsive input
@@ -120,9 +214,17 @@ impl Tui {
         self.refresh_all().await;
         terminal.draw(|frame| self.draw(frame))?;
 
-        // Create a 5-second interval timer for auto-refresh
-        let mut refresh_timer = interval(Duration::from_secs(5));
+        // Create the auto-refresh timer from the configured interval
+        let mut refresh_secs = lock_state(&self.state).get_refresh_secs();
+        let mut refresh_timer = interval(Duration::from_secs(refresh_secs));
         refresh_timer.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+        let autosave_period = Duration::from_secs(AUTOSAVE_SECS);
+        let mut autosave_timer = interval_at(Instant::now() + autosave_period, autosave_period);
+        autosave_timer.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+
+        // Raw mode turns Ctrl+C into a key event; this catches SIGINT sent from elsewhere
+        let interrupt = tokio::signal::ctrl_c();
+        tokio::pin!(interrupt);
 
         // Track if we need to redraw
         let mut needs_redraw = false;
@@ -134,6 +236,15 @@ impl Tui {
                 needs_redraw = false;
             }
 
+            // Rebuild the timer after `refresh <seconds>`, first tick one full period out
+            let configured = lock_state(&self.state).get_refresh_secs();
+            if configured != refresh_secs {
+                refresh_secs = configured;
+                let period = Duration::from_secs(refresh_secs);
+                refresh_timer = interval_at(Instant::now() + period, period);
+                refresh_timer.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+            }
+
             // concurrent: checking for input while refreshing,
             // do whatever comes first skip the other one as both update the ui needed as either could overrun
             tokio::select! {
@@ -144,26 +255,87 @@ impl Tui {
                             self.handle_key_event(key_event).await;
                             needs_redraw = true; // Redraw after input
                         }
-                        Ok(_) => {} // Other events (resize, etc)
+                        // Redraw at the new size now rather than on the next key or refresh
+                        Ok(Some(Event::Resize(_, _))) => needs_redraw = true,
+                        Ok(_) => {} // Other events (mouse, focus, paste)
                         Err(_) => {} // Error reading event
                     }
                 }
 
                 // TODO: refresh after executing orders
-                // Handle periodic refresh every 5 seconds
+                // Handle periodic refresh on the configured interval
                 _ = refresh_timer.tick() => {
-                    self.refresh_all().await;
-                    self.last_refresh = Instant::now();
+                    self.refresh_tick().await;
+                    needs_redraw = true; // Keep the refresh age current
+                }
+
+                // Leave the loop so the caller restores the terminal and saves
+                _ = &mut interrupt => self.exit(),
+
+                // Write background changes so a kill mid-typing doesn't lose them
+                _ = autosave_timer.tick() => {
+                    Storage::save_if_dirty(&self.state, &self.db).await;
                 }
 
                 Some(message) = self.message_rx.recv() => {
                     match message {
-                        TuiMessage::PricesUpdated { holdings, watchlist} => {
-                            self.holdings.update_prices(holdings);
-                            self.watchlist.update_prices(watchlist);
+                        TuiMessage::PricesUpdated { prices, retry } => {
+                            if retry {
+                                self.refresh_health = self.refresh_health.after_retry(&prices);
+                            } else {
+                                self.refresh_health = prices.health();
+                                self.retry_symbols = self.refresh_health.failed.clone();
+                            }
+                            self.status_bar.set_refresh_health(self.refresh_health.clone());
+                            if let (Some(quote), Some((symbol, price, prev_close))) = (self.watching.as_mut(), prices.watched)
+                                && quote.get_symbol() == &symbol
+                                && (!retry || price > Decimal::ZERO)
+                            {
+                                quote.update_quote(price, prev_close, lock_state(&self.state).now_timestamp());
+                            }
+                            if retry {
+                                // Fill in what the retry priced, keeping the rest of the last refresh
+                                self.holdings.merge_prices(prices.holdings);
+                                self.holdings.merge_previous_closes(prices.previous_closes);
+                                self.watchlist.merge_prices(prices.watchlist);
+                            } else {
+                                {
+                                    // Daily account value for `performance`
+                                    let mut state_guard = lock_state(&self.state);
+                                    if let Some(value) = state_guard.portfolio_value(&prices.holdings, &prices.fx_rates) {
+                                        state_guard.record_equity_snapshot(value);
+                                    }
+                                }
+                                self.holdings.update_prices(prices.holdings);
+                                self.holdings.update_previous_closes(prices.previous_closes);
+                                self.watchlist.update_prices(prices.watchlist);
+                                self.fx_rates = prices.fx_rates;
+                            }
                             self.price_refresh_running = false;
+                            self.last_refresh = Instant::now();
+                            self.watchlist.set_last_refresh(self.last_refresh.into_std());
+                            self.status_bar.set_last_refresh(self.last_refresh.into_std());
+                            self.update_status_bar();
                             needs_redraw = true;
                         }
+                        TuiMessage::DetailLoaded { symbol, price, previous_close, closes } => {
+                            // Ignore a late result for a pane that was closed or replaced
+                            if let Some(detail) = self.detail.as_mut()
+                                && detail.get_symbol() == &symbol
+                            {
+                                detail.update_quote(price, previous_close);
+                                detail.set_history(closes);
+                                needs_redraw = true;
+                            }
+                        }
+                        TuiMessage::WizardQuote { symbol, price } => {
+                            if let Some(wizard) = self.wizard.as_mut()
+                                && wizard.symbol() == symbol
+                            {
+                                wizard.set_quote(symbol, price);
+                                needs_redraw = true;
+                            }
+                        }
                     }
                 }
             }
@@ -188,30 +360,62 @@ impl Tui {
 
     /// SECTION: Rendering
 
-    /// Draws all UI components in their assigned areas
-    fn draw(&self, frame: &mut Frame) {
-        let areas = self.calculate_layout(frame.area()); // in case user resizes terminal window
+    /// Draws all UI components in their assigned areas, or a resize prompt when the
+    /// terminal is below `MIN_WIDTH` x `MIN_HEIGHT`
+    pub fn draw(&self, frame: &mut Frame) {
+        let area = frame.area();
+        if area.width < MIN_WIDTH || area.height < MIN_HEIGHT {
+            let message = format!(
+                "Terminal too small\nResize to at least {}x{}",
+                MIN_WIDTH, MIN_HEIGHT
+            );
+            frame.render_widget(
+                Paragraph::new(message)
+                    .alignment(Alignment::Center)
+                    .wrap(Wrap { trim: true }),
+                area,
+            );
+            return;
+        }
+        let areas = self.calculate_layout(area); // in case user resizes terminal window
 
         // Render top row (3 components horizontally)
         frame.render_widget(&self.holdings, areas.holdings);
         frame.render_widget(&self.open_orders, areas.open_orders);
         frame.render_widget(&self.watchlist, areas.watchlist);
 
-        // Render middle and bottom sections
+        // Render status bar, middle and bottom sections
+        frame.render_widget(&self.status_bar, areas.status);
         frame.render_widget(&self.input, areas.input);
-        frame.render_widget(&self.output, areas.output);
+        match &self.watching {
+            Some(quote) => frame.render_widget(quote, areas.output),
+            None => frame.render_widget(&self.output, areas.output),
+        }
+
+        // Detail pane and order wizard cover the whole top row
+        if let Some(detail) = &self.detail {
+            frame.render_widget(detail, areas.holdings.union(areas.watchlist));
+        }
+        if let Some(wizard) = &self.wizard {
+            frame.render_widget(wizard, areas.holdings.union(areas.watchlist));
+        }
+
+        // Show the terminal cursor where the next character will be inserted
+        frame.set_cursor_position(self.input.cursor_screen_position(areas.input));
     }
 
     /// Calculates the screen layout
     /// Top row: 3 horizontal components (Holdings | Open Orders | Watchlist)
+    /// Status bar: one line
     /// Middle: Input
     /// Bottom: Output
     fn calculate_layout(&self, area: Rect) -> LayoutAreas {
-        // First split vertically: top row (40%), input (20%), output (40%)
+        // First split vertically: top row (40%), status bar (1 line), input (20%), output (40%)
         let vertical_chunks = Layout::default()
             .direction(Direction::Vertical)
             .constraints([
                 Constraint::Percentage(40),
+                Constraint::Length(1),
                 Constraint::Percentage(20),
                 Constraint::Percentage(40),
             ])
@@ -231,8 +435,9 @@ impl Tui {
             holdings: top_row[0],
             open_orders: top_row[1],
             watchlist: top_row[2],
-            input: vertical_chunks[1],
-            output: vertical_chunks[2],
+            status: vertical_chunks[1],
+            input: vertical_chunks[2],
+            output: vertical_chunks[3],
         }
     }
 
@@ -240,11 +445,66 @@ impl Tui {
 
     /// Handles keyboard key press events
     async fn handle_key_event(&mut self, key_event: KeyEvent) {
+        // The wizard takes every key, so a symbol like QQQ can be typed into it
+        if self.wizard.is_some()
+            && !(key_event.code == KeyCode::Char('c')
+                && key_event
+                    .modifiers
+                    .contains(crossterm::event::KeyModifiers::CONTROL))
+        {
+            self.handle_wizard_key(key_event.code).await;
+            return;
+        }
+
         match key_event.code {
             // Global quit
             KeyCode::Char('Q') => {
                 self.exit();
             }
+            KeyCode::Char('c')
+                if key_event
+                    .modifiers
+                    .contains(crossterm::event::KeyModifiers::CONTROL) =>
+            {
+                self.exit()
+            }
+
+            // Close the detail pane first, keeping the panel focused
+            KeyCode::Esc if self.detail.is_some() => self.detail = None,
+
+            // Discard a staged quick trade without running it
+            KeyCode::Esc if self.quick_trade => {
+                self.quick_trade = false;
+                self.input.clear();
+                self.output.commit_to_history();
+                self.output.set_output("Quick trade cancelled".to_string());
+            }
+
+            // Leave watch mode, the output underneath is left as it was, and return focus to input
+            KeyCode::Esc => {
+                self.watching = None;
+                self.set_focus(Focus::Input);
+            }
+
+            // Detail for the selected row; in the input `i` is just text
+            KeyCode::Char('i') if self.focus != Focus::Input => self.open_detail(),
+
+            // Stage a market buy/sell of the selected holding; Enter places it
+            KeyCode::Char('b') if self.focus == Focus::Holdings => self.stage_quick_trade("buy"),
+            KeyCode::Char('s') if self.focus == Focus::Holdings => self.stage_quick_trade("sell"),
+
+            // Panel focus and row selection
+            KeyCode::Tab => self.set_focus(self.focus.next()),
+            KeyCode::Down => match self.focus {
+                Focus::Holdings => self.holdings.select_next(),
+                Focus::Watchlist => self.watchlist.select_next(),
+                Focus::Input => {}
+            },
+            KeyCode::Up => match self.focus {
+                Focus::Holdings => self.holdings.select_previous(),
+                Focus::Watchlist => self.watchlist.select_previous(),
+                Focus::Input => {}
+            },
 
             // Input navigation
             KeyCode::Left => self.input.move_cursor_left(),
@@ -264,8 +524,28 @@ impl Tui {
                 self.input.move_cursor_end()
             }
 
+            // Guided order entry
+            KeyCode::Char('o')
+                if key_event
+                    .modifiers
+                    .contains(crossterm::event::KeyModifiers::CONTROL) =>
+            {
+                self.open_wizard()
+            }
+
+            // Manual price refresh
+            KeyCode::Char('r')
+                if key_event
+                    .modifiers
+                    .contains(crossterm::event::KeyModifiers::CONTROL) =>
+            {
+                self.refresh_all().await
+            }
+
             // Text input
-            KeyCode::Char(c) => self.input.enter_char(c),
+            KeyCode::Char(c) => {
+                self.input.enter_char(c);
+            }
             KeyCode::Backspace => self.input.backspace(),
 
             // Command execution
@@ -293,31 +573,171 @@ impl Tui {
         }
     }
 
+    fn set_focus(&mut self, focus: Focus) {
+        self.focus = focus;
+        self.holdings.set_active(focus == Focus::Holdings);
+        self.watchlist.set_active(focus == Focus::Watchlist);
+    }
+
+    /// Opens the detail pane for the focused panel's selected symbol and loads its quote
+    fn open_detail(&mut self) {
+        let symbol = match self.focus {
+            Focus::Holdings => self.holdings.selected_symbol(),
+            Focus::Watchlist => self.watchlist.selected_symbol(),
+            Focus::Input => None,
+        };
+        let Some(symbol) = symbol.cloned() else {
+            return;
+        };
+
+        let (provider, today) = {
+            let state_guard = lock_state(&self.state);
+            self.detail = Some(DetailComponent::new(
+                symbol.clone(),
+                state_guard.get_holdings_map().get(&symbol).cloned(),
+                state_guard.get_note(&symbol).cloned(),
+                state_guard.get_money_format(),
+            ));
+            (
+                state_guard.provider(),
+                state_guard.clock().now().date_naive(),
+            )
+        };
+        let tx = self.message_tx.clone();
+        tokio::spawn(async move {
+            let (price, previous_close, closes) = tokio::join!(
+                provider.curr_price(&symbol),
+                provider.previous_close(&symbol),
+                provider.daily_closes(
+                    &symbol,
+                    today - chrono::Duration::days(DETAIL_HISTORY_DAYS),
+                    today
+                ),
+            );
+            let _ = tx.send(TuiMessage::DetailLoaded {
+                symbol,
+                price,
+                previous_close,
+                closes: closes.into_iter().map(|(_, close)| close).collect(),
+            });
+        });
+    }
+
+    /// Puts `<verb> <symbol> ` for the selected holding in the input and moves focus
+    /// there so the quantity can be typed. Nothing is traded until Enter
+    fn stage_quick_trade(&mut self, verb: &str) {
+        let Some(symbol) = self.holdings.selected_symbol().cloned() else {
+            return;
+        };
+        let held = lock_state(&self.state).get_ticker_holdings_qty(&symbol);
+
+        self.input.set_command(&format!("{} {} ", verb, symbol));
+        self.set_focus(Focus::Input);
+        self.quick_trade = true;
+        self.output.commit_to_history();
+        self.output.set_output(format!(
+            "Quick {} {} ({} held): type a quantity and press Enter to place a market order, Esc to cancel",
+            verb,
+            symbol,
+            crate::format::format_qty(held, AssetType::for_symbol(&symbol))
+        ));
+    }
+
+    /// Opens the order wizard, starting from the focused panel's selected symbol if any
+    fn open_wizard(&mut self) {
+        let selected = match self.focus {
+            Focus::Holdings => self.holdings.selected_symbol().cloned(),
+            Focus::Watchlist => self.watchlist.selected_symbol().cloned(),
+            Focus::Input => None,
+        };
+        let state_guard = lock_state(&self.state);
+        let holdings = state_guard.get_holdings_map();
+        let mut known_symbols: Vec<Symbol> = holdings.keys().cloned().collect();
+        known_symbols.sort();
+        for symbol in state_guard.get_watchlist() {
+            if !known_symbols.contains(&symbol) {
+                known_symbols.push(symbol);
+            }
+        }
+        let held = holdings
+            .iter()
+            .map(|(symbol, holding)| (symbol.clone(), holding.get_qty()))
+            .collect();
+        self.detail = None;
+        self.wizard = Some(OrderWizardComponent::new(
+            known_symbols,
+            held,
+            state_guard.check_balance(),
+            state_guard.get_money_format(),
+            selected,
+        ));
+    }
+
+    /// Passes a key to the open wizard and acts on the result
+    async fn handle_wizard_key(&mut self, code: KeyCode) {
+        let Some(wizard) = self.wizard.as_mut() else {
+            return;
+        };
+        match wizard.handle_key(code) {
+            WizardAction::Continue => {}
+            WizardAction::Cancel => self.wizard = None,
+            WizardAction::Quote(symbol) => {
+                let provider = lock_state(&self.state).provider();
+                let tx = self.message_tx.clone();
+                tokio::spawn(async move {
+                    let price = provider.curr_price(&symbol).await;
+                    let _ = tx.send(TuiMessage::WizardQuote { symbol, price });
+                });
+            }
+            WizardAction::Submit(command) => {
+                self.wizard = None;
+                self.quick_trade = false;
+                self.output.commit_to_history();
+                self.run_command(command).await;
+            }
+        }
+    }
+
     /// SECTION: Command Execution
 
     /// Executes the current command from input and displays result
     async fn execute_command(&mut self) {
         let command = self.input.get_command().to_string();
+        self.quick_trade = false;
 
         // Commit output to history
         self.output.commit_to_history();
 
         self.input.clear();
+        self.run_command(command).await;
+    }
 
+    /// Runs a command line and shows its result, whether typed or built by the wizard
+    async fn run_command(&mut self, command: String) {
         if command.eq_ignore_ascii_case("exit") || command.eq_ignore_ascii_case("quit") {
             self.exit();
             return;
         }
 
+        // Any command leaves watch mode so its result is visible
+        self.watching = None;
+        if let Some(symbol) = watch_symbol(&command) {
+            let symbol = lock_state(&self.state).normalize_symbol(&symbol);
+            self.watching = Some(QuoteComponent::new(symbol));
+        }
+
         if command.eq_ignore_ascii_case("clear") {
             self.output.clear();
+            self.output.clear_history();
             self.output.set_output("Screen cleared".to_string());
             return;
         }
 
         let result = process_command(&command, &self.state, &self.db, &self.running).await;
 
-        // Display result
+        // Display result, scrolled as configured (the command may have just changed it)
+        self.output
+            .set_scroll_after(lock_state(&self.state).get_output_scroll());
         self.output.set_output(result);
 
         // Refresh all data if command might have changed state
@@ -326,33 +746,101 @@ impl Tui {
 
     /// SECTION: Data Refresh
 
+    /// Recomputes the status bar's money fields at the holdings panel's cached prices;
+    /// the value is the account's, converted and net of any margin loan
+    fn update_status_bar(&mut self) {
+        let value = lock_state(&self.state).portfolio_value(self.holdings.prices(), &self.fx_rates);
+        self.status_bar
+            .update_account(value, self.holdings.cash(), self.holdings.day_pnl());
+    }
+
     /// Refreshes all top section components with current data
     /// Used after commands that modify state
     async fn refresh_all(&mut self) {
-        let state_guard = self.state.lock().unwrap();
+        self.sync_from_state();
+        self.start_refresh_price(None);
+    }
+
+    /// Timer refresh: when the last full refresh left symbols unpriced, only those
+    /// are fetched again, and the tick after goes back to refreshing everything
+    async fn refresh_tick(&mut self) {
+        self.sync_from_state();
+        if self.retry_symbols.is_empty() || self.price_refresh_running {
+            self.start_refresh_price(None);
+        } else {
+            let retry = std::mem::take(&mut self.retry_symbols);
+            self.start_refresh_price(Some(retry));
+        }
+    }
+
+    /// Copies holdings, orders, settings and background events from state into the panels
+    fn sync_from_state(&mut self) {
+        let mut state_guard = lock_state(&self.state);
 
         // Get all data from state
         let holdings = state_guard.get_holdings_map();
         let orders = state_guard.get_open_orders();
         let watchlist = state_guard.get_watchlist();
         let cash = state_guard.check_balance();
+        let market_status = state_guard
+            .market_calendar()
+            .status_at(state_guard.clock().now());
+        let money_format = state_guard.get_money_format();
+        let notes = state_guard.get_notes();
+        let events = state_guard.drain_events();
+        let display_timezone = state_guard.get_display_timezone();
+        let offline = state_guard.is_offline();
+        if let Some(quote) = self.watching.as_mut() {
+            let asset_type = AssetType::for_symbol(quote.get_symbol());
+            quote.set_market_status(
+                state_guard
+                    .market_calendar()
+                    .status_for(asset_type, state_guard.clock().now()),
+            );
+            quote.set_money_format(money_format.clone());
+            quote.set_display_timezone(display_timezone);
+        }
 
         // Update components
         self.holdings.update_holdings(holdings, cash);
+        self.holdings.set_market_status(market_status);
+        self.holdings.set_money_format(money_format.clone());
+        self.open_orders.set_money_format(money_format.clone());
+        self.open_orders.set_fee_model(state_guard.get_fee_model());
+        self.input.set_max_len(state_guard.get_max_command_len());
+        self.status_bar.set_money_format(money_format.clone());
+        self.watchlist.set_money_format(money_format);
+        self.watchlist
+            .set_refresh_interval(state_guard.get_refresh_secs());
         self.open_orders.update_orders(orders);
         self.watchlist.update_symbols(watchlist);
+        self.holdings.set_notes(notes.clone());
+        self.watchlist.set_notes(notes);
+        self.holdings.set_offline(offline);
+        self.watchlist.set_offline(offline);
+        self.status_bar.set_offline(offline);
+        self.status_bar
+            .set_throttled(state_guard.throttle().delayed_recently());
 
         // Release lock before async operations
         drop(state_guard);
-
-        // Fetch prices for holdings and watchlist in the background.
-        self.start_refresh_price();
+        self.update_status_bar();
+
+        // Show what the background monitor did since the last refresh
+        if !events.is_empty() {
+            self.output.append_output("");
+            self.output
+                .append_output(&format_events(&events, display_timezone));
+            self.output.scroll_to_bottom();
+        }
     }
 
     /// Spawns a background task to fetch holdings/watchlist prices without blocking the UI loop.
     /// Sends a `TuiMessage::PricesUpdated` through `message_tx` when the refresh completes.
-    fn start_refresh_price(&mut self) {
-        if self.price_refresh_running {
+    /// `only` limits the fetch to those symbols, for a retry of the ones that failed.
+    /// Offline, nothing is fetched and the cached prices stay on screen
+    fn start_refresh_price(&mut self, only: Option<Vec<Symbol>>) {
+        if self.price_refresh_running || lock_state(&self.state).is_offline() {
             return;
         }
 
@@ -360,32 +848,48 @@ impl Tui {
 
         // Fetch prices for holdings and watchlist in parallel
         let tx = self.message_tx.clone(); // cloned due to move block, which takes ownership of variables
-        let holdings_symbols = self.holdings.get_holdings();
-        let watchlist_symbols = self.watchlist.get_symbols();
+        let wanted = |symbol: &Symbol| only.as_ref().is_none_or(|only| only.contains(symbol));
+        let holdings_symbols: Vec<Symbol> = self
+            .holdings
+            .get_holdings()
+            .into_iter()
+            .filter(|symbol| wanted(symbol))
+            .collect();
+        let watchlist_symbols: Vec<Symbol> = self
+            .watchlist
+            .get_symbols()
+            .into_iter()
+            .filter(|symbol| wanted(symbol))
+            .collect();
+        let watched_symbol = self
+            .watching
+            .as_ref()
+            .map(|quote| quote.get_symbol().clone())
+            .filter(|symbol| wanted(symbol));
+        let retry = only.is_some();
+        let (provider, currencies) = {
+            let state_guard = lock_state(&self.state);
+            // A retry doesn't value the account, so it needs no exchange rates
+            let currencies = if retry {
+                Vec::new()
+            } else {
+                state_guard.valued_currencies()
+            };
+            (state_guard.provider(), currencies)
+        };
         tokio::spawn(async move {
-            let message = Self::refresh_prices(holdings_symbols, watchlist_symbols).await;
-            let _ = tx.send(message);
+            let mut prices = refresh::fetch_prices(
+                provider.clone(),
+                holdings_symbols,
+                watchlist_symbols,
+                watched_symbol,
+            )
+            .await;
+            prices.fx_rates = fx::rates_to_base(provider.as_ref(), &currencies).await;
+            let _ = tx.send(TuiMessage::PricesUpdated { prices, retry });
         });
     }
-    async fn refresh_prices(
-        holding_symbols: Vec<Symbol>,
-        watchlist_symbols: Vec<Symbol>,
-    ) -> TuiMessage {
-        let mut holdings_map: HashMap<Symbol, Decimal> = HashMap::new();
-        for symbol in holding_symbols {
-            let price = FinanceProvider::curr_price(&symbol, false).await;
-            holdings_map.insert(symbol, price);
-        }
-        let mut watchlist_map: HashMap<Symbol, Decimal> = HashMap::new();
-        for symbol in watchlist_symbols {
-            let price = FinanceProvider::curr_price(&symbol, false).await;
-            watchlist_map.insert(symbol, price);
-        }
-        TuiMessage::PricesUpdated {
-            holdings: holdings_map,
-            watchlist: watchlist_map,
-        }
-    }
+
     /// SECTION: Application Control
 
     /// Signals the application to exit
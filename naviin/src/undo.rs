@@ -0,0 +1,312 @@
+/// Undo Module
+///
+/// Inverse operations recorded as state-mutating commands execute.
+/// `undo` pops the most recent entry and reverses it against the current state,
+/// refusing when later activity makes the reversal impossible (e.g. the shares
+/// bought have since been sold). The stack lives for the session only.
+use rust_decimal::Decimal;
+
+use crate::AppState::AppState;
+use crate::Finance::{self, AssetType, Holding, QTY_DUST, Symbol};
+use crate::Orders::{OpenOrder, Side, Trade};
+use crate::cashflow::CashFlowKind;
+use crate::format::{MoneyFormat, format_qty};
+use crate::fx;
+
+/// Most undo entries kept; older ones are dropped first
+pub const UNDO_LIMIT: usize = 20;
+
+/// How to reverse one command
+#[derive(Clone, Debug)]
+pub enum UndoAction {
+    /// Reverse a deposit by withdrawing it again; `value` is its base value in the ledger
+    Fund {
+        amount: Decimal,
+        currency: String,
+        value: Decimal,
+    },
+    /// Reverse a withdrawal by depositing it again; `value` is its base value in the ledger
+    Withdraw {
+        amount: Decimal,
+        currency: String,
+        value: Decimal,
+    },
+    /// Reverse a market buy: refund the cost and fee and remove the shares
+    Buy {
+        symbol: Symbol,
+        quantity: Decimal,
+        price: Decimal,
+    },
+    /// Reverse a market sell: debit the proceeds net of the fee and restore the shares
+    /// at their old cost
+    Sell {
+        symbol: Symbol,
+        quantity: Decimal,
+        price: Decimal,
+        avg_cost: Decimal,
+    },
+    /// Cancel an order that was placed, if it hasn't filled yet
+    PlaceOrder { order: OpenOrder },
+    /// Put a modified order's price and quantity back, if it hasn't filled yet
+    ModifyOrder { before: OpenOrder, after: OpenOrder },
+}
+
+impl UndoAction {
+    /// Short description of the command being undone
+    pub fn describe(&self, money: &MoneyFormat) -> String {
+        match self {
+            UndoAction::Fund {
+                amount, currency, ..
+            } => {
+                format!("fund {}", fx::money_in(money, currency, *amount))
+            }
+            UndoAction::Withdraw {
+                amount, currency, ..
+            } => {
+                format!("withdraw {}", fx::money_in(money, currency, *amount))
+            }
+            UndoAction::Buy {
+                symbol, quantity, ..
+            } => format!(
+                "buy {} {}",
+                symbol,
+                format_qty(*quantity, AssetType::for_symbol(symbol))
+            ),
+            UndoAction::Sell {
+                symbol, quantity, ..
+            } => format!(
+                "sell {} {}",
+                symbol,
+                format_qty(*quantity, AssetType::for_symbol(symbol))
+            ),
+            UndoAction::PlaceOrder { order } => format!(
+                "{:?} {} {} @ {}",
+                order.get_order_type(),
+                order.get_symbol(),
+                format_qty(order.get_qty(), AssetType::for_symbol(order.get_symbol())),
+                money.money(order.get_price_per())
+            ),
+            UndoAction::ModifyOrder { before, .. } => format!(
+                "modify {:?} {}",
+                before.get_order_type(),
+                before.get_symbol()
+            ),
+        }
+    }
+
+    /// Applies the inverse operation to state
+    /// Returns Err without changing anything when the reversal isn't possible
+    pub fn revert(&self, state: &mut AppState) -> Result<String, String> {
+        let money = state.get_money_format();
+        match self {
+            UndoAction::Fund {
+                amount,
+                currency,
+                value,
+            } => {
+                let balance = state.currency_balance(currency);
+                if *amount > balance {
+                    return Err(format!(
+                        "Can't undo fund: only {} cash left",
+                        fx::money_in(&money, currency, balance)
+                    ));
+                }
+                state.withdraw_currency(currency, *amount);
+                state.remove_last_cash_flow(CashFlowKind::Deposit, currency, *value);
+                Ok(format!(
+                    "Removed {} deposit",
+                    fx::money_in(&money, currency, *amount)
+                ))
+            }
+            UndoAction::Withdraw {
+                amount,
+                currency,
+                value,
+            } => {
+                state.deposit_currency(currency, *amount);
+                state.remove_last_cash_flow(CashFlowKind::Withdrawal, currency, *value);
+                Ok(format!(
+                    "Returned {} to cash",
+                    fx::money_in(&money, currency, *amount)
+                ))
+            }
+            UndoAction::Buy {
+                symbol,
+                quantity,
+                price,
+            } => revert_buy(state, &money, symbol, *quantity, *price),
+            UndoAction::Sell {
+                symbol,
+                quantity,
+                price,
+                avg_cost,
+            } => revert_sell(state, &money, symbol, *quantity, *price, *avg_cost),
+            UndoAction::PlaceOrder { order } => {
+                if find_open_order(state, order).is_none() {
+                    return Err(format!(
+                        "Can't undo order for {}: it has already filled",
+                        order.get_symbol()
+                    ));
+                }
+                state.remove_from_open_orders(order.clone());
+                Ok(format!(
+                    "Cancelled {:?} order for {}",
+                    order.get_order_type(),
+                    order.get_symbol()
+                ))
+            }
+            UndoAction::ModifyOrder { before, after } => {
+                let Some(index) = find_open_order(state, after) else {
+                    return Err(format!(
+                        "Can't undo modify for {}: the order has filled or changed",
+                        after.get_symbol()
+                    ));
+                };
+                state
+                    .modify_open_order(index, Some(before.get_price_per()), Some(before.get_qty()))
+                    .map_err(|e| format!("Can't undo modify: {}", e))?;
+                Ok(format!(
+                    "Restored {:?} order for {} to {} @ {}",
+                    before.get_order_type(),
+                    before.get_symbol(),
+                    before.get_qty(),
+                    money.money(before.get_price_per())
+                ))
+            }
+        }
+    }
+}
+
+// Position of this exact order on the book, None once it has filled or been changed
+fn find_open_order(state: &AppState, order: &OpenOrder) -> Option<usize> {
+    state.get_open_orders().iter().position(|open| {
+        open.get_symbol() == order.get_symbol()
+            && open.get_qty() == order.get_qty()
+            && open.get_price_per() == order.get_price_per()
+            && open.get_timestamp() == order.get_timestamp()
+    })
+}
+
+// Remove the bought shares and back their cost out of the average
+fn revert_buy(
+    state: &mut AppState,
+    money: &MoneyFormat,
+    symbol: &Symbol,
+    quantity: Decimal,
+    price: Decimal,
+) -> Result<String, String> {
+    let held = state.get_ticker_holdings_qty(symbol);
+    if held + QTY_DUST < quantity {
+        return Err(format!(
+            "Can't undo buy: only {} shares of {} still held",
+            format_qty(held, AssetType::for_symbol(symbol)),
+            symbol
+        ));
+    }
+
+    let mut holdings = state.get_holdings_map();
+    let remaining = held - quantity;
+    if remaining <= QTY_DUST {
+        holdings.remove(symbol);
+    } else if let Some(holding) = holdings.get(symbol) {
+        // Exact inverse of the weighted average in add_to_holdings
+        let avg_cost = (held * holding.get_avg_price() - quantity * price) / remaining;
+        holdings.insert(
+            symbol.clone(),
+            Holding::new(symbol.clone(), remaining, avg_cost),
+        );
+    }
+    state.set_holdings_map(holdings);
+    let (currency, fee) = fill_settlement(state, symbol, &Side::Buy, quantity, price);
+    let refund = quantity * price + fee;
+    state.receive_in(&currency, refund);
+    remove_trade(state, symbol, &Side::Buy, quantity, price);
+
+    Ok(format!(
+        "Reversed buy of {} {}: refunded {}",
+        quantity,
+        symbol,
+        fx::money_in(money, &currency, refund)
+    ))
+}
+
+// Debit the proceeds and put the shares back at the cost they had before the sale
+fn revert_sell(
+    state: &mut AppState,
+    money: &MoneyFormat,
+    symbol: &Symbol,
+    quantity: Decimal,
+    price: Decimal,
+    avg_cost: Decimal,
+) -> Result<String, String> {
+    let (currency, fee) = fill_settlement(state, symbol, &Side::Sell, quantity, price);
+    let proceeds = quantity * price - fee;
+    let balance = state.currency_balance(&currency);
+    if proceeds > balance {
+        return Err(format!(
+            "Can't undo sell: need {} cash, have {}",
+            fx::money_in(money, &currency, proceeds),
+            fx::money_in(money, &currency, balance)
+        ));
+    }
+
+    state.pay_in(&currency, proceeds);
+    Finance::add_to_holdings(symbol, quantity, avg_cost, state);
+    remove_trade(state, symbol, &Side::Sell, quantity, price);
+
+    Ok(format!(
+        "Reversed sell of {} {}: debited {}",
+        quantity,
+        symbol,
+        fx::money_in(money, &currency, proceeds)
+    ))
+}
+
+// Position of the most recent trade record matching the undone fill
+fn find_trade(
+    trades: &[Trade],
+    symbol: &Symbol,
+    side: &Side,
+    quantity: Decimal,
+    price: Decimal,
+) -> Option<usize> {
+    trades.iter().rposition(|trade| {
+        trade.get_symbol() == symbol
+            && trade.get_side() == side
+            && trade.get_quantity() == quantity
+            && trade.get_price_per() == price
+    })
+}
+
+// Currency the undone fill settled in and the fee it paid, as its trade recorded them
+fn fill_settlement(
+    state: &AppState,
+    symbol: &Symbol,
+    side: &Side,
+    quantity: Decimal,
+    price: Decimal,
+) -> (String, Decimal) {
+    let trades = state.get_trades();
+    match find_trade(&trades, symbol, side, quantity, price) {
+        Some(index) => (
+            state.trade_currency(&trades[index]),
+            trades[index].get_fee(),
+        ),
+        None => (state.settlement_currency(symbol), Decimal::ZERO),
+    }
+}
+
+// Drop the most recent trade record matching the undone fill
+fn remove_trade(
+    state: &mut AppState,
+    symbol: &Symbol,
+    side: &Side,
+    quantity: Decimal,
+    price: Decimal,
+) {
+    let mut trades: Vec<Trade> = state.get_trades();
+    if let Some(index) = find_trade(&trades, symbol, side, quantity, price) {
+        trades.remove(index);
+        state.set_trades(trades);
+    }
+}
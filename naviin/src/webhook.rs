@@ -0,0 +1,99 @@
+/// Webhook Module
+///
+/// Posts a JSON notice to a user-configured URL whenever the background monitor
+/// fills an open order. Delivery is fire-and-forget on its own task with a short
+/// timeout, so a slow or dead endpoint never holds up order monitoring. The last
+/// failure is kept on the state and shown by the `webhook` command.
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use rust_decimal::Decimal;
+use serde_json::{Value, json};
+use tokio::task::JoinHandle;
+
+use crate::AppState::{AppState, lock_state};
+use crate::Finance::Symbol;
+use crate::Orders::{OpenOrder, OrderType, Side};
+
+/// How long a delivery may take before it is abandoned
+pub const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// One executed open order, as sent to the webhook
+#[derive(Clone, Debug)]
+pub struct OrderFill {
+    pub order_type: OrderType,
+    pub side: Side,
+    pub symbol: Symbol,
+    pub quantity: Decimal,
+    pub fill_price: Decimal,
+    pub timestamp: i64,
+}
+
+impl OrderFill {
+    pub fn new(order: &OpenOrder, fill_price: Decimal, timestamp: i64) -> Self {
+        Self {
+            order_type: order.get_order_type(),
+            side: order.get_side(),
+            symbol: order.get_symbol().clone(),
+            quantity: order.get_qty(),
+            fill_price,
+            timestamp,
+        }
+    }
+
+    /// Payload body; decimals are strings so no precision is lost
+    pub fn to_json(&self) -> Value {
+        json!({
+            "event": "order_filled",
+            "order_type": format!("{:?}", self.order_type),
+            "side": format!("{:?}", self.side),
+            "symbol": self.symbol,
+            "quantity": self.quantity,
+            "fill_price": self.fill_price,
+            "timestamp": self.timestamp,
+        })
+    }
+}
+
+/// Whether a URL looks deliverable: http or https with a host
+pub fn is_valid_url(url: &str) -> bool {
+    ["http://", "https://"]
+        .iter()
+        .filter_map(|scheme| url.strip_prefix(scheme))
+        .any(|rest| {
+            !rest.is_empty() && !rest.starts_with('/') && !rest.contains(char::is_whitespace)
+        })
+}
+
+/// Sends the fill to `url` in the background
+/// The outcome is recorded on the state; errors are never propagated
+pub fn notify(url: String, fill: OrderFill, state: Arc<Mutex<AppState>>) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let result = deliver(&url, &fill).await;
+        lock_state(&state).record_webhook_result(result.err());
+    })
+}
+
+async fn deliver(url: &str, fill: &OrderFill) -> Result<(), String> {
+    let client = reqwest::Client::builder()
+        .timeout(WEBHOOK_TIMEOUT)
+        .build()
+        .map_err(|e| e.to_string())?;
+    let response = client
+        .post(url)
+        .json(&fill.to_json())
+        .send()
+        .await
+        .map_err(|e| {
+            if e.is_timeout() {
+                format!("timed out after {}s", WEBHOOK_TIMEOUT.as_secs())
+            } else {
+                e.to_string()
+            }
+        })?;
+    if response.status().is_success() {
+        Ok(())
+    } else {
+        Err(format!("endpoint returned {}", response.status()))
+    }
+}
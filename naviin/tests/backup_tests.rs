@@ -0,0 +1,328 @@
+use chrono::Duration;
+use naviin::AppState::{AppState, BACKUP_INTERVAL_SECS};
+use naviin::FinanceProvider::MockProvider;
+use naviin::Storage;
+use naviin::clock::MockClock;
+use naviin::commands::process_command;
+use naviin::events::EventKind;
+use rust_decimal::Decimal;
+use sea_orm::ConnectionTrait;
+use std::path::PathBuf;
+use std::sync::atomic::AtomicBool;
+use std::sync::{Arc, Mutex};
+
+// Fresh directory under the system temp dir for one test's database files
+fn scratch_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("naviin_{}_{}", name, std::process::id()));
+    std::fs::remove_dir_all(&dir).ok();
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn url_for(path: &std::path::Path) -> String {
+    format!("sqlite://{}?mode=rwc", path.display())
+}
+
+// ===== Backup Tests =====
+
+#[test]
+fn test_sqlite_path_and_backup_path() {
+    assert_eq!(
+        Storage::sqlite_path("sqlite:///data/naviin.sqlite?mode=rwc"),
+        Some(PathBuf::from("/data/naviin.sqlite"))
+    );
+    assert_eq!(
+        Storage::sqlite_path("sqlite://db.sqlite"),
+        Some(PathBuf::from("db.sqlite"))
+    );
+    assert_eq!(Storage::sqlite_path("sqlite::memory:"), None);
+    assert_eq!(Storage::sqlite_path("postgres://localhost/naviin"), None);
+    assert_eq!(
+        Storage::backup_path(&PathBuf::from("/data/naviin.sqlite")),
+        PathBuf::from("/data/naviin.sqlite.bak")
+    );
+}
+
+#[tokio::test]
+async fn test_save_keeps_backup_of_last_good_state() {
+    let dir = scratch_dir("backup");
+    let path = dir.join("naviin.sqlite");
+
+    let (db, state) = Storage::open(&url_for(&path)).await.unwrap();
+    assert_eq!(state.get_backup_path(), Some(dir.join("naviin.sqlite.bak")));
+    let state = Arc::new(Mutex::new(state));
+    state.lock().unwrap().deposit(Decimal::from(750));
+    Storage::save_state(&state, &db).await;
+    db.close().await.ok();
+
+    // The backup is a complete database on its own
+    let (_, restored) = Storage::open(&url_for(&dir.join("naviin.sqlite.bak")))
+        .await
+        .unwrap();
+    assert_eq!(restored.check_balance(), Decimal::from(750));
+    assert!(!dir.join("naviin.sqlite.bak.tmp").exists());
+    std::fs::remove_dir_all(dir).ok();
+}
+
+// Balance saved in the backup of `dir`'s database, read from a copy so the backup
+// itself is left as it was
+async fn backup_balance(dir: &std::path::Path) -> Decimal {
+    let copy = dir.join("backup_copy.sqlite");
+    std::fs::copy(dir.join("naviin.sqlite.bak"), &copy).unwrap();
+    let (db, state) = Storage::open(&url_for(&copy)).await.unwrap();
+    db.close().await.ok();
+    std::fs::remove_file(copy).ok();
+    state.check_balance()
+}
+
+#[tokio::test]
+async fn test_backup_is_refreshed_at_most_once_per_interval() {
+    let dir = scratch_dir("throttle");
+    let path = dir.join("naviin.sqlite");
+    let (db, mut state) = Storage::open(&url_for(&path)).await.unwrap();
+    let clock = Arc::new(MockClock::at_timestamp(1_700_000_000));
+    state.set_clock(clock.clone());
+    let state = Arc::new(Mutex::new(state));
+
+    state.lock().unwrap().deposit(Decimal::from(100));
+    Storage::save_state(&state, &db).await;
+    assert_eq!(backup_balance(&dir).await, Decimal::from(100));
+
+    // A save soon after keeps the database current but leaves the backup alone
+    clock.advance(Duration::seconds(BACKUP_INTERVAL_SECS - 1));
+    state.lock().unwrap().deposit(Decimal::from(50));
+    Storage::save_state(&state, &db).await;
+    assert_eq!(backup_balance(&dir).await, Decimal::from(100));
+
+    clock.advance(Duration::seconds(1));
+    Storage::save_state(&state, &db).await;
+    assert_eq!(backup_balance(&dir).await, Decimal::from(150));
+    db.close().await.ok();
+    std::fs::remove_dir_all(dir).ok();
+}
+
+// ===== Recovery Tests =====
+
+#[tokio::test]
+async fn test_corrupted_database_recovers_from_backup() {
+    let dir = scratch_dir("recover");
+    let path = dir.join("naviin.sqlite");
+    {
+        let (db, state) = Storage::open(&url_for(&path)).await.unwrap();
+        let state = Arc::new(Mutex::new(state));
+        state.lock().unwrap().deposit(Decimal::from(2500));
+        Storage::save_state(&state, &db).await;
+        db.close().await.ok();
+    }
+    // Simulate a crash that left garbage in the primary file
+    std::fs::write(&path, b"not a database, just the first few bytes of one").unwrap();
+
+    let (_db, mut state) = Storage::open_or_recover(&url_for(&path)).await.unwrap();
+
+    assert_eq!(state.check_balance(), Decimal::from(2500));
+    let events = state.drain_events();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].kind, EventKind::Error);
+    assert!(events[0].message.contains("restored the last backup"));
+    assert!(dir.join("naviin.sqlite.corrupt").exists());
+    std::fs::remove_dir_all(dir).ok();
+}
+
+#[tokio::test]
+async fn test_corrupted_database_without_backup_is_an_error() {
+    let dir = scratch_dir("nobackup");
+    let path = dir.join("naviin.sqlite");
+    std::fs::write(&path, b"garbage").unwrap();
+
+    assert!(Storage::open_or_recover(&url_for(&path)).await.is_err());
+    // Nothing is moved aside when there is no backup to restore
+    assert!(path.exists());
+    std::fs::remove_dir_all(dir).ok();
+}
+
+#[tokio::test]
+async fn test_readable_database_that_fails_to_open_is_not_replaced() {
+    let dir = scratch_dir("migrationfail");
+    let path = dir.join("naviin.sqlite");
+    {
+        let (db, state) = Storage::open(&url_for(&path)).await.unwrap();
+        let state = Arc::new(Mutex::new(state));
+        state.lock().unwrap().deposit(Decimal::from(900));
+        Storage::save_state(&state, &db).await;
+        // A migration this build doesn't know, as if a newer Naviin had opened it
+        db.execute_unprepared(
+            "INSERT INTO seaql_migrations (version, applied_at) VALUES ('m9999_from_the_future', 0)",
+        )
+        .await
+        .unwrap();
+        db.close().await.ok();
+    }
+    assert!(dir.join("naviin.sqlite.bak").exists());
+
+    assert!(Storage::open_or_recover(&url_for(&path)).await.is_err());
+    // The file is healthy, so it stays in place rather than being swapped for the backup
+    assert!(!dir.join("naviin.sqlite.corrupt").exists());
+    std::fs::remove_dir_all(dir).ok();
+}
+
+// ===== Account Backup Tests =====
+
+// Everything `backup` is meant to carry, in a comparable form
+fn account_snapshot(state: &Arc<Mutex<AppState>>) -> String {
+    let guard = state.lock().unwrap();
+    let mut holdings: Vec<_> = guard
+        .get_holdings_map()
+        .into_iter()
+        .map(|(symbol, h)| (symbol, h.get_qty(), h.get_avg_price()))
+        .collect();
+    holdings.sort();
+    let trades: Vec<_> = guard
+        .get_trades()
+        .iter()
+        .map(|t| {
+            (
+                t.get_symbol().clone(),
+                t.get_quantity(),
+                t.get_price_per(),
+                t.get_timestamp(),
+            )
+        })
+        .collect();
+    let orders: Vec<_> = guard
+        .get_open_orders()
+        .iter()
+        .map(|o| (o.get_symbol().clone(), o.get_qty(), o.get_price_per()))
+        .collect();
+    // Accrual clocks restart at load, like any other start
+    let mut settings: Vec<_> = guard
+        .get_settings()
+        .into_iter()
+        .filter(|(key, _)| !key.ends_with("_accrued_at"))
+        .collect();
+    settings.sort();
+    format!(
+        "{} {:?} {:?} {:?} {:?} {:?} {:?} {:?} {}",
+        guard.check_balance(),
+        holdings,
+        trades,
+        orders,
+        guard.get_watchlist(),
+        guard.get_notes(),
+        guard.get_templates(),
+        settings,
+        guard.get_cash_flows().len()
+    )
+}
+
+#[tokio::test]
+async fn test_backup_restores_every_part_of_the_account() {
+    let dir = scratch_dir("account_backup");
+    let archive = dir.join("account.sqlite");
+    let provider = Arc::new(MockProvider::new());
+    provider.set_price("AAPL", Decimal::from(100));
+    let running = Arc::new(AtomicBool::new(false));
+
+    let (db, state) = Storage::open(&url_for(&dir.join("laptop.sqlite")))
+        .await
+        .unwrap();
+    let state = Arc::new(Mutex::new(state));
+    state.lock().unwrap().set_provider(provider.clone());
+    for command in [
+        "fund 5000",
+        "buy AAPL 3",
+        "buylimit AAPL 2 90",
+        "addwatch MSFT --force",
+        "note AAPL long term",
+        "template save bracket buy 10; stoploss 10 -5%",
+        "slippage 10",
+        "risklimit position 40",
+    ] {
+        process_command(command, &state, &db, &running).await;
+    }
+    let result = process_command(
+        &format!("backup {}", archive.display()),
+        &state,
+        &db,
+        &running,
+    )
+    .await;
+    assert_eq!(
+        result,
+        format!("Account backed up to {}", archive.display())
+    );
+
+    // A fresh install elsewhere, with its own account to replace
+    let (other_db, other) = Storage::open(&url_for(&dir.join("desktop.sqlite")))
+        .await
+        .unwrap();
+    let other = Arc::new(Mutex::new(other));
+    other.lock().unwrap().set_provider(provider);
+    process_command("fund 10", &other, &other_db, &running).await;
+
+    let preview = process_command(
+        &format!("restore {}", archive.display()),
+        &other,
+        &other_db,
+        &running,
+    )
+    .await;
+    assert!(
+        preview.contains("1 holdings, 1 trades, 1 open orders"),
+        "{}",
+        preview
+    );
+    assert!(preview.contains("confirm"), "{}", preview);
+    assert_eq!(other.lock().unwrap().check_balance(), Decimal::from(10));
+
+    let result = process_command(
+        &format!("restore {} confirm", archive.display()),
+        &other,
+        &other_db,
+        &running,
+    )
+    .await;
+    assert_eq!(
+        result,
+        format!("Account restored from {}", archive.display())
+    );
+    assert_eq!(account_snapshot(&other), account_snapshot(&state));
+
+    // The restored account is what the next start loads
+    let reloaded = Storage::load_state(&other_db).await;
+    assert_eq!(account_snapshot(&reloaded), account_snapshot(&state));
+    std::fs::remove_dir_all(dir).ok();
+}
+
+#[tokio::test]
+async fn test_restore_refuses_files_that_are_not_backups() {
+    let dir = scratch_dir("bad_restore");
+    let (db, state) = Storage::open("sqlite::memory:").await.unwrap();
+    let state = Arc::new(Mutex::new(state));
+    let running = Arc::new(AtomicBool::new(false));
+
+    let text = dir.join("notes.txt");
+    std::fs::write(&text, "not a backup").unwrap();
+    let result = process_command(
+        &format!("restore {} confirm", text.display()),
+        &state,
+        &db,
+        &running,
+    )
+    .await;
+    assert_eq!(result, format!("{} is not a Naviin backup", text.display()));
+
+    // A database with no account in it
+    let (empty_db, _) = Storage::open(&url_for(&dir.join("empty.sqlite")))
+        .await
+        .unwrap();
+    empty_db.close().await.ok();
+    let result = process_command(
+        &format!("restore {}", dir.join("empty.sqlite").display()),
+        &state,
+        &db,
+        &running,
+    )
+    .await;
+    assert!(result.ends_with("holds no account"), "{}", result);
+    std::fs::remove_dir_all(dir).ok();
+}
@@ -0,0 +1,218 @@
+mod common;
+
+use naviin::AppState::AppState;
+use naviin::Storage;
+use naviin::cashflow::{CashFlow, CashFlowKind, net_deposited, to_csv};
+use naviin::clock::MockClock;
+use naviin::commands::process_command;
+use naviin::entities::cash_flow;
+use naviin::import::import_cash_flows_from_csv;
+use rust_decimal::Decimal;
+use sea_orm::{DatabaseConnection, EntityTrait};
+use std::sync::atomic::AtomicBool;
+use std::sync::{Arc, Mutex};
+
+const START: i64 = 1_700_000_000;
+
+// Empty account whose clock is frozen at START
+async fn setup() -> (Arc<Mutex<AppState>>, DatabaseConnection, Arc<AtomicBool>) {
+    common::setup(None, Some(Arc::new(MockClock::at_timestamp(START)))).await
+}
+
+// ===== Ledger Tests =====
+
+#[tokio::test]
+async fn test_fund_and_withdraw_build_net_deposited() {
+    let (state, db, running) = setup().await;
+    for command in [
+        "fund 1000",
+        "fund 500",
+        "withdraw 200",
+        "fund 50",
+        "withdraw 350",
+    ] {
+        process_command(command, &state, &db, &running).await;
+    }
+
+    let flows = state.lock().unwrap().get_cash_flows();
+    assert_eq!(flows.len(), 5);
+    assert_eq!(flows[2].kind, CashFlowKind::Withdrawal);
+    assert_eq!(flows[2].timestamp, START);
+    assert_eq!(net_deposited(&flows), Decimal::from(1000));
+
+    let report = process_command("cashflow", &state, &db, &running).await;
+    assert!(report.ends_with("Net deposited: $1,000.00 (3 deposits, 2 withdrawals)"));
+    assert!(report.contains("Withdrawal       -$200.00      $1,300.00"));
+}
+
+#[tokio::test]
+async fn test_rejected_withdraw_and_undo_leave_no_entry() {
+    let (state, db, running) = setup().await;
+    process_command("fund 100", &state, &db, &running).await;
+    process_command("withdraw 500", &state, &db, &running).await;
+    process_command("fund 40", &state, &db, &running).await;
+    process_command("undo", &state, &db, &running).await;
+
+    let flows = state.lock().unwrap().get_cash_flows();
+    assert_eq!(flows.len(), 1);
+    assert_eq!(flows[0].amount, Decimal::from(100));
+}
+
+// ===== Persistence Tests =====
+
+#[tokio::test]
+async fn test_cash_flows_saved_to_database() {
+    let (state, db, running) = setup().await;
+    process_command("fund 300", &state, &db, &running).await;
+    process_command("withdraw 120", &state, &db, &running).await;
+
+    Storage::save_state(&state, &db).await;
+    let rows = cash_flow::Entity::find().all(&db).await.unwrap();
+
+    assert_eq!(rows.len(), 2);
+    assert_eq!(rows[1].kind, "Withdrawal");
+    assert_eq!(rows[1].amount.0, Decimal::from(120));
+}
+
+#[tokio::test]
+async fn test_export_then_import_replays_ledger() {
+    let (state, db, running) = setup().await;
+    process_command("fund 800", &state, &db, &running).await;
+    process_command("withdraw 300", &state, &db, &running).await;
+    let path = std::env::temp_dir().join(format!("naviin_cashflow_{}.csv", std::process::id()));
+    let path = path.to_str().unwrap();
+
+    let exported = process_command(&format!("cashflow export {path}"), &state, &db, &running).await;
+    assert!(exported.starts_with("Exported 2 cash flows"));
+
+    let (restored, db2, running2) = setup().await;
+    let imported = process_command(
+        &format!("cashflow import {path}"),
+        &restored,
+        &db2,
+        &running2,
+    )
+    .await;
+    std::fs::remove_file(path).ok();
+
+    assert_eq!(imported, "Imported 2 cash flows.");
+    let guard = restored.lock().unwrap();
+    assert_eq!(guard.check_balance(), Decimal::from(500));
+    assert_eq!(
+        guard.get_cash_flows(),
+        state.lock().unwrap().get_cash_flows()
+    );
+}
+
+#[tokio::test]
+async fn test_export_writes_tiny_and_large_amounts_that_import_exactly() {
+    let tiny = "0.000001".parse::<Decimal>().unwrap();
+    let large = "12345678901234.567890120".parse::<Decimal>().unwrap();
+    let flows = vec![
+        CashFlow::new(CashFlowKind::Deposit, tiny, START),
+        CashFlow::new(CashFlowKind::Deposit, large, START + 60),
+    ];
+    let csv = to_csv(&flows);
+    assert!(csv.contains(",Deposit,0.000001,USD\n"));
+    assert!(csv.contains(",Deposit,12345678901234.56789012,USD\n"));
+    assert!(
+        csv.lines()
+            .skip(1)
+            .all(|line| !line.split(',').nth(2).unwrap().contains('e'))
+    );
+
+    let path =
+        std::env::temp_dir().join(format!("naviin_cashflow_exact_{}.csv", std::process::id()));
+    std::fs::write(&path, csv).unwrap();
+    let (restored, _db, _running) = setup().await;
+    let imported = import_cash_flows_from_csv(&restored, path.to_str().unwrap());
+    std::fs::remove_file(&path).ok();
+
+    assert_eq!(imported.unwrap(), "Imported 2 cash flows.");
+    let amounts: Vec<Decimal> = restored
+        .lock()
+        .unwrap()
+        .get_cash_flows()
+        .iter()
+        .map(|flow| flow.amount)
+        .collect();
+    assert_eq!(amounts, vec![tiny, large]);
+}
+
+#[tokio::test]
+async fn test_import_skips_flows_in_another_currency() {
+    let flows = vec![
+        CashFlow::new(CashFlowKind::Deposit, Decimal::from(1000), START),
+        CashFlow::new(CashFlowKind::Deposit, Decimal::from(550), START + 60)
+            .with_currency(Some("EUR".to_string())),
+    ];
+    let csv = to_csv(&flows);
+    assert!(csv.ends_with(",Deposit,1000,USD\n2023-11-14T22:14:20+00:00,Deposit,550,EUR\n"));
+
+    let path = std::env::temp_dir().join(format!("naviin_cashflow_fx_{}.csv", std::process::id()));
+    std::fs::write(&path, csv).unwrap();
+    let (restored, _db, _running) = setup().await;
+    let imported = import_cash_flows_from_csv(&restored, path.to_str().unwrap());
+    std::fs::remove_file(&path).ok();
+
+    assert_eq!(
+        imported.unwrap(),
+        "Imported 1 cash flows. 1 errors. Example: Line 3: EUR flows can't be imported; \
+         fund them with 'fund <amount> EUR'"
+    );
+    let guard = restored.lock().unwrap();
+    assert_eq!(guard.check_balance(), Decimal::from(1000));
+    assert_eq!(guard.currency_balance("EUR"), Decimal::ZERO);
+    assert_eq!(guard.get_cash_flows().len(), 1);
+}
+
+// ===== Deposit Cap Tests =====
+
+#[tokio::test]
+async fn test_fund_up_to_cap_then_reject_past_it() {
+    let (state, db, running) = setup().await;
+    let set = process_command("cap 10000", &state, &db, &running).await;
+    assert_eq!(
+        set,
+        "Deposit cap set to $10,000.00: $10,000.00 left to deposit"
+    );
+
+    process_command("fund 6000", &state, &db, &running).await;
+    let rejected = process_command("fund 5000", &state, &db, &running).await;
+    assert_eq!(
+        rejected,
+        "Deposit would exceed the $10,000.00 deposit cap. You can add up to $4,000.00 more"
+    );
+    assert_eq!(state.lock().unwrap().check_balance(), Decimal::from(6000));
+
+    // Exactly reaching the cap is allowed; one cent more is not
+    let funded = process_command("fund 4000", &state, &db, &running).await;
+    assert_eq!(funded, "Added $4,000.00 to account");
+    let rejected = process_command("fund 0.01", &state, &db, &running).await;
+    assert!(
+        rejected.ends_with("You can add up to $0.00 more"),
+        "{}",
+        rejected
+    );
+    assert_eq!(state.lock().unwrap().get_cash_flows().len(), 2);
+}
+
+#[tokio::test]
+async fn test_withdraw_frees_headroom_and_cap_persists_until_off() {
+    let (state, db, running) = setup().await;
+    process_command("cap 1000", &state, &db, &running).await;
+    process_command("fund 1000", &state, &db, &running).await;
+    process_command("withdraw 300", &state, &db, &running).await;
+    let status = process_command("cap", &state, &db, &running).await;
+    assert_eq!(status, "Deposit cap: $1,000.00, $300.00 left to deposit");
+
+    let loaded = Storage::try_load_state(&db).await.unwrap();
+    assert_eq!(loaded.get_deposit_cap(), Some(Decimal::from(1000)));
+    assert_eq!(loaded.deposit_headroom(), Some(Decimal::from(300)));
+
+    process_command("cap off", &state, &db, &running).await;
+    let funded = process_command("fund 5000", &state, &db, &running).await;
+    assert_eq!(funded, "Added $5,000.00 to account");
+    let loaded = Storage::try_load_state(&db).await.unwrap();
+    assert_eq!(loaded.get_deposit_cap(), None);
+}
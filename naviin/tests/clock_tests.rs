@@ -0,0 +1,198 @@
+mod common;
+
+use chrono::{Duration, TimeZone, Utc};
+use naviin::AppState::{AppState, lock_state, process_open_orders};
+use naviin::FinanceProvider::MockProvider;
+use naviin::Orders::{OpenOrder, OrderType, Side};
+use naviin::cashflow::CashFlowKind;
+use naviin::clock::{Clock, DisplayTimezone, MockClock};
+use naviin::commands::process_command;
+use naviin::entities::app_state;
+use naviin::performance::EquitySnapshot;
+use rust_decimal::Decimal;
+use sea_orm::{Database, EntityTrait};
+use std::sync::atomic::AtomicBool;
+use std::sync::{Arc, Mutex};
+
+const START: i64 = 1_700_000_000;
+const DAY_SECS: i64 = 86_400;
+
+// ===== Timestamp Tests =====
+
+#[tokio::test]
+async fn test_orders_and_trades_are_stamped_from_state_clock() {
+    let clock = Arc::new(MockClock::at_timestamp(START));
+    let provider = Arc::new(MockProvider::new());
+    provider.set_price("AAPL", Decimal::from(100));
+    let (state, db, running) = common::setup(Some(provider), Some(clock.clone())).await;
+    process_command("fund 1000", &state, &db, &running).await;
+    process_command("buylimit AAPL 2 90", &state, &db, &running).await;
+
+    clock.advance(Duration::minutes(5));
+    process_command("buy AAPL 1", &state, &db, &running).await;
+
+    let guard = lock_state(&state);
+    assert_eq!(guard.get_open_orders()[0].get_timestamp(), START);
+    let bought = guard
+        .get_trades()
+        .into_iter()
+        .find(|trade| trade.get_symbol() == "AAPL")
+        .unwrap();
+    assert_eq!(bought.get_timestamp(), START + 300);
+}
+
+#[tokio::test]
+async fn test_save_is_stamped_from_state_clock() {
+    let clock = Arc::new(MockClock::at_timestamp(START));
+    let (state, db, running) = common::setup(None, Some(clock)).await;
+    process_command("fund 1000", &state, &db, &running).await;
+
+    let row = app_state::Entity::find_by_id(1)
+        .one(&db)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(row.updated_at, START);
+}
+
+#[tokio::test]
+async fn test_market_status_follows_state_clock() {
+    // Monday 2026-01-05, 10:00 in New York
+    let clock = Arc::new(MockClock::new(
+        Utc.with_ymd_and_hms(2026, 1, 5, 15, 0, 0).unwrap(),
+    ));
+    let provider = Arc::new(MockProvider::new());
+    let (state, db, running) = common::setup(Some(provider), Some(clock.clone())).await;
+
+    let summary = process_command("summary", &state, &db, &running).await;
+    assert!(summary.contains("US market: OPEN until"), "{}", summary);
+
+    clock.advance(Duration::hours(7));
+    let summary = process_command("summary", &state, &db, &running).await;
+    assert!(
+        summary.contains("US market: MARKET CLOSED (after hours)"),
+        "{}",
+        summary
+    );
+}
+
+#[tokio::test]
+async fn test_background_fill_is_stamped_from_state_clock() {
+    let clock = Arc::new(MockClock::at_timestamp(START));
+    let provider = Arc::new(MockProvider::new());
+    provider.set_price("AAPL", Decimal::from(95));
+    let mut app_state = AppState::with_provider(provider);
+    app_state.set_clock(clock.clone());
+    app_state.deposit(Decimal::from(1000));
+    let state = Arc::new(Mutex::new(app_state));
+    lock_state(&state)
+        .add_open_order(OpenOrder::new_at(
+            "AAPL".to_string(),
+            Decimal::from(2),
+            Decimal::from(100),
+            OrderType::BuyLimit,
+            Side::Buy,
+            clock.timestamp(),
+        ))
+        .unwrap();
+
+    clock.advance(Duration::hours(3));
+    assert_eq!(process_open_orders(&state).await, 1);
+
+    let mut guard = lock_state(&state);
+    let expected = START + 3 * 3600;
+    assert_eq!(guard.get_trades()[0].get_timestamp(), expected);
+    assert_eq!(guard.drain_events()[0].timestamp, expected);
+}
+
+// ===== Display Timezone Tests =====
+
+#[tokio::test]
+async fn test_same_timestamp_renders_in_the_configured_timezone() {
+    // 2023-11-14 22:13:20 UTC
+    let utc = DisplayTimezone::parse("UTC").unwrap();
+    let tokyo = DisplayTimezone::parse("Asia/Tokyo").unwrap();
+    assert_eq!(utc.date_time(START), "2023-11-14 22:13");
+    assert_eq!(tokyo.date_time(START), "2023-11-15 07:13");
+    assert_eq!(
+        tokyo.format(START, "%:z").unwrap(),
+        "+09:00",
+        "Tokyo is nine hours ahead"
+    );
+    assert_eq!(DisplayTimezone::parse("Mars/Olympus"), None);
+
+    let mut account = AppState::with_clock(Arc::new(MockClock::at_timestamp(START)));
+    account.record_cash_flow(CashFlowKind::Deposit, Decimal::from(500));
+    let state = Arc::new(Mutex::new(account));
+    let db = Database::connect("sqlite::memory:").await.unwrap();
+    let running = Arc::new(AtomicBool::new(false));
+
+    let result = process_command("timezone utc", &state, &db, &running).await;
+    assert_eq!(result, "Times will be shown in utc");
+    let ledger = process_command("cashflow", &state, &db, &running).await;
+    assert!(ledger.contains("2023-11-14 22:13"), "{}", ledger);
+
+    process_command("timezone Asia/Tokyo", &state, &db, &running).await;
+    let ledger = process_command("cashflow", &state, &db, &running).await;
+    assert!(ledger.contains("2023-11-15 07:13"), "{}", ledger);
+
+    // The zone is a setting and survives a reload; storage is unchanged
+    let settings = lock_state(&state).get_settings();
+    let mut reloaded = AppState::new();
+    for (key, value) in &settings {
+        reloaded.apply_setting(key, value);
+    }
+    assert_eq!(reloaded.get_display_timezone(), tokyo);
+    assert_eq!(lock_state(&state).get_cash_flows()[0].timestamp, START);
+}
+
+#[tokio::test]
+async fn test_performance_dates_follow_the_configured_timezone() {
+    let mut account = AppState::with_clock(Arc::new(MockClock::at_timestamp(START + DAY_SECS)));
+    account.deposit(Decimal::from(500));
+    account.set_equity_snapshots(vec![EquitySnapshot::new(START, Decimal::from(500))]);
+    let state = Arc::new(Mutex::new(account));
+    let db = Database::connect("sqlite::memory:").await.unwrap();
+    let running = Arc::new(AtomicBool::new(false));
+
+    process_command("timezone utc", &state, &db, &running).await;
+    let report = process_command("performance", &state, &db, &running).await;
+    assert!(
+        report.starts_with("Performance 2023-11-14 to 2023-11-15"),
+        "{}",
+        report
+    );
+
+    process_command("timezone Asia/Tokyo", &state, &db, &running).await;
+    let report = process_command("performance", &state, &db, &running).await;
+    assert!(
+        report.starts_with("Performance 2023-11-15 to 2023-11-16"),
+        "{}",
+        report
+    );
+}
+
+// ===== Market Status Tests =====
+
+#[tokio::test]
+async fn test_market_status_follows_the_state_clock() {
+    // Saturday 2023-11-18 16:00 UTC, then the following Monday at 15:00 UTC (10:00 New York)
+    let saturday = 1_700_323_200;
+    let clock = Arc::new(MockClock::at_timestamp(saturday));
+    let provider = Arc::new(MockProvider::new());
+    provider.set_price("AAPL", Decimal::from(150));
+    let mut account = AppState::with_clock(clock.clone());
+    account.set_provider(provider);
+    let state = Arc::new(Mutex::new(account));
+    let db = Database::connect("sqlite::memory:").await.unwrap();
+    let running = Arc::new(AtomicBool::new(false));
+
+    let quoted = process_command("price AAPL --json", &state, &db, &running).await;
+    let json: serde_json::Value = serde_json::from_str(&quoted).unwrap();
+    assert_eq!(json["market_status"], "MARKET CLOSED (weekend)");
+
+    clock.advance(Duration::days(2) - Duration::hours(1));
+    let quoted = process_command("price AAPL --json", &state, &db, &running).await;
+    let json: serde_json::Value = serde_json::from_str(&quoted).unwrap();
+    assert_eq!(json["market_status"], "OPEN");
+}
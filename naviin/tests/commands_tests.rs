@@ -0,0 +1,969 @@
+mod common;
+
+use chrono::{NaiveDate, TimeZone, Utc};
+use naviin::AppState::{AppState, process_open_orders};
+use naviin::Finance::{Holding, SharePolicy};
+use naviin::FinanceProvider::{
+    DailyBar, MockProvider, OFFLINE_MESSAGE, PriceProvider, SymbolMatch,
+};
+use naviin::Storage;
+use naviin::clock::MockClock;
+use naviin::commands::{MAX_SEARCH_RESULTS, parse_decimal_arg, process_command};
+use naviin::components::holdings::HoldingsComponent;
+use rust_decimal::Decimal;
+use sea_orm::DatabaseConnection;
+use std::collections::HashMap;
+use std::sync::atomic::AtomicBool;
+use std::sync::{Arc, Mutex};
+
+// ===== Malformed Numeric Input =====
+
+#[test]
+fn test_parse_decimal_arg_valid() {
+    assert_eq!(
+        parse_decimal_arg("12.5", "amount").unwrap(),
+        Decimal::new(125, 1)
+    );
+}
+
+#[test]
+fn test_parse_decimal_arg_rejects_garbage() {
+    for raw in ["abc", "", "1.2.3", "$100", "10abc"] {
+        let err = parse_decimal_arg(raw, "amount").unwrap_err();
+        assert_eq!(err.to_string(), format!("Invalid amount: '{}'", raw));
+    }
+}
+
+#[tokio::test]
+async fn test_fund_with_malformed_amount_does_not_panic() {
+    let (state, db, running) = common::setup(None, None).await;
+
+    let result = process_command("fund abc", &state, &db, &running).await;
+
+    assert_eq!(result, "Invalid amount: 'abc'");
+    assert_eq!(state.lock().unwrap().check_balance(), Decimal::ZERO);
+}
+
+#[tokio::test]
+async fn test_withdraw_with_negative_amount_is_rejected() {
+    let (state, db, running) = common::setup(None, None).await;
+    state.lock().unwrap().deposit(Decimal::from(100));
+
+    let result = process_command("withdraw -5", &state, &db, &running).await;
+
+    assert_eq!(result, "Amount must be positive");
+    assert_eq!(state.lock().unwrap().check_balance(), Decimal::from(100));
+}
+
+#[tokio::test]
+async fn test_buy_with_malformed_quantity_does_not_panic() {
+    let (state, db, running) = common::setup(None, None).await;
+
+    let result = process_command("buy AAPL ten", &state, &db, &running).await;
+
+    assert_eq!(result, "Invalid quantity: 'ten'");
+}
+
+#[tokio::test]
+async fn test_buylimit_with_malformed_price_does_not_panic() {
+    let (state, db, running) = common::setup(None, None).await;
+
+    let result = process_command("buylimit AAPL 1 1,50", &state, &db, &running).await;
+
+    assert_eq!(result, "Invalid price: '1,50'");
+    assert!(state.lock().unwrap().get_open_orders().is_empty());
+}
+
+// ===== Dollar-amount Orders =====
+
+#[tokio::test]
+async fn test_buy_with_malformed_dollar_amount_does_not_panic() {
+    let (state, db, running) = common::setup(None, None).await;
+
+    let result = process_command("buy AAPL $abc", &state, &db, &running).await;
+
+    assert_eq!(result, "Invalid amount: 'abc'");
+}
+
+#[tokio::test]
+async fn test_sell_with_zero_dollar_amount_is_rejected() {
+    let (state, db, running) = common::setup(None, None).await;
+
+    let result = process_command("sell AAPL $0", &state, &db, &running).await;
+
+    assert_eq!(result, "Amount must be positive");
+}
+
+// ===== Help Topics =====
+
+#[tokio::test]
+async fn test_help_for_command_shows_usage_and_example() {
+    let (state, db, running) = common::setup(None, None).await;
+
+    let result = process_command("help BuyLimit", &state, &db, &running).await;
+
+    assert!(result.starts_with("buylimit - "));
+    assert!(result.contains("Usage: buylimit <symbol> <quantity> <price>"));
+    assert!(result.contains("Example: buylimit AAPL 10 150"));
+    assert!(result.contains("Good till cancelled"));
+}
+
+#[tokio::test]
+async fn test_help_alias_and_unknown_topic() {
+    let (state, db, running) = common::setup(None, None).await;
+
+    let quit = process_command("help quit", &state, &db, &running).await;
+    let unknown = process_command("help frobnicate", &state, &db, &running).await;
+    let overview = process_command("help", &state, &db, &running).await;
+
+    assert!(quit.starts_with("exit - "));
+    assert_eq!(unknown, "No help for 'frobnicate'");
+    assert!(overview.starts_with("Available Commands:"));
+}
+
+// ===== Closing Positions =====
+
+#[tokio::test]
+async fn test_close_symbol_not_held_is_friendly() {
+    let (state, db, running) = common::setup(None, None).await;
+
+    let result = process_command("close aapl", &state, &db, &running).await;
+
+    assert_eq!(result, "You don't hold any AAPL");
+}
+
+#[tokio::test]
+async fn test_closeall_without_positions() {
+    let (state, db, running) = common::setup(None, None).await;
+
+    let result = process_command("closeall", &state, &db, &running).await;
+
+    assert_eq!(result, "No positions to close");
+}
+
+#[tokio::test]
+async fn test_close_reports_proceeds_net_of_the_fee() {
+    let provider = Arc::new(MockProvider::new());
+    provider.set_price("AAPL", Decimal::from(100));
+    let (state, db, running) = common::setup(Some(provider), None).await;
+    process_command("fund 1000", &state, &db, &running).await;
+    process_command("fee 5", &state, &db, &running).await;
+    process_command("buy AAPL 2", &state, &db, &running).await;
+
+    let result = process_command("close AAPL", &state, &db, &running).await;
+    assert_eq!(
+        result,
+        "Closed AAPL: sold 2 shares at $100.00. Proceeds: $195.00, fee: $5.00. Cash: $990.00"
+    );
+}
+
+#[tokio::test]
+async fn test_closed_position_stays_closed_after_reload() {
+    let provider = Arc::new(MockProvider::new());
+    provider.set_price("AAPL", Decimal::from(100));
+    let (state, db, running) = common::setup(Some(provider), None).await;
+    process_command("fund 1000", &state, &db, &running).await;
+    process_command("buy AAPL 2", &state, &db, &running).await;
+    process_command("close AAPL", &state, &db, &running).await;
+
+    let loaded = Storage::try_load_state(&db).await.unwrap();
+    assert!(loaded.get_holdings_map().is_empty());
+    assert_eq!(loaded.check_balance(), Decimal::from(1000));
+}
+
+// ===== Currency Display =====
+
+#[tokio::test]
+async fn test_currency_command_changes_displayed_amounts() {
+    let (state, db, running) = common::setup(None, None).await;
+
+    let result = process_command("currency € eu", &state, &db, &running).await;
+    let funded = process_command("fund 1234.5", &state, &db, &running).await;
+    let summary = process_command("summary", &state, &db, &running).await;
+
+    assert_eq!(result, "Amounts will be shown like €1.234,56");
+    assert_eq!(funded, "Added €1.234,50 to account");
+    assert!(summary.starts_with("Cash balance: €1.234,50"));
+}
+
+#[tokio::test]
+async fn test_currency_command_rejects_bad_input() {
+    let (state, db, running) = common::setup(None, None).await;
+
+    let style = process_command("currency £ dots", &state, &db, &running).await;
+    let symbol = process_command("currency 100", &state, &db, &running).await;
+
+    assert_eq!(style, "Unknown number style 'dots'. Use us|eu|fr|ch");
+    assert!(symbol.starts_with("Invalid currency symbol: '100'"));
+    assert_eq!(state.lock().unwrap().get_money_format().symbol(), "$");
+}
+
+#[test]
+fn test_currency_setting_round_trips() {
+    let mut state = AppState::new();
+    state.set_money_format(naviin::format::MoneyFormat::new(
+        "£",
+        naviin::format::NumberStyle::Fr,
+    ));
+
+    let mut restored = AppState::new();
+    for (key, value) in state.get_settings() {
+        restored.apply_setting(&key, &value);
+    }
+
+    assert_eq!(restored.get_money_format(), state.get_money_format());
+}
+
+#[tokio::test]
+async fn test_summary_of_an_empty_account_reports_no_positions() {
+    let (state, db, running) = common::setup(None, None).await;
+    let summary = process_command("summary", &state, &db, &running).await;
+
+    assert!(
+        summary.contains("\nHoldings: no positions\n"),
+        "{}",
+        summary
+    );
+    assert!(!summary.contains("Positions:"), "{}", summary);
+    assert!(!summary.contains("NaN") && !summary.contains("inf"));
+}
+
+#[tokio::test]
+async fn test_summary_lists_position_pnl_pct() {
+    let (state, db, running) = common::setup(None, None).await;
+    let provider = Arc::new(MockProvider::new());
+    provider.set_price("AAPL", Decimal::from(110));
+    {
+        let mut guard = state.lock().unwrap();
+        guard.set_provider(provider);
+        guard.deposit(Decimal::from(1000));
+    }
+    process_command("buy AAPL 2", &state, &db, &running).await;
+    process_command("addwatch MSFT --force", &state, &db, &running).await;
+    let mut holdings = state.lock().unwrap().get_holdings_map();
+    holdings.insert(
+        "AAPL".to_string(),
+        naviin::Finance::Holding::new("AAPL".to_string(), Decimal::from(2), Decimal::from(100)),
+    );
+    state.lock().unwrap().set_holdings_map(holdings);
+
+    let summary = process_command("summary", &state, &db, &running).await;
+
+    assert!(
+        summary.contains("\nPositions:\n  ▲ AAPL P&L $20.00 (+10.00%)"),
+        "{}",
+        summary
+    );
+}
+
+#[tokio::test]
+async fn test_summary_and_holdings_table_agree_on_pnl_when_previous_close_differs() {
+    let (state, db, running) = common::setup(None, None).await;
+    let provider = Arc::new(MockProvider::new());
+    provider.set_price("AAPL", Decimal::from(100));
+    {
+        let mut guard = state.lock().unwrap();
+        guard.set_provider(provider.clone());
+        guard.deposit(Decimal::from(1000));
+    }
+    process_command("buy AAPL 5", &state, &db, &running).await;
+    // Intraday move: up 12 from cost, down 8 from yesterday's close
+    provider.set_price("AAPL", Decimal::from(112));
+    provider.set_previous_close("AAPL", Decimal::from(120));
+
+    let summary = process_command("summary", &state, &db, &running).await;
+    assert!(
+        summary.contains("▲ AAPL P&L $60.00 (+12.00%)"),
+        "{}",
+        summary
+    );
+
+    // The TUI caches curr_price for holdings and previous_close only for day change
+    let holdings = state.lock().unwrap().get_holdings_map();
+    let holding = &holdings["AAPL"];
+    let price = provider.curr_price("AAPL").await;
+    let mut component = HoldingsComponent::new();
+    component.update_holdings(holdings.clone(), Decimal::ZERO);
+    component.update_prices(HashMap::from([("AAPL".to_string(), price)]));
+    component.update_previous_closes(HashMap::from([("AAPL".to_string(), Decimal::from(120))]));
+    assert_eq!(component.total_pnl(), Decimal::from(60));
+    assert_eq!(holding.get_pnl(provider.as_ref()).await, Decimal::from(60));
+}
+
+#[tokio::test]
+async fn test_offline_stops_fetches_until_online() {
+    let (state, db, running) = common::setup(None, None).await;
+    let provider = Arc::new(MockProvider::new());
+    provider.set_price("AAPL", Decimal::from(100));
+    provider.add_listing(SymbolMatch::new("AAPL", "Apple Inc.", "NASDAQ", "Equity"));
+    {
+        let mut guard = state.lock().unwrap();
+        guard.set_provider(provider.clone());
+        guard.deposit(Decimal::from(1000));
+    }
+    process_command("buylimit AAPL 2 95", &state, &db, &running).await;
+
+    let result = process_command("offline", &state, &db, &running).await;
+    assert!(result.starts_with("Offline:"), "{}", result);
+    let calls = provider.calls();
+    provider.set_price("AAPL", Decimal::from(90));
+    assert_eq!(
+        process_command("price AAPL", &state, &db, &running).await,
+        "Could not fetch price for AAPL"
+    );
+    let searched = process_command("search apple", &state, &db, &running).await;
+    assert!(searched.contains(OFFLINE_MESSAGE), "{}", searched);
+    let compared = process_command("compare AAPL MSFT", &state, &db, &running).await;
+    assert!(
+        compared.starts_with("Not enough overlapping history"),
+        "{}",
+        compared
+    );
+    assert_eq!(process_open_orders(&state).await, 0);
+    assert_eq!(provider.calls(), calls);
+
+    assert_eq!(
+        process_command("online", &state, &db, &running).await,
+        "Online: fetching prices again"
+    );
+    assert_eq!(process_open_orders(&state).await, 1);
+    let quoted = process_command("price AAPL", &state, &db, &running).await;
+    assert!(quoted.starts_with("AAPL: $90.00"), "{}", quoted);
+}
+
+// ===== Compare =====
+
+#[tokio::test]
+async fn test_compare_ranks_provider_history_in_the_clock_window() {
+    let provider = Arc::new(MockProvider::new());
+    let day = |month, d| NaiveDate::from_ymd_opt(2026, month, d).unwrap();
+    let bar = |close: i64| DailyBar {
+        open: Decimal::from(close),
+        high: Decimal::from(close),
+        low: Decimal::from(close),
+        close: Decimal::from(close),
+    };
+    provider.set_daily_bar("AAPL", day(10, 5), bar(100));
+    provider.set_daily_bar("AAPL", day(10, 14), bar(110));
+    provider.set_daily_bar("MSFT", day(10, 5), bar(200));
+    provider.set_daily_bar("MSFT", day(10, 14), bar(190));
+    // Outside the 30 days before the clock, so it doesn't move the start
+    provider.set_daily_bar("AAPL", day(8, 3), bar(50));
+    let clock = Arc::new(MockClock::new(
+        Utc.with_ymd_and_hms(2026, 10, 15, 12, 0, 0).unwrap(),
+    ));
+    let (state, db, running) = common::setup(Some(provider), Some(clock)).await;
+
+    let result = process_command("compare msft aapl tsla", &state, &db, &running).await;
+    assert_eq!(
+        result,
+        "Relative performance, last 30 days (2 common sessions, start = 100):\n \
+         1. AAPL      +10.00%  .^\n \
+         2. MSFT       -5.00%  ._\n\
+         No history for: TSLA"
+    );
+}
+
+// ===== JSON Output =====
+
+#[tokio::test]
+async fn test_price_json_shape() {
+    let (state, db, running) = setup_with_price(150).await;
+
+    let result = process_command("price aapl --json", &state, &db, &running).await;
+    let json: serde_json::Value = serde_json::from_str(&result).unwrap();
+    assert_eq!(json["symbol"], "AAPL");
+    assert_eq!(json["price"], "150");
+    assert_eq!(json["asset_type"], "stock");
+    assert!(json["market_open"].is_boolean());
+    assert!(json["market_status"].is_string());
+
+    let result = process_command("price --json NOPE", &state, &db, &running).await;
+    let json: serde_json::Value = serde_json::from_str(&result).unwrap();
+    assert_eq!(json["error"], "Could not fetch price for NOPE");
+}
+
+#[tokio::test]
+async fn test_summary_json_shape() {
+    let (state, db, running) = setup_with_price(150).await;
+    process_command("buy AAPL 2", &state, &db, &running).await;
+    let mut holdings = state.lock().unwrap().get_holdings_map();
+    holdings.insert(
+        "GONE".to_string(),
+        Holding::new("GONE".to_string(), Decimal::ONE, Decimal::from(10)),
+    );
+    state.lock().unwrap().set_holdings_map(holdings);
+
+    let result = process_command("summary --json", &state, &db, &running).await;
+    let json: serde_json::Value = serde_json::from_str(&result).unwrap();
+    assert_eq!(json["cash"], "700");
+    assert_eq!(json["cash_by_currency"]["USD"], "700");
+    assert_eq!(json["holdings_value"], "300");
+    assert_eq!(json["total_value"], "1000");
+    let positions = json["positions"].as_array().unwrap();
+    assert_eq!(positions.len(), 2);
+    assert_eq!(positions[0]["symbol"], "AAPL");
+    assert_eq!(positions[0]["quantity"], "2");
+    assert_eq!(positions[0]["market_value"], "300");
+    assert_eq!(positions[0]["pnl"], "0");
+    assert_eq!(positions[1]["symbol"], "GONE");
+    assert!(positions[1]["price"].is_null());
+    assert!(positions[1]["pnl_pct"].is_null());
+
+    assert_eq!(
+        process_command("summary --xml", &state, &db, &running).await,
+        "Usage: summary [--json]"
+    );
+}
+
+// ===== Symbol Normalization =====
+
+#[tokio::test]
+async fn test_commands_normalize_typed_symbols_and_user_aliases() {
+    let provider = Arc::new(MockProvider::new());
+    provider.set_price("BRK-B", Decimal::from(400));
+    provider.set_price("BTC-USD", Decimal::from(60000));
+    let (state, db, running) = common::setup(Some(provider), None).await;
+
+    process_command("fund 1000", &state, &db, &running).await;
+    process_command("buy brk.b 2", &state, &db, &running).await;
+    let holdings = state.lock().unwrap().get_holdings_map();
+    assert_eq!(holdings.keys().collect::<Vec<_>>(), vec!["BRK-B"]);
+
+    let price = process_command("price btc", &state, &db, &running).await;
+    assert!(price.contains("BTC-USD"), "{}", price);
+
+    let result = process_command("alias bitcoin btc/usd", &state, &db, &running).await;
+    assert_eq!(result, "BITCOIN now means BTC-USD");
+    let price = process_command("price Bitcoin", &state, &db, &running).await;
+    assert!(price.contains("BTC-USD"), "{}", price);
+    let listing = process_command("alias", &state, &db, &running).await;
+    assert_eq!(listing, "Symbol Aliases:\n  BITCOIN    -> BTC-USD");
+    let result = process_command("alias remove bitcoin", &state, &db, &running).await;
+    assert_eq!(result, "Removed alias BITCOIN");
+}
+
+// ===== Trade History =====
+
+// Adds `count` trades with timestamps out of insertion order
+fn add_trades(state: &Arc<Mutex<AppState>>, count: i64) {
+    let mut guard = state.lock().unwrap();
+    for i in 0..count {
+        let mut trade = naviin::Orders::Trade::buy(format!("S{}", i), Decimal::ONE, Decimal::TEN);
+        // Odd trades are older than even ones
+        trade.set_timestamp(1_700_000_000 + if i % 2 == 0 { i } else { -i });
+        guard.add_trade(trade);
+    }
+}
+
+#[tokio::test]
+async fn test_trades_defaults_to_latest_fifty_with_footer() {
+    let (state, db, running) = common::setup(None, None).await;
+    add_trades(&state, 60);
+
+    let result = process_command("trades", &state, &db, &running).await;
+
+    assert_eq!(result.matches("Market").count(), 50);
+    assert!(result.ends_with("… 10 more (use trades --all)"));
+    let all = process_command("trades --all", &state, &db, &running).await;
+    assert_eq!(all.matches("Market").count(), 60);
+    assert!(!all.contains("more (use trades --all)"));
+}
+
+#[tokio::test]
+async fn test_trades_last_n_sorted_newest_first() {
+    let (state, db, running) = common::setup(None, None).await;
+    add_trades(&state, 6);
+
+    let result = process_command("trades --last 3", &state, &db, &running).await;
+    let symbols: Vec<&str> = result
+        .lines()
+        .filter(|line| line.starts_with("Market"))
+        .map(|line| line.split_whitespace().nth(1).unwrap())
+        .collect();
+
+    assert_eq!(symbols, vec!["S4", "S2", "S0"]);
+    assert!(result.ends_with("… 3 more (use trades --all)"));
+    assert_eq!(
+        process_command("trades --last zero", &state, &db, &running).await,
+        "Invalid count: 'zero'. Usage: trades [--last N | --all] [--tag <t>]"
+    );
+}
+
+// ===== Refresh Interval =====
+
+#[tokio::test]
+async fn test_refresh_sets_price_and_monitor_intervals() {
+    let (state, db, running) = common::setup(None, None).await;
+
+    let prices = process_command("refresh 15", &state, &db, &running).await;
+    let orders = process_command("refresh monitor 30", &state, &db, &running).await;
+
+    assert_eq!(prices, "Prices will refresh every 15s");
+    assert_eq!(orders, "Open orders will be checked every 30s");
+    let shown = process_command("refresh", &state, &db, &running).await;
+    assert!(shown.starts_with("Prices refresh every 15s, orders are checked every 30s."));
+}
+
+#[tokio::test]
+async fn test_refresh_rejects_out_of_range_intervals() {
+    let (state, db, running) = common::setup(None, None).await;
+
+    let too_fast = process_command("refresh 0", &state, &db, &running).await;
+    let garbage = process_command("refresh monitor soon", &state, &db, &running).await;
+
+    assert_eq!(too_fast, "Interval must be between 1 and 3600 seconds");
+    assert!(garbage.starts_with("Invalid seconds: 'soon'"));
+    assert_eq!(state.lock().unwrap().get_refresh_secs(), 5);
+}
+
+#[test]
+fn test_refresh_intervals_round_trip_through_settings() {
+    let mut state = AppState::new();
+    state.set_refresh_secs(20).unwrap();
+    state.set_monitor_secs(60).unwrap();
+
+    let mut restored = AppState::new();
+    for (key, value) in state.get_settings() {
+        restored.apply_setting(&key, &value);
+    }
+    // An out-of-range stored value keeps the default
+    restored.apply_setting("refresh_secs", "0");
+
+    assert_eq!(restored.get_refresh_secs(), 20);
+    assert_eq!(restored.get_monitor_secs(), 60);
+}
+
+// ===== Watchlist Validation =====
+
+#[tokio::test]
+async fn test_addwatch_rejects_symbol_without_quote() {
+    let (state, db, running) = common::setup(None, None).await;
+    state
+        .lock()
+        .unwrap()
+        .set_provider(Arc::new(MockProvider::new()));
+
+    let result = process_command("addwatch appl", &state, &db, &running).await;
+
+    assert!(result.starts_with("Unknown symbol: APPL"));
+    assert!(state.lock().unwrap().get_watchlist().is_empty());
+}
+
+#[tokio::test]
+async fn test_addwatch_caches_valid_symbol() {
+    let (state, db, running) = common::setup(None, None).await;
+    let provider = Arc::new(MockProvider::new());
+    provider.set_price("AAPL", Decimal::from(190));
+    state.lock().unwrap().set_provider(provider.clone());
+
+    let result = process_command("addwatch aapl", &state, &db, &running).await;
+    assert_eq!(result, "Added AAPL to watchlist");
+    process_command("unwatch AAPL", &state, &db, &running).await;
+    let result = process_command("addwatch AAPL", &state, &db, &running).await;
+
+    assert_eq!(result, "Added AAPL to watchlist");
+    assert_eq!(provider.calls(), 1);
+}
+
+#[tokio::test]
+async fn test_addwatch_force_skips_validation() {
+    let (state, db, running) = common::setup(None, None).await;
+    let provider = Arc::new(MockProvider::new());
+    state.lock().unwrap().set_provider(provider.clone());
+
+    let result = process_command("addwatch XYZ1 --force", &state, &db, &running).await;
+
+    assert_eq!(result, "Added XYZ1 to watchlist");
+    assert_eq!(provider.calls(), 0);
+    assert_eq!(
+        state.lock().unwrap().get_watchlist(),
+        vec!["XYZ1".to_string()]
+    );
+}
+
+// ===== Watchlist Movers =====
+
+#[tokio::test]
+async fn test_movers_ranks_gainers_and_losers_and_notes_exclusions() {
+    let (state, db, running) = common::setup(None, None).await;
+    let provider = Arc::new(MockProvider::new());
+    for (symbol, price, previous_close) in [
+        ("AAPL", 105, 100),
+        ("NVDA", 120, 100),
+        ("MSFT", 99, 100),
+        ("TSLA", 80, 100),
+        ("IPO", 50, 0),
+    ] {
+        provider.set_price(symbol, Decimal::from(price));
+        provider.set_previous_close(symbol, Decimal::from(previous_close));
+    }
+    {
+        let mut guard = state.lock().unwrap();
+        guard.set_provider(provider);
+        for symbol in ["AAPL", "NVDA", "MSFT", "TSLA", "IPO"] {
+            guard.add_to_watchlist(symbol.to_string());
+        }
+    }
+
+    let result = process_command("movers --top 1", &state, &db, &running).await;
+    let lines: Vec<&str> = result.lines().collect();
+
+    assert_eq!(lines[1], "Gainers:");
+    assert_eq!(lines[2], "  ▲ NVDA      +20.00%");
+    assert_eq!(lines[3], "Losers:");
+    assert_eq!(lines[4], "  ▼ TSLA      -20.00%");
+    assert_eq!(lines[5], "Excluded (no previous close): IPO");
+    assert_eq!(lines.len(), 6);
+}
+
+#[tokio::test]
+async fn test_today_ranks_holdings_by_day_pnl_under_the_total() {
+    let (state, db, running) = common::setup(None, None).await;
+    let provider = Arc::new(MockProvider::new());
+    let mut holdings = HashMap::new();
+    for (symbol, qty, price, previous_close) in [
+        ("AAPL", 10, 105, 100),
+        ("NVDA", 2, 120, 100),
+        ("MSFT", 4, 90, 100),
+        ("TSLA", 1, 80, 100),
+        ("IPO", 5, 50, 0),
+    ] {
+        provider.set_price(symbol, Decimal::from(price));
+        provider.set_previous_close(symbol, Decimal::from(previous_close));
+        holdings.insert(
+            symbol.to_string(),
+            Holding::new(symbol.to_string(), Decimal::from(qty), Decimal::from(100)),
+        );
+    }
+    {
+        let mut guard = state.lock().unwrap();
+        guard.set_provider(provider);
+        guard.set_holdings_map(holdings);
+    }
+
+    let result = process_command("today", &state, &db, &running).await;
+    let lines: Vec<&str> = result.lines().collect();
+
+    // +50 +40 -40 -20 = +30 on $1,700 at the previous close
+    assert_eq!(lines[0], "Today's P&L: +$30.00 (+1.76%) across 4 holdings");
+    assert_eq!(lines[1], "Winners:");
+    assert_eq!(lines[2], "  ▲ AAPL          +$50.00   +5.00%");
+    assert_eq!(lines[3], "  ▲ NVDA          +$40.00  +20.00%");
+    assert_eq!(lines[4], "Losers:");
+    assert_eq!(lines[5], "  ▼ MSFT          -$40.00  -10.00%");
+    assert_eq!(lines[6], "  ▼ TSLA          -$20.00  -20.00%");
+    assert_eq!(
+        lines[7],
+        "Unavailable (no current price or previous close): IPO"
+    );
+    assert_eq!(lines.len(), 8);
+}
+
+#[tokio::test]
+async fn test_movers_rejects_bad_count() {
+    let (state, db, running) = common::setup(None, None).await;
+
+    let result = process_command("movers --top 0", &state, &db, &running).await;
+
+    assert_eq!(result, "Invalid count: '0' (expected 1-50)");
+}
+
+// ===== Share Rounding Policy =====
+
+async fn setup_with_price(
+    price: i64,
+) -> (Arc<Mutex<AppState>>, DatabaseConnection, Arc<AtomicBool>) {
+    let provider = Arc::new(MockProvider::new());
+    provider.set_price("AAPL", Decimal::from(price));
+    let (state, db, running) = common::setup(Some(provider), None).await;
+    state.lock().unwrap().deposit(Decimal::from(1000));
+    (state, db, running)
+}
+
+#[tokio::test]
+async fn test_trade_messages_show_quantities_at_display_precision() {
+    let provider = Arc::new(MockProvider::new());
+    provider.set_price("BTC-USD", Decimal::from(30000));
+    let (state, db, running) = common::setup(Some(provider), None).await;
+    process_command("fund 1000", &state, &db, &running).await;
+
+    let bought = process_command("buy BTC-USD $100", &state, &db, &running).await;
+    assert!(bought.starts_with("Bought 0.003333 shares"), "{}", bought);
+    let sold = process_command("sell BTC-USD 0.00100", &state, &db, &running).await;
+    assert!(sold.starts_with("Sold 0.001 shares"), "{}", sold);
+    let undone = process_command("undo", &state, &db, &running).await;
+    assert!(undone.contains("sell BTC-USD 0.001"), "{}", undone);
+    let closed = process_command("close BTC-USD", &state, &db, &running).await;
+    assert!(closed.contains("sold 0.003333 shares"), "{}", closed);
+}
+
+#[tokio::test]
+async fn test_whole_share_policy_returns_leftover_cash() {
+    let (state, db, running) = setup_with_price(30).await;
+
+    let set = process_command("shares whole", &state, &db, &running).await;
+    assert_eq!(
+        set,
+        "Buys will round down to whole shares; the rest stays as cash"
+    );
+
+    let result = process_command("buy AAPL $100", &state, &db, &running).await;
+
+    assert_eq!(
+        result,
+        "Bought 3 shares of AAPL at $30.00 (total: $90.00). $10.00 left as cash"
+    );
+    let guard = state.lock().unwrap();
+    assert_eq!(
+        guard.get_ticker_holdings_qty(&"AAPL".to_string()),
+        Decimal::from(3)
+    );
+    assert_eq!(guard.check_balance(), Decimal::from(910));
+}
+
+#[tokio::test]
+async fn test_precision_share_policy_rounds_down() {
+    let (state, db, running) = setup_with_price(30).await;
+    process_command("shares precision-2", &state, &db, &running).await;
+
+    process_command("buy AAPL $100", &state, &db, &running).await;
+
+    let guard = state.lock().unwrap();
+    assert_eq!(
+        guard.get_ticker_holdings_qty(&"AAPL".to_string()),
+        Decimal::new(333, 2)
+    );
+    assert_eq!(guard.check_balance(), Decimal::new(90010, 2));
+}
+
+#[tokio::test]
+async fn test_whole_share_policy_rejects_amount_below_one_share() {
+    let (state, db, running) = setup_with_price(30).await;
+    process_command("shares 0", &state, &db, &running).await;
+
+    let result = process_command("buy AAPL $20", &state, &db, &running).await;
+
+    assert_eq!(result, "Amount is too small to buy any AAPL at $30.00");
+    assert_eq!(state.lock().unwrap().check_balance(), Decimal::from(1000));
+}
+
+#[tokio::test]
+async fn test_shares_command_rejects_bad_policy_and_persists_setting() {
+    let (state, db, running) = common::setup(None, None).await;
+
+    let result = process_command("shares precision-9", &state, &db, &running).await;
+    assert!(result.starts_with("Unknown share policy: 'precision-9'"));
+
+    process_command("shares 4", &state, &db, &running).await;
+    let mut restored = AppState::new();
+    for (key, value) in state.lock().unwrap().get_settings() {
+        restored.apply_setting(&key, &value);
+    }
+    assert_eq!(restored.get_share_policy(), SharePolicy::Precision(4));
+}
+
+// ===== Stock Splits =====
+
+// Total cost basis of a holding, rounded to cents
+fn cost_basis(state: &Arc<Mutex<AppState>>, symbol: &str) -> Decimal {
+    let holding = state.lock().unwrap().get_holdings_map()[symbol].clone();
+    (holding.get_qty() * holding.get_avg_price()).round_dp(2)
+}
+
+#[tokio::test]
+async fn test_forward_split_preserves_cost_basis_and_realized_pnl() {
+    let (state, db, running) = setup_with_price(30).await;
+    process_command("buy AAPL 10", &state, &db, &running).await;
+    process_command("stoploss AAPL 10 25", &state, &db, &running).await;
+
+    let result = process_command("split aapl 4:1", &state, &db, &running).await;
+
+    assert_eq!(
+        result,
+        "Applied 4:1 split to AAPL: now 40 shares at $7.50 avg"
+    );
+    assert_eq!(cost_basis(&state, "AAPL"), Decimal::from(300));
+    let order = state.lock().unwrap().get_open_orders()[0].clone();
+    assert_eq!(order.get_qty(), Decimal::from(40));
+    assert_eq!(order.get_price_per(), Decimal::new(625, 2));
+    let trades = process_command("trades", &state, &db, &running).await;
+    assert!(trades.contains("Split  AAPL   ADJ   x4"), "{}", trades);
+
+    // Lots are split too, so selling at the adjusted price realizes the right gain
+    let provider = Arc::new(MockProvider::new());
+    provider.set_price("AAPL", Decimal::from(10));
+    {
+        let mut guard = state.lock().unwrap();
+        guard.set_provider(provider);
+        guard.set_open_orders(Vec::new());
+    }
+    process_command("sell AAPL 40", &state, &db, &running).await;
+    let realized = process_command("realized", &state, &db, &running).await;
+    assert!(realized.contains("\n  AAPL  $100.00\n"), "{}", realized);
+}
+
+#[tokio::test]
+async fn test_reverse_split_preserves_cost_basis() {
+    let (state, db, running) = setup_with_price(3).await;
+    process_command("buy AAPL 35", &state, &db, &running).await;
+
+    let result = process_command("split AAPL 1:10", &state, &db, &running).await;
+
+    assert_eq!(
+        result,
+        "Applied 1:10 split to AAPL: now 3.5 shares at $30.00 avg"
+    );
+    assert_eq!(cost_basis(&state, "AAPL"), Decimal::from(105));
+}
+
+#[tokio::test]
+async fn test_split_rejects_bad_ratio_and_unheld_symbol() {
+    let (state, db, running) = common::setup(None, None).await;
+
+    for ratio in ["4", "4-1", "0:1", "2:2", "a:b"] {
+        let result = process_command(&format!("split AAPL {}", ratio), &state, &db, &running).await;
+        assert!(result.starts_with("Invalid split ratio"), "{}", ratio);
+    }
+    let result = process_command("split AAPL 2:1", &state, &db, &running).await;
+    assert_eq!(result, "You don't hold AAPL");
+    assert!(state.lock().unwrap().get_trades().is_empty());
+}
+
+// ===== Symbol Search =====
+
+async fn setup_with_listings() -> (Arc<Mutex<AppState>>, DatabaseConnection, Arc<AtomicBool>) {
+    let provider = Arc::new(MockProvider::new());
+    provider.add_listing(SymbolMatch::new("AAPL", "Apple Inc.", "NASDAQ", "Equity"));
+    provider.add_listing(SymbolMatch::new(
+        "APLE",
+        "Apple Hospitality REIT, Inc.",
+        "NYSE",
+        "Equity",
+    ));
+    provider.add_listing(SymbolMatch::new(
+        "MSFT",
+        "Microsoft Corporation",
+        "NASDAQ",
+        "Equity",
+    ));
+    common::setup(Some(provider), None).await
+}
+
+#[tokio::test]
+async fn test_search_lists_matching_symbols() {
+    let (state, db, running) = setup_with_listings().await;
+
+    let result = process_command("search apple", &state, &db, &running).await;
+
+    let lines: Vec<&str> = result.lines().collect();
+    assert_eq!(lines[0], "Symbols matching \"apple\":");
+    assert_eq!(lines.len(), 3);
+    assert!(lines[1].starts_with("  AAPL       Apple Inc."));
+    assert!(lines[1].ends_with("NASDAQ     Equity"));
+    assert!(lines[2].contains("APLE"));
+}
+
+#[tokio::test]
+async fn test_search_caps_results() {
+    let (state, db, running) = common::setup(None, None).await;
+    let provider = Arc::new(MockProvider::new());
+    for i in 0..12 {
+        provider.add_listing(SymbolMatch::new(&format!("FUND{}", i), "", "", "Fund"));
+    }
+    state.lock().unwrap().set_provider(provider);
+
+    let result = process_command("search fund", &state, &db, &running).await;
+
+    assert_eq!(result.lines().count(), 1 + MAX_SEARCH_RESULTS + 1);
+    assert!(result.ends_with("… 4 more, refine the query to narrow it down"));
+    assert!(result.contains("FUND0      -                                -          Fund"));
+}
+
+#[tokio::test]
+async fn test_search_reports_no_results_and_errors() {
+    let (state, db, running) = setup_with_listings().await;
+
+    let result = process_command("search zzzz", &state, &db, &running).await;
+    assert_eq!(result, "No symbols found for \"zzzz\"");
+    let result = process_command("search", &state, &db, &running).await;
+    assert!(result.starts_with("Usage: search"));
+
+    let provider = Arc::new(MockProvider::new());
+    provider.fail_search("Search failed: connection refused");
+    state.lock().unwrap().set_provider(provider);
+    let result = process_command("search apple", &state, &db, &running).await;
+    assert_eq!(result, "Search failed: connection refused");
+}
+
+// ===== Symbol Footprint =====
+
+#[tokio::test]
+async fn test_symbols_lists_each_symbol_once_with_where_it_appears() {
+    let provider = Arc::new(MockProvider::new());
+    provider.set_price("AAPL", Decimal::from(100));
+    provider.set_price("TSLA", Decimal::from(200));
+    let (state, db, running) = common::setup(Some(provider), None).await;
+    assert_eq!(
+        process_command("symbols", &state, &db, &running).await,
+        "No symbols in the account yet"
+    );
+
+    for command in [
+        "fund 10000",
+        "buy AAPL 2",
+        "buy AAPL 1",
+        "buylimit AAPL 1 50",
+        "addwatch AAPL --force",
+        "addwatch MSFT --force",
+        "buy TSLA 1",
+        "sell TSLA 1",
+    ] {
+        process_command(command, &state, &db, &running).await;
+    }
+    // Interest credits are trades against cash, not a symbol
+    state
+        .lock()
+        .unwrap()
+        .add_trade(naviin::Orders::Trade::from_database(
+            "CASH".to_string(),
+            Decimal::ONE,
+            Decimal::ONE,
+            naviin::Orders::Side::Buy,
+            1_700_000_000,
+            naviin::Finance::INTEREST_ORDER_TYPE.to_string(),
+        ));
+
+    let footprint = state.lock().unwrap().symbol_footprint();
+    assert_eq!(
+        footprint.keys().collect::<Vec<_>>(),
+        vec!["AAPL", "MSFT", "TSLA"]
+    );
+    assert_eq!(
+        footprint["AAPL"].places(),
+        vec!["held", "watched", "ordered", "traded"]
+    );
+    assert_eq!(footprint["AAPL"].trades, 2);
+    assert_eq!(footprint["MSFT"].places(), vec!["watched"]);
+    assert_eq!(footprint["TSLA"].places(), vec!["traded"]);
+
+    let result = process_command("symbols", &state, &db, &running).await;
+    let rows: Vec<Vec<&str>> = result
+        .lines()
+        .skip(2)
+        .take(3)
+        .map(|line| line.split_whitespace().collect())
+        .collect();
+    assert!(result.starts_with("Symbols (3):"), "{}", result);
+    assert_eq!(
+        rows[0],
+        vec!["AAPL", "held,", "watched,", "ordered,", "traded", "1", "2"]
+    );
+    assert_eq!(rows[1], vec!["MSFT", "watched", "0", "0"]);
+    assert_eq!(rows[2], vec!["TSLA", "traded", "0", "2"]);
+    assert!(
+        result.ends_with("1 held, 2 watched, 1 ordered, 2 traded"),
+        "{}",
+        result
+    );
+}
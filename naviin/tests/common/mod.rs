@@ -0,0 +1,31 @@
+// Shared fixture for tests that drive the app through `process_command`
+use migration::{Migrator, MigratorTrait};
+use naviin::AppState::AppState;
+use naviin::FinanceProvider::MockProvider;
+use naviin::clock::MockClock;
+use sea_orm::{Database, DatabaseConnection};
+use std::sync::atomic::AtomicBool;
+use std::sync::{Arc, Mutex};
+
+// Empty account on a migrated in-memory database, pricing through `provider` and
+// telling time from `clock` when given. The running flag starts cleared so commands
+// don't spawn the background monitor
+pub async fn setup(
+    provider: Option<Arc<MockProvider>>,
+    clock: Option<Arc<MockClock>>,
+) -> (Arc<Mutex<AppState>>, DatabaseConnection, Arc<AtomicBool>) {
+    let mut state = AppState::new();
+    if let Some(provider) = provider {
+        state.set_provider(provider);
+    }
+    if let Some(clock) = clock {
+        state.set_clock(clock);
+    }
+    let db = Database::connect("sqlite::memory:").await.unwrap();
+    Migrator::up(&db, None).await.unwrap();
+    (
+        Arc::new(Mutex::new(state)),
+        db,
+        Arc::new(AtomicBool::new(false)),
+    )
+}
@@ -0,0 +1,128 @@
+use naviin::AppState::{AppState, lock_state};
+use naviin::Finance;
+use naviin::Orders::Side;
+use naviin::events::EventKind;
+use rust_decimal::Decimal;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+// ===== Concurrent Trading Tests =====
+
+// Buys and sells race on a multi-threaded runtime. Every order holds the state
+// lock only while applying itself, so the tasks must finish and the ledger must
+// balance exactly.
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_concurrent_buys_and_sells_keep_balance_consistent() {
+    let state = Arc::new(Mutex::new(AppState::new()));
+    {
+        let mut guard = lock_state(&state);
+        guard.deposit(Decimal::from(100_000));
+    }
+    let symbol = "AAPL".to_string();
+    let price = Decimal::from(10);
+    // Seed a position so sells have something to draw from
+    Finance::create_buy_with_params(&state, symbol.clone(), Decimal::from(100), price)
+        .await
+        .unwrap();
+
+    let mut tasks = Vec::new();
+    for i in 0..50 {
+        let state = state.clone();
+        let symbol = symbol.clone();
+        tasks.push(tokio::spawn(async move {
+            if i % 2 == 0 {
+                Finance::create_buy_with_params(&state, symbol, Decimal::ONE, price).await
+            } else {
+                Finance::create_sell_with_params(&state, symbol, Decimal::ONE, price).await
+            }
+        }));
+    }
+
+    let results = tokio::time::timeout(Duration::from_secs(10), async {
+        let mut results = Vec::new();
+        for task in tasks {
+            results.push(task.await.unwrap());
+        }
+        results
+    })
+    .await
+    .expect("concurrent orders deadlocked");
+
+    assert!(results.iter().all(|r| r.is_ok()));
+    let guard = lock_state(&state);
+    let buys = guard
+        .get_trades()
+        .iter()
+        .filter(|t| t.get_side() == &Side::Buy)
+        .count();
+    let sells = guard.get_trades().len() - buys;
+    assert_eq!(buys, 26);
+    assert_eq!(sells, 25);
+    // 100 seeded + 25 bought - 25 sold, and cash moved by the same net amount
+    assert_eq!(guard.get_ticker_holdings_qty(&symbol), Decimal::from(100));
+    assert_eq!(guard.check_balance(), Decimal::from(99_000));
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_concurrent_buys_never_overspend() {
+    let state = Arc::new(Mutex::new(AppState::new()));
+    {
+        let mut guard = lock_state(&state);
+        guard.deposit(Decimal::from(100));
+    }
+
+    let tasks: Vec<_> = (0..20)
+        .map(|_| {
+            let state = state.clone();
+            tokio::spawn(async move {
+                Finance::create_buy_with_params(
+                    &state,
+                    "MSFT".to_string(),
+                    Decimal::ONE,
+                    Decimal::from(30),
+                )
+                .await
+            })
+        })
+        .collect();
+    let mut filled = 0;
+    for task in tasks {
+        if task.await.unwrap().is_ok() {
+            filled += 1;
+        }
+    }
+
+    // Only three $30 buys fit into $100
+    assert_eq!(filled, 3);
+    assert_eq!(lock_state(&state).check_balance(), Decimal::from(10));
+}
+
+// ===== Lock Poisoning Tests =====
+
+#[tokio::test]
+async fn test_state_usable_after_panic_while_locked() {
+    let state = Arc::new(Mutex::new(AppState::new()));
+    lock_state(&state).deposit(Decimal::from(100));
+
+    let poisoner = state.clone();
+    let result = tokio::spawn(async move {
+        let mut guard = lock_state(&poisoner);
+        guard.deposit(Decimal::from(50));
+        panic!("simulated failure while holding the state lock");
+    })
+    .await;
+    assert!(result.is_err());
+    assert!(state.is_poisoned());
+
+    let mut guard = lock_state(&state);
+    assert_eq!(guard.check_balance(), Decimal::from(150));
+    let events = guard.drain_events();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].kind, EventKind::Error);
+    drop(guard);
+
+    // Recovery clears the flag, so later locks don't warn again
+    assert!(!state.is_poisoned());
+    lock_state(&state).deposit(Decimal::from(1));
+    assert_eq!(lock_state(&state).pending_events(), 0);
+}
@@ -0,0 +1,259 @@
+use naviin::AppState::AppState;
+use naviin::FinanceProvider::MockProvider;
+use naviin::Orders::Trade;
+use naviin::commands::process_command;
+use naviin::costbasis::{CostBasisMethod, closed_lots, realized_by_symbol, realized_gains};
+use rust_decimal::Decimal;
+use sea_orm::Database;
+use std::sync::atomic::AtomicBool;
+use std::sync::{Arc, Mutex};
+
+// Buy 10 @ 100, buy 10 @ 120, sell 10 @ 130, buy 5 @ 90, sell 8 @ 100
+fn trade_sequence() -> Vec<Trade> {
+    let aapl = || "AAPL".to_string();
+    vec![
+        Trade::buy_at(aapl(), Decimal::from(10), Decimal::from(100), 1),
+        Trade::buy_at(aapl(), Decimal::from(10), Decimal::from(120), 2),
+        Trade::sell_at(aapl(), Decimal::from(10), Decimal::from(130), 3),
+        Trade::buy_at(aapl(), Decimal::from(5), Decimal::from(90), 4),
+        Trade::sell_at(aapl(), Decimal::from(8), Decimal::from(100), 5),
+    ]
+}
+
+fn total_realized(method: CostBasisMethod) -> Decimal {
+    realized_by_symbol(&realized_gains(&trade_sequence(), method))["AAPL"].round_dp(2)
+}
+
+// ===== Method Comparison Tests =====
+
+#[test]
+fn test_realized_pnl_differs_by_method() {
+    // FIFO: 10 x (130 - 100) + 8 x (100 - 120)
+    assert_eq!(total_realized(CostBasisMethod::Fifo), Decimal::from(140));
+    // LIFO: 10 x (130 - 120) + 5 x (100 - 90) + 3 x (100 - 100)
+    assert_eq!(total_realized(CostBasisMethod::Lifo), Decimal::from(150));
+    // Average: 10 x (130 - 110) + 8 x (100 - 103.33)
+    assert_eq!(
+        total_realized(CostBasisMethod::Average),
+        Decimal::new(17333, 2)
+    );
+}
+
+#[test]
+fn test_realized_gains_record_each_sell() {
+    let gains = realized_gains(&trade_sequence(), CostBasisMethod::Fifo);
+
+    assert_eq!(gains.len(), 2);
+    assert_eq!(gains[0].quantity, Decimal::from(10));
+    assert_eq!(gains[0].cost, Decimal::from(1000));
+    assert_eq!(gains[1].timestamp, 5);
+    assert_eq!(gains[1].gain(), Decimal::from(-160));
+}
+
+#[test]
+fn test_sell_without_recorded_buy_is_left_out() {
+    let trades = vec![
+        Trade::buy_at("MSFT".to_string(), Decimal::from(2), Decimal::from(50), 1),
+        Trade::sell_at("MSFT".to_string(), Decimal::from(5), Decimal::from(60), 2),
+    ];
+
+    let gains = realized_gains(&trades, CostBasisMethod::Lifo);
+
+    assert_eq!(gains[0].quantity, Decimal::from(2));
+    assert_eq!(gains[0].gain(), Decimal::from(20));
+}
+
+#[test]
+fn test_fees_count_against_realized_gains() {
+    let fee = Decimal::from(5);
+    let trades = vec![
+        Trade::buy_at("MSFT".to_string(), Decimal::from(10), Decimal::from(100), 1).with_fee(fee),
+        Trade::sell_at("MSFT".to_string(), Decimal::from(10), Decimal::from(130), 2).with_fee(fee),
+    ];
+
+    // The buy's fee is cost, the sell's comes out of the proceeds
+    let gains = realized_gains(&trades, CostBasisMethod::Fifo);
+    assert_eq!(gains[0].cost, Decimal::from(1005));
+    assert_eq!(gains[0].proceeds, Decimal::from(1295));
+    assert_eq!(gains[0].gain(), Decimal::from(290));
+    let lots = closed_lots(&trades, CostBasisMethod::Average);
+    assert_eq!(lots[0].gain(), Decimal::from(290));
+}
+
+// ===== Setting Tests =====
+
+#[test]
+fn test_cost_basis_setting_round_trips() {
+    let mut state = AppState::new();
+    assert_eq!(state.get_cost_basis(), CostBasisMethod::Average);
+    state.set_cost_basis(CostBasisMethod::Lifo);
+
+    let mut restored = AppState::new();
+    for (key, value) in state.get_settings() {
+        restored.apply_setting(&key, &value);
+    }
+
+    assert_eq!(restored.get_cost_basis(), CostBasisMethod::Lifo);
+}
+
+#[tokio::test]
+async fn test_costbasis_command_switches_realized_report() {
+    let state = Arc::new(Mutex::new(AppState::new()));
+    let db = Database::connect("sqlite::memory:").await.unwrap();
+    let running = Arc::new(AtomicBool::new(false));
+    for trade in trade_sequence() {
+        state.lock().unwrap().add_trade(trade);
+    }
+
+    let result = process_command("costbasis fifo", &state, &db, &running).await;
+    assert_eq!(result, "Realized P&L will use FIFO cost basis");
+
+    let report = process_command("realized", &state, &db, &running).await;
+    assert!(report.starts_with("Realized P&L (FIFO):"));
+    assert!(report.contains("$140.00"));
+
+    let result = process_command("costbasis hifo", &state, &db, &running).await;
+    assert!(result.starts_with("Unknown cost basis method: 'hifo'"));
+    assert_eq!(
+        state.lock().unwrap().get_cost_basis(),
+        CostBasisMethod::Fifo
+    );
+}
+
+// ===== Tax Report Tests =====
+
+fn utc(year: i32, month: u32, day: u32) -> i64 {
+    chrono::NaiveDate::from_ymd_opt(year, month, day)
+        .unwrap()
+        .and_hms_opt(0, 0, 0)
+        .unwrap()
+        .and_utc()
+        .timestamp()
+}
+
+// Two buys in 2024, a 2025 sell spanning both lots, and a 2026 sell of part of the rest
+fn taxable_sequence() -> Vec<Trade> {
+    let aapl = || "AAPL".to_string();
+    vec![
+        Trade::buy_at(
+            aapl(),
+            Decimal::from(10),
+            Decimal::from(100),
+            utc(2024, 1, 10),
+        ),
+        Trade::buy_at(
+            aapl(),
+            Decimal::from(10),
+            Decimal::from(120),
+            utc(2024, 6, 1),
+        ),
+        Trade::sell_at(
+            aapl(),
+            Decimal::from(15),
+            Decimal::from(150),
+            utc(2025, 3, 1),
+        ),
+        Trade::sell_at(
+            aapl(),
+            Decimal::from(2),
+            Decimal::from(130),
+            utc(2026, 2, 1),
+        ),
+    ]
+}
+
+#[test]
+fn test_closed_lots_split_partial_closes_by_holding_period() {
+    let lots = closed_lots(&taxable_sequence(), CostBasisMethod::Fifo);
+
+    assert_eq!(lots.len(), 3);
+    // First lot held 416 days, all 10 shares sold
+    assert_eq!(lots[0].quantity, Decimal::from(10));
+    assert_eq!(lots[0].gain(), Decimal::from(500));
+    assert!(lots[0].is_long_term());
+    // Half of the second lot, held 273 days
+    assert_eq!(lots[1].quantity, Decimal::from(5));
+    assert_eq!(lots[1].opened, utc(2024, 6, 1));
+    assert_eq!(lots[1].gain(), Decimal::from(150));
+    assert!(!lots[1].is_long_term());
+    // The next sell takes from what was left of the second lot
+    assert_eq!(lots[2].cost, Decimal::from(240));
+    assert_eq!(lots[2].gain(), Decimal::from(20));
+    assert!(lots[2].is_long_term());
+}
+
+#[tokio::test]
+async fn test_taxreport_writes_only_that_years_lots() {
+    let state = Arc::new(Mutex::new(AppState::new()));
+    let db = Database::connect("sqlite::memory:").await.unwrap();
+    let running = Arc::new(AtomicBool::new(false));
+    for trade in taxable_sequence() {
+        state.lock().unwrap().add_trade(trade);
+    }
+    state.lock().unwrap().set_cost_basis(CostBasisMethod::Fifo);
+    let path = std::env::temp_dir().join(format!("naviin_taxreport_{}.csv", std::process::id()));
+
+    let command = format!("taxreport 2025 {}", path.display());
+    let result = process_command(&command, &state, &db, &running).await;
+
+    assert!(result.starts_with("Exported 2 closed lots for 2025 (FIFO)"));
+    assert!(result.contains("Short term: $150.00"));
+    assert!(result.contains("Long term:  $500.00"));
+    let csv = std::fs::read_to_string(&path).unwrap();
+    assert_eq!(
+        csv,
+        "symbol,open_date,close_date,quantity,proceeds,cost_basis,gain_loss,holding_period\n\
+         AAPL,2024-01-10,2025-03-01,10,1500,1000,500,long\n\
+         AAPL,2024-06-01,2025-03-01,5,750,600,150,short\n"
+    );
+    std::fs::remove_file(path).ok();
+
+    let result = process_command("taxreport 2023 unused.csv", &state, &db, &running).await;
+    assert_eq!(result, "No lots closed in 2023");
+    let result = process_command("taxreport next unused.csv", &state, &db, &running).await;
+    assert!(result.starts_with("Invalid year: 'next'"));
+}
+
+// ===== P&L Report Tests =====
+
+#[tokio::test]
+async fn test_pnl_splits_unrealized_and_realized_per_symbol() {
+    let provider = Arc::new(MockProvider::new());
+    provider.set_price("AAPL", Decimal::from(100));
+    provider.set_price("MSFT", Decimal::from(50));
+    let state = Arc::new(Mutex::new(AppState::with_provider(provider.clone())));
+    let db = Database::connect("sqlite::memory:").await.unwrap();
+    let running = Arc::new(AtomicBool::new(false));
+    for command in ["fund 5000", "buy AAPL 10", "buy MSFT 2"] {
+        process_command(command, &state, &db, &running).await;
+    }
+    provider.set_price("AAPL", Decimal::from(120));
+    process_command("sell AAPL 4", &state, &db, &running).await;
+    provider.set_price("AAPL", Decimal::from(110));
+    provider.set_price("MSFT", Decimal::ZERO);
+
+    let report = process_command("pnl", &state, &db, &running).await;
+    let rows: Vec<Vec<&str>> = report
+        .lines()
+        .map(|line| line.split_whitespace().collect())
+        .collect();
+
+    assert_eq!(rows[0], vec!["P&L", "(realized", "at", "Average", "cost):"]);
+    // 6 shares up $10 each, 4 sold $20 above cost
+    assert!(rows.contains(&vec!["▲", "AAPL", "$60.00", "$80.00", "$140.00"]));
+    assert!(rows.contains(&vec!["▲", "MSFT", "N/A", "$0.00", "$0.00"]));
+    assert!(rows.contains(&vec!["Total", "$60.00", "$80.00", "$140.00"]));
+    assert!(report.ends_with("No price for MSFT; left out of the unrealized total"));
+}
+
+#[tokio::test]
+async fn test_pnl_without_positions_or_sells() {
+    let state = Arc::new(Mutex::new(AppState::new()));
+    let db = Database::connect("sqlite::memory:").await.unwrap();
+    let running = Arc::new(AtomicBool::new(false));
+
+    assert_eq!(
+        process_command("pnl", &state, &db, &running).await,
+        "No P&L yet"
+    );
+}
@@ -0,0 +1,58 @@
+use migration::{Migrator, MigratorTrait};
+use naviin::Storage;
+use naviin::commands::process_command;
+use naviin::demo::demo_state;
+use naviin::entities::{app_state, holding, trade};
+use rust_decimal::Decimal;
+use sea_orm::{Database, EntityTrait};
+use std::sync::atomic::AtomicBool;
+use std::sync::{Arc, Mutex};
+
+// ===== Demo Mode Tests =====
+
+#[test]
+fn test_demo_state_is_consistent_sample_portfolio() {
+    let state = demo_state();
+
+    assert!(state.is_demo());
+    let holdings = state.get_holdings_map();
+    assert_eq!(holdings.len(), 4);
+    assert_eq!(holdings["NVDA"].get_qty(), Decimal::from(40));
+    assert_eq!(state.get_trades().len(), 5);
+    assert_eq!(state.check_balance(), Decimal::from(18_915));
+    assert_eq!(state.get_watchlist().len(), 6);
+}
+
+#[tokio::test]
+async fn test_demo_prices_come_from_fixed_quotes() {
+    let provider = demo_state().provider();
+
+    assert_eq!(provider.curr_price("AAPL").await, Decimal::new(18930, 2));
+    assert_eq!(provider.previous_close("TSLA").await, Decimal::from(252));
+}
+
+#[tokio::test]
+async fn test_demo_mode_never_writes_to_disk() {
+    let path = std::env::temp_dir().join(format!("naviin_demo_{}.sqlite", std::process::id()));
+    let db = Database::connect(format!("sqlite://{}?mode=rwc", path.display()))
+        .await
+        .unwrap();
+    Migrator::up(&db, None).await.unwrap();
+    let state = Arc::new(Mutex::new(demo_state()));
+    let running = Arc::new(AtomicBool::new(false));
+
+    process_command("buy MSFT 2", &state, &db, &running).await;
+    process_command("fund 500", &state, &db, &running).await;
+    Storage::save_state(&state, &db).await;
+    process_command("reset", &state, &db, &running).await;
+
+    assert!(app_state::Entity::find().all(&db).await.unwrap().is_empty());
+    assert!(holding::Entity::find().all(&db).await.unwrap().is_empty());
+    assert!(trade::Entity::find().all(&db).await.unwrap().is_empty());
+    // Reset brings the sample back rather than leaving demo mode
+    assert!(state.lock().unwrap().is_demo());
+    assert_eq!(state.lock().unwrap().get_trades().len(), 5);
+
+    db.close().await.ok();
+    std::fs::remove_file(path).ok();
+}
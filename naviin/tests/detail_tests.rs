@@ -0,0 +1,79 @@
+use naviin::Finance::Holding;
+use naviin::components::detail::DetailComponent;
+use naviin::format::MoneyFormat;
+use ratatui::{buffer::Buffer, layout::Rect, widgets::Widget};
+use rust_decimal::Decimal;
+
+// Flattens the pane's lines into one string per line
+fn text(detail: &DetailComponent) -> Vec<String> {
+    detail.lines().iter().map(|line| line.to_string()).collect()
+}
+
+fn detail(position: Option<Holding>, note: Option<&str>) -> DetailComponent {
+    DetailComponent::new(
+        "AAPL".to_string(),
+        position,
+        note.map(str::to_string),
+        MoneyFormat::default(),
+    )
+}
+
+// ===== Detail Pane Tests =====
+
+#[test]
+fn test_detail_shows_loading_until_quote_arrives() {
+    let pane = detail(None, None);
+    let lines = text(&pane);
+
+    assert!(lines.contains(&"Loading quote...".to_string()));
+    assert!(lines.contains(&"Loading history...".to_string()));
+    assert!(lines.contains(&"No position".to_string()));
+    assert!(lines.contains(&"No note".to_string()));
+}
+
+#[test]
+fn test_detail_shows_day_change_and_position_pnl() {
+    let holding = Holding::new("AAPL".to_string(), Decimal::from(10), Decimal::from(100));
+    let mut pane = detail(Some(holding), Some("earnings next week"));
+    pane.update_quote(Decimal::from(110), Decimal::from(105));
+
+    let lines = text(&pane);
+    assert_eq!(lines[0], "$110.00");
+    assert!(lines[1].starts_with("+$5.00 ("));
+    assert!(lines[1].ends_with("today, previous close $105.00"));
+    assert!(lines.contains(&"Position: 10 @ $100.00 avg".to_string()));
+    assert!(
+        lines
+            .iter()
+            .any(|line| line.starts_with("Value $1,100.00, P&L $100.00"))
+    );
+    assert!(lines.contains(&"Note: earnings next week".to_string()));
+}
+
+#[test]
+fn test_detail_ignores_failed_quote() {
+    let mut pane = detail(None, None);
+    pane.update_quote(Decimal::ZERO, Decimal::ZERO);
+
+    assert_eq!(pane.day_change(), None);
+    assert_eq!(text(&pane)[0], "Loading quote...");
+}
+
+#[test]
+fn test_detail_draws_sparkline_once_history_loads() {
+    let mut pane = detail(None, None);
+    pane.set_history(vec![Decimal::ONE]);
+    assert!(text(&pane).contains(&"No recent history".to_string()));
+
+    pane.set_history((1..=5).map(Decimal::from).collect());
+    let lines = text(&pane);
+    assert!(lines.iter().any(|line| line.starts_with("30 days  ")));
+    assert!(lines.contains(&"Range $1.00 - $5.00".to_string()));
+
+    let area = Rect::new(0, 0, 60, 14);
+    let mut buf = Buffer::empty(area);
+    (&pane).render(area, &mut buf);
+    let rendered: String = buf.content().iter().map(|cell| cell.symbol()).collect();
+    assert!(rendered.contains(" AAPL "));
+    assert!(rendered.contains("Close <Esc>"));
+}
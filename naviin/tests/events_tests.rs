@@ -0,0 +1,214 @@
+use chrono::NaiveDate;
+use migration::{Migrator, MigratorTrait};
+use naviin::AppState::{AppState, lock_state, monitor_order, process_open_orders};
+use naviin::FinanceProvider::{BarFuture, MockProvider, PriceFuture, PriceProvider, SearchFuture};
+use naviin::Orders::{OpenOrder, OrderType, Side};
+use naviin::Storage;
+use naviin::clock::DisplayTimezone;
+use naviin::events::{AppEvent, EVENT_LIMIT, EventKind, format_events};
+use rust_decimal::Decimal;
+use sea_orm::Database;
+use std::sync::atomic::AtomicBool;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+// ===== Event Buffer Tests =====
+
+#[test]
+fn test_event_buffer_drops_oldest_past_limit() {
+    let mut state = AppState::new();
+    for i in 0..EVENT_LIMIT + 5 {
+        state.push_event(EventKind::Info, format!("event {}", i));
+    }
+
+    assert_eq!(state.pending_events(), EVENT_LIMIT);
+    let events = state.drain_events();
+    assert_eq!(events[0].message, "event 5");
+    assert_eq!(state.pending_events(), 0);
+}
+
+#[tokio::test]
+async fn test_background_fill_is_recorded_as_event() {
+    let provider = Arc::new(MockProvider::new());
+    provider.set_price("AAPL", Decimal::from(95));
+    let state = Arc::new(Mutex::new(AppState::with_provider(provider)));
+    {
+        let mut guard = lock_state(&state);
+        guard.deposit(Decimal::from(1000));
+        guard
+            .add_open_order(OpenOrder::new(
+                "AAPL".to_string(),
+                Decimal::from(2),
+                Decimal::from(100),
+                OrderType::BuyLimit,
+                Side::Buy,
+            ))
+            .unwrap();
+    }
+
+    process_open_orders(&state).await;
+
+    let events = lock_state(&state).drain_events();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].kind, EventKind::Fill);
+    assert_eq!(events[0].message, "BuyLimit bought 2 AAPL at $95.00");
+}
+
+#[test]
+fn test_webhook_failure_is_recorded_as_error_event() {
+    let mut state = AppState::new();
+    state.record_webhook_result(None);
+    assert_eq!(state.pending_events(), 0);
+
+    state.record_webhook_result(Some("timed out after 5s".to_string()));
+
+    let events = state.drain_events();
+    assert_eq!(events[0].kind, EventKind::Error);
+    assert_eq!(
+        events[0].message,
+        "Webhook delivery failed: timed out after 5s"
+    );
+}
+
+#[test]
+fn test_events_format_with_time_and_kind() {
+    let events = vec![AppEvent::new(
+        0,
+        EventKind::Fill,
+        "StopLoss sold 1 AAPL".to_string(),
+    )];
+
+    let text = format_events(&events, DisplayTimezone::Utc);
+
+    let lines: Vec<&str> = text.lines().collect();
+    assert_eq!(lines[0], "Background activity (1):");
+    assert_eq!(lines[1], "[00:00:00] FILL  StopLoss sold 1 AAPL");
+}
+
+// ===== Autosave Tests =====
+
+#[tokio::test]
+async fn test_autosave_persists_background_fill_once() {
+    let db = Database::connect("sqlite::memory:").await.unwrap();
+    Migrator::up(&db, None).await.unwrap();
+    let provider = Arc::new(MockProvider::new());
+    provider.set_price("AAPL", Decimal::from(105));
+    let state = Arc::new(Mutex::new(AppState::with_provider(provider.clone())));
+    {
+        let mut guard = lock_state(&state);
+        guard.deposit(Decimal::from(1000));
+        guard
+            .add_open_order(OpenOrder::new(
+                "AAPL".to_string(),
+                Decimal::from(2),
+                Decimal::from(100),
+                OrderType::BuyLimit,
+                Side::Buy,
+            ))
+            .unwrap();
+    }
+    Storage::save_state(&state, &db).await;
+    // Nothing changed since that save, so there is nothing to write
+    assert!(!Storage::save_if_dirty(&state, &db).await);
+
+    provider.set_price("AAPL", Decimal::from(95));
+    assert_eq!(process_open_orders(&state).await, 1);
+    assert!(lock_state(&state).is_dirty());
+    assert!(Storage::save_if_dirty(&state, &db).await);
+    assert!(!Storage::save_if_dirty(&state, &db).await);
+
+    let loaded = Storage::try_load_state(&db).await.unwrap();
+    assert!(loaded.get_open_orders().is_empty());
+    assert_eq!(
+        loaded.get_holdings_map()["AAPL"].get_qty(),
+        Decimal::from(2)
+    );
+    assert_eq!(loaded.check_balance(), Decimal::from(810));
+}
+
+#[tokio::test]
+async fn test_shutdown_stops_monitor_and_saves_unsaved_changes() {
+    use std::sync::atomic::Ordering;
+
+    let db = Database::connect("sqlite::memory:").await.unwrap();
+    Migrator::up(&db, None).await.unwrap();
+    let state = Arc::new(Mutex::new(AppState::new()));
+    Storage::save_state(&state, &db).await;
+    {
+        // A change nothing has saved yet, as a background fill leaves it
+        let mut guard = lock_state(&state);
+        guard.deposit(Decimal::from(250));
+        guard.mark_dirty();
+    }
+    let running = AtomicBool::new(true);
+
+    Storage::shutdown(&state, &db, &running).await;
+
+    assert!(!running.load(Ordering::Relaxed));
+    assert!(!lock_state(&state).is_dirty());
+    let loaded = Storage::try_load_state(&db).await.unwrap();
+    assert_eq!(loaded.check_balance(), Decimal::from(250));
+}
+
+// Provider whose quotes arrive after a delay, so a monitor pass is still pricing
+// when shutdown runs
+struct SlowProvider;
+
+impl PriceProvider for SlowProvider {
+    fn name(&self) -> &str {
+        "slow"
+    }
+
+    fn curr_price<'a>(&'a self, _symbol: &'a str) -> PriceFuture<'a> {
+        Box::pin(async {
+            tokio::time::sleep(Duration::from_millis(200)).await;
+            Decimal::from(95)
+        })
+    }
+
+    fn previous_close<'a>(&'a self, symbol: &'a str) -> PriceFuture<'a> {
+        self.curr_price(symbol)
+    }
+
+    fn search<'a>(&'a self, _query: &'a str) -> SearchFuture<'a> {
+        Box::pin(async { Ok(Vec::new()) })
+    }
+
+    fn daily_bar<'a>(&'a self, _symbol: &'a str, _date: NaiveDate) -> BarFuture<'a> {
+        Box::pin(async { None })
+    }
+}
+
+#[tokio::test]
+async fn test_shutdown_stops_a_monitor_pass_in_progress() {
+    let db = Database::connect("sqlite::memory:").await.unwrap();
+    Migrator::up(&db, None).await.unwrap();
+    let state = Arc::new(Mutex::new(AppState::with_provider(Arc::new(SlowProvider))));
+    {
+        let mut guard = lock_state(&state);
+        guard.deposit(Decimal::from(1000));
+        guard
+            .add_open_order(OpenOrder::new(
+                "AAPL".to_string(),
+                Decimal::from(2),
+                Decimal::from(100),
+                OrderType::BuyLimit,
+                Side::Buy,
+            ))
+            .unwrap();
+    }
+    Storage::save_state(&state, &db).await;
+    let running = Arc::new(AtomicBool::new(true));
+    monitor_order(state.clone(), running.clone());
+
+    // The first pass is waiting on the quote when shutdown starts
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    Storage::shutdown(&state, &db, &running).await;
+    tokio::time::sleep(Duration::from_millis(400)).await;
+
+    // Nothing filled after the final save, so memory and the database agree
+    assert_eq!(lock_state(&state).get_open_orders().len(), 1);
+    let loaded = Storage::try_load_state(&db).await.unwrap();
+    assert_eq!(loaded.get_open_orders().len(), 1);
+    assert_eq!(loaded.check_balance(), Decimal::from(1000));
+}
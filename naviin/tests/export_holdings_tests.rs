@@ -0,0 +1,47 @@
+use naviin::AppState::AppState;
+use naviin::Finance::HOLDINGS_CSV_HEADER;
+use naviin::FinanceProvider::MockProvider;
+use naviin::commands::process_command;
+use rust_decimal::Decimal;
+use sea_orm::Database;
+use std::sync::atomic::AtomicBool;
+use std::sync::{Arc, Mutex};
+
+// ===== Holdings Export Tests =====
+
+#[tokio::test]
+async fn test_export_holdings_writes_rows_and_flags_unpriced_symbols() {
+    let provider = Arc::new(MockProvider::new());
+    provider.set_price("AAPL", Decimal::from(100));
+    provider.set_price("MSFT", Decimal::from(300));
+    let state = Arc::new(Mutex::new(AppState::with_provider(provider.clone())));
+    let db = Database::connect("sqlite::memory:").await.unwrap();
+    let running = Arc::new(AtomicBool::new(false));
+    for command in ["fund 5000", "buy AAPL 10", "buy MSFT 2.5"] {
+        process_command(command, &state, &db, &running).await;
+    }
+    provider.set_price("AAPL", Decimal::new(1105, 1));
+    // MSFT can no longer be priced
+    provider.set_price("MSFT", Decimal::ZERO);
+
+    let path = std::env::temp_dir().join(format!("naviin_holdings_{}.csv", std::process::id()));
+    let command = format!("export-holdings {}", path.display());
+    let result = process_command(&command, &state, &db, &running).await;
+    assert!(result.starts_with("Exported 2 holdings to"), "{}", result);
+    assert!(result.ends_with("1 without a current price are flagged no_price"));
+
+    let csv = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).ok();
+    let rows: Vec<Vec<&str>> = csv.lines().map(|line| line.split(',').collect()).collect();
+    assert_eq!(csv.lines().next().unwrap(), HOLDINGS_CSV_HEADER);
+    assert_eq!(
+        rows[1],
+        vec!["AAPL", "10", "100", "110.5", "1105", "105", ""]
+    );
+    assert_eq!(rows[2], vec!["MSFT", "2.5", "300", "", "", "", "no_price"]);
+    assert_eq!(rows.len(), 3);
+
+    let empty = Arc::new(Mutex::new(AppState::with_provider(provider)));
+    let result = process_command(&command, &empty, &db, &running).await;
+    assert_eq!(result, "No holdings to export");
+}
@@ -1 +1,484 @@
+use naviin::AppState::AppState;
+use naviin::Finance;
+use naviin::Orders::Side;
+use rust_decimal::Decimal;
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
 
+// ===== Slippage Tests =====
+
+#[test]
+fn test_apply_slippage_direction() {
+    let price = Decimal::from(100);
+    let bps = Decimal::from(50);
+
+    assert_eq!(
+        Finance::apply_slippage(price, &Side::Buy, bps),
+        Decimal::new(1005, 1)
+    );
+    assert_eq!(
+        Finance::apply_slippage(price, &Side::Sell, bps),
+        Decimal::new(995, 1)
+    );
+    assert_eq!(
+        Finance::apply_slippage(price, &Side::Buy, Decimal::ZERO),
+        price
+    );
+}
+
+#[tokio::test]
+async fn test_market_buy_records_slipped_fill() {
+    let state = Arc::new(Mutex::new(AppState::new()));
+    {
+        let mut guard = state.lock().unwrap();
+        guard.deposit(Decimal::from(1000));
+        guard.set_slippage_bps(Decimal::from(50));
+    }
+
+    let fill = Finance::create_buy_with_params(
+        &state,
+        "AAPL".to_string(),
+        Decimal::from(2),
+        Decimal::from(100),
+    )
+    .await
+    .unwrap();
+
+    let guard = state.lock().unwrap();
+    assert_eq!(fill, Decimal::new(1005, 1));
+    assert_eq!(guard.get_trades()[0].get_price_per(), Decimal::new(1005, 1));
+    assert_eq!(guard.check_balance(), Decimal::from(799));
+    assert_eq!(
+        guard.get_holdings_map()["AAPL"].get_avg_price(),
+        Decimal::new(1005, 1)
+    );
+}
+
+#[tokio::test]
+async fn test_market_sell_records_slipped_fill() {
+    let state = Arc::new(Mutex::new(AppState::new()));
+    {
+        let mut guard = state.lock().unwrap();
+        guard.deposit(Decimal::from(1000));
+    }
+    Finance::create_buy_with_params(
+        &state,
+        "AAPL".to_string(),
+        Decimal::from(4),
+        Decimal::from(100),
+    )
+    .await
+    .unwrap();
+    state.lock().unwrap().set_slippage_bps(Decimal::from(100));
+
+    let fill = Finance::create_sell_with_params(
+        &state,
+        "AAPL".to_string(),
+        Decimal::from(1),
+        Decimal::from(100),
+    )
+    .await
+    .unwrap();
+
+    let guard = state.lock().unwrap();
+    assert_eq!(fill, Decimal::from(99));
+    assert_eq!(guard.get_trades()[1].get_price_per(), Decimal::from(99));
+    assert_eq!(guard.check_balance(), Decimal::from(699));
+}
+
+// ===== Cash Yield Tests =====
+
+const DAY: i64 = 86_400;
+
+#[test]
+fn test_cash_yield_compounds_daily() {
+    let mut state = AppState::new();
+    state.deposit(Decimal::from(1000));
+    // 36.5% APY is exactly 0.1% per day
+    state.set_cash_apy(Decimal::new(365, 1), 1_000);
+
+    let interest = state.accrue_cash_yield(1_000 + 3 * DAY);
+
+    // 1.00 + 1.001 + 1.002001, credited to the cent
+    assert_eq!(interest, Decimal::new(300, 2));
+    assert_eq!(state.check_balance(), Decimal::new(100300, 2));
+
+    let trade = &state.get_trades()[0];
+    assert_eq!(trade.get_symbol(), "CASH");
+    assert_eq!(trade.get_order_type(), "Interest");
+    assert_eq!(trade.get_quantity(), Decimal::new(300, 2));
+}
+
+#[test]
+fn test_cash_yield_does_not_double_accrue() {
+    let mut state = AppState::new();
+    state.deposit(Decimal::from(1000));
+    state.set_cash_apy(Decimal::new(365, 1), 0);
+    state.set_yield_accrued_at(1_000);
+
+    state.accrue_cash_yield(1_000 + DAY + DAY / 2);
+    let balance_after_first = state.check_balance();
+
+    // Same moment again, and a "restart" within the same partial day
+    assert_eq!(
+        state.accrue_cash_yield(1_000 + DAY + DAY / 2),
+        Decimal::ZERO
+    );
+    assert_eq!(
+        state.accrue_cash_yield(1_000 + DAY + DAY - 1),
+        Decimal::ZERO
+    );
+    assert_eq!(state.check_balance(), balance_after_first);
+    assert_eq!(state.get_yield_accrued_at(), 1_000 + DAY);
+    assert_eq!(state.get_trades().len(), 1);
+}
+
+#[test]
+fn test_cash_yield_disabled_by_default() {
+    let mut state = AppState::new();
+    state.deposit(Decimal::from(1000));
+    state.set_yield_accrued_at(1_000);
+
+    assert_eq!(state.accrue_cash_yield(1_000 + 30 * DAY), Decimal::ZERO);
+    assert_eq!(state.check_balance(), Decimal::from(1000));
+    assert!(state.get_trades().is_empty());
+}
+
+#[test]
+fn test_cash_yield_settings_round_trip() {
+    let mut state = AppState::new();
+    state.set_cash_apy(Decimal::new(45, 1), 12_345);
+
+    let mut restored = AppState::new();
+    for (key, value) in state.get_settings() {
+        restored.apply_setting(&key, &value);
+    }
+
+    assert_eq!(restored.get_cash_apy(), Decimal::new(45, 1));
+    assert_eq!(restored.get_yield_accrued_at(), 12_345);
+}
+
+// ===== Closing Position Tests =====
+
+#[tokio::test]
+async fn test_selling_full_fractional_position_removes_holding() {
+    let state = Arc::new(Mutex::new(AppState::new()));
+    state.lock().unwrap().deposit(Decimal::from(1000));
+    let qty = Finance::quantity_for_notional(Decimal::from(100), Decimal::from(3));
+    Finance::create_buy_with_params(&state, "AAPL".to_string(), qty, Decimal::from(3))
+        .await
+        .unwrap();
+
+    let held = state
+        .lock()
+        .unwrap()
+        .get_ticker_holdings_qty(&"AAPL".to_string());
+    Finance::create_sell_with_params(&state, "AAPL".to_string(), held, Decimal::from(3))
+        .await
+        .unwrap();
+
+    assert!(state.lock().unwrap().get_holdings_map().is_empty());
+}
+
+#[tokio::test]
+async fn test_selling_down_to_dust_removes_holding() {
+    let state = Arc::new(Mutex::new(AppState::new()));
+    state.lock().unwrap().deposit(Decimal::from(1000));
+    Finance::create_buy_with_params(&state, "AAPL".to_string(), Decimal::ONE, Decimal::from(10))
+        .await
+        .unwrap();
+
+    let almost_all = Decimal::ONE - Finance::QTY_DUST;
+    Finance::create_sell_with_params(&state, "AAPL".to_string(), almost_all, Decimal::from(10))
+        .await
+        .unwrap();
+
+    assert!(state.lock().unwrap().get_holdings_map().is_empty());
+}
+
+// ===== Dollar-amount Sizing Tests =====
+
+#[test]
+fn test_quantity_for_notional_allows_fractional_shares() {
+    let qty = Finance::quantity_for_notional(Decimal::from(100), Decimal::from(400));
+
+    assert_eq!(qty, Decimal::new(25, 2));
+}
+
+#[test]
+fn test_quantity_for_notional_never_exceeds_amount() {
+    let price = Decimal::from(3);
+    let qty = Finance::quantity_for_notional(Decimal::from(10), price);
+
+    assert_eq!(qty, Decimal::new(333333333, 8));
+    assert!(qty * price <= Decimal::from(10));
+    assert_eq!(
+        Finance::quantity_for_notional(Decimal::from(10), Decimal::ZERO),
+        Decimal::ZERO
+    );
+}
+
+// ===== Compare Tests =====
+
+fn day(d: u32) -> chrono::NaiveDate {
+    chrono::NaiveDate::from_ymd_opt(2024, 3, d).unwrap()
+}
+
+#[test]
+fn test_normalize_aligns_on_common_dates() {
+    let series = vec![
+        (
+            "AAA".to_string(),
+            vec![
+                (day(1), Decimal::from(50)),
+                (day(2), Decimal::from(55)),
+                (day(4), Decimal::from(60)),
+            ],
+        ),
+        (
+            "BBB".to_string(),
+            vec![
+                (day(2), Decimal::from(200)),
+                (day(3), Decimal::from(210)),
+                (day(4), Decimal::from(180)),
+            ],
+        ),
+    ];
+
+    let normalized = Finance::normalize_on_common_dates(&series);
+
+    // Only day 2 and day 4 are shared, so both series start at 100 on day 2
+    assert_eq!(normalized.len(), 2);
+    assert_eq!(normalized[0].1.len(), 2);
+    assert_eq!(normalized[0].1[0], Decimal::from(100));
+    assert_eq!(normalized[1].1[0], Decimal::from(100));
+    assert_eq!(normalized[1].1[1], Decimal::from(90));
+}
+
+#[test]
+fn test_rank_by_return_sorts_best_first_and_skips_empty_series() {
+    let series = vec![
+        (
+            "LOSER".to_string(),
+            vec![(day(1), Decimal::from(100)), (day(2), Decimal::from(80))],
+        ),
+        ("EMPTY".to_string(), vec![]),
+        (
+            "WINNER".to_string(),
+            vec![(day(1), Decimal::from(10)), (day(2), Decimal::from(12))],
+        ),
+    ];
+
+    let ranked = Finance::rank_by_return(&Finance::normalize_on_common_dates(&series));
+
+    assert_eq!(
+        ranked,
+        vec![
+            ("WINNER".to_string(), Decimal::from(20)),
+            ("LOSER".to_string(), Decimal::from(-20)),
+        ]
+    );
+}
+
+#[test]
+fn test_ascii_sparkline_spans_shared_scale() {
+    let values = vec![Decimal::from(90), Decimal::from(100), Decimal::from(110)];
+
+    let line = Finance::ascii_sparkline(&values, Decimal::from(90), Decimal::from(110), 10);
+
+    assert_eq!(line, "_-^");
+}
+
+// ===== Asset Type Tests =====
+
+#[test]
+fn test_asset_type_inferred_from_symbol() {
+    use naviin::Finance::AssetType;
+    assert_eq!(AssetType::for_symbol("BTC-USD"), AssetType::Crypto);
+    assert_eq!(AssetType::for_symbol("eth-eur"), AssetType::Crypto);
+    assert_eq!(AssetType::for_symbol("AAPL"), AssetType::Stock);
+    // Share classes aren't currency pairs
+    assert_eq!(AssetType::for_symbol("BRK-B"), AssetType::Stock);
+    assert!(AssetType::Crypto.trades_24_7());
+}
+
+#[test]
+fn test_crypto_pair_appends_quote_currency() {
+    assert_eq!(Finance::crypto_pair("btc", None), "BTC-USD");
+    assert_eq!(Finance::crypto_pair("ETH", Some("eur")), "ETH-EUR");
+    assert_eq!(Finance::crypto_pair("SOL-USD", Some("EUR")), "SOL-USD");
+}
+
+// ===== Symbol Normalization Tests =====
+
+fn normalize(raw: &str) -> String {
+    Finance::normalize_symbol(raw, &BTreeMap::new())
+}
+
+#[test]
+fn test_crypto_spellings_normalize_to_yahoo_pairs() {
+    assert_eq!(normalize("btc"), "BTC-USD");
+    assert_eq!(normalize(" Eth "), "ETH-USD");
+    assert_eq!(normalize("eth/usdt"), "ETH-USDT");
+    assert_eq!(normalize("btc-eur"), "BTC-EUR");
+    assert_eq!(normalize("BTC-USD"), "BTC-USD");
+}
+
+#[test]
+fn test_share_classes_use_a_dash_but_exchange_suffixes_stay() {
+    assert_eq!(normalize("brk.b"), "BRK-B");
+    assert_eq!(normalize("BF.A"), "BF-A");
+    assert_eq!(normalize("brk/b"), "BRK-B");
+    assert_eq!(normalize("BRK-B"), "BRK-B");
+    // London and Tokyo listings keep their exchange suffix
+    assert_eq!(normalize("vod.l"), "VOD.L");
+    assert_eq!(normalize("7203.T"), "7203.T");
+    assert_eq!(normalize("shop.to"), "SHOP.TO");
+}
+
+#[test]
+fn test_index_nicknames_map_to_caret_symbols() {
+    assert_eq!(normalize("spx"), "^GSPC");
+    assert_eq!(normalize("ndx"), "^NDX");
+    assert_eq!(normalize("^gspc"), "^GSPC");
+    assert_eq!(normalize("EURUSD=X"), "EURUSD=X");
+    assert_eq!(normalize("aapl"), "AAPL");
+}
+
+#[test]
+fn test_user_aliases_are_checked_before_built_in_spellings() {
+    let aliases = BTreeMap::from([
+        ("BITCOIN".to_string(), "BTC-USD".to_string()),
+        ("BTC".to_string(), "BTC".to_string()),
+    ]);
+    assert_eq!(Finance::normalize_symbol("bitcoin", &aliases), "BTC-USD");
+    assert_eq!(Finance::normalize_symbol("btc", &aliases), "BTC");
+    assert_eq!(Finance::normalize_symbol("eth", &aliases), "ETH-USD");
+}
+
+// ===== P&L Percentage Tests =====
+
+#[test]
+fn test_pnl_pct_against_average_cost() {
+    let holding = Finance::Holding::new("AAPL".to_string(), Decimal::from(4), Decimal::from(80));
+
+    assert_eq!(holding.pnl_pct(Decimal::from(100)), Some(Decimal::from(25)));
+    assert_eq!(holding.pnl_pct(Decimal::from(60)), Some(Decimal::from(-25)));
+    assert_eq!(holding.pnl_pct(Decimal::ZERO), None);
+
+    let free = Finance::Holding::new("GIFT".to_string(), Decimal::ONE, Decimal::ZERO);
+    assert_eq!(free.pnl_pct(Decimal::from(10)), None);
+}
+
+#[test]
+fn test_percentages_of_an_empty_total_are_none() {
+    assert_eq!(
+        Finance::percent_of(Decimal::from(25), Decimal::from(200)),
+        Some(Decimal::new(125, 1))
+    );
+    assert_eq!(Finance::percent_of(Decimal::ZERO, Decimal::ZERO), None);
+    assert_eq!(
+        Finance::percent_of(Decimal::from(5), Decimal::from(-10)),
+        None
+    );
+
+    // Every consumer reports the empty case rather than dividing by zero
+    assert_eq!(
+        Finance::day_change_pct(Decimal::from(10), Decimal::ZERO),
+        None
+    );
+    assert!(
+        naviin::rebalance::plan_rebalance(
+            &std::collections::HashMap::new(),
+            &std::collections::HashMap::new(),
+            Decimal::ZERO,
+            &[("VTI".to_string(), Decimal::from(60))],
+        )
+        .is_empty()
+    );
+    assert_eq!(
+        naviin::risk::concentration_pct(Decimal::ZERO, Decimal::ZERO),
+        Decimal::ONE_HUNDRED
+    );
+    assert!(
+        naviin::margin::MarginStatus::new(Decimal::ZERO, Decimal::ZERO, Decimal::ONE).is_none()
+    );
+}
+
+// ===== Position Fill Tests =====
+
+// (qty, avg) after filling `quantity` at `price` against an optional (qty, avg) position
+fn fill(position: Option<(i64, i64)>, quantity: i64, price: i64) -> Option<(Decimal, Decimal)> {
+    let symbol = "AAPL".to_string();
+    let holding = position.map(|(qty, avg)| {
+        Finance::Holding::new(symbol.clone(), Decimal::from(qty), Decimal::from(avg))
+    });
+    Finance::apply_fill(
+        holding.as_ref(),
+        &symbol,
+        Decimal::from(quantity),
+        Decimal::from(price),
+    )
+    .map(|holding| (holding.get_qty(), holding.get_avg_price()))
+}
+
+fn position(qty: i64, avg: i64) -> Option<(Decimal, Decimal)> {
+    Some((Decimal::from(qty), Decimal::from(avg)))
+}
+
+#[test]
+fn test_fill_matrix_keeps_average_cost_economic() {
+    let cases = [
+        // Opening a long and a short at the fill price
+        (None, 10, 100, position(10, 100)),
+        (None, -10, 100, position(-10, 100)),
+        // Adding to a long or a short averages the cost in
+        (Some((10, 100)), 10, 120, position(20, 110)),
+        (Some((-10, 100)), -30, 80, position(-40, 85)),
+        // Trimming either side keeps the average
+        (Some((10, 100)), -4, 150, position(6, 100)),
+        (Some((-10, 100)), 4, 50, position(-6, 100)),
+        // Closing exactly leaves nothing
+        (Some((10, 100)), -10, 90, None),
+        (Some((-10, 100)), 10, 90, None),
+        // Crossing zero in one order opens the rest at the fill price
+        (Some((10, 100)), -25, 90, position(-15, 90)),
+        (Some((-10, 100)), 25, 110, position(15, 110)),
+    ];
+    for (start, quantity, price, expected) in cases {
+        assert_eq!(
+            fill(start, quantity, price),
+            expected,
+            "{:?} filled {} @ {}",
+            start,
+            quantity,
+            price
+        );
+    }
+}
+
+#[test]
+fn test_short_pnl_and_dust_after_a_fill() {
+    // A short at 100 gains as the price falls
+    let short = Finance::apply_fill(
+        None,
+        &"TSLA".to_string(),
+        Decimal::from(-5),
+        Decimal::from(100),
+    )
+    .unwrap();
+    assert_eq!(short.pnl(Decimal::from(80)), Decimal::from(100));
+
+    // A remainder within QTY_DUST counts as flat
+    let left = Finance::apply_fill(
+        Some(&Finance::Holding::new(
+            "BTC-USD".to_string(),
+            Decimal::ONE,
+            Decimal::from(100),
+        )),
+        &"BTC-USD".to_string(),
+        -(Decimal::ONE - Finance::QTY_DUST),
+        Decimal::from(100),
+    );
+    assert!(left.is_none());
+}
@@ -0,0 +1,295 @@
+use naviin::AppState::AppState;
+use naviin::Finance::{AssetType, Holding};
+use naviin::Orders::Trade;
+use naviin::components::holdings::HoldingsComponent;
+use naviin::format::{
+    Align, MoneyFormat, NumberStyle, TextTable, csv_decimal, csv_field, csv_money, format_amount,
+    format_currency, format_fixed, format_pct, format_price, format_qty, price_decimals,
+    quantity_decimals, sum_rounded,
+};
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+
+// ===== Currency Formatting Tests =====
+
+#[test]
+fn test_format_currency_zero() {
+    assert_eq!(format_currency(Decimal::ZERO, "$"), "$0.00");
+    // A tiny negative that rounds to zero is not shown as "-$0.00"
+    assert_eq!(format_currency(Decimal::new(-1, 3), "$"), "$0.00");
+}
+
+#[test]
+fn test_format_currency_large_values() {
+    assert_eq!(
+        format_currency(Decimal::new(100000000000, 2), "$"),
+        "$1,000,000,000.00"
+    );
+    assert_eq!(format_currency(Decimal::from(999), "$"), "$999.00");
+    assert_eq!(format_currency(Decimal::from(1000), "$"), "$1,000.00");
+    assert_eq!(
+        format_currency(Decimal::new(1234567891, 3), "€"),
+        "€1,234,567.89"
+    );
+}
+
+#[test]
+fn test_format_currency_negative_values() {
+    assert_eq!(format_currency(Decimal::new(-125000, 2), "$"), "-$1,250.00");
+    assert_eq!(format_currency(Decimal::new(-5, 1), "£"), "-£0.50");
+}
+
+#[test]
+fn test_format_amount_rounds_half_away_from_zero() {
+    assert_eq!(format_amount(Decimal::new(12345, 3)), "12.35");
+    assert_eq!(format_amount(Decimal::new(-12345, 3)), "-12.35");
+    assert_eq!(format_amount(Decimal::new(1234567, 0)), "1,234,567.00");
+}
+
+#[test]
+fn test_fixed_and_percent_round_instead_of_truncating() {
+    // Decimal's own {:.2} would give 2.67 and +0.01%
+    assert_eq!(format_fixed(Decimal::new(2675, 3), 2), "2.68");
+    assert_eq!(format_pct(Decimal::new(15, 3)), "+0.02%");
+    assert_eq!(format_pct(Decimal::new(-15, 3)), "-0.02%");
+}
+
+// ===== Rounded Totals Tests =====
+
+#[test]
+fn test_total_matches_sum_of_displayed_rows() {
+    let rows = [
+        Decimal::new(1005, 3),
+        Decimal::new(2005, 3),
+        Decimal::new(3005, 3),
+    ];
+    let displayed: Vec<String> = rows.iter().map(|row| format_amount(*row)).collect();
+    assert_eq!(displayed, vec!["1.01", "2.01", "3.01"]);
+
+    // The unrounded sum 6.015 shows as 6.01 or 6.02, short of what the rows add up to
+    let unrounded: Decimal = rows.iter().sum();
+    assert_eq!(format!("{:.2}", unrounded), "6.01");
+    assert_eq!(format_amount(unrounded), "6.02");
+    assert_eq!(format_amount(sum_rounded(rows)), "6.03");
+}
+
+#[test]
+fn test_holdings_total_pnl_uses_rounded_rows() {
+    let mut holdings = HashMap::new();
+    let mut prices = HashMap::new();
+    for symbol in ["AAA", "BBB", "CCC"] {
+        // 1 share with a half-cent gain each
+        holdings.insert(
+            symbol.to_string(),
+            Holding::new(symbol.to_string(), Decimal::ONE, Decimal::from(10)),
+        );
+        prices.insert(symbol.to_string(), Decimal::new(10005, 3));
+    }
+    let mut component = HoldingsComponent::new();
+    component.update_holdings(holdings, Decimal::ZERO);
+    component.update_prices(prices);
+
+    // Each row shows $0.01, so the total is $0.03 rather than $0.015 -> $0.02
+    assert_eq!(component.total_pnl(), Decimal::new(3, 2));
+}
+
+// ===== Price Precision Tests =====
+
+#[test]
+fn test_format_price_sub_cent_crypto_keeps_significant_digits() {
+    assert_eq!(
+        format_price(Decimal::new(1234, 8), AssetType::Crypto),
+        "0.00001234"
+    );
+    assert_eq!(
+        format_price(Decimal::new(123456, 6), AssetType::Crypto),
+        "0.1235"
+    );
+    assert_eq!(price_decimals(Decimal::new(1, 9), AssetType::Crypto), 8);
+}
+
+#[test]
+fn test_format_qty_keeps_tiny_crypto_amounts_visible() {
+    assert_eq!(
+        format_qty(Decimal::new(31, 5), AssetType::Crypto),
+        "0.00031"
+    );
+    assert_eq!(
+        format_qty(Decimal::new(1, 8), AssetType::Crypto),
+        "0.00000001"
+    );
+    assert_eq!(
+        format_qty(Decimal::new(123456789, 9), AssetType::Crypto),
+        "0.1235"
+    );
+    assert_eq!(format_qty(Decimal::new(15, 1), AssetType::Crypto), "1.5");
+    assert_eq!(quantity_decimals(Decimal::new(1, 12), AssetType::Crypto), 8);
+}
+
+#[test]
+fn test_format_qty_drops_decimals_for_whole_share_counts() {
+    assert_eq!(format_qty(Decimal::from(1200), AssetType::Stock), "1200");
+    assert_eq!(
+        format_qty(Decimal::new(120000, 2), AssetType::Stock),
+        "1200"
+    );
+    assert_eq!(format_qty(Decimal::new(25, 1), AssetType::Stock), "2.5");
+    assert_eq!(
+        format_qty(Decimal::new(123456, 3), AssetType::Stock),
+        "123.46"
+    );
+    assert_eq!(format_qty(Decimal::new(5, 1), AssetType::Stock), "0.5");
+    assert_eq!(quantity_decimals(Decimal::ZERO, AssetType::Stock), 0);
+}
+
+#[test]
+fn test_format_price_stock_precision_is_capped() {
+    assert_eq!(
+        format_price(Decimal::new(1234, 8), AssetType::Stock),
+        "0.0000"
+    );
+    assert_eq!(
+        format_price(Decimal::new(4567, 4), AssetType::Stock),
+        "0.4567"
+    );
+}
+
+#[test]
+fn test_format_price_dollar_and_up_uses_cents() {
+    assert_eq!(
+        format_price(Decimal::new(6425051, 2), AssetType::Crypto),
+        "64,250.51"
+    );
+    assert_eq!(format_price(Decimal::ONE, AssetType::Crypto), "1.00");
+    assert_eq!(format_price(Decimal::ZERO, AssetType::Stock), "0.00");
+}
+
+// ===== CSV Number Tests =====
+
+#[test]
+fn test_csv_decimal_is_plain_normalized_and_parses_back() {
+    let cases = [
+        ("0.000001", "0.000001"),
+        ("1.500000", "1.5"),
+        ("-0.00", "0"),
+        (
+            "79228162514264337593543950335",
+            "79228162514264337593543950335",
+        ),
+    ];
+    for (value, expected) in cases {
+        let value = value.parse::<Decimal>().unwrap();
+        assert_eq!(csv_decimal(value), expected);
+        assert_eq!(expected.parse::<Decimal>().unwrap(), value);
+    }
+    assert_eq!(
+        csv_decimal(Decimal::from_scientific("1e-7").unwrap()),
+        "0.0000001"
+    );
+    assert_eq!(csv_money(Decimal::new(2675, 3)), "2.68");
+    assert_eq!(csv_money(Decimal::new(150000, 2)), "1500");
+}
+
+#[test]
+fn test_csv_field_quotes_only_when_needed() {
+    assert_eq!(csv_field("AAPL"), "AAPL");
+    assert_eq!(csv_field("BRK,B"), "\"BRK,B\"");
+    assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+    assert_eq!(csv_field("two\nlines"), "\"two\nlines\"");
+}
+
+// ===== Money Format Setting Tests =====
+
+#[test]
+fn test_money_format_separator_styles() {
+    let amount = Decimal::new(-123456789, 2);
+
+    assert_eq!(
+        MoneyFormat::new("€", NumberStyle::Eu).money(amount),
+        "-€1.234.567,89"
+    );
+    assert_eq!(
+        MoneyFormat::new("€", NumberStyle::Fr).money(amount),
+        "-€1 234 567,89"
+    );
+    assert_eq!(
+        MoneyFormat::new("CHF ", NumberStyle::Ch).money(amount),
+        "-CHF 1'234'567.89"
+    );
+    assert_eq!(MoneyFormat::default().money(amount), "-$1,234,567.89");
+}
+
+#[test]
+fn test_money_format_prices_keep_precision_and_style() {
+    let eu = MoneyFormat::new("€", NumberStyle::Eu);
+
+    assert_eq!(
+        eu.money_price(Decimal::new(1234, 8), AssetType::Crypto),
+        "€0,00001234"
+    );
+    assert_eq!(
+        eu.price(Decimal::new(6425051, 2), AssetType::Stock),
+        "64.250,51"
+    );
+    assert_eq!(NumberStyle::parse("EU"), Some(NumberStyle::Eu));
+    assert_eq!(NumberStyle::parse("xx"), None);
+}
+
+// ===== Table Layout Tests =====
+
+#[test]
+fn test_text_table_widens_columns_to_fit_large_values() {
+    let mut table = TextTable::new(&[Align::Left, Align::Right, Align::Right])
+        .with_header(&["Symbol", "Qty", "Price"]);
+    table.push_row(vec!["A".to_string(), "1".to_string(), "$1.00".to_string()]);
+    table.push_row(vec![
+        "BTC-USD".to_string(),
+        "0.125".to_string(),
+        "$123,456.78".to_string(),
+    ]);
+    table.push_row(vec!["MSFT".to_string(), "1500".to_string()]);
+
+    assert_eq!(
+        table.lines(),
+        vec![
+            "Symbol    Qty       Price",
+            "A           1       $1.00",
+            "BTC-USD 0.125 $123,456.78",
+            "MSFT     1500",
+        ]
+    );
+    assert_eq!(table.line_width(), 25);
+}
+
+#[test]
+fn test_trade_history_columns_align_with_mixed_widths() {
+    let mut state = AppState::new();
+    state.add_trade(Trade::buy_at(
+        "F".to_string(),
+        Decimal::ONE,
+        Decimal::new(1234, 2),
+        1_700_000_000,
+    ));
+    state.add_trade(Trade::buy_at(
+        "BTC-USD".to_string(),
+        Decimal::new(25, 2),
+        Decimal::new(12345678, 2),
+        1_700_000_100,
+    ));
+
+    let history = state.display_trades(None, None);
+    let rows: Vec<&str> = history
+        .lines()
+        .filter(|line| line.starts_with("Market"))
+        .collect();
+    assert_eq!(rows.len(), 2);
+    // Every price ends in the same column and every time starts in the same column
+    let price_ends: Vec<usize> = rows
+        .iter()
+        .map(|row| row.find(".78").or_else(|| row.find(".34")).unwrap() + 3)
+        .collect();
+    assert_eq!(price_ends[0], price_ends[1]);
+    let time_starts: Vec<usize> = rows.iter().map(|row| row.find("20").unwrap()).collect();
+    assert_eq!(time_starts[0], time_starts[1]);
+    assert!(rows[0].contains("$123,456.78"));
+}
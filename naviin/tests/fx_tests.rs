@@ -0,0 +1,302 @@
+mod common;
+
+use naviin::AppState::AppState;
+use naviin::FinanceProvider::MockProvider;
+use naviin::Storage;
+use naviin::commands::process_command;
+use naviin::fx::{parse_currency, trading_currency};
+use rust_decimal::Decimal;
+use sea_orm::DatabaseConnection;
+use std::sync::atomic::AtomicBool;
+use std::sync::{Arc, Mutex};
+
+async fn setup() -> (
+    Arc<MockProvider>,
+    Arc<Mutex<AppState>>,
+    DatabaseConnection,
+    Arc<AtomicBool>,
+) {
+    let provider = Arc::new(MockProvider::new());
+    provider.set_price("AAPL", Decimal::from(100));
+    provider.set_price("SAP.DE", Decimal::from(200));
+    provider.set_price("EURUSD=X", Decimal::new(11, 1));
+    let (state, db, running) = common::setup(Some(provider.clone()), None).await;
+    (provider, state, db, running)
+}
+
+// ===== Currency Tests =====
+
+#[test]
+fn test_trading_currency_from_suffix_and_crypto_pair() {
+    assert_eq!(trading_currency("AAPL"), "USD");
+    assert_eq!(trading_currency("SAP.DE"), "EUR");
+    assert_eq!(trading_currency("shop.to"), "CAD");
+    assert_eq!(trading_currency("BTC-EUR"), "EUR");
+    assert_eq!(trading_currency("ETH-USDT"), "USD");
+    // Pence quotes and share classes stay in the base currency
+    assert_eq!(trading_currency("VOD.L"), "USD");
+    assert_eq!(trading_currency("BRK-B"), "USD");
+
+    assert_eq!(parse_currency("eur"), Some("EUR".to_string()));
+    assert_eq!(parse_currency("EURO"), None);
+    assert_eq!(parse_currency("E1R"), None);
+}
+
+// ===== Bucket Tests =====
+
+#[tokio::test]
+async fn test_funding_two_currencies_keeps_separate_buckets_and_persists() {
+    let (_, state, db, running) = setup().await;
+
+    let result = process_command("fund 1000", &state, &db, &running).await;
+    assert_eq!(result, "Added $1,000.00 to account");
+    let result = process_command("fund 500 eur", &state, &db, &running).await;
+    assert_eq!(result, "Added 500.00 EUR to account (worth $550.00)");
+    let result = process_command("withdraw 600 EUR", &state, &db, &running).await;
+    assert_eq!(result, "Insufficient balance. Current: 500.00 EUR");
+    process_command("withdraw 100 EUR", &state, &db, &running).await;
+    let result = process_command("fund 10 EURO", &state, &db, &running).await;
+    assert!(result.starts_with("Invalid currency: 'EURO'"));
+
+    {
+        let guard = state.lock().unwrap();
+        assert_eq!(guard.check_balance(), Decimal::from(1000));
+        assert_eq!(
+            guard.currency_balances(),
+            vec![
+                ("USD".to_string(), Decimal::from(1000)),
+                ("EUR".to_string(), Decimal::from(400)),
+            ]
+        );
+        // Foreign flows are in the ledger at their USD value
+        let flows = guard.get_cash_flows();
+        let values: Vec<_> = flows
+            .iter()
+            .map(|flow| (flow.currency_code(), flow.signed_amount()))
+            .collect();
+        assert_eq!(
+            values,
+            vec![
+                ("USD", Decimal::from(1000)),
+                ("EUR", Decimal::from(550)),
+                ("EUR", Decimal::from(-110)),
+            ]
+        );
+    }
+
+    let loaded = Storage::try_load_state(&db).await.unwrap();
+    assert_eq!(loaded.currency_balance("EUR"), Decimal::from(400));
+    assert_eq!(loaded.check_balance(), Decimal::from(1000));
+    assert_eq!(
+        loaded.get_cash_flows(),
+        state.lock().unwrap().get_cash_flows()
+    );
+
+    let undone = process_command("undo", &state, &db, &running).await;
+    assert!(undone.contains("Returned 100.00 EUR to cash"), "{}", undone);
+    let guard = state.lock().unwrap();
+    assert_eq!(guard.currency_balance("EUR"), Decimal::from(500));
+    assert_eq!(guard.get_cash_flows().len(), 2);
+}
+
+#[tokio::test]
+async fn test_foreign_deposits_count_toward_the_cap() {
+    let (_, state, db, running) = setup().await;
+    process_command("cap 1000", &state, &db, &running).await;
+    process_command("fund 500", &state, &db, &running).await;
+
+    let result = process_command("fund 500 EUR", &state, &db, &running).await;
+    assert_eq!(
+        result,
+        "Deposit would exceed the $1,000.00 deposit cap. You can add up to $500.00 more"
+    );
+    let result = process_command("fund 400 EUR", &state, &db, &running).await;
+    assert_eq!(result, "Added 400.00 EUR to account (worth $440.00)");
+    let result = process_command("fund 100", &state, &db, &running).await;
+    assert!(
+        result.contains("You can add up to $60.00 more"),
+        "{}",
+        result
+    );
+
+    let undone = process_command("undo", &state, &db, &running).await;
+    assert!(undone.contains("Removed 400.00 EUR deposit"), "{}", undone);
+    assert_eq!(
+        state.lock().unwrap().deposit_headroom(),
+        Some(Decimal::from(500))
+    );
+}
+
+#[tokio::test]
+async fn test_currency_without_a_rate_is_rejected() {
+    let (_, state, db, running) = setup().await;
+    process_command("fund 1000", &state, &db, &running).await;
+
+    let result = process_command("fund 10 XYZ", &state, &db, &running).await;
+    assert_eq!(result, "No exchange rate for XYZ to USD");
+    assert!(state.lock().unwrap().foreign_currencies().is_empty());
+
+    // The account can still be valued, so snapshots keep being recorded
+    process_command("performance", &state, &db, &running).await;
+    let snapshots = state.lock().unwrap().get_equity_snapshots();
+    assert_eq!(snapshots[0].value, Decimal::from(1000));
+}
+
+#[tokio::test]
+async fn test_foreign_quoted_holdings_are_converted() {
+    let (_, state, db, running) = setup().await;
+    process_command("fund 1000", &state, &db, &running).await;
+    process_command("fund 1000 EUR", &state, &db, &running).await;
+    process_command("buy SAP.DE 2", &state, &db, &running).await;
+
+    // 1000 USD + 600 EUR cash + 400 EUR of shares, at 1.1
+    let result = process_command("summary --json", &state, &db, &running).await;
+    let json: serde_json::Value = serde_json::from_str(&result).unwrap();
+    assert_eq!(json["holdings_value"], "440.0");
+    assert_eq!(json["total_value"], "2100.0");
+    process_command("performance", &state, &db, &running).await;
+    let snapshots = state.lock().unwrap().get_equity_snapshots();
+    assert_eq!(snapshots[0].value, Decimal::from(2100));
+
+    // Two more shares would make the position 880 of 2100
+    process_command("risklimit position 35", &state, &db, &running).await;
+    let result = process_command("buy SAP.DE 2", &state, &db, &running).await;
+    assert!(
+        result.contains("SAP.DE would be 41.90% of the portfolio"),
+        "{}",
+        result
+    );
+    let result = process_command("buy SAP.DE 1", &state, &db, &running).await;
+    assert!(result.starts_with("Bought"), "{}", result);
+}
+
+#[tokio::test]
+async fn test_trades_settle_in_the_assets_currency_bucket() {
+    let (_, state, db, running) = setup().await;
+    process_command("fund 1000", &state, &db, &running).await;
+
+    // Without a EUR bucket the listing still settles in USD
+    process_command("buy SAP.DE 1", &state, &db, &running).await;
+    assert_eq!(state.lock().unwrap().check_balance(), Decimal::from(800));
+
+    process_command("fund 500 EUR", &state, &db, &running).await;
+    process_command("buy SAP.DE 2", &state, &db, &running).await;
+    let result = process_command("buy SAP.DE 1", &state, &db, &running).await;
+    assert!(
+        result.contains("Need 200.00 EUR, have 100.00 EUR"),
+        "{}",
+        result
+    );
+    process_command("buy AAPL 1", &state, &db, &running).await;
+    {
+        let guard = state.lock().unwrap();
+        assert_eq!(guard.currency_balance("EUR"), Decimal::from(100));
+        assert_eq!(guard.check_balance(), Decimal::from(700));
+    }
+
+    process_command("sell SAP.DE 3", &state, &db, &running).await;
+    let guard = state.lock().unwrap();
+    assert_eq!(guard.currency_balance("EUR"), Decimal::from(700));
+    assert_eq!(guard.check_balance(), Decimal::from(700));
+}
+
+#[tokio::test]
+async fn test_buys_are_checked_against_their_own_bucket() {
+    let (_, state, db, running) = setup().await;
+    process_command("fund 500 EUR", &state, &db, &running).await;
+
+    // Only the EUR bucket has money in it
+    let result = process_command("buy SAP.DE 2", &state, &db, &running).await;
+    assert!(result.starts_with("Bought"), "{}", result);
+    let result = process_command("buy SAP.DE 1", &state, &db, &running).await;
+    assert!(
+        result.contains("Need 200.00 EUR, have 100.00 EUR"),
+        "{}",
+        result
+    );
+
+    // Pending USD buys don't use up EUR, nor EUR buys USD
+    process_command("fund 1000", &state, &db, &running).await;
+    let result = process_command("buylimit AAPL 10 100", &state, &db, &running).await;
+    assert!(result.starts_with("Buy limit order created"), "{}", result);
+    let result = process_command("buylimit SAP.DE 1 90", &state, &db, &running).await;
+    assert!(result.starts_with("Buy limit order created"), "{}", result);
+    let result = process_command("buylimit SAP.DE 1 20", &state, &db, &running).await;
+    assert!(result.contains("enough of cash"), "{}", result);
+    let guard = state.lock().unwrap();
+    assert_eq!(guard.get_available_cash_in("EUR"), Decimal::from(10));
+    assert_eq!(guard.get_available_cash(), Decimal::ZERO);
+}
+
+#[tokio::test]
+async fn test_summary_lists_buckets_with_a_converted_total() {
+    let (provider, state, db, running) = setup().await;
+    process_command("fund 1000", &state, &db, &running).await;
+    process_command("fund 500 EUR", &state, &db, &running).await;
+
+    provider.set_price("EURUSD=X", Decimal::ZERO);
+    let summary = process_command("summary", &state, &db, &running).await;
+    assert!(summary.contains("Cash by currency:"));
+    assert!(summary.contains("no rate"));
+    assert!(summary.contains("Total cash: N/A"));
+
+    provider.set_price("EURUSD=X", Decimal::new(11, 1));
+    let summary = process_command("summary", &state, &db, &running).await;
+    assert!(summary.contains("USD  $1,000.00"), "{}", summary);
+    assert!(summary.contains("EUR 500.00 EUR = $550.00"), "{}", summary);
+    assert!(summary.contains("Total cash: $1,550.00 (USD)"));
+}
+
+#[tokio::test]
+async fn test_fills_keep_the_currency_they_settled_in() {
+    let (_, state, db, running) = setup().await;
+    process_command("fund 1000", &state, &db, &running).await;
+    // Settles in USD: there is no EUR bucket yet
+    process_command("buy SAP.DE 1", &state, &db, &running).await;
+    process_command("fund 500 EUR", &state, &db, &running).await;
+    process_command("buy SAP.DE 1", &state, &db, &running).await;
+
+    let trades = state.lock().unwrap().get_trades();
+    let currencies: Vec<Option<&str>> = trades.iter().map(|trade| trade.get_currency()).collect();
+    assert_eq!(currencies, vec![Some("USD"), Some("EUR")]);
+    let loaded = Storage::try_load_state(&db).await.unwrap();
+    assert_eq!(loaded.get_trades()[0].get_currency(), Some("USD"));
+
+    // Opening the bucket later doesn't move the first buy into euros
+    let replayed = process_command("replay", &state, &db, &running).await;
+    assert!(
+        replayed.ends_with("Stored account matches the trade log"),
+        "{}",
+        replayed
+    );
+
+    // Undo refunds each buy to the bucket it paid from
+    process_command("undo", &state, &db, &running).await;
+    process_command("undo", &state, &db, &running).await;
+    let undone = process_command("undo", &state, &db, &running).await;
+    assert!(undone.contains("refunded $200.00"), "{}", undone);
+    let guard = state.lock().unwrap();
+    assert_eq!(guard.check_balance(), Decimal::from(1000));
+    assert_eq!(guard.currency_balance("EUR"), Decimal::ZERO);
+}
+
+#[tokio::test]
+async fn test_account_value_converts_other_currencies() {
+    let (provider, state, db, running) = setup().await;
+    process_command("fund 1000", &state, &db, &running).await;
+    process_command("fund 500 EUR", &state, &db, &running).await;
+
+    provider.set_price("EURUSD=X", Decimal::ZERO);
+    let result = process_command("summary --json", &state, &db, &running).await;
+    let json: serde_json::Value = serde_json::from_str(&result).unwrap();
+    assert!(json["total_value"].is_null(), "{}", result);
+
+    provider.set_price("EURUSD=X", Decimal::new(11, 1));
+    let result = process_command("summary --json", &state, &db, &running).await;
+    let json: serde_json::Value = serde_json::from_str(&result).unwrap();
+    assert_eq!(json["total_value"], "1550.0");
+
+    process_command("performance", &state, &db, &running).await;
+    let snapshots = state.lock().unwrap().get_equity_snapshots();
+    assert_eq!(snapshots[0].value, Decimal::from(1550));
+}
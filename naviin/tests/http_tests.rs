@@ -0,0 +1,96 @@
+#![cfg(feature = "http")]
+
+use naviin::AppState::AppState;
+use naviin::Finance::Holding;
+use naviin::FinanceProvider::MockProvider;
+use naviin::Orders::{OpenOrder, OrderType, Side};
+use naviin::http::serve;
+use rust_decimal::Decimal;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+const TOKEN: &str = "secret-token";
+
+// Serves an account with $500 cash and 2 AAPL priced at $100 on a random local port
+async fn start_server() -> std::net::SocketAddr {
+    let provider = Arc::new(MockProvider::new());
+    provider.set_price("AAPL", Decimal::from(100));
+    let mut state = AppState::with_provider(provider);
+    state.deposit(Decimal::from(500));
+    state.set_holdings_map(HashMap::from([(
+        "AAPL".to_string(),
+        Holding::new("AAPL".to_string(), Decimal::from(2), Decimal::from(90)),
+    )]));
+    state
+        .add_open_order(OpenOrder::new(
+            "AAPL".to_string(),
+            Decimal::ONE,
+            Decimal::from(80),
+            OrderType::StopLoss,
+            Side::Sell,
+        ))
+        .unwrap();
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(serve(
+        listener,
+        Arc::new(Mutex::new(state)),
+        TOKEN.to_string(),
+    ));
+    addr
+}
+
+// Sends a raw request and splits the response into (status code, JSON body)
+async fn request(addr: std::net::SocketAddr, path: &str, token: Option<&str>) -> (u16, Value) {
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+    let auth = token
+        .map(|token| format!("Authorization: Bearer {}\r\n", token))
+        .unwrap_or_default();
+    let raw = format!("GET {} HTTP/1.1\r\nHost: localhost\r\n{}\r\n", path, auth);
+    stream.write_all(raw.as_bytes()).await.unwrap();
+
+    let mut response = String::new();
+    stream.read_to_string(&mut response).await.unwrap();
+    let status = response[9..12].parse().unwrap();
+    let body = response.split("\r\n\r\n").nth(1).unwrap();
+    (status, serde_json::from_str(body).unwrap())
+}
+
+// ===== Status Endpoint Tests =====
+
+#[tokio::test]
+async fn test_portfolio_marks_holdings_to_market() {
+    let addr = start_server().await;
+
+    let (status, body) = request(addr, "/portfolio", Some(TOKEN)).await;
+
+    assert_eq!(status, 200);
+    assert_eq!(body["cash"], "500");
+    assert_eq!(body["holdings"][0]["symbol"], "AAPL");
+    assert_eq!(body["holdings"][0]["market_value"], "200");
+    assert_eq!(body["total_value"], "700");
+}
+
+#[tokio::test]
+async fn test_orders_lists_open_orders() {
+    let addr = start_server().await;
+
+    let (status, body) = request(addr, "/orders", Some(TOKEN)).await;
+
+    assert_eq!(status, 200);
+    assert_eq!(body["orders"][0]["order_type"], "StopLoss");
+    assert_eq!(body["orders"][0]["price"], "80");
+}
+
+#[tokio::test]
+async fn test_requests_without_valid_token_are_rejected() {
+    let addr = start_server().await;
+
+    assert_eq!(request(addr, "/portfolio", None).await.0, 401);
+    assert_eq!(request(addr, "/portfolio", Some("wrong")).await.0, 401);
+    assert_eq!(request(addr, "/missing", Some(TOKEN)).await.0, 404);
+}
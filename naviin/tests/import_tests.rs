@@ -0,0 +1,271 @@
+use naviin::AppState::AppState;
+use naviin::import::{import_watchlist_from_file, is_valid_symbol};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+// Helper to write a uniquely named fixture into the system temp directory
+fn write_fixture(name: &str, contents: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("naviin_{}_{}", std::process::id(), name));
+    fs::write(&path, contents).unwrap();
+    path
+}
+
+// ===== Watchlist File Import Tests =====
+
+#[test]
+fn test_loadwatch_dedupes_and_skips_blank_lines() {
+    let path = write_fixture("watch.txt", "aapl\n\nMSFT, tsla\nAAPL\n  \nmsft\n");
+    let state = Arc::new(Mutex::new(AppState::new()));
+    state.lock().unwrap().add_to_watchlist("TSLA".to_string());
+
+    let report = import_watchlist_from_file(&state, path.to_str().unwrap()).unwrap();
+
+    assert_eq!(
+        report,
+        "Added 2 symbols to watchlist (3 already present, 0 invalid)."
+    );
+    assert_eq!(
+        state.lock().unwrap().get_watchlist(),
+        vec!["TSLA".to_string(), "AAPL".to_string(), "MSFT".to_string()]
+    );
+
+    let _ = fs::remove_file(path);
+}
+
+#[test]
+fn test_loadwatch_reports_invalid_symbols() {
+    let path = write_fixture("watch_invalid.txt", "GOOG,not a ticker,WAYTOOLONGSYMBOL1\n");
+    let state = Arc::new(Mutex::new(AppState::new()));
+
+    let report = import_watchlist_from_file(&state, path.to_str().unwrap()).unwrap();
+
+    assert!(report.starts_with("Added 1 symbols to watchlist (0 already present, 2 invalid)."));
+    assert_eq!(
+        state.lock().unwrap().get_watchlist(),
+        vec!["GOOG".to_string()]
+    );
+
+    let _ = fs::remove_file(path);
+}
+
+#[test]
+fn test_loadwatch_missing_file() {
+    let state = Arc::new(Mutex::new(AppState::new()));
+
+    let result = import_watchlist_from_file(&state, "/definitely/not/here.txt");
+
+    assert!(result.unwrap_err().starts_with("Failed to open file"));
+}
+
+#[test]
+fn test_symbol_shape_check() {
+    assert!(is_valid_symbol("AAPL"));
+    assert!(is_valid_symbol("BRK-B"));
+    assert!(is_valid_symbol("^GSPC"));
+    assert!(is_valid_symbol("EURUSD=X"));
+    assert!(!is_valid_symbol(""));
+    assert!(!is_valid_symbol("A B"));
+    assert!(!is_valid_symbol("---"));
+    assert!(!is_valid_symbol("ABCDEFGHIJKLM"));
+}
+
+// ===== Trade CSV Import Tests =====
+
+#[tokio::test]
+async fn test_import_crypto_rows_use_quote_pair_symbol() {
+    let path = write_fixture(
+        "crypto_trades.csv",
+        "date,asset,asset_type,side,quantity,price,currency\n\
+         2024-01-02,btc,CRYPTO,BUY,0.015,42000,USD\n\
+         2024-01-03,ETH,CRYPTO,BUY,1.5,2300,EUR\n\
+         2024-01-04,AAPL,STOCK,BUY,2,185,USD\n",
+    );
+    let state = Arc::new(Mutex::new(AppState::new()));
+
+    naviin::import::import_trades_from_csv(&state, path.to_str().unwrap())
+        .await
+        .unwrap();
+
+    let guard = state.lock().unwrap();
+    assert_eq!(
+        guard.get_ticker_holdings_qty(&"BTC-USD".to_string()),
+        rust_decimal::Decimal::new(15, 3)
+    );
+    assert_eq!(
+        guard.get_ticker_holdings_qty(&"ETH-EUR".to_string()),
+        rust_decimal::Decimal::new(15, 1)
+    );
+    assert_eq!(
+        guard.get_ticker_holdings_qty(&"AAPL".to_string()),
+        rust_decimal::Decimal::from(2)
+    );
+    drop(guard);
+
+    let _ = fs::remove_file(path);
+}
+
+#[tokio::test]
+async fn test_dry_run_matches_real_import_without_changing_state() {
+    // The sell on line 3 needs the buy on line 2; line 5 sells more than is held,
+    // and line 6 has a bad quantity
+    let path = write_fixture(
+        "dry_run_trades.csv",
+        "date,asset,asset_type,side,quantity,price\n\
+         2024-01-02,AAPL,STOCK,BUY,10,150\n\
+         2024-01-03,AAPL,STOCK,SELL,4,160\n\
+         2024-01-04,MSFT,STOCK,BUY,2,300\n\
+         2024-01-05,AAPL,STOCK,SELL,7,170\n\
+         2024-01-06,MSFT,STOCK,BUY,two,300\n",
+    );
+    let path = path.to_str().unwrap();
+    let state = Arc::new(Mutex::new(AppState::new()));
+
+    let preview = naviin::import::dry_run_trades_from_csv(&state, path)
+        .await
+        .unwrap();
+    assert!(
+        preview.starts_with("Dry run: would import 3 trades (2 skipped). 2 errors."),
+        "{}",
+        preview
+    );
+    assert!(preview.contains("Insufficient holdings for AAPL (have 6, need 7)"));
+    assert!(preview.ends_with("Nothing was changed"));
+    {
+        let guard = state.lock().unwrap();
+        assert!(guard.get_trades().is_empty());
+        assert!(guard.get_holdings_map().is_empty());
+    }
+
+    let report = naviin::import::import_trades_from_csv(&state, path)
+        .await
+        .unwrap();
+    assert!(
+        report.starts_with("Imported 3 trades (2 skipped). 2 errors."),
+        "{}",
+        report
+    );
+    assert_eq!(state.lock().unwrap().get_trades().len(), 3);
+
+    let _ = fs::remove_file(path);
+}
+
+const COMPRESSED_FIXTURE: &str = "date,asset,asset_type,side,quantity,price\n\
+     2024-01-02,AAPL,STOCK,BUY,10,150\n\
+     2024-01-03,AAPL,STOCK,SELL,4,160\n\
+     2024-01-04,btc,CRYPTO,BUY,0.5,42000\n\
+     2024-01-05,MSFT,STOCK,BUY,two,300\n";
+
+// Imports a fixture into a fresh state, returning the report and the resulting trades
+async fn import_fixture(path: &std::path::Path) -> (Result<String, String>, String) {
+    let state = Arc::new(Mutex::new(AppState::new()));
+    let report = naviin::import::import_trades_from_csv(&state, path.to_str().unwrap()).await;
+    let trades = format!("{:?}", state.lock().unwrap().get_trades());
+    (report, trades)
+}
+
+// Builds a zip archive with uncompressed (stored) entries
+fn zip_archive(entries: &[(&str, &[u8])]) -> Vec<u8> {
+    let mut archive = Vec::new();
+    let mut directory = Vec::new();
+    for (name, data) in entries {
+        let mut crc = flate2::Crc::new();
+        crc.update(data);
+        let offset = archive.len() as u32;
+        let mut fields = Vec::new();
+        fields.extend_from_slice(&[0; 6]); // flags, method (stored), time
+        fields.extend_from_slice(&[0; 2]); // date
+        fields.extend_from_slice(&crc.sum().to_le_bytes());
+        fields.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        fields.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        fields.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        fields.extend_from_slice(&[0; 2]); // extra length
+
+        archive.extend_from_slice(b"PK\x03\x04\x14\x00");
+        archive.extend_from_slice(&fields);
+        archive.extend_from_slice(name.as_bytes());
+        archive.extend_from_slice(data);
+
+        directory.extend_from_slice(b"PK\x01\x02\x14\x00\x14\x00");
+        directory.extend_from_slice(&fields);
+        directory.extend_from_slice(&[0; 12]); // comment, disk, attributes
+        directory.extend_from_slice(&offset.to_le_bytes());
+        directory.extend_from_slice(name.as_bytes());
+    }
+    let directory_offset = archive.len() as u32;
+    archive.extend_from_slice(&directory);
+    archive.extend_from_slice(b"PK\x05\x06\x00\x00\x00\x00");
+    archive.extend_from_slice(&(entries.len() as u16).to_le_bytes());
+    archive.extend_from_slice(&(entries.len() as u16).to_le_bytes());
+    archive.extend_from_slice(&(directory.len() as u32).to_le_bytes());
+    archive.extend_from_slice(&directory_offset.to_le_bytes());
+    archive.extend_from_slice(&[0; 2]); // comment length
+    archive
+}
+
+#[tokio::test]
+async fn test_import_gzipped_csv_matches_plain_file() {
+    use std::io::Write;
+
+    let plain = write_fixture("compressed_trades.csv", COMPRESSED_FIXTURE);
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder.write_all(COMPRESSED_FIXTURE.as_bytes()).unwrap();
+    let gzipped = std::env::temp_dir().join(format!(
+        "naviin_{}_compressed_trades.csv.gz",
+        std::process::id()
+    ));
+    fs::write(&gzipped, encoder.finish().unwrap()).unwrap();
+
+    let (plain_report, plain_trades) = import_fixture(&plain).await;
+    let (gzip_report, gzip_trades) = import_fixture(&gzipped).await;
+
+    assert!(
+        plain_report
+            .as_ref()
+            .unwrap()
+            .starts_with("Imported 3 trades (1 skipped)."),
+        "{:?}",
+        plain_report
+    );
+    assert_eq!(gzip_report, plain_report);
+    assert_eq!(gzip_trades, plain_trades);
+
+    let _ = fs::remove_file(plain);
+    let _ = fs::remove_file(gzipped);
+}
+
+#[tokio::test]
+async fn test_import_zip_needs_a_single_csv_entry() {
+    let plain = write_fixture("zip_trades.csv", COMPRESSED_FIXTURE);
+    let single = std::env::temp_dir().join(format!("naviin_{}_single.zip", std::process::id()));
+    fs::write(
+        &single,
+        zip_archive(&[("trades.csv", COMPRESSED_FIXTURE.as_bytes())]),
+    )
+    .unwrap();
+    let multiple = std::env::temp_dir().join(format!("naviin_{}_multiple.zip", std::process::id()));
+    fs::write(
+        &multiple,
+        zip_archive(&[
+            ("trades.csv", COMPRESSED_FIXTURE.as_bytes()),
+            ("notes.txt", b"exported 2024-01-06"),
+        ]),
+    )
+    .unwrap();
+
+    let (plain_report, plain_trades) = import_fixture(&plain).await;
+    let (zip_report, zip_trades) = import_fixture(&single).await;
+    assert_eq!(zip_report, plain_report);
+    assert_eq!(zip_trades, plain_trades);
+
+    let (multiple_report, multiple_trades) = import_fixture(&multiple).await;
+    assert_eq!(
+        multiple_report.unwrap_err(),
+        "Zip archive must contain exactly one CSV file (found 2 entries)"
+    );
+    assert_eq!(multiple_trades, "[]");
+
+    let _ = fs::remove_file(plain);
+    let _ = fs::remove_file(single);
+    let _ = fs::remove_file(multiple);
+}
@@ -0,0 +1,175 @@
+use naviin::AppState::{AppState, lock_state};
+use naviin::commands::process_command;
+use naviin::components::input::{DEFAULT_MAX_COMMAND_LEN, InputComponent};
+use ratatui::layout::{Position, Rect};
+use sea_orm::Database;
+use std::sync::atomic::AtomicBool;
+use std::sync::{Arc, Mutex};
+
+// Helper to type a whole string into the input
+fn type_text(input: &mut InputComponent, text: &str) {
+    for ch in text.chars() {
+        input.enter_char(ch);
+    }
+}
+
+// ===== Cursor Rendering Tests =====
+
+#[test]
+fn test_cursor_starts_inside_border() {
+    let input = InputComponent::new();
+    let area = Rect::new(0, 10, 20, 3);
+
+    assert_eq!(input.cursor_screen_position(area), Position::new(1, 11));
+}
+
+#[test]
+fn test_cursor_follows_typing_and_movement() {
+    let mut input = InputComponent::new();
+    let area = Rect::new(0, 0, 20, 3);
+    type_text(&mut input, "buy");
+    assert_eq!(input.cursor_screen_position(area), Position::new(4, 1));
+
+    input.move_cursor_left();
+    input.move_cursor_left();
+    assert_eq!(input.cursor_screen_position(area), Position::new(2, 1));
+
+    input.move_cursor_start();
+    assert_eq!(input.cursor_screen_position(area), Position::new(1, 1));
+}
+
+#[test]
+fn test_cursor_stays_visible_for_long_commands() {
+    let mut input = InputComponent::new();
+    // 10 wide box leaves 8 columns inside the border
+    let area = Rect::new(0, 0, 10, 3);
+    type_text(&mut input, "buylimit AAPL 10 145");
+
+    // Cursor pinned to the last inner column while scrolled
+    assert_eq!(input.cursor_screen_position(area), Position::new(8, 1));
+
+    input.move_cursor_start();
+    assert_eq!(input.cursor_screen_position(area), Position::new(1, 1));
+}
+
+// ===== Multi-byte Input Tests =====
+
+#[test]
+fn test_insert_multibyte_characters() {
+    let mut input = InputComponent::new();
+    type_text(&mut input, "n€te 📈");
+
+    assert_eq!(input.get_command(), "n€te 📈");
+}
+
+#[test]
+fn test_insert_in_middle_of_multibyte_text() {
+    let mut input = InputComponent::new();
+    type_text(&mut input, "€€");
+    input.move_cursor_left();
+    input.enter_char('é');
+
+    assert_eq!(input.get_command(), "€é€");
+}
+
+#[test]
+fn test_backspace_multibyte_characters() {
+    let mut input = InputComponent::new();
+    type_text(&mut input, "a📈b€");
+
+    input.backspace();
+    assert_eq!(input.get_command(), "a📈b");
+
+    input.move_cursor_left();
+    input.backspace();
+    assert_eq!(input.get_command(), "ab");
+
+    input.move_cursor_start();
+    input.backspace();
+    assert_eq!(input.get_command(), "ab");
+}
+
+#[test]
+fn test_cursor_end_counts_characters_not_bytes() {
+    let mut input = InputComponent::new();
+    type_text(&mut input, "€€€");
+    input.move_cursor_start();
+    input.move_cursor_end();
+    input.enter_char('!');
+
+    assert_eq!(input.get_command(), "€€€!");
+}
+
+#[test]
+fn test_cursor_right_stops_at_last_multibyte_character() {
+    let mut input = InputComponent::new();
+    type_text(&mut input, "📈");
+    input.move_cursor_right();
+    input.move_cursor_right();
+    input.enter_char('x');
+
+    assert_eq!(input.get_command(), "📈x");
+}
+
+#[test]
+fn test_staged_command_is_completed_by_typing() {
+    let mut input = InputComponent::new();
+    type_text(&mut input, "old text");
+    input.set_command("sell BTC-USD ");
+    type_text(&mut input, "0.5");
+
+    assert_eq!(input.get_command(), "sell BTC-USD 0.5");
+}
+
+// ===== Length Cap Tests =====
+
+#[test]
+fn test_input_stops_growing_at_the_cap() {
+    let mut input = InputComponent::new();
+    type_text(&mut input, &"x".repeat(DEFAULT_MAX_COMMAND_LEN + 100));
+    assert_eq!(input.get_command().chars().count(), DEFAULT_MAX_COMMAND_LEN);
+    assert!(input.is_at_limit());
+    assert!(!input.enter_char('y'));
+
+    // Room made by deleting is usable again
+    input.backspace();
+    assert!(!input.is_at_limit());
+    assert!(input.enter_char('y'));
+    assert!(input.get_command().ends_with('y'));
+
+    input.set_max_len(4);
+    input.clear();
+    type_text(&mut input, "buy AAPL");
+    assert_eq!(input.get_command(), "buy ");
+    input.set_command("sell AAPL 5");
+    assert_eq!(input.get_command(), "sell");
+}
+
+#[tokio::test]
+async fn test_over_long_command_is_rejected_without_running() {
+    let state = Arc::new(Mutex::new(AppState::new()));
+    let db = Database::connect("sqlite::memory:").await.unwrap();
+    let running = Arc::new(AtomicBool::new(false));
+
+    let padded = format!("fund 100 {}", "x".repeat(600));
+    let result = process_command(&padded, &state, &db, &running).await;
+    assert_eq!(
+        result,
+        "Command too long: 609 characters (limit 512). Change it with: inputlimit <chars>"
+    );
+
+    let many = format!("watch{}", " AAPL".repeat(65));
+    let result = process_command(&many, &state, &db, &running).await;
+    assert_eq!(result, "Too many arguments (limit 64)");
+    assert!(lock_state(&state).get_cash_flows().is_empty());
+
+    let result = process_command("inputlimit 1000", &state, &db, &running).await;
+    assert_eq!(result, "Commands are now limited to 1000 characters");
+    let result = process_command(&padded, &state, &db, &running).await;
+    assert!(!result.starts_with("Command too long"), "{}", result);
+    let result = process_command("inputlimit 10", &state, &db, &running).await;
+    assert_eq!(
+        result,
+        "Command length limit must be between 64 and 8192 characters"
+    );
+}
@@ -0,0 +1,129 @@
+use naviin::AppState::AppState;
+use naviin::Finance::Holding;
+use naviin::FinanceProvider::MockProvider;
+use naviin::Storage;
+use naviin::cashflow::CashFlowKind;
+use naviin::commands::process_command;
+use rust_decimal::Decimal;
+use sea_orm::Database;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::AtomicBool;
+use std::sync::{Arc, Mutex};
+
+// Fresh directory under the system temp dir for one test's portfolio files
+fn scratch_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("naviin_{}_{}", name, std::process::id()));
+    std::fs::remove_dir_all(&dir).ok();
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+// Account that deposited `deposit` and spent it all but `cash` on the holdings
+fn account(deposit: i64, cash: i64, holdings: &[(&str, i64, i64)]) -> AppState {
+    let mut state = AppState::new();
+    state.set_cash_balance(Decimal::from(cash));
+    state.record_cash_flow(CashFlowKind::Deposit, Decimal::from(deposit));
+    let holdings: HashMap<String, Holding> = holdings
+        .iter()
+        .map(|(symbol, qty, avg)| {
+            (
+                symbol.to_string(),
+                Holding::new(symbol.to_string(), Decimal::from(*qty), Decimal::from(*avg)),
+            )
+        })
+        .collect();
+    state.set_holdings_map(holdings);
+    state
+}
+
+// Writes the account to `<dir>/<name>.sqlite`
+async fn save_portfolio(dir: &Path, name: &str, account: AppState) {
+    let path = dir.join(format!("{}.sqlite", name));
+    let (db, _) = Storage::open(&format!("sqlite://{}?mode=rwc", path.display()))
+        .await
+        .unwrap();
+    Storage::save_state(&Arc::new(Mutex::new(account)), &db).await;
+    db.close().await.ok();
+}
+
+// ===== Leaderboard Tests =====
+
+#[tokio::test]
+async fn test_leaderboard_ranks_saved_portfolios_by_return() {
+    let dir = scratch_dir("leaderboard");
+    // alpha: 500 cash + 5 AAPL at 120 on 1000 deposited = +10%
+    save_portfolio(&dir, "alpha", account(1000, 500, &[("AAPL", 5, 100)])).await;
+    // beta: 500 cash + 5 MSFT at 150 on 1000 deposited = +25%
+    save_portfolio(&dir, "beta", account(1000, 500, &[("MSFT", 5, 100)])).await;
+    // gamma: 900 cash and an unpriceable holding on 1000 deposited = -10% so far
+    save_portfolio(&dir, "gamma", account(1000, 900, &[("GONE", 1, 100)])).await;
+
+    let provider = Arc::new(MockProvider::new());
+    provider.set_price("AAPL", Decimal::from(120));
+    provider.set_price("MSFT", Decimal::from(150));
+    let state = Arc::new(Mutex::new(AppState::with_provider(provider)));
+    let db = Database::connect("sqlite::memory:").await.unwrap();
+    let running = Arc::new(AtomicBool::new(false));
+
+    let board = process_command(
+        &format!("leaderboard {}", dir.display()),
+        &state,
+        &db,
+        &running,
+    )
+    .await;
+    let rows: Vec<&str> = board.lines().collect();
+    assert_eq!(rows[0], "Leaderboard:");
+    assert!(
+        rows[2].contains("beta") && rows[2].contains("+25.00%"),
+        "{}",
+        board
+    );
+    assert!(
+        rows[3].contains("alpha") && rows[3].contains("+10.00%"),
+        "{}",
+        board
+    );
+    assert!(
+        rows[4].contains("gamma *") && rows[4].contains("-10.00%"),
+        "{}",
+        board
+    );
+    assert_eq!(rows[5], "* gamma: partial data, no price for GONE");
+    std::fs::remove_dir_all(&dir).ok();
+}
+
+#[tokio::test]
+async fn test_leaderboard_uses_the_live_session_for_its_own_file() {
+    let dir = scratch_dir("leaderboard_current");
+    save_portfolio(&dir, "mine", account(1000, 1000, &[])).await;
+    save_portfolio(&dir, "other", account(1000, 1050, &[])).await;
+
+    // The session has gained since its last save
+    let state = Arc::new(Mutex::new(account(1000, 1200, &[])));
+    state
+        .lock()
+        .unwrap()
+        .set_backup_path(Some(dir.join("mine.sqlite.bak")));
+    let db = Database::connect("sqlite::memory:").await.unwrap();
+    let running = Arc::new(AtomicBool::new(false));
+
+    let board = process_command("leaderboard", &state, &db, &running).await;
+    let rows: Vec<&str> = board.lines().collect();
+    assert!(
+        rows[2].contains("mine (current)") && rows[2].contains("+20.00%"),
+        "{}",
+        board
+    );
+    assert!(
+        rows[3].contains("other") && rows[3].contains("+5.00%"),
+        "{}",
+        board
+    );
+
+    let memory = Arc::new(Mutex::new(AppState::new()));
+    let result = process_command("leaderboard", &memory, &db, &running).await;
+    assert!(result.starts_with("This session isn't saved to a file"));
+    std::fs::remove_dir_all(&dir).ok();
+}
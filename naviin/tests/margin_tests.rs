@@ -0,0 +1,199 @@
+mod common;
+
+use chrono::Duration;
+use naviin::AppState::{AppState, check_maintenance};
+use naviin::FinanceProvider::MockProvider;
+use naviin::cashflow::CashFlowKind;
+use naviin::clock::{Clock, MockClock};
+use naviin::commands::process_command;
+use naviin::events::EventKind;
+use naviin::margin::{self, MarginStatus};
+use rust_decimal::Decimal;
+use sea_orm::DatabaseConnection;
+use std::sync::atomic::AtomicBool;
+use std::sync::{Arc, Mutex};
+
+const START: i64 = 1_767_225_600;
+
+// Account with 1,000 cash and AAPL quoted at 100
+async fn setup() -> (
+    Arc<Mutex<AppState>>,
+    DatabaseConnection,
+    Arc<AtomicBool>,
+    Arc<MockProvider>,
+    Arc<MockClock>,
+) {
+    let provider = Arc::new(MockProvider::new());
+    provider.set_price("AAPL", Decimal::from(100));
+    let clock = Arc::new(MockClock::at_timestamp(START));
+    let (state, db, running) = common::setup(Some(provider.clone()), Some(clock.clone())).await;
+    state.lock().unwrap().deposit(Decimal::from(1_000));
+    (state, db, running, provider, clock)
+}
+
+#[test]
+fn test_buying_power_scales_cash_by_leverage() {
+    let cash = Decimal::from(1_000);
+    assert_eq!(margin::buying_power(cash, margin::NO_LEVERAGE), cash);
+    assert_eq!(
+        margin::buying_power(cash, Decimal::from(2)),
+        Decimal::from(2_000)
+    );
+    assert_eq!(
+        margin::buying_power(Decimal::ZERO, Decimal::from(2)),
+        Decimal::ZERO
+    );
+    // Below 1x is treated as no leverage rather than shrinking buying power
+    assert_eq!(margin::buying_power(cash, Decimal::new(5, 1)), cash);
+}
+
+#[tokio::test]
+async fn test_buy_past_cash_needs_leverage_and_borrows_the_difference() {
+    let (state, db, running, _, _) = setup().await;
+
+    let rejected = process_command("buy AAPL 15", &state, &db, &running).await;
+    assert_eq!(
+        rejected,
+        "Insufficient funds. Need $1,500.00, have $1,000.00"
+    );
+
+    let set = process_command("leverage 2", &state, &db, &running).await;
+    assert_eq!(set, "Leverage set to 2x: buying power $2,000.00");
+    let bought = process_command("buy AAPL 15", &state, &db, &running).await;
+    assert!(bought.starts_with("Bought 15 shares of AAPL"), "{}", bought);
+    {
+        let state_guard = state.lock().unwrap();
+        assert_eq!(state_guard.check_balance(), Decimal::ZERO);
+        assert_eq!(state_guard.get_margin_loan(), Decimal::from(500));
+        assert_eq!(state_guard.buying_power(), Decimal::ZERO);
+    }
+
+    // Proceeds repay the loan before adding to cash
+    process_command("sell AAPL 7", &state, &db, &running).await;
+    let state_guard = state.lock().unwrap();
+    assert_eq!(state_guard.get_margin_loan(), Decimal::ZERO);
+    assert_eq!(state_guard.check_balance(), Decimal::from(200));
+}
+
+#[tokio::test]
+async fn test_margin_loan_accrues_daily_interest() {
+    let (state, db, running, _, clock) = setup().await;
+    process_command("leverage 2", &state, &db, &running).await;
+    process_command("leverage rate 3.65", &state, &db, &running).await;
+    process_command("buy AAPL 20", &state, &db, &running).await;
+
+    let mut state_guard = state.lock().unwrap();
+    clock.advance(Duration::hours(12));
+    assert_eq!(
+        state_guard.accrue_margin_interest(clock.timestamp()),
+        Decimal::ZERO
+    );
+
+    // 0.01% a day on 1,000, compounded over two days
+    clock.advance(Duration::hours(36));
+    let charged = state_guard.accrue_margin_interest(clock.timestamp());
+    assert_eq!(charged, Decimal::new(20, 2));
+    assert_eq!(state_guard.get_margin_loan(), Decimal::new(100020, 2));
+}
+
+#[tokio::test]
+async fn test_maintenance_check_flags_once_without_selling() {
+    let (state, db, running, provider, _) = setup().await;
+    process_command("leverage 2", &state, &db, &running).await;
+    process_command("buy AAPL 20", &state, &db, &running).await;
+
+    // 20 shares at 60 = 1,200 against a 1,000 loan: equity is 16.7% of holdings
+    provider.set_price("AAPL", Decimal::from(60));
+    let status = check_maintenance(&state).await.unwrap();
+    assert!(status.is_margin_call());
+    assert_eq!(status.equity, Decimal::from(200));
+    check_maintenance(&state).await;
+
+    let mut state_guard = state.lock().unwrap();
+    let calls: Vec<_> = state_guard
+        .drain_events()
+        .into_iter()
+        .filter(|event| event.kind == EventKind::Error)
+        .collect();
+    assert_eq!(calls.len(), 1);
+    assert!(
+        calls[0]
+            .message
+            .starts_with("Margin call: equity is 16.7% of holdings")
+    );
+    assert_eq!(
+        state_guard.get_holdings_map()["AAPL"].get_qty(),
+        Decimal::from(20)
+    );
+
+    let healthy = MarginStatus::new(Decimal::ZERO, Decimal::from(2_000), Decimal::from(1_000));
+    assert!(!healthy.unwrap().is_margin_call());
+    assert!(MarginStatus::new(Decimal::ZERO, Decimal::from(2_000), Decimal::ZERO).is_none());
+}
+
+#[tokio::test]
+async fn test_summary_margin_line_matches_the_maintenance_check() {
+    let (state, db, running, provider, _) = setup().await;
+    provider.set_price("MSFT", Decimal::from(100));
+    process_command("leverage 2", &state, &db, &running).await;
+    process_command("buy MSFT 9", &state, &db, &running).await;
+    process_command("buy AAPL 2", &state, &db, &running).await;
+
+    // 9 MSFT at 100 and 2 AAPL at 10 = 920 against a 100 loan
+    provider.set_price("AAPL", Decimal::from(10));
+    let summary = process_command("summary", &state, &db, &running).await;
+    assert!(
+        summary.contains("Margin: equity $820.00 is 89.1% of holdings"),
+        "{}",
+        summary
+    );
+
+    // Counting the unpriced MSFT as worth nothing would report a false call
+    provider.set_price("MSFT", Decimal::ZERO);
+    let summary = process_command("summary", &state, &db, &running).await;
+    assert!(!summary.contains("of holdings (maintenance"), "{}", summary);
+    assert!(check_maintenance(&state).await.is_none());
+}
+
+#[tokio::test]
+async fn test_margin_interest_is_logged_and_survives_a_replay() {
+    let (state, db, running, _, clock) = setup().await;
+    // The setup deposit, in the ledger so the replay starts from it
+    state
+        .lock()
+        .unwrap()
+        .record_cash_flow(CashFlowKind::Deposit, Decimal::from(1_000));
+    process_command("leverage 2", &state, &db, &running).await;
+    process_command("leverage rate 3.65", &state, &db, &running).await;
+    process_command("buy AAPL 20", &state, &db, &running).await;
+    clock.advance(Duration::days(2));
+    let charged = state
+        .lock()
+        .unwrap()
+        .accrue_margin_interest(clock.timestamp());
+    assert_eq!(charged, Decimal::new(20, 2));
+
+    {
+        let state_guard = state.lock().unwrap();
+        let trades = state_guard.get_trades();
+        let charge = trades.last().unwrap();
+        assert_eq!(charge.get_symbol(), "CASH");
+        assert_eq!(charge.get_order_type(), "Interest");
+        assert_eq!(*charge.get_side(), naviin::Orders::Side::Sell);
+        assert_eq!(charge.get_quantity(), charged);
+    }
+
+    let compared = process_command("replay", &state, &db, &running).await;
+    assert!(
+        compared.ends_with("Stored account matches the trade log"),
+        "{}",
+        compared
+    );
+    // Applying a replay keeps the interest owed
+    state.lock().unwrap().set_margin_loan(Decimal::from(1000));
+    process_command("replay --apply", &state, &db, &running).await;
+    assert_eq!(
+        state.lock().unwrap().get_margin_loan(),
+        Decimal::new(100020, 2)
+    );
+}
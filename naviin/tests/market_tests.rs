@@ -0,0 +1,126 @@
+use chrono::{NaiveDate, NaiveTime, TimeZone, Utc};
+use naviin::AppState::AppState;
+use naviin::Finance::AssetType;
+use naviin::market::{ClosedReason, MarketCalendar, MarketStatus, new_york_time, us_holidays};
+
+fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(y, m, d).unwrap()
+}
+
+fn time(hour: u32, min: u32) -> NaiveTime {
+    NaiveTime::from_hms_opt(hour, min, 0).unwrap()
+}
+
+fn status(calendar: &MarketCalendar, y: i32, m: u32, d: u32, hour: u32, min: u32) -> MarketStatus {
+    calendar.status_at(Utc.with_ymd_and_hms(y, m, d, hour, min, 0).unwrap())
+}
+
+// ===== Session Hours Tests =====
+
+#[test]
+fn test_regular_session_respects_new_york_time() {
+    let calendar = MarketCalendar::default();
+
+    // January is EST (UTC-5): the session runs 14:30-21:00 UTC
+    assert_eq!(
+        status(&calendar, 2026, 1, 5, 14, 29),
+        MarketStatus::Closed(ClosedReason::PreMarket)
+    );
+    assert_eq!(status(&calendar, 2026, 1, 5, 14, 30), MarketStatus::Open);
+    assert_eq!(status(&calendar, 2026, 1, 5, 20, 59), MarketStatus::Open);
+    assert_eq!(
+        status(&calendar, 2026, 1, 5, 21, 0),
+        MarketStatus::Closed(ClosedReason::AfterHours)
+    );
+
+    // July is EDT (UTC-4): 13:30 UTC is already 9:30 in New York
+    assert_eq!(status(&calendar, 2026, 7, 6, 13, 30), MarketStatus::Open);
+}
+
+#[test]
+fn test_new_york_time_follows_the_tz_database() {
+    let at =
+        |y, m, d, hour, min| new_york_time(Utc.with_ymd_and_hms(y, m, d, hour, min, 0).unwrap());
+
+    // 2026 switches at 2:00 on March 8 and November 1
+    assert_eq!(at(2026, 3, 8, 6, 59).time(), time(1, 59));
+    assert_eq!(at(2026, 3, 8, 7, 0).time(), time(3, 0));
+    assert_eq!(at(2026, 11, 1, 5, 59).time(), time(1, 59));
+    assert_eq!(at(2026, 11, 1, 6, 0).time(), time(1, 0));
+
+    // Before 2007 daylight saving started in April
+    assert_eq!(at(2006, 3, 20, 14, 30).time(), time(9, 30));
+}
+
+#[test]
+fn test_weekends_and_holidays_are_closed() {
+    let calendar = MarketCalendar::default();
+
+    assert_eq!(
+        status(&calendar, 2026, 10, 17, 15, 0),
+        MarketStatus::Closed(ClosedReason::Weekend)
+    );
+    assert_eq!(
+        status(&calendar, 2026, 11, 26, 15, 0),
+        MarketStatus::Closed(ClosedReason::Holiday("Thanksgiving Day".to_string()))
+    );
+    assert!(
+        status(&calendar, 2026, 11, 26, 15, 0)
+            .to_string()
+            .starts_with("MARKET CLOSED")
+    );
+}
+
+#[test]
+fn test_us_holiday_rules_for_2026() {
+    let dates: Vec<NaiveDate> = us_holidays(2026).into_iter().map(|(d, _)| d).collect();
+
+    assert!(dates.contains(&date(2026, 1, 19))); // MLK, third Monday
+    assert!(dates.contains(&date(2026, 4, 3))); // Good Friday
+    assert!(dates.contains(&date(2026, 5, 25))); // Memorial Day, last Monday
+    // July 4th falls on a Saturday, so the market closes Friday
+    assert!(dates.contains(&date(2026, 7, 3)));
+    assert!(!dates.contains(&date(2026, 7, 4)));
+}
+
+#[test]
+fn test_saturday_new_year_is_not_observed() {
+    let dates: Vec<NaiveDate> = us_holidays(2022).into_iter().map(|(d, _)| d).collect();
+
+    assert!(!dates.contains(&date(2021, 12, 31)));
+    assert!(!dates.contains(&date(2022, 1, 1)));
+}
+
+// ===== Custom Holiday and Crypto Tests =====
+
+#[test]
+fn test_custom_holidays_close_market_and_persist() {
+    let mut state = AppState::new();
+    assert!(state.add_market_holiday(date(2026, 12, 24)));
+    assert!(!state.add_market_holiday(date(2026, 12, 24)));
+
+    let calendar = state.market_calendar();
+    assert_eq!(
+        status(&calendar, 2026, 12, 24, 15, 0),
+        MarketStatus::Closed(ClosedReason::Holiday("Custom holiday".to_string()))
+    );
+
+    let mut restored = AppState::new();
+    for (key, value) in state.get_settings() {
+        restored.apply_setting(&key, &value);
+    }
+    assert_eq!(restored.get_market_holidays(), vec![date(2026, 12, 24)]);
+    assert!(restored.remove_market_holiday(date(2026, 12, 24)));
+}
+
+#[test]
+fn test_crypto_is_always_open() {
+    let calendar = MarketCalendar::default();
+    let saturday = Utc.with_ymd_and_hms(2026, 10, 17, 3, 0, 0).unwrap();
+
+    assert_eq!(
+        calendar.status_for(AssetType::Crypto, saturday),
+        MarketStatus::AlwaysOpen
+    );
+    assert!(!calendar.status_for(AssetType::Stock, saturday).is_open());
+}
@@ -0,0 +1,98 @@
+use migration::{Migrator, MigratorTrait, SchemaManager};
+use naviin::AppState::AppState;
+use naviin::Finance::Holding;
+use naviin::Storage;
+use rust_decimal::Decimal;
+use sea_orm::{Database, DatabaseConnection};
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+
+const TABLES: &[&str] = &[
+    "app_state",
+    "holding",
+    "trade",
+    "open_order",
+    "watchlist",
+    "setting",
+    "note",
+    "cash_flow",
+    "equity_snapshot",
+    "order_template",
+    "recurring_buy",
+    "executed_order",
+];
+
+async fn missing_tables(db: &DatabaseConnection) -> Vec<&'static str> {
+    let manager = SchemaManager::new(db);
+    let mut missing = Vec::new();
+    for table in TABLES {
+        if !manager.has_table(*table).await.unwrap() {
+            missing.push(*table);
+        }
+    }
+    missing
+}
+
+// Saves an account holding 1.5 AAPL at 123.45 with 1000.25 cash
+async fn save_account(db: &DatabaseConnection) {
+    let mut account = AppState::new();
+    account.set_cash_balance(Decimal::from_str("1000.25").unwrap());
+    let mut holdings = HashMap::new();
+    holdings.insert(
+        "AAPL".to_string(),
+        Holding::new(
+            "AAPL".to_string(),
+            Decimal::from_str("1.5").unwrap(),
+            Decimal::from_str("123.45").unwrap(),
+        ),
+    );
+    account.set_holdings_map(holdings);
+    Storage::save_state(&Arc::new(Mutex::new(account)), db).await;
+}
+
+async fn assert_account(db: &DatabaseConnection) {
+    let state = Storage::try_load_state(db).await.unwrap();
+    assert_eq!(state.check_balance(), Decimal::from_str("1000.25").unwrap());
+    let holding = &state.get_holdings_map()["AAPL"];
+    assert_eq!(holding.get_qty(), Decimal::from_str("1.5").unwrap());
+    assert_eq!(
+        holding.get_avg_price(),
+        Decimal::from_str("123.45").unwrap()
+    );
+}
+
+// ===== Migration Tests =====
+
+#[tokio::test]
+async fn test_up_down_up_leaves_a_working_schema() {
+    let db = Database::connect("sqlite::memory:").await.unwrap();
+    Migrator::up(&db, None).await.unwrap();
+    // Running up against an existing database is a no-op
+    Migrator::up(&db, None).await.unwrap();
+    assert!(missing_tables(&db).await.is_empty());
+    save_account(&db).await;
+    assert_account(&db).await;
+
+    Migrator::down(&db, None).await.unwrap();
+    assert_eq!(missing_tables(&db).await, TABLES);
+
+    Migrator::up(&db, None).await.unwrap();
+    assert!(missing_tables(&db).await.is_empty());
+    save_account(&db).await;
+    assert_account(&db).await;
+}
+
+#[tokio::test]
+async fn test_reverting_the_latest_migration_keeps_data() {
+    let db = Database::connect("sqlite::memory:").await.unwrap();
+    Migrator::up(&db, None).await.unwrap();
+    save_account(&db).await;
+
+    // The cash flow currency, trade fee, trade currency, recurring anchor day and trade
+    // tag columns are dropped, text decimal columns go back to numeric, and all of it
+    // comes forward again
+    Migrator::down(&db, Some(6)).await.unwrap();
+    Migrator::up(&db, None).await.unwrap();
+    assert_account(&db).await;
+}
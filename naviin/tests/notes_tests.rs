@@ -0,0 +1,75 @@
+mod common;
+
+use naviin::Storage;
+use naviin::commands::process_command;
+use naviin::entities::note;
+use sea_orm::EntityTrait;
+
+// ===== Note Command Tests =====
+
+#[tokio::test]
+async fn test_note_set_and_show() {
+    let (state, db, running) = common::setup(None, None).await;
+
+    let result = process_command("note aapl Bought on earnings dip", &state, &db, &running).await;
+    assert_eq!(result, "Saved note for AAPL");
+
+    let shown = process_command("note AAPL", &state, &db, &running).await;
+    assert_eq!(shown, "AAPL: Bought on earnings dip");
+}
+
+#[tokio::test]
+async fn test_note_overwrite_replaces_text() {
+    let (state, db, running) = common::setup(None, None).await;
+    process_command("note TSLA first idea", &state, &db, &running).await;
+
+    let result = process_command("note TSLA second idea", &state, &db, &running).await;
+
+    assert_eq!(result, "Updated note for TSLA");
+    assert_eq!(
+        state.lock().unwrap().get_note(&"TSLA".to_string()),
+        Some(&"second idea".to_string())
+    );
+}
+
+#[tokio::test]
+async fn test_note_clear_removes_it() {
+    let (state, db, running) = common::setup(None, None).await;
+    process_command("note MSFT cloud thesis", &state, &db, &running).await;
+
+    let result = process_command("note MSFT --clear", &state, &db, &running).await;
+    assert_eq!(result, "Cleared note for MSFT");
+    assert!(state.lock().unwrap().get_notes().is_empty());
+
+    let again = process_command("note MSFT --clear", &state, &db, &running).await;
+    assert_eq!(again, "No note for MSFT");
+}
+
+#[tokio::test]
+async fn test_note_kept_after_unwatch() {
+    let (state, db, running) = common::setup(None, None).await;
+    process_command("addwatch NVDA --force", &state, &db, &running).await;
+    process_command("note NVDA AI capex", &state, &db, &running).await;
+    process_command("unwatch NVDA", &state, &db, &running).await;
+    process_command("addwatch NVDA --force", &state, &db, &running).await;
+
+    let shown = process_command("note NVDA", &state, &db, &running).await;
+    assert_eq!(shown, "NVDA: AI capex");
+}
+
+// ===== Persistence Tests =====
+
+#[tokio::test]
+async fn test_notes_saved_to_database() {
+    let (state, db, running) = common::setup(None, None).await;
+    process_command("note AAPL keep", &state, &db, &running).await;
+    process_command("note GOOG drop", &state, &db, &running).await;
+    process_command("note GOOG --clear", &state, &db, &running).await;
+
+    Storage::save_state(&state, &db).await;
+    let rows = note::Entity::find().all(&db).await.unwrap();
+
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0].symbol, "AAPL");
+    assert_eq!(rows[0].text, "keep");
+}
@@ -0,0 +1,121 @@
+use crossterm::event::KeyCode;
+use naviin::components::order_wizard::{
+    OrderWizardComponent, WizardAction, WizardOrder, WizardStep,
+};
+use naviin::format::MoneyFormat;
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+
+fn wizard(symbol: Option<&str>) -> OrderWizardComponent {
+    OrderWizardComponent::new(
+        vec!["AAPL".to_string(), "AMD".to_string(), "MSFT".to_string()],
+        HashMap::from([("AAPL".to_string(), Decimal::from(5))]),
+        Decimal::from(1000),
+        MoneyFormat::default(),
+        symbol.map(str::to_string),
+    )
+}
+
+// Types each character, returning the action for the last one
+fn type_text(wizard: &mut OrderWizardComponent, text: &str) -> WizardAction {
+    let mut action = WizardAction::Continue;
+    for c in text.chars() {
+        action = wizard.handle_key(KeyCode::Char(c));
+    }
+    action
+}
+
+// ===== Step Flow Tests =====
+
+#[test]
+fn test_buy_limit_walks_every_step_and_submits_command_line() {
+    let mut wizard = wizard(None);
+    wizard.handle_key(KeyCode::Down);
+    wizard.handle_key(KeyCode::Down);
+    assert_eq!(wizard.order(), WizardOrder::BuyLimit);
+    wizard.handle_key(KeyCode::Enter);
+    assert_eq!(wizard.step(), WizardStep::Symbol);
+
+    type_text(&mut wizard, "a");
+    assert_eq!(wizard.completions(), vec!["AAPL", "AMD"]);
+    wizard.handle_key(KeyCode::Tab);
+    assert_eq!(wizard.symbol(), "AAPL");
+    assert_eq!(
+        wizard.handle_key(KeyCode::Enter),
+        WizardAction::Quote("AAPL".to_string())
+    );
+
+    type_text(&mut wizard, "10");
+    assert_eq!(wizard.estimate().unwrap(), "Estimate: waiting for a price");
+    wizard.handle_key(KeyCode::Enter);
+    assert_eq!(wizard.step(), WizardStep::Price);
+    type_text(&mut wizard, "145");
+    assert_eq!(
+        wizard.estimate().unwrap(),
+        "Estimated cost: $1,450.00 of $1,000.00 cash (short by $450.00)"
+    );
+    assert_eq!(
+        wizard.handle_key(KeyCode::Enter),
+        WizardAction::Submit("buylimit AAPL 10 145".to_string())
+    );
+}
+
+#[test]
+fn test_market_sell_submits_after_quantity_and_uses_quote_for_estimate() {
+    let mut wizard = wizard(Some("AAPL"));
+    wizard.handle_key(KeyCode::Down);
+    wizard.handle_key(KeyCode::Enter);
+    wizard.handle_key(KeyCode::Enter);
+    wizard.set_quote("AAPL".to_string(), Decimal::from(200));
+
+    type_text(&mut wizard, "2");
+    assert_eq!(
+        wizard.estimate().unwrap(),
+        "Estimated proceeds: $400.00 (5 held)"
+    );
+    assert_eq!(
+        wizard.handle_key(KeyCode::Enter),
+        WizardAction::Submit("sell AAPL 2".to_string())
+    );
+}
+
+// ===== Validation Tests =====
+
+#[test]
+fn test_invalid_fields_stay_on_their_step_with_an_error() {
+    let mut wizard = wizard(None);
+    wizard.handle_key(KeyCode::Down);
+    wizard.handle_key(KeyCode::Enter);
+
+    assert_eq!(wizard.handle_key(KeyCode::Enter), WizardAction::Continue);
+    assert_eq!(wizard.step(), WizardStep::Symbol);
+    assert!(wizard.error().unwrap().contains("Enter a symbol"));
+
+    type_text(&mut wizard, "aapl");
+    wizard.handle_key(KeyCode::Enter);
+    assert_eq!(wizard.step(), WizardStep::Quantity);
+    assert_eq!(wizard.error(), None);
+
+    // Letters are ignored, and more than is held is refused
+    assert_eq!(type_text(&mut wizard, "x"), WizardAction::Continue);
+    wizard.handle_key(KeyCode::Enter);
+    assert!(wizard.error().unwrap().contains("greater than zero"));
+    type_text(&mut wizard, "6");
+    wizard.handle_key(KeyCode::Enter);
+    assert_eq!(wizard.error(), Some("Only 5 AAPL held"));
+    assert_eq!(wizard.step(), WizardStep::Quantity);
+}
+
+#[test]
+fn test_backspace_on_empty_field_steps_back_and_esc_cancels() {
+    let mut wizard = wizard(Some("MSFT"));
+    wizard.handle_key(KeyCode::Enter);
+    wizard.handle_key(KeyCode::Enter);
+    assert_eq!(wizard.step(), WizardStep::Quantity);
+
+    wizard.handle_key(KeyCode::Backspace);
+    assert_eq!(wizard.step(), WizardStep::Symbol);
+    assert_eq!(wizard.symbol(), "MSFT");
+
+    assert_eq!(wizard.handle_key(KeyCode::Esc), WizardAction::Cancel);
+}
@@ -0,0 +1,553 @@
+mod common;
+
+use chrono::NaiveDate;
+use naviin::AppState::{AppState, process_open_orders};
+use naviin::Finance::Holding;
+use naviin::FinanceProvider::{DailyBar, MockProvider};
+use naviin::Orders::{OrderType, simulated_fill};
+use naviin::Storage;
+use naviin::commands::process_command;
+use naviin::components::open_orders::OpenOrdersComponent;
+use naviin::replay::{compare, replay_state};
+use rust_decimal::Decimal;
+use sea_orm::DatabaseConnection;
+use std::collections::HashMap;
+use std::sync::atomic::AtomicBool;
+use std::sync::{Arc, Mutex};
+
+// Account holding 10 AAPL bought at $100 from a mock provider
+async fn setup() -> (Arc<Mutex<AppState>>, DatabaseConnection, Arc<AtomicBool>) {
+    let provider = Arc::new(MockProvider::new());
+    provider.set_price("AAPL", Decimal::from(100));
+    let (state, db, running) = common::setup(Some(provider), None).await;
+    process_command("fund 1000", &state, &db, &running).await;
+    process_command("buy AAPL 10", &state, &db, &running).await;
+    (state, db, running)
+}
+
+// ===== Reserved Shares Tests =====
+
+#[tokio::test]
+async fn test_second_stop_loss_exceeding_holdings_is_rejected() {
+    let (state, db, running) = setup().await;
+
+    let first = process_command("stoploss AAPL 6 90", &state, &db, &running).await;
+    let second = process_command("stoploss AAPL 6 80", &state, &db, &running).await;
+
+    assert!(first.starts_with("Stop loss order created"));
+    assert_eq!(
+        second,
+        "Insufficient holdings. Have 10 shares of AAPL, 6 reserved by open sell orders"
+    );
+    assert_eq!(state.lock().unwrap().get_open_orders().len(), 1);
+}
+
+#[tokio::test]
+async fn test_take_profit_counts_shares_reserved_by_stop_loss() {
+    let (state, db, running) = setup().await;
+    process_command("stoploss AAPL 6 90", &state, &db, &running).await;
+
+    let too_many = process_command("takeprofit AAPL 5 120", &state, &db, &running).await;
+    let remaining = process_command("takeprofit AAPL 4 120", &state, &db, &running).await;
+
+    assert!(too_many.starts_with("Insufficient holdings"));
+    assert!(remaining.starts_with("Take profit order created"));
+    assert_eq!(
+        state
+            .lock()
+            .unwrap()
+            .get_available_holdings_qty(&"AAPL".to_string()),
+        Decimal::ZERO
+    );
+}
+
+#[tokio::test]
+async fn test_market_sell_cannot_use_reserved_shares() {
+    let (state, db, running) = setup().await;
+    process_command("stoploss AAPL 8 90", &state, &db, &running).await;
+
+    let result = process_command("sell AAPL 5", &state, &db, &running).await;
+
+    assert!(result.contains("8 reserved by open sell orders"));
+    assert_eq!(
+        state
+            .lock()
+            .unwrap()
+            .get_ticker_holdings_qty(&"AAPL".to_string()),
+        Decimal::from(10)
+    );
+}
+
+#[tokio::test]
+async fn test_close_cancels_open_sell_orders() {
+    let (state, db, running) = setup().await;
+    process_command("stoploss AAPL 6 90", &state, &db, &running).await;
+    process_command("takeprofit AAPL 4 120", &state, &db, &running).await;
+
+    let result = process_command("close AAPL", &state, &db, &running).await;
+
+    assert!(result.starts_with("Closed AAPL: sold 10 shares"));
+    assert!(result.ends_with("Cancelled 2 open sell orders"));
+    let guard = state.lock().unwrap();
+    assert!(guard.get_open_orders().is_empty());
+    assert!(guard.get_holdings_map().is_empty());
+}
+
+// ===== Modify Order Tests =====
+
+#[tokio::test]
+async fn test_modify_buy_limit_price_keeps_placement_and_persists() {
+    let (state, db, running) = setup().await;
+    process_command("fund 500", &state, &db, &running).await;
+    process_command("buylimit AAPL 4 90", &state, &db, &running).await;
+    let placed_at = state.lock().unwrap().get_open_orders()[0].get_timestamp();
+
+    let result = process_command("modify 1 price 95", &state, &db, &running).await;
+    assert_eq!(
+        result,
+        "Modified order 1: BuyLimit AAPL 4 @ $95.00 (was BuyLimit AAPL 4 @ $90.00)"
+    );
+    // 4 at 130 would need 520 of the 500 cash
+    let rejected = process_command("modify 1 price 130", &state, &db, &running).await;
+    assert_eq!(rejected, "You don't have enough of cash for this purchase!");
+
+    let loaded = Storage::try_load_state(&db).await.unwrap();
+    let orders = loaded.get_open_orders();
+    assert_eq!(orders.len(), 1);
+    assert!(matches!(orders[0].get_order_type(), OrderType::BuyLimit));
+    assert_eq!(orders[0].get_price_per(), Decimal::from(95));
+    assert_eq!(orders[0].get_timestamp(), placed_at);
+}
+
+#[tokio::test]
+async fn test_order_modified_while_prices_load_is_not_filled_from_the_old_copy() {
+    let provider = Arc::new(MockProvider::new());
+    provider.set_price("AAPL", Decimal::from(100));
+    let (state, db, running) = common::setup(Some(provider.clone()), None).await;
+    process_command("fund 1000", &state, &db, &running).await;
+    process_command("buy AAPL 10", &state, &db, &running).await;
+    process_command("takeprofit AAPL 10 95", &state, &db, &running).await;
+
+    // The monitor reads the book, then waits on the quote
+    provider.hold_prices();
+    let calls = provider.calls();
+    let monitor = {
+        let state = state.clone();
+        tokio::spawn(async move { process_open_orders(&state).await })
+    };
+    while provider.calls() == calls {
+        tokio::task::yield_now().await;
+    }
+    // Raised above the quote before the old $95 copy can fill
+    let modified = process_command("modify 1 price 120", &state, &db, &running).await;
+    assert!(modified.starts_with("Modified order 1"), "{}", modified);
+    provider.release_prices();
+
+    assert_eq!(monitor.await.unwrap(), 0);
+    let guard = state.lock().unwrap();
+    assert_eq!(
+        guard.get_ticker_holdings_qty(&"AAPL".to_string()),
+        Decimal::from(10)
+    );
+    let orders = guard.get_open_orders();
+    assert_eq!(orders.len(), 1);
+    assert_eq!(orders[0].get_price_per(), Decimal::from(120));
+}
+
+#[tokio::test]
+async fn test_modify_stop_loss_qty_is_checked_against_unreserved_shares() {
+    let (state, db, running) = setup().await;
+    process_command("stoploss AAPL 4 90", &state, &db, &running).await;
+    process_command("takeprofit AAPL 3 120", &state, &db, &running).await;
+
+    // Orders list AAPL sells highest price first: take profit, then stop loss
+    let rejected = process_command("modify 2 qty 8", &state, &db, &running).await;
+    assert_eq!(
+        rejected,
+        "Insufficient holdings. Have 10 shares of AAPL, 3 reserved by open sell orders"
+    );
+    let result = process_command("modify 2 qty 7", &state, &db, &running).await;
+    assert!(
+        result.starts_with("Modified order 2: StopLoss AAPL 7 @ $90.00"),
+        "{}",
+        result
+    );
+
+    let loaded = Storage::try_load_state(&db).await.unwrap();
+    let stop = loaded
+        .get_open_orders()
+        .into_iter()
+        .find(|order| matches!(order.get_order_type(), OrderType::StopLoss))
+        .unwrap();
+    assert_eq!(stop.get_qty(), Decimal::from(7));
+
+    let undone = process_command("undo", &state, &db, &running).await;
+    assert!(
+        undone.contains("Restored StopLoss order for AAPL to 4"),
+        "{}",
+        undone
+    );
+    let missing = process_command("modify 5 price 80", &state, &db, &running).await;
+    assert_eq!(missing, "No open order #5. There are 2 open orders");
+}
+
+// ===== Limit Fill Price Tests =====
+
+// Books a 2-share BuyLimit at $100 and runs the order monitor with AAPL quoted at
+// `price`, returning the state after the fill
+async fn fill_buy_limit(price: i64) -> Arc<Mutex<AppState>> {
+    let provider = Arc::new(MockProvider::new());
+    provider.set_price("AAPL", Decimal::from(price));
+    let state = Arc::new(Mutex::new(AppState::with_provider(provider)));
+    let order = naviin::Orders::OpenOrder::new(
+        "AAPL".to_string(),
+        Decimal::from(2),
+        Decimal::from(100),
+        OrderType::BuyLimit,
+        naviin::Orders::Side::Buy,
+    );
+    {
+        let mut guard = state.lock().unwrap();
+        guard.deposit(Decimal::from(1000));
+        guard.add_open_order(order).unwrap();
+    }
+    assert_eq!(naviin::AppState::process_open_orders(&state).await, 1);
+    assert!(state.lock().unwrap().get_open_orders().is_empty());
+    state
+}
+
+#[tokio::test]
+async fn test_buy_limit_below_limit_fills_at_current_price() {
+    let state = fill_buy_limit(95).await;
+    let guard = state.lock().unwrap();
+
+    let trades = guard.get_trades();
+    assert_eq!(trades.len(), 1);
+    assert_eq!(trades[0].get_price_per(), Decimal::from(95));
+    assert_eq!(
+        guard.get_holdings_map()["AAPL"].get_avg_price(),
+        Decimal::from(95)
+    );
+    assert_eq!(guard.check_balance(), Decimal::from(810));
+}
+
+#[tokio::test]
+async fn test_buy_limit_at_limit_fills_at_limit_price() {
+    let state = fill_buy_limit(100).await;
+    let guard = state.lock().unwrap();
+
+    assert_eq!(guard.get_trades()[0].get_price_per(), Decimal::from(100));
+    assert_eq!(
+        guard.get_holdings_map()["AAPL"].get_avg_price(),
+        Decimal::from(100)
+    );
+    assert_eq!(guard.check_balance(), Decimal::from(800));
+}
+
+#[tokio::test]
+async fn test_sell_limit_fills_at_the_better_price_unlike_take_profit() {
+    let (state, db, running) = setup().await;
+    let take_profit = process_command("takeprofit AAPL 5 120", &state, &db, &running).await;
+    let sell_limit = process_command("selllimit AAPL 5 120", &state, &db, &running).await;
+    assert_eq!(
+        sell_limit,
+        "Sell limit order created: 5 shares of AAPL at $120.00"
+    );
+    assert!(take_profit.starts_with("Take profit order created"));
+    // Every share is now reserved by the two sells
+    let over = process_command("selllimit AAPL 1 150", &state, &db, &running).await;
+    assert!(over.starts_with("Insufficient holdings"), "{}", over);
+
+    let provider = Arc::new(MockProvider::new());
+    provider.set_price("AAPL", Decimal::from(115));
+    state.lock().unwrap().set_provider(provider.clone());
+    assert_eq!(process_open_orders(&state).await, 0);
+
+    // The price gaps through the limit: take profit sells at its target, sell limit at the quote
+    provider.set_price("AAPL", Decimal::from(130));
+    assert_eq!(process_open_orders(&state).await, 2);
+    let guard = state.lock().unwrap();
+    let fills: Vec<(String, Decimal)> = guard.get_trades()[1..]
+        .iter()
+        .map(|trade| (trade.get_order_type().clone(), trade.get_price_per()))
+        .collect();
+    assert!(fills.contains(&("TakeProfit".to_string(), Decimal::from(120))));
+    assert!(fills.contains(&("SellLimit".to_string(), Decimal::from(130))));
+    assert_eq!(guard.check_balance(), Decimal::from(1250));
+    assert!(guard.get_holdings_map().is_empty());
+}
+
+#[tokio::test]
+async fn test_stop_loss_credits_only_the_shares_still_held() {
+    let (state, db, running) = setup().await;
+    process_command("stoploss AAPL 5 95", &state, &db, &running).await;
+    // The position shrinks after the order was booked
+    let mut holdings = HashMap::new();
+    holdings.insert(
+        "AAPL".to_string(),
+        Holding::new("AAPL".to_string(), Decimal::from(2), Decimal::from(100)),
+    );
+    state.lock().unwrap().set_holdings_map(holdings);
+    let balance = state.lock().unwrap().check_balance();
+
+    let provider = Arc::new(MockProvider::new());
+    provider.set_price("AAPL", Decimal::from(90));
+    state.lock().unwrap().set_provider(provider);
+    assert_eq!(process_open_orders(&state).await, 1);
+
+    let guard = state.lock().unwrap();
+    let sale = guard.get_trades().last().unwrap().clone();
+    assert_eq!(sale.get_quantity(), Decimal::from(2));
+    assert_eq!(guard.check_balance(), balance + Decimal::from(180));
+    assert!(guard.get_holdings_map().is_empty());
+    assert_eq!(guard.get_executed_orders()[0].quantity, Decimal::from(2));
+}
+
+// ===== Executed Order Log Tests =====
+
+#[tokio::test]
+async fn test_filled_stop_loss_is_logged_with_target_and_persisted() {
+    let provider = Arc::new(MockProvider::new());
+    provider.set_price("AAPL", Decimal::from(100));
+    let (state, db, running) = common::setup(Some(provider.clone()), None).await;
+    process_command("fund 1000", &state, &db, &running).await;
+    process_command("buy AAPL 10", &state, &db, &running).await;
+    process_command("stoploss AAPL 4 95", &state, &db, &running).await;
+    assert_eq!(
+        process_command("executed", &state, &db, &running).await,
+        "No executed orders yet"
+    );
+
+    // Gaps down through the stop
+    provider.set_price("AAPL", Decimal::from(90));
+    assert_eq!(naviin::AppState::process_open_orders(&state).await, 1);
+    Storage::save_state(&state, &db).await;
+
+    let loaded = Storage::try_load_state(&db).await.unwrap();
+    let executed = loaded.get_executed_orders();
+    assert_eq!(executed.len(), 1);
+    assert!(matches!(executed[0].order_type, OrderType::StopLoss));
+    assert_eq!(executed[0].target_price, Decimal::from(95));
+    assert_eq!(executed[0].fill_price, Decimal::from(90));
+    assert_eq!(executed[0].quantity, Decimal::from(4));
+
+    let report = process_command("executed aapl", &state, &db, &running).await;
+    let row: Vec<&str> = report.lines().nth(2).unwrap().split_whitespace().collect();
+    assert_eq!(
+        row[..6],
+        ["StopLoss", "AAPL", "4", "$95.00", "$90.00", "-5.26%"]
+    );
+    assert_eq!(
+        process_command("executed MSFT", &state, &db, &running).await,
+        "No executed orders for MSFT"
+    );
+}
+
+// ===== Simulated Fill Tests =====
+
+fn bar(open: i64, high: i64, low: i64, close: i64) -> DailyBar {
+    DailyBar {
+        open: Decimal::from(open),
+        high: Decimal::from(high),
+        low: Decimal::from(low),
+        close: Decimal::from(close),
+    }
+}
+
+#[test]
+fn test_simulated_fill_uses_day_range_and_gaps_fill_at_open() {
+    let day = bar(148, 150, 143, 146);
+    let limit = |price: i64| Decimal::from(price);
+
+    assert_eq!(
+        simulated_fill(&OrderType::BuyLimit, limit(145), &day),
+        Some(limit(145))
+    );
+    assert_eq!(simulated_fill(&OrderType::BuyLimit, limit(142), &day), None);
+    // Opened below the limit, so a resting buy fills at the open
+    assert_eq!(
+        simulated_fill(&OrderType::BuyLimit, limit(155), &day),
+        Some(limit(148))
+    );
+    assert_eq!(
+        simulated_fill(&OrderType::StopLoss, limit(144), &day),
+        Some(limit(144))
+    );
+    assert_eq!(
+        simulated_fill(&OrderType::TakeProfit, limit(150), &day),
+        Some(limit(150))
+    );
+    assert_eq!(
+        simulated_fill(&OrderType::TakeProfit, limit(151), &day),
+        None
+    );
+    assert_eq!(
+        simulated_fill(&OrderType::TakeProfit, limit(140), &day),
+        Some(limit(148))
+    );
+}
+
+#[tokio::test]
+async fn test_simfill_reports_fill_miss_and_missing_day() {
+    let provider = Arc::new(MockProvider::new());
+    let date = NaiveDate::from_ymd_opt(2025, 3, 4).unwrap();
+    provider.set_daily_bar("AAPL", date, bar(148, 150, 143, 146));
+    let (state, db, running) = common::setup(Some(provider), None).await;
+
+    let filled = process_command(
+        "simfill buylimit aapl 10 145 2025-03-04",
+        &state,
+        &db,
+        &running,
+    )
+    .await;
+    assert_eq!(
+        filled,
+        "Simulated BuyLimit AAPL 10 @ $145.00 on 2025-03-04\n\
+         Day: open $148.00, high $150.00, low $143.00, close $146.00\n\
+         Would have filled at $145.00, $1,450.00 total"
+    );
+
+    let missed = process_command(
+        "simfill takeprofit AAPL 10 155 2025-03-04",
+        &state,
+        &db,
+        &running,
+    )
+    .await;
+    assert!(
+        missed.ends_with("Would not have filled: the day's high of $150.00 stayed below $155.00")
+    );
+
+    let weekend = process_command(
+        "simfill stoploss AAPL 10 140 2025-03-08",
+        &state,
+        &db,
+        &running,
+    )
+    .await;
+    assert_eq!(
+        weekend,
+        "No price data for AAPL on 2025-03-08: the market was closed or history is unavailable"
+    );
+    assert!(state.lock().unwrap().get_trades().is_empty());
+}
+
+#[tokio::test]
+async fn test_simfill_replays_open_order_and_checks_arguments() {
+    let provider = Arc::new(MockProvider::new());
+    let date = NaiveDate::from_ymd_opt(2025, 3, 4).unwrap();
+    provider.set_daily_bar("MSFT", date, bar(390, 401, 385, 398));
+    let (state, db, running) = common::setup(Some(provider), None).await;
+    process_command("fund 10000", &state, &db, &running).await;
+    process_command("buylimit MSFT 2 395", &state, &db, &running).await;
+
+    let result = process_command("simfill 1 2025-03-04", &state, &db, &running).await;
+    assert!(result.starts_with("Simulated BuyLimit MSFT 2 @ $395.00 on 2025-03-04"));
+    assert!(
+        result
+            .ends_with("Would have filled at $390.00 (opened through the trigger), $780.00 total")
+    );
+    assert_eq!(state.lock().unwrap().get_open_orders().len(), 1);
+
+    let result = process_command("simfill 2 2025-03-04", &state, &db, &running).await;
+    assert_eq!(result, "No open order 2. 'modify' lists them by number");
+    let result = process_command("simfill 1 03/04/2025", &state, &db, &running).await;
+    assert_eq!(result, "Invalid date: '03/04/2025' (expected YYYY-MM-DD)");
+    let result = process_command("simfill 1 2999-01-01", &state, &db, &running).await;
+    assert_eq!(
+        result,
+        "2999-01-01 is in the future; pick a day that has traded"
+    );
+}
+
+// ===== Fee Tests =====
+
+#[tokio::test]
+async fn test_buy_limit_must_cover_its_estimated_fee() {
+    let (state, db, running) = common::setup(Some(Arc::new(MockProvider::new())), None).await;
+    process_command("fund 1000", &state, &db, &running).await;
+    let set = process_command("fee 1 10", &state, &db, &running).await;
+    assert_eq!(set, "Fee per order: $1.00 + 10 bps, charged on every fill");
+
+    // $1,000 covers the shares but not the $1 + 10 bps = $2.00 fee
+    let rejected = process_command("buylimit AAPL 10 100", &state, &db, &running).await;
+    assert_eq!(rejected, "You don't have enough of cash for this purchase!");
+    assert!(state.lock().unwrap().get_open_orders().is_empty());
+
+    let placed = process_command("buylimit AAPL 9 100", &state, &db, &running).await;
+    assert!(placed.starts_with("Buy limit order created"), "{}", placed);
+    let order = state.lock().unwrap().get_open_orders()[0].clone();
+    assert_eq!(
+        state.lock().unwrap().estimated_fee(&order),
+        Decimal::new(190, 2)
+    );
+    // 1000 - 900 - 1.90 left for other buys
+    assert_eq!(
+        state.lock().unwrap().get_available_cash(),
+        Decimal::new(9810, 2)
+    );
+
+    let mut component = OpenOrdersComponent::new();
+    component.update_orders(vec![order.clone()]);
+    assert_eq!(component.estimated_fee_cell(&order), "-");
+    component.set_fee_model(state.lock().unwrap().get_fee_model());
+    assert_eq!(component.estimated_fee_cell(&order), "$1.90");
+}
+
+#[tokio::test]
+async fn test_every_fill_pays_the_fee_and_records_it() {
+    let provider = Arc::new(MockProvider::new());
+    provider.set_price("AAPL", Decimal::from(100));
+    let (state, db, running) = common::setup(Some(provider), None).await;
+    process_command("fund 2000", &state, &db, &running).await;
+    process_command("fee 1 10", &state, &db, &running).await;
+
+    // $1 + 10 bps of $1,000 on top of the buy
+    let bought = process_command("buy AAPL 10", &state, &db, &running).await;
+    assert!(
+        bought.starts_with("Bought 10 shares of AAPL at $100.00 (total: $1,000.00, fee: $2.00)"),
+        "{}",
+        bought
+    );
+    assert_eq!(state.lock().unwrap().check_balance(), Decimal::from(998));
+
+    // $1 + 10 bps of $500 out of the proceeds when the sell limit fills
+    process_command("selllimit AAPL 5 100", &state, &db, &running).await;
+    assert_eq!(process_open_orders(&state).await, 1);
+    assert_eq!(
+        state.lock().unwrap().check_balance(),
+        Decimal::new(149650, 2)
+    );
+
+    Storage::save_state(&state, &db).await;
+    let loaded = Storage::try_load_state(&db).await.unwrap();
+    let fees: Vec<Decimal> = loaded
+        .get_trades()
+        .iter()
+        .map(|trade| trade.get_fee())
+        .collect();
+    assert_eq!(fees, vec![Decimal::new(200, 2), Decimal::new(150, 2)]);
+    assert!(compare(&loaded, &replay_state(&loaded)).is_empty());
+}
+
+#[tokio::test]
+async fn test_undoing_a_buy_refunds_its_fee() {
+    let provider = Arc::new(MockProvider::new());
+    provider.set_price("AAPL", Decimal::from(100));
+    let (state, db, running) = common::setup(Some(provider), None).await;
+    process_command("fund 2000", &state, &db, &running).await;
+    process_command("fee 1 10", &state, &db, &running).await;
+
+    // Not enough once the fee is added
+    let rejected = process_command("buy AAPL 20", &state, &db, &running).await;
+    assert_eq!(
+        rejected,
+        "Insufficient funds. Need $2,003.00, have $2,000.00"
+    );
+
+    process_command("buy AAPL 10", &state, &db, &running).await;
+    let undone = process_command("undo", &state, &db, &running).await;
+    assert!(undone.contains("refunded $1,002.00"), "{}", undone);
+    assert_eq!(state.lock().unwrap().check_balance(), Decimal::from(2000));
+}
@@ -0,0 +1,174 @@
+use naviin::components::output::{DEFAULT_HISTORY_LIMIT, OutputComponent, ScrollAfterCommand};
+use ratatui::{buffer::Buffer, layout::Rect, style::Color, widgets::Widget};
+
+// Helper to run one command's worth of output through the component
+fn show(output: &mut OutputComponent, text: &str) {
+    output.commit_to_history();
+    output.set_output(text.to_string());
+}
+
+// ===== History Cap Tests =====
+
+#[test]
+fn test_history_defaults_to_limit() {
+    let output = OutputComponent::new();
+
+    assert_eq!(output.get_history_limit(), DEFAULT_HISTORY_LIMIT);
+}
+
+#[test]
+fn test_history_drops_oldest_entries_past_limit() {
+    let mut output = OutputComponent::with_history_limit(3);
+    for i in 0..6 {
+        show(&mut output, &format!("output {}", i));
+    }
+
+    // "output 5" is still the current output, not yet in history
+    let history: Vec<&str> = output.get_history().iter().map(|s| s.as_str()).collect();
+    assert_eq!(history, vec!["output 2", "output 3", "output 4"]);
+}
+
+#[test]
+fn test_lowering_limit_trims_existing_history() {
+    let mut output = OutputComponent::new();
+    for i in 0..10 {
+        show(&mut output, &format!("output {}", i));
+    }
+
+    output.set_history_limit(2);
+
+    assert_eq!(output.get_history().len(), 2);
+    assert_eq!(output.get_history()[0], "output 7");
+}
+
+// ===== Combined History Rendering Tests =====
+
+// Renders the component and returns the text of each inner row
+fn render_rows(output: &OutputComponent, height: u16) -> Vec<String> {
+    let area = Rect::new(0, 0, 30, height);
+    let mut buf = Buffer::empty(area);
+    output.render(area, &mut buf);
+    (1..height - 1)
+        .map(|y| {
+            (1..area.width - 1)
+                .map(|x| buf[(x, y)].symbol())
+                .collect::<String>()
+                .trim_end()
+                .to_string()
+        })
+        .collect()
+}
+
+#[test]
+fn test_combined_lines_include_history_then_current() {
+    let mut output = OutputComponent::new();
+    show(&mut output, "first");
+    show(&mut output, "second\nline");
+
+    assert_eq!(output.combined_lines(), vec!["first", "", "second", "line"]);
+    assert_eq!(output.current_start_line(), 2);
+}
+
+#[test]
+fn test_new_output_starts_at_top_of_pane() {
+    let mut output = OutputComponent::new();
+    show(&mut output, "a\nb\nc");
+    show(&mut output, "latest\nresult\nend");
+
+    let rows = render_rows(&output, 5);
+
+    assert_eq!(rows, vec!["latest", "result", "end"]);
+}
+
+#[test]
+fn test_can_scroll_back_into_previous_outputs() {
+    let mut output = OutputComponent::new();
+    show(&mut output, "a\nb\nc");
+    show(&mut output, "latest");
+    render_rows(&output, 5);
+
+    output.scroll_to_top();
+    assert_eq!(render_rows(&output, 5), vec!["a", "b", "c"]);
+
+    output.scroll_to_bottom();
+    assert_eq!(render_rows(&output, 5), vec!["c", "", "latest"]);
+
+    // Scrolling up from the bottom moves immediately instead of burning keypresses
+    output.scroll_up(1);
+    assert_eq!(render_rows(&output, 5), vec!["b", "c", ""]);
+}
+
+#[test]
+fn test_trimming_keeps_scroll_on_same_lines() {
+    let mut output = OutputComponent::with_history_limit(2);
+    show(&mut output, "one");
+    show(&mut output, "two");
+    show(&mut output, "three");
+    assert_eq!(output.get_scroll_offset(), 4);
+
+    show(&mut output, "four");
+
+    assert_eq!(
+        output.combined_lines(),
+        vec!["two", "", "three", "", "four"]
+    );
+    assert_eq!(output.get_scroll_offset(), 4);
+}
+
+// ===== Marker Coloring Tests =====
+
+#[test]
+fn test_marker_lines_render_green_and_red() {
+    let mut output = OutputComponent::new();
+    show(&mut output, "Gainers:\n  ▲ NVDA  +2.00%\n  ▼ TSLA  -1.00%");
+    let area = Rect::new(0, 0, 30, 5);
+    let mut buf = Buffer::empty(area);
+
+    output.render(area, &mut buf);
+
+    assert_eq!(buf[(3, 1)].fg, Color::Reset);
+    assert_eq!(buf[(3, 2)].fg, Color::Green);
+    assert_eq!(buf[(3, 3)].fg, Color::Red);
+}
+
+// ===== Scroll After Command Tests =====
+
+#[test]
+fn test_scroll_after_command_modes() {
+    let mut output = OutputComponent::new();
+    show(&mut output, "a\nb\nc");
+    output.set_scroll_after(ScrollAfterCommand::Bottom);
+    show(&mut output, "one\ntwo\nthree\nfour");
+    assert_eq!(render_rows(&output, 5), vec!["two", "three", "four"]);
+
+    output.scroll_to_top();
+    output.set_scroll_after(ScrollAfterCommand::Preserve);
+    show(&mut output, "five");
+    assert_eq!(render_rows(&output, 5), vec!["a", "b", "c"]);
+
+    output.set_scroll_after(ScrollAfterCommand::Top);
+    show(&mut output, "six\nseven\neight\nnine");
+    assert_eq!(render_rows(&output, 5), vec!["six", "seven", "eight"]);
+    assert_eq!(
+        ScrollAfterCommand::parse("BOTTOM"),
+        Some(ScrollAfterCommand::Bottom)
+    );
+    assert_eq!(ScrollAfterCommand::parse("middle"), None);
+}
+
+#[test]
+fn test_long_output_scrolls_to_its_last_line() {
+    // More lines than a u16 scroll offset can reach
+    let long: Vec<String> = (0..70_000).map(|i| format!("line {}", i)).collect();
+    let mut output = OutputComponent::new();
+    output.set_scroll_after(ScrollAfterCommand::Bottom);
+    show(&mut output, &long.join("\n"));
+
+    assert_eq!(
+        render_rows(&output, 5),
+        vec!["line 69997", "line 69998", "line 69999"]
+    );
+    output.scroll_up(1);
+    assert_eq!(output.get_scroll_offset(), 69_996);
+    assert_eq!(render_rows(&output, 5)[0], "line 69996");
+}
@@ -0,0 +1,170 @@
+use chrono::Duration;
+use naviin::AppState::AppState;
+use naviin::FinanceProvider::MockProvider;
+use naviin::cashflow::{CashFlow, CashFlowKind};
+use naviin::clock::MockClock;
+use naviin::commands::process_command;
+use naviin::performance::{EquitySnapshot, annualize, performance};
+use rust_decimal::Decimal;
+use sea_orm::Database;
+use std::sync::atomic::AtomicBool;
+use std::sync::{Arc, Mutex};
+
+const START: i64 = 1_700_000_000;
+const DAY_SECS: i64 = 86_400;
+
+fn snapshot(day: i64, value: i64) -> EquitySnapshot {
+    EquitySnapshot::new(START + day * DAY_SECS, Decimal::from(value))
+}
+
+fn deposit(at: i64, amount: i64) -> CashFlow {
+    CashFlow::new(CashFlowKind::Deposit, Decimal::from(amount), at)
+}
+
+// ===== Return Metric Tests =====
+
+#[test]
+fn test_time_weighted_return_ignores_deposit_timing() {
+    // 10% each day, with a 1000 deposit right after the first day's snapshot
+    let snapshots = [snapshot(0, 1000), snapshot(1, 1100), snapshot(2, 2310)];
+    let flows = [deposit(START + DAY_SECS + 60, 1000)];
+
+    let report = performance(&snapshots, &flows, None).unwrap();
+
+    assert_eq!(report.time_weighted, Some(Decimal::new(21, 2)));
+    // 310 gained on 2000 put in
+    assert_eq!(report.simple_return, Some(Decimal::new(155, 3)));
+    assert_eq!(report.net_flows, Decimal::from(1000));
+    let mwr = report.money_weighted_period().unwrap();
+    assert!(
+        mwr > Decimal::new(20, 2) && mwr < Decimal::new(24, 2),
+        "{mwr}"
+    );
+}
+
+#[test]
+fn test_money_weighted_return_matches_simple_growth_without_flows() {
+    let snapshots = [snapshot(0, 1000), snapshot(365, 1100)];
+
+    let report = performance(&snapshots, &[], None).unwrap();
+
+    let annual = report.money_weighted_annual().unwrap().round_dp(6);
+    assert_eq!(annual, Decimal::new(1, 1));
+    assert_eq!(
+        report
+            .annualize(report.time_weighted.unwrap())
+            .unwrap()
+            .round_dp(6),
+        annual
+    );
+}
+
+#[test]
+fn test_window_starts_at_last_snapshot_before_it() {
+    let snapshots = [snapshot(0, 500), snapshot(10, 1000), snapshot(20, 1200)];
+
+    let report = performance(&snapshots, &[], Some(START + 15 * DAY_SECS)).unwrap();
+
+    assert_eq!(report.start.value, Decimal::from(1000));
+    assert_eq!(report.days, Decimal::from(10));
+    assert_eq!(report.simple_return, Some(Decimal::new(2, 1)));
+}
+
+// ===== Degenerate Case Tests =====
+
+#[test]
+fn test_degenerate_windows_do_not_panic() {
+    assert!(performance(&[], &[], None).is_none());
+    // Two snapshots on the same day are not a window
+    let same_day = [
+        snapshot(0, 1000),
+        EquitySnapshot::new(START + 60, Decimal::from(1010)),
+    ];
+    assert!(performance(&same_day, &[], None).is_none());
+
+    let empty_account = [snapshot(0, 0), snapshot(3, 0)];
+    let report = performance(&empty_account, &[], None).unwrap();
+    assert_eq!(report.simple_return, None);
+    assert_eq!(report.time_weighted, None);
+    assert_eq!(report.money_weighted_daily, None);
+}
+
+#[test]
+fn test_zero_starting_value_measures_from_first_deposit() {
+    let snapshots = [snapshot(0, 0), snapshot(1, 550)];
+    let flows = [deposit(START + 100, 500)];
+
+    let report = performance(&snapshots, &flows, None).unwrap();
+
+    assert_eq!(report.time_weighted, Some(Decimal::new(1, 1)));
+    assert_eq!(report.simple_return, Some(Decimal::new(1, 1)));
+    assert!(report.money_weighted_daily.unwrap() > Decimal::ZERO);
+}
+
+#[test]
+fn test_annualize_compounds_and_rejects_total_loss() {
+    let half_year = annualize(Decimal::new(1, 1), Decimal::new(1825, 1)).unwrap();
+    assert_eq!(half_year.round_dp(6), Decimal::new(21, 2));
+    assert_eq!(annualize(-Decimal::ONE, Decimal::from(30)), None);
+    assert_eq!(annualize(Decimal::new(1, 1), Decimal::ZERO), None);
+}
+
+// ===== Snapshot and Command Tests =====
+
+#[tokio::test]
+async fn test_performance_command_records_a_snapshot_per_day() {
+    let clock = Arc::new(MockClock::at_timestamp(START));
+    let state = Arc::new(Mutex::new(AppState::with_clock(clock.clone())));
+    let db = Database::connect("sqlite::memory:").await.unwrap();
+    let running = Arc::new(AtomicBool::new(false));
+    process_command("fund 1000", &state, &db, &running).await;
+
+    let first = process_command("performance", &state, &db, &running).await;
+    assert!(first.starts_with("Not enough history yet"));
+    process_command("perf", &state, &db, &running).await;
+    assert_eq!(state.lock().unwrap().get_equity_snapshots().len(), 1);
+
+    clock.advance(Duration::days(2));
+    let report = process_command("performance", &state, &db, &running).await;
+
+    assert_eq!(state.lock().unwrap().get_equity_snapshots().len(), 2);
+    assert!(report.contains("(2.0 days)"));
+    assert!(report.contains("  Time-weighted        +0.00%      +0.00%"));
+}
+
+#[tokio::test]
+async fn test_leveraged_account_snapshots_equity_net_of_the_margin_loan() {
+    let clock = Arc::new(MockClock::at_timestamp(START));
+    let provider = Arc::new(MockProvider::new());
+    provider.set_price("AAPL", Decimal::from(100));
+    let mut account = AppState::with_provider(provider.clone());
+    account.set_clock(clock.clone());
+    let state = Arc::new(Mutex::new(account));
+    let db = Database::connect("sqlite::memory:").await.unwrap();
+    let running = Arc::new(AtomicBool::new(false));
+    process_command("fund 1000", &state, &db, &running).await;
+    process_command("leverage 2", &state, &db, &running).await;
+    // 2,000 of stock on 1,000 of cash: 1,000 is borrowed
+    process_command("buy AAPL 20", &state, &db, &running).await;
+    assert_eq!(state.lock().unwrap().get_margin_loan(), Decimal::from(1000));
+
+    process_command("performance", &state, &db, &running).await;
+    clock.advance(Duration::days(1));
+    provider.set_price("AAPL", Decimal::from(110));
+    let report = process_command("performance", &state, &db, &running).await;
+
+    let values: Vec<Decimal> = state
+        .lock()
+        .unwrap()
+        .get_equity_snapshots()
+        .iter()
+        .map(|snapshot| snapshot.value)
+        .collect();
+    assert_eq!(values, vec![Decimal::from(1000), Decimal::from(1200)]);
+    // A 10% move on 2x leverage is a 20% return on the equity put in
+    assert!(
+        report.contains("  Time-weighted       +20.00%"),
+        "{}",
+        report
+    );
+}
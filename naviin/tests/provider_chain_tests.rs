@@ -0,0 +1,161 @@
+use chrono::NaiveDate;
+use naviin::AppState::{AppState, lock_state};
+use naviin::FinanceProvider::{
+    DailyBar, MockProvider, PriceProvider, ProviderChain, ProviderHealth, ProviderSource,
+    parse_stooq_close,
+};
+use naviin::commands::process_command;
+use rust_decimal::Decimal;
+use sea_orm::Database;
+use std::sync::atomic::AtomicBool;
+use std::sync::{Arc, Mutex};
+
+fn health(name: &str, attempts: usize, successes: usize) -> ProviderHealth {
+    ProviderHealth {
+        name: name.to_string(),
+        attempts,
+        successes,
+    }
+}
+
+// ===== Provider Chain Tests =====
+
+#[tokio::test]
+async fn test_failing_primary_falls_through_to_secondary_then_cache() {
+    // The primary has no quotes, like Yahoo during an outage
+    let primary = Arc::new(MockProvider::new());
+    let secondary = Arc::new(MockProvider::new());
+    secondary.set_price("AAPL", Decimal::from(150));
+    let chain = ProviderChain::new(vec![primary.clone(), secondary.clone()], true);
+
+    assert_eq!(chain.curr_price("AAPL").await, Decimal::from(150));
+    assert_eq!(primary.calls(), 1);
+    assert_eq!(
+        chain.health(),
+        vec![
+            health("mock", 1, 0),
+            health("mock", 1, 1),
+            health("cache", 0, 0)
+        ]
+    );
+
+    // With both down, the last price served comes from the cache
+    secondary.set_price("AAPL", Decimal::ZERO);
+    assert_eq!(chain.curr_price("AAPL").await, Decimal::from(150));
+    assert_eq!(chain.curr_price("MSFT").await, Decimal::ZERO);
+    assert_eq!(
+        chain.health(),
+        vec![
+            health("mock", 3, 0),
+            health("mock", 3, 1),
+            health("cache", 2, 1)
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_history_falls_through_to_the_first_provider_with_closes() {
+    let primary = Arc::new(MockProvider::new());
+    let secondary = Arc::new(MockProvider::new());
+    let day = |d| NaiveDate::from_ymd_opt(2026, 10, d).unwrap();
+    let bar = |close| DailyBar {
+        open: close,
+        high: close,
+        low: close,
+        close,
+    };
+    secondary.set_daily_bar("AAPL", day(13), bar(Decimal::from(101)));
+    secondary.set_daily_bar("AAPL", day(12), bar(Decimal::from(100)));
+    secondary.set_daily_bar("AAPL", day(2), bar(Decimal::from(90)));
+    let chain = ProviderChain::new(vec![primary.clone(), secondary], false);
+
+    assert_eq!(
+        chain.daily_closes("AAPL", day(5), day(14)).await,
+        vec![(day(12), Decimal::from(100)), (day(13), Decimal::from(101))]
+    );
+    assert_eq!(primary.calls(), 1);
+    assert_eq!(
+        chain.health(),
+        vec![health("mock", 1, 0), health("mock", 1, 1)]
+    );
+}
+
+#[tokio::test]
+async fn test_providers_command_lists_and_configures_the_chain() {
+    let secondary = Arc::new(MockProvider::new());
+    secondary.set_price("AAPL", Decimal::from(150));
+    let chain = ProviderChain::new(vec![Arc::new(MockProvider::new()), secondary], false);
+    let mut account = AppState::new();
+    account.set_provider_chain(Arc::new(chain));
+    let state = Arc::new(Mutex::new(account));
+    let db = Database::connect("sqlite::memory:").await.unwrap();
+    let running = Arc::new(AtomicBool::new(false));
+
+    let price = process_command("price AAPL", &state, &db, &running).await;
+    assert!(price.contains("150"), "{}", price);
+    let listing = process_command("providers", &state, &db, &running).await;
+    assert_eq!(
+        listing,
+        "Provider chain (tried in order):\n  \
+         1. mock     0% of the last 1 lookups (0 ok)\n  \
+         2. mock     100% of the last 1 lookups (1 ok)"
+    );
+
+    let result = process_command("providers yahoo,stooq,cache", &state, &db, &running).await;
+    assert_eq!(result, "Provider chain set: yahoo -> stooq -> cache");
+    let (sources, settings) = {
+        let guard = lock_state(&state);
+        (guard.get_provider_sources(), guard.get_settings())
+    };
+    assert_eq!(
+        sources,
+        vec![
+            ProviderSource::Yahoo,
+            ProviderSource::Stooq,
+            ProviderSource::Cache
+        ]
+    );
+    assert!(settings.contains(&(
+        "provider_chain".to_string(),
+        "yahoo,stooq,cache".to_string()
+    )));
+
+    let result = process_command("providers yahoo,bloomberg", &state, &db, &running).await;
+    assert!(
+        result.starts_with("Unknown provider: 'bloomberg'"),
+        "{}",
+        result
+    );
+    let result = process_command("providers yahoo,yahoo", &state, &db, &running).await;
+    assert!(result.starts_with("yahoo is listed twice"), "{}", result);
+}
+
+#[tokio::test]
+async fn test_providers_command_reports_an_injected_provider() {
+    let provider = Arc::new(MockProvider::new());
+    provider.set_price("AAPL", Decimal::from(150));
+    let state = Arc::new(Mutex::new(AppState::with_provider(provider)));
+    let db = Database::connect("sqlite::memory:").await.unwrap();
+    let running = Arc::new(AtomicBool::new(false));
+
+    let listing = process_command("providers", &state, &db, &running).await;
+    assert_eq!(
+        listing,
+        "Provider chain (tried in order):\n  1. mock     no lookups yet"
+    );
+    process_command("price AAPL", &state, &db, &running).await;
+    let listing = process_command("providers", &state, &db, &running).await;
+    assert_eq!(
+        listing,
+        "Provider chain (tried in order):\n  1. mock     100% of the last 1 lookups (1 ok)"
+    );
+}
+
+#[test]
+fn test_stooq_close_is_read_from_the_quote_csv() {
+    let csv = "Symbol,Date,Time,Open,High,Low,Close\n\
+               AAPL.US,2026-10-13,22:00:09,180.1,182.5,179.9,181.25\n";
+    assert_eq!(parse_stooq_close(csv), Some(Decimal::new(18125, 2)));
+    let missing = "Symbol,Date,Time,Open,High,Low,Close\nNOPE.US,N/D,N/D,N/D,N/D,N/D,N/D\n";
+    assert_eq!(parse_stooq_close(missing), None);
+}
@@ -0,0 +1,83 @@
+use chrono::{TimeZone, Utc};
+use naviin::FinanceProvider::previous_close_or_last;
+use naviin::clock::DisplayTimezone;
+use naviin::commands::watch_symbol;
+use naviin::components::quote::QuoteComponent;
+use ratatui::{buffer::Buffer, layout::Rect, widgets::Widget};
+use rust_decimal::Decimal;
+
+// Renders the component and flattens the buffer into one string
+fn render(quote: &QuoteComponent) -> String {
+    let area = Rect::new(0, 0, 60, 12);
+    let mut buf = Buffer::empty(area);
+    quote.render(area, &mut buf);
+    buf.content().iter().map(|cell| cell.symbol()).collect()
+}
+
+// ===== Watch Mode Tests =====
+
+#[test]
+fn test_watch_symbol_parses_only_watch_commands() {
+    assert_eq!(watch_symbol("watch tsla"), Some("TSLA".to_string()));
+    assert_eq!(
+        watch_symbol("  WATCH btc-usd "),
+        Some("BTC-USD".to_string())
+    );
+    assert_eq!(watch_symbol("watch"), None);
+    assert_eq!(watch_symbol("watchlist AAPL"), None);
+    assert_eq!(watch_symbol("price AAPL"), None);
+}
+
+#[test]
+fn test_quote_shows_price_change_and_time() {
+    let mut quote = QuoteComponent::new("AAPL".to_string());
+    assert!(render(&quote).contains("Loading quote"));
+
+    let at = Utc.with_ymd_and_hms(2026, 10, 14, 10, 15, 30).unwrap();
+    quote.set_display_timezone(DisplayTimezone::Utc);
+    quote.update_quote(Decimal::from(105), Decimal::from(100), at.timestamp());
+
+    assert_eq!(
+        quote.day_change(),
+        Some((Decimal::from(5), Decimal::from(5)))
+    );
+    let screen = render(&quote);
+    assert!(screen.contains("Watching AAPL"));
+    assert!(screen.contains("$105.00"));
+    assert!(screen.contains("+$5.00 (+5.00%) today"));
+    assert!(screen.contains("Updated 10:15:30"));
+
+    // The update time follows the display timezone
+    quote.set_display_timezone(DisplayTimezone::parse("Asia/Tokyo").unwrap());
+    assert!(render(&quote).contains("Updated 19:15:30"));
+}
+
+#[test]
+fn test_failed_refresh_keeps_last_quote() {
+    let mut quote = QuoteComponent::new("AAPL".to_string());
+    let at = Utc.with_ymd_and_hms(2026, 10, 14, 10, 15, 30).unwrap();
+    quote.set_display_timezone(DisplayTimezone::Utc);
+    quote.update_quote(Decimal::from(95), Decimal::from(100), at.timestamp());
+
+    quote.update_quote(Decimal::ZERO, Decimal::ZERO, at.timestamp() + 60);
+
+    let screen = render(&quote);
+    assert!(screen.contains("$95.00"));
+    assert!(screen.contains("-$5.00 (-5.00%) today"));
+    assert!(screen.contains("Updated 10:15:30"));
+}
+
+// ===== Previous Close Tests =====
+
+#[test]
+fn test_missing_previous_close_falls_back_to_last_price() {
+    let last = Some(Decimal::from(187));
+    assert_eq!(previous_close_or_last(None, last), last);
+    // Yahoo sometimes sends zero rather than leaving the field out
+    assert_eq!(previous_close_or_last(Some(Decimal::ZERO), last), last);
+    assert_eq!(
+        previous_close_or_last(Some(Decimal::from(180)), last),
+        Some(Decimal::from(180))
+    );
+    assert_eq!(previous_close_or_last(None, None), None);
+}
@@ -0,0 +1,145 @@
+use naviin::AppState::AppState;
+use naviin::FinanceProvider::MockProvider;
+use naviin::Orders::Side;
+use naviin::commands::process_command;
+use naviin::rebalance::{RebalanceOrder, plan_rebalance};
+use rust_decimal::Decimal;
+use sea_orm::Database;
+use std::collections::HashMap;
+use std::sync::atomic::AtomicBool;
+use std::sync::{Arc, Mutex};
+
+fn map(entries: &[(&str, i64)]) -> HashMap<String, Decimal> {
+    entries
+        .iter()
+        .map(|(symbol, value)| (symbol.to_string(), Decimal::from(*value)))
+        .collect()
+}
+
+// ===== Rebalance Plan Tests =====
+
+#[test]
+fn test_plan_sells_overweight_before_buying_unheld_target() {
+    // $1,000 in AAPL and $1,000 cash; target 25% AAPL, 50% MSFT
+    let quantities = map(&[("AAPL", 10)]);
+    let prices = map(&[("AAPL", 100), ("MSFT", 50)]);
+    let targets = vec![
+        ("AAPL".to_string(), Decimal::from(25)),
+        ("MSFT".to_string(), Decimal::from(50)),
+    ];
+
+    let plan = plan_rebalance(&quantities, &prices, Decimal::from(1000), &targets);
+
+    assert_eq!(
+        plan,
+        vec![
+            RebalanceOrder {
+                symbol: "AAPL".to_string(),
+                side: Side::Sell,
+                quantity: Decimal::from(5),
+                price: Decimal::from(100),
+                current_pct: Decimal::from(50),
+                target_pct: Decimal::from(25),
+            },
+            RebalanceOrder {
+                symbol: "MSFT".to_string(),
+                side: Side::Buy,
+                quantity: Decimal::from(20),
+                price: Decimal::from(50),
+                current_pct: Decimal::ZERO,
+                target_pct: Decimal::from(50),
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_plan_skips_on_target_and_unpriced_symbols() {
+    let quantities = map(&[("AAPL", 5), ("TSLA", 3)]);
+    let prices = map(&[("AAPL", 100), ("TSLA", 200)]);
+    // Total is 500 + 600 + 400 cash = 1,500; AAPL is already a third
+    let targets = vec![
+        ("AAPL".to_string(), Decimal::new(3333, 2)),
+        ("NOPRICE".to_string(), Decimal::from(10)),
+    ];
+
+    let plan = plan_rebalance(&quantities, &prices, Decimal::from(400), &targets);
+
+    assert!(plan.is_empty());
+}
+
+// ===== Target Validation Tests =====
+
+#[test]
+fn test_targets_cannot_exceed_one_hundred_percent() {
+    let mut state = AppState::new();
+    state
+        .set_target_allocation("AAPL".to_string(), Decimal::from(70))
+        .unwrap();
+
+    let err = state
+        .set_target_allocation("MSFT".to_string(), Decimal::from(40))
+        .unwrap_err();
+    assert_eq!(
+        err,
+        "Targets would total 110%. Other targets already use 70%"
+    );
+
+    // Replacing an existing target only counts the others
+    assert_eq!(
+        state.set_target_allocation("AAPL".to_string(), Decimal::from(100)),
+        Ok(Decimal::from(100))
+    );
+    assert_eq!(
+        state.set_target_allocation("AAPL".to_string(), Decimal::ZERO),
+        Ok(Decimal::ZERO)
+    );
+    assert!(state.get_target_allocations().is_empty());
+}
+
+// ===== Rebalance Command Tests =====
+
+#[tokio::test]
+async fn test_rebalance_suggests_by_default_and_executes_on_request() {
+    let provider = Arc::new(MockProvider::new());
+    provider.set_price("VTI", Decimal::from(200));
+    provider.set_price("BND", Decimal::from(50));
+    let state = Arc::new(Mutex::new(AppState::with_provider(provider)));
+    let db = Database::connect("sqlite::memory:").await.unwrap();
+    let running = Arc::new(AtomicBool::new(false));
+    process_command("fund 10000", &state, &db, &running).await;
+    process_command("target VTI 60", &state, &db, &running).await;
+    process_command("target BND 40%", &state, &db, &running).await;
+
+    let suggested = process_command("rebalance", &state, &db, &running).await;
+    assert!(suggested.contains("BUY  30 VTI at ~$200.00 (0.0% -> 60%)"));
+    assert!(suggested.contains("BUY  80 BND"));
+    assert!(state.lock().unwrap().get_holdings_map().is_empty());
+
+    let executed = process_command("rebalance --execute", &state, &db, &running).await;
+    assert!(executed.ends_with("Cash: $0.00"));
+    let guard = state.lock().unwrap();
+    assert_eq!(
+        guard.get_ticker_holdings_qty(&"VTI".to_string()),
+        Decimal::from(30)
+    );
+    assert_eq!(
+        guard.get_ticker_holdings_qty(&"BND".to_string()),
+        Decimal::from(80)
+    );
+}
+
+#[tokio::test]
+async fn test_rebalance_on_an_empty_account_reports_no_positions() {
+    let state = Arc::new(Mutex::new(AppState::with_provider(Arc::new(
+        MockProvider::new(),
+    ))));
+    let db = Database::connect("sqlite::memory:").await.unwrap();
+    let running = Arc::new(AtomicBool::new(false));
+    process_command("target VTI 60", &state, &db, &running).await;
+
+    assert_eq!(
+        process_command("rebalance", &state, &db, &running).await,
+        "No price for VTI; skipped\nNo positions or cash to rebalance"
+    );
+}
@@ -0,0 +1,151 @@
+mod common;
+
+use chrono::{Duration, TimeZone, Utc};
+use naviin::AppState::{AppState, process_recurring_buys};
+use naviin::FinanceProvider::MockProvider;
+use naviin::Storage;
+use naviin::clock::MockClock;
+use naviin::commands::process_command;
+use naviin::events::EventKind;
+use naviin::recurring::Frequency;
+use rust_decimal::Decimal;
+use sea_orm::DatabaseConnection;
+use std::sync::atomic::AtomicBool;
+use std::sync::{Arc, Mutex};
+
+// 2026-01-31 12:00 UTC, the end of a month so monthly schedules hit short months
+const START: i64 = 1_769_860_800;
+
+// Funded account on a migrated database with AAPL quoted at 50 and a controllable clock
+async fn setup() -> (
+    Arc<Mutex<AppState>>,
+    DatabaseConnection,
+    Arc<AtomicBool>,
+    Arc<MockClock>,
+) {
+    let provider = Arc::new(MockProvider::new());
+    provider.set_price("AAPL", Decimal::from(50));
+    let clock = Arc::new(MockClock::at_timestamp(START));
+    let (state, db, running) = common::setup(Some(provider), Some(clock.clone())).await;
+    state.lock().unwrap().deposit(Decimal::from(1_000));
+    (state, db, running, clock)
+}
+
+#[test]
+fn test_monthly_schedule_keeps_day_of_month_where_it_exists() {
+    let jan_31 = Utc.with_ymd_and_hms(2026, 1, 31, 12, 0, 0).unwrap();
+    let feb_28 = Utc.with_ymd_and_hms(2026, 2, 28, 12, 0, 0).unwrap();
+    assert_eq!(
+        Frequency::Monthly.next_after(jan_31.timestamp(), 31),
+        feb_28.timestamp()
+    );
+    assert_eq!(
+        Frequency::Weekly.next_after(START, 31),
+        START + Duration::weeks(1).num_seconds()
+    );
+}
+
+#[tokio::test]
+async fn test_monthly_schedule_returns_to_its_anchor_day_after_february() {
+    let (state, db, running, clock) = setup().await;
+    process_command("recurring aapl $100 monthly", &state, &db, &running).await;
+    let next_due =
+        |state: &Arc<Mutex<AppState>>| state.lock().unwrap().get_recurring_buys()["AAPL"].next_due;
+    let feb_28 = Utc.with_ymd_and_hms(2026, 2, 28, 12, 0, 0).unwrap();
+    assert_eq!(next_due(&state), feb_28.timestamp());
+
+    // Each buy moves the schedule on from the 31st, not from the clamped 28th
+    let expected = [(2026, 3, 31), (2026, 4, 30), (2026, 5, 31)];
+    for (year, month, day) in expected {
+        clock.set(Utc.timestamp_opt(next_due(&state), 0).unwrap());
+        assert_eq!(process_recurring_buys(&state).await, 1);
+        let due = Utc.with_ymd_and_hms(year, month, day, 12, 0, 0).unwrap();
+        assert_eq!(next_due(&state), due.timestamp());
+    }
+
+    // The anchor survives a reload
+    Storage::save_state(&state, &db).await;
+    let reloaded = Storage::try_load_state(&db).await.unwrap();
+    let schedule = &reloaded.get_recurring_buys()["AAPL"];
+    assert_eq!(schedule.anchor_day, 31);
+    let mut schedule = schedule.clone();
+    schedule.advance(schedule.next_due);
+    let june_30 = Utc.with_ymd_and_hms(2026, 6, 30, 12, 0, 0).unwrap();
+    assert_eq!(schedule.next_due, june_30.timestamp());
+}
+
+#[tokio::test]
+async fn test_weekly_buys_run_as_the_clock_crosses_each_due_date() {
+    let (state, db, running, clock) = setup().await;
+    let result = process_command("recurring aapl $100 weekly", &state, &db, &running).await;
+    assert!(result.starts_with("Recurring buy set: $100.00 of AAPL weekly, first on"));
+
+    // Nothing is owed before the first week is up
+    clock.advance(Duration::days(6));
+    assert_eq!(process_recurring_buys(&state).await, 0);
+
+    for week in 1..=3 {
+        clock.advance(Duration::days(7));
+        assert_eq!(process_recurring_buys(&state).await, 1);
+        // Running again before the next due date buys nothing more
+        assert_eq!(process_recurring_buys(&state).await, 0);
+        let state_guard = state.lock().unwrap();
+        assert_eq!(
+            state_guard.get_holdings_map()["AAPL"].get_qty(),
+            Decimal::from(2 * week)
+        );
+    }
+
+    let state_guard = state.lock().unwrap();
+    assert_eq!(state_guard.check_balance(), Decimal::from(700));
+    let schedule = &state_guard.get_recurring_buys()["AAPL"];
+    assert_eq!(schedule.next_due, START + Duration::weeks(4).num_seconds());
+}
+
+#[tokio::test]
+async fn test_missed_periods_buy_once_and_skipped_buys_still_advance() {
+    let (state, db, running, clock) = setup().await;
+    process_command("recurring AAPL $600 daily", &state, &db, &running).await;
+
+    // Three days late: one buy, then the schedule moves to the next future date
+    clock.advance(Duration::days(3));
+    assert_eq!(process_recurring_buys(&state).await, 1);
+    let next_due = state.lock().unwrap().get_recurring_buys()["AAPL"].next_due;
+    assert_eq!(next_due, START + Duration::days(4).num_seconds());
+
+    // Only 400 left, so the next buy is skipped with an event
+    clock.advance(Duration::days(1));
+    assert_eq!(process_recurring_buys(&state).await, 0);
+    let mut state_guard = state.lock().unwrap();
+    let events = state_guard.drain_events();
+    let skipped = events.last().unwrap();
+    assert_eq!(skipped.kind, EventKind::Error);
+    assert!(skipped.message.starts_with("Skipped recurring buy of AAPL"));
+    assert_eq!(
+        state_guard.get_recurring_buys()["AAPL"].next_due,
+        START + Duration::days(5).num_seconds()
+    );
+}
+
+#[tokio::test]
+async fn test_schedules_persist_and_cancel() {
+    let (state, db, running, _) = setup().await;
+    process_command("recurring MSFT 250 monthly", &state, &db, &running).await;
+
+    let loaded = Storage::try_load_state(&db).await.unwrap();
+    let schedule = &loaded.get_recurring_buys()["MSFT"];
+    assert_eq!(schedule.amount, Decimal::from(250));
+    assert_eq!(schedule.frequency, Frequency::Monthly);
+
+    let listed = process_command("recurring list", &state, &db, &running).await;
+    assert!(listed.contains("MSFT"), "{}", listed);
+    assert_eq!(
+        process_command("recurring MSFT $250 hourly", &state, &db, &running).await,
+        "Invalid frequency: 'hourly' (expected daily, weekly or monthly)"
+    );
+
+    let cancelled = process_command("recurring cancel msft", &state, &db, &running).await;
+    assert_eq!(cancelled, "Cancelled recurring buy of MSFT");
+    let loaded = Storage::try_load_state(&db).await.unwrap();
+    assert!(loaded.get_recurring_buys().is_empty());
+}
@@ -0,0 +1,128 @@
+use naviin::AppState::AppState;
+use naviin::FinanceProvider::{MockProvider, PriceProvider};
+use naviin::commands::{MAX_PRICES_SYMBOLS, process_command};
+use naviin::refresh::fetch_prices;
+use rust_decimal::Decimal;
+use sea_orm::Database;
+use std::sync::atomic::AtomicBool;
+use std::sync::{Arc, Mutex};
+
+fn symbols(names: &[&str]) -> Vec<String> {
+    names.iter().map(|name| name.to_string()).collect()
+}
+
+// ===== Refresh Health Tests =====
+
+#[tokio::test]
+async fn test_refresh_reports_the_symbols_a_provider_failed() {
+    // GONE and DEAD have no quote in the mock, so their lookups fail
+    let provider = Arc::new(MockProvider::new());
+    provider.set_price("AAPL", Decimal::from(100));
+    provider.set_price("MSFT", Decimal::from(300));
+
+    let prices = fetch_prices(
+        provider,
+        symbols(&["AAPL", "GONE"]),
+        symbols(&["AAPL", "MSFT", "DEAD"]),
+        None,
+    )
+    .await;
+    let health = prices.health();
+
+    assert_eq!(health.summary(), "2/4 symbols priced");
+    assert!(health.is_degraded());
+    assert_eq!(health.failed, symbols(&["DEAD", "GONE"]));
+    assert_eq!(prices.holdings["AAPL"], Decimal::from(100));
+}
+
+#[tokio::test]
+async fn test_retry_fetches_only_the_failed_symbols() {
+    let provider = Arc::new(MockProvider::new());
+    provider.set_price("AAPL", Decimal::from(100));
+    let shared: Arc<dyn PriceProvider> = provider.clone();
+
+    let first = fetch_prices(
+        shared.clone(),
+        symbols(&["AAPL", "GONE"]),
+        symbols(&["DEAD"]),
+        None,
+    )
+    .await;
+    let health = first.health();
+    assert_eq!(health.summary(), "1/3 symbols priced");
+
+    // GONE recovers; DEAD is still down
+    provider.set_price("GONE", Decimal::from(5));
+    let calls = provider.calls();
+    let retry = fetch_prices(shared, symbols(&["GONE"]), symbols(&["DEAD"]), None).await;
+    // Price and previous close for GONE, price for DEAD; AAPL isn't asked again
+    assert_eq!(provider.calls() - calls, 3);
+
+    let health = health.after_retry(&retry);
+    assert_eq!(health.summary(), "2/3 symbols priced");
+    assert_eq!(health.failed, symbols(&["DEAD"]));
+
+    let healthy = fetch_prices(provider, symbols(&["AAPL"]), Vec::new(), None)
+        .await
+        .health();
+    assert!(!healthy.is_degraded());
+    assert_eq!(healthy.summary(), "1/1 symbols priced");
+}
+
+// ===== Bulk Prices Tests =====
+
+#[tokio::test]
+async fn test_prices_quotes_every_symbol_and_lists_failures() {
+    let provider = Arc::new(MockProvider::new());
+    provider.set_price("AAPL", Decimal::from(110));
+    provider.set_previous_close("AAPL", Decimal::from(100));
+    provider.set_price("MSFT", Decimal::from(270));
+    provider.set_previous_close("MSFT", Decimal::from(300));
+    provider.set_price("BTC-USD", Decimal::from(50_000));
+    let state = Arc::new(Mutex::new(AppState::with_provider(provider)));
+    let db = Database::connect("sqlite::memory:").await.unwrap();
+    let running = Arc::new(AtomicBool::new(false));
+
+    let result = process_command("prices aapl MSFT btc AAPL GONE", &state, &db, &running).await;
+    let rows: Vec<&str> = result.lines().collect();
+    assert_eq!(rows[0], "Prices:");
+    // Asked order, each symbol once
+    assert!(
+        rows[2].contains("AAPL") && rows[2].contains("$110.00"),
+        "{}",
+        result
+    );
+    assert!(
+        rows[2].contains("+$10.00") && rows[2].contains("+10.00%"),
+        "{}",
+        result
+    );
+    assert!(
+        rows[3].contains("MSFT") && rows[3].contains("-10.00%"),
+        "{}",
+        result
+    );
+    // No previous close: priced, without a change
+    assert!(
+        rows[4].contains("BTC-USD") && rows[4].contains("$50,000"),
+        "{}",
+        result
+    );
+    assert_eq!(rows[5], "No price for GONE");
+
+    let too_many: Vec<String> = (0..=MAX_PRICES_SYMBOLS)
+        .map(|i| format!("S{}", i))
+        .collect();
+    let result = process_command(
+        &format!("prices {}", too_many.join(" ")),
+        &state,
+        &db,
+        &running,
+    )
+    .await;
+    assert_eq!(result, "Too many symbols: 51 (limit 50 per call)");
+    assert_eq!(
+        process_command("prices", &state, &db, &running).await,
+        "Usage: prices <sym1> <sym2> ..."
+    );
+}
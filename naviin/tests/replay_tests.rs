@@ -0,0 +1,153 @@
+mod common;
+
+use naviin::AppState::AppState;
+use naviin::Finance::{self, Holding, SplitRatio};
+use naviin::FinanceProvider::MockProvider;
+use naviin::Storage;
+use naviin::commands::process_command;
+use naviin::replay::{self, Discrepancy};
+use rust_decimal::Decimal;
+use sea_orm::DatabaseConnection;
+use std::sync::atomic::AtomicBool;
+use std::sync::{Arc, Mutex};
+
+async fn setup() -> (Arc<Mutex<AppState>>, DatabaseConnection, Arc<AtomicBool>) {
+    let provider = Arc::new(MockProvider::new());
+    provider.set_price("AAPL", Decimal::from(100));
+    provider.set_price("MSFT", Decimal::from(300));
+    let (state, db, running) = common::setup(Some(provider), None).await;
+    for command in ["fund 2000", "buy AAPL 5", "buy MSFT 2", "sell AAPL 2"] {
+        process_command(command, &state, &db, &running).await;
+    }
+    (state, db, running)
+}
+
+// ===== Replay Tests =====
+
+#[tokio::test]
+async fn test_consistent_account_replays_without_discrepancies() {
+    let (state, db, running) = setup().await;
+    {
+        let mut guard = state.lock().unwrap();
+        Finance::apply_split(
+            &"AAPL".to_string(),
+            SplitRatio::parse("2:1").unwrap(),
+            &mut guard,
+        )
+        .unwrap();
+        let replayed = replay::replay_state(&guard);
+        assert_eq!(replayed.cash, Decimal::from(1100));
+        assert_eq!(replayed.holdings["AAPL"].get_qty(), Decimal::from(6));
+        assert_eq!(replayed.holdings["AAPL"].get_avg_price(), Decimal::from(50));
+    }
+
+    let result = process_command("replay", &state, &db, &running).await;
+    assert_eq!(
+        result,
+        "Replayed 1 cash flows and 4 trades\nStored account matches the trade log"
+    );
+}
+
+#[tokio::test]
+async fn test_diverged_state_reports_discrepancies_and_only_apply_fixes_them() {
+    let (state, db, running) = setup().await;
+    {
+        let mut guard = state.lock().unwrap();
+        guard.set_cash_balance(Decimal::from(1150));
+        let mut holdings = guard.get_holdings_map();
+        holdings.remove("MSFT");
+        holdings.insert(
+            "TSLA".to_string(),
+            Holding::new("TSLA".to_string(), Decimal::ONE, Decimal::from(200)),
+        );
+        guard.set_holdings_map(holdings);
+
+        let discrepancies = replay::compare(&guard, &replay::replay_state(&guard));
+        assert_eq!(
+            discrepancies[0],
+            Discrepancy::Cash {
+                stored: Decimal::from(1150),
+                replayed: Decimal::from(1100),
+            }
+        );
+        assert_eq!(discrepancies.len(), 3);
+    }
+
+    let result = process_command("replay", &state, &db, &running).await;
+    assert!(result.contains("3 discrepancies:"), "{}", result);
+    assert!(result.contains("Cash drift: stored $1,150.00, replayed $1,100.00 ($50.00 off)"));
+    assert!(result.contains("MSFT quantity mismatch: stored 0, replayed 2"));
+    assert!(result.contains("TSLA quantity mismatch: stored 1, replayed 0"));
+    assert_eq!(state.lock().unwrap().check_balance(), Decimal::from(1150));
+
+    let result = process_command("replay --apply", &state, &db, &running).await;
+    assert!(result.ends_with("Applied: cash and holdings now match the trade log"));
+    {
+        let guard = state.lock().unwrap();
+        assert_eq!(guard.check_balance(), Decimal::from(1100));
+        assert_eq!(
+            guard.get_ticker_holdings_qty(&"MSFT".to_string()),
+            Decimal::from(2)
+        );
+        assert!(!guard.get_holdings_map().contains_key("TSLA"));
+    }
+    let result = process_command("replay", &state, &db, &running).await;
+    assert!(result.ends_with("Stored account matches the trade log"));
+}
+
+#[tokio::test]
+async fn test_applied_replay_survives_a_reload() {
+    let (state, db, running) = setup().await;
+    {
+        let mut guard = state.lock().unwrap();
+        let mut holdings = guard.get_holdings_map();
+        holdings.insert(
+            "ZZZ".to_string(),
+            Holding::new("ZZZ".to_string(), Decimal::ONE, Decimal::from(10)),
+        );
+        guard.set_holdings_map(holdings);
+    }
+    Storage::save_state(&state, &db).await;
+
+    let result = process_command("replay --apply", &state, &db, &running).await;
+    assert!(result.ends_with("Applied: cash and holdings now match the trade log"));
+
+    let loaded = Storage::try_load_state(&db).await.unwrap();
+    assert!(!loaded.get_holdings_map().contains_key("ZZZ"));
+    assert!(replay::compare(&loaded, &replay::replay_state(&loaded)).is_empty());
+}
+
+#[tokio::test]
+async fn test_imported_trades_do_not_count_as_cash_drift() {
+    let (state, db, running) = setup().await;
+    let path =
+        std::env::temp_dir().join(format!("naviin_{}_replay_import.csv", std::process::id()));
+    std::fs::write(
+        &path,
+        "date,asset,asset_type,side,quantity,price,currency\n\
+         2024-01-02,NVDA,STOCK,BUY,4,500,USD\n\
+         2024-01-03,NVDA,STOCK,SELL,1,600,USD\n",
+    )
+    .unwrap();
+    naviin::import::import_trades_from_csv(&state, path.to_str().unwrap())
+        .await
+        .unwrap();
+    let _ = std::fs::remove_file(path);
+
+    // The shares come in, the cash stays where it was
+    {
+        let guard = state.lock().unwrap();
+        assert_eq!(guard.check_balance(), Decimal::from(1100));
+        let replayed = replay::replay_state(&guard);
+        assert_eq!(replayed.cash, Decimal::from(1100));
+        assert_eq!(replayed.holdings["NVDA"].get_qty(), Decimal::from(3));
+        assert!(replay::compare(&guard, &replayed).is_empty());
+    }
+
+    let result = process_command("replay", &state, &db, &running).await;
+    assert!(
+        result.ends_with("Stored account matches the trade log"),
+        "{}",
+        result
+    );
+}
@@ -0,0 +1,250 @@
+mod common;
+
+use chrono::Duration;
+use naviin::AppState::{AppState, process_open_orders, process_recurring_buys};
+use naviin::FinanceProvider::MockProvider;
+use naviin::clock::MockClock;
+use naviin::commands::process_command;
+use naviin::events::EventKind;
+use naviin::risk::{RiskBreach, RiskLimits, concentration_pct};
+use rust_decimal::Decimal;
+use sea_orm::DatabaseConnection;
+use std::sync::atomic::AtomicBool;
+use std::sync::{Arc, Mutex};
+
+// Account with $10,000 cash, 20 MSFT at $100 and AAPL quoted at $100
+async fn setup() -> (Arc<Mutex<AppState>>, DatabaseConnection, Arc<AtomicBool>) {
+    let (state, db, running, _) = setup_with_provider().await;
+    (state, db, running)
+}
+
+// Same account, with the provider kept so prices can move
+async fn setup_with_provider() -> (
+    Arc<Mutex<AppState>>,
+    DatabaseConnection,
+    Arc<AtomicBool>,
+    Arc<MockProvider>,
+) {
+    let provider = Arc::new(MockProvider::new());
+    provider.set_price("AAPL", Decimal::from(100));
+    provider.set_price("MSFT", Decimal::from(100));
+    let (state, db, running) = common::setup(Some(provider.clone()), None).await;
+    process_command("fund 12000", &state, &db, &running).await;
+    process_command("buy MSFT 20", &state, &db, &running).await;
+    (state, db, running, provider)
+}
+
+// ===== Limit Check Tests =====
+
+#[test]
+fn test_check_buy_reports_the_limit_broken() {
+    let limits = RiskLimits {
+        max_position_pct: Some(Decimal::from(25)),
+        max_order_notional: Some(Decimal::from(1000)),
+    };
+
+    assert_eq!(
+        limits.check_buy(Decimal::from(1500), Decimal::ZERO, Decimal::from(10_000)),
+        Err(RiskBreach::OrderSize {
+            notional: Decimal::from(1500),
+            limit: Decimal::from(1000),
+        })
+    );
+    assert_eq!(
+        limits.check_buy(
+            Decimal::from(900),
+            Decimal::from(3000),
+            Decimal::from(10_000)
+        ),
+        Err(RiskBreach::Concentration {
+            pct: Decimal::from(30),
+            limit: Decimal::from(25),
+        })
+    );
+    assert!(
+        limits
+            .check_buy(
+                Decimal::from(900),
+                Decimal::from(2500),
+                Decimal::from(10_000)
+            )
+            .is_ok()
+    );
+    assert!(
+        RiskLimits::default()
+            .check_buy(Decimal::MAX, Decimal::ONE, Decimal::ONE)
+            .is_ok()
+    );
+    assert_eq!(
+        concentration_pct(Decimal::ONE, Decimal::ZERO),
+        Decimal::ONE_HUNDRED
+    );
+}
+
+// ===== Buy Path Tests =====
+
+#[tokio::test]
+async fn test_buy_over_concentration_cap_is_rejected() {
+    let (state, db, running) = setup().await;
+    let result = process_command("risklimit position 25", &state, &db, &running).await;
+    assert_eq!(
+        result,
+        "Risk limits: position 25% of the portfolio, order off"
+    );
+
+    // The portfolio is worth $12,000, so 40 AAPL would be a third of it
+    let result = process_command("buy AAPL 40", &state, &db, &running).await;
+    assert_eq!(
+        result,
+        "Buy rejected: AAPL would be 33.33% of the portfolio, above the 25% position limit"
+    );
+    assert!(
+        !state
+            .lock()
+            .unwrap()
+            .get_holdings_map()
+            .contains_key("AAPL")
+    );
+    assert_eq!(state.lock().unwrap().check_balance(), Decimal::from(10_000));
+
+    // 30 AAPL is exactly 25%
+    let result = process_command("buy AAPL 30", &state, &db, &running).await;
+    assert!(result.starts_with("Bought 30 shares of AAPL"), "{}", result);
+    // Adding to the position counts what is already held
+    let result = process_command("buy AAPL 1", &state, &db, &running).await;
+    assert!(
+        result.starts_with("Buy rejected: AAPL would be 25.83%"),
+        "{}",
+        result
+    );
+}
+
+#[tokio::test]
+async fn test_order_limit_rejects_large_buys() {
+    let (state, db, running) = setup().await;
+    process_command("risklimit order 2500", &state, &db, &running).await;
+
+    let result = process_command("buy AAPL $3000", &state, &db, &running).await;
+    assert_eq!(
+        result,
+        "Buy rejected: the $3,000.00 order is above the $2,500.00 order limit"
+    );
+    let result = process_command("buy AAPL 25", &state, &db, &running).await;
+    assert!(result.starts_with("Bought 25 shares of AAPL"), "{}", result);
+
+    assert_eq!(
+        process_command("risklimit order off", &state, &db, &running).await,
+        "Risk limits off"
+    );
+    assert_eq!(
+        process_command("risklimit position 120", &state, &db, &running).await,
+        "Position limit must be above 0 and at most 100 percent"
+    );
+}
+
+// ===== Order and Schedule Tests =====
+
+#[tokio::test]
+async fn test_buy_limit_is_checked_when_placed() {
+    let (state, db, running) = setup().await;
+    process_command("risklimit position 25 order 2500", &state, &db, &running).await;
+
+    // Checked at the limit price, whatever the market is doing now
+    let result = process_command("buylimit AAPL 30 90", &state, &db, &running).await;
+    assert_eq!(
+        result,
+        "Buy rejected: the $2,700.00 order is above the $2,500.00 order limit"
+    );
+    process_command("risklimit order off", &state, &db, &running).await;
+    let result = process_command("buylimit AAPL 40 90", &state, &db, &running).await;
+    assert_eq!(
+        result,
+        "Buy rejected: AAPL would be 30.00% of the portfolio, above the 25% position limit"
+    );
+    assert!(state.lock().unwrap().get_open_orders().is_empty());
+
+    let result = process_command("buylimit AAPL 30 90", &state, &db, &running).await;
+    assert!(result.starts_with("Buy limit order created"), "{}", result);
+}
+
+#[tokio::test]
+async fn test_buy_limit_over_the_limit_at_fill_is_cancelled() {
+    let (state, db, running, provider) = setup_with_provider().await;
+    process_command("risklimit position 25", &state, &db, &running).await;
+    provider.set_price("AAPL", Decimal::from(110));
+    let result = process_command("buylimit AAPL 30 100", &state, &db, &running).await;
+    assert!(result.starts_with("Buy limit order created"), "{}", result);
+
+    // MSFT halves, so by the time AAPL reaches the limit 30 shares are over a quarter
+    provider.set_price("MSFT", Decimal::from(50));
+    provider.set_price("AAPL", Decimal::from(100));
+    assert_eq!(process_open_orders(&state).await, 0);
+
+    let mut state_guard = state.lock().unwrap();
+    assert!(state_guard.get_open_orders().is_empty());
+    assert!(!state_guard.get_holdings_map().contains_key("AAPL"));
+    let event = state_guard.drain_events().pop().unwrap();
+    assert_eq!(event.kind, EventKind::Error);
+    assert_eq!(
+        event.message,
+        "Cancelled BuyLimit for 30 AAPL: AAPL would be 27.27% of the portfolio, above the 25% position limit"
+    );
+}
+
+#[tokio::test]
+async fn test_recurring_buy_over_the_limit_is_skipped() {
+    let (state, db, running) = setup().await;
+    let clock = Arc::new(MockClock::at_timestamp(1_769_860_800));
+    state.lock().unwrap().set_clock(clock.clone());
+    process_command("risklimit position 25", &state, &db, &running).await;
+    process_command("recurring AAPL $4000 daily", &state, &db, &running).await;
+
+    clock.advance(Duration::days(1));
+    assert_eq!(process_recurring_buys(&state).await, 0);
+
+    let mut state_guard = state.lock().unwrap();
+    assert!(!state_guard.get_holdings_map().contains_key("AAPL"));
+    let event = state_guard.drain_events().pop().unwrap();
+    assert_eq!(event.kind, EventKind::Error);
+    assert_eq!(
+        event.message,
+        "Skipped recurring buy of AAPL: Buy rejected: AAPL would be 33.33% of the portfolio, above the 25% position limit"
+    );
+}
+
+#[tokio::test]
+async fn test_rebalance_buys_are_checked() {
+    let (state, db, running) = setup().await;
+    process_command("risklimit position 25", &state, &db, &running).await;
+    process_command("target AAPL 50", &state, &db, &running).await;
+
+    let result = process_command("rebalance --execute", &state, &db, &running).await;
+    assert!(
+        result.contains(
+            "(Buy rejected: AAPL would be 50.00% of the portfolio, above the 25% position limit)"
+        ),
+        "{}",
+        result
+    );
+    assert!(
+        !state
+            .lock()
+            .unwrap()
+            .get_holdings_map()
+            .contains_key("AAPL")
+    );
+}
+
+#[tokio::test]
+async fn test_buy_is_checked_at_its_fill_price_after_slippage() {
+    let (state, db, running) = setup().await;
+    process_command("risklimit position 25", &state, &db, &running).await;
+    process_command("slippage 100", &state, &db, &running).await;
+
+    // 30 AAPL at the $100 quote is exactly 25%, but they fill at $101
+    let result = process_command("buy AAPL 30", &state, &db, &running).await;
+    assert_eq!(
+        result,
+        "Buy rejected: AAPL would be 25.25% of the portfolio, above the 25% position limit"
+    );
+}
@@ -0,0 +1,104 @@
+use naviin::AppState::{AppState, lock_state};
+use naviin::Finance::Holding;
+use naviin::FinanceProvider::MockProvider;
+use naviin::commands::process_command;
+use naviin::scenario::{Scenario, Shock};
+use rust_decimal::Decimal;
+use sea_orm::Database;
+use std::collections::HashMap;
+use std::sync::atomic::AtomicBool;
+use std::sync::{Arc, Mutex};
+
+// 10 AAPL and 4 TSLA, both worth 1000 at market, plus 500 cash
+fn holdings() -> HashMap<String, Holding> {
+    [("AAPL", 10, 90), ("TSLA", 4, 200)]
+        .into_iter()
+        .map(|(symbol, qty, avg)| {
+            (
+                symbol.to_string(),
+                Holding::new(symbol.to_string(), Decimal::from(qty), Decimal::from(avg)),
+            )
+        })
+        .collect()
+}
+
+// ===== What-if Tests =====
+
+#[test]
+fn test_shocks_revalue_only_the_named_symbols() {
+    let marks = HashMap::from([
+        ("AAPL".to_string(), Decimal::from(100)),
+        ("TSLA".to_string(), Decimal::from(250)),
+    ]);
+    let shocks = vec![
+        Shock::parse("AAPL=-10").unwrap(),
+        Shock::parse("tsla=+5%").unwrap(),
+    ];
+    let scenario = Scenario::new(&holdings(), &marks, &shocks, Decimal::from(500)).unwrap();
+
+    assert_eq!(scenario.current_value(), Decimal::from(2500));
+    assert_eq!(scenario.shocked_value(), Decimal::from(2450));
+    assert_eq!(scenario.positions[0].shocked_price, Decimal::from(90));
+    assert_eq!(scenario.positions[0].change(), Decimal::from(-100));
+    assert_eq!(scenario.positions[1].shocked_price, Decimal::new(2625, 1));
+    assert_eq!(scenario.positions[1].change(), Decimal::from(50));
+
+    // Unshocked holdings stay at market
+    let scenario = Scenario::new(&holdings(), &marks, &shocks[..1], Decimal::from(500)).unwrap();
+    assert_eq!(scenario.shocked_value(), Decimal::from(2400));
+    assert_eq!(scenario.positions[1].shock_pct, None);
+
+    assert!(Shock::parse("AAPL").is_err());
+    assert!(Shock::parse("AAPL=-150").is_err());
+    assert_eq!(
+        Scenario::new(
+            &holdings(),
+            &marks,
+            &[Shock::parse("MSFT=5").unwrap()],
+            Decimal::ZERO
+        ),
+        Err("No holding in MSFT".to_string())
+    );
+}
+
+#[tokio::test]
+async fn test_whatif_command_reports_scenario_without_touching_the_account() {
+    let provider = Arc::new(MockProvider::new());
+    provider.set_price("AAPL", Decimal::from(100));
+    provider.set_price("TSLA", Decimal::from(250));
+    let mut account = AppState::with_provider(provider);
+    account.set_cash_balance(Decimal::from(500));
+    account.set_holdings_map(holdings());
+    let state = Arc::new(Mutex::new(account));
+    let db = Database::connect("sqlite::memory:").await.unwrap();
+    let running = Arc::new(AtomicBool::new(false));
+
+    let result = process_command("whatif AAPL=-10 TSLA=+5", &state, &db, &running).await;
+    let lines: Vec<&str> = result.lines().collect();
+    assert_eq!(lines[0], "What-if (nothing is traded):");
+    assert!(
+        lines[2].contains("AAPL") && lines[2].contains("-10.00%"),
+        "{}",
+        result
+    );
+    assert!(lines[2].ends_with("-$100.00"), "{}", result);
+    assert!(
+        lines[3].contains("TSLA") && lines[3].ends_with("+$50.00"),
+        "{}",
+        result
+    );
+    assert_eq!(lines[4], "Current value:  $2,500.00");
+    assert_eq!(lines[5], "Scenario value: $2,450.00 (-$50.00, -2.00%)");
+
+    let guard = lock_state(&state);
+    assert_eq!(guard.check_balance(), Decimal::from(500));
+    assert_eq!(
+        guard.get_ticker_holdings_qty(&"AAPL".to_string()),
+        Decimal::from(10)
+    );
+    assert_eq!(
+        guard.get_holdings_map()["TSLA"].get_avg_price(),
+        Decimal::from(200)
+    );
+    assert!(guard.get_trades().is_empty());
+}
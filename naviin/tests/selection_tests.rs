@@ -0,0 +1,103 @@
+use naviin::Finance::Holding;
+use naviin::Orders::{OpenOrder, OrderType, Side};
+use naviin::components::holdings::HoldingsComponent;
+use naviin::components::open_orders::OpenOrdersComponent;
+use naviin::components::watchlist::WatchlistComponent;
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+
+fn holdings(symbols: &[&str]) -> HashMap<String, Holding> {
+    symbols
+        .iter()
+        .map(|symbol| {
+            (
+                symbol.to_string(),
+                Holding::new(symbol.to_string(), Decimal::ONE, Decimal::from(100)),
+            )
+        })
+        .collect()
+}
+
+fn order(symbol: &str, timestamp: i64) -> OpenOrder {
+    OpenOrder::new_at(
+        symbol.to_string(),
+        Decimal::ONE,
+        Decimal::from(100),
+        OrderType::BuyLimit,
+        Side::Buy,
+        timestamp,
+    )
+}
+
+// Moves the holdings selection down until it reaches the symbol
+fn select_holding(component: &mut HoldingsComponent, symbol: &str) {
+    while component.selected_symbol().map(String::as_str) != Some(symbol) {
+        component.select_next();
+    }
+}
+
+// ===== Holdings Selection Tests =====
+
+#[test]
+fn test_holdings_selection_follows_symbol_across_rebuilds() {
+    let mut component = HoldingsComponent::new();
+    let symbols = ["AAPL", "AMD", "GOOG", "MSFT", "NVDA", "TSLA"];
+    component.update_holdings(holdings(&symbols), Decimal::ZERO);
+    select_holding(&mut component, "MSFT");
+
+    // Each rebuild is a fresh HashMap, so the row order can change every time
+    for _ in 0..20 {
+        component.update_holdings(holdings(&symbols), Decimal::ZERO);
+        assert_eq!(component.selected_symbol().unwrap(), "MSFT");
+    }
+    component.update_holdings(holdings(&["MSFT", "TSLA"]), Decimal::ZERO);
+    assert_eq!(component.selected_symbol().unwrap(), "MSFT");
+}
+
+#[test]
+fn test_holdings_selection_clamps_when_symbol_disappears() {
+    let mut component = HoldingsComponent::new();
+    component.update_holdings(holdings(&["AAPL", "AMD", "MSFT"]), Decimal::ZERO);
+    select_holding(&mut component, "MSFT");
+
+    component.update_holdings(holdings(&["AAPL"]), Decimal::ZERO);
+    assert_eq!(component.selected_symbol().unwrap(), "AAPL");
+
+    component.update_holdings(HashMap::new(), Decimal::ZERO);
+    assert_eq!(component.selected_symbol(), None);
+    component.update_holdings(holdings(&["AMD"]), Decimal::ZERO);
+    assert_eq!(component.selected_symbol().unwrap(), "AMD");
+}
+
+// ===== Watchlist and Orders Selection Tests =====
+
+#[test]
+fn test_watchlist_selection_follows_symbol_when_rows_shift() {
+    let mut watchlist = WatchlistComponent::new(vec![
+        "AAPL".to_string(),
+        "MSFT".to_string(),
+        "TSLA".to_string(),
+    ]);
+    watchlist.select_next();
+    assert_eq!(watchlist.selected_symbol().unwrap(), "MSFT");
+
+    watchlist.update_symbols(vec!["MSFT".to_string(), "TSLA".to_string()]);
+    assert_eq!(watchlist.selected_symbol().unwrap(), "MSFT");
+    watchlist.update_symbols(vec!["NVDA".to_string(), "TSLA".to_string()]);
+    assert_eq!(watchlist.selected_symbol().unwrap(), "NVDA");
+}
+
+#[test]
+fn test_orders_selection_follows_order_when_list_changes() {
+    let mut component = OpenOrdersComponent::new();
+    component.update_orders(vec![order("AAPL", 1), order("MSFT", 2)]);
+    assert_eq!(component.selected_order().unwrap().get_symbol(), "AAPL");
+
+    component.update_orders(vec![order("TSLA", 3), order("MSFT", 2), order("AAPL", 1)]);
+    assert_eq!(component.selected_order().unwrap().get_symbol(), "AAPL");
+
+    component.update_orders(vec![order("TSLA", 3)]);
+    assert_eq!(component.selected_order().unwrap().get_symbol(), "TSLA");
+    component.update_orders(Vec::new());
+    assert!(component.selected_order().is_none());
+}
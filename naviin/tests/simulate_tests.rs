@@ -0,0 +1,90 @@
+use naviin::AppState::{AppState, lock_state, process_open_orders};
+use naviin::FinanceProvider::{MockProvider, PriceProvider};
+use naviin::Orders::{OpenOrder, OrderType, Side};
+use naviin::simulate::run_simulation;
+use rust_decimal::Decimal;
+use std::sync::{Arc, Mutex};
+
+// ===== Mock Provider Tests =====
+
+#[tokio::test]
+async fn test_mock_provider_serves_configured_prices() {
+    let provider = MockProvider::new();
+    provider.set_price("AAPL", Decimal::from(190));
+    provider.set_previous_close("AAPL", Decimal::from(185));
+
+    assert_eq!(provider.curr_price("AAPL").await, Decimal::from(190));
+    assert_eq!(provider.previous_close("AAPL").await, Decimal::from(185));
+    assert_eq!(provider.curr_price("NOPE").await, Decimal::ZERO);
+    assert_eq!(provider.calls(), 3);
+}
+
+#[tokio::test]
+async fn test_open_orders_fill_against_state_provider() {
+    let provider = Arc::new(MockProvider::new());
+    provider.set_price("AAPL", Decimal::from(105));
+    let state = Arc::new(Mutex::new(AppState::with_provider(provider.clone())));
+    {
+        let mut guard = lock_state(&state);
+        guard.deposit(Decimal::from(1000));
+        let order = OpenOrder::new(
+            "AAPL".to_string(),
+            Decimal::from(2),
+            Decimal::from(100),
+            OrderType::BuyLimit,
+            Side::Buy,
+        );
+        guard.add_open_order(order).unwrap();
+    }
+
+    // Above the limit: nothing fills
+    assert_eq!(process_open_orders(&state).await, 0);
+
+    provider.set_price("AAPL", Decimal::from(99));
+    assert_eq!(process_open_orders(&state).await, 1);
+    let guard = lock_state(&state);
+    assert!(guard.get_open_orders().is_empty());
+    assert_eq!(guard.check_balance(), Decimal::from(802));
+}
+
+#[tokio::test]
+async fn test_unavailable_price_never_triggers_orders() {
+    let provider = Arc::new(MockProvider::new());
+    let state = Arc::new(Mutex::new(AppState::with_provider(provider)));
+    {
+        let mut guard = lock_state(&state);
+        guard.deposit(Decimal::from(1000));
+        let order = OpenOrder::new(
+            "GONE".to_string(),
+            Decimal::ONE,
+            Decimal::from(50),
+            OrderType::BuyLimit,
+            Side::Buy,
+        );
+        guard.add_open_order(order).unwrap();
+    }
+
+    assert_eq!(process_open_orders(&state).await, 0);
+    assert_eq!(lock_state(&state).check_balance(), Decimal::from(1000));
+}
+
+// ===== Simulation Tests =====
+
+#[tokio::test]
+async fn test_simulation_cash_matches_trade_log() {
+    let report = run_simulation(2_000, 7).await;
+
+    assert_eq!(report.operations, 2_000);
+    assert!(report.trades > 0);
+    assert!(report.buys > 0 && report.sells > 0 && report.orders_filled > 0);
+    assert_eq!(report.final_cash, report.expected_cash);
+}
+
+#[tokio::test]
+async fn test_simulation_is_reproducible_for_a_seed() {
+    let first = run_simulation(500, 99).await;
+    let second = run_simulation(500, 99).await;
+
+    assert_eq!(first.final_cash, second.final_cash);
+    assert_eq!(first.trades, second.trades);
+}
@@ -0,0 +1,114 @@
+use migration::{Migrator, MigratorTrait};
+use naviin::AppState::AppState;
+use naviin::Finance;
+use naviin::Orders::Trade;
+use naviin::Storage;
+use naviin::cashflow::{CashFlow, CashFlowKind};
+use naviin::entities::setting;
+use rust_decimal::Decimal;
+use sea_orm::{ColumnTrait, Database, DatabaseConnection, EntityTrait, QueryFilter};
+use std::sync::{Arc, Mutex};
+
+async fn setup() -> DatabaseConnection {
+    let db = Database::connect("sqlite::memory:").await.unwrap();
+    Migrator::up(&db, None).await.unwrap();
+    db
+}
+
+// Account funded with 10,000 before the ledger existed: a buy, a sell, and interest
+fn legacy_state() -> AppState {
+    let mut state = AppState::new();
+    state.set_cash_balance(Decimal::new(960500, 2));
+    state.add_trade(Trade::buy_at(
+        "AAPL".to_string(),
+        Decimal::from(10),
+        Decimal::from(100),
+        1_000,
+    ));
+    state.add_trade(Trade::sell_at(
+        "AAPL".to_string(),
+        Decimal::from(5),
+        Decimal::from(120),
+        2_000,
+    ));
+    state.add_trade(Trade::from_database(
+        "CASH".to_string(),
+        Decimal::from(5),
+        Decimal::ONE,
+        naviin::Orders::Side::Buy,
+        3_000,
+        Finance::INTEREST_ORDER_TYPE.to_string(),
+    ));
+    state.set_note("AAPL".to_string(), "legacy note".to_string());
+    state
+}
+
+// Saves the state, then deletes the version row as data saved before versioning had none
+async fn save_as_version_zero(state: AppState, db: &DatabaseConnection) {
+    Storage::save_state(&Arc::new(Mutex::new(state)), db).await;
+    setting::Entity::delete_many()
+        .filter(setting::Column::Key.eq(Storage::STATE_VERSION_KEY))
+        .exec(db)
+        .await
+        .unwrap();
+}
+
+// ===== Version Tests =====
+
+#[test]
+fn test_missing_version_is_zero() {
+    assert_eq!(Storage::stored_version(&[]), 0);
+    let settings = vec![(Storage::STATE_VERSION_KEY.to_string(), "1".to_string())];
+    assert_eq!(Storage::stored_version(&settings), 1);
+}
+
+#[tokio::test]
+async fn test_save_stamps_current_version() {
+    let db = setup().await;
+    Storage::save_state(&Arc::new(Mutex::new(AppState::new())), &db).await;
+
+    let rows = setting::Entity::find()
+        .filter(setting::Column::Key.eq(Storage::STATE_VERSION_KEY))
+        .all(&db)
+        .await
+        .unwrap();
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0].value, Storage::STATE_VERSION.to_string());
+}
+
+// ===== Upgrade Tests =====
+
+#[tokio::test]
+async fn test_version_zero_data_is_upgraded_not_discarded() {
+    let db = setup().await;
+    save_as_version_zero(legacy_state(), &db).await;
+
+    let state = Storage::try_load_state(&db).await.unwrap();
+
+    // Everything saved is still there
+    assert_eq!(state.check_balance(), Decimal::new(960500, 2));
+    assert_eq!(state.get_trades().len(), 3);
+    assert_eq!(
+        state.get_note(&"AAPL".to_string()),
+        Some(&"legacy note".to_string())
+    );
+    // Cash 9,605 + 1,000 bought - 600 sold - 5 interest = 10,000 put in
+    assert_eq!(
+        state.get_cash_flows(),
+        vec![CashFlow::new(
+            CashFlowKind::Deposit,
+            Decimal::from(10_000),
+            1_000
+        )]
+    );
+}
+
+#[tokio::test]
+async fn test_current_version_data_is_not_backfilled() {
+    let db = setup().await;
+    Storage::save_state(&Arc::new(Mutex::new(legacy_state())), &db).await;
+
+    let state = Storage::try_load_state(&db).await.unwrap();
+
+    assert!(state.get_cash_flows().is_empty());
+}
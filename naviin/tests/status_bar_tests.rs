@@ -0,0 +1,146 @@
+use naviin::AppState::AppState;
+use naviin::Finance::Holding;
+use naviin::components::holdings::HoldingsComponent;
+use naviin::components::status_bar::StatusBarComponent;
+use naviin::refresh::RefreshHealth;
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+
+fn status_bar() -> StatusBarComponent {
+    let mut bar = StatusBarComponent::new();
+    bar.set_portfolio_name("naviin".to_string());
+    bar.update_account(
+        Some(Decimal::new(1234567, 2)),
+        Decimal::new(50000, 2),
+        Some(Decimal::new(-1230, 2)),
+    );
+    bar
+}
+
+// ===== Status Line Tests =====
+
+#[test]
+fn test_status_line_shows_every_field_when_wide() {
+    let mut bar = status_bar();
+    assert_eq!(
+        bar.status_line(200),
+        "[naviin] │ Value $12,345.67 │ Cash $500.00 │ Day -$12.30 │ online │ not refreshed yet"
+    );
+
+    bar.set_offline(true);
+    bar.update_account(None, Decimal::ZERO, Some(Decimal::new(5, 0)));
+    let line = bar.status_line(200);
+    assert!(line.contains("Value --"));
+    assert!(line.contains("Day +$5.00"));
+    assert!(line.contains("OFFLINE"));
+}
+
+#[test]
+fn test_narrow_status_line_drops_low_priority_fields_then_truncates() {
+    let bar = status_bar();
+
+    let line = bar.status_line(65);
+    assert_eq!(
+        line,
+        "[naviin] │ Value $12,345.67 │ Cash $500.00 │ Day -$12.30 │ online"
+    );
+    let line = bar.status_line(64);
+    assert_eq!(
+        line,
+        "Value $12,345.67 │ Cash $500.00 │ Day -$12.30 │ online"
+    );
+
+    let line = bar.status_line(30);
+    assert_eq!(line, "Value $12,345.67 │ online");
+
+    let line = bar.status_line(10);
+    assert_eq!(line.chars().count(), 10);
+    assert!(line.ends_with('…'));
+}
+
+#[test]
+fn test_status_line_shows_how_many_symbols_were_priced() {
+    let mut bar = status_bar();
+    bar.set_refresh_health(RefreshHealth {
+        priced: 2,
+        total: 3,
+        failed: vec!["GONE".to_string()],
+    });
+    assert!(bar.is_degraded());
+    assert_eq!(
+        bar.status_line(200),
+        "[naviin] │ Value $12,345.67 │ Cash $500.00 │ Day -$12.30 │ online │ 2/3 symbols priced │ not refreshed yet"
+    );
+    // A failed count outlasts the portfolio name and cash on a narrow terminal
+    assert_eq!(
+        bar.status_line(70),
+        "Value $12,345.67 │ Day -$12.30 │ online │ 2/3 symbols priced"
+    );
+
+    // When everything is priced the count is the first thing dropped
+    bar.set_refresh_health(RefreshHealth {
+        priced: 3,
+        total: 3,
+        failed: Vec::new(),
+    });
+    assert!(!bar.is_degraded());
+    assert_eq!(
+        bar.status_line(98),
+        "[naviin] │ Value $12,345.67 │ Cash $500.00 │ Day -$12.30 │ online │ not refreshed yet"
+    );
+}
+
+// ===== Holdings Totals Tests =====
+
+#[test]
+fn test_holdings_value_and_day_pnl_wait_for_prices() {
+    let positions = HashMap::from([
+        (
+            "AAPL".to_string(),
+            Holding::new("AAPL".to_string(), Decimal::from(10), Decimal::from(100)),
+        ),
+        (
+            "MSFT".to_string(),
+            Holding::new("MSFT".to_string(), Decimal::from(2), Decimal::from(300)),
+        ),
+    ]);
+    let mut account = AppState::new();
+    account.deposit(Decimal::from(1000));
+    account.set_holdings_map(positions.clone());
+    let value =
+        |holdings: &HoldingsComponent| account.portfolio_value(holdings.prices(), &HashMap::new());
+    let mut holdings = HoldingsComponent::new();
+    holdings.update_holdings(positions, Decimal::from(1000));
+    assert_eq!(value(&holdings), None);
+    assert_eq!(holdings.day_pnl(), None);
+
+    holdings.update_prices(HashMap::from([("AAPL".to_string(), Decimal::from(110))]));
+    holdings.update_previous_closes(HashMap::from([("AAPL".to_string(), Decimal::from(105))]));
+    assert_eq!(value(&holdings), None);
+    assert_eq!(holdings.day_pnl(), Some(Decimal::from(50)));
+
+    holdings.update_prices(HashMap::from([
+        ("AAPL".to_string(), Decimal::from(110)),
+        ("MSFT".to_string(), Decimal::from(310)),
+    ]));
+    assert_eq!(value(&holdings), Some(Decimal::from(2720)));
+}
+
+#[test]
+fn test_status_value_is_net_of_the_margin_loan() {
+    let positions = HashMap::from([(
+        "AAPL".to_string(),
+        Holding::new("AAPL".to_string(), Decimal::from(10), Decimal::from(100)),
+    )]);
+    let mut account = AppState::new();
+    account.set_holdings_map(positions.clone());
+    account.set_margin_loan(Decimal::from(400));
+    let mut holdings = HoldingsComponent::new();
+    holdings.update_holdings(positions, Decimal::ZERO);
+    holdings.update_prices(HashMap::from([("AAPL".to_string(), Decimal::from(110))]));
+
+    assert_eq!(
+        account.portfolio_value(holdings.prices(), &HashMap::new()),
+        Some(Decimal::from(700))
+    );
+}
@@ -0,0 +1,211 @@
+use migration::{Migrator, MigratorTrait};
+use naviin::AppState::AppState;
+use naviin::Finance::Holding;
+use naviin::Storage;
+use rust_decimal::Decimal;
+use sea_orm::{ConnectionTrait, Database};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+
+// Environment lookup backed by a fixed map instead of the process environment
+fn env_of(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> + use<> {
+    let vars: HashMap<String, String> = vars
+        .iter()
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect();
+    move |key| vars.get(key).cloned()
+}
+
+// ===== Location Resolution Tests =====
+
+#[test]
+fn test_database_url_prefers_url_then_state_path() {
+    let lookup = env_of(&[
+        ("DATABASE_URL", "sqlite://custom.db"),
+        ("NAVIIN_STATE", "/tmp/naviin/state.sqlite"),
+        ("HOME", "/home/ada"),
+    ]);
+    assert_eq!(
+        Storage::database_url_from(lookup),
+        Some("sqlite://custom.db".to_string())
+    );
+
+    let lookup = env_of(&[
+        ("NAVIIN_STATE", "/tmp/naviin/state.sqlite"),
+        ("HOME", "/home/ada"),
+    ]);
+    assert_eq!(
+        Storage::database_url_from(lookup),
+        Some("sqlite:///tmp/naviin/state.sqlite?mode=rwc".to_string())
+    );
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+#[test]
+fn test_default_state_path_is_per_user_data_dir() {
+    assert_eq!(
+        Storage::state_path_from(env_of(&[("HOME", "/home/ada")])),
+        Some(PathBuf::from("/home/ada/.local/share/naviin/naviin.sqlite"))
+    );
+    assert_eq!(
+        Storage::state_path_from(env_of(&[("HOME", "/home/ada"), ("XDG_DATA_HOME", "/data")])),
+        Some(PathBuf::from("/data/naviin/naviin.sqlite"))
+    );
+    assert_eq!(Storage::state_path_from(env_of(&[])), None);
+}
+
+#[tokio::test]
+async fn test_state_saved_to_configured_path_loads_back() {
+    let dir = std::env::temp_dir().join(format!("naviin_state_{}", std::process::id()));
+    let path = dir.join("nested").join("account.sqlite");
+    let lookup = env_of(&[("NAVIIN_STATE", path.to_str().unwrap())]);
+    assert_eq!(Storage::state_path_from(&lookup), Some(path.clone()));
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+
+    let url = Storage::database_url_from(&lookup).unwrap();
+    let db = Database::connect(&url).await.unwrap();
+    Migrator::up(&db, None).await.unwrap();
+    let state = Arc::new(Mutex::new(AppState::new()));
+    state.lock().unwrap().deposit(Decimal::from(1234));
+    Storage::save_state(&state, &db).await;
+
+    let loaded = Storage::load_state(&db).await;
+    assert_eq!(loaded.lock().unwrap().check_balance(), Decimal::from(1234));
+    assert!(path.exists());
+
+    db.close().await.ok();
+    std::fs::remove_dir_all(dir).ok();
+}
+
+// ===== First Run Tests =====
+
+#[test]
+fn test_starting_account_flag_overrides_environment() {
+    let args = |list: &[&str]| list.iter().map(|arg| arg.to_string()).collect::<Vec<_>>();
+
+    assert_eq!(
+        Storage::StartingAccount::from_config(&[], env_of(&[])).unwrap(),
+        Storage::StartingAccount::default()
+    );
+
+    let lookup = env_of(&[
+        ("NAVIIN_STARTING_BALANCE", "5000"),
+        ("NAVIIN_STARTING_WATCHLIST", "spy, aapl,,SPY"),
+    ]);
+    let starting = Storage::StartingAccount::from_config(&[], &lookup).unwrap();
+    assert_eq!(starting.balance, Decimal::from(5000));
+    assert_eq!(
+        starting.watchlist,
+        vec!["SPY".to_string(), "AAPL".to_string()]
+    );
+
+    let flagged =
+        Storage::StartingAccount::from_config(&args(&["--starting-balance", "250.50"]), &lookup)
+            .unwrap();
+    assert_eq!(flagged.balance, Decimal::new(25050, 2));
+    let flagged =
+        Storage::StartingAccount::from_config(&args(&["--starting-balance=0"]), &lookup).unwrap();
+    assert_eq!(flagged.balance, Decimal::ZERO);
+
+    assert!(
+        Storage::StartingAccount::from_config(&args(&["--starting-balance", "-5"]), &lookup)
+            .is_err()
+    );
+    assert!(
+        Storage::StartingAccount::from_config(&[], env_of(&[("NAVIIN_STARTING_BALANCE", "lots")]))
+            .is_err()
+    );
+}
+
+#[tokio::test]
+async fn test_first_run_seeds_balance_once() {
+    let db = Database::connect("sqlite::memory:").await.unwrap();
+    Migrator::up(&db, None).await.unwrap();
+    let starting = Storage::StartingAccount {
+        balance: Decimal::from(10_000),
+        watchlist: vec!["SPY".to_string()],
+    };
+
+    let first = Storage::load_state(&db).await;
+    assert!(
+        Storage::seed_first_run(&first, &db, &starting)
+            .await
+            .unwrap()
+    );
+    {
+        let guard = first.lock().unwrap();
+        assert_eq!(guard.check_balance(), Decimal::from(10_000));
+        assert_eq!(guard.get_cash_flows().len(), 1);
+        assert_eq!(guard.get_watchlist(), vec!["SPY".to_string()]);
+    }
+
+    // The next start loads the seeded account and leaves it alone
+    let second = Storage::load_state(&db).await;
+    assert!(
+        !Storage::seed_first_run(&second, &db, &starting)
+            .await
+            .unwrap()
+    );
+    let guard = second.lock().unwrap();
+    assert_eq!(guard.check_balance(), Decimal::from(10_000));
+    assert_eq!(guard.get_cash_flows().len(), 1);
+    assert_eq!(guard.get_watchlist(), vec!["SPY".to_string()]);
+}
+
+// ===== Decimal Precision Tests =====
+
+#[tokio::test]
+async fn test_high_precision_decimals_round_trip_unchanged() {
+    let db = Database::connect("sqlite::memory:").await.unwrap();
+    Migrator::up(&db, None).await.unwrap();
+
+    let quantity = Decimal::from_str("0.123456789012345678").unwrap();
+    let avg_cost = Decimal::from_str("64250.12345678").unwrap();
+    let cash = Decimal::from_str("79228162514264.337593543950335").unwrap();
+    let state = Arc::new(Mutex::new(AppState::new()));
+    {
+        let mut guard = state.lock().unwrap();
+        guard.deposit(cash);
+        guard.set_holdings_map(HashMap::from([(
+            "BTC".to_string(),
+            Holding::new("BTC".to_string(), quantity, avg_cost),
+        )]));
+    }
+    Storage::save_state(&state, &db).await;
+
+    let loaded = Storage::load_state(&db).await;
+    let guard = loaded.lock().unwrap();
+    let holding = &guard.get_holdings_map()["BTC"];
+    assert_eq!(holding.get_qty().to_string(), "0.123456789012345678");
+    assert_eq!(holding.get_avg_price().to_string(), "64250.12345678");
+    assert_eq!(
+        guard.check_balance().to_string(),
+        "79228162514264.337593543950335"
+    );
+}
+
+#[tokio::test]
+async fn test_decimal_migration_keeps_existing_rows() {
+    let db = Database::connect("sqlite::memory:").await.unwrap();
+    // Every migration before decimals were stored as text
+    Migrator::up(&db, Some(8)).await.unwrap();
+    db.execute_unprepared(
+        "INSERT INTO app_state (id, cash_balance, updated_at) VALUES (1, 2500.25, 0);
+         INSERT INTO holding (symbol, quantity, avg_cost) VALUES ('AAPL', 1.5, 0.0000001);",
+    )
+    .await
+    .unwrap();
+    Migrator::up(&db, None).await.unwrap();
+
+    let loaded = Storage::load_state(&db).await;
+    let guard = loaded.lock().unwrap();
+    assert_eq!(guard.check_balance(), Decimal::from_str("2500.25").unwrap());
+    let holding = &guard.get_holdings_map()["AAPL"];
+    assert_eq!(holding.get_qty(), Decimal::from_str("1.5").unwrap());
+    assert_eq!(
+        holding.get_avg_price(),
+        Decimal::from_str("0.0000001").unwrap()
+    );
+}
@@ -0,0 +1,146 @@
+use naviin::AppState::AppState;
+use naviin::FinanceProvider::MockProvider;
+use naviin::Orders::{Trade, parse_tag};
+use naviin::Storage;
+use naviin::commands::process_command;
+use naviin::costbasis::{CostBasisMethod, closed_lots, open_lots, realized_by_tag};
+use rust_decimal::Decimal;
+use sea_orm::Database;
+use std::sync::atomic::AtomicBool;
+use std::sync::{Arc, Mutex};
+
+fn tagged(trade: Trade, tag: &str) -> Trade {
+    trade.with_tag(Some(tag.to_string()))
+}
+
+// ===== Lot Attribution Tests =====
+
+#[test]
+fn test_closed_lots_take_the_sell_tag_or_the_buy_tag() {
+    let aapl = || "AAPL".to_string();
+    let trades = vec![
+        tagged(
+            Trade::buy_at(aapl(), Decimal::from(10), Decimal::from(100), 1),
+            "swing",
+        ),
+        Trade::buy_at(aapl(), Decimal::from(10), Decimal::from(120), 2),
+        // Untagged: closes the swing lot and counts toward it
+        Trade::sell_at(aapl(), Decimal::from(10), Decimal::from(130), 3),
+        // Tagged: its own tag wins over the untagged lot it closes
+        tagged(
+            Trade::sell_at(aapl(), Decimal::from(4), Decimal::from(110), 4),
+            "hedge",
+        ),
+    ];
+
+    let lots = closed_lots(&trades, CostBasisMethod::Fifo);
+    assert_eq!(lots[0].tag.as_deref(), Some("swing"));
+    assert_eq!(lots[1].tag.as_deref(), Some("hedge"));
+
+    let totals = realized_by_tag(&lots);
+    assert_eq!(totals[&Some("swing".to_string())], Decimal::from(300));
+    assert_eq!(totals[&Some("hedge".to_string())], Decimal::from(-40));
+    assert!(!totals.contains_key(&None));
+
+    // The rest of the untagged buy is still open
+    let open = open_lots(&trades, CostBasisMethod::Fifo);
+    assert_eq!(open.len(), 1);
+    assert_eq!(open[0].quantity, Decimal::from(6));
+    assert_eq!(open[0].tag, None);
+}
+
+#[test]
+fn test_parse_tag_lowercases_and_rejects_bad_labels() {
+    assert_eq!(parse_tag("Swing-2").unwrap(), "swing-2");
+    assert!(parse_tag("").is_err());
+    assert!(parse_tag("two words").is_err());
+    assert!(parse_tag(&"x".repeat(25)).is_err());
+}
+
+// ===== Command Tests =====
+
+#[tokio::test]
+async fn test_tagged_trades_filter_and_group_pnl() {
+    let provider = Arc::new(MockProvider::new());
+    provider.set_price("AAPL", Decimal::from(100));
+    provider.set_price("MSFT", Decimal::from(50));
+    let mut account = AppState::with_provider(provider.clone());
+    account.set_cash_balance(Decimal::from(10_000));
+    let state = Arc::new(Mutex::new(account));
+    let db = Database::connect("sqlite::memory:").await.unwrap();
+    let running = Arc::new(AtomicBool::new(false));
+
+    let bought = process_command("buy AAPL 10 --tag Swing", &state, &db, &running).await;
+    assert!(bought.ends_with("[tag: swing]"), "{}", bought);
+    process_command("buy MSFT 10", &state, &db, &running).await;
+    provider.set_price("AAPL", Decimal::from(110));
+    provider.set_price("MSFT", Decimal::from(60));
+    process_command("sell AAPL 4", &state, &db, &running).await;
+    process_command("sell MSFT 5 --tag hedge", &state, &db, &running).await;
+
+    let trades = process_command("trades --tag swing", &state, &db, &running).await;
+    assert!(trades.contains("Tag"), "{}", trades);
+    assert!(!trades.contains("MSFT"), "{}", trades);
+    assert_eq!(trades.matches("swing").count(), 1, "{}", trades);
+    assert_eq!(
+        process_command("trades --tag nope", &state, &db, &running).await,
+        "No trades tagged 'nope'"
+    );
+
+    // The untagged AAPL sell closes swing shares: 4 x (110 - 100)
+    let by_tag = process_command("realized --by-tag", &state, &db, &running).await;
+    let rows: Vec<&str> = by_tag.lines().collect();
+    assert!(
+        rows[1].contains("hedge") && rows[1].contains("$50.00"),
+        "{}",
+        by_tag
+    );
+    assert!(
+        rows[2].contains("swing") && rows[2].contains("$40.00"),
+        "{}",
+        by_tag
+    );
+    assert!(
+        rows[3].contains("Total") && rows[3].contains("$90.00"),
+        "{}",
+        by_tag
+    );
+
+    // Swing: 6 open shares up $10 each and $40 realized; MSFT isn't tagged swing
+    let pnl = process_command("pnl --tag swing", &state, &db, &running).await;
+    assert!(pnl.starts_with("P&L tagged 'swing'"), "{}", pnl);
+    assert!(!pnl.contains("MSFT"), "{}", pnl);
+    let total = pnl.lines().last().unwrap();
+    assert!(
+        total.contains("$60.00") && total.contains("$100.00"),
+        "{}",
+        pnl
+    );
+
+    assert_eq!(
+        process_command("buy AAPL 1 --tag", &state, &db, &running).await,
+        "--tag needs a value, e.g. --tag swing"
+    );
+}
+
+#[tokio::test]
+async fn test_trade_tags_survive_a_reload() {
+    let (db, _) = Storage::open("sqlite::memory:").await.unwrap();
+    let mut account = AppState::new();
+    account.add_trade(tagged(
+        Trade::buy_at("AAPL".to_string(), Decimal::ONE, Decimal::from(100), 1),
+        "swing",
+    ));
+    account.add_trade(Trade::buy_at(
+        "AAPL".to_string(),
+        Decimal::ONE,
+        Decimal::from(100),
+        2,
+    ));
+    Storage::save_state(&Arc::new(Mutex::new(account)), &db).await;
+
+    let loaded = Storage::try_load_state(&db).await.unwrap();
+    let trades = loaded.get_trades();
+    let tags: Vec<Option<&str>> = trades.iter().map(|t| t.get_tag()).collect();
+    assert_eq!(tags, vec![Some("swing"), None]);
+}
@@ -0,0 +1,155 @@
+mod common;
+
+use naviin::AppState::AppState;
+use naviin::FinanceProvider::MockProvider;
+use naviin::Orders::OrderType;
+use naviin::Storage;
+use naviin::commands::process_command;
+use naviin::templates::{LegKind, LegPrice, OrderTemplate};
+use rust_decimal::Decimal;
+use sea_orm::DatabaseConnection;
+use std::sync::atomic::AtomicBool;
+use std::sync::{Arc, Mutex};
+
+// Funded account on a migrated in-memory database, quoting AAPL at 100 and MSFT at 200
+async fn setup() -> (
+    Arc<Mutex<AppState>>,
+    DatabaseConnection,
+    Arc<AtomicBool>,
+    Arc<MockProvider>,
+) {
+    let provider = Arc::new(MockProvider::new());
+    provider.set_price("AAPL", Decimal::from(100));
+    provider.set_price("MSFT", Decimal::from(200));
+    let (state, db, running) = common::setup(Some(provider.clone()), None).await;
+    state.lock().unwrap().deposit(Decimal::from(10_000));
+    (state, db, running, provider)
+}
+
+#[test]
+fn test_spec_parses_and_prints_back() {
+    let template = OrderTemplate::parse("buy 10 ;STOPLOSS 10 -5%; takeprofit 5 +12.5%;").unwrap();
+
+    let legs = template.get_legs();
+    assert_eq!(legs.len(), 3);
+    assert_eq!(legs[1].kind, LegKind::StopLoss);
+    assert_eq!(legs[2].price, Some(LegPrice::Offset(Decimal::new(125, 1))));
+    assert_eq!(
+        template.to_string(),
+        "buy 10; stoploss 10 -5%; takeprofit 5 +12.5%"
+    );
+    assert_eq!(
+        OrderTemplate::parse(&template.to_string()).unwrap(),
+        template
+    );
+
+    assert!(OrderTemplate::parse("").is_err());
+    assert!(OrderTemplate::parse("buy 10 150").is_err());
+    assert!(OrderTemplate::parse("stoploss 10").is_err());
+    assert!(OrderTemplate::parse("stoploss 10 -100%").is_err());
+    assert!(OrderTemplate::parse("short 10").is_err());
+}
+
+#[tokio::test]
+async fn test_save_captures_recent_orders_and_run_places_them_on_another_symbol() {
+    let (state, db, running, _) = setup().await;
+    process_command("buy AAPL 10", &state, &db, &running).await;
+    process_command("stoploss AAPL 5 95", &state, &db, &running).await;
+    process_command("takeprofit AAPL 5 110", &state, &db, &running).await;
+
+    let saved = process_command("template save bracket", &state, &db, &running).await;
+    assert_eq!(
+        saved,
+        "Saved template bracket: buy 10; stoploss 5 -5%; takeprofit 5 +10%"
+    );
+
+    let result = process_command("template run bracket MSFT 4", &state, &db, &running).await;
+    assert!(
+        result.starts_with("Running template bracket on MSFT:"),
+        "{}",
+        result
+    );
+
+    let state_guard = state.lock().unwrap();
+    assert_eq!(
+        state_guard.get_holdings_map()["MSFT"].get_qty(),
+        Decimal::from(4)
+    );
+    let mut msft: Vec<_> = state_guard
+        .get_open_orders()
+        .into_iter()
+        .filter(|order| order.get_symbol() == "MSFT")
+        .map(|order| {
+            (
+                order.get_order_type(),
+                order.get_qty(),
+                order.get_price_per(),
+            )
+        })
+        .collect();
+    msft.sort_by_key(|(_, _, price)| *price);
+    assert_eq!(msft.len(), 2);
+    assert!(matches!(msft[0].0, OrderType::StopLoss));
+    assert_eq!(
+        (msft[0].1, msft[0].2),
+        (Decimal::from(2), Decimal::from(190))
+    );
+    assert!(matches!(msft[1].0, OrderType::TakeProfit));
+    assert_eq!(
+        (msft[1].1, msft[1].2),
+        (Decimal::from(2), Decimal::from(220))
+    );
+}
+
+#[tokio::test]
+async fn test_templates_survive_save_and_load() {
+    let (state, db, running, _) = setup().await;
+    process_command(
+        "template save dip buylimit 2 -3%; takeprofit 2 150",
+        &state,
+        &db,
+        &running,
+    )
+    .await;
+
+    let loaded = Storage::try_load_state(&db).await.unwrap();
+    let template = loaded.get_template("dip").unwrap();
+    assert_eq!(template.to_string(), "buylimit 2 -3%; takeprofit 2 150");
+
+    let deleted = process_command("template delete dip", &state, &db, &running).await;
+    assert_eq!(deleted, "Deleted template dip");
+    let loaded = Storage::try_load_state(&db).await.unwrap();
+    assert!(loaded.get_template("dip").is_none());
+}
+
+#[tokio::test]
+async fn test_run_stops_at_rejected_leg_and_reports_unknown_templates() {
+    let (state, db, running, _) = setup().await;
+    process_command(
+        "template save big buy 100; stoploss 100 -5%",
+        &state,
+        &db,
+        &running,
+    )
+    .await;
+
+    // 100 MSFT at 200 costs more than the 10,000 cash
+    let result = process_command("template run big MSFT", &state, &db, &running).await;
+    assert!(result.contains("Insufficient funds"), "{}", result);
+    assert!(
+        result.ends_with("Stopped at leg 1 of 2; earlier legs stay placed (undo reverses them)")
+    );
+    assert!(state.lock().unwrap().get_open_orders().is_empty());
+
+    let missing = process_command("template run nope MSFT", &state, &db, &running).await;
+    assert_eq!(
+        missing,
+        "No template named 'nope'. Type 'template list' to see saved templates"
+    );
+    let captured = process_command("template save empty", &state, &db, &running).await;
+    assert!(
+        captured.starts_with("No recent orders to capture"),
+        "{}",
+        captured
+    );
+}
@@ -0,0 +1,133 @@
+use chrono::NaiveDate;
+use naviin::AppState::AppState;
+use naviin::FinanceProvider::{
+    BarFuture, PriceFuture, PriceProvider, SearchFuture, Throttle, ThrottleLimits,
+    ThrottledProvider,
+};
+use naviin::commands::process_command;
+use rust_decimal::Decimal;
+use sea_orm::Database;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+// Provider whose lookups take a while, recording the most ever in flight at once
+#[derive(Default)]
+struct SlowProvider {
+    in_flight: AtomicUsize,
+    max_in_flight: AtomicUsize,
+}
+
+impl PriceProvider for SlowProvider {
+    fn name(&self) -> &str {
+        "slow"
+    }
+
+    fn curr_price<'a>(&'a self, _symbol: &'a str) -> PriceFuture<'a> {
+        Box::pin(async move {
+            let now = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            self.max_in_flight.fetch_max(now, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            self.in_flight.fetch_sub(1, Ordering::SeqCst);
+            Decimal::from(100)
+        })
+    }
+
+    fn previous_close<'a>(&'a self, symbol: &'a str) -> PriceFuture<'a> {
+        self.curr_price(symbol)
+    }
+
+    fn search<'a>(&'a self, _query: &'a str) -> SearchFuture<'a> {
+        Box::pin(async { Ok(Vec::new()) })
+    }
+
+    fn daily_bar<'a>(&'a self, _symbol: &'a str, _date: NaiveDate) -> BarFuture<'a> {
+        Box::pin(async { None })
+    }
+}
+
+fn throttled(
+    max_concurrent: usize,
+    min_spacing_ms: u64,
+) -> (Arc<SlowProvider>, Arc<ThrottledProvider>, Arc<Throttle>) {
+    let inner = Arc::new(SlowProvider::default());
+    let throttle = Arc::new(Throttle::new(ThrottleLimits {
+        max_concurrent,
+        min_spacing_ms,
+    }));
+    let provider = Arc::new(ThrottledProvider::new(inner.clone(), throttle.clone()));
+    (inner, provider, throttle)
+}
+
+// Starts `count` lookups at once and waits for all of them
+async fn burst(provider: &Arc<ThrottledProvider>, count: usize) -> Vec<Decimal> {
+    let mut tasks = tokio::task::JoinSet::new();
+    for _ in 0..count {
+        let provider = provider.clone();
+        tasks.spawn(async move { provider.curr_price("AAPL").await });
+    }
+    tasks.join_all().await
+}
+
+// ===== Throttle Tests =====
+
+#[tokio::test]
+async fn test_burst_never_exceeds_concurrency_limit() {
+    let (inner, provider, _) = throttled(2, 0);
+
+    let prices = burst(&provider, 10).await;
+
+    assert_eq!(prices, vec![Decimal::from(100); 10]);
+    assert_eq!(inner.max_in_flight.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn test_spacing_delays_starts_and_long_waits_raise_warning() {
+    let (_, provider, throttle) = throttled(8, 40);
+    let started = Instant::now();
+    burst(&provider, 4).await;
+    assert!(started.elapsed() >= Duration::from_millis(120));
+    assert!(!throttle.delayed_recently());
+
+    throttle.set_limits(ThrottleLimits {
+        max_concurrent: 8,
+        min_spacing_ms: 600,
+    });
+    burst(&provider, 2).await;
+    assert!(throttle.delayed_recently());
+}
+
+// ===== Command Tests =====
+
+#[tokio::test]
+async fn test_throttle_command_validates_and_persists_limits() {
+    let state = Arc::new(Mutex::new(AppState::new()));
+    let db = Database::connect("sqlite::memory:").await.unwrap();
+    let running = Arc::new(AtomicBool::new(false));
+
+    let shown = process_command("throttle", &state, &db, &running).await;
+    assert!(shown.starts_with("Up to 4 lookups run at once, started at least 100ms apart"));
+    let result = process_command("throttle 0 100", &state, &db, &running).await;
+    assert_eq!(result, "Concurrent lookups must be between 1 and 32");
+    let result = process_command("throttle 2 fast", &state, &db, &running).await;
+    assert!(result.starts_with("Invalid spacing: 'fast'"));
+
+    let result = process_command("throttle 2 250", &state, &db, &running).await;
+    assert_eq!(
+        result,
+        "Lookups limited to 2 at once, started at least 250ms apart"
+    );
+
+    let settings = state.lock().unwrap().get_settings();
+    let mut restored = AppState::new();
+    for (key, value) in &settings {
+        restored.apply_setting(key, value);
+    }
+    assert_eq!(
+        restored.get_throttle_limits(),
+        ThrottleLimits {
+            max_concurrent: 2,
+            min_spacing_ms: 250,
+        }
+    );
+}
@@ -0,0 +1,55 @@
+use naviin::Tui::{MIN_HEIGHT, MIN_WIDTH, Tui};
+use naviin::demo::demo_state;
+use ratatui::{Terminal, backend::TestBackend};
+use sea_orm::Database;
+use std::sync::atomic::AtomicBool;
+use std::sync::{Arc, Mutex};
+
+async fn tui() -> Tui {
+    let db = Database::connect("sqlite::memory:").await.unwrap();
+    Tui::new(
+        vec!["AAPL".to_string()],
+        Arc::new(Mutex::new(demo_state())),
+        db,
+        Arc::new(AtomicBool::new(false)),
+    )
+}
+
+// Draws one frame at the given size and flattens the buffer into one string
+fn render(tui: &Tui, width: u16, height: u16) -> String {
+    let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+    terminal.draw(|frame| tui.draw(frame)).unwrap();
+    terminal
+        .backend()
+        .buffer()
+        .content()
+        .iter()
+        .map(|cell| cell.symbol())
+        .collect()
+}
+
+// ===== Terminal Size Tests =====
+
+#[tokio::test]
+async fn test_tiny_terminal_shows_too_small_message_without_panicking() {
+    let tui = tui().await;
+
+    let screen = render(&tui, 10, 3);
+    assert!(screen.contains("Terminal"));
+    assert!(screen.contains("too small"));
+    assert!(!screen.contains("Holdings"));
+
+    // Degenerate sizes draw nothing rather than panic
+    render(&tui, 1, 1);
+    render(&tui, MIN_WIDTH - 1, MIN_HEIGHT);
+}
+
+#[tokio::test]
+async fn test_minimum_size_draws_every_panel() {
+    let tui = tui().await;
+
+    let screen = render(&tui, MIN_WIDTH, MIN_HEIGHT);
+    assert!(!screen.contains("too small"));
+    assert!(screen.contains("Holdings"));
+    assert!(screen.contains("Watchlist"));
+}
@@ -0,0 +1,127 @@
+mod common;
+
+use naviin::AppState::AppState;
+use naviin::FinanceProvider::MockProvider;
+use naviin::Storage;
+use naviin::commands::process_command;
+use rust_decimal::Decimal;
+use sea_orm::DatabaseConnection;
+use std::sync::atomic::AtomicBool;
+use std::sync::{Arc, Mutex};
+
+// Account priced by a mock provider with AAPL at $100
+async fn setup() -> (Arc<Mutex<AppState>>, DatabaseConnection, Arc<AtomicBool>) {
+    let provider = Arc::new(MockProvider::new());
+    provider.set_price("AAPL", Decimal::from(100));
+    common::setup(Some(provider), None).await
+}
+
+// ===== Undo Tests =====
+
+#[tokio::test]
+async fn test_undo_with_empty_stack() {
+    let (state, db, running) = setup().await;
+
+    let result = process_command("undo", &state, &db, &running).await;
+
+    assert_eq!(result, "Nothing to undo");
+}
+
+#[tokio::test]
+async fn test_undo_buy_refunds_cash_and_removes_shares() {
+    let (state, db, running) = setup().await;
+    process_command("fund 1000", &state, &db, &running).await;
+    process_command("buy AAPL 3", &state, &db, &running).await;
+
+    let result = process_command("undo", &state, &db, &running).await;
+
+    assert!(result.starts_with("Undid buy AAPL 3."));
+    {
+        let guard = state.lock().unwrap();
+        assert_eq!(guard.check_balance(), Decimal::from(1000));
+        assert!(guard.get_holdings_map().is_empty());
+        assert!(guard.get_trades().is_empty());
+    }
+
+    // The undone buy stays undone once saved and loaded again
+    let loaded = Storage::try_load_state(&db).await.unwrap();
+    assert_eq!(loaded.check_balance(), Decimal::from(1000));
+    assert!(loaded.get_holdings_map().is_empty());
+    assert!(loaded.get_trades().is_empty());
+}
+
+#[tokio::test]
+async fn test_undo_partial_buy_restores_average_cost() {
+    let (state, db, running) = setup().await;
+    process_command("fund 1000", &state, &db, &running).await;
+    process_command("buy AAPL 2", &state, &db, &running).await;
+    let pricier = Arc::new(MockProvider::new());
+    pricier.set_price("AAPL", Decimal::from(160));
+    state.lock().unwrap().set_provider(pricier);
+    process_command("buy AAPL 1", &state, &db, &running).await;
+
+    process_command("undo", &state, &db, &running).await;
+
+    let holding = state.lock().unwrap().get_holdings_map()["AAPL"].clone();
+    assert_eq!(holding.get_qty(), Decimal::from(2));
+    assert_eq!(holding.get_avg_price(), Decimal::from(100));
+}
+
+#[tokio::test]
+async fn test_undo_sell_restores_shares_and_debits_cash() {
+    let (state, db, running) = setup().await;
+    process_command("fund 500", &state, &db, &running).await;
+    process_command("buy AAPL 2", &state, &db, &running).await;
+    process_command("close AAPL", &state, &db, &running).await;
+
+    process_command("undo", &state, &db, &running).await;
+
+    {
+        let guard = state.lock().unwrap();
+        assert_eq!(guard.check_balance(), Decimal::from(300));
+        assert_eq!(
+            guard.get_ticker_holdings_qty(&"AAPL".to_string()),
+            Decimal::from(2)
+        );
+        assert_eq!(guard.get_trades().len(), 1);
+    }
+
+    let loaded = Storage::try_load_state(&db).await.unwrap();
+    assert_eq!(loaded.check_balance(), Decimal::from(300));
+    assert_eq!(
+        loaded.get_ticker_holdings_qty(&"AAPL".to_string()),
+        Decimal::from(2)
+    );
+    assert_eq!(loaded.get_trades().len(), 1);
+}
+
+#[tokio::test]
+async fn test_undo_steps_back_through_orders_and_funding() {
+    let (state, db, running) = setup().await;
+    process_command("fund 1000", &state, &db, &running).await;
+    process_command("buylimit AAPL 1 90", &state, &db, &running).await;
+
+    let cancelled = process_command("undo", &state, &db, &running).await;
+    assert!(cancelled.contains("Cancelled BuyLimit order for AAPL"));
+    assert!(state.lock().unwrap().get_open_orders().is_empty());
+
+    process_command("undo", &state, &db, &running).await;
+    assert_eq!(state.lock().unwrap().check_balance(), Decimal::ZERO);
+    assert_eq!(
+        process_command("undo", &state, &db, &running).await,
+        "Nothing to undo"
+    );
+}
+
+#[tokio::test]
+async fn test_undo_fund_refused_after_cash_is_spent() {
+    let (state, db, running) = setup().await;
+    process_command("fund 100", &state, &db, &running).await;
+    state.lock().unwrap().withdraw(Decimal::from(60));
+
+    let result = process_command("undo", &state, &db, &running).await;
+
+    assert_eq!(result, "Can't undo fund: only $40.00 cash left");
+    // The entry stays so it can be retried
+    assert_eq!(state.lock().unwrap().undo_depth(), 1);
+}
@@ -0,0 +1,76 @@
+use naviin::components::watchlist::WatchlistComponent;
+use ratatui::{buffer::Buffer, layout::Rect, widgets::Widget};
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+
+// Renders the component and flattens the buffer into one string
+fn render(watchlist: &WatchlistComponent) -> String {
+    let area = Rect::new(0, 0, 60, 8);
+    let mut buf = Buffer::empty(area);
+    watchlist.render(area, &mut buf);
+    buf.content().iter().map(|cell| cell.symbol()).collect()
+}
+
+fn prices(quotes: &[(&str, Decimal)]) -> HashMap<String, Decimal> {
+    quotes
+        .iter()
+        .map(|(symbol, price)| (symbol.to_string(), *price))
+        .collect()
+}
+
+// ===== Tick Delta Tests =====
+
+#[test]
+fn test_first_refresh_is_neutral_then_ticks_show_direction() {
+    let mut watchlist = WatchlistComponent::new(vec!["AAPL".to_string(), "MSFT".to_string()]);
+    watchlist.update_prices(prices(&[
+        ("AAPL", Decimal::new(10000, 2)),
+        ("MSFT", Decimal::new(40000, 2)),
+    ]));
+    assert_eq!(watchlist.tick_delta(&"AAPL".to_string()), None);
+    assert!(!render(&watchlist).contains('↑'));
+
+    watchlist.update_prices(prices(&[
+        ("AAPL", Decimal::new(10012, 2)),
+        ("MSFT", Decimal::new(39995, 2)),
+    ]));
+    assert_eq!(
+        watchlist.tick_delta(&"AAPL".to_string()),
+        Some(Decimal::new(12, 2))
+    );
+    let screen = render(&watchlist);
+    assert!(screen.contains("↑0.12"), "{}", screen);
+    assert!(screen.contains("↓0.05"), "{}", screen);
+}
+
+#[test]
+fn test_ticks_follow_symbols_when_the_list_changes() {
+    let mut watchlist = WatchlistComponent::new(vec!["AAPL".to_string(), "MSFT".to_string()]);
+    watchlist.update_prices(prices(&[
+        ("AAPL", Decimal::from(100)),
+        ("MSFT", Decimal::from(400)),
+    ]));
+
+    // AAPL removed and TSLA added in front: MSFT still compares with its own price
+    watchlist.update_symbols(vec!["TSLA".to_string(), "MSFT".to_string()]);
+    watchlist.update_prices(prices(&[
+        ("TSLA", Decimal::from(250)),
+        ("MSFT", Decimal::from(401)),
+    ]));
+    assert_eq!(watchlist.tick_delta(&"TSLA".to_string()), None);
+    assert_eq!(
+        watchlist.tick_delta(&"MSFT".to_string()),
+        Some(Decimal::ONE)
+    );
+
+    // A failed fetch reads as zero and is shown as no tick, not a crash to zero
+    watchlist.update_prices(prices(&[
+        ("TSLA", Decimal::from(251)),
+        ("MSFT", Decimal::ZERO),
+    ]));
+    assert_eq!(watchlist.tick_delta(&"MSFT".to_string()), None);
+    assert_eq!(
+        watchlist.tick_delta(&"TSLA".to_string()),
+        Some(Decimal::ONE)
+    );
+}
@@ -0,0 +1,135 @@
+use naviin::AppState::{AppState, lock_state, process_open_orders};
+use naviin::FinanceProvider::MockProvider;
+use naviin::Orders::{OpenOrder, OrderType, Side};
+use naviin::webhook::{OrderFill, is_valid_url, notify};
+use rust_decimal::Decimal;
+use serde_json::Value;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+// Account with a $100 buy limit on AAPL, currently priced at $95 so it fills next pass
+fn state_with_fillable_order() -> Arc<Mutex<AppState>> {
+    let provider = Arc::new(MockProvider::new());
+    provider.set_price("AAPL", Decimal::from(95));
+    let mut state = AppState::with_provider(provider);
+    state.deposit(Decimal::from(1000));
+    state
+        .add_open_order(OpenOrder::new(
+            "AAPL".to_string(),
+            Decimal::from(2),
+            Decimal::from(100),
+            OrderType::BuyLimit,
+            Side::Buy,
+        ))
+        .unwrap();
+    Arc::new(Mutex::new(state))
+}
+
+// Accepts one request, answers 200, and returns its JSON body
+async fn receive_one(listener: TcpListener) -> Value {
+    let (mut stream, _) = listener.accept().await.unwrap();
+    let mut raw = Vec::new();
+    let mut chunk = [0u8; 1024];
+    loop {
+        let n = stream.read(&mut chunk).await.unwrap();
+        raw.extend_from_slice(&chunk[..n]);
+        let text = String::from_utf8_lossy(&raw).to_string();
+        if let Some((head, body)) = text.split_once("\r\n\r\n") {
+            let length: usize = head
+                .lines()
+                .find_map(|line| {
+                    line.to_lowercase()
+                        .strip_prefix("content-length:")
+                        .map(|v| v.trim().to_string())
+                })
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(0);
+            if body.len() >= length {
+                stream
+                    .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n")
+                    .await
+                    .unwrap();
+                return serde_json::from_str(body).unwrap();
+            }
+        }
+    }
+}
+
+// ===== Webhook Tests =====
+
+#[test]
+fn test_webhook_url_validation() {
+    assert!(is_valid_url("https://example.com/hook"));
+    assert!(is_valid_url("http://127.0.0.1:9000"));
+    assert!(!is_valid_url("ftp://example.com"));
+    assert!(!is_valid_url("https://"));
+    assert!(!is_valid_url("example.com"));
+}
+
+#[tokio::test]
+async fn test_filled_order_is_posted_to_webhook() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/hook", listener.local_addr().unwrap());
+    let state = state_with_fillable_order();
+    lock_state(&state).set_webhook_url(Some(url));
+
+    assert_eq!(process_open_orders(&state).await, 1);
+    let body = tokio::time::timeout(Duration::from_secs(5), receive_one(listener))
+        .await
+        .unwrap();
+
+    assert_eq!(body["event"], "order_filled");
+    assert_eq!(body["order_type"], "BuyLimit");
+    assert_eq!(body["side"], "Buy");
+    assert_eq!(body["symbol"], "AAPL");
+    assert_eq!(body["quantity"], "2");
+    assert_eq!(body["fill_price"], "95");
+}
+
+#[tokio::test]
+async fn test_no_webhook_still_fills_orders() {
+    let state = state_with_fillable_order();
+
+    assert_eq!(process_open_orders(&state).await, 1);
+    assert_eq!(lock_state(&state).check_balance(), Decimal::from(810));
+    assert_eq!(lock_state(&state).get_webhook_error(), None);
+}
+
+#[tokio::test]
+async fn test_unreachable_webhook_records_failure() {
+    // Bind then drop to get a port nothing is listening on
+    let addr = TcpListener::bind("127.0.0.1:0")
+        .await
+        .unwrap()
+        .local_addr()
+        .unwrap();
+    let state = state_with_fillable_order();
+    let order = lock_state(&state).get_open_orders()[0].clone();
+    let fill = OrderFill::new(&order, Decimal::from(95), 0);
+
+    notify(format!("http://{}/hook", addr), fill, state.clone())
+        .await
+        .unwrap();
+
+    assert!(lock_state(&state).get_webhook_error().is_some());
+}
+
+#[test]
+fn test_webhook_url_round_trips_through_settings() {
+    let mut state = AppState::new();
+    state.set_webhook_url(Some("https://example.com/hook".to_string()));
+
+    let mut restored = AppState::new();
+    for (key, value) in state.get_settings() {
+        restored.apply_setting(&key, &value);
+    }
+
+    assert_eq!(
+        restored.get_webhook_url(),
+        Some("https://example.com/hook".to_string())
+    );
+    restored.apply_setting("webhook_url", "");
+    assert_eq!(restored.get_webhook_url(), None);
+}